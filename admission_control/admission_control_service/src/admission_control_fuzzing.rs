@@ -53,6 +53,7 @@ pub fn fuzzer(data: &[u8]) {
         Arc::new(MockStorageReadClient),
         Arc::new(MockVMValidator),
         false,
+        false,
     );
 
     // process the request
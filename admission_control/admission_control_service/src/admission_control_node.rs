@@ -95,6 +95,9 @@ impl AdmissionControlNode {
             self.node_config
                 .admission_control
                 .need_to_check_mempool_before_validation,
+            self.node_config
+                .admission_control
+                .enable_simulate_transaction,
         );
         let service = admission_control_grpc::create_admission_control(handle);
 
@@ -8,15 +8,21 @@
 use crate::OP_COUNTERS;
 use admission_control_proto::{
     proto::{
-        admission_control::{SubmitTransactionRequest, SubmitTransactionResponse},
+        admission_control::{
+            SimulateTransactionRequest, SimulateTransactionResponse, SubmitTransactionRequest,
+            SubmitTransactionResponse,
+        },
         admission_control_grpc::AdmissionControl,
     },
     AdmissionControlStatus,
 };
 use failure::prelude::*;
 use futures::future::Future;
-use futures03::executor::block_on;
-use grpc_helpers::provide_grpc_response;
+use futures03::{
+    executor::block_on,
+    stream::{Stream, StreamExt},
+};
+use grpc_helpers::{provide_grpc_response, provide_grpc_stream_response};
 use logger::prelude::*;
 use mempool::proto::{
     mempool::{AddTransactionWithValidationRequest, HealthCheckRequest},
@@ -31,7 +37,10 @@ use proto_conv::{FromProto, IntoProto};
 use std::sync::Arc;
 use storage_client::StorageRead;
 use types::{
-    proto::get_with_proof::{UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse},
+    proto::get_with_proof::{
+        SubscribeToTransactionsRequest, SubscribeToTransactionsResponse,
+        UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
+    },
     transaction::SignedTransaction,
 };
 use vm_validator::vm_validator::{get_account_state, TransactionValidation};
@@ -57,6 +66,9 @@ pub struct AdmissionControlService<M, V> {
     /// Flag indicating whether we need to check mempool before validation, drop txn if check
     /// fails.
     need_to_check_mempool_before_validation: bool,
+    /// Flag indicating whether transactions may be simulated, which costs as much as executing
+    /// them.
+    enable_simulate_transaction: bool,
 }
 
 impl<M: 'static, V> AdmissionControlService<M, V>
@@ -70,12 +82,14 @@ where
         storage_read_client: Arc<dyn StorageRead>,
         vm_validator: Arc<V>,
         need_to_check_mempool_before_validation: bool,
+        enable_simulate_transaction: bool,
     ) -> Self {
         AdmissionControlService {
             mempool_client,
             storage_read_client,
             vm_validator,
             need_to_check_mempool_before_validation,
+            enable_simulate_transaction,
         }
     }
 
@@ -152,6 +166,42 @@ where
         self.add_txn_to_mempool(add_transaction_request)
     }
 
+    /// Run the transaction through the VM against the latest committed state, without adding it
+    /// to Mempool.
+    pub(crate) fn simulate_transaction_inner(
+        &self,
+        req: SimulateTransactionRequest,
+    ) -> Result<SimulateTransactionResponse> {
+        let mut response = SimulateTransactionResponse::new();
+        if !self.enable_simulate_transaction {
+            response.set_ac_status(
+                AdmissionControlStatus::Rejected("simulate txn disabled".to_string()).into_proto(),
+            );
+            OP_COUNTERS.inc_by("simulate_txn.rejected.disabled", 1);
+            return Ok(response);
+        }
+        let signed_txn = match SignedTransaction::from_proto(req.get_signed_txn().clone()) {
+            Ok(t) => t,
+            Err(e) => {
+                debug!("Failed to decode the transaction to simulate: {:?}", e);
+                response.set_ac_status(
+                    AdmissionControlStatus::Rejected("simulate txn rejected".to_string())
+                        .into_proto(),
+                );
+                OP_COUNTERS.inc_by("simulate_txn.rejected.invalid_txn", 1);
+                return Ok(response);
+            }
+        };
+
+        let output = self
+            .vm_validator
+            .simulate_transaction(signed_txn, !req.get_skip_signature_check())
+            .wait()?;
+        OP_COUNTERS.inc_by("simulate_txn.simulated", 1);
+        response.set_output(output.into_proto());
+        Ok(response)
+    }
+
     fn can_send_txn_to_mempool(&self) -> Result<bool> {
         if self.need_to_check_mempool_before_validation {
             let req = HealthCheckRequest::new();
@@ -201,16 +251,41 @@ where
         req: UpdateToLatestLedgerRequest,
     ) -> Result<UpdateToLatestLedgerResponse> {
         let rust_req = types::get_with_proof::UpdateToLatestLedgerRequest::from_proto(req)?;
-        let (response_items, ledger_info_with_sigs, validator_change_events) = self
+        let (
+            response_items,
+            ledger_info_with_sigs,
+            validator_change_events,
+            ledger_consistency_proof,
+        ) = self
             .storage_read_client
             .update_to_latest_ledger(rust_req.client_known_version, rust_req.requested_items)?;
         let rust_resp = types::get_with_proof::UpdateToLatestLedgerResponse::new(
             response_items,
             ledger_info_with_sigs,
             validator_change_events,
+            ledger_consistency_proof,
         );
         Ok(rust_resp.into_proto())
     }
+
+    /// Pass the SubscribeToTransactionsRequest to Storage and relay the chunks it streams back.
+    fn subscribe_to_transactions_inner(
+        &self,
+        req: SubscribeToTransactionsRequest,
+    ) -> Result<impl Stream<Item = Result<SubscribeToTransactionsResponse>> + Send + Unpin> {
+        let rust_req = types::get_with_proof::SubscribeToTransactionsRequest::from_proto(req)?;
+        Ok(self
+            .storage_read_client
+            .subscribe_to_transactions(rust_req.start_version, rust_req.batch_size)
+            .map(|chunk| {
+                let (txn_list_with_proof, ledger_info_with_sigs) = chunk?;
+                let rust_resp = types::get_with_proof::SubscribeToTransactionsResponse::new(
+                    txn_list_with_proof,
+                    ledger_info_with_sigs,
+                );
+                Ok(rust_resp.into_proto())
+            }))
+    }
 }
 
 impl<M: 'static, V> AdmissionControl for AdmissionControlService<M, V>
@@ -236,6 +311,20 @@ where
         provide_grpc_response(resp, ctx, sink);
     }
 
+    /// Run a transaction through the VM against the latest committed state and return its output,
+    /// without submitting it. The signature isn't checked if the request says so.
+    fn simulate_transaction(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        req: SimulateTransactionRequest,
+        sink: ::grpcio::UnarySink<SimulateTransactionResponse>,
+    ) {
+        debug!("[GRPC] AdmissionControl::simulate_transaction");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let resp = self.simulate_transaction_inner(req);
+        provide_grpc_response(resp, ctx, sink);
+    }
+
     /// This API is used to update the client to the latest ledger version and optionally also
     /// request 1..n other pieces of data.  This allows for batch queries.  All queries return
     /// proofs that a client should check to validate the data.
@@ -253,4 +342,21 @@ where
         let resp = self.update_to_latest_ledger_inner(req);
         provide_grpc_response(resp, ctx, sink);
     }
+
+    /// This API streams committed transactions together with their infos and events, starting
+    /// from a given version, and keeps following the ledger as new blocks are committed. Each
+    /// chunk carries the ledger info its proofs are relative to, so a client can verify it as it
+    /// arrives.
+    /// AC will not directly process this request but relay the stream from Storage instead.
+    fn subscribe_to_transactions(
+        &mut self,
+        ctx: grpcio::RpcContext<'_>,
+        req: SubscribeToTransactionsRequest,
+        sink: grpcio::ServerStreamingSink<SubscribeToTransactionsResponse>,
+    ) {
+        debug!("[GRPC] AdmissionControl::subscribe_to_transactions");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let resp = self.subscribe_to_transactions_inner(req);
+        provide_grpc_stream_response(resp, ctx, sink);
+    }
 }
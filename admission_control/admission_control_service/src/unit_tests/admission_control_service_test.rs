@@ -3,7 +3,7 @@
 
 use crate::{
     admission_control_service::{
-        AdmissionControlService, SubmitTransactionRequest,
+        AdmissionControlService, SimulateTransactionRequest, SubmitTransactionRequest,
         SubmitTransactionResponse as ProtoSubmitTransactionResponse,
     },
     mocks::local_mock_mempool::LocalMockMempool,
@@ -12,13 +12,14 @@ use admission_control_proto::{AdmissionControlStatus, SubmitTransactionResponse}
 
 use crypto::{ed25519::*, test_utils::TEST_SEED};
 use mempool::proto::shared::mempool_status::MempoolAddTransactionStatusCode;
-use proto_conv::FromProto;
+use proto_conv::{FromProto, IntoProto};
 use rand::SeedableRng;
 use std::sync::Arc;
 use storage_service::mocks::mock_storage_client::MockStorageReadClient;
 use types::{
     account_address::{AccountAddress, ADDRESS_LENGTH},
-    test_helpers::transaction_test_helpers::get_test_signed_txn,
+    test_helpers::transaction_test_helpers::{get_test_signed_txn, get_test_unchecked_txn},
+    transaction::{TransactionOutput, TransactionStatus},
     vm_error::{StatusCode, VMStatus},
 };
 use vm_validator::mocks::mock_vm_validator::MockVMValidator;
@@ -29,6 +30,7 @@ pub fn create_ac_service_for_ut() -> AdmissionControlService<LocalMockMempool, M
         Arc::new(MockStorageReadClient),
         Arc::new(MockVMValidator),
         false,
+        true,
     )
 }
 
@@ -220,3 +222,69 @@ fn test_submit_txn_inner_mempool() {
         AdmissionControlStatus::Accepted,
     );
 }
+
+#[test]
+fn test_simulate_txn_inner() {
+    let mut rng = ::rand::rngs::StdRng::from_seed(TEST_SEED);
+    let ac_service = create_ac_service_for_ut();
+    let keypair = compat::generate_keypair(&mut rng);
+    let (other_private_key, _) = compat::generate_keypair(&mut rng);
+    let simulate = |signed_txn, skip_signature_check| {
+        let mut req = SimulateTransactionRequest::new();
+        req.set_signed_txn(signed_txn);
+        req.set_skip_signature_check(skip_signature_check);
+        let mut response = ac_service.simulate_transaction_inner(req).unwrap();
+        assert!(response.has_output());
+        TransactionOutput::from_proto(response.take_output()).unwrap()
+    };
+
+    let sender = AccountAddress::new([0; ADDRESS_LENGTH]);
+    let output = simulate(
+        get_test_signed_txn(sender, 0, keypair.0.clone(), keypair.1.clone(), None),
+        false,
+    );
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Discard(VMStatus::new(StatusCode::SENDING_ACCOUNT_DOES_NOT_EXIST))
+    );
+
+    let sender = AccountAddress::new([103; ADDRESS_LENGTH]);
+    let signed_txn =
+        get_test_unchecked_txn(sender, 0, other_private_key, keypair.1.clone(), None).into_proto();
+    let output = simulate(signed_txn.clone(), false);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Discard(VMStatus::new(StatusCode::INVALID_SIGNATURE))
+    );
+    let output = simulate(signed_txn.clone(), true);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+    );
+
+    // A transaction that can't be decoded isn't simulated.
+    let mut req = SimulateTransactionRequest::new();
+    req.mut_signed_txn().set_signed_txn(vec![0; 3]);
+    let response = ac_service.simulate_transaction_inner(req).unwrap();
+    assert_eq!(
+        AdmissionControlStatus::from_proto(response.get_ac_status().clone()).unwrap(),
+        AdmissionControlStatus::Rejected("simulate txn rejected".to_string()),
+    );
+
+    // Nothing is simulated unless simulations are enabled.
+    let ac_service = AdmissionControlService::new(
+        Some(Arc::new(LocalMockMempool::new())),
+        Arc::new(MockStorageReadClient),
+        Arc::new(MockVMValidator),
+        false,
+        false,
+    );
+    let mut req = SimulateTransactionRequest::new();
+    req.set_signed_txn(signed_txn);
+    let response = ac_service.simulate_transaction_inner(req).unwrap();
+    assert!(!response.has_output());
+    assert_eq!(
+        AdmissionControlStatus::from_proto(response.get_ac_status().clone()).unwrap(),
+        AdmissionControlStatus::Rejected("simulate txn disabled".to_string()),
+    );
+}
@@ -11,13 +11,16 @@ use admission_control_proto::{
     },
     AdmissionControlStatus, SubmitTransactionResponse,
 };
-use crypto::ed25519::*;
+use crypto::{
+    ed25519::*,
+    hash::{CryptoHash, TransactionAccumulatorHasher},
+};
 use failure::prelude::*;
 use futures::Future;
 use grpcio::{CallOption, ChannelBuilder, EnvBuilder};
 use logger::prelude::*;
 use proto_conv::{FromProto, IntoProto};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use types::{
     access_path::AccessPath,
     account_address::AccountAddress,
@@ -28,6 +31,7 @@ use types::{
     get_with_proof::{
         RequestItem, ResponseItem, UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
     },
+    proof::accumulator::Accumulator,
     transaction::{SignedTransaction, Version},
     vm_error::StatusCode,
 };
@@ -38,6 +42,9 @@ const MAX_GRPC_RETRY_COUNT: u64 = 1;
 pub struct GRPCClient {
     client: AdmissionControlClient,
     validator_verifier: Arc<ValidatorVerifier>,
+    /// Transaction accumulator of the latest ledger verified so far, which the ledger in every
+    /// later response must extend. `None` until the first response is verified.
+    trusted_accumulator: Arc<Mutex<Option<Arc<Accumulator<TransactionAccumulatorHasher>>>>>,
 }
 
 impl GRPCClient {
@@ -53,6 +60,7 @@ impl GRPCClient {
         Ok(GRPCClient {
             client,
             validator_verifier,
+            trusted_accumulator: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -137,10 +145,26 @@ impl GRPCClient {
     ) -> Result<
         impl Future<Item = UpdateToLatestLedgerResponse<Ed25519Signature>, Error = failure::Error>,
     > {
-        let req = UpdateToLatestLedgerRequest::new(0, requested_items.clone());
+        let known_accumulator = self.trusted_accumulator.lock().unwrap().clone();
+        let mut requested_items = requested_items;
+        let client_known_version = match &known_accumulator {
+            Some(accumulator) => accumulator.num_leaves() - 1,
+            None => {
+                // Nothing is known yet: also fetch the first transaction, whose info is the only
+                // leaf of the accumulator at version 0, to prove the ledger extends.
+                requested_items.push(RequestItem::GetTransactions {
+                    start_version: 0,
+                    limit: 1,
+                    fetch_events: false,
+                });
+                0
+            }
+        };
+        let req = UpdateToLatestLedgerRequest::new(client_known_version, requested_items);
         debug!("get_with_proof with request: {:?}", req);
         let proto_req = req.clone().into_proto();
         let validator_verifier = Arc::clone(&self.validator_verifier);
+        let trusted_accumulator = Arc::clone(&self.trusted_accumulator);
         let ret = self
             .client
             .update_to_latest_ledger_async_opt(&proto_req, Self::get_default_grpc_call_option())?
@@ -148,8 +172,31 @@ impl GRPCClient {
                 // TODO: Cache/persist client_known_version to work with validator set change when
                 // the feature is available.
 
-                let resp = UpdateToLatestLedgerResponse::from_proto(get_with_proof_resp?)?;
+                let mut resp = UpdateToLatestLedgerResponse::from_proto(get_with_proof_resp?)?;
                 resp.verify(validator_verifier, &req)?;
+
+                let known_accumulator = match known_accumulator {
+                    Some(accumulator) => accumulator,
+                    None => {
+                        let txn_list_with_proof = resp
+                            .response_items
+                            .pop()
+                            .expect("Response items are verified to match the request.")
+                            .into_get_transactions_response()?;
+                        let (_, txn_info) = txn_list_with_proof
+                            .transaction_and_infos
+                            .first()
+                            .ok_or_else(|| format_err!("Transaction at version 0 missing."))?;
+                        Arc::new(Accumulator::default().append(vec![txn_info.hash()]))
+                    }
+                };
+                let accumulator = resp.verify_ledger_consistency(&known_accumulator)?;
+                let mut trusted_accumulator = trusted_accumulator.lock().unwrap();
+                if trusted_accumulator.as_ref().map_or(true, |trusted| {
+                    trusted.num_leaves() < accumulator.num_leaves()
+                }) {
+                    *trusted_accumulator = Some(Arc::new(accumulator));
+                }
                 Ok(resp)
             });
         Ok(ret)
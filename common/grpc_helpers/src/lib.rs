@@ -1,10 +1,12 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+#![feature(async_await)]
+
 use failure::{prelude::*, Result};
 use futures::{compat::Future01CompatExt, future::Future, prelude::*};
-use futures_01::future::Future as Future01;
-use grpcio::{ChannelBuilder, EnvBuilder, ServerBuilder};
+use futures_01::{future::Future as Future01, sink::Sink as Sink01};
+use grpcio::{ChannelBuilder, EnvBuilder, ServerBuilder, ServerStreamingSink, WriteFlags};
 use logger::prelude::*;
 use metrics::counters::SVC_COUNTERS;
 use std::{
@@ -53,6 +55,60 @@ pub fn provide_grpc_response<ResponseType: std::fmt::Debug>(
     SVC_COUNTERS.resp(&ctx, success);
 }
 
+/// This is the counterpart of [`provide_grpc_response`] for server streaming calls. The items of
+/// the response stream are sent as they come, and the call ends when the stream does. An error,
+/// either instead of the stream or from it, fails the call.
+pub fn provide_grpc_stream_response<ResponseType, S>(
+    resp: Result<S>,
+    ctx: ::grpcio::RpcContext<'_>,
+    sink: ServerStreamingSink<ResponseType>,
+) where
+    ResponseType: Send + 'static,
+    S: Stream<Item = Result<ResponseType>> + Send + Unpin + 'static,
+{
+    let method = from_utf8(ctx.method())
+        .expect("Unable to convert function name to string")
+        .to_string();
+    let success = resp.is_ok();
+    match resp {
+        Ok(stream) => ctx.spawn(
+            send_grpc_stream(method, stream, sink)
+                .boxed()
+                .compat()
+                .map_err(default_reply_error_logger),
+        ),
+        Err(e) => ctx.spawn(
+            sink.fail(create_grpc_invalid_arg_status(&method, e))
+                .map_err(default_reply_error_logger),
+        ),
+    }
+    SVC_COUNTERS.resp(&ctx, success);
+}
+
+async fn send_grpc_stream<ResponseType, S>(
+    method: String,
+    mut stream: S,
+    mut sink: ServerStreamingSink<ResponseType>,
+) -> ::grpcio::Result<()>
+where
+    S: Stream<Item = Result<ResponseType>> + Unpin,
+{
+    while let Some(item) = stream.next().await {
+        match item {
+            Ok(resp) => sink = sink.send((resp, WriteFlags::default())).compat().await?,
+            Err(e) => {
+                return sink
+                    .fail(create_grpc_invalid_arg_status(&method, e))
+                    .compat()
+                    .await;
+            }
+        }
+    }
+    futures_01::future::poll_fn(move || sink.close())
+        .compat()
+        .await
+}
+
 pub fn spawn_service_thread(
     service: ::grpcio::Service,
     service_host_address: String,
@@ -42,8 +42,12 @@ impl SwarmConfig {
 
         // Setup consensus keys and peers config file.
         let consensus_peers_file = template.consensus.consensus_peers_file.clone();
-        let (mut consensus_private_keys, consensus_peers_config) =
+        let (mut consensus_private_keys, mut consensus_peers_config) =
             ConfigHelpers::get_test_consensus_config(num_nodes, key_seed);
+        let mut vrf_private_keys =
+            ConfigHelpers::get_test_vrf_keys(&mut consensus_peers_config, key_seed);
+        let mut bls_private_keys =
+            ConfigHelpers::get_test_bls_keys(&mut consensus_peers_config, key_seed);
         consensus_peers_config.save_config(&output_dir.join(&consensus_peers_file));
 
         // Setup network keys and file.
@@ -74,7 +78,11 @@ impl SwarmConfig {
             // network_keypairs and consensus_keypair fields are skipped during
             // (de)serialization.
             let consensus_private_key = consensus_private_keys.remove_entry(node_id).unwrap().1;
-            let consensus_keypair = ConsensusKeyPair::load(Some(consensus_private_key));
+            let vrf_private_key = vrf_private_keys.remove_entry(node_id).unwrap().1;
+            let bls_private_key = bls_private_keys.remove_entry(node_id).unwrap().1;
+            let consensus_keypair = ConsensusKeyPair::load(Some(consensus_private_key))
+                .with_vrf_private_key(vrf_private_key)
+                .with_bls_private_key(bls_private_key);
             let NetworkPeerPrivateKeys {
                 network_signing_private_key,
                 network_identity_private_key,
@@ -187,6 +195,12 @@ impl SwarmConfig {
             consensus_keypair_file: consensus_keys_file_name.into(),
             consensus_peers: template.consensus.consensus_peers.clone(),
             consensus_peers_file: template.consensus.consensus_peers_file.clone(),
+            safety_rules: template.consensus.safety_rules.clone(),
+            recording_path: template.consensus.recording_path.clone(),
+            decoupled_execution: template.consensus.decoupled_execution,
+            proposal_backpressure: template.consensus.proposal_backpressure.clone(),
+            leader_reputation: template.consensus.leader_reputation.clone(),
+            signature_scheme: template.consensus.signature_scheme.clone(),
         };
         let mut config = NodeConfig {
             base: base_config,
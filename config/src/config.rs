@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    config::ConsensusProposerType::{FixedProposer, MultipleOrderedProposers, RotatingProposer},
+    config::ConsensusProposerType::{
+        FixedProposer, LeaderReputation, MultipleOrderedProposers, RotatingProposer, VrfProposer,
+    },
     keys::{ConsensusKeyPair, NetworkKeyPairs},
     seed_peers::{SeedPeersConfig, SeedPeersConfigHelpers},
     trusted_peers::{
@@ -11,7 +13,12 @@ use crate::{
     },
     utils::{deserialize_whitelist, get_available_port, get_local_ip, serialize_whitelist},
 };
-use crypto::{ed25519::Ed25519PublicKey, ValidKey};
+use crypto::{
+    bls12381::{BLS12381ProofOfPossession, BLS12381PublicKey},
+    ed25519::Ed25519PublicKey,
+    vrf::ecvrf::VRFPublicKey,
+    ValidKey,
+};
 use failure::prelude::*;
 use logger::LoggerType;
 use parity_multiaddr::{Multiaddr, Protocol};
@@ -266,6 +273,9 @@ pub struct AdmissionControlConfig {
     pub address: String,
     pub admission_control_service_port: u16,
     pub need_to_check_mempool_before_validation: bool,
+    // Whether clients may run transactions through the VM without submitting them. Each
+    // simulation costs a full execution, so it is off by default.
+    pub enable_simulate_transaction: bool,
 }
 
 impl Default for AdmissionControlConfig {
@@ -274,6 +284,7 @@ impl Default for AdmissionControlConfig {
             address: "0.0.0.0".to_string(),
             admission_control_service_port: 8000,
             need_to_check_mempool_before_validation: false,
+            enable_simulate_transaction: false,
         }
     }
 }
@@ -283,6 +294,7 @@ impl Default for AdmissionControlConfig {
 pub struct DebugInterfaceConfig {
     pub admission_control_node_debug_port: u16,
     pub secret_service_node_debug_port: u16,
+    pub safety_rules_node_debug_port: u16,
     pub storage_node_debug_port: u16,
     // This has similar use to the core-node-debug-server itself
     pub metrics_server_port: u16,
@@ -295,6 +307,7 @@ impl Default for DebugInterfaceConfig {
             admission_control_node_debug_port: 6191,
             storage_node_debug_port: 6194,
             secret_service_node_debug_port: 6195,
+            safety_rules_node_debug_port: 6196,
             metrics_server_port: 9101,
             address: "localhost".to_string(),
         }
@@ -308,6 +321,9 @@ pub struct StorageConfig {
     pub port: u16,
     pub dir: PathBuf,
     pub grpc_max_receive_len: Option<i32>,
+    // Other than the latest version, how many historical versions of the ledger history
+    // (transactions, events and transaction infos) to keep. `None` keeps all of them.
+    pub ledger_prune_window: Option<u64>,
 }
 
 impl StorageConfig {
@@ -323,6 +339,7 @@ impl Default for StorageConfig {
             port: 6184,
             dir: PathBuf::from("libradb"),
             grpc_max_receive_len: Some(100_000_000),
+            ledger_prune_window: None,
         }
     }
 }
@@ -440,6 +457,22 @@ pub struct ConsensusConfig {
     #[serde(skip)]
     pub consensus_peers: ConsensusPeersConfig,
     pub consensus_peers_file: PathBuf,
+    pub safety_rules: SafetyRulesConfig,
+    // If set, the data consensus starts from, its messages, local timeouts and the results of the
+    // state computer are appended to this file, relative to the data directory, at each start, so
+    // that the runs can be replayed with consensus-replay.
+    pub recording_path: Option<PathBuf>,
+    // If set, the validators vote for the blocks without waiting for their execution: the quorum
+    // certs only order the blocks, whose executed state is certified by separate commit votes.
+    pub decoupled_execution: bool,
+    pub proposal_backpressure: ProposalBackpressureConfig,
+    pub leader_reputation: LeaderReputationConfig,
+    // The scheme of the signatures of the votes and timeouts that the quorum and timeout certs
+    // carry: "ed25519" certs carry the signature of each validator, "bls12381" certs carry the
+    // aggregate of the signatures of the BLS12-381 keys the validators registered. The latter
+    // requires the decoupled execution mode: the ledger infos committed to storage keep the
+    // Ed25519 signatures of the commit votes, which state sync and the clients verify.
+    pub signature_scheme: String,
 }
 
 impl Default for ConsensusConfig {
@@ -454,10 +487,24 @@ impl Default for ConsensusConfig {
             consensus_keypair_file: PathBuf::from("consensus_keypair.config.toml"),
             consensus_peers: ConsensusPeersConfig::default(),
             consensus_peers_file: PathBuf::from("consensus_peers.config.toml"),
+            safety_rules: SafetyRulesConfig::default(),
+            recording_path: None,
+            decoupled_execution: false,
+            proposal_backpressure: ProposalBackpressureConfig::default(),
+            leader_reputation: LeaderReputationConfig::default(),
+            signature_scheme: "ed25519".to_string(),
         }
     }
 }
 
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ConsensusSignatureScheme {
+    // The certificates carry the Ed25519 signature of each validator
+    Ed25519,
+    // The certificates carry the aggregate of the BLS12-381 signatures of the validators
+    Bls12381,
+}
+
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum ConsensusProposerType {
     // Choose the smallest PeerId as the proposer
@@ -466,6 +513,10 @@ pub enum ConsensusProposerType {
     RotatingProposer,
     // Multiple ordered proposers per round (primary, secondary, etc.)
     MultipleOrderedProposers,
+    // Proposers weighted by their record in the recently committed blocks
+    LeaderReputation,
+    // Proposers drawn from the VRF output of the blocks they extend
+    VrfProposer,
 }
 
 impl ConsensusConfig {
@@ -486,10 +537,26 @@ impl ConsensusConfig {
             "fixed_proposer" => FixedProposer,
             "rotating_proposer" => RotatingProposer,
             "multiple_ordered_proposers" => MultipleOrderedProposers,
+            "leader_reputation" => LeaderReputation,
+            "vrf_proposer" => VrfProposer,
             &_ => unimplemented!("Invalid proposer type: {}", self.proposer_type),
         }
     }
 
+    pub fn get_signature_scheme(&self) -> ConsensusSignatureScheme {
+        match self.signature_scheme.as_str() {
+            "ed25519" => ConsensusSignatureScheme::Ed25519,
+            "bls12381" => {
+                assert!(
+                    self.decoupled_execution,
+                    "The bls12381 signature scheme requires the decoupled execution mode"
+                );
+                ConsensusSignatureScheme::Bls12381
+            }
+            &_ => unimplemented!("Invalid signature scheme: {}", self.signature_scheme),
+        }
+    }
+
     pub fn contiguous_rounds(&self) -> u32 {
         self.contiguous_rounds
     }
@@ -506,6 +573,26 @@ impl ConsensusConfig {
         &self.pacemaker_initial_timeout_ms
     }
 
+    pub fn safety_rules(&self) -> &SafetyRulesConfig {
+        &self.safety_rules
+    }
+
+    pub fn recording_path(&self) -> &Option<PathBuf> {
+        &self.recording_path
+    }
+
+    pub fn decoupled_execution(&self) -> bool {
+        self.decoupled_execution
+    }
+
+    pub fn proposal_backpressure(&self) -> &ProposalBackpressureConfig {
+        &self.proposal_backpressure
+    }
+
+    pub fn leader_reputation(&self) -> &LeaderReputationConfig {
+        &self.leader_reputation
+    }
+
     pub fn get_consensus_peers(&self) -> HashMap<PeerId, Ed25519PublicKey> {
         self.consensus_peers
             .peers
@@ -523,6 +610,171 @@ impl ConsensusConfig {
             })
             .collect()
     }
+
+    /// Returns the VRF public keys of the consensus peers that registered one.
+    pub fn get_consensus_peers_vrf_keys(&self) -> HashMap<PeerId, VRFPublicKey> {
+        self.consensus_peers
+            .peers
+            .iter()
+            .filter_map(|(peer_id_str, peer_info)| {
+                Some((
+                    PeerId::from_str(peer_id_str).unwrap_or_else(|_| {
+                        panic!(
+                            "Failed to deserialize PeerId: {} from consensus peers config: ",
+                            peer_id_str
+                        )
+                    }),
+                    peer_info.vrf_pubkey.clone()?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns the BLS12-381 public keys of the consensus peers that registered one, along with
+    /// the proofs of possession of their private keys.
+    pub fn get_consensus_peers_bls_keys(
+        &self,
+    ) -> HashMap<PeerId, (BLS12381PublicKey, BLS12381ProofOfPossession)> {
+        self.consensus_peers
+            .peers
+            .iter()
+            .filter_map(|(peer_id_str, peer_info)| {
+                Some((
+                    PeerId::from_str(peer_id_str).unwrap_or_else(|_| {
+                        panic!(
+                            "Failed to deserialize PeerId: {} from consensus peers config: ",
+                            peer_id_str
+                        )
+                    }),
+                    (
+                        peer_info.bls_pubkey.clone()?,
+                        peer_info.bls_proof_of_possession.clone()?,
+                    ),
+                ))
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SafetyRulesConfig {
+    // Where the consensus state of the safety rules is persisted: "consensus_db" (only for the
+    // safety rules running in the consensus process), "on_disk" or "in_memory".
+    pub backend: String,
+    // File holding the consensus state for the on disk backend, relative to the data directory.
+    pub path: PathBuf,
+    // Whether the safety rules run in the consensus process ("local") or in their own one
+    // ("process"), which listens on the address and port below.
+    pub service: String,
+    pub address: String,
+    pub safety_rules_port: u16,
+    // How long the consensus process waits for the safety rules process to answer a request.
+    pub request_timeout_ms: u64,
+}
+
+impl Default for SafetyRulesConfig {
+    fn default() -> SafetyRulesConfig {
+        SafetyRulesConfig {
+            backend: "consensus_db".to_string(),
+            path: PathBuf::from("safety_rules.json"),
+            service: "local".to_string(),
+            address: "localhost".to_string(),
+            safety_rules_port: 6186,
+            request_timeout_ms: 1000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProposalBackpressureConfig {
+    // If not set, the proposers always pull up to max_block_size transactions from mempool.
+    pub enabled: bool,
+    // The block size never goes below this, unless the proposer falls back to empty blocks.
+    pub min_block_size: u64,
+    // The block size is halved while executing a block takes longer than this on average.
+    pub target_execution_latency_ms: u64,
+    // The block size grows by this many transactions while the execution keeps up and mempool
+    // holds more transactions than fit in a block.
+    pub block_size_increase: u64,
+    // The proposers propose empty blocks while the branch they extend has this many uncommitted
+    // blocks.
+    pub max_pending_blocks: u64,
+}
+
+impl Default for ProposalBackpressureConfig {
+    fn default() -> ProposalBackpressureConfig {
+        ProposalBackpressureConfig {
+            enabled: false,
+            min_block_size: 10,
+            target_execution_latency_ms: 300,
+            block_size_increase: 10,
+            max_pending_blocks: 20,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LeaderReputationConfig {
+    // The proposers are weighted by their record in this many blocks of the chain being extended.
+    pub window_size: u64,
+    // The blocks in this many rounds before the round of a proposal aren't taken into account,
+    // so that the latest proposers have no say in who is elected next.
+    pub exclude_rounds: u64,
+    // The weight of the validators which proposed or voted for any block in the window.
+    pub active_weight: u64,
+    // The weight of the other validators, which must be positive so that they can be elected.
+    pub inactive_weight: u64,
+}
+
+impl Default for LeaderReputationConfig {
+    fn default() -> LeaderReputationConfig {
+        LeaderReputationConfig {
+            window_size: 100,
+            exclude_rounds: 20,
+            active_weight: 100,
+            inactive_weight: 1,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum SafetyRulesBackend {
+    // The consensus state is saved along with the blocks in the consensus DB
+    ConsensusDb,
+    // The consensus state is saved in its own file
+    OnDisk,
+    // The consensus state is lost on restart (for tests only)
+    InMemory,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum SafetyRulesService {
+    // The safety rules and the signing key live in the consensus process
+    Local,
+    // The safety rules and the signing key live in their own process
+    Process,
+}
+
+impl SafetyRulesConfig {
+    pub fn get_backend(&self) -> SafetyRulesBackend {
+        match self.backend.as_str() {
+            "consensus_db" => SafetyRulesBackend::ConsensusDb,
+            "on_disk" => SafetyRulesBackend::OnDisk,
+            "in_memory" => SafetyRulesBackend::InMemory,
+            &_ => unimplemented!("Invalid safety rules backend: {}", self.backend),
+        }
+    }
+
+    pub fn get_service(&self) -> SafetyRulesService {
+        match self.service.as_str() {
+            "local" => SafetyRulesService::Local,
+            "process" => SafetyRulesService::Process,
+            &_ => unimplemented!("Invalid safety rules service: {}", self.service),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -728,6 +980,15 @@ impl NodeConfigHelpers {
             config.metrics.dir = config.base.data_dir_path.join(&config.metrics.dir);
         }
         config.storage.dir = config.base.data_dir_path.join(config.storage.get_dir());
+        config.consensus.safety_rules.path = config
+            .base
+            .data_dir_path
+            .join(&config.consensus.safety_rules.path);
+        config.consensus.recording_path = config
+            .consensus
+            .recording_path
+            .as_ref()
+            .map(|path| config.base.data_dir_path.join(path));
         if config.execution.genesis_file_location == DISPOSABLE_DIR_MARKER {
             config.execution.genesis_file_location = config
                 .base
@@ -752,10 +1013,12 @@ impl NodeConfigHelpers {
         config.debug_interface.admission_control_node_debug_port = get_available_port();
         config.debug_interface.metrics_server_port = get_available_port();
         config.debug_interface.secret_service_node_debug_port = get_available_port();
+        config.debug_interface.safety_rules_node_debug_port = get_available_port();
         config.debug_interface.storage_node_debug_port = get_available_port();
         config.execution.port = get_available_port();
         config.mempool.mempool_service_port = get_available_port();
         config.secret_service.secret_service_port = get_available_port();
+        config.consensus.safety_rules.safety_rules_port = get_available_port();
         config.storage.port = get_available_port();
         for network in &mut config.networks {
             network.advertised_address = Self::randomize_tcp_port(&network.advertised_address);
@@ -770,12 +1033,16 @@ impl NodeConfigHelpers {
 #[serde(default)]
 pub struct VMConfig {
     pub publishing_options: VMPublishingOption,
+    // If set, the transactions of a block are executed speculatively on multiple threads and
+    // re-executed in order when they conflict. The outputs are the same as the sequential ones.
+    pub parallel_execution: bool,
 }
 
 impl Default for VMConfig {
     fn default() -> VMConfig {
         VMConfig {
             publishing_options: VMPublishingOption::Open,
+            parallel_execution: false,
         }
     }
 }
@@ -823,6 +1090,7 @@ impl VMConfig {
     pub fn empty_whitelist_FOR_TESTING() -> Self {
         VMConfig {
             publishing_options: VMPublishingOption::Locked(HashSet::new()),
+            parallel_execution: false,
         }
     }
 }
@@ -1,7 +1,9 @@
 use crate::trusted_peers::{deserialize_key, serialize_key};
 use crypto::{
+    bls12381::BLS12381PrivateKey,
     ed25519::*,
     test_utils::TEST_SEED,
+    vrf::ecvrf::VRFPrivateKey,
     x25519::{self, X25519StaticPrivateKey, X25519StaticPublicKey},
     PrivateKey, ValidKeyStringExt,
 };
@@ -147,6 +149,18 @@ pub struct ConsensusKeyPair {
     #[serde(serialize_with = "serialize_opt_key")]
     #[serde(deserialize_with = "deserialize_opt_key")]
     consensus_public_key: Option<Ed25519PublicKey>,
+    // Only needed by the validators proving the VRF outputs of their proposals.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_opt_key")]
+    #[serde(deserialize_with = "deserialize_opt_key")]
+    vrf_private_key: Option<VRFPrivateKey>,
+    // Only needed by the validators aggregating their signatures in the certificates.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_opt_key")]
+    #[serde(deserialize_with = "deserialize_opt_key")]
+    bls_private_key: Option<BLS12381PrivateKey>,
 }
 
 // required for serialization
@@ -157,6 +171,8 @@ impl Default for ConsensusKeyPair {
         Self {
             consensus_private_key: PrivateKeyContainer::Present(consensus_private_sig),
             consensus_public_key: Some(consensus_public_sig),
+            vrf_private_key: None,
+            bls_private_key: None,
         }
     }
 }
@@ -176,13 +192,37 @@ impl ConsensusKeyPair {
         Self {
             consensus_private_key,
             consensus_public_key,
+            vrf_private_key: None,
+            bls_private_key: None,
         }
     }
 
+    // used in testing to add a VRF key to the structure
+    pub fn with_vrf_private_key(mut self, vrf_private_key: VRFPrivateKey) -> Self {
+        self.vrf_private_key = Some(vrf_private_key);
+        self
+    }
+
+    // used in testing to add a BLS12-381 key to the structure
+    pub fn with_bls_private_key(mut self, bls_private_key: BLS12381PrivateKey) -> Self {
+        self.bls_private_key = Some(bls_private_key);
+        self
+    }
+
     /// Beware, this destroys the private key from this NodeConfig
     pub fn take_consensus_private(&mut self) -> Option<Ed25519PrivateKey> {
         self.consensus_private_key.take()
     }
+
+    /// Beware, this destroys the VRF private key from this NodeConfig
+    pub fn take_vrf_private(&mut self) -> Option<VRFPrivateKey> {
+        self.vrf_private_key.take()
+    }
+
+    /// Beware, this destroys the BLS12-381 private key from this NodeConfig
+    pub fn take_bls_private(&mut self) -> Option<BLS12381PrivateKey> {
+        self.bls_private_key.take()
+    }
 }
 
 pub fn serialize_opt_key<S, K>(opt_key: &Option<K>, serializer: S) -> Result<S::Ok, S::Error>
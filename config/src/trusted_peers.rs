@@ -1,10 +1,14 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::keys::{deserialize_opt_key, serialize_opt_key};
 use crypto::{
+    bls12381::{BLS12381PrivateKey, BLS12381ProofOfPossession, BLS12381PublicKey},
     ed25519::{compat, *},
-    traits::ValidKeyStringExt,
+    traits::{Uniform, ValidKeyStringExt},
+    vrf::ecvrf::{VRFPrivateKey, VRFPublicKey},
     x25519::{self, X25519StaticPrivateKey, X25519StaticPublicKey},
+    HashValue,
 };
 use rand::{rngs::StdRng, SeedableRng};
 use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
@@ -53,6 +57,27 @@ pub struct ConsensusPeerInfo {
     #[serde(deserialize_with = "deserialize_key")]
     #[serde(rename = "c")]
     pub consensus_pubkey: Ed25519PublicKey,
+    // Only the validators proving the VRF outputs of their proposals register a VRF key.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_opt_key")]
+    #[serde(deserialize_with = "deserialize_opt_key")]
+    #[serde(rename = "v")]
+    pub vrf_pubkey: Option<VRFPublicKey>,
+    // Only the validators aggregating their signatures in the certificates register a BLS12-381
+    // key, along with the proof of possession of its private key.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_opt_key")]
+    #[serde(deserialize_with = "deserialize_opt_key")]
+    #[serde(rename = "b")]
+    pub bls_pubkey: Option<BLS12381PublicKey>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "serialize_opt_key")]
+    #[serde(deserialize_with = "deserialize_opt_key")]
+    #[serde(rename = "bp")]
+    pub bls_proof_of_possession: Option<BLS12381ProofOfPossession>,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -98,7 +123,7 @@ impl ConsensusPeersConfig {
             .peers
             .iter()
             .map(|(peer_id_str, peer_info)| {
-                ValidatorPublicKeys::new(
+                let keys = ValidatorPublicKeys::new(
                     AccountAddress::from_str(peer_id_str).expect("[config] invalid peer_id"),
                     peer_info.consensus_pubkey.clone(),
                     network_peers_config
@@ -113,7 +138,13 @@ impl ConsensusPeersConfig {
                         .unwrap()
                         .network_identity_pubkey
                         .clone(),
-                )
+                    peer_info.vrf_pubkey.clone(),
+                );
+                match (&peer_info.bls_pubkey, &peer_info.bls_proof_of_possession) {
+                    (Some(bls_pubkey), Some(bls_proof_of_possession)) => keys
+                        .with_bls_public_key(bls_pubkey.clone(), bls_proof_of_possession.clone()),
+                    _ => keys,
+                }
             })
             .collect();
         // self.peers is a HashMap, so iterating over it produces a differently ordered vector each
@@ -153,6 +184,9 @@ impl ConfigHelpers {
                 peer_id.to_string(),
                 ConsensusPeerInfo {
                     consensus_pubkey: public0,
+                    vrf_pubkey: None,
+                    bls_pubkey: None,
+                    bls_proof_of_possession: None,
                 },
             );
             // save the private keys in a different hashmap
@@ -166,6 +200,47 @@ impl ConfigHelpers {
         )
     }
 
+    /// Registers a VRF key for each of the consensus peers, and returns their private keys.
+    pub fn get_test_vrf_keys(
+        consensus_peers: &mut ConsensusPeersConfig,
+        seed: Option<[u8; 32]>,
+    ) -> HashMap<String, VRFPrivateKey> {
+        let mut peers_private_keys = HashMap::new();
+        // deterministically derive keys from a seeded-rng, seeded differently from the one of the
+        // other keys so that no VRF key is also the private key of another key pair
+        let seed = HashValue::from_sha3_256(&seed.unwrap_or([0u8; 32]));
+        let mut fast_rng = StdRng::from_seed(*seed.as_ref());
+        let peers_ordered: BTreeMap<_, _> = consensus_peers.peers.iter_mut().collect();
+        for (peer_id, peer_info) in peers_ordered {
+            let private_key = VRFPrivateKey::generate_for_testing(&mut fast_rng);
+            peer_info.vrf_pubkey = Some((&private_key).into());
+            peers_private_keys.insert(peer_id.clone(), private_key);
+        }
+        peers_private_keys
+    }
+
+    /// Registers a BLS12-381 key along with the proof of possession of its private key for each
+    /// of the consensus peers, and returns their private keys.
+    pub fn get_test_bls_keys(
+        consensus_peers: &mut ConsensusPeersConfig,
+        seed: Option<[u8; 32]>,
+    ) -> HashMap<String, BLS12381PrivateKey> {
+        let mut peers_private_keys = HashMap::new();
+        // deterministically derive keys from a seeded-rng, seeded differently from the ones of the
+        // other keys
+        let seed =
+            HashValue::from_sha3_256(HashValue::from_sha3_256(&seed.unwrap_or([0u8; 32])).as_ref());
+        let mut fast_rng = StdRng::from_seed(*seed.as_ref());
+        let peers_ordered: BTreeMap<_, _> = consensus_peers.peers.iter_mut().collect();
+        for (peer_id, peer_info) in peers_ordered {
+            let private_key = BLS12381PrivateKey::generate_for_testing(&mut fast_rng);
+            peer_info.bls_pubkey = Some((&private_key).into());
+            peer_info.bls_proof_of_possession = Some(private_key.prove_possession());
+            peers_private_keys.insert(peer_id.clone(), private_key);
+        }
+        peers_private_keys
+    }
+
     pub fn get_test_network_peers_config(
         consensus_peers: &ConsensusPeersConfig,
         seed: Option<[u8; 32]>,
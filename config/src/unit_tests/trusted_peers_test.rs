@@ -1,10 +1,55 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use super::ConfigHelpers;
+use super::{ConfigHelpers, ConsensusPeersConfig};
+use crypto::{bls12381::BLS12381PublicKey, vrf::ecvrf::VRFPublicKey};
 
 #[test]
 fn generate_test_config() {
     let (_, consensus_peers_config) = ConfigHelpers::get_test_consensus_config(10, None);
     let (_, _) = ConfigHelpers::get_test_network_peers_config(&consensus_peers_config, None);
 }
+
+#[test]
+fn generate_test_vrf_keys() {
+    let (_, mut consensus_peers_config) = ConfigHelpers::get_test_consensus_config(4, None);
+    let (_, network_peers_config) =
+        ConfigHelpers::get_test_network_peers_config(&consensus_peers_config, None);
+    let vrf_private_keys = ConfigHelpers::get_test_vrf_keys(&mut consensus_peers_config, None);
+    assert_eq!(vrf_private_keys.len(), 4);
+
+    // The VRF keys are registered in the validator set, and survive the config serialization.
+    let serialized = toml::to_string(&consensus_peers_config).unwrap();
+    let consensus_peers_config: ConsensusPeersConfig = toml::from_str(&serialized).unwrap();
+    let validator_set = consensus_peers_config.get_validator_set(&network_peers_config);
+    for keys in validator_set.payload() {
+        let vrf_private_key = &vrf_private_keys[&keys.account_address().to_string()];
+        assert_eq!(
+            keys.vrf_public_key(),
+            Some(&VRFPublicKey::from(vrf_private_key))
+        );
+    }
+}
+
+#[test]
+fn generate_test_bls_keys() {
+    let (_, mut consensus_peers_config) = ConfigHelpers::get_test_consensus_config(4, None);
+    let (_, network_peers_config) =
+        ConfigHelpers::get_test_network_peers_config(&consensus_peers_config, None);
+    let bls_private_keys = ConfigHelpers::get_test_bls_keys(&mut consensus_peers_config, None);
+    assert_eq!(bls_private_keys.len(), 4);
+
+    // The BLS12-381 keys are registered in the validator set along with valid proofs of
+    // possession, and survive the config serialization.
+    let serialized = toml::to_string(&consensus_peers_config).unwrap();
+    let consensus_peers_config: ConsensusPeersConfig = toml::from_str(&serialized).unwrap();
+    let validator_set = consensus_peers_config.get_validator_set(&network_peers_config);
+    for keys in validator_set.payload() {
+        let bls_private_key = &bls_private_keys[&keys.account_address().to_string()];
+        let (bls_public_key, proof_of_possession) = keys.bls_public_key().unwrap();
+        assert_eq!(bls_public_key, &BLS12381PublicKey::from(bls_private_key));
+        assert!(bls_public_key
+            .verify_possession(proof_of_possession)
+            .is_ok());
+    }
+}
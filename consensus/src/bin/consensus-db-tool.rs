@@ -0,0 +1,91 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tool to inspect the ConsensusDB of a validator offline and to repair it when consensus can't
+//! recover from it on startup: rather than wiping the whole DB, the blocks that don't descend
+//! from the root committed in the ledger can be deleted, and a missing root can be rebuilt from the
+//! ledger. The validator must be stopped to repair its DB, not to inspect it.
+
+use consensus::ConsensusDbInspector;
+use failure::prelude::*;
+use libradb::LibraDB;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use types::transaction::SignedTransaction;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "consensus-db-tool",
+    author = "Libra",
+    about = "Tool to inspect and repair a ConsensusDB offline"
+)]
+struct Opt {
+    /// Directory holding the DBs, i.e. `storage.dir` in the node config.
+    #[structopt(short = "d", long = "db-dir", parse(from_os_str))]
+    db_dir: PathBuf,
+    #[structopt(subcommand)]
+    cmd: Command,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Prints the consensus state and the stored blocks and quorum certs, as a tree below the
+    /// root committed in the ledger.
+    #[structopt(name = "show")]
+    Show,
+    /// Deletes the blocks that don't descend from the root committed in the ledger, along with
+    /// their quorum certs and the quorum certs of blocks that aren't stored.
+    #[structopt(name = "prune")]
+    Prune,
+    /// Resets the ConsensusDB to the genesis the root committed in the ledger derives from, if
+    /// the root or its quorum certs are missing. Only works for roots ending an epoch.
+    #[structopt(name = "repair")]
+    Repair,
+}
+
+fn run(opt: Opt) -> Result<()> {
+    let ledger_info = LibraDB::open_readonly(&opt.db_dir)?
+        .get_latest_ledger_info_option()
+        .ok_or_else(|| format_err!("LibraDB is not bootstrapped."))?
+        .ledger_info()
+        .clone();
+    let committed_root = ledger_info.consensus_block_id();
+
+    match opt.cmd {
+        Command::Show => {
+            let inspector = ConsensusDbInspector::open_readonly(&opt.db_dir)?;
+            print!(
+                "{}",
+                inspector.inspect::<Vec<SignedTransaction>>(committed_root)?
+            );
+        }
+        Command::Prune => {
+            let inspector = ConsensusDbInspector::open(&opt.db_dir)?;
+            let deleted = inspector.prune_orphans::<Vec<SignedTransaction>>(committed_root)?;
+            println!("Deleted {} blocks and quorum certs:", deleted.len());
+            for block_id in deleted {
+                println!("  {}", block_id);
+            }
+        }
+        Command::Repair => {
+            let inspector = ConsensusDbInspector::open(&opt.db_dir)?;
+            if inspector.repair_root::<Vec<SignedTransaction>>(&ledger_info)? {
+                println!(
+                    "Reset the ConsensusDB to the genesis of root {}.",
+                    committed_root
+                );
+            } else {
+                println!(
+                    "Consensus can recover from root {}, nothing to repair.",
+                    committed_root
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    run(Opt::from_args()).expect("Failed to inspect the ConsensusDB.");
+}
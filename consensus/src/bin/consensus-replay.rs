@@ -0,0 +1,63 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tool to replay the consensus events that a validator recorded (see `consensus.recording_path`
+//! in the node config) and check that the validator takes the same decisions again, e.g. to
+//! reproduce a liveness issue under a debugger.
+
+use config::config::NodeConfig;
+use consensus::{read_recorded_runs, ReplayNode};
+use failure::prelude::*;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use types::transaction::SignedTransaction;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "consensus-replay",
+    author = "Libra",
+    about = "Tool to replay the consensus events recorded by a validator"
+)]
+struct Opt {
+    /// Peer id of the validator.
+    #[structopt(short = "p", long = "peer-id")]
+    peer_id: String,
+    /// Node config of the validator, as it was when recording.
+    #[structopt(short = "f", long = "config", parse(from_os_str))]
+    config: PathBuf,
+    /// Recording to replay, the recording path of the node config by default.
+    #[structopt(short = "r", long = "recording", parse(from_os_str))]
+    recording: Option<PathBuf>,
+    /// Run to replay, the last one by default: each start of the validator begins a new run.
+    #[structopt(long = "run")]
+    run: Option<usize>,
+}
+
+fn run(opt: Opt) -> Result<()> {
+    let mut node_config = NodeConfig::load(Some(opt.peer_id), &opt.config)?;
+    let recording = match opt.recording {
+        Some(recording) => recording,
+        None => node_config
+            .consensus
+            .recording_path()
+            .clone()
+            .ok_or_else(|| format_err!("The node config has no recording path."))?,
+    };
+    let mut runs = read_recorded_runs(&recording)?;
+    println!("{} runs recorded to {:?}", runs.len(), recording);
+    let run = opt.run.unwrap_or_else(|| runs.len().saturating_sub(1));
+    ensure!(run < runs.len(), "No run {} in the recording.", run);
+
+    println!("Replaying run {} ({} events)", run, runs[run].len());
+    ReplayNode::<Vec<SignedTransaction>>::from_node_config(
+        &mut node_config,
+        runs.swap_remove(run),
+    )?
+    .replay()?;
+    println!("The validator took the recorded decisions.");
+    Ok(())
+}
+
+fn main() {
+    run(Opt::from_args()).expect("Failed to replay the consensus recording.");
+}
@@ -6,7 +6,7 @@ use crate::{
         block_storage::{block_tree::BlockTree, BlockReader, InsertError, VoteReceptionResult},
         common::{Payload, Round},
         consensus_types::{
-            block::{Block, ExecutedBlock},
+            block::{Block, BlockData, ExecutedBlock},
             quorum_cert::QuorumCert,
             vote_msg::VoteMsg,
         },
@@ -25,7 +25,7 @@ use std::{
     collections::{vec_deque::VecDeque, HashMap},
     sync::{Arc, RwLock},
 };
-use types::{crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo};
+use types::ledger_info::LedgerInfo;
 
 #[cfg(test)]
 #[path = "block_store_test.rs"]
@@ -58,7 +58,6 @@ pub enum NeedFetchResult {
 ///             ╰--------------> D3
 pub struct BlockStore<T> {
     inner: Arc<RwLock<BlockTree<T>>>,
-    validator_signer: ValidatorSigner,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
     enforce_increasing_timestamps: bool,
     /// The persistent storage backing up the in-memory data structure, every write should go
@@ -70,7 +69,6 @@ impl<T: Payload> BlockStore<T> {
     pub async fn new(
         storage: Arc<dyn PersistentStorage<T>>,
         initial_data: RecoveryData<T>,
-        validator_signer: ValidatorSigner,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         enforce_increasing_timestamps: bool,
         max_pruned_blocks_in_mem: usize,
@@ -88,7 +86,6 @@ impl<T: Payload> BlockStore<T> {
         ));
         BlockStore {
             inner,
-            validator_signer,
             state_computer,
             enforce_increasing_timestamps,
             storage,
@@ -163,10 +160,6 @@ impl<T: Payload> BlockStore<T> {
         *self.inner.write().unwrap() = tree;
     }
 
-    pub fn signer(&self) -> &ValidatorSigner {
-        &self.validator_signer
-    }
-
     /// Execute and insert a block if it passes all validation tests.
     /// Returns the Arc to the block kept in the block store after persisting it to storage
     ///
@@ -329,25 +322,27 @@ impl<T: Payload> BlockStore<T> {
     }
 
     /// If block id information is found, returns the ledger info placeholder, otherwise, return
-    /// a placeholder with info of the genesis block.
-    pub fn ledger_info_placeholder(&self, id: Option<HashValue>) -> LedgerInfo {
+    /// a placeholder with info of the genesis block. The placeholder carries the validator set of
+    /// the next epoch if committing the block ends the given epoch.
+    pub fn ledger_info_placeholder(&self, id: Option<HashValue>, epoch_num: u64) -> LedgerInfo {
         let block_id = match id {
-            None => return Self::zero_ledger_info_placeholder(),
+            None => return Self::zero_ledger_info_placeholder(epoch_num),
             Some(id) => id,
         };
         let block = match self.get_block(block_id) {
             Some(b) => b,
             None => {
-                return Self::zero_ledger_info_placeholder();
+                return Self::zero_ledger_info_placeholder(epoch_num);
             }
         };
-        let (state_id, version) = match self.get_compute_result(block_id) {
+        let (state_id, version, next_validator_set) = match self.get_compute_result(block_id) {
             Some(compute_state) => (
                 compute_state.executed_state.state_id,
                 compute_state.executed_state.version,
+                compute_state.executed_state.validators.clone(),
             ),
             None => {
-                return Self::zero_ledger_info_placeholder();
+                return Self::zero_ledger_info_placeholder(epoch_num);
             }
         };
         LedgerInfo::new(
@@ -355,22 +350,22 @@ impl<T: Payload> BlockStore<T> {
             state_id,
             HashValue::zero(),
             block_id,
-            0, // TODO [Reconfiguration] use the real epoch number.
+            epoch_num,
             block.timestamp_usecs(),
-            None,
+            next_validator_set,
         )
     }
 
     /// Used in case we're using a ledger info just as a placeholder for signing the votes / QCs
     /// and there is no real block committed.
     /// It's all pretty much zeroes.
-    fn zero_ledger_info_placeholder() -> LedgerInfo {
+    fn zero_ledger_info_placeholder(epoch_num: u64) -> LedgerInfo {
         LedgerInfo::new(
             0,
             HashValue::zero(),
             HashValue::zero(),
             HashValue::zero(),
-            0,
+            epoch_num,
             0,
             None,
         )
@@ -451,7 +446,7 @@ impl<T: Payload> BlockReader for BlockStore<T> {
         payload: Self::Payload,
         round: Round,
         timestamp_usecs: u64,
-    ) -> Block<Self::Payload> {
+    ) -> BlockData<Self::Payload> {
         if self.enforce_increasing_timestamps {
             checked_precondition!(parent.timestamp_usecs() < timestamp_usecs);
         }
@@ -460,14 +455,7 @@ impl<T: Payload> BlockReader for BlockStore<T> {
             .expect("Parent for the newly created block is not certified!")
             .as_ref()
             .clone();
-        Block::make_block(
-            parent,
-            payload,
-            round,
-            timestamp_usecs,
-            quorum_cert,
-            &self.validator_signer,
-        )
+        BlockData::new(parent, payload, round, timestamp_usecs, quorum_cert)
     }
 
     fn highest_certified_block(&self) -> Arc<Block<Self::Payload>> {
@@ -501,6 +489,15 @@ impl<T: Payload> BlockStore<T> {
         self.inner.read().unwrap().pruned_blocks_in_mem()
     }
 
+    /// Returns the quorum certs of all the blocks in memory, including the pruned ones
+    pub fn quorum_certs(&self) -> Vec<Arc<QuorumCert>> {
+        let tree = self.inner.read().unwrap();
+        tree.get_all_block_id()
+            .iter()
+            .filter_map(|block_id| tree.get_quorum_cert_for_block(block_id))
+            .collect()
+    }
+
     /// Helper to insert vote and qc
     /// Can't be used in production, because production insertion potentially requires state sync
     pub fn insert_vote_and_qc(&self, vote_msg: VoteMsg, qc_size: usize) -> VoteReceptionResult {
@@ -8,11 +8,11 @@ use crate::chained_bft::{
         block::{block_test, Block},
         quorum_cert::QuorumCert,
         vote_data::VoteData,
-        vote_msg::VoteMsg,
+        vote_msg::{VoteMsg, VoteMsgVerificationError},
     },
     test_utils::{
-        build_empty_tree, build_empty_tree_with_custom_signing, placeholder_certificate_for_block,
-        placeholder_ledger_info, TreeInserter,
+        aggregating_validator, build_empty_tree, placeholder_certificate_for_block,
+        placeholder_ledger_info, with_bls_key, TreeInserter,
     },
 };
 use crypto::{HashValue, PrivateKey};
@@ -20,7 +20,9 @@ use futures::executor::block_on;
 use proptest::prelude::*;
 use std::{cmp::min, collections::HashSet, sync::Arc};
 use types::{
-    account_address::AccountAddress, crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo,
+    account_address::AccountAddress,
+    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
+    ledger_info::LedgerInfo,
 };
 
 fn build_simple_tree() -> (Vec<Arc<Block<Vec<usize>>>>, Arc<BlockStore<Vec<usize>>>) {
@@ -62,8 +64,11 @@ fn build_simple_tree() -> (Vec<Arc<Block<Vec<usize>>>>, Arc<BlockStore<Vec<usize
 #[test]
 fn test_block_store_create_block() {
     let block_store = build_empty_tree();
+    let signer = ValidatorSigner::random(None);
     let genesis = block_store.root();
-    let a1 = block_store.create_block(&genesis, vec![1], 1, 1);
+    let a1 = block_store
+        .create_block(&genesis, vec![1], 1, 1)
+        .sign(&signer);
     assert_eq!(a1.parent_id(), genesis.id());
     assert_eq!(a1.round(), 1);
     assert_eq!(a1.height(), 1);
@@ -86,13 +91,15 @@ fn test_block_store_create_block() {
             a1_ref.quorum_cert().grandparent_block_id(),
             a1_ref.quorum_cert().grandparent_block_round(),
         ),
-        block_store.signer().author(),
+        signer.author(),
         placeholder_ledger_info(),
-        block_store.signer(),
+        &signer,
     );
     block_store.insert_vote_and_qc(vote_msg, 1);
 
-    let b1 = block_store.create_block(&a1_ref, vec![2], 2, 2);
+    let b1 = block_store
+        .create_block(&a1_ref, vec![2], 2, 2)
+        .sign(&signer);
     assert_eq!(b1.parent_id(), a1_ref.id());
     assert_eq!(b1.round(), 2);
     assert_eq!(b1.height(), 2);
@@ -185,16 +192,14 @@ proptest! {
 
     #[test]
     fn test_block_store_insert(
-        (mut private_keys, blocks) in block_test::block_forest_and_its_keys(
+        (private_keys, blocks) in block_test::block_forest_and_its_keys(
             // quorum size
             10,
             // recursion depth
             50)
     ){
         let authors: HashSet<Author> = private_keys.iter().map(|private_key| AccountAddress::from_public_key(&private_key.public_key())).collect();
-        let priv_key = private_keys.pop().expect("several keypairs generated");
-        let signer = ValidatorSigner::new(None, priv_key);
-        let block_store = build_empty_tree_with_custom_signing(signer);
+        let block_store = build_empty_tree();
         for block in blocks {
             if block.round() > 0 && authors.contains(&block.author().unwrap()) {
                 let known_parent = block_store.block_exists(block.parent_id());
@@ -349,9 +354,9 @@ fn test_insert_vote() {
         signers.push(ValidatorSigner::random([i as u8; 32]));
     }
     let my_signer = ValidatorSigner::random([qc_size as u8; 32]);
-    let block_store = build_empty_tree_with_custom_signing(my_signer);
+    let block_store = build_empty_tree();
     let genesis = block_store.root();
-    let mut inserter = TreeInserter::new(block_store.clone());
+    let mut inserter = TreeInserter::new_with_signer(my_signer, block_store.clone());
     let block =
         inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), genesis.as_ref(), 1);
 
@@ -421,6 +426,69 @@ fn test_insert_vote() {
     assert_eq!(block_qc.certified_block_id(), block.id());
 }
 
+#[test]
+fn test_insert_vote_aggregates_signatures() {
+    let signers: Vec<_> = (0..4)
+        .map(|i| with_bls_key(ValidatorSigner::random([i as u8; 32]), [i as u8 + 4; 32]))
+        .collect();
+    let validator = aggregating_validator(&signers.iter().collect::<Vec<_>>());
+    let qc_size = validator.quorum_size();
+    let block_store = build_empty_tree();
+    let genesis = block_store.root();
+    let mut inserter = TreeInserter::new_with_signer(signers[0].clone(), block_store.clone());
+    let block =
+        inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), genesis.as_ref(), 1);
+
+    let mut qc = None;
+    for voter in signers.iter().take(qc_size) {
+        let vote_msg = VoteMsg::new(
+            VoteData::new(
+                block.id(),
+                block_store
+                    .get_compute_result(block.id())
+                    .unwrap()
+                    .executed_state
+                    .state_id,
+                block.round(),
+                block.quorum_cert().parent_block_id(),
+                block.quorum_cert().parent_block_round(),
+                block.quorum_cert().grandparent_block_id(),
+                block.quorum_cert().grandparent_block_round(),
+            ),
+            voter.author(),
+            placeholder_ledger_info(),
+            voter,
+        );
+        assert_eq!(vote_msg.verify(&validator), Ok(()));
+        if let VoteReceptionResult::NewQuorumCertificate(new_qc) =
+            block_store.insert_vote_and_qc(vote_msg, qc_size)
+        {
+            qc = Some(new_qc);
+        }
+    }
+
+    // The QC carries the aggregate of the signatures of the voters instead of their signatures,
+    // which only the validators aggregating their signatures accept.
+    let qc = qc.expect("QC not formed!");
+    assert!(qc.ledger_info().signatures().is_empty());
+    let mut signers_of_qc = qc.signers();
+    signers_of_qc.sort();
+    let mut voters: Vec<_> = signers.iter().take(qc_size).map(|s| s.author()).collect();
+    voters.sort();
+    assert_eq!(signers_of_qc, voters);
+    assert_eq!(qc.verify(&validator, None), Ok(()));
+    let non_aggregating_validator = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    );
+    assert_eq!(
+        qc.verify(&non_aggregating_validator, None),
+        Err(VoteMsgVerificationError::SignatureSchemeMismatch)
+    );
+}
+
 #[test]
 /// Verify that votes are properly aggregated based on their LedgerInfo digest
 fn test_vote_aggregation() {
@@ -433,9 +501,9 @@ fn test_vote_aggregation() {
         signers.push(ValidatorSigner::random([i as u8; 32]));
     }
     let my_signer = ValidatorSigner::random([qc_size as u8; 32]);
-    let block_store = build_empty_tree_with_custom_signing(my_signer);
+    let block_store = build_empty_tree();
     let genesis = block_store.root();
-    let mut inserter = TreeInserter::new(block_store.clone());
+    let mut inserter = TreeInserter::new_with_signer(my_signer, block_store.clone());
     let block =
         inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), genesis.as_ref(), 1);
 
@@ -519,9 +587,9 @@ fn test_malicious_vote_validator() {
     let my_signer = ValidatorSigner::random([qc_size as u8; 32]);
     let bad_signer = ValidatorSigner::random([3u8; 32]);
 
-    let block_store = build_empty_tree_with_custom_signing(my_signer);
+    let block_store = build_empty_tree();
     let genesis = block_store.root();
-    let mut inserter = TreeInserter::new(block_store.clone());
+    let mut inserter = TreeInserter::new_with_signer(my_signer, block_store.clone());
     let block0 =
         inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), genesis.as_ref(), 1);
     let block1 =
@@ -653,7 +721,7 @@ fn test_illegal_timestamp() {
         // This timestamp is illegal, it is the same as genesis
         genesis.timestamp_usecs(),
         QuorumCert::certificate_for_genesis(),
-        block_store.signer(),
+        &ValidatorSigner::random(None),
     );
     let result = block_on(block_store.execute_and_insert_block(block_with_illegal_timestamp));
     assert!(result.is_err());
@@ -691,7 +759,7 @@ fn test_need_fetch_for_qc() {
     let a3 = inserter.insert_block(a2.as_ref(), 3);
     block_tree.prune_tree(a2.id());
     let need_fetch_qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         HashValue::zero(),
         a3.round() + 1,
         HashValue::zero(),
@@ -701,7 +769,7 @@ fn test_need_fetch_for_qc() {
     );
     let too_old_qc = QuorumCert::certificate_for_genesis();
     let can_insert_qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         a3.id(),
         a3.round(),
         a2.id(),
@@ -742,7 +810,7 @@ fn test_need_sync_for_qc() {
     let a3 = inserter.insert_block(a2.as_ref(), 3);
     block_tree.prune_tree(a3.id());
     let qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         HashValue::zero(),
         a3.round() + 3,
         HashValue::zero(),
@@ -755,7 +823,7 @@ fn test_need_sync_for_qc() {
         true
     );
     let qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         HashValue::zero(),
         a3.round() + 2,
         HashValue::zero(),
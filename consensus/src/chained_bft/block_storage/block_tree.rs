@@ -6,6 +6,7 @@ use crate::{
         block_storage::{BlockTreeError, VoteReceptionResult},
         common::Author,
         consensus_types::{
+            aggregated_signature::AggregatedSignature,
             block::{Block, ExecutedBlock},
             quorum_cert::QuorumCert,
             vote_data::VoteData,
@@ -22,12 +23,12 @@ use logger::prelude::*;
 use mirai_annotations::{checked_verify_eq, precondition};
 use serde::Serialize;
 use std::{
-    collections::{vec_deque::VecDeque, HashMap},
+    collections::{vec_deque::VecDeque, BTreeMap, HashMap},
     fmt::Debug,
     sync::Arc,
     time::Duration,
 };
-use types::crypto_proxies::LedgerInfoWithSignatures;
+use types::crypto_proxies::{bls12381, LedgerInfoWithSignatures};
 
 /// This structure maintains tuple of block_id and LedgerInfo for last voted block by an Author
 /// We only remember latest vote from Author. Digest is used to identify and prune pending vote from
@@ -37,6 +38,13 @@ struct BlockPendingVote {
     digest: HashValue,
 }
 
+/// The votes collected for a LedgerInfo: its signatures, along with the BLS12-381 ones that the
+/// QuorumCertificate aggregates if the validators aggregate their signatures.
+struct PendingVotes {
+    li_with_sig: LedgerInfoWithSignatures,
+    bls_signatures: BTreeMap<Author, bls12381::Signature>,
+}
+
 /// This structure maintains a consistent block tree of parent and children links. Blocks contain
 /// parent links and are immutable.  For all parent links, a child link exists. This structure
 /// should only be used internally in BlockStore.
@@ -58,8 +66,8 @@ pub struct BlockTree<T> {
     /// LedgerInfo digest covers the potential commit ids, as well as the vote information
     /// (including the 3-chain of a voted proposal).
     /// Thus, the structure of `id_to_votes` is as follows:
-    /// HashMap<proposed_block_id, HashMap<ledger_info_digest, LedgerInfoWithSignatures>>
-    id_to_votes: HashMap<HashValue, HashMap<HashValue, LedgerInfoWithSignatures>>,
+    /// HashMap<proposed_block_id, HashMap<ledger_info_digest, PendingVotes>>
+    id_to_votes: HashMap<HashValue, HashMap<HashValue, PendingVotes>>,
     /// Map of Author to last voted block id & digest. Any pending vote from Author is cleaned up
     /// whenever new vote is added by same Author
     author_to_last_voted_block_id: HashMap<Author, BlockPendingVote>,
@@ -279,10 +287,11 @@ where
         }
 
         if let Some(block_pending_votes) = self.id_to_votes.get_mut(&last_voted_block.block_id) {
-            if let Some(li_digest_to_sig) = block_pending_votes.get_mut(&last_voted_block.digest) {
+            if let Some(pending_votes) = block_pending_votes.get_mut(&last_voted_block.digest) {
                 // Removing signature from last voted block
-                li_digest_to_sig.remove_signature(author);
-                if li_digest_to_sig.signatures().is_empty() {
+                pending_votes.li_with_sig.remove_signature(author);
+                pending_votes.bls_signatures.remove(&author);
+                if pending_votes.li_with_sig.signatures().is_empty() {
                     // Last vote/signature for block, remove digest entry
                     block_pending_votes.remove(&last_voted_block.digest);
                     if block_pending_votes.is_empty() {
@@ -318,26 +327,47 @@ where
         // Note that the digest covers the ledger info information, which is also indirectly
         // covering vote data hash (in its `consensus_data_hash` field).
         let digest = vote_msg.ledger_info().hash();
-        let li_with_sig = block_votes.entry(digest).or_insert_with(|| {
-            LedgerInfoWithSignatures::new(vote_msg.ledger_info().clone(), HashMap::new())
+        let pending_votes = block_votes.entry(digest).or_insert_with(|| PendingVotes {
+            li_with_sig: LedgerInfoWithSignatures::new(
+                vote_msg.ledger_info().clone(),
+                HashMap::new(),
+            ),
+            bls_signatures: BTreeMap::new(),
         });
 
-        vote_msg.signature().clone().add_to_li(author, li_with_sig);
+        vote_msg
+            .signature()
+            .clone()
+            .add_to_li(author, &mut pending_votes.li_with_sig);
+        if let Some(bls_signature) = vote_msg.bls_signature() {
+            pending_votes
+                .bls_signatures
+                .insert(author, bls_signature.clone());
+        }
 
-        let num_votes = li_with_sig.signatures().len();
+        let num_votes = pending_votes.li_with_sig.signatures().len();
         if num_votes >= min_votes_for_qc {
-            let quorum_cert = QuorumCert::new(
-                VoteData::new(
-                    block_id,
-                    vote_msg.executed_state_id(),
-                    vote_msg.block_round(),
-                    vote_msg.parent_block_id(),
-                    vote_msg.parent_block_round(),
-                    vote_msg.grandparent_block_id(),
-                    vote_msg.grandparent_block_round(),
-                ),
-                li_with_sig.clone(),
+            let vote_data = VoteData::new(
+                block_id,
+                vote_msg.executed_state_id(),
+                vote_msg.block_round(),
+                vote_msg.parent_block_id(),
+                vote_msg.parent_block_round(),
+                vote_msg.grandparent_block_id(),
+                vote_msg.grandparent_block_round(),
             );
+            // The votes carry BLS12-381 signatures if and only if the validators aggregate them,
+            // in which case the QC carries their aggregate instead of the signatures.
+            let quorum_cert = if pending_votes.bls_signatures.is_empty() {
+                QuorumCert::new(vote_data, pending_votes.li_with_sig.clone())
+            } else {
+                QuorumCert::new_aggregated(
+                    vote_data,
+                    vote_msg.ledger_info().clone(),
+                    AggregatedSignature::aggregate(&pending_votes.bls_signatures)
+                        .expect("Failed to aggregate the signatures of verified votes"),
+                )
+            };
             // Note that the block might not be present locally, in which case we cannot calculate
             // time between block creation and qc
             if let Some(time_to_qc) = self.try_get_block(&block_id).and_then(|block| {
@@ -7,7 +7,9 @@ use crate::{
         common::Author,
         epoch_manager::EpochManager,
         network::ConsensusNetworkImpl,
+        observer::ConsensusPublisher,
         persistent_storage::{PersistentStorage, StorageWriteProxy},
+        recorder::ConsensusRecorder,
     },
     consensus_provider::ConsensusProvider,
     counters,
@@ -15,7 +17,11 @@ use crate::{
     state_replication::StateMachineReplication,
     txn_manager::MempoolProxy,
 };
-use config::config::{ConsensusProposerType::FixedProposer, NodeConfig};
+use config::config::{
+    ConsensusConfig,
+    ConsensusProposerType::{FixedProposer, VrfProposer},
+    ConsensusSignatureScheme, NodeConfig, SafetyRulesService,
+};
 use executor::Executor;
 use failure::prelude::*;
 use logger::prelude::*;
@@ -26,14 +32,15 @@ use std::{convert::TryFrom, sync::Arc};
 use tokio::runtime;
 use types::{
     account_address::AccountAddress,
-    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
+    crypto_proxies::{bls12381, ValidatorSigner, ValidatorVerifier},
     transaction::SignedTransaction,
 };
 use vm_runtime::MoveVM;
 
 struct InitialSetup {
     author: Author,
-    signer: ValidatorSigner,
+    // Only set if the safety rules run in the consensus process.
+    signer: Option<ValidatorSigner>,
     validator: ValidatorVerifier,
 }
 
@@ -53,6 +60,7 @@ impl ChainedBftProvider {
         mempool_client: Arc<MempoolClient>,
         executor: Arc<Executor<MoveVM>>,
         synchronizer_client: Arc<StateSyncClient>,
+        consensus_publisher: Option<ConsensusPublisher>,
     ) -> Self {
         let runtime = runtime::Builder::new()
             .name_prefix("consensus-")
@@ -61,12 +69,21 @@ impl ChainedBftProvider {
 
         let initial_setup = Self::initialize_setup(node_config);
         let epoch_mgr = Arc::new(EpochManager::new(0, initial_setup.validator.clone()));
-        let network = ConsensusNetworkImpl::new(
+        let mut network = ConsensusNetworkImpl::new(
             initial_setup.author,
             network_sender.clone(),
             network_events,
             Arc::clone(&epoch_mgr),
         );
+        if let Some(recording_path) = node_config.consensus.recording_path() {
+            info!("Recording the consensus events to {:?}", recording_path);
+            let recorder = ConsensusRecorder::new(recording_path)
+                .expect("Failed to create the consensus recording");
+            network = network.with_recorder(Arc::new(recorder));
+        }
+        if let Some(publisher) = consensus_publisher {
+            network = network.with_publisher(Arc::new(publisher));
+        }
         let proposer = {
             let peers = epoch_mgr.validators().get_ordered_account_addresses();
             if node_config.consensus.get_proposer_type() == FixedProposer {
@@ -113,19 +130,18 @@ impl ChainedBftProvider {
             .clone();
         let author =
             AccountAddress::try_from(peer_id_str).expect("Failed to parse peer id of a validator");
-        let private_key = node_config
-            .consensus
-            .consensus_keypair
-            .take_consensus_private()
-            .expect(
-            "Failed to move a Consensus private key from a NodeConfig, key absent or already read",
-        );
-        let signer = ValidatorSigner::new(author, private_key);
+        // The signing keys are left to the safety rules process if they run in their own.
+        let signer = match node_config.consensus.safety_rules().get_service() {
+            SafetyRulesService::Local => Some(Self::take_signer(node_config, author)),
+            SafetyRulesService::Process => None,
+        };
         // Keeping the initial set of validators in a node config is embarrassing and we should
         // all feel bad about it.
-        let peers_with_public_keys = node_config.consensus.get_consensus_peers();
-        let validator = ValidatorVerifier::new(peers_with_public_keys);
-        counters::EPOCH_NUM.set(0); // No reconfiguration yet, so it is always zero
+        let validator = Self::first_epoch_validator(&node_config.consensus)
+            .expect("Failed to build the validators of the first epoch from the node config");
+        // These are the validators of the first epoch: the current epoch and its validators are
+        // recovered from the ledger when the state machine starts.
+        counters::EPOCH_NUM.set(0);
         counters::CURRENT_EPOCH_NUM_VALIDATORS.set(validator.len() as i64);
         counters::CURRENT_EPOCH_QUORUM_SIZE.set(validator.quorum_size() as i64);
         debug!("[Consensus]: quorum_size = {:?}", validator.quorum_size());
@@ -136,6 +152,64 @@ impl ChainedBftProvider {
         }
     }
 
+    pub(crate) fn take_signer(node_config: &mut NodeConfig, author: Author) -> ValidatorSigner {
+        let private_key = node_config
+            .consensus
+            .consensus_keypair
+            .take_consensus_private()
+            .expect(
+            "Failed to move a Consensus private key from a NodeConfig, key absent or already read",
+        );
+        let mut signer = ValidatorSigner::new(author, private_key);
+        // Only the VRF proposer needs the proposals to prove their VRF outputs.
+        if node_config.consensus.get_proposer_type() == VrfProposer {
+            let vrf_private_key = node_config
+                .consensus
+                .consensus_keypair
+                .take_vrf_private()
+                .expect(
+                "Failed to move a VRF private key from a NodeConfig, key absent or already read",
+            );
+            signer = signer.with_vrf_private_key(vrf_private_key);
+        }
+        // Only the validators aggregating their signatures sign with a BLS12-381 key.
+        if node_config.consensus.get_signature_scheme() == ConsensusSignatureScheme::Bls12381 {
+            let bls_private_key = node_config
+                .consensus
+                .consensus_keypair
+                .take_bls_private()
+                .expect(
+                    "Failed to move a BLS12-381 private key from a NodeConfig, key absent or already read",
+                );
+            signer = signer.with_bls_private_key(bls_private_key);
+        }
+        signer
+    }
+
+    /// The validators of the first epoch, as configured: the ones of the later epochs are only
+    /// trusted once the ledger infos ending the epochs before are verified. Fails if the validators
+    /// aggregate their signatures but one of them didn't register a BLS12-381 key with a valid
+    /// proof of possession.
+    pub(crate) fn first_epoch_validator(
+        consensus_config: &ConsensusConfig,
+    ) -> Result<ValidatorVerifier> {
+        let validator = ValidatorVerifier::new(consensus_config.get_consensus_peers())
+            .with_vrf_public_keys(consensus_config.get_consensus_peers_vrf_keys());
+        if consensus_config.get_signature_scheme() != ConsensusSignatureScheme::Bls12381 {
+            return Ok(validator);
+        }
+        let bls_keys = consensus_config.get_consensus_peers_bls_keys();
+        ensure!(
+            bls_keys.len() == validator.len(),
+            "Only {} of the {} validators registered a BLS12-381 key",
+            bls_keys.len(),
+            validator.len()
+        );
+        Ok(validator.with_aggregation_verifier(
+            bls12381::ValidatorVerifier::new_with_proofs_of_possession(bls_keys)?,
+        ))
+    }
+
     /// Choose a proposer that is going to be the single leader (relevant for a mock fixed proposer
     /// election only).
     fn choose_leader(peers: Vec<Author>) -> Author {
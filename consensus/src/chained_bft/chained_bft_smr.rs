@@ -5,18 +5,34 @@ use crate::{
     chained_bft::{
         block_storage::BlockStore,
         common::{Payload, Round},
+        consensus_types::{block::Block, quorum_cert::QuorumCert},
         event_processor::EventProcessor,
+        execution_pipeline::{ExecutedBlocks, ExecutionPipeline, OrderedBlocks},
         liveness::{
+            block_size_controller::{
+                BlockSizeController, ExecutionLatencyTracker, LatencyTrackingStateComputer,
+            },
+            leader_reputation::{LeaderReputation, StorageBackend},
             multi_proposer_election::MultiProposer,
             pacemaker::{ExponentialTimeInterval, Pacemaker},
             pacemaker_timeout_manager::HighestTimeoutCertificates,
             proposal_generator::ProposalGenerator,
             proposer_election::ProposerElection,
             rotating_proposer_election::RotatingProposer,
+            vrf_proposer_election::VrfProposer,
         },
         network::{ConsensusNetworkImpl, NetworkReceivers},
         persistent_storage::{PersistentLivenessStorage, PersistentStorage, RecoveryData},
-        safety::safety_rules::SafetyRules,
+        recorder::{ConsensusRecorder, RecordedEvent, RecordingStateComputer},
+        safety::{
+            local_safety_rules::LocalSafetyRules,
+            persistent_safety_storage::{
+                ConsensusDbStorage, InMemoryStorage, OnDiskStorage, PersistentSafetyStorage,
+            },
+            remote_safety_rules::RemoteSafetyRules,
+            safety_rules::ConsensusState,
+            t_safety_rules::TSafetyRules,
+        },
     },
     counters,
     state_replication::{StateComputer, StateMachineReplication, TxnManager},
@@ -25,6 +41,7 @@ use crate::{
 use channel;
 use failure::prelude::*;
 use futures::{
+    channel::mpsc,
     compat::Future01CompatExt,
     executor::block_on,
     future::{FutureExt, TryFutureExt},
@@ -33,13 +50,17 @@ use futures::{
 };
 
 use crate::chained_bft::{common::Author, epoch_manager::EpochManager};
-use config::config::{ConsensusConfig, ConsensusProposerType};
+use config::config::{
+    ConsensusConfig, ConsensusProposerType, LeaderReputationConfig, ProposalBackpressureConfig,
+    SafetyRulesBackend, SafetyRulesConfig, SafetyRulesService,
+};
 use logger::prelude::*;
 use std::{sync::Arc, time::Duration};
 use tokio::runtime::{Runtime, TaskExecutor};
-use types::crypto_proxies::ValidatorSigner;
+use types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner};
 
 /// Consensus configuration derived from ConsensusConfig
+#[derive(Clone)]
 pub struct ChainedBftSMRConfig {
     /// Keep up to this number of committed blocks before cleaning them up from the block store.
     pub max_pruned_blocks_in_mem: usize,
@@ -51,6 +72,14 @@ pub struct ChainedBftSMRConfig {
     pub contiguous_rounds: u32,
     /// Max block size (number of transactions) that consensus pulls from mempool
     pub max_block_size: u64,
+    /// Where the safety rules run and how they persist their state
+    pub safety_rules: SafetyRulesConfig,
+    /// Whether the blocks are executed off the voting path and committed by commit votes
+    pub decoupled_execution: bool,
+    /// How the proposers adapt the block size when the execution falls behind
+    pub proposal_backpressure: ProposalBackpressureConfig,
+    /// How the leader reputation proposer election weights the proposers
+    pub leader_reputation: LeaderReputationConfig,
 }
 
 impl ChainedBftSMRConfig {
@@ -62,6 +91,10 @@ impl ChainedBftSMRConfig {
             proposer_type: cfg.get_proposer_type(),
             contiguous_rounds: cfg.contiguous_rounds(),
             max_block_size: cfg.max_block_size(),
+            safety_rules: cfg.safety_rules().clone(),
+            decoupled_execution: cfg.decoupled_execution(),
+            proposal_backpressure: cfg.proposal_backpressure().clone(),
+            leader_reputation: cfg.leader_reputation().clone(),
         }
     }
 }
@@ -82,41 +115,31 @@ pub struct ChainedBftSMR<T> {
     epoch_mgr: Arc<EpochManager>,
 }
 
-impl<T: Payload> ChainedBftSMR<T> {
-    pub fn new(
-        author: Author,
-        signer: ValidatorSigner,
-        proposers: Vec<Author>,
-        network: ConsensusNetworkImpl,
-        runtime: Runtime,
-        config: ChainedBftSMRConfig,
-        storage: Arc<dyn PersistentStorage<T>>,
-        initial_data: RecoveryData<T>,
-        epoch_mgr: Arc<EpochManager>,
-    ) -> Self {
-        Self {
-            author,
-            signer: Some(signer),
-            proposers,
-            runtime: Some(runtime),
-            block_store: None,
-            network,
-            config,
-            storage,
-            initial_data: Some(initial_data),
-            epoch_mgr,
-        }
-    }
-
-    #[cfg(test)]
-    pub fn block_store(&self) -> Option<Arc<BlockStore<T>>> {
-        self.block_store.clone()
-    }
+/// The components shared by the event processors of all the epochs, used to start the event
+/// processor of each epoch.
+pub(crate) struct EpochContext<T> {
+    pub(crate) author: Author,
+    pub(crate) config: ChainedBftSMRConfig,
+    pub(crate) block_store: Arc<BlockStore<T>>,
+    pub(crate) txn_manager: Arc<dyn TxnManager<Payload = T>>,
+    pub(crate) state_computer: Arc<dyn StateComputer<Payload = T>>,
+    pub(crate) network: ConsensusNetworkImpl,
+    pub(crate) storage: Arc<dyn PersistentStorage<T>>,
+    pub(crate) time_service: Arc<dyn TimeService>,
+    pub(crate) epoch_mgr: Arc<EpochManager>,
+    // Set in the decoupled execution mode.
+    pub(crate) execution_pipeline: Option<(
+        Arc<ExecutionPipeline<T>>,
+        mpsc::UnboundedSender<OrderedBlocks<T>>,
+    )>,
+    // Set if the proposers adapt the block size to the execution latency.
+    pub(crate) execution_latency: Option<Arc<ExecutionLatencyTracker>>,
+}
 
+impl<T: Payload> EpochContext<T> {
     fn create_pacemaker(
         &self,
         persistent_liveness_storage: Box<dyn PersistentLivenessStorage>,
-        time_service: Arc<dyn TimeService>,
         timeout_sender: channel::Sender<Round>,
         highest_timeout_certificate: HighestTimeoutCertificates,
     ) -> Pacemaker {
@@ -130,60 +153,368 @@ impl<T: Payload> ChainedBftSMR<T> {
         Pacemaker::new(
             persistent_liveness_storage,
             time_interval,
-            time_service,
+            self.time_service.clone(),
             timeout_sender,
             highest_timeout_certificate,
         )
     }
 
     /// Create a proposer election handler based on proposers
-    fn create_proposer_election(&self) -> Box<dyn ProposerElection<T> + Send + Sync> {
-        assert!(!self.proposers.is_empty());
+    fn create_proposer_election(
+        &self,
+        proposers: Vec<Author>,
+    ) -> Box<dyn ProposerElection<T> + Send + Sync> {
+        assert!(!proposers.is_empty());
         match self.config.proposer_type {
             ConsensusProposerType::MultipleOrderedProposers => {
-                Box::new(MultiProposer::new(self.proposers.clone(), 2))
+                Box::new(MultiProposer::new(proposers, 2))
+            }
+            ConsensusProposerType::LeaderReputation => {
+                let config = &self.config.leader_reputation;
+                let backend = StorageBackend::new(
+                    Arc::clone(&self.storage),
+                    self.epoch_mgr.epoch() as u64,
+                    config.window_size as usize,
+                    config.exclude_rounds,
+                );
+                Box::new(LeaderReputation::new(
+                    proposers,
+                    Box::new(backend),
+                    self.block_store.clone(),
+                    config.window_size as usize,
+                    config.exclude_rounds,
+                    config.active_weight,
+                    config.inactive_weight,
+                ))
+            }
+            ConsensusProposerType::VrfProposer => {
+                Box::new(VrfProposer::new(proposers, self.epoch_mgr.validators()))
             }
             // We don't really have a fixed proposer!
             _ => Box::new(RotatingProposer::new(
-                self.proposers.clone(),
+                proposers,
                 self.config.contiguous_rounds,
             )),
         }
     }
 
+    /// Creates the event processor of the current epoch along with the receiver of its pacemaker
+    /// timeouts.
+    fn create_event_processor(
+        &self,
+        proposers: Vec<Author>,
+        safety_rules: Box<dyn TSafetyRules<T> + Send + Sync>,
+        highest_timeout_certificates: HighestTimeoutCertificates,
+    ) -> (EventProcessor<T>, channel::Receiver<Round>) {
+        // txn manager is required both by proposal generator (to pull the proposers)
+        // and by event processor (to update their status).
+        let proposal_generator = ProposalGenerator::new(
+            self.block_store.clone(),
+            Arc::clone(&self.txn_manager),
+            self.time_service.clone(),
+            self.config.max_block_size,
+            true,
+        );
+        let proposal_generator = match &self.execution_latency {
+            Some(execution_latency) => {
+                proposal_generator.with_block_size_controller(BlockSizeController::new(
+                    self.config.proposal_backpressure.clone(),
+                    self.config.max_block_size,
+                    Arc::clone(execution_latency),
+                ))
+            }
+            None => proposal_generator,
+        };
+
+        let (timeout_sender, timeout_receiver) =
+            channel::new(1_024, &counters::PENDING_PACEMAKER_TIMEOUTS);
+        let pacemaker = self.create_pacemaker(
+            self.storage.persistent_liveness_storage(),
+            timeout_sender,
+            highest_timeout_certificates,
+        );
+
+        let proposer_election = self.create_proposer_election(proposers);
+        let event_processor = EventProcessor::new(
+            self.author,
+            Arc::clone(&self.block_store),
+            pacemaker,
+            proposer_election,
+            proposal_generator,
+            safety_rules,
+            Arc::clone(&self.state_computer),
+            Arc::clone(&self.txn_manager),
+            self.network.clone(),
+            Arc::clone(&self.storage),
+            self.time_service.clone(),
+            true,
+            Arc::clone(&self.epoch_mgr),
+        );
+        let event_processor = match &self.execution_pipeline {
+            Some((execution_pipeline, ordered_blocks_tx)) => event_processor
+                .with_execution_pipeline(Arc::clone(execution_pipeline), ordered_blocks_tx.clone()),
+            None => event_processor,
+        };
+        (event_processor, timeout_receiver)
+    }
+
+    /// Moves to the epoch following the one ended by the last of the given ledger infos: the block
+    /// tree starts over from the genesis of the new epoch and the event processor is recreated
+    /// with the new validator set and fresh safety and liveness data. The safety rules are handed
+    /// over from the event processor of the previous epoch, and the ledger infos ending all the
+    /// epochs so far prove them the new one.
+    pub(crate) async fn start_new_epoch(
+        &self,
+        epoch_change_proof: &[LedgerInfoWithSignatures],
+        mut safety_rules: Box<dyn TSafetyRules<T> + Send + Sync>,
+    ) -> (EventProcessor<T>, channel::Receiver<Round>) {
+        let ledger_info = epoch_change_proof
+            .last()
+            .expect("no ledger info ending the epoch")
+            .ledger_info();
+        self.epoch_mgr.start_new_epoch(ledger_info);
+
+        let genesis = Block::make_genesis_block_from_ledger_info(ledger_info);
+        let genesis_qc = QuorumCert::certificate_for_genesis_from_ledger_info(ledger_info);
+        self.block_store
+            .rebuild(
+                (genesis.clone(), genesis_qc.clone(), genesis_qc.clone()),
+                vec![],
+                vec![],
+            )
+            .await;
+        // The blocks of the previous epoch are pruned from the storage by now, including the
+        // last one whose id the genesis takes over.
+        self.storage
+            .save_tree(vec![genesis], vec![genesis_qc])
+            .expect("unable to save the genesis block of the new epoch");
+
+        // Rounds start over in the new epoch, so do the safety and liveness data.
+        safety_rules
+            .start_new_epoch(epoch_change_proof)
+            .expect("unable to reset the consensus state for the new epoch");
+        let highest_timeout_certificates = HighestTimeoutCertificates::default();
+        self.storage
+            .save_highest_timeout_cert(highest_timeout_certificates.clone())
+            .expect("unable to reset the highest timeout certificates for the new epoch");
+
+        self.create_event_processor(
+            self.epoch_proposers(),
+            safety_rules,
+            highest_timeout_certificates,
+        )
+    }
+
+    /// Creates the event processor to start with, from the recovered root committed by the given
+    /// ledger info and the ledger infos ending the previous epochs, which the ledger info is added
+    /// to if it ends an epoch too. The given proposers are the ones of the first epoch.
+    pub(crate) async fn start_from_root(
+        &self,
+        root_ledger_info: LedgerInfoWithSignatures,
+        epoch_change_proof: &mut Vec<LedgerInfoWithSignatures>,
+        mut safety_rules: Box<dyn TSafetyRules<T> + Send + Sync>,
+        highest_timeout_certificates: HighestTimeoutCertificates,
+        initial_proposers: Vec<Author>,
+    ) -> (EventProcessor<T>, channel::Receiver<Round>) {
+        // The root is committed by a ledger info ending the epoch if the node stopped right after
+        // committing the last block of the epoch, or if it's about to sync to that block: the new
+        // epoch has yet to start from it.
+        if root_ledger_info
+            .ledger_info()
+            .next_validator_set()
+            .is_some()
+        {
+            // The ledger info ending the epoch may not be committed to the ledger yet.
+            if epoch_change_proof.last().map_or(true, |last| {
+                last.ledger_info().epoch_num() < root_ledger_info.ledger_info().epoch_num()
+            }) {
+                epoch_change_proof.push(root_ledger_info);
+            }
+            return self.start_new_epoch(epoch_change_proof, safety_rules).await;
+        }
+        // Otherwise the epoch carries on from the latest ledger info ending an epoch committed to
+        // the ledger, if any. The safety rules may have restarted since then, e.g. if they run in
+        // their own process, so they're proven the epoch again.
+        safety_rules
+            .start_new_epoch(epoch_change_proof)
+            .expect("unable to prove the current epoch to the safety rules");
+        let proposers = match epoch_change_proof.last() {
+            Some(ledger_info) => {
+                self.epoch_mgr.start_new_epoch(ledger_info.ledger_info());
+                self.epoch_proposers()
+            }
+            None => initial_proposers,
+        };
+        self.create_event_processor(proposers, safety_rules, highest_timeout_certificates)
+    }
+
+    /// The proposers among the validators of the current epoch.
+    pub(crate) fn epoch_proposers(&self) -> Vec<Author> {
+        let peers = self.epoch_mgr.validators().get_ordered_account_addresses();
+        match self.config.proposer_type {
+            // As on startup, the validator with the highest address is the fixed proposer.
+            ConsensusProposerType::FixedProposer => vec![peers
+                .into_iter()
+                .max()
+                .expect("No validators in the epoch!")],
+            _ => peers,
+        }
+    }
+}
+
+impl<T: Payload> ChainedBftSMR<T> {
+    /// The signer is only needed if the safety rules run in the consensus process.
+    pub fn new(
+        author: Author,
+        signer: Option<ValidatorSigner>,
+        proposers: Vec<Author>,
+        network: ConsensusNetworkImpl,
+        runtime: Runtime,
+        config: ChainedBftSMRConfig,
+        storage: Arc<dyn PersistentStorage<T>>,
+        initial_data: RecoveryData<T>,
+        epoch_mgr: Arc<EpochManager>,
+    ) -> Self {
+        Self {
+            author,
+            signer,
+            proposers,
+            runtime: Some(runtime),
+            block_store: None,
+            network,
+            config,
+            storage,
+            initial_data: Some(initial_data),
+            epoch_mgr,
+        }
+    }
+
+    /// Creates the safety rules described by the config, either in the current process or as a
+    /// client of the safety rules process, which holds the signing key instead.
+    fn create_safety_rules(
+        &mut self,
+        consensus_state: ConsensusState,
+    ) -> Result<Box<dyn TSafetyRules<T> + Send + Sync>> {
+        let config = &self.config.safety_rules;
+        Ok(match config.get_service() {
+            SafetyRulesService::Local => {
+                // the signer is only stored in the SMR to be provided here
+                let signer = Arc::new(self.signer.take().ok_or_else(|| {
+                    format_err!("The safety rules running in the consensus process need a signer")
+                })?);
+                let storage: Box<dyn PersistentSafetyStorage> = match config.get_backend() {
+                    SafetyRulesBackend::ConsensusDb => Box::new(ConsensusDbStorage::new(
+                        Arc::clone(&self.storage),
+                        consensus_state,
+                    )),
+                    SafetyRulesBackend::OnDisk => Box::new(OnDiskStorage::new_with_default(
+                        config.path.clone(),
+                        consensus_state,
+                        Arc::clone(&signer),
+                    )?),
+                    SafetyRulesBackend::InMemory => Box::new(InMemoryStorage::new(consensus_state)),
+                };
+                Box::new(LocalSafetyRules::new(signer, storage))
+            }
+            SafetyRulesService::Process => Box::new(RemoteSafetyRules::connect(
+                &config.address,
+                config.safety_rules_port,
+                Duration::from_millis(config.request_timeout_ms),
+            )),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn block_store(&self) -> Option<Arc<BlockStore<T>>> {
+        self.block_store.clone()
+    }
+
+    /// Drives the event processor of the current epoch and replaces it with a new one whenever
+    /// a committed block ends the epoch. The events are recorded in the order they are processed
+    /// if a recorder is given.
     fn start_event_processing(
         &mut self,
         executor: TaskExecutor,
+        context: EpochContext<T>,
         mut event_processor: EventProcessor<T>,
         mut pacemaker_timeout_sender_rx: channel::Receiver<Round>,
         mut network_receivers: NetworkReceivers<T>,
+        mut executed_blocks_rx: mpsc::UnboundedReceiver<Result<ExecutedBlocks<T>>>,
+        recorder: Option<Arc<ConsensusRecorder>>,
+        mut epoch_change_proof: Vec<LedgerInfoWithSignatures>,
     ) {
         let fut = async move {
-            event_processor.start().await;
             loop {
-                select! {
-                    proposal_msg = network_receivers.proposals.select_next_some() => {
-                        event_processor.process_proposal_msg(proposal_msg).await;
-                    }
-                    block_retrieval = network_receivers.block_retrieval.select_next_some() => {
-                        event_processor.process_block_retrieval(block_retrieval).await;
-                    }
-                    vote_msg = network_receivers.votes.select_next_some() => {
-                        event_processor.process_vote(vote_msg).await;
+                event_processor.start().await;
+                let epoch_ending_ledger_infos = loop {
+                    select! {
+                        proposal_msg = network_receivers.proposals.select_next_some() => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record_proposal(&proposal_msg);
+                            }
+                            event_processor.process_proposal_msg(proposal_msg).await;
+                        }
+                        block_retrieval = network_receivers.block_retrieval.select_next_some() => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record(RecordedEvent::BlockRetrievalRequest {
+                                    block_id: block_retrieval.block_id,
+                                    num_blocks: block_retrieval.num_blocks,
+                                });
+                            }
+                            event_processor.process_block_retrieval(block_retrieval).await;
+                        }
+                        vote_msg = network_receivers.votes.select_next_some() => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record_vote(&vote_msg);
+                            }
+                            event_processor.process_vote(vote_msg).await;
+                        }
+                        remote_timeout_msg = network_receivers.timeout_msgs.select_next_some() => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record_timeout_msg(&remote_timeout_msg);
+                            }
+                            event_processor.process_remote_timeout_msg(remote_timeout_msg).await;
+                        }
+                        local_timeout_round = pacemaker_timeout_sender_rx.select_next_some() => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record(RecordedEvent::LocalTimeout {
+                                    round: local_timeout_round,
+                                });
+                            }
+                            event_processor.process_local_timeout(local_timeout_round).await;
+                        }
+                        sync_info_msg = network_receivers.sync_info_msgs.select_next_some() => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record_sync_info(&sync_info_msg.0, sync_info_msg.1);
+                            }
+                            event_processor.process_sync_info_msg(sync_info_msg.0, sync_info_msg.1).await;
+                        }
+                        executed_blocks = executed_blocks_rx.select_next_some() => {
+                            event_processor.process_executed_blocks(executed_blocks).await;
+                        }
+                        commit_vote = network_receivers.commit_votes.select_next_some() => {
+                            event_processor.process_commit_vote(commit_vote).await;
+                        }
+                        different_epoch = network_receivers.different_epoch.select_next_some() => {
+                            event_processor.process_different_epoch(different_epoch.0, different_epoch.1, &epoch_change_proof).await;
+                        }
+                        epoch_change = network_receivers.epoch_change_proofs.select_next_some() => {
+                            event_processor.process_epoch_change_proof(epoch_change.0, epoch_change.1).await;
+                        }
+                        complete => {
+                            return;
+                        }
                     }
-                    remote_timeout_msg = network_receivers.timeout_msgs.select_next_some() => {
-                        event_processor.process_remote_timeout_msg(remote_timeout_msg).await;
+                    if !event_processor.epoch_change_proof().is_empty() {
+                        break event_processor.epoch_change_proof().to_vec();
                     }
-                    local_timeout_round = pacemaker_timeout_sender_rx.select_next_some() => {
-                        event_processor.process_local_timeout(local_timeout_round).await;
-                    }
-                    sync_info_msg = network_receivers.sync_info_msgs.select_next_some() => {
-                        event_processor.process_sync_info_msg(sync_info_msg.0, sync_info_msg.1).await;
-                    }
-                    complete => {
-                        break;
-                    }
-                }
+                };
+                epoch_change_proof.extend(epoch_ending_ledger_infos);
+                let (new_event_processor, new_timeout_rx) = context
+                    .start_new_epoch(&epoch_change_proof, event_processor.into_safety_rules())
+                    .await;
+                event_processor = new_event_processor;
+                pacemaker_timeout_sender_rx = new_timeout_rx;
             }
         };
         executor.spawn(fut.boxed().unit_error().compat());
@@ -213,6 +544,22 @@ impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
             .expect("already started, initial data is None");
         let consensus_state = initial_data.state();
         let highest_timeout_certificates = initial_data.highest_timeout_certificates().clone();
+        let mut epoch_change_proof = initial_data.epoch_change_proof().to_vec();
+        let root_ledger_info = initial_data.root_ledger_info().ledger_info().clone();
+
+        // In recording mode, the results of the state computer are recorded along with the
+        // messages, after the data consensus starts from.
+        let recorder = self.network.recorder();
+        let state_computer: Arc<dyn StateComputer<Payload = T>> = match &recorder {
+            Some(recorder) => {
+                recorder.record_startup(self.author, &initial_data, self.storage.as_ref());
+                Arc::new(RecordingStateComputer::new(
+                    state_computer,
+                    Arc::clone(recorder),
+                ))
+            }
+            None => state_computer,
+        };
         if initial_data.need_sync() {
             loop {
                 // make sure we sync to the root state in case we're not
@@ -234,15 +581,42 @@ impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
             }
         }
 
-        // the signer is only stored in the SMR to be provided here
-        let signer = self
-            .signer
-            .take()
-            .expect("start called twice on the same Chained BFT SMR!");
+        // With proposal backpressure, the actual executions are timed, underneath the pipeline.
+        let execution_latency = if self.config.proposal_backpressure.enabled {
+            Some(Arc::new(ExecutionLatencyTracker::new()))
+        } else {
+            None
+        };
+        let state_computer: Arc<dyn StateComputer<Payload = T>> = match &execution_latency {
+            Some(execution_latency) => Arc::new(LatencyTrackingStateComputer::new(
+                state_computer,
+                Arc::clone(execution_latency),
+            )),
+            None => state_computer,
+        };
+        // In decoupled execution mode, the block store gets the ordering-only state of the blocks
+        // right away while the pipeline executes them.
+        let (execution_pipeline, executed_blocks_rx) = if self.config.decoupled_execution {
+            let execution_pipeline = Arc::new(ExecutionPipeline::new(Arc::clone(&state_computer)));
+            let (ordered_blocks_tx, executed_blocks_rx) =
+                ExecutionPipeline::start(Arc::clone(&execution_pipeline), &executor);
+            (
+                Some((execution_pipeline, ordered_blocks_tx)),
+                executed_blocks_rx,
+            )
+        } else {
+            // Nothing is ever executed off the voting path.
+            (None, mpsc::unbounded().1)
+        };
+        let state_computer: Arc<dyn StateComputer<Payload = T>> = match &execution_pipeline {
+            Some((execution_pipeline, _)) => execution_pipeline.clone(),
+            None => state_computer,
+        };
+
+        let safety_rules = self.create_safety_rules(consensus_state)?;
         let block_store = Arc::new(block_on(BlockStore::new(
             Arc::clone(&self.storage),
             initial_data,
-            signer,
             Arc::clone(&state_computer),
             true,
             self.config.max_pruned_blocks_in_mem,
@@ -250,49 +624,36 @@ impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
 
         self.block_store = Some(Arc::clone(&block_store));
 
-        // txn manager is required both by proposal generator (to pull the proposers)
-        // and by event processor (to update their status).
-        let proposal_generator = ProposalGenerator::new(
-            block_store.clone(),
-            Arc::clone(&txn_manager),
-            time_service.clone(),
-            self.config.max_block_size,
-            true,
-        );
-
-        let safety_rules = SafetyRules::new(consensus_state);
-
-        let (timeout_sender, timeout_receiver) =
-            channel::new(1_024, &counters::PENDING_PACEMAKER_TIMEOUTS);
-        let pacemaker = self.create_pacemaker(
-            self.storage.persistent_liveness_storage(),
-            time_service.clone(),
-            timeout_sender,
-            highest_timeout_certificates,
-        );
-
-        let proposer_election = self.create_proposer_election();
-        let event_processor = EventProcessor::new(
-            self.author,
-            Arc::clone(&block_store),
-            pacemaker,
-            proposer_election,
-            proposal_generator,
-            safety_rules,
-            state_computer,
+        let context = EpochContext {
+            author: self.author,
+            config: self.config.clone(),
+            block_store,
             txn_manager,
-            self.network.clone(),
-            Arc::clone(&self.storage),
-            time_service.clone(),
-            true,
-            Arc::clone(&self.epoch_mgr),
-        );
+            state_computer,
+            network: self.network.clone(),
+            storage: Arc::clone(&self.storage),
+            time_service,
+            epoch_mgr: Arc::clone(&self.epoch_mgr),
+            execution_pipeline,
+            execution_latency,
+        };
+        let (event_processor, timeout_receiver) = block_on(context.start_from_root(
+            root_ledger_info,
+            &mut epoch_change_proof,
+            safety_rules,
+            highest_timeout_certificates,
+            self.proposers.clone(),
+        ));
 
         self.start_event_processing(
             executor,
+            context,
             event_processor,
             timeout_receiver,
             network_receivers,
+            executed_blocks_rx,
+            recorder,
+            epoch_change_proof,
         );
 
         debug!("Chained BFT SMR started.");
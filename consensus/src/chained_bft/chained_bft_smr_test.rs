@@ -5,19 +5,24 @@ use crate::{
     chained_bft::{
         block_storage::BlockReader,
         chained_bft_smr::{ChainedBftSMR, ChainedBftSMRConfig},
-        common::Author,
+        common::{Author, Round},
         consensus_types::{
+            block::Block,
             proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
             vote_msg::VoteMsg,
         },
         network::ConsensusNetworkImpl,
-        network_tests::NetworkPlayground,
-        test_utils::{MockStateComputer, MockStorage, MockTransactionManager, TestPayload},
+        network_tests::{NetworkPlayground, TwinId},
+        recorder::{ConsensusRecorder, RecordedEntry, RecordedEvent},
+        replay::ReplayNode,
+        test_utils::{
+            with_vrf_key, MockStateComputer, MockStorage, MockTransactionManager, TestPayload,
+        },
     },
     state_replication::StateMachineReplication,
 };
 use channel;
-use crypto::hash::CryptoHash;
+use crypto::{hash::CryptoHash, x25519, HashValue};
 use futures::{channel::mpsc, executor::block_on, prelude::*};
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
 use proto_conv::FromProto;
@@ -29,72 +34,84 @@ use crate::chained_bft::{
     persistent_storage::RecoveryData,
     test_utils::{consensus_runtime, with_smr_id},
 };
-use config::config::ConsensusProposerType::{
-    self, FixedProposer, MultipleOrderedProposers, RotatingProposer,
+use config::config::{
+    ConsensusProposerType::{
+        self, FixedProposer, MultipleOrderedProposers, RotatingProposer, VrfProposer,
+    },
+    LeaderReputationConfig, ProposalBackpressureConfig, SafetyRulesConfig,
 };
 use std::{collections::HashMap, time::Duration};
 use tokio::runtime;
-use types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier};
+use types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier},
+    validator_public_keys::ValidatorPublicKeys,
+    validator_set::ValidatorSet,
+};
 
 /// Auxiliary struct that is preparing SMR for the test
 struct SMRNode {
     author: Author,
+    twin_id: TwinId,
     signer: ValidatorSigner,
     epoch_mgr: Arc<EpochManager>,
+    // The validators of the first epoch, which a restarted node starts from.
+    initial_validators: Arc<ValidatorVerifier>,
     proposer: Vec<Author>,
     proposer_type: ConsensusProposerType,
+    decoupled_execution: bool,
     smr_id: usize,
     smr: ChainedBftSMR<TestPayload>,
     commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    state_computer: Arc<MockStateComputer>,
     mempool: Arc<MockTransactionManager>,
     mempool_notif_receiver: mpsc::Receiver<usize>,
     storage: Arc<MockStorage<TestPayload>>,
+    recorder: Arc<ConsensusRecorder>,
 }
 
 impl SMRNode {
     fn start(
         playground: &mut NetworkPlayground,
         signer: ValidatorSigner,
+        twin_id: TwinId,
         epoch_mgr: Arc<EpochManager>,
         proposer: Vec<Author>,
         smr_id: usize,
         storage: Arc<MockStorage<TestPayload>>,
         initial_data: RecoveryData<TestPayload>,
         proposer_type: ConsensusProposerType,
+        decoupled_execution: bool,
     ) -> Self {
         let author = signer.author();
+        assert_eq!(twin_id.author, author);
+        let initial_validators = epoch_mgr.validators();
 
         let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
         let (consensus_tx, consensus_rx) = channel::new_test(8);
         let network_sender = ConsensusNetworkSender::new(network_reqs_tx);
         let network_events = ConsensusNetworkEvents::new(consensus_rx);
 
-        playground.add_node(author, consensus_tx, network_reqs_rx);
+        playground.add_twin(twin_id, consensus_tx, network_reqs_rx);
         let runtime = runtime::Builder::new()
             .after_start(with_smr_id(signer.author().short_str()))
             .build()
             .expect("Failed to create Tokio runtime!");
+        let recorder = Arc::new(ConsensusRecorder::in_memory());
         let network = ConsensusNetworkImpl::new(
             author,
             network_sender,
             network_events,
             Arc::clone(&epoch_mgr),
-        );
+        )
+        .with_recorder(Arc::clone(&recorder));
 
-        let config = ChainedBftSMRConfig {
-            max_pruned_blocks_in_mem: 10000,
-            pacemaker_initial_timeout: Duration::from_secs(3),
-            proposer_type,
-            contiguous_rounds: 2,
-            max_block_size: 50,
-        };
         let mut smr = ChainedBftSMR::new(
             author,
-            signer.clone(),
+            Some(signer.clone()),
             proposer.clone(),
             network,
             runtime,
-            config,
+            Self::config(proposer_type, decoupled_execution),
             storage.clone(),
             initial_data,
             Arc::clone(&epoch_mgr),
@@ -103,41 +120,84 @@ impl SMRNode {
         let mut mp = MockTransactionManager::new();
         let commit_receiver = mp.take_commit_receiver();
         let mempool = Arc::new(mp);
-        smr.start(
-            mempool.clone(),
-            Arc::new(MockStateComputer::new(commit_cb_sender.clone())),
-        )
-        .expect("Failed to start SMR!");
+        let state_computer = Arc::new(
+            MockStateComputer::new(commit_cb_sender.clone())
+                .with_storage(Arc::clone(&storage.shared_storage)),
+        );
+        smr.start(mempool.clone(), state_computer.clone())
+            .expect("Failed to start SMR!");
         Self {
             author,
+            twin_id,
             signer,
             epoch_mgr,
+            initial_validators,
             proposer,
             proposer_type,
+            decoupled_execution,
             smr_id,
             smr,
             commit_cb_receiver,
+            state_computer,
             mempool,
             mempool_notif_receiver: commit_receiver,
             storage,
+            recorder,
         }
     }
 
+    fn config(
+        proposer_type: ConsensusProposerType,
+        decoupled_execution: bool,
+    ) -> ChainedBftSMRConfig {
+        ChainedBftSMRConfig {
+            max_pruned_blocks_in_mem: 10000,
+            pacemaker_initial_timeout: Duration::from_secs(3),
+            proposer_type,
+            contiguous_rounds: 2,
+            max_block_size: 50,
+            safety_rules: SafetyRulesConfig::default(),
+            decoupled_execution,
+            proposal_backpressure: ProposalBackpressureConfig::default(),
+            leader_reputation: LeaderReputationConfig::default(),
+        }
+    }
+
+    /// Replays the given run of the node.
+    fn replay(&self, recording: Vec<RecordedEntry>) {
+        ReplayNode::<TestPayload>::new(
+            self.signer.clone(),
+            self.initial_validators.as_ref().clone(),
+            Self::config(self.proposer_type, self.decoupled_execution),
+            recording,
+        )
+        .unwrap()
+        .replay()
+        .unwrap();
+    }
+
     fn restart(mut self, playground: &mut NetworkPlayground) -> Self {
         self.smr.stop();
         let recover_data = self
             .storage
             .get_recovery_data()
             .unwrap_or_else(|e| panic!("fail to restart due to: {}", e));
+        // The epoch is recovered from the ledger, starting over from the first one.
+        let epoch_mgr = Arc::new(EpochManager::new(
+            0,
+            self.initial_validators.as_ref().clone(),
+        ));
         Self::start(
             playground,
             self.signer,
-            self.epoch_mgr,
+            self.twin_id,
+            epoch_mgr,
             self.proposer,
             self.smr_id + 10,
             self.storage,
             recover_data,
             self.proposer_type,
+            self.decoupled_execution,
         )
     }
 
@@ -147,11 +207,41 @@ impl SMRNode {
         playground: &mut NetworkPlayground,
         proposer_type: ConsensusProposerType,
     ) -> Vec<Self> {
+        Self::start_num_nodes_with_twins(
+            num_nodes,
+            0,
+            quorum_size,
+            playground,
+            proposer_type,
+            false,
+        )
+    }
+
+    /// Starts a node for each of the `num_nodes` validators, followed by a twin (with the same
+    /// keys but its own storage) for each of the first `num_twins` validators.
+    fn start_num_nodes_with_twins(
+        num_nodes: usize,
+        num_twins: usize,
+        quorum_size: usize,
+        playground: &mut NetworkPlayground,
+        proposer_type: ConsensusProposerType,
+        decoupled_execution: bool,
+    ) -> Vec<Self> {
+        assert!(num_twins <= num_nodes);
         let mut signers = vec![];
         let mut author_to_public_keys = HashMap::new();
+        let mut author_to_vrf_public_keys = HashMap::new();
         for smr_id in 0..num_nodes {
             // 0 -> [0000], 1 -> [1000] in the logs
-            let random_validator_signer = ValidatorSigner::from_int(smr_id as u8);
+            let mut random_validator_signer = ValidatorSigner::from_int(smr_id as u8);
+            // Only the VRF proposer needs the validators to have a VRF key.
+            if proposer_type == VrfProposer {
+                random_validator_signer = with_vrf_key(random_validator_signer, [smr_id as u8; 32]);
+                author_to_vrf_public_keys.insert(
+                    random_validator_signer.author(),
+                    random_validator_signer.vrf_public_key().unwrap(),
+                );
+            }
             author_to_public_keys.insert(
                 random_validator_signer.author(),
                 random_validator_signer.public_key(),
@@ -160,27 +250,49 @@ impl SMRNode {
         }
         let validator_verifier =
             ValidatorVerifier::new_with_quorum_size(author_to_public_keys, quorum_size)
-                .expect("Invalid quorum_size.");
-        let epoch_mgr = Arc::new(EpochManager::new(0, validator_verifier));
-        let peers = epoch_mgr.validators().get_ordered_account_addresses();
+                .expect("Invalid quorum_size.")
+                .with_vrf_public_keys(author_to_vrf_public_keys);
+        let peers = validator_verifier.get_ordered_account_addresses();
         let proposer = {
             match proposer_type {
                 FixedProposer => vec![peers[0]],
-                RotatingProposer | MultipleOrderedProposers => peers,
+                _ => peers,
             }
         };
+        let twins: Vec<_> = signers
+            .iter()
+            .take(num_twins)
+            .map(|signer| {
+                (
+                    signer.clone(),
+                    TwinId {
+                        id: 1,
+                        author: signer.author(),
+                    },
+                )
+            })
+            .collect();
+        let nodes_and_twins = signers
+            .into_iter()
+            .map(|signer| {
+                let twin_id = TwinId::from(signer.author());
+                (signer, twin_id)
+            })
+            .chain(twins);
         let mut nodes = vec![];
-        for smr_id in 0..num_nodes {
+        for (smr_id, (signer, twin_id)) in nodes_and_twins.enumerate() {
             let (storage, initial_data) = MockStorage::start_for_testing();
             nodes.push(Self::start(
                 playground,
-                signers.remove(0),
-                Arc::clone(&epoch_mgr),
+                signer,
+                twin_id,
+                Arc::new(EpochManager::new(0, validator_verifier.clone())),
                 proposer.clone(),
                 smr_id,
                 storage,
                 initial_data,
                 proposer_type,
+                decoupled_execution,
             ));
         }
         nodes
@@ -199,6 +311,70 @@ fn verify_finality_proof(node: &SMRNode, ledger_info_with_sig: &LedgerInfoWithSi
     }
 }
 
+/// Returns the committed blocks of a node, from its root back to the genesis or to the first
+/// block that isn't in memory anymore.
+fn committed_chain(node: &SMRNode) -> Vec<Arc<Block<TestPayload>>> {
+    let block_store = node.smr.block_store().expect("No valid block store!");
+    let mut chain = vec![block_store.root()];
+    while let Some(parent) = chain
+        .last()
+        .filter(|block| block.round() > 0)
+        .and_then(|block| block_store.get_block(block.parent_id()))
+    {
+        chain.push(parent);
+    }
+    chain
+}
+
+/// Checks the safety invariants across the honest nodes as they make progress: there are no two
+/// quorum certs for different blocks in the same round, including the ones pruned since they were
+/// recorded, and the committed chains don't conflict.
+#[derive(Default)]
+struct SafetyChecker {
+    certified_blocks: HashMap<Round, HashValue>,
+}
+
+impl SafetyChecker {
+    fn check(&mut self, honest_nodes: &[SMRNode]) {
+        for node in honest_nodes {
+            for qc in node.smr.block_store().unwrap().quorum_certs() {
+                let block_id = self
+                    .certified_blocks
+                    .entry(qc.certified_block_round())
+                    .or_insert_with(|| qc.certified_block_id());
+                assert_eq!(
+                    *block_id,
+                    qc.certified_block_id(),
+                    "Two quorum certs for different blocks in round {}",
+                    qc.certified_block_round()
+                );
+            }
+        }
+
+        let chains: Vec<_> = honest_nodes.iter().map(committed_chain).collect();
+        for chain in &chains {
+            for other_chain in &chains {
+                // The root of a chain must be in any chain that commits beyond it.
+                let root = &chain[0];
+                if other_chain[0].round() <= root.round() {
+                    continue;
+                }
+                if let Some(block) = other_chain
+                    .iter()
+                    .find(|block| block.round() <= root.round())
+                {
+                    assert_eq!(
+                        block.id(),
+                        root.id(),
+                        "Conflicting commits at round {}",
+                        root.round()
+                    );
+                }
+            }
+        }
+    }
+}
+
 #[test]
 /// Should receive a new proposal upon start
 fn basic_start_test() {
@@ -308,6 +484,12 @@ fn happy_path_with_multi_proposer() {
     basic_full_round(2, 2, MultipleOrderedProposers);
 }
 
+#[test]
+/// Basic happy path with the proposers drawn from the VRF outputs
+fn happy_path_with_vrf_proposer() {
+    basic_full_round(2, 2, VrfProposer);
+}
+
 /// Verify the basic e2e flow: blocks are committed, txn manager is notified, block tree is
 /// pruned, restart the node and we can still continue.
 #[test]
@@ -406,6 +588,207 @@ fn basic_commit_and_restart() {
     });
 }
 
+/// Drives the network until every node commits a block of the given epoch.
+async fn wait_for_commit_in_epoch(
+    playground: &mut NetworkPlayground,
+    nodes: &mut [SMRNode],
+    epoch: u64,
+) {
+    let mut committed = vec![false; nodes.len()];
+    while committed.iter().any(|committed| !committed) {
+        playground
+            .wait_for_messages(1, NetworkPlayground::exclude_timeout_msg)
+            .await;
+        for (node, committed) in nodes.iter_mut().zip(committed.iter_mut()) {
+            while let Ok(Some(commit)) = node.commit_cb_receiver.try_next() {
+                verify_finality_proof(node, &commit);
+                if commit.ledger_info().epoch_num() == epoch {
+                    *committed = true;
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn reconfiguration_and_restart() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer);
+
+    // The next block executed ends the epoch, the validators stay the same.
+    let next_validator_set = ValidatorSet::new(
+        nodes
+            .iter()
+            .map(|node| {
+                ValidatorPublicKeys::new(
+                    node.author,
+                    node.signer.public_key(),
+                    node.signer.public_key(),
+                    x25519::compat::generate_keypair(None).1,
+                    node.signer.vrf_public_key(),
+                )
+            })
+            .collect(),
+    );
+    for node in &nodes {
+        node.state_computer.reconfigure(next_validator_set.clone());
+    }
+    block_on(wait_for_commit_in_epoch(&mut playground, &mut nodes, 1));
+    for node in &nodes {
+        assert_eq!(node.epoch_mgr.epoch(), 1);
+    }
+
+    // The restarted nodes recover the epoch from the ledger and keep making progress in it.
+    playground = NetworkPlayground::new(runtime.executor());
+    nodes = nodes
+        .into_iter()
+        .map(|node| node.restart(&mut playground))
+        .collect();
+    for node in &nodes {
+        assert_eq!(node.epoch_mgr.epoch(), 1);
+    }
+    block_on(wait_for_commit_in_epoch(&mut playground, &mut nodes, 1));
+}
+
+/// A node which misses the end of an epoch catches up with it once it hears of the next epoch.
+#[test]
+fn lagging_node_catches_up_with_epoch() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(4, 3, &mut playground, RotatingProposer);
+
+    // Node 3 doesn't hear from the others while they move to the next epoch, with the same
+    // validators.
+    let next_validator_set = ValidatorSet::new(
+        nodes
+            .iter()
+            .map(|node| {
+                ValidatorPublicKeys::new(
+                    node.author,
+                    node.signer.public_key(),
+                    node.signer.public_key(),
+                    x25519::compat::generate_keypair(None).1,
+                    node.signer.vrf_public_key(),
+                )
+            })
+            .collect(),
+    );
+    for node in &nodes[..3] {
+        playground.drop_message_for(&node.author, nodes[3].author);
+        node.state_computer.reconfigure(next_validator_set.clone());
+    }
+    block_on(wait_for_commit_in_epoch(
+        &mut playground,
+        &mut nodes[..3],
+        1,
+    ));
+    assert_eq!(nodes[3].epoch_mgr.epoch(), 0);
+
+    // Once it does, it's proven the end of the epoch and carries on in the next one.
+    for node in &nodes[..3] {
+        playground.stop_drop_message_for(&node.author, &nodes[3].author);
+    }
+    block_on(wait_for_commit_in_epoch(
+        &mut playground,
+        &mut nodes[3..],
+        1,
+    ));
+    assert_eq!(nodes[3].epoch_mgr.epoch(), 1);
+}
+
+/// In the decoupled execution mode, the quorum certs only order the blocks: the blocks are
+/// committed by a quorum of commit votes, whose ledger info carries no vote data.
+#[test]
+fn decoupled_execution_commit() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes =
+        SMRNode::start_num_nodes_with_twins(2, 0, 2, &mut playground, RotatingProposer, true);
+
+    block_on(async {
+        let mut commits = vec![None, None];
+        for _ in 0..100 {
+            if commits.iter().all(Option::is_some) {
+                break;
+            }
+            playground
+                .wait_for_messages(1, NetworkPlayground::exclude_timeout_msg)
+                .await;
+            for (node, commit) in nodes.iter_mut().zip(commits.iter_mut()) {
+                if commit.is_none() {
+                    if let Ok(Some(ledger_info_with_sigs)) = node.commit_cb_receiver.try_next() {
+                        *commit = Some(ledger_info_with_sigs);
+                    }
+                }
+            }
+        }
+        let commits: Vec<LedgerInfoWithSignatures> = commits
+            .into_iter()
+            .map(|commit| commit.expect("No block committed"))
+            .collect();
+        // Both validators commit the blocks ordered by the first commit quorum cert.
+        assert_eq!(
+            commits[0].ledger_info().consensus_block_id(),
+            commits[1].ledger_info().consensus_block_id()
+        );
+        for (node, commit) in nodes.iter().zip(commits.iter()) {
+            assert_eq!(
+                commit.ledger_info().consensus_data_hash(),
+                HashValue::zero()
+            );
+            assert_eq!(commit.signatures().len(), 2);
+            verify_finality_proof(node, commit);
+        }
+    });
+}
+
+/// The decisions of a validator are reproduced by replaying the events it recorded, including
+/// after a restart.
+#[test]
+fn record_and_replay() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer);
+
+    let wait_for_votes = |playground: &mut NetworkPlayground| {
+        block_on(async {
+            let mut num_votes = 0;
+            while num_votes < 5 {
+                let msg = playground
+                    .wait_for_messages(1, NetworkPlayground::exclude_timeout_msg)
+                    .await;
+                if msg[0].1.has_vote() {
+                    num_votes += 1;
+                }
+            }
+        })
+    };
+    wait_for_votes(&mut playground);
+    nodes[0].smr.stop();
+    let first_run = nodes[0].recorder.entries();
+    assert!(first_run.iter().any(|entry| match entry.event {
+        RecordedEvent::StateCompute { .. } => true,
+        _ => false,
+    }));
+    nodes[0].replay(first_run);
+
+    // The restarted node starts from the blocks it recovers, which are recorded too.
+    playground = NetworkPlayground::new(runtime.executor());
+    nodes = nodes
+        .into_iter()
+        .map(|node| node.restart(&mut playground))
+        .collect();
+    wait_for_votes(&mut playground);
+    nodes[0].smr.stop();
+    let second_run = nodes[0].recorder.entries();
+    match &second_run[0].event {
+        RecordedEvent::Start(startup) => assert!(startup.blocks.len() > 1),
+        event => panic!("Unexpected first event {:?}", event),
+    }
+    nodes[0].replay(second_run);
+}
+
 #[test]
 fn basic_block_retrieval() {
     let runtime = consensus_runtime();
@@ -871,3 +1254,56 @@ fn secondary_proposers() {
         assert_eq!(secondary_proposal_committed, true);
     });
 }
+
+/// A validator runs two twins (nodes[0] and nodes[4]) that are in the given partitions of node
+/// indices for the first rounds, so that it equivocates: each twin proposes and votes in its own
+/// partition. The quorum certs are checked as they are formed, until all the honest nodes commit
+/// once the network is whole again: the honest validators must stay safe throughout.
+fn run_twins_equivocation(partitions: &[Vec<usize>]) {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let nodes =
+        SMRNode::start_num_nodes_with_twins(4, 1, 3, &mut playground, RotatingProposer, false);
+    let twin_ids: Vec<_> = nodes.iter().map(|node| node.twin_id).collect();
+    assert_eq!(twin_ids[0].author, twin_ids[4].author);
+    let partitions: Vec<Vec<_>> = partitions
+        .iter()
+        .map(|partition| partition.iter().map(|i| twin_ids[*i]).collect())
+        .collect();
+    for round in 1..=4 {
+        playground.split_network_round(round, &partitions);
+    }
+
+    let honest_nodes = &nodes[1..4];
+    let mut safety_checker = SafetyChecker::default();
+    block_on(async {
+        while honest_nodes
+            .iter()
+            .any(|node| committed_chain(node)[0].round() == 0)
+        {
+            playground
+                .wait_for_messages(1, NetworkPlayground::votes_only)
+                .await;
+            safety_checker.check(honest_nodes);
+        }
+    });
+}
+
+/// Only the partition with the first twin can form quorum certs in the first rounds.
+#[test]
+fn twins_equivocation_safety() {
+    run_twins_equivocation(&[vec![0, 1, 2], vec![4, 3]]);
+}
+
+/// Each twin is in a partition of three validators, which could reach quorum if the honest
+/// validator in both voted twice in a round.
+#[test]
+fn twins_equivocation_safety_overlapping_partitions() {
+    run_twins_equivocation(&[vec![0, 1, 2], vec![4, 2, 3]]);
+}
+
+/// Same as above, with another honest validator in both partitions.
+#[test]
+fn twins_equivocation_safety_overlapping_partitions_two_shared() {
+    run_twins_equivocation(&[vec![0, 1, 2], vec![4, 1, 2]]);
+}
@@ -0,0 +1,103 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::common::Author;
+use crypto::HashValue;
+use failure::prelude::*;
+use network::proto::AggregatedSignature as ProtoAggregatedSignature;
+use proto_conv::{FromProto, IntoProto};
+use protobuf::RepeatedField;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, convert::TryFrom};
+use types::{
+    crypto_proxies::{bls12381, ValidatorVerifier},
+    validator_verifier::VerifyError,
+};
+
+/// The aggregate of the BLS12-381 signatures of a message by several authors. The certificates
+/// carry it instead of the signature of each author when the validators aggregate their
+/// signatures (see `ValidatorVerifier::with_aggregation_verifier`), so that their size and
+/// verification cost don't grow with the number of validators.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct AggregatedSignature {
+    /// The authors of the aggregated signatures.
+    authors: Vec<Author>,
+    /// The aggregate of the signatures.
+    signature: bls12381::Signature,
+}
+
+impl AggregatedSignature {
+    /// Aggregates the signatures of the same message by their authors.
+    pub fn aggregate(signatures: &BTreeMap<Author, bls12381::Signature>) -> Result<Self> {
+        let signature = bls12381::Signature::aggregate(&signatures.values().collect::<Vec<_>>())?;
+        Ok(Self {
+            authors: signatures.keys().cloned().collect(),
+            signature,
+        })
+    }
+
+    /// Return the authors of the aggregated signatures
+    pub fn authors(&self) -> &[Author] {
+        &self.authors
+    }
+
+    /// Verifies aggregated signatures of different messages, each of them by its own authors:
+    /// the authors together must be a quorum of the validators. Fails unless the validators
+    /// aggregate their signatures.
+    pub fn verify_all(
+        validator: &ValidatorVerifier,
+        aggregated_signatures: &[(HashValue, &AggregatedSignature)],
+    ) -> std::result::Result<(), VerifyError> {
+        let aggregation_verifier = validator
+            .aggregation_verifier()
+            .ok_or(VerifyError::UnprovenPossession)?;
+        let aggregated_signatures: Vec<_> = aggregated_signatures
+            .iter()
+            .map(|(message, aggregated_signature)| {
+                (
+                    *message,
+                    aggregated_signature.authors(),
+                    &aggregated_signature.signature,
+                )
+            })
+            .collect();
+        bls12381::Signature::verify_aggregates(aggregation_verifier, &aggregated_signatures)
+    }
+
+    /// Verifies that this is the aggregate of the signatures of the message by a quorum of the
+    /// validators.
+    pub fn verify(
+        &self,
+        validator: &ValidatorVerifier,
+        message: HashValue,
+    ) -> std::result::Result<(), VerifyError> {
+        Self::verify_all(validator, &[(message, self)])
+    }
+}
+
+impl IntoProto for AggregatedSignature {
+    type ProtoType = ProtoAggregatedSignature;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto = Self::ProtoType::new();
+        proto.set_authors(RepeatedField::from_vec(
+            self.authors.into_iter().map(Into::into).collect(),
+        ));
+        proto.set_signature(bytes::Bytes::from(self.signature.to_bytes()));
+        proto
+    }
+}
+
+impl FromProto for AggregatedSignature {
+    type ProtoType = ProtoAggregatedSignature;
+
+    fn from_proto(mut object: Self::ProtoType) -> Result<Self> {
+        let authors = object
+            .take_authors()
+            .into_iter()
+            .map(Author::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let signature = bls12381::Signature::try_from(object.get_signature())?;
+        Ok(Self { authors, signature })
+    }
+}
@@ -12,6 +12,7 @@ use canonical_serialization::{
 };
 use crypto::{
     hash::{BlockHasher, CryptoHash, CryptoHasher, GENESIS_BLOCK_ID},
+    vrf::ecvrf::{Output, Proof},
     HashValue,
 };
 use executor::{ExecutedState, StateComputeResult};
@@ -31,6 +32,7 @@ use std::{
 use types::{
     crypto_proxies::{LedgerInfoWithSignatures, Signature, ValidatorSigner, ValidatorVerifier},
     ledger_info::LedgerInfo,
+    transaction::Version,
 };
 
 #[cfg(test)]
@@ -51,6 +53,8 @@ pub enum BlockVerificationError {
     QCVerificationError(VoteMsgVerificationError),
     /// The signature verification of this block failed.
     SigVerifyError,
+    /// The VRF proof of this block is missing or doesn't verify.
+    InvalidVrfProof,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
@@ -104,6 +108,23 @@ pub struct Block<T> {
     quorum_cert: QuorumCert,
     /// If a block is a real proposal, contains its author and signature.
     block_source: BlockSource,
+    /// The ECVRF proof of the author of a real proposal over the id of the certified parent and
+    /// the round, whose output is a source of randomness that the author can't bias. Only the
+    /// proposals of the validators with a VRF key carry one, i.e. when the VRF proposer is used.
+    vrf_proof: Option<Vec<u8>>,
+}
+
+/// The content of a real proposal before its author signs it, which turns it into a block (see
+/// `BlockData::sign`). This lets the proposals be signed wherever the signing key is, e.g. by the
+/// safety rules running in their own process.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockData<T> {
+    parent_id: HashValue,
+    payload: T,
+    round: Round,
+    height: Height,
+    timestamp_usecs: u64,
+    quorum_cert: QuorumCert,
 }
 
 /// ExecutedBlocks are managed in a speculative tree, the committed blocks form a chain. Besides
@@ -157,27 +178,56 @@ where
 {
     // Make an empty genesis block
     pub fn make_genesis_block() -> Self {
+        let state_id = ExecutedState::state_for_genesis().state_id;
+        Self::make_genesis_block_internal(
+            *GENESIS_BLOCK_ID,
+            0,
+            state_id,
+            0,
+            0, // The beginning of UNIX TIME
+        )
+    }
+
+    /// Make the genesis block of the epoch following the one ended by the given ledger info.
+    /// It takes over the id of the last block committed in the previous epoch, which the state
+    /// computer knows as its latest committed block, so the blocks of the new epoch can be executed
+    /// on top of it.
+    pub fn make_genesis_block_from_ledger_info(ledger_info: &LedgerInfo) -> Self {
+        Self::make_genesis_block_internal(
+            ledger_info.consensus_block_id(),
+            ledger_info.version(),
+            ledger_info.transaction_accumulator_hash(),
+            ledger_info.epoch_num() + 1,
+            ledger_info.timestamp_usecs(),
+        )
+    }
+
+    fn make_genesis_block_internal(
+        genesis_id: HashValue,
+        version: Version,
+        state_id: HashValue,
+        epoch_num: u64,
+        timestamp_usecs: u64,
+    ) -> Self {
         let ancestor_id = HashValue::zero();
         let genesis_validator_signer = ValidatorSigner::genesis();
-        let state_id = ExecutedState::state_for_genesis().state_id;
         // Genesis carries a placeholder quorum certificate to its parent id with LedgerInfo
-        // carrying information about version `0`.
+        // carrying information about the version it starts from.
         let genesis_quorum_cert = QuorumCert::new(
             VoteData::new(ancestor_id, state_id, 0, ancestor_id, 0, ancestor_id, 0),
             LedgerInfoWithSignatures::new(
                 LedgerInfo::new(
-                    0,
+                    version,
                     state_id,
                     HashValue::zero(),
                     HashValue::zero(),
-                    0,
-                    0,
+                    epoch_num,
+                    timestamp_usecs,
                     None,
                 ),
                 HashMap::new(),
             ),
         );
-        let genesis_id = *GENESIS_BLOCK_ID;
         let signature = genesis_validator_signer
             .sign_message(genesis_id)
             .expect("Failed to sign genesis id.");
@@ -188,12 +238,13 @@ where
             parent_id: HashValue::zero(),
             round: 0,
             height: 0,
-            timestamp_usecs: 0, // The beginning of UNIX TIME
+            timestamp_usecs,
             quorum_cert: genesis_quorum_cert,
             block_source: BlockSource::Proposal {
                 author: genesis_validator_signer.author(),
                 signature: signature.into(),
             },
+            vrf_proof: None,
         }
     }
 
@@ -208,6 +259,9 @@ where
         quorum_cert: QuorumCert,
         validator_signer: &ValidatorSigner,
     ) -> Self {
+        let vrf_proof = validator_signer
+            .vrf_prove(&vrf_input(quorum_cert.certified_block_id(), round))
+            .map(|proof| proof.to_bytes().to_vec());
         let block_internal = BlockSerializer {
             parent_id,
             payload: &payload,
@@ -216,6 +270,7 @@ where
             timestamp_usecs,
             quorum_cert: &quorum_cert,
             author: Some(validator_signer.author()),
+            vrf_proof: &vrf_proof,
         };
 
         let id = block_internal.hash();
@@ -235,6 +290,7 @@ where
                 author: validator_signer.author(),
                 signature: signature.into(),
             },
+            vrf_proof,
         }
     }
 
@@ -246,23 +302,8 @@ where
         quorum_cert: QuorumCert,
         validator_signer: &ValidatorSigner,
     ) -> Self {
-        // A block must carry a QC to its parent.
-        checked_precondition_eq!(quorum_cert.certified_block_id(), parent_block.id());
-        checked_precondition!(round > parent_block.round());
-
-        // This precondition guards the addition overflow caused by passing
-        // parent_block.height() + 1 to new_internal.
-        checked_precondition!(parent_block.height() < std::u64::MAX);
-        Block::new_internal(
-            payload,
-            parent_block.id(),
-            round,
-            // Height is always parent's height + 1 because it's just the position in the chain.
-            parent_block.height() + 1,
-            timestamp_usecs,
-            quorum_cert,
-            validator_signer,
-        )
+        BlockData::new(parent_block, payload, round, timestamp_usecs, quorum_cert)
+            .sign(validator_signer)
     }
 
     /// The NIL blocks are special: they're not carrying any real payload and are generated
@@ -292,6 +333,7 @@ where
             // the author here doesn't really matter for as long as all the NIL Blocks are hashing
             // the same value, hence use the special genesis author for hashing.
             author: None,
+            vrf_proof: &None,
         };
 
         let id = block_serializer.hash();
@@ -305,6 +347,7 @@ where
             timestamp_usecs,
             quorum_cert,
             block_source: BlockSource::NilBlock,
+            vrf_proof: None,
         }
     }
 
@@ -313,13 +356,14 @@ where
     }
 
     /// Verifies that the proposal and the QC are correctly signed.
-    /// If this is the genesis block, we skip these checks.
+    /// If this is the genesis block of the current epoch, we skip these checks.
     pub fn validate_signatures(
         &self,
         validator: &ValidatorVerifier,
+        epoch_ending_ledger_info: Option<&LedgerInfo>,
     ) -> ::std::result::Result<(), BlockVerificationError> {
         // if genesis block, we don't verify anything
-        if self.is_genesis_block() {
+        if self.is_epoch_genesis_block(epoch_ending_ledger_info) {
             return Ok(());
         }
         // verify signature from leader if it's a real proposal
@@ -330,10 +374,40 @@ where
         }
         // verify signatures of quorum cert
         self.quorum_cert
-            .verify(validator)
+            .verify(validator, epoch_ending_ledger_info)
             .map_err(BlockVerificationError::QCVerificationError)
     }
 
+    /// Verifies the VRF proof of a real proposal with the VRF public key of its author, which is
+    /// only required by the VRF proposer election.
+    /// If this is the genesis block, we skip this check.
+    pub fn verify_vrf_proof(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> ::std::result::Result<(), BlockVerificationError> {
+        if self.is_genesis_block() {
+            return Ok(());
+        }
+        let author = match self.author() {
+            Some(author) => author,
+            None => return Ok(()),
+        };
+        let public_key = validator
+            .get_vrf_public_key(author)
+            .ok_or(BlockVerificationError::InvalidVrfProof)?;
+        let proof = self
+            .vrf_proof
+            .as_ref()
+            .and_then(|proof| Proof::try_from(&proof[..]).ok())
+            .ok_or(BlockVerificationError::InvalidVrfProof)?;
+        public_key
+            .verify(
+                &proof,
+                &vrf_input(self.quorum_cert.certified_block_id(), self.round),
+            )
+            .map_err(|_| BlockVerificationError::InvalidVrfProof)
+    }
+
     /// Makes sure that the proposal makes sense, independently of the current state.
     /// If this is the genesis block, we skip these checks.
     pub fn verify_well_formed(&self) -> ::std::result::Result<(), BlockVerificationError> {
@@ -419,9 +493,109 @@ where
             && self.timestamp_usecs == 0
     }
 
+    /// Whether this is the genesis block of the current epoch: the genesis block of the first
+    /// epoch if no ledger info ended an epoch yet, or the one derived from the ledger info ending
+    /// the previous epoch.
+    pub fn is_epoch_genesis_block(&self, epoch_ending_ledger_info: Option<&LedgerInfo>) -> bool {
+        match epoch_ending_ledger_info {
+            Some(ledger_info) => *self == Self::make_genesis_block_from_ledger_info(ledger_info),
+            None => self.is_genesis_block(),
+        }
+    }
+
     pub fn is_nil_block(&self) -> bool {
         self.block_source == BlockSource::NilBlock
     }
+
+    /// The output of the VRF proof of the block, if it carries a well-formed one. It can only be
+    /// trusted once the proof is verified (see `verify_vrf_proof`).
+    pub fn vrf_output(&self) -> Option<Output> {
+        let proof = Proof::try_from(&self.vrf_proof.as_ref()?[..]).ok()?;
+        Some(Output::from(&proof))
+    }
+}
+
+impl<T> BlockData<T>
+where
+    T: Serialize + Default + CanonicalSerialize + PartialEq,
+{
+    pub fn new(
+        parent_block: &Block<T>,
+        payload: T,
+        round: Round,
+        timestamp_usecs: u64,
+        quorum_cert: QuorumCert,
+    ) -> Self {
+        // A block must carry a QC to its parent.
+        checked_precondition_eq!(quorum_cert.certified_block_id(), parent_block.id());
+        checked_precondition!(round > parent_block.round());
+
+        // This precondition guards the addition overflow caused by using
+        // parent_block.height() + 1 as the height.
+        checked_precondition!(parent_block.height() < std::u64::MAX);
+        BlockData {
+            parent_id: parent_block.id(),
+            payload,
+            round,
+            // Height is always parent's height + 1 because it's just the position in the chain.
+            height: parent_block.height() + 1,
+            timestamp_usecs,
+            quorum_cert,
+        }
+    }
+
+    /// Makes the block authored by the given signer, along with its VRF proof if the signer has a
+    /// VRF key.
+    pub fn sign(self, validator_signer: &ValidatorSigner) -> Block<T> {
+        Block::new_internal(
+            self.payload,
+            self.parent_id,
+            self.round,
+            self.height,
+            self.timestamp_usecs,
+            self.quorum_cert,
+            validator_signer,
+        )
+    }
+
+    pub fn parent_id(&self) -> HashValue {
+        self.parent_id
+    }
+
+    pub fn payload(&self) -> &T {
+        &self.payload
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    pub fn height(&self) -> Height {
+        self.height
+    }
+
+    pub fn timestamp_usecs(&self) -> u64 {
+        self.timestamp_usecs
+    }
+
+    pub fn quorum_cert(&self) -> &QuorumCert {
+        &self.quorum_cert
+    }
+}
+
+impl<T> Display for BlockData<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "[round: {:02}, parent_id: {}]",
+            self.round, self.parent_id
+        )
+    }
+}
+
+/// The input of the VRF proof of a block: the parent id certified by its QC and its round.
+fn vrf_input(certified_block_id: HashValue, round: Round) -> Vec<u8> {
+    [certified_block_id.as_ref(), &round.to_le_bytes()[..]].concat()
 }
 
 impl<T> ExecutedBlock<T> {
@@ -474,6 +648,7 @@ where
             timestamp_usecs: self.timestamp_usecs,
             quorum_cert: &self.quorum_cert,
             author,
+            vrf_proof: &self.vrf_proof,
         };
         block_internal.hash()
     }
@@ -489,6 +664,7 @@ struct BlockSerializer<'a, T> {
     timestamp_usecs: u64,
     quorum_cert: &'a QuorumCert,
     author: Option<Author>,
+    vrf_proof: &'a Option<Vec<u8>>,
 }
 
 impl<'a, T> CryptoHash for BlockSerializer<'a, T>
@@ -518,7 +694,8 @@ where
             .encode_struct(self.payload)?
             .encode_bytes(self.parent_id.as_ref())?
             .encode_bytes(self.quorum_cert.certified_block_id().as_ref())?
-            .encode_optional(&self.author)?;
+            .encode_optional(&self.author)?
+            .encode_optional(self.vrf_proof)?;
         Ok(())
     }
 }
@@ -558,6 +735,9 @@ where
             proto.set_signature(bytes);
             proto.set_author(author.into());
         }
+        if let Some(vrf_proof) = self.vrf_proof {
+            proto.set_vrf_proof(vrf_proof.into());
+        }
         proto
     }
 }
@@ -584,6 +764,11 @@ where
                 signature: Signature::try_from(object.get_signature())?,
             }
         };
+        let vrf_proof = if object.get_vrf_proof().is_empty() {
+            None
+        } else {
+            Some(object.take_vrf_proof().to_vec())
+        };
         Ok(Block {
             id,
             parent_id,
@@ -593,6 +778,7 @@ where
             height,
             quorum_cert,
             block_source,
+            vrf_proof,
         })
     }
 }
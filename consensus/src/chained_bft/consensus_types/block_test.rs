@@ -7,7 +7,7 @@ use crate::chained_bft::{
         block::{Block, BlockSource},
         quorum_cert::QuorumCert,
     },
-    test_utils::placeholder_certificate_for_block,
+    test_utils::{placeholder_certificate_for_block, with_vrf_key},
 };
 
 use crypto::HashValue;
@@ -19,9 +19,13 @@ use std::{
 };
 #[cfg(test)]
 use types::crypto_proxies::SecretKey;
-use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
 #[cfg(test)]
 use types::validator_signer::proptests;
+use types::{
+    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+    validator_set::ValidatorSet,
+};
 
 type LinearizedBlockForest<T> = Vec<Block<T>>;
 
@@ -103,6 +107,7 @@ prop_compose! {
                     author: block.author().unwrap(),
                     signature: block.signature().unwrap().clone(),
                 },
+                vrf_proof: block.vrf_proof.clone(),
             }
         }
 }
@@ -202,6 +207,82 @@ fn test_genesis() {
     assert!(genesis_block.is_genesis_block());
 }
 
+#[test]
+fn test_genesis_from_ledger_info() {
+    // The ledger info ending epoch 3 at the block to take over.
+    let last_block_id = HashValue::random();
+    let ledger_info = LedgerInfo::new(
+        10,
+        HashValue::random(),
+        HashValue::zero(),
+        last_block_id,
+        3,
+        1000,
+        Some(ValidatorSet::new(vec![])),
+    );
+    let genesis_block = Block::<i64>::make_genesis_block_from_ledger_info(&ledger_info);
+    assert_eq!(genesis_block.id(), last_block_id);
+    assert_eq!(genesis_block.round(), 0);
+    assert_eq!(genesis_block.timestamp_usecs(), 1000);
+    // Only the genesis of the first epoch has the predetermined identity.
+    assert!(!genesis_block.is_genesis_block());
+    assert!(genesis_block.is_epoch_genesis_block(Some(&ledger_info)));
+    assert!(!genesis_block.is_epoch_genesis_block(None));
+
+    let quorum_cert = QuorumCert::certificate_for_genesis_from_ledger_info(&ledger_info);
+    assert_eq!(quorum_cert.certified_block_id(), genesis_block.id());
+    assert_eq!(quorum_cert.committed_block_id(), Some(genesis_block.id()));
+    assert_eq!(
+        quorum_cert.certified_state_id(),
+        ledger_info.transaction_accumulator_hash()
+    );
+    let genesis_ledger_info = quorum_cert.ledger_info().ledger_info();
+    assert_eq!(genesis_ledger_info.version(), 10);
+    assert_eq!(genesis_ledger_info.epoch_num(), 4);
+    assert!(genesis_ledger_info.next_validator_set().is_none());
+
+    // The validators of the new epoch accept the genesis derived from the ledger info ending the
+    // previous epoch without signing it.
+    let signer = ValidatorSigner::random(None);
+    let mut author_to_public_keys = HashMap::new();
+    author_to_public_keys.insert(signer.author(), signer.public_key());
+    let validator_verifier = ValidatorVerifier::new(author_to_public_keys);
+    assert!(quorum_cert
+        .verify(&validator_verifier, Some(&ledger_info))
+        .is_ok());
+    assert!(genesis_block
+        .validate_signatures(&validator_verifier, Some(&ledger_info))
+        .is_ok());
+    // But not any other certificate of round 0 without a quorum of signatures.
+    assert!(quorum_cert.verify(&validator_verifier, None).is_err());
+    let other_ledger_info = LedgerInfo::new(
+        10,
+        HashValue::random(),
+        HashValue::zero(),
+        HashValue::random(),
+        3,
+        1000,
+        Some(ValidatorSet::new(vec![])),
+    );
+    assert!(quorum_cert
+        .verify(&validator_verifier, Some(&other_ledger_info))
+        .is_err());
+    assert!(
+        QuorumCert::certificate_for_genesis_from_ledger_info(&other_ledger_info)
+            .verify(&validator_verifier, Some(&ledger_info))
+            .is_err()
+    );
+    assert!(QuorumCert::certificate_for_genesis()
+        .verify(&validator_verifier, Some(&ledger_info))
+        .is_err());
+
+    let next_block = Block::make_block(&genesis_block, 101, 1, 1001, quorum_cert, &signer);
+    assert!(next_block.verify_well_formed().is_ok());
+    assert!(next_block
+        .validate_signatures(&validator_verifier, Some(&ledger_info))
+        .is_ok());
+}
+
 #[test]
 fn test_nil_block() {
     let genesis_block = Block::make_genesis_block();
@@ -222,7 +303,7 @@ fn test_nil_block() {
 
     let dummy_verifier = Arc::new(ValidatorVerifier::new(HashMap::new()));
     assert!(nil_block
-        .validate_signatures(dummy_verifier.as_ref())
+        .validate_signatures(dummy_verifier.as_ref(), None)
         .is_ok());
     assert!(nil_block.verify_well_formed().is_ok());
 
@@ -330,6 +411,100 @@ fn test_block_qc() {
     assert_eq!(a2.height(), 2);
 }
 
+#[test]
+fn test_vrf_proof() {
+    let signer = with_vrf_key(ValidatorSigner::random([0u8; 32]), [2u8; 32]);
+    let another_signer = with_vrf_key(ValidatorSigner::random([1u8; 32]), [3u8; 32]);
+    let mut author_to_public_keys = HashMap::new();
+    let mut author_to_vrf_public_keys = HashMap::new();
+    for signer in &[&signer, &another_signer] {
+        author_to_public_keys.insert(signer.author(), signer.public_key());
+        author_to_vrf_public_keys.insert(signer.author(), signer.vrf_public_key().unwrap());
+    }
+    let validator_verifier = ValidatorVerifier::new(author_to_public_keys.clone())
+        .with_vrf_public_keys(author_to_vrf_public_keys);
+    let genesis_block = Block::make_genesis_block();
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    let timestamp_usecs = get_current_timestamp().as_micros() as u64;
+
+    let block = Block::make_block(
+        &genesis_block,
+        42,
+        1,
+        timestamp_usecs,
+        genesis_qc.clone(),
+        &signer,
+    );
+    assert!(block.verify_vrf_proof(&validator_verifier).is_ok());
+
+    // The output only depends on the author, the certified parent and the round.
+    let output = block.vrf_output().unwrap().to_bytes();
+    let other_payload_block = Block::make_block(
+        &genesis_block,
+        43,
+        1,
+        timestamp_usecs + 1,
+        genesis_qc.clone(),
+        &signer,
+    );
+    assert_eq!(
+        other_payload_block.vrf_output().unwrap().to_bytes()[..],
+        output[..]
+    );
+    let other_round_block = Block::make_block(
+        &genesis_block,
+        42,
+        2,
+        timestamp_usecs,
+        genesis_qc.clone(),
+        &signer,
+    );
+    assert_ne!(
+        other_round_block.vrf_output().unwrap().to_bytes()[..],
+        output[..]
+    );
+
+    // The proof can't be reused by another author.
+    let mut forged_block = Block::make_block(
+        &genesis_block,
+        42,
+        1,
+        timestamp_usecs,
+        genesis_qc.clone(),
+        &another_signer,
+    );
+    assert!(forged_block.verify_vrf_proof(&validator_verifier).is_ok());
+    forged_block.vrf_proof = block.vrf_proof.clone();
+    assert!(forged_block.verify_vrf_proof(&validator_verifier).is_err());
+    forged_block.vrf_proof = None;
+    assert!(forged_block.verify_vrf_proof(&validator_verifier).is_err());
+    assert!(forged_block.vrf_output().is_none());
+
+    // The proof doesn't verify without the VRF key of the author.
+    let verifier_without_vrf_keys = ValidatorVerifier::new(author_to_public_keys);
+    assert!(block.verify_vrf_proof(&verifier_without_vrf_keys).is_err());
+
+    // The signers without a VRF key don't prove anything.
+    let signer_without_vrf_key = ValidatorSigner::random([0u8; 32]);
+    let block_without_proof = Block::make_block(
+        &genesis_block,
+        42,
+        1,
+        timestamp_usecs,
+        genesis_qc.clone(),
+        &signer_without_vrf_key,
+    );
+    assert!(block_without_proof.vrf_output().is_none());
+    assert!(block_without_proof
+        .verify_vrf_proof(&validator_verifier)
+        .is_err());
+
+    // NIL blocks don't have any.
+    let nil_block = Block::make_nil_block(&genesis_block, 1, genesis_qc);
+    assert!(nil_block.verify_vrf_proof(&validator_verifier).is_ok());
+    assert!(nil_block.vrf_output().is_none());
+}
+
 // Using current_timestamp in this test
 // because it's a bit hard to generate incremental timestamps in proptests
 fn get_current_timestamp() -> Duration {
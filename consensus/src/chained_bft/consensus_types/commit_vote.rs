@@ -0,0 +1,103 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::common::Author;
+use crypto::hash::CryptoHash;
+use failure::Result as ProtoResult;
+use network::proto::CommitVote as ProtoCommitVote;
+use proto_conv::{FromProto, IntoProto};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter},
+};
+use types::{
+    crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+    validator_verifier::VerifyError,
+};
+
+/// In the decoupled execution mode, the quorum certs only order the blocks: once a block is
+/// ordered and executed, each validator signs the ledger info committing it along with the state
+/// that executing it led to. A quorum of commit votes for the same ledger info commits the block.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct CommitVote {
+    /// The identity of the voter.
+    author: Author,
+    /// LedgerInfo of the executed block that is committed if this vote gathers a quorum.
+    ledger_info: LedgerInfo,
+    /// Signature of the LedgerInfo
+    signature: Signature,
+}
+
+impl Display for CommitVote {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "CommitVote: [author: {}, {}]",
+            self.author.short_str(),
+            self.ledger_info
+        )
+    }
+}
+
+impl CommitVote {
+    pub fn new(ledger_info: LedgerInfo, validator_signer: &ValidatorSigner) -> Self {
+        let signature = validator_signer
+            .sign_message(ledger_info.hash())
+            .expect("Failed to sign LedgerInfo");
+        Self {
+            author: validator_signer.author(),
+            ledger_info,
+            signature: signature.into(),
+        }
+    }
+
+    /// Return the author of the commit vote
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    /// Return the LedgerInfo signed by the commit vote
+    pub fn ledger_info(&self) -> &LedgerInfo {
+        &self.ledger_info
+    }
+
+    /// Return the signature of the commit vote
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Verifies that the ledger info is signed by the author.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> Result<(), VerifyError> {
+        self.signature
+            .verify(validator, self.author, self.ledger_info.hash())
+    }
+}
+
+impl IntoProto for CommitVote {
+    type ProtoType = ProtoCommitVote;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto = Self::ProtoType::new();
+        proto.set_author(self.author.into());
+        proto.set_ledger_info(self.ledger_info.into_proto());
+        proto.set_signature(bytes::Bytes::from(self.signature.to_bytes()));
+        proto
+    }
+}
+
+impl FromProto for CommitVote {
+    type ProtoType = ProtoCommitVote;
+
+    fn from_proto(mut object: Self::ProtoType) -> ProtoResult<Self> {
+        let author = Author::try_from(object.take_author())?;
+        let ledger_info = LedgerInfo::from_proto(object.take_ledger_info())?;
+        let signature = Signature::try_from(object.get_signature())?;
+        Ok(CommitVote {
+            author,
+            ledger_info,
+            signature,
+        })
+    }
+}
@@ -1,7 +1,9 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod aggregated_signature;
 pub(crate) mod block;
+pub(crate) mod commit_vote;
 pub(crate) mod proposal_msg;
 pub(crate) mod quorum_cert;
 pub(crate) mod sync_info;
@@ -9,7 +9,7 @@ use failure::prelude::*;
 use network::proto::Proposal as ProtoProposal;
 use proto_conv::{FromProto, IntoProto};
 use std::fmt;
-use types::crypto_proxies::ValidatorVerifier;
+use types::{crypto_proxies::ValidatorVerifier, ledger_info::LedgerInfo};
 
 /// ProposalMsg contains the required information for the proposer election protocol to make its
 /// choice (typically depends on round and proposer info).
@@ -43,13 +43,24 @@ impl<T: Payload> From<ProposalUncheckedSignatures<T>> for ProposalMsg<T> {
 }
 
 impl<T: Payload> ProposalUncheckedSignatures<T> {
+    /// The epoch of the sync info, which the signatures can only be validated in.
+    pub fn epoch(&self) -> u64 {
+        self.0.sync_info.epoch()
+    }
+
     /// Validates the signatures of the proposal. This includes the leader's signature over the
     /// block and the QC, the timeout certificate signatures and the highest_ledger_info signatures.
-    pub fn validate_signatures(self, validator: &ValidatorVerifier) -> Result<ProposalMsg<T>> {
+    /// The QCs may certify the genesis of the current epoch, derived from the ledger info ending
+    /// the previous epoch if any.
+    pub fn validate_signatures(
+        self,
+        validator: &ValidatorVerifier,
+        epoch_ending_ledger_info: Option<&LedgerInfo>,
+    ) -> Result<ProposalMsg<T>> {
         // verify block leader's signature and QC
         self.0
             .proposal
-            .validate_signatures(validator)
+            .validate_signatures(validator, epoch_ending_ledger_info)
             .map_err(|e| format_err!("{:?}", e))?;
         // if there is a timeout certificate, verify its signatures
         if let Some(tc) = self.0.sync_info.highest_timeout_certificate() {
@@ -59,7 +70,7 @@ impl<T: Payload> ProposalUncheckedSignatures<T> {
         self.0
             .sync_info
             .highest_ledger_info()
-            .verify(validator)
+            .verify(validator, epoch_ending_ledger_info)
             .map_err(|e| format_err!("{:?}", e))?;
         // return proposal
         Ok(self.0)
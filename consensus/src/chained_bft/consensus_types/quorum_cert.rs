@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
-    common::Round,
-    consensus_types::{vote_data::VoteData, vote_msg::VoteMsgVerificationError},
+    common::{Author, Round},
+    consensus_types::{
+        aggregated_signature::AggregatedSignature, vote_data::VoteData,
+        vote_msg::VoteMsgVerificationError,
+    },
 };
 use crypto::{
     hash::{CryptoHash, ACCUMULATOR_PLACEHOLDER_HASH, GENESIS_BLOCK_ID},
@@ -28,6 +31,9 @@ pub struct QuorumCert {
     vote_data: VoteData,
     /// The signed LedgerInfo of a committed block that carries the data about the certified block.
     signed_ledger_info: LedgerInfoWithSignatures,
+    /// The aggregate of the signatures of the LedgerInfo, which the QC carries instead of the
+    /// signatures if the validators aggregate them.
+    aggregated_signature: Option<AggregatedSignature>,
 }
 
 impl Display for QuorumCert {
@@ -45,8 +51,24 @@ impl QuorumCert {
         QuorumCert {
             vote_data,
             signed_ledger_info,
+            aggregated_signature: None,
         }
     }
+
+    /// Creates a QC that carries the aggregate of the signatures of the LedgerInfo instead of the
+    /// signatures themselves.
+    pub fn new_aggregated(
+        vote_data: VoteData,
+        ledger_info: LedgerInfo,
+        aggregated_signature: AggregatedSignature,
+    ) -> Self {
+        QuorumCert {
+            vote_data,
+            signed_ledger_info: LedgerInfoWithSignatures::new(ledger_info, HashMap::new()),
+            aggregated_signature: Some(aggregated_signature),
+        }
+    }
+
     /// All the vote data getters are just proxies for retrieving the values from the VoteData
     pub fn certified_block_id(&self) -> HashValue {
         self.vote_data.block_id()
@@ -79,6 +101,19 @@ impl QuorumCert {
         &self.signed_ledger_info
     }
 
+    /// Returns the authors whose signatures the QC carries, individually or aggregated.
+    pub fn signers(&self) -> Vec<Author> {
+        match &self.aggregated_signature {
+            Some(aggregated_signature) => aggregated_signature.authors().to_vec(),
+            None => self
+                .signed_ledger_info
+                .signatures()
+                .keys()
+                .cloned()
+                .collect(),
+        }
+    }
+
     pub fn committed_block_id(&self) -> Option<HashValue> {
         let id = self.ledger_info().ledger_info().consensus_block_id();
         if id.is_zero() {
@@ -132,24 +167,70 @@ impl QuorumCert {
         )
     }
 
+    /// QuorumCert for the genesis block of the epoch following the one ended by the given ledger
+    /// info (see `Block::make_genesis_block_from_ledger_info`): the genesis block takes over the
+    /// id of the block committed by the ledger info, and certifies and commits itself at the state
+    /// of the ledger info. Like the genesis of the first epoch, it's implicitly agreed upon.
+    pub fn certificate_for_genesis_from_ledger_info(ledger_info: &LedgerInfo) -> QuorumCert {
+        let genesis_id = ledger_info.consensus_block_id();
+        let state_id = ledger_info.transaction_accumulator_hash();
+        let vote_data = VoteData::new(genesis_id, state_id, 0, genesis_id, 0, genesis_id, 0);
+        let signer = ValidatorSigner::genesis();
+        let li = LedgerInfo::new(
+            ledger_info.version(),
+            state_id,
+            vote_data.hash(),
+            genesis_id,
+            ledger_info.epoch_num() + 1,
+            ledger_info.timestamp_usecs(),
+            None,
+        );
+        let signature = signer
+            .sign_message(li.hash())
+            .expect("Fail to sign genesis ledger info");
+        let mut signatures = HashMap::new();
+        signatures.insert(signer.author(), signature);
+        QuorumCert::new(vote_data, LedgerInfoWithSignatures::new(li, signatures))
+    }
+
+    /// Verifies that the QC is signed by a quorum of the given validators. The only exception is
+    /// the certificate for the genesis block of the current epoch, which is derived from the
+    /// ledger info ending the previous epoch, if any.
     pub fn verify(
         &self,
         validator: &ValidatorVerifier,
+        epoch_ending_ledger_info: Option<&LedgerInfo>,
     ) -> ::std::result::Result<(), VoteMsgVerificationError> {
         let vote_hash = self.vote_data.hash();
         if self.ledger_info().ledger_info().consensus_data_hash() != vote_hash {
             return Err(VoteMsgVerificationError::ConsensusDataMismatch);
         }
         // Genesis is implicitly agreed upon, it doesn't have real signatures.
-        if self.vote_data.block_round() == 0
-            && self.vote_data.block_id() == *GENESIS_BLOCK_ID
-            && self.vote_data.executed_state_id() == *ACCUMULATOR_PLACEHOLDER_HASH
-        {
-            return Ok(());
+        if self.vote_data.block_round() == 0 {
+            let genesis_qc = match epoch_ending_ledger_info {
+                Some(ledger_info) => Self::certificate_for_genesis_from_ledger_info(ledger_info),
+                None => Self::certificate_for_genesis(),
+            };
+            if self.vote_data == genesis_qc.vote_data
+                && self.ledger_info().ledger_info() == genesis_qc.ledger_info().ledger_info()
+            {
+                return Ok(());
+            }
+        }
+        // Any other QC, including the ones of round 0, must be signed by a quorum, with the
+        // signature scheme of the validators.
+        let li_hash = self.ledger_info().ledger_info().hash();
+        match (validator.aggregation_verifier(), &self.aggregated_signature) {
+            (Some(_), Some(aggregated_signature)) if self.ledger_info().signatures().is_empty() => {
+                aggregated_signature
+                    .verify(validator, li_hash)
+                    .map_err(VoteMsgVerificationError::SigVerifyError)
+            }
+            (None, None) => validator
+                .batch_verify_aggregated_signature(li_hash, self.ledger_info().signatures())
+                .map_err(VoteMsgVerificationError::SigVerifyError),
+            _ => Err(VoteMsgVerificationError::SignatureSchemeMismatch),
         }
-        self.ledger_info()
-            .verify(validator)
-            .map_err(VoteMsgVerificationError::SigVerifyError)
     }
 }
 
@@ -160,6 +241,9 @@ impl IntoProto for QuorumCert {
         let mut proto = Self::ProtoType::new();
         proto.set_vote_data(self.vote_data.into_proto());
         proto.set_signed_ledger_info(self.signed_ledger_info.into_proto());
+        if let Some(aggregated_signature) = self.aggregated_signature {
+            proto.set_aggregated_signature(aggregated_signature.into_proto());
+        }
         proto
     }
 }
@@ -171,10 +255,18 @@ impl FromProto for QuorumCert {
         let vote_data = VoteData::from_proto(object.take_vote_data())?;
         let signed_ledger_info =
             LedgerInfoWithSignatures::from_proto(object.take_signed_ledger_info())?;
+        let aggregated_signature = if object.has_aggregated_signature() {
+            Some(AggregatedSignature::from_proto(
+                object.take_aggregated_signature(),
+            )?)
+        } else {
+            None
+        };
 
         Ok(QuorumCert {
             vote_data,
             signed_ledger_info,
+            aggregated_signature,
         })
     }
 }
@@ -13,7 +13,7 @@ use crate::chained_bft::{
 use proto_conv::{FromProto, IntoProto};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
-use types::crypto_proxies::ValidatorVerifier;
+use types::{crypto_proxies::ValidatorVerifier, ledger_info::LedgerInfo};
 
 #[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq)]
 /// This struct describes basic synchronization metadata.
@@ -103,14 +103,30 @@ impl SyncInfo {
         }
     }
 
+    /// The epoch of the highest quorum cert, which the sync info can only be verified in.
+    pub fn epoch(&self) -> u64 {
+        self.highest_quorum_cert
+            .ledger_info()
+            .ledger_info()
+            .epoch_num()
+    }
+
     /// The highest round the SyncInfo carries.
     pub fn highest_round(&self) -> Round {
         std::cmp::max(self.hqc_round(), self.htc_round())
     }
 
-    pub fn verify(&self, validator: &ValidatorVerifier) -> Result<(), SyncInfoVerificationError> {
-        self.highest_quorum_cert.verify(validator)?;
-        self.highest_ledger_info.verify(validator)?;
+    /// Verifies the certificates, which may certify the genesis of the current epoch, derived from
+    /// the ledger info ending the previous epoch if any.
+    pub fn verify(
+        &self,
+        validator: &ValidatorVerifier,
+        epoch_ending_ledger_info: Option<&LedgerInfo>,
+    ) -> Result<(), SyncInfoVerificationError> {
+        self.highest_quorum_cert
+            .verify(validator, epoch_ending_ledger_info)?;
+        self.highest_ledger_info
+            .verify(validator, epoch_ending_ledger_info)?;
         if let Some(tc) = &self.highest_timeout_cert {
             tc.verify(validator)?;
         }
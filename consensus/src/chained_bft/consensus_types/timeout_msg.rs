@@ -4,6 +4,7 @@
 use crate::chained_bft::{
     common::{Author, Round},
     consensus_types::{
+        aggregated_signature::AggregatedSignature,
         sync_info::SyncInfo,
         timeout_msg::PacemakerTimeoutCertificateVerificationError::*,
         vote_msg::{VoteMsg, VoteMsgVerificationError},
@@ -11,18 +12,26 @@ use crate::chained_bft::{
 };
 use canonical_serialization::{CanonicalSerialize, CanonicalSerializer, SimpleSerializer};
 use crypto::{
-    hash::{CryptoHash, CryptoHasher, PacemakerTimeoutHasher, TimeoutMsgHasher},
+    hash::{
+        CryptoHash, CryptoHasher, PacemakerTimeoutHasher, TimeoutMsgHasher, TimeoutRoundHasher,
+    },
     HashValue,
 };
+use failure::format_err;
 use mirai_annotations::assumed_postcondition;
 use network;
 use proto_conv::{FromProto, IntoProto};
 use protobuf::RepeatedField;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, convert::TryFrom, fmt, iter::FromIterator};
+use std::{
+    collections::{BTreeMap, HashSet},
+    convert::TryFrom,
+    fmt,
+    iter::FromIterator,
+};
 use types::{
     account_address::AccountAddress,
-    crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier},
+    crypto_proxies::{bls12381, Signature, ValidatorSigner, ValidatorVerifier},
     validator_verifier::VerifyError,
 };
 
@@ -51,6 +60,30 @@ impl CryptoHash for PacemakerTimeoutSerializer {
     }
 }
 
+// Internal use only. Contains all the fields that contribute to the computation of the digest
+// that every author timing out a round signs with its BLS12-381 key, so that the signatures can
+// be aggregated.
+struct TimeoutRoundSerializer {
+    round: Round,
+}
+
+impl CanonicalSerialize for TimeoutRoundSerializer {
+    fn serialize(&self, serializer: &mut impl CanonicalSerializer) -> failure::Result<()> {
+        serializer.encode_u64(self.round)?;
+        Ok(())
+    }
+}
+
+impl CryptoHash for TimeoutRoundSerializer {
+    type Hasher = TimeoutRoundHasher;
+
+    fn hash(&self) -> HashValue {
+        let mut state = Self::Hasher::default();
+        state.write(&SimpleSerializer::<Vec<u8>>::serialize(self).expect("Should serialize."));
+        state.finish()
+    }
+}
+
 /// TimeoutMsg verification errors.
 #[derive(Debug, Fail, PartialEq)]
 pub enum TimeoutVerificationError {
@@ -60,6 +93,10 @@ pub enum TimeoutVerificationError {
     /// The signature doesn't pass verification
     #[fail(display = "SigVerifyError: {}", _0)]
     SigVerifyError(VerifyError),
+    /// The timeout carries a BLS12-381 signature while the validators don't aggregate their
+    /// signatures, or the other way around.
+    #[fail(display = "SignatureSchemeMismatch")]
+    SignatureSchemeMismatch,
 }
 
 /// This message will be broadcast by a pacemaker as part of TimeoutMsg when its local
@@ -72,6 +109,9 @@ pub struct PacemakerTimeout {
     author: Author,
     signature: Signature,
     vote: Option<VoteMsg>,
+    // BLS12-381 signature of the round, which the TimeoutCertificate aggregates if the validators
+    // aggregate their signatures.
+    bls_signature: Option<bls12381::Signature>,
 }
 
 impl PacemakerTimeout {
@@ -82,14 +122,24 @@ impl PacemakerTimeout {
         let signature = validator_signer
             .sign_message(digest)
             .expect("Failed to sign PacemakerTimeout");
+        let bls_signature = validator_signer
+            .bls_sign_message(Self::round_digest(round))
+            .map(Into::into);
         PacemakerTimeout {
             round,
             author,
             signature: signature.into(),
             vote,
+            bls_signature,
         }
     }
 
+    /// The digest of the round signed by the BLS12-381 signatures of the timeouts, which unlike
+    /// `digest` doesn't depend on the author.
+    fn round_digest(round: Round) -> HashValue {
+        TimeoutRoundSerializer { round }.hash()
+    }
+
     fn pacemaker_timeout_digest(author: AccountAddress, round: Round) -> HashValue {
         PacemakerTimeoutSerializer { round, author }.hash()
     }
@@ -107,11 +157,26 @@ impl PacemakerTimeout {
         self.vote.as_ref()
     }
 
-    /// Verifies that this message has valid signature
+    /// Verifies that this message has valid signature, as well as a valid BLS12-381 one if and
+    /// only if the validators aggregate their signatures.
     pub fn verify(&self, validator: &ValidatorVerifier) -> Result<(), TimeoutVerificationError> {
         self.signature
             .verify(validator, self.author, self.digest())
             .map_err(TimeoutVerificationError::SigVerifyError)?;
+        match (
+            validator.aggregation_verifier(),
+            self.bls_signature.as_ref(),
+        ) {
+            (Some(aggregation_verifier), Some(bls_signature)) => bls_signature
+                .verify(
+                    aggregation_verifier,
+                    self.author,
+                    Self::round_digest(self.round),
+                )
+                .map_err(TimeoutVerificationError::SigVerifyError)?,
+            (None, None) => (),
+            _ => return Err(TimeoutVerificationError::SignatureSchemeMismatch),
+        }
         if let Some(vote) = self.vote.as_ref() {
             vote.verify(validator)
                 .map_err(TimeoutVerificationError::VoteMsgError)?;
@@ -128,6 +193,11 @@ impl PacemakerTimeout {
     pub fn signature(&self) -> &Signature {
         &self.signature
     }
+
+    /// Returns the BLS12-381 signature of the round, if the validators aggregate their signatures
+    pub fn bls_signature(&self) -> Option<&bls12381::Signature> {
+        self.bls_signature.as_ref()
+    }
 }
 
 impl IntoProto for PacemakerTimeout {
@@ -141,6 +211,9 @@ impl IntoProto for PacemakerTimeout {
         if let Some(vote) = self.vote {
             proto.set_vote(vote.into_proto());
         }
+        if let Some(bls_signature) = self.bls_signature {
+            proto.set_bls_signature(bytes::Bytes::from(bls_signature.to_bytes()));
+        }
         proto
     }
 }
@@ -157,11 +230,17 @@ impl FromProto for PacemakerTimeout {
         } else {
             None
         };
+        let bls_signature = if object.get_bls_signature().is_empty() {
+            None
+        } else {
+            Some(bls12381::Signature::try_from(object.get_bls_signature())?)
+        };
         Ok(PacemakerTimeout {
             round,
             author,
             signature,
             vote,
+            bls_signature,
         })
     }
 }
@@ -290,6 +369,40 @@ impl IntoProto for TimeoutMsg {
 pub struct PacemakerTimeoutCertificate {
     round: Round,
     timeouts: Vec<PacemakerTimeout>,
+    // The aggregated signatures of the timeouts per round, which the certificate carries instead
+    // of the timeouts if the validators aggregate their signatures.
+    aggregated_timeouts: Vec<AggregatedTimeouts>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+/// The aggregate of the BLS12-381 signatures of the timeouts of the same round
+pub struct AggregatedTimeouts {
+    round: Round,
+    aggregated_signature: AggregatedSignature,
+}
+
+impl IntoProto for AggregatedTimeouts {
+    type ProtoType = network::proto::AggregatedTimeouts;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto = Self::ProtoType::new();
+        proto.set_round(self.round);
+        proto.set_aggregated_signature(self.aggregated_signature.into_proto());
+        proto
+    }
+}
+
+impl FromProto for AggregatedTimeouts {
+    type ProtoType = network::proto::AggregatedTimeouts;
+
+    fn from_proto(mut object: Self::ProtoType) -> failure::Result<Self> {
+        Ok(AggregatedTimeouts {
+            round: object.get_round(),
+            aggregated_signature: AggregatedSignature::from_proto(
+                object.take_aggregated_signature(),
+            )?,
+        })
+    }
 }
 
 /// PacemakerTimeoutCertificate verification errors.
@@ -304,6 +417,13 @@ pub enum PacemakerTimeoutCertificateVerificationError {
     /// The signature on one of timeouts doesn't pass verification
     #[fail(display = "SigVerifyError for {}: {}", _0, _1)]
     SigVerifyError(Author, VerifyError),
+    /// The aggregated signatures of the timeouts don't pass verification
+    #[fail(display = "AggregatedSigVerifyError: {}", _0)]
+    AggregatedSigVerifyError(VerifyError),
+    /// The certificate carries individual timeouts while the validators aggregate their
+    /// signatures, or aggregated ones while they don't
+    #[fail(display = "SignatureSchemeMismatch")]
+    SignatureSchemeMismatch,
 }
 
 impl fmt::Display for PacemakerTimeoutCertificate {
@@ -315,6 +435,17 @@ impl fmt::Display for PacemakerTimeoutCertificate {
                 write!(f, ", ")?;
             }
         }
+        for (idx, aggregated_timeouts) in self.aggregated_timeouts.iter().enumerate() {
+            write!(
+                f,
+                "<{} x{}>",
+                aggregated_timeouts.round,
+                aggregated_timeouts.aggregated_signature.authors().len()
+            )?;
+            if idx != self.aggregated_timeouts.len() - 1 {
+                write!(f, ", ")?;
+            }
+        }
         write!(f, "]")
     }
 }
@@ -322,7 +453,48 @@ impl fmt::Display for PacemakerTimeoutCertificate {
 impl PacemakerTimeoutCertificate {
     /// Creates new PacemakerTimeoutCertificate
     pub fn new(round: Round, timeouts: Vec<PacemakerTimeout>) -> PacemakerTimeoutCertificate {
-        PacemakerTimeoutCertificate { round, timeouts }
+        PacemakerTimeoutCertificate {
+            round,
+            timeouts,
+            aggregated_timeouts: vec![],
+        }
+    }
+
+    /// Creates new PacemakerTimeoutCertificate that carries the aggregates of the BLS12-381
+    /// signatures of the timeouts of each round instead of the timeouts. Fails if one of the
+    /// timeouts doesn't have a BLS12-381 signature.
+    pub fn aggregate(
+        round: Round,
+        timeouts: &[PacemakerTimeout],
+    ) -> failure::Result<PacemakerTimeoutCertificate> {
+        let mut round_to_signatures: BTreeMap<Round, BTreeMap<Author, bls12381::Signature>> =
+            BTreeMap::new();
+        for timeout in timeouts {
+            let bls_signature = timeout.bls_signature().ok_or_else(|| {
+                format_err!(
+                    "Timeout of {} has no BLS12-381 signature",
+                    timeout.author().short_str()
+                )
+            })?;
+            round_to_signatures
+                .entry(timeout.round())
+                .or_insert_with(BTreeMap::new)
+                .insert(timeout.author(), bls_signature.clone());
+        }
+        let aggregated_timeouts = round_to_signatures
+            .into_iter()
+            .map(|(round, signatures)| {
+                Ok(AggregatedTimeouts {
+                    round,
+                    aggregated_signature: AggregatedSignature::aggregate(&signatures)?,
+                })
+            })
+            .collect::<failure::Result<_>>()?;
+        Ok(PacemakerTimeoutCertificate {
+            round,
+            timeouts: vec![],
+            aggregated_timeouts,
+        })
     }
 
     /// Verifies that timeouts in message actually certify the round
@@ -330,6 +502,27 @@ impl PacemakerTimeoutCertificate {
         &self,
         validator: &ValidatorVerifier,
     ) -> Result<(), PacemakerTimeoutCertificateVerificationError> {
+        let min_round = match validator.aggregation_verifier() {
+            Some(_) => self.verify_aggregated_timeouts(validator)?,
+            None => self.verify_timeouts(validator)?,
+        };
+        if min_round == Some(self.round) {
+            Ok(())
+        } else {
+            Err(RoundMismatch {
+                expected: min_round.unwrap_or(0),
+            })
+        }
+    }
+
+    /// Verifies the signatures of the timeouts, and returns their min round
+    fn verify_timeouts(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> Result<Option<Round>, PacemakerTimeoutCertificateVerificationError> {
+        if !self.aggregated_timeouts.is_empty() {
+            return Err(SignatureSchemeMismatch);
+        }
         let mut min_round: Option<Round> = None;
         let mut unique_authors = HashSet::new();
         for timeout in &self.timeouts {
@@ -347,13 +540,35 @@ impl PacemakerTimeoutCertificate {
         if unique_authors.len() < validator.quorum_size() {
             return Err(NoQuorum);
         }
-        if min_round == Some(self.round) {
-            Ok(())
-        } else {
-            Err(RoundMismatch {
-                expected: min_round.unwrap_or(0),
-            })
+        Ok(min_round)
+    }
+
+    /// Verifies the aggregated signatures of the timeouts of each round, which must be signed by
+    /// a quorum of distinct authors, and returns their min round
+    fn verify_aggregated_timeouts(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> Result<Option<Round>, PacemakerTimeoutCertificateVerificationError> {
+        if !self.timeouts.is_empty() {
+            return Err(SignatureSchemeMismatch);
         }
+        let aggregated_signatures: Vec<_> = self
+            .aggregated_timeouts
+            .iter()
+            .map(|aggregated_timeouts| {
+                (
+                    PacemakerTimeout::round_digest(aggregated_timeouts.round),
+                    &aggregated_timeouts.aggregated_signature,
+                )
+            })
+            .collect();
+        AggregatedSignature::verify_all(validator, &aggregated_signatures)
+            .map_err(AggregatedSigVerifyError)?;
+        Ok(self
+            .aggregated_timeouts
+            .iter()
+            .map(|aggregated_timeouts| aggregated_timeouts.round)
+            .min())
     }
 
     /// Returns the round of the timeout
@@ -380,6 +595,11 @@ impl IntoProto for PacemakerTimeoutCertificate {
         proto.set_timeouts(RepeatedField::from_iter(
             self.timeouts.into_iter().map(PacemakerTimeout::into_proto),
         ));
+        proto.set_aggregated_timeouts(RepeatedField::from_iter(
+            self.aggregated_timeouts
+                .into_iter()
+                .map(AggregatedTimeouts::into_proto),
+        ));
         proto.set_round(self.round);
         proto
     }
@@ -394,9 +614,15 @@ impl FromProto for PacemakerTimeoutCertificate {
             .into_iter()
             .map(PacemakerTimeout::from_proto)
             .collect::<failure::Result<Vec<_>>>()?;
-        Ok(PacemakerTimeoutCertificate::new(
-            object.get_round(),
+        let aggregated_timeouts = object
+            .take_aggregated_timeouts()
+            .into_iter()
+            .map(AggregatedTimeouts::from_proto)
+            .collect::<failure::Result<Vec<_>>>()?;
+        Ok(PacemakerTimeoutCertificate {
+            round: object.get_round(),
             timeouts,
-        ))
+            aggregated_timeouts,
+        })
     }
 }
@@ -15,7 +15,7 @@ use std::{
     fmt::{Display, Formatter},
 };
 use types::{
-    crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier},
+    crypto_proxies::{bls12381, Signature, ValidatorSigner, ValidatorVerifier},
     ledger_info::LedgerInfo,
     validator_verifier::VerifyError,
 };
@@ -29,6 +29,10 @@ pub enum VoteMsgVerificationError {
     /// The signature doesn't pass verification
     #[fail(display = "SigVerifyError: {}", _0)]
     SigVerifyError(VerifyError),
+    /// The signatures are not the ones of the scheme of the validators: either individual ones
+    /// while they aggregate them, or aggregated ones while they don't.
+    #[fail(display = "SignatureSchemeMismatch")]
+    SignatureSchemeMismatch,
 }
 
 /// VoteMsg is the struct that is ultimately sent by the voter in response for
@@ -45,6 +49,9 @@ pub struct VoteMsg {
     ledger_info: LedgerInfo,
     /// Signature of the LedgerInfo
     signature: Signature,
+    /// BLS12-381 signature of the LedgerInfo, which the QC aggregates if the validators aggregate
+    /// their signatures
+    bls_signature: Option<bls12381::Signature>,
 }
 
 impl Display for VoteMsg {
@@ -70,11 +77,15 @@ impl VoteMsg {
         let li_sig = validator_signer
             .sign_message(ledger_info_placeholder.hash())
             .expect("Failed to sign LedgerInfo");
+        let bls_signature = validator_signer
+            .bls_sign_message(ledger_info_placeholder.hash())
+            .map(Into::into);
         Self {
             vote_data,
             author,
             ledger_info: ledger_info_placeholder,
             signature: li_sig.into(),
+            bls_signature,
         }
     }
 
@@ -121,15 +132,28 @@ impl VoteMsg {
         &self.signature
     }
 
+    /// Return the BLS12-381 signature of the vote, if the validators aggregate their signatures
+    pub fn bls_signature(&self) -> Option<&bls12381::Signature> {
+        self.bls_signature.as_ref()
+    }
+
     /// Verifies that the consensus data hash of LedgerInfo corresponds to the vote info,
-    /// and then verifies the signature.
+    /// and then verifies the signature, as well as the BLS12-381 one if and only if the
+    /// validators aggregate their signatures.
     pub fn verify(&self, validator: &ValidatorVerifier) -> Result<(), VoteMsgVerificationError> {
         if self.ledger_info.consensus_data_hash() != self.vote_data.hash() {
             return Err(VoteMsgVerificationError::ConsensusDataMismatch);
         }
         self.signature()
             .verify(validator, self.author(), self.ledger_info.hash())
-            .map_err(VoteMsgVerificationError::SigVerifyError)
+            .map_err(VoteMsgVerificationError::SigVerifyError)?;
+        match (validator.aggregation_verifier(), self.bls_signature()) {
+            (Some(aggregation_verifier), Some(bls_signature)) => bls_signature
+                .verify(aggregation_verifier, self.author(), self.ledger_info.hash())
+                .map_err(VoteMsgVerificationError::SigVerifyError),
+            (None, None) => Ok(()),
+            _ => Err(VoteMsgVerificationError::SignatureSchemeMismatch),
+        }
     }
 }
 
@@ -142,6 +166,9 @@ impl IntoProto for VoteMsg {
         proto.set_author(self.author.into());
         proto.set_ledger_info(self.ledger_info.into_proto());
         proto.set_signature(bytes::Bytes::from(self.signature.to_bytes()));
+        if let Some(bls_signature) = self.bls_signature {
+            proto.set_bls_signature(bytes::Bytes::from(bls_signature.to_bytes()));
+        }
         proto
     }
 }
@@ -154,11 +181,17 @@ impl FromProto for VoteMsg {
         let author = Author::try_from(object.take_author())?;
         let ledger_info = LedgerInfo::from_proto(object.take_ledger_info())?;
         let signature = Signature::try_from(object.get_signature())?;
+        let bls_signature = if object.get_bls_signature().is_empty() {
+            None
+        } else {
+            Some(bls12381::Signature::try_from(object.get_bls_signature())?)
+        };
         Ok(VoteMsg {
             vote_data,
             author,
             ledger_info,
             signature,
+            bls_signature,
         })
     }
 }
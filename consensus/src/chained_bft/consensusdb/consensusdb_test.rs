@@ -3,6 +3,7 @@
 
 use super::*;
 use tools::tempdir::TempPath;
+use types::account_address::AccountAddress;
 
 #[test]
 fn test_put_get() {
@@ -53,3 +54,32 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks::<i64>().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_block_metadata() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+    let author = AccountAddress::random();
+    let metadata = |round| BlockMetadata::new(round, Some(author), vec![author]);
+
+    db.save_block_metadata(0, &[metadata(1), metadata(2)])
+        .unwrap();
+    db.save_block_metadata(1, &[metadata(1), metadata(3), metadata(4)])
+        .unwrap();
+    assert_eq!(
+        db.get_block_metadata(0).unwrap(),
+        vec![metadata(1), metadata(2)]
+    );
+    assert_eq!(
+        db.get_block_metadata(1).unwrap(),
+        vec![metadata(1), metadata(3), metadata(4)]
+    );
+
+    // The metadata of the previous epochs goes along with the older blocks of the epoch.
+    db.delete_block_metadata_before(1, 3).unwrap();
+    assert!(db.get_block_metadata(0).unwrap().is_empty());
+    assert_eq!(
+        db.get_block_metadata(1).unwrap(),
+        vec![metadata(3), metadata(4)]
+    );
+}
@@ -0,0 +1,356 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Payload, Round},
+    consensus_types::{block::Block, quorum_cert::QuorumCert},
+    consensusdb::ConsensusDB,
+    liveness::pacemaker_timeout_manager::HighestTimeoutCertificates,
+    safety::safety_rules::ConsensusState,
+};
+use crypto::{hash::GENESIS_BLOCK_ID, HashValue};
+use failure::prelude::*;
+use rmp_serde::{from_slice, to_vec_named};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter},
+    path::Path,
+};
+use types::ledger_info::LedgerInfo;
+
+#[cfg(test)]
+#[path = "inspector_test.rs"]
+mod inspector_test;
+
+/// Gives access to the ConsensusDB of a stopped validator, to look at what consensus would recover
+/// from on startup and to get rid of the blocks it couldn't recover from, or to rebuild the root
+/// it can't recover without.
+pub struct ConsensusDbInspector {
+    db: ConsensusDB,
+}
+
+struct ReportedBlock {
+    id: HashValue,
+    // The depth below the committed root (0 for the orphans).
+    depth: usize,
+    description: String,
+    certified: bool,
+}
+
+/// The content of a ConsensusDB, laid out relative to the root committed in the ledger.
+pub struct ConsensusDbReport {
+    committed_root: HashValue,
+    consensus_state: Option<ConsensusState>,
+    highest_timeout_round: Option<Round>,
+    // The committed root followed by its descendants, depth first.
+    tree: Vec<ReportedBlock>,
+    // The blocks that don't descend from the committed root, by round.
+    orphans: Vec<ReportedBlock>,
+    // Whether a quorum cert certifies the committed root and whether another one commits it, both
+    // of which consensus recovers along with the root.
+    root_certified: bool,
+    root_committed: bool,
+    // The quorum certs whose certified block isn't stored.
+    dangling_quorum_certs: Vec<QuorumCert>,
+    // The epochs with metadata of committed blocks, along with their first and last rounds and
+    // the number of blocks.
+    block_metadata: Vec<(u64, Round, Round, usize)>,
+}
+
+impl ConsensusDbInspector {
+    /// Opens the ConsensusDB under the given directory, i.e., `storage.dir` in the node config,
+    /// to inspect and repair it. Fails if there is no ConsensusDB.
+    pub fn open<P: AsRef<Path>>(db_root_path: P) -> Result<Self> {
+        Ok(Self {
+            db: ConsensusDB::open_existing(db_root_path)?,
+        })
+    }
+
+    /// Opens the ConsensusDB under the given directory in read-only mode, to inspect it only.
+    /// Fails if there is no ConsensusDB.
+    pub fn open_readonly<P: AsRef<Path>>(db_root_path: P) -> Result<Self> {
+        Ok(Self {
+            db: ConsensusDB::open_readonly(db_root_path)?,
+        })
+    }
+
+    /// Reports the stored blocks and quorum certs along with the consensus state, given the id
+    /// of the block committed by the latest ledger info.
+    pub fn inspect<T: Payload>(&self, committed_root: HashValue) -> Result<ConsensusDbReport> {
+        let (consensus_state, highest_timeout_certificates, mut blocks, quorum_certs) =
+            self.db.get_data::<T>()?;
+        let consensus_state = match consensus_state {
+            Some(bytes) => Some(from_slice::<ConsensusState>(&bytes)?),
+            None => None,
+        };
+        let highest_timeout_round = match highest_timeout_certificates {
+            Some(bytes) => from_slice::<HighestTimeoutCertificates>(&bytes)?
+                .highest_timeout_certificate()
+                .map(|tc| tc.round()),
+            None => None,
+        };
+        blocks.sort_by_key(Block::round);
+        let certified: HashSet<_> = quorum_certs
+            .iter()
+            .map(QuorumCert::certified_block_id)
+            .collect();
+        let stored: HashSet<_> = blocks.iter().map(Block::id).collect();
+        let report_block = |block: &Block<T>, depth| ReportedBlock {
+            id: block.id(),
+            depth,
+            description: format!("{}", block),
+            certified: certified.contains(&block.id()),
+        };
+
+        let mut children: HashMap<HashValue, Vec<&Block<T>>> = HashMap::new();
+        for block in &blocks {
+            children.entry(block.parent_id()).or_default().push(block);
+        }
+        let mut tree = vec![];
+        let mut stack: Vec<_> = blocks
+            .iter()
+            .filter(|block| block.id() == committed_root)
+            .map(|block| (block, 0))
+            .collect();
+        while let Some((block, depth)) = stack.pop() {
+            tree.push(report_block(block, depth));
+            if let Some(children) = children.get(&block.id()) {
+                // Reversed so that the children are reported by round.
+                stack.extend(children.iter().rev().map(|child| (*child, depth + 1)));
+            }
+        }
+        let in_tree: HashSet<_> = tree.iter().map(|block| block.id).collect();
+        let orphans = blocks
+            .iter()
+            .filter(|block| !in_tree.contains(&block.id()))
+            .map(|block| report_block(block, 0))
+            .collect();
+        let root_certified = quorum_certs
+            .iter()
+            .any(|qc| qc.certified_block_id() == committed_root);
+        let root_committed = quorum_certs
+            .iter()
+            .any(|qc| qc.committed_block_id() == Some(committed_root));
+        let dangling_quorum_certs = quorum_certs
+            .into_iter()
+            .filter(|qc| !stored.contains(&qc.certified_block_id()))
+            .collect();
+        let mut block_metadata: Vec<(u64, Round, Round, usize)> = vec![];
+        for (epoch, metadata) in self.db.get_all_block_metadata()? {
+            match block_metadata.last_mut() {
+                Some((last_epoch, _, last_round, count)) if *last_epoch == epoch => {
+                    *last_round = metadata.round();
+                    *count += 1;
+                }
+                _ => block_metadata.push((epoch, metadata.round(), metadata.round(), 1)),
+            }
+        }
+
+        Ok(ConsensusDbReport {
+            committed_root,
+            consensus_state,
+            highest_timeout_round,
+            tree,
+            orphans,
+            root_certified,
+            root_committed,
+            dangling_quorum_certs,
+            block_metadata,
+        })
+    }
+
+    /// Deletes the blocks that don't descend from the committed root, along with their quorum
+    /// certs and the quorum certs of blocks that aren't stored. Returns the ids of the deleted
+    /// blocks and quorum certs.
+    ///
+    /// Nothing is deleted if the committed root itself isn't stored: the validator may have
+    /// crashed while syncing up to blocks the ledger doesn't have yet.
+    pub fn prune_orphans<T: Payload>(&self, committed_root: HashValue) -> Result<Vec<HashValue>> {
+        let report = self.inspect::<T>(committed_root)?;
+        ensure!(
+            report.is_root_stored(),
+            "The committed root {} isn't in the ConsensusDB, refusing to delete every block",
+            committed_root
+        );
+        let to_delete: Vec<_> = report
+            .orphans()
+            .into_iter()
+            .chain(
+                report
+                    .dangling_quorum_certs()
+                    .iter()
+                    .map(QuorumCert::certified_block_id),
+            )
+            .collect();
+        if !to_delete.is_empty() {
+            self.db
+                .delete_blocks_and_quorum_certificates::<T>(to_delete.clone())?;
+        }
+        Ok(to_delete)
+    }
+
+    /// Makes sure consensus recovers from the root committed by the given ledger info, i.e., the
+    /// latest one in the ledger: if the root block or a quorum cert certifying or committing it
+    /// is missing, the ConsensusDB is reset to the genesis the root derives from, the way consensus
+    /// starts over on epoch changes. All the other blocks and quorum certs are deleted, as are the
+    /// highest timeout certificates and the metadata of the blocks committed from the epoch of the
+    /// genesis on. Returns whether the DB was reset.
+    ///
+    /// Only the genesis and the ledger infos ending an epoch determine the root: the root of any
+    /// other ledger info can't be rebuilt without the quorum certs of its epoch.
+    pub fn repair_root<T: Payload>(&self, ledger_info: &LedgerInfo) -> Result<bool> {
+        let report = self.inspect::<T>(ledger_info.consensus_block_id())?;
+        if report.is_root_recoverable() {
+            return Ok(false);
+        }
+        let (genesis, genesis_qc, epoch) = if ledger_info.consensus_block_id() == *GENESIS_BLOCK_ID
+        {
+            (
+                Block::<T>::make_genesis_block(),
+                QuorumCert::certificate_for_genesis(),
+                0,
+            )
+        } else if ledger_info.next_validator_set().is_some() {
+            (
+                Block::<T>::make_genesis_block_from_ledger_info(ledger_info),
+                QuorumCert::certificate_for_genesis_from_ledger_info(ledger_info),
+                ledger_info.epoch_num() + 1,
+            )
+        } else {
+            bail!(
+                "The root {} can't be rebuilt: the ledger info committing it doesn't end epoch {}",
+                ledger_info.consensus_block_id(),
+                ledger_info.epoch_num()
+            );
+        };
+
+        let to_delete: Vec<_> = report
+            .tree()
+            .into_iter()
+            .chain(report.orphans())
+            .chain(
+                report
+                    .dangling_quorum_certs()
+                    .iter()
+                    .map(QuorumCert::certified_block_id),
+            )
+            .filter(|id| *id != genesis.id())
+            .collect();
+        if !to_delete.is_empty() {
+            self.db
+                .delete_blocks_and_quorum_certificates::<T>(to_delete)?;
+        }
+        self.db
+            .save_blocks_and_quorum_certificates(vec![genesis], vec![genesis_qc])?;
+        self.db.save_highest_timeout_certificates(to_vec_named(
+            &HighestTimeoutCertificates::default(),
+        )?)?;
+        self.db.delete_block_metadata_from(epoch)?;
+        Ok(true)
+    }
+}
+
+impl ConsensusDbReport {
+    /// Whether the block committed in the ledger is stored.
+    pub fn is_root_stored(&self) -> bool {
+        !self.tree.is_empty()
+    }
+
+    /// The ids of the committed root and its descendants, depth first.
+    pub fn tree(&self) -> Vec<HashValue> {
+        self.tree.iter().map(|block| block.id).collect()
+    }
+
+    /// The ids of the blocks that don't descend from the committed root, by round.
+    pub fn orphans(&self) -> Vec<HashValue> {
+        self.orphans.iter().map(|block| block.id).collect()
+    }
+
+    /// Whether consensus can recover from the committed root: the root must be stored along with
+    /// a quorum cert certifying it and one committing it.
+    pub fn is_root_recoverable(&self) -> bool {
+        self.is_root_stored() && self.root_certified && self.root_committed
+    }
+
+    /// The quorum certs whose certified block isn't stored.
+    pub fn dangling_quorum_certs(&self) -> &[QuorumCert] {
+        &self.dangling_quorum_certs
+    }
+}
+
+impl Display for ConsensusDbReport {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match &self.consensus_state {
+            Some(consensus_state) => writeln!(f, "{}", consensus_state)?,
+            None => writeln!(f, "ConsensusState: none")?,
+        }
+        match self.highest_timeout_round {
+            Some(round) => writeln!(f, "Highest timeout certificate: round {}", round)?,
+            None => writeln!(f, "Highest timeout certificate: none")?,
+        }
+        let certified_marker =
+            |block: &ReportedBlock| if block.certified { " (certified)" } else { "" };
+        if self.is_root_stored() {
+            writeln!(
+                f,
+                "Blocks descending from the committed root {}:",
+                self.committed_root
+            )?;
+        } else {
+            writeln!(
+                f,
+                "The committed root {} isn't stored.",
+                self.committed_root
+            )?;
+        }
+        if self.is_root_stored() && !self.is_root_recoverable() {
+            writeln!(
+                f,
+                "No quorum cert {} the committed root: consensus can't recover from it.",
+                if self.root_certified {
+                    "commits"
+                } else {
+                    "certifies"
+                }
+            )?;
+        }
+        for block in &self.tree {
+            writeln!(
+                f,
+                "{}{}{}",
+                "  ".repeat(block.depth + 1),
+                block.description,
+                certified_marker(block)
+            )?;
+        }
+        writeln!(
+            f,
+            "Blocks not descending from the committed root: {}",
+            self.orphans.len()
+        )?;
+        for block in &self.orphans {
+            writeln!(f, "  {}{}", block.description, certified_marker(block))?;
+        }
+        writeln!(
+            f,
+            "Quorum certs of blocks not stored: {}",
+            self.dangling_quorum_certs.len()
+        )?;
+        for qc in &self.dangling_quorum_certs {
+            writeln!(f, "  {}", qc)?;
+        }
+        writeln!(
+            f,
+            "Epochs with metadata of committed blocks: {}",
+            self.block_metadata.len()
+        )?;
+        for (epoch, first_round, last_round, count) in &self.block_metadata {
+            writeln!(
+                f,
+                "  epoch {}: {} blocks from round {} to {}",
+                epoch, count, first_round, last_round
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    consensus_types::{block::Block, quorum_cert::QuorumCert},
+    consensusdb::{inspector::ConsensusDbInspector, ConsensusDB},
+    liveness::leader_reputation::BlockMetadata,
+    test_utils::{epoch_ending_ledger_info, placeholder_certificate_for_block, TestPayload},
+};
+use crypto::HashValue;
+use tools::tempdir::TempPath;
+use types::{crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo};
+
+/// Stores genesis <- b1 <- b2 and genesis <- b3, along with the quorum certs of genesis, b1 and
+/// of a block that isn't stored. Returns the blocks.
+fn populate(db: &ConsensusDB) -> Vec<Block<TestPayload>> {
+    let signer = ValidatorSigner::random(None);
+    let genesis = Block::<TestPayload>::make_genesis_block();
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    let b1 = Block::make_block(&genesis, vec![1], 1, 1, genesis_qc.clone(), &signer);
+    let b1_qc = placeholder_certificate_for_block(
+        vec![&signer],
+        b1.id(),
+        b1.round(),
+        genesis.id(),
+        genesis.round(),
+        genesis.id(),
+        genesis.round(),
+    );
+    let b2 = Block::make_block(&b1, vec![2], 2, 2, b1_qc.clone(), &signer);
+    let b3 = Block::make_block(&genesis, vec![3], 3, 3, genesis_qc.clone(), &signer);
+    let dangling_qc = placeholder_certificate_for_block(
+        vec![&signer],
+        HashValue::random(),
+        4,
+        b2.id(),
+        b2.round(),
+        b1.id(),
+        b1.round(),
+    );
+    let blocks = vec![genesis, b1, b2, b3];
+    db.save_blocks_and_quorum_certificates(blocks.clone(), vec![genesis_qc, b1_qc, dangling_qc])
+        .unwrap();
+    blocks
+}
+
+#[test]
+fn test_inspect() {
+    let tmp_dir = TempPath::new();
+    let blocks = populate(&ConsensusDB::new(&tmp_dir));
+    let inspector = ConsensusDbInspector::open(&tmp_dir).unwrap();
+
+    let report = inspector.inspect::<TestPayload>(blocks[1].id()).unwrap();
+    assert!(report.is_root_stored());
+    assert_eq!(report.tree(), vec![blocks[1].id(), blocks[2].id()]);
+    assert_eq!(report.orphans(), vec![blocks[0].id(), blocks[3].id()]);
+    assert_eq!(report.dangling_quorum_certs().len(), 1);
+
+    // Everything descends from the genesis, depth first.
+    let report = inspector.inspect::<TestPayload>(blocks[0].id()).unwrap();
+    assert_eq!(
+        report.tree(),
+        blocks.iter().map(Block::id).collect::<Vec<_>>()
+    );
+    assert!(report.orphans().is_empty());
+
+    let report = inspector
+        .inspect::<TestPayload>(HashValue::random())
+        .unwrap();
+    assert!(!report.is_root_stored());
+    assert_eq!(report.orphans().len(), blocks.len());
+}
+
+#[test]
+fn test_prune_orphans() {
+    let tmp_dir = TempPath::new();
+    let blocks = populate(&ConsensusDB::new(&tmp_dir));
+    let inspector = ConsensusDbInspector::open(&tmp_dir).unwrap();
+
+    // Nothing is deleted if the committed root isn't stored.
+    assert!(inspector
+        .prune_orphans::<TestPayload>(HashValue::random())
+        .is_err());
+    assert_eq!(
+        inspector
+            .inspect::<TestPayload>(blocks[1].id())
+            .unwrap()
+            .orphans()
+            .len(),
+        2
+    );
+
+    let deleted = inspector
+        .prune_orphans::<TestPayload>(blocks[1].id())
+        .unwrap();
+    assert_eq!(deleted.len(), 3);
+    let report = inspector.inspect::<TestPayload>(blocks[1].id()).unwrap();
+    assert_eq!(report.tree(), vec![blocks[1].id(), blocks[2].id()]);
+    assert!(report.orphans().is_empty());
+    assert!(report.dangling_quorum_certs().is_empty());
+}
+
+#[test]
+fn test_open_missing_db() {
+    let tmp_dir = TempPath::new();
+    assert!(ConsensusDbInspector::open(&tmp_dir).is_err());
+    assert!(ConsensusDbInspector::open_readonly(&tmp_dir).is_err());
+    // Nothing was created along the way.
+    assert!(!tmp_dir.path().exists());
+}
+
+#[test]
+fn test_inspect_readonly() {
+    let tmp_dir = TempPath::new();
+    let blocks = populate(&ConsensusDB::new(&tmp_dir));
+    let inspector = ConsensusDbInspector::open_readonly(&tmp_dir).unwrap();
+
+    let report = inspector.inspect::<TestPayload>(blocks[1].id()).unwrap();
+    assert_eq!(report.tree(), vec![blocks[1].id(), blocks[2].id()]);
+    assert!(inspector
+        .prune_orphans::<TestPayload>(blocks[1].id())
+        .is_err());
+}
+
+#[test]
+fn test_repair_root() {
+    let tmp_dir = TempPath::new();
+    let blocks = populate(&ConsensusDB::new(&tmp_dir));
+    let inspector = ConsensusDbInspector::open(&tmp_dir).unwrap();
+    let signer = ValidatorSigner::random(None);
+    for epoch in 0..2 {
+        inspector
+            .db
+            .save_block_metadata(epoch, &[BlockMetadata::new(1, None, vec![])])
+            .unwrap();
+    }
+
+    // Consensus recovers from the genesis, which a quorum cert certifies and commits.
+    let genesis_ledger_info = QuorumCert::certificate_for_genesis()
+        .ledger_info()
+        .ledger_info()
+        .clone();
+    assert!(inspector
+        .inspect::<TestPayload>(blocks[0].id())
+        .unwrap()
+        .is_root_recoverable());
+    assert!(!inspector
+        .repair_root::<TestPayload>(&genesis_ledger_info)
+        .unwrap());
+
+    // No quorum cert commits b1, whose root can't be rebuilt in the middle of an epoch.
+    assert!(!inspector
+        .inspect::<TestPayload>(blocks[1].id())
+        .unwrap()
+        .is_root_recoverable());
+    let ledger_info = LedgerInfo::new(
+        1,
+        HashValue::zero(),
+        HashValue::zero(),
+        blocks[1].id(),
+        0,
+        0,
+        None,
+    );
+    assert!(inspector.repair_root::<TestPayload>(&ledger_info).is_err());
+
+    // The root of a ledger info ending an epoch is the genesis of the next one.
+    let ledger_info = epoch_ending_ledger_info(vec![&signer], 0)
+        .ledger_info()
+        .clone();
+    assert!(inspector.repair_root::<TestPayload>(&ledger_info).unwrap());
+    let report = inspector
+        .inspect::<TestPayload>(ledger_info.consensus_block_id())
+        .unwrap();
+    assert!(report.is_root_recoverable());
+    assert_eq!(
+        report.tree(),
+        vec![Block::<TestPayload>::make_genesis_block_from_ledger_info(&ledger_info).id()]
+    );
+    assert!(report.orphans().is_empty());
+    assert!(report.dangling_quorum_certs().is_empty());
+    // Only the metadata of the epochs before the new one is left.
+    let block_metadata = inspector.db.get_all_block_metadata().unwrap();
+    assert_eq!(block_metadata.len(), 1);
+    assert_eq!(block_metadata[0].0, 0);
+}
@@ -3,23 +3,27 @@
 
 #[cfg(test)]
 mod consensusdb_test;
+pub mod inspector;
 mod schema;
 
 use crate::chained_bft::{
-    common::Payload,
+    common::{Payload, Round},
     consensus_types::{block::Block, quorum_cert::QuorumCert},
     consensusdb::schema::{
         block::BlockSchema,
+        block_metadata::BlockMetadataSchema,
         quorum_certificate::QCSchema,
         single_entry::{SingleEntryKey, SingleEntrySchema},
     },
+    liveness::leader_reputation::BlockMetadata,
 };
 use crypto::HashValue;
 use failure::prelude::*;
 use logger::prelude::*;
-use schema::{BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
+use schema::{BLOCK_CF_NAME, BLOCK_METADATA_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
 use schemadb::{
-    ColumnFamilyOptions, ColumnFamilyOptionsMap, ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME,
+    ColumnFamilyName, ColumnFamilyOptions, ColumnFamilyOptionsMap, ReadOptions, SchemaBatch, DB,
+    DEFAULT_CF_NAME,
 };
 use std::{collections::HashMap, iter::Iterator, path::Path, time::Instant};
 
@@ -32,22 +36,9 @@ pub struct ConsensusDB {
 
 impl ConsensusDB {
     pub fn new<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
-        let cf_opts_map: ColumnFamilyOptionsMap = [
-            (
-                /* UNUSED CF = */ DEFAULT_CF_NAME,
-                ColumnFamilyOptions::default(),
-            ),
-            (BLOCK_CF_NAME, ColumnFamilyOptions::default()),
-            (QC_CF_NAME, ColumnFamilyOptions::default()),
-            (SINGLE_ENTRY_CF_NAME, ColumnFamilyOptions::default()),
-        ]
-        .iter()
-        .cloned()
-        .collect();
-
         let path = db_root_path.as_ref().join("consensusdb");
         let instant = Instant::now();
-        let db = DB::open(path.clone(), cf_opts_map).unwrap_or_else(|e| {
+        let db = Self::open_db(&path).unwrap_or_else(|e| {
             panic!("ConsensusDB open failed due to {:?}, unable to continue", e)
         });
 
@@ -60,6 +51,45 @@ impl ConsensusDB {
         Self { db }
     }
 
+    /// Opens the ConsensusDB under the given directory, failing rather than creating it if it
+    /// doesn't exist, e.g. to repair the DB of a stopped validator.
+    pub fn open_existing<P: AsRef<Path>>(db_root_path: P) -> Result<Self> {
+        let path = db_root_path.as_ref().join("consensusdb");
+        ensure!(path.exists(), "ConsensusDB doesn't exist at {:?}.", path);
+        let db = Self::open_db(&path)?;
+        info!("Opened ConsensusDB at {:?}", path);
+
+        Ok(Self { db })
+    }
+
+    /// Opens an existing ConsensusDB in read-only mode, for offline inspection while the DB might
+    /// be in use by a running validator. Any attempt to write to it fails.
+    pub fn open_readonly<P: AsRef<Path>>(db_root_path: P) -> Result<Self> {
+        let path = db_root_path.as_ref().join("consensusdb");
+        let db = DB::open_readonly(&path, Self::column_families())?;
+        info!("Opened ConsensusDB at {:?} in read-only mode", path);
+
+        Ok(Self { db })
+    }
+
+    fn open_db(path: &Path) -> Result<DB> {
+        let cf_opts_map: ColumnFamilyOptionsMap = Self::column_families()
+            .into_iter()
+            .map(|cf_name| (cf_name, ColumnFamilyOptions::default()))
+            .collect();
+        DB::open(path, cf_opts_map)
+    }
+
+    fn column_families() -> Vec<ColumnFamilyName> {
+        vec![
+            /* UNUSED CF = */ DEFAULT_CF_NAME,
+            BLOCK_CF_NAME,
+            BLOCK_METADATA_CF_NAME,
+            QC_CF_NAME,
+            SINGLE_ENTRY_CF_NAME,
+        ]
+    }
+
     pub fn get_data<T: Payload>(
         &self,
     ) -> Result<(
@@ -143,6 +173,58 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    /// Saves the metadata of the given blocks committed in the given epoch.
+    pub fn save_block_metadata(&self, epoch: u64, block_metadata: &[BlockMetadata]) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        block_metadata
+            .iter()
+            .map(|metadata| batch.put::<BlockMetadataSchema>(&(epoch, metadata.round()), metadata))
+            .collect::<Result<()>>()?;
+        self.commit(batch)
+    }
+
+    /// Deletes the metadata of the blocks committed before the given round of the given epoch,
+    /// including the ones of the previous epochs.
+    pub fn delete_block_metadata_before(&self, epoch: u64, round: Round) -> Result<()> {
+        self.db
+            .range_delete::<BlockMetadataSchema, (u64, Round)>(&(0, 0), &(epoch, round))
+    }
+
+    /// Get the metadata of the committed blocks of the given epoch, by round.
+    pub fn get_block_metadata(&self, epoch: u64) -> Result<Vec<BlockMetadata>> {
+        let mut iter = self
+            .db
+            .iter::<BlockMetadataSchema>(ReadOptions::default())?;
+        iter.seek(&(epoch, 0))?;
+        let mut block_metadata = vec![];
+        for row in iter {
+            let ((metadata_epoch, _), metadata) = row?;
+            if metadata_epoch != epoch {
+                break;
+            }
+            block_metadata.push(metadata);
+        }
+        Ok(block_metadata)
+    }
+
+    /// Deletes the metadata of the blocks committed in the given epoch and the later ones.
+    pub fn delete_block_metadata_from(&self, epoch: u64) -> Result<()> {
+        self.db.range_delete::<BlockMetadataSchema, (u64, Round)>(
+            &(epoch, 0),
+            &(u64::max_value(), Round::max_value()),
+        )
+    }
+
+    /// Get the metadata of the committed blocks of all the epochs, by epoch and round.
+    pub fn get_all_block_metadata(&self) -> Result<Vec<(u64, BlockMetadata)>> {
+        let mut iter = self
+            .db
+            .iter::<BlockMetadataSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        iter.map(|row| row.map(|((epoch, _), metadata)| (epoch, metadata)))
+            .collect()
+    }
+
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support.
     fn commit(&self, batch: SchemaBatch) -> Result<()> {
@@ -0,0 +1,59 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the metadata of the committed blocks that
+//! leader reputation is based on.
+//!
+//! Serialized block metadata identified by the epoch and the round of the block.
+//! ```text
+//! |<-----key----->|<---value---->|
+//! | epoch | round | block metadata |
+//! ```
+
+use super::{ensure_slice_len_eq, BLOCK_METADATA_CF_NAME};
+use crate::chained_bft::{common::Round, liveness::leader_reputation::BlockMetadata};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use failure::prelude::*;
+use rmp_serde::{from_slice, to_vec_named};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    BlockMetadataSchema,
+    (u64, Round),
+    BlockMetadata,
+    BLOCK_METADATA_CF_NAME
+);
+
+impl KeyCodec<BlockMetadataSchema> for (u64, Round) {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let mut encoded = Vec::with_capacity(2 * size_of::<u64>());
+        encoded.write_u64::<BigEndian>(self.0)?;
+        encoded.write_u64::<BigEndian>(self.1)?;
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 2 * size_of::<u64>())?;
+        let mut data = data;
+        let epoch = data.read_u64::<BigEndian>()?;
+        let round = data.read_u64::<BigEndian>()?;
+        Ok((epoch, round))
+    }
+}
+
+impl ValueCodec<BlockMetadataSchema> for BlockMetadata {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(to_vec_named(self)?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(from_slice(data)?)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,15 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use schemadb::schema::assert_encode_decode;
+use types::account_address::AccountAddress;
+
+#[test]
+fn test_encode_decode() {
+    let author = AccountAddress::random();
+    assert_encode_decode::<BlockMetadataSchema>(
+        &(1, 2),
+        &BlockMetadata::new(2, Some(author), vec![author]),
+    );
+}
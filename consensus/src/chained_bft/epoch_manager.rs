@@ -1,25 +1,41 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::{Arc, RwLock};
-use types::crypto_proxies::ValidatorVerifier;
+use crate::counters;
+use failure::prelude::*;
+use logger::prelude::*;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, RwLock,
+};
+use types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+};
 
 /// Manages the current epoch and validator set to provide quorum size/voting power and signature
 /// verification.
 pub struct EpochManager {
-    #[allow(dead_code)]
-    epoch: usize,
+    epoch: AtomicUsize,
     validators: RwLock<Arc<ValidatorVerifier>>,
+    // The ledger info ending the previous epoch, which the genesis of the current epoch is derived
+    // from. `None` in the first epoch.
+    epoch_ending_ledger_info: RwLock<Option<Arc<LedgerInfo>>>,
 }
 
 impl EpochManager {
     pub fn new(epoch: usize, validators: ValidatorVerifier) -> Self {
         Self {
-            epoch,
+            epoch: AtomicUsize::new(epoch),
             validators: RwLock::new(Arc::new(validators)),
+            epoch_ending_ledger_info: RwLock::new(None),
         }
     }
 
+    pub fn epoch(&self) -> usize {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
     pub fn quorum_size(&self) -> usize {
         self.validators.read().unwrap().quorum_size()
     }
@@ -27,4 +43,77 @@ impl EpochManager {
     pub fn validators(&self) -> Arc<ValidatorVerifier> {
         Arc::clone(&self.validators.read().unwrap())
     }
+
+    /// The ledger info ending the previous epoch, which pins the genesis block and its quorum cert
+    /// in the current epoch (see `Block::make_genesis_block_from_ledger_info`).
+    pub fn epoch_ending_ledger_info(&self) -> Option<Arc<LedgerInfo>> {
+        self.epoch_ending_ledger_info.read().unwrap().clone()
+    }
+
+    /// Verifies the given ledger infos ending consecutive epochs, each of which must be signed by
+    /// the validators of the epoch it ends, starting with the current epoch. Returns the ledger
+    /// infos ending the current epoch and the following ones: the ones ending the previous epochs
+    /// are skipped.
+    pub fn verify_epoch_change_proof(
+        &self,
+        epoch_change_proof: &[LedgerInfoWithSignatures],
+    ) -> Result<Vec<LedgerInfoWithSignatures>> {
+        let mut validators = self.validators();
+        let mut epoch = self.epoch() as u64;
+        let mut verified = vec![];
+        for ledger_info in epoch_change_proof {
+            let ledger_info_epoch = ledger_info.ledger_info().epoch_num();
+            if ledger_info_epoch < epoch {
+                continue;
+            }
+            ensure!(
+                ledger_info_epoch == epoch,
+                "Missing the ledger infos ending the epochs {} to {}",
+                epoch,
+                ledger_info_epoch - 1
+            );
+            // A nominal ledger info verifies without any signature.
+            ensure!(
+                !ledger_info.ledger_info().is_zero(),
+                "Nominal ledger info can't end epoch {}",
+                epoch
+            );
+            ledger_info
+                .verify(validators.as_ref())
+                .map_err(|e| format_err!("Invalid ledger info ending epoch {}: {}", epoch, e))?;
+            let next_validator_set = ledger_info
+                .ledger_info()
+                .next_validator_set()
+                .ok_or_else(|| format_err!("Ledger info doesn't end epoch {}", epoch))?;
+            validators = Arc::new(validators.for_next_epoch(next_validator_set)?);
+            epoch += 1;
+            verified.push(ledger_info.clone());
+        }
+        Ok(verified)
+    }
+
+    /// Moves to the epoch following the one ended by the given ledger info, whose validators are
+    /// the next validator set carried by the ledger info.
+    pub fn start_new_epoch(&self, ledger_info: &LedgerInfo) {
+        let validator_set = ledger_info
+            .next_validator_set()
+            .expect("Ledger info doesn't end an epoch.");
+        let validators = self
+            .validators()
+            .for_next_epoch(validator_set)
+            .expect("Invalid validators of the next epoch");
+        let epoch = ledger_info.epoch_num() as usize + 1;
+        counters::EPOCH_NUM.set(epoch as i64);
+        counters::CURRENT_EPOCH_NUM_VALIDATORS.set(validators.len() as i64);
+        counters::CURRENT_EPOCH_QUORUM_SIZE.set(validators.quorum_size() as i64);
+        info!(
+            "Starting epoch {} with validators {} (quorum size {})",
+            epoch,
+            validator_set,
+            validators.quorum_size()
+        );
+        *self.validators.write().unwrap() = Arc::new(validators);
+        *self.epoch_ending_ledger_info.write().unwrap() = Some(Arc::new(ledger_info.clone()));
+        self.epoch.store(epoch, Ordering::SeqCst);
+    }
 }
@@ -11,22 +11,24 @@ use crate::{
         common::{Author, Payload, Round},
         consensus_types::{
             block::Block,
+            commit_vote::CommitVote,
             proposal_msg::ProposalMsg,
             quorum_cert::QuorumCert,
             sync_info::SyncInfo,
-            timeout_msg::{PacemakerTimeout, PacemakerTimeoutCertificate, TimeoutMsg},
-            vote_data::VoteData,
+            timeout_msg::{PacemakerTimeoutCertificate, TimeoutMsg},
             vote_msg::VoteMsg,
         },
         epoch_manager::EpochManager,
+        execution_pipeline::{ExecutedBlocks, ExecutionPipeline, OrderedBlocks},
         liveness::{
             pacemaker::{NewRoundEvent, NewRoundReason, Pacemaker},
-            proposal_generator::{ProposalGenerationError, ProposalGenerator},
+            proposal_generator::ProposalGenerator,
             proposer_election::ProposerElection,
         },
         network::{BlockRetrievalRequest, BlockRetrievalResponse, ConsensusNetworkImpl},
+        observer::ConsensusPublisher,
         persistent_storage::PersistentStorage,
-        safety::safety_rules::SafetyRules,
+        safety::{safety_rules::SafetyRules, t_safety_rules::TSafetyRules},
         sync_manager::{SyncManager, SyncMgrContext},
     },
     counters,
@@ -35,15 +37,17 @@ use crate::{
         duration_since_epoch, wait_if_possible, TimeService, WaitingError, WaitingSuccess,
     },
 };
+use crypto::{hash::CryptoHash, HashValue};
+use futures::channel::mpsc;
 use logger::prelude::*;
 use mirai_annotations::{
     debug_checked_precondition, debug_checked_precondition_eq, debug_checked_verify,
     debug_checked_verify_eq,
 };
 use network::proto::BlockRetrievalStatus;
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use termion::color::*;
-use types::crypto_proxies::LedgerInfoWithSignatures;
+use types::{crypto_proxies::LedgerInfoWithSignatures, ledger_info::LedgerInfo};
 
 #[cfg(test)]
 #[path = "event_processor_test.rs"]
@@ -64,17 +68,31 @@ pub struct EventProcessor<T> {
     pacemaker: Pacemaker,
     proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
     proposal_generator: ProposalGenerator<T>,
-    safety_rules: SafetyRules,
+    safety_rules: Box<dyn TSafetyRules<T> + Send + Sync>,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
     txn_manager: Arc<dyn TxnManager<Payload = T>>,
     network: ConsensusNetworkImpl,
-    storage: Arc<dyn PersistentStorage<T>>,
     sync_manager: SyncManager<T>,
     time_service: Arc<dyn TimeService>,
     enforce_increasing_timestamps: bool,
     // Cache of the last sent vote message.
     last_vote_sent: Option<(VoteMsg, Round)>,
     epoch_mgr: Arc<EpochManager>,
+    // Set once a committed block ends the current epoch, along with the ledger infos ending the
+    // following epochs if the validator catches up with a peer in a later epoch: the event
+    // processor must then be replaced by a new one for the epoch they lead to.
+    epoch_change_proof: Vec<LedgerInfoWithSignatures>,
+    // Set in the decoupled execution mode: the pipeline executing the blocks and the sender of
+    // the blocks to execute once they're ordered.
+    execution_pipeline: Option<(
+        Arc<ExecutionPipeline<T>>,
+        mpsc::UnboundedSender<OrderedBlocks<T>>,
+    )>,
+    // The round of the last block handed over to the execution pipeline.
+    last_ordered_round: Round,
+    // The latest commit vote of each validator for a block above the root: the validators vote
+    // for the blocks in the order they execute them.
+    pending_commit_votes: HashMap<Author, CommitVote>,
 }
 
 impl<T: Payload> EventProcessor<T> {
@@ -84,7 +102,7 @@ impl<T: Payload> EventProcessor<T> {
         pacemaker: Pacemaker,
         proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
         proposal_generator: ProposalGenerator<T>,
-        safety_rules: SafetyRules,
+        safety_rules: Box<dyn TSafetyRules<T> + Send + Sync>,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         txn_manager: Arc<dyn TxnManager<Payload = T>>,
         network: ConsensusNetworkImpl,
@@ -93,9 +111,10 @@ impl<T: Payload> EventProcessor<T> {
         enforce_increasing_timestamps: bool,
         epoch_mgr: Arc<EpochManager>,
     ) -> Self {
+        let last_ordered_round = block_store.root().round();
         let sync_manager = SyncManager::new(
             Arc::clone(&block_store),
-            Arc::clone(&storage),
+            storage,
             network.clone(),
             Arc::clone(&state_computer),
         );
@@ -109,15 +128,29 @@ impl<T: Payload> EventProcessor<T> {
             state_computer,
             txn_manager,
             network,
-            storage,
             sync_manager,
             time_service,
             enforce_increasing_timestamps,
             last_vote_sent: None,
             epoch_mgr,
+            epoch_change_proof: vec![],
+            execution_pipeline: None,
+            last_ordered_round,
+            pending_commit_votes: HashMap::new(),
         }
     }
 
+    /// Turns on the decoupled execution mode: the quorum certs only order the blocks, which are
+    /// then executed by the given pipeline and committed by a quorum of commit votes.
+    pub fn with_execution_pipeline(
+        mut self,
+        execution_pipeline: Arc<ExecutionPipeline<T>>,
+        ordered_blocks_tx: mpsc::UnboundedSender<OrderedBlocks<T>>,
+    ) -> Self {
+        self.execution_pipeline = Some((execution_pipeline, ordered_blocks_tx));
+        self
+    }
+
     /// Leader:
     ///
     /// This event is triggered by a new quorum certificate at the previous round or a
@@ -130,7 +163,7 @@ impl<T: Payload> EventProcessor<T> {
     /// Replica:
     ///
     /// Do nothing
-    async fn process_new_round_event(&self, new_round_event: NewRoundEvent) {
+    async fn process_new_round_event(&mut self, new_round_event: NewRoundEvent) {
         debug!("Processing {}", new_round_event);
         counters::CURRENT_ROUND.set(new_round_event.round as i64);
         counters::ROUND_TIMEOUT_MS.set(new_round_event.timeout.as_millis() as i64);
@@ -142,9 +175,11 @@ impl<T: Payload> EventProcessor<T> {
                 counters::TIMEOUT_ROUNDS_COUNT.inc();
             }
         };
+        // The proposal is going to extend the highest certified block.
+        let hqc_block = self.block_store.highest_certified_block();
         if self
             .proposer_election
-            .is_valid_proposer(self.author, new_round_event.round)
+            .is_valid_proposer_extending(self.author, new_round_event.round, hqc_block.as_ref())
             .is_none()
         {
             return;
@@ -162,9 +197,9 @@ impl<T: Payload> EventProcessor<T> {
     }
 
     async fn generate_proposal(
-        &self,
+        &mut self,
         new_round_event: NewRoundEvent,
-    ) -> Result<ProposalMsg<T>, ProposalGenerationError> {
+    ) -> failure::Result<ProposalMsg<T>> {
         // Proposal generator will ensure that at most one proposal is generated per round
         let proposal = self
             .proposal_generator
@@ -173,6 +208,8 @@ impl<T: Payload> EventProcessor<T> {
                 self.pacemaker.current_round_deadline(),
             )
             .await?;
+        // The safety rules hold the signing key and refuse to sign an equivocating proposal.
+        let proposal = self.safety_rules.sign_proposal(proposal)?;
         debug!("Propose {}", proposal);
         // should we include a TC?
         let timeout_certificate = match &new_round_event.reason {
@@ -227,6 +264,16 @@ impl<T: Payload> EventProcessor<T> {
             );
             return None;
         }
+        if let Err(e) = self
+            .proposer_election
+            .verify_proposal(proposal_msg.proposal())
+        {
+            warn!(
+                "Proposal {} is rejected by the proposer election: {:?}",
+                proposal_msg, e
+            );
+            return None;
+        }
         if let Err(e) = self
             .sync_up(proposal_msg.sync_info(), proposal_msg.proposer(), true)
             .await
@@ -237,6 +284,10 @@ impl<T: Payload> EventProcessor<T> {
             );
             return None;
         }
+        // There is nothing left to do in this epoch if syncing up ended it.
+        if !self.epoch_change_proof.is_empty() {
+            return None;
+        }
 
         // pacemaker may catch up with the SyncInfo, check again
         let current_round = self.pacemaker.current_round();
@@ -250,6 +301,27 @@ impl<T: Payload> EventProcessor<T> {
             return None;
         }
 
+        // The proposer may depend on the parent, which is only known after syncing up.
+        let parent = self
+            .block_store
+            .get_block(proposal_msg.proposal().parent_id())?;
+        if self
+            .proposer_election
+            .is_valid_proposer_extending(
+                proposal_msg.proposer(),
+                proposal_msg.round(),
+                parent.as_ref(),
+            )
+            .is_none()
+        {
+            warn!(
+                "Proposer {} for block {} is not a valid proposer for this round and parent",
+                proposal_msg.proposer(),
+                proposal_msg.proposal()
+            );
+            return None;
+        }
+
         self.proposer_election
             .process_proposal(proposal_msg.take_proposal())
     }
@@ -274,6 +346,10 @@ impl<T: Payload> EventProcessor<T> {
             warn!("Stop timeout msg processing because of sync up error.");
             return;
         };
+        // There is nothing left to do in this epoch if syncing up ended it.
+        if !self.epoch_change_proof.is_empty() {
+            return;
+        }
         if let Some(vote) = timeout_msg.pacemaker_timeout().vote_msg() {
             self.add_vote(vote.clone(), self.epoch_mgr.quorum_size())
                 .await;
@@ -322,6 +398,20 @@ impl<T: Payload> EventProcessor<T> {
         }
     }
 
+    /// Sends the current sync info, which ends the epoch, to the other validators of the epoch.
+    async fn broadcast_epoch_ending_sync_info(&self) {
+        let sync_info = SyncInfo::new(
+            self.block_store.highest_quorum_cert().as_ref().clone(),
+            self.block_store.highest_ledger_info().as_ref().clone(),
+            self.pacemaker.highest_timeout_certificate(),
+        );
+        for peer in self.epoch_mgr.validators().get_ordered_account_addresses() {
+            if peer != self.author {
+                self.network.send_sync_info(sync_info.clone(), peer).await;
+            }
+        }
+    }
+
     /// The function makes sure that it brings the missing dependencies from the QC and LedgerInfo
     /// of the given sync info and update the pacemaker with the certificates if succeed.
     /// Returns Error in case sync mgr failed to bring the missing dependencies.
@@ -332,6 +422,22 @@ impl<T: Payload> EventProcessor<T> {
         author: Author,
         help_remote: bool,
     ) -> failure::Result<()> {
+        // The certificates of another epoch can't be added to the block tree of this one: the
+        // network hands the messages of other epochs over to `process_different_epoch` instead.
+        let epoch = self.epoch_mgr.epoch() as u64;
+        let sync_info_epoch = sync_info
+            .highest_quorum_cert()
+            .ledger_info()
+            .ledger_info()
+            .epoch_num();
+        if sync_info_epoch != epoch {
+            return Err(format_err!(
+                "Sync info of epoch {} from {} in epoch {}",
+                sync_info_epoch,
+                author.short_str(),
+                epoch
+            ));
+        }
         if help_remote {
             self.help_remote_if_stale(author, sync_info.highest_round(), sync_info.hqc_round())
                 .await;
@@ -362,6 +468,23 @@ impl<T: Payload> EventProcessor<T> {
                     e
                 })?;
             debug!("Caught up to HQC at round {}", sync_info.hqc_round());
+            // The state sync may have gone through the end of the epoch, in which case the block
+            // ending it is the new root rather than being committed by `process_certificates`.
+            let root_id = self.block_store.root().id();
+            let highest_ledger_info = self.block_store.highest_ledger_info();
+            let ledger_info = highest_ledger_info.ledger_info().ledger_info();
+            if ledger_info.next_validator_set().is_some()
+                && ledger_info.consensus_block_id() == root_id
+            {
+                info!(
+                    "Epoch {} ended by the sync to {}",
+                    ledger_info.epoch_num(),
+                    root_id
+                );
+                self.epoch_change_proof
+                    .push(highest_ledger_info.ledger_info().clone());
+                return Ok(());
+            }
         }
 
         self.process_certificates(
@@ -372,6 +495,94 @@ impl<T: Payload> EventProcessor<T> {
         Ok(())
     }
 
+    /// Brings a peer in another epoch up to date with the epochs: a peer in an earlier epoch is
+    /// sent the ledger infos ending its epoch and the following ones, out of the given ones ending
+    /// all the epochs so far, and a peer in a later epoch is asked for the ones ending the current
+    /// epoch and the following ones.
+    pub async fn process_different_epoch(
+        &self,
+        remote_epoch: u64,
+        peer: Author,
+        epoch_change_proof: &[LedgerInfoWithSignatures],
+    ) {
+        let epoch = self.epoch_mgr.epoch() as u64;
+        if remote_epoch < epoch {
+            let remote_epoch_change_proof: Vec<_> = epoch_change_proof
+                .iter()
+                .filter(|ledger_info| ledger_info.ledger_info().epoch_num() >= remote_epoch)
+                .cloned()
+                .collect();
+            if remote_epoch_change_proof.is_empty() {
+                warn!(
+                    "No ledger info ending epoch {} for {}",
+                    remote_epoch,
+                    peer.short_str()
+                );
+                return;
+            }
+            debug!(
+                "Peer {} is in epoch {}, sending it the ledger infos ending the epochs up to {}",
+                peer.short_str(),
+                remote_epoch,
+                epoch
+            );
+            self.network
+                .send_epoch_change_proof(remote_epoch_change_proof, peer)
+                .await;
+        } else if remote_epoch > epoch {
+            debug!(
+                "Peer {} is in epoch {}, requesting the ledger infos ending the epochs from {}",
+                peer.short_str(),
+                remote_epoch,
+                epoch
+            );
+            self.network.request_epoch(epoch, peer).await;
+        }
+    }
+
+    /// Catches up with the epochs that a peer in a later epoch proves ended, starting with the
+    /// current one: the state is synced to the end of the last of them, which the event processor
+    /// of the next epoch starts from.
+    pub async fn process_epoch_change_proof(
+        &mut self,
+        epoch_change_proof: Vec<LedgerInfoWithSignatures>,
+        peer: Author,
+    ) {
+        let epoch_change_proof = match self
+            .epoch_mgr
+            .verify_epoch_change_proof(&epoch_change_proof)
+        {
+            Ok(epoch_change_proof) => epoch_change_proof,
+            Err(e) => {
+                warn!(
+                    "Invalid epoch change proof from {}: {:?}",
+                    peer.short_str(),
+                    e
+                );
+                return;
+            }
+        };
+        let epoch_ending_ledger_info = match epoch_change_proof.last() {
+            Some(ledger_info) => ledger_info.clone(),
+            None => return,
+        };
+        let next_epoch = epoch_ending_ledger_info.ledger_info().epoch_num() + 1;
+        if let Err(e) = self
+            .sync_manager
+            .sync_to_epoch_ending(epoch_ending_ledger_info)
+            .await
+        {
+            warn!("Fail to catch up with epoch {}: {:?}", next_epoch, e);
+            return;
+        }
+        info!(
+            "Caught up with epoch {} from {}",
+            next_epoch,
+            peer.short_str()
+        );
+        self.epoch_change_proof = epoch_change_proof;
+    }
+
     /// Process the SyncInfo sent by peers to catch up to latest state.
     pub async fn process_sync_info_msg(&mut self, sync_info: SyncInfo, peer: Author) {
         debug!("Received a sync info msg: {}", sync_info);
@@ -390,7 +601,13 @@ impl<T: Payload> EventProcessor<T> {
         if !self.pacemaker.process_local_timeout(round) {
             return;
         }
-        let last_vote_round = self.safety_rules.consensus_state().last_vote_round();
+        let last_vote_round = match self.safety_rules.consensus_state() {
+            Ok(consensus_state) => consensus_state.last_vote_round(),
+            Err(e) => {
+                error!("Failed to get the consensus state due to {:?}", e);
+                return;
+            }
+        };
         warn!(
             "Round {} timed out: {}, expected round proposer was {:?}, broadcasting new round to all replicas",
             round,
@@ -414,28 +631,28 @@ impl<T: Payload> EventProcessor<T> {
             }
         };
 
-        // Stop voting at this round, persist the consensus state to support restarting from
-        // a recent round (i.e. > the last vote round)  and then send the SyncInfo
-        let consensus_state = self.safety_rules.increase_last_vote_round(round);
-
-        if let Some(consensus_state) = consensus_state {
-            if let Err(e) = self.storage.save_consensus_state(consensus_state) {
-                error!("Failed to persist consensus state after increasing the last vote round due to {:?}", e);
+        // Stop voting at this round: the safety rules persist the consensus state to support
+        // restarting from a recent round (i.e. > the last vote round) before signing the timeout,
+        // which is sent along with the SyncInfo
+        let sync_info = SyncInfo::new(
+            self.block_store.highest_quorum_cert().as_ref().clone(),
+            self.block_store.highest_ledger_info().as_ref().clone(),
+            self.pacemaker.highest_timeout_certificate(),
+        );
+        let timeout_msg = match self
+            .safety_rules
+            .sign_timeout(sync_info, round, vote_msg_to_attach)
+        {
+            Ok(timeout_msg) => timeout_msg,
+            Err(e) => {
+                error!(
+                    "Failed to sign the timeout of round {} due to {:?}",
+                    round, e
+                );
                 return;
             }
-        }
-
-        self.network
-            .broadcast_timeout_msg(TimeoutMsg::new(
-                SyncInfo::new(
-                    self.block_store.highest_quorum_cert().as_ref().clone(),
-                    self.block_store.highest_ledger_info().as_ref().clone(),
-                    self.pacemaker.highest_timeout_certificate(),
-                ),
-                PacemakerTimeout::new(round, self.block_store.signer(), vote_msg_to_attach),
-                self.block_store.signer(),
-            ))
-            .await;
+        };
+        self.network.broadcast_timeout_msg(timeout_msg).await;
     }
 
     async fn gen_backup_vote(&mut self, round: Round) -> failure::Result<VoteMsg> {
@@ -465,7 +682,12 @@ impl<T: Payload> EventProcessor<T> {
         qc: &QuorumCert,
         tc: Option<&PacemakerTimeoutCertificate>,
     ) {
-        self.safety_rules.update(qc);
+        if let Err(e) = self.safety_rules.update(qc) {
+            error!(
+                "Failed to update the safety rules with {} due to {:?}",
+                qc, e
+            );
+        }
 
         let mut highest_committed_proposal_round = None;
         if let Some(block) = qc
@@ -476,8 +698,18 @@ impl<T: Payload> EventProcessor<T> {
             if !block.is_nil_block() {
                 highest_committed_proposal_round = Some(block.round());
             }
-            let finality_proof = qc.ledger_info().clone();
-            self.process_commit(block, finality_proof).await;
+            if self.execution_pipeline.is_some() {
+                self.process_ordered(block);
+            } else {
+                let finality_proof = qc.ledger_info().clone();
+                self.process_commit(block, finality_proof).await;
+            }
+            // There is nothing left to do in this epoch, except for handing the QC ending it to
+            // the validators which may not have it, e.g. if they sent their votes to this one.
+            if !self.epoch_change_proof.is_empty() {
+                self.broadcast_epoch_ending_sync_info().await;
+                return;
+            }
         }
 
         if let Some(new_round_event) = self.pacemaker.process_certificates(
@@ -534,24 +766,30 @@ impl<T: Payload> EventProcessor<T> {
         // Safety invariant: The last voted round is updated to be the same as the proposed block's
         // round. At this point, the replica has decided to vote for the proposed block.
         debug_checked_verify_eq!(
-            self.safety_rules.consensus_state().last_vote_round(),
-            proposal_round
+            self.safety_rules
+                .consensus_state()
+                .ok()
+                .map(|consensus_state| consensus_state.last_vote_round()),
+            Some(proposal_round)
         );
         // Safety invariant: qc_parent <-- qc
         // the preferred block round must be at least as large as qc_parent's round.
-        debug_checked_verify!(
-            (*self)
-                .safety_rules
-                .consensus_state()
-                .preferred_block_round()
+        debug_checked_verify!(self.safety_rules.consensus_state().ok().map_or(
+            false,
+            |consensus_state| consensus_state.preferred_block_round()
                 >= certified_parent_block_round
-        );
+        ));
 
         self.last_vote_sent
             .replace((vote_msg.clone(), proposal_round));
-        let recipients = self
-            .proposer_election
-            .get_valid_proposers(proposal_round + 1);
+        let recipients = match self.block_store.get_block(proposal_id) {
+            Some(block) => self
+                .proposer_election
+                .get_valid_proposers_extending(proposal_round + 1, block.as_ref()),
+            None => self
+                .proposer_election
+                .get_valid_proposers(proposal_round + 1),
+        };
         debug!("{}Voted: {} {}", Fg(Green), Fg(Reset), vote_msg);
 
         // Safety invariant: The parent block must be present in the block store and the replica
@@ -622,9 +860,8 @@ impl<T: Payload> EventProcessor<T> {
 
     /// The function generates a VoteMsg for a given proposed_block:
     /// * first execute the block and add it to the block store
-    /// * then verify the voting rules
-    /// * save the updated state to consensus DB
-    /// * return a VoteMsg with the LedgerInfo to be committed in case the vote gathers QC.
+    /// * then ask the safety rules to verify the voting rules, persist their updated state and sign
+    ///   a VoteMsg with the LedgerInfo to be committed in case the vote gathers QC.
     ///
     /// This function assumes that it might be called from different tasks concurrently.
     async fn execute_and_vote(&mut self, proposed_block: Block<T>) -> failure::Result<VoteMsg> {
@@ -650,42 +887,24 @@ impl<T: Payload> EventProcessor<T> {
         self.wait_before_vote_if_needed(block.timestamp_usecs())
             .await?;
 
-        let vote_info = self.safety_rules.voting_rule(&block).map_err(|e| {
-            debug!("{}Rejected{} {}: {:?}", Fg(Red), Fg(Reset), block, e);
-            e
-        })?;
-        self.storage
-            .save_consensus_state(vote_info.consensus_state().clone())
-            .map_err(|e| {
-                debug!("Fail to persist consensus state: {:?}", e);
-                e
-            })?;
-
-        let proposal_id = vote_info.proposal_id();
         let executed_state_id = self
             .block_store
-            .get_compute_result(proposal_id)
+            .get_compute_result(block.id())
             .expect("Block proposed_block: no execution state found for inserted block.")
             .executed_state
             .state_id;
 
+        let potential_commit_id =
+            SafetyRules::commit_rule_for_certified_block(block.quorum_cert(), block.round());
         let ledger_info_placeholder = self
             .block_store
-            .ledger_info_placeholder(vote_info.potential_commit_id());
-        Ok(VoteMsg::new(
-            VoteData::new(
-                proposal_id,
-                executed_state_id,
-                block.round(),
-                vote_info.parent_block_id(),
-                vote_info.parent_block_round(),
-                vote_info.grandparent_block_id(),
-                vote_info.grandparent_block_round(),
-            ),
-            self.author,
-            ledger_info_placeholder,
-            self.block_store.signer(),
-        ))
+            .ledger_info_placeholder(potential_commit_id, self.epoch_mgr.epoch() as u64);
+        self.safety_rules
+            .construct_and_sign_vote(&block, executed_state_id, ledger_info_placeholder)
+            .map_err(|e| {
+                debug!("{}Rejected{} {}: {:?}", Fg(Red), Fg(Reset), block, e);
+                e
+            })
     }
 
     /// Upon new vote:
@@ -696,11 +915,19 @@ impl<T: Payload> EventProcessor<T> {
     pub async fn process_vote(&mut self, vote: VoteMsg) {
         // Check whether this validator is a valid recipient of the vote.
         let next_round = vote.block_round() + 1;
-        if self
-            .proposer_election
-            .is_valid_proposer(self.author, next_round)
-            .is_none()
-        {
+        let valid_proposer = match self.block_store.get_block(vote.block_id()) {
+            Some(block) => self.proposer_election.is_valid_proposer_extending(
+                self.author,
+                next_round,
+                block.as_ref(),
+            ),
+            // The proposers of the next round may depend on the voted block, which we don't know
+            // yet.
+            None => self
+                .proposer_election
+                .is_valid_proposer(self.author, next_round),
+        };
+        if valid_proposer.is_none() {
             debug!(
                 "Received {}, but I am not a valid proposer for round {}, ignore.",
                 vote, next_round
@@ -727,7 +954,16 @@ impl<T: Payload> EventProcessor<T> {
     async fn add_vote(&mut self, vote: VoteMsg, quorum_size: usize) -> Option<Arc<QuorumCert>> {
         let deadline = self.pacemaker.current_round_deadline();
         let preferred_peer = vote.author();
-        // TODO [Reconfiguration] Verify epoch of the vote message.
+        let epoch = self.epoch_mgr.epoch() as u64;
+        if vote.ledger_info().epoch_num() != epoch {
+            debug!(
+                "Ignoring vote of epoch {} in epoch {}: {}",
+                vote.ledger_info().epoch_num(),
+                epoch,
+                vote
+            );
+            return None;
+        }
         // Add the vote and check whether it completes a new QC.
         if let VoteReceptionResult::NewQuorumCertificate(qc) =
             self.block_store.insert_vote(vote, quorum_size)
@@ -754,6 +990,185 @@ impl<T: Payload> EventProcessor<T> {
         None
     }
 
+    /// In the decoupled execution mode, the blocks that a quorum cert would commit are only
+    /// ordered: hand them over to the execution pipeline.
+    fn process_ordered(&mut self, ordered_block: Arc<Block<T>>) {
+        let ordered_blocks_tx = match &self.execution_pipeline {
+            Some((_, ordered_blocks_tx)) => ordered_blocks_tx,
+            None => return,
+        };
+        // The blocks up to the root are ordered already, e.g., if the validator synced up.
+        let last_ordered_round =
+            std::cmp::max(self.last_ordered_round, self.block_store.root().round());
+        if ordered_block.round() <= last_ordered_round {
+            return;
+        }
+        let mut blocks = match self.block_store.path_from_root(Arc::clone(&ordered_block)) {
+            Some(blocks) => blocks,
+            None => return,
+        };
+        blocks.retain(|block| block.round() > last_ordered_round);
+        blocks.reverse();
+        debug!("{}Ordered{} {}", Fg(Blue), Fg(Reset), *ordered_block);
+        self.last_ordered_round = ordered_block.round();
+        if ordered_blocks_tx
+            .unbounded_send(OrderedBlocks { blocks })
+            .is_err()
+        {
+            error!("Failed to send the ordered blocks: the execution pipeline is gone");
+        }
+    }
+
+    /// In the decoupled execution mode, signs the ledger info of the last block that a quorum
+    /// cert ordered along with the state that executing it led to, and broadcasts the commit vote.
+    /// If the blocks failed to execute, they're ordered again from the root along with the next
+    /// ones, so that their execution is retried then.
+    pub async fn process_executed_blocks(
+        &mut self,
+        executed_blocks: failure::Result<ExecutedBlocks<T>>,
+    ) {
+        let executed_blocks = match executed_blocks {
+            Ok(executed_blocks) => executed_blocks,
+            Err(e) => {
+                error!("{:?}", e);
+                self.last_ordered_round = self.block_store.root().round();
+                return;
+            }
+        };
+        let (block, compute_result) = match executed_blocks.blocks.last() {
+            Some(last) => last,
+            None => return,
+        };
+        if block.round() <= self.block_store.root().round() {
+            return;
+        }
+        let executed_state = &compute_result.executed_state;
+        let ledger_info = LedgerInfo::new(
+            executed_state.version,
+            executed_state.state_id,
+            HashValue::zero(),
+            block.id(),
+            self.epoch_mgr.epoch() as u64,
+            block.timestamp_usecs(),
+            executed_state.validators.clone(),
+        );
+        let commit_vote = match self.safety_rules.sign_commit_vote(ledger_info) {
+            Ok(commit_vote) => commit_vote,
+            Err(e) => {
+                error!("Failed to sign the commit vote for {}: {:?}", block, e);
+                return;
+            }
+        };
+        debug!("Sending {}", commit_vote);
+        let mut network = self.network.clone();
+        network.broadcast_commit_vote(commit_vote).await;
+    }
+
+    /// In the decoupled execution mode, aggregates the commit votes: the block is committed once
+    /// a quorum of validators signed the same ledger info for it and the validator executed it
+    /// to the same state too. If the validator misses the block or its execution, the ledger info
+    /// is the finality proof the validator fast forwards to when it syncs up.
+    pub async fn process_commit_vote(&mut self, commit_vote: CommitVote) {
+        let execution_pipeline = match &self.execution_pipeline {
+            Some((execution_pipeline, _)) => Arc::clone(execution_pipeline),
+            None => return,
+        };
+        let epoch = self.epoch_mgr.epoch() as u64;
+        if commit_vote.ledger_info().epoch_num() != epoch {
+            debug!(
+                "Ignoring commit vote of epoch {} in epoch {}: {}",
+                commit_vote.ledger_info().epoch_num(),
+                epoch,
+                commit_vote
+            );
+            return;
+        }
+        let timestamp_usecs = commit_vote.ledger_info().timestamp_usecs();
+        if timestamp_usecs <= self.block_store.root().timestamp_usecs() {
+            return;
+        }
+        if let Some(previous) = self.pending_commit_votes.get(&commit_vote.author()) {
+            if previous.ledger_info().timestamp_usecs() >= timestamp_usecs {
+                debug!(
+                    "Ignoring commit vote older than the latest one of its author: {}",
+                    commit_vote
+                );
+                return;
+            }
+        }
+        let ledger_info = commit_vote.ledger_info().clone();
+        self.pending_commit_votes
+            .insert(commit_vote.author(), commit_vote);
+        let ledger_info_hash = ledger_info.hash();
+        let mut finality_proof = LedgerInfoWithSignatures::new(ledger_info, HashMap::new());
+        for vote in self.pending_commit_votes.values() {
+            if vote.ledger_info().hash() == ledger_info_hash {
+                vote.signature()
+                    .clone()
+                    .add_to_li(vote.author(), &mut finality_proof);
+            }
+        }
+        if finality_proof.signatures().len() < self.epoch_mgr.quorum_size() {
+            return;
+        }
+        let block_id = finality_proof.ledger_info().consensus_block_id();
+        let (block, compute_result) = match (
+            self.block_store.get_block(block_id),
+            execution_pipeline.executed_result(block_id),
+        ) {
+            (Some(block), Some(compute_result)) => (block, compute_result),
+            _ => {
+                self.sync_manager
+                    .update_highest_commit_proof(finality_proof);
+                return;
+            }
+        };
+        let executed_state = &compute_result.executed_state;
+        let ledger_info = finality_proof.ledger_info();
+        if ledger_info.transaction_accumulator_hash() != executed_state.state_id
+            || ledger_info.version() != executed_state.version
+            || ledger_info.next_validator_set() != executed_state.validators.as_ref()
+        {
+            error!(
+                "Executing block {} led to state {} at version {}, while a quorum of validators \
+                 committed {}",
+                block, executed_state.state_id, executed_state.version, ledger_info
+            );
+            return;
+        }
+        self.process_commit(block, finality_proof).await;
+        // The commit votes for the committed blocks aren't needed anymore.
+        let committed_timestamp_usecs = self.block_store.root().timestamp_usecs();
+        self.pending_commit_votes
+            .retain(|_, vote| vote.ledger_info().timestamp_usecs() > committed_timestamp_usecs);
+    }
+
+    /// Pushes the committed blocks, oldest first, along with their quorum certs to the full nodes
+    /// subscribed to the validator.
+    fn publish_commit(
+        &self,
+        publisher: &ConsensusPublisher,
+        committed_path: &[Arc<Block<T>>],
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) {
+        let blocks: Option<Vec<_>> = committed_path
+            .iter()
+            .rev()
+            .map(|block| {
+                self.block_store
+                    .get_quorum_cert_for_block(block.id())
+                    .map(|qc| (block.as_ref().clone(), qc.as_ref().clone()))
+            })
+            .collect();
+        match blocks {
+            Some(blocks) => publisher.publish(blocks, ledger_info_with_sigs),
+            None => warn!(
+                "Quorum certs of the blocks committed by {} are missing, not publishing them",
+                ledger_info_with_sigs.ledger_info()
+            ),
+        }
+    }
+
     /// Upon (potentially) new commit:
     /// 0. Verify that this commit is newer than the current root.
     /// 1. Notify state computer with the finality proof.
@@ -761,7 +1176,7 @@ impl<T: Payload> EventProcessor<T> {
     /// transactions.
     /// 3. Prune the tree.
     async fn process_commit(
-        &self,
+        &mut self,
         committed_block: Arc<Block<T>>,
         finality_proof: LedgerInfoWithSignatures,
     ) {
@@ -777,6 +1192,14 @@ impl<T: Payload> EventProcessor<T> {
             committed_block.id()
         );
 
+        let ledger_info = finality_proof.ledger_info().clone();
+        let epoch_ending_ledger_info = if ledger_info.next_validator_set().is_some() {
+            Some(finality_proof.clone())
+        } else {
+            None
+        };
+        let publisher = self.network.publisher();
+        let ledger_info_with_sigs = publisher.as_ref().map(|_| finality_proof.clone());
         if let Err(e) = self.state_computer.commit(finality_proof).await {
             // We assume that state computer cannot enter an inconsistent state that might
             // violate safety of the protocol. Specifically, an executor service is going to panic
@@ -791,20 +1214,28 @@ impl<T: Payload> EventProcessor<T> {
         // At this moment the new state is persisted and we can notify the clients.
         // Multiple blocks might be committed at once: notify about all the transactions in the
         // path from the old root to the new root.
-        for committed in self
+        let committed_path = self
             .block_store
             .path_from_root(Arc::clone(&committed_block))
-            .unwrap_or_else(Vec::new)
-        {
+            .unwrap_or_else(Vec::new);
+        if let (Some(publisher), Some(ledger_info_with_sigs)) = (publisher, ledger_info_with_sigs) {
+            if !committed_path.is_empty() {
+                self.publish_commit(&publisher, &committed_path, ledger_info_with_sigs);
+            }
+        }
+        self.proposer_election
+            .process_committed_blocks(&committed_path);
+        for committed in committed_path {
             if let Some(time_to_commit) = duration_since_epoch()
                 .checked_sub(Duration::from_micros(committed.timestamp_usecs()))
             {
                 counters::CREATION_TO_COMMIT_S.observe_duration(time_to_commit);
             }
-            let compute_result = self
-                .block_store
-                .get_compute_result(committed.id())
-                .expect("Compute result of a pending block is unknown");
+            let compute_result = match &self.execution_pipeline {
+                Some((execution_pipeline, _)) => execution_pipeline.executed_result(committed.id()),
+                None => self.block_store.get_compute_result(committed.id()),
+            }
+            .expect("Compute result of a pending block is unknown");
             if let Err(e) = self
                 .txn_manager
                 .commit_txns(
@@ -824,7 +1255,18 @@ impl<T: Payload> EventProcessor<T> {
             "round": committed_block.round(),
             "parent_id": committed_block.parent_id().short_str(),
         );
-        self.block_store.prune_tree(committed_block.id());
+        let pruned_block_ids = self.block_store.prune_tree(committed_block.id());
+        if let Some((execution_pipeline, _)) = &self.execution_pipeline {
+            execution_pipeline.prune(pruned_block_ids);
+        }
+        if let Some(epoch_ending_ledger_info) = epoch_ending_ledger_info {
+            info!(
+                "Epoch {} ended by block {}",
+                ledger_info.epoch_num(),
+                *committed_block
+            );
+            self.epoch_change_proof.push(epoch_ending_ledger_info);
+        }
     }
 
     /// Retrieve a n chained blocks from the block store starting from
@@ -874,9 +1316,23 @@ impl<T: Payload> EventProcessor<T> {
         self.process_new_round_event(new_round_event).await;
     }
 
+    /// Returns the ledger info ending the current epoch, along with the signatures certifying it,
+    /// once it's committed, followed by the ones ending the later epochs the validator caught up
+    /// with, if any.
+    pub fn epoch_change_proof(&self) -> &[LedgerInfoWithSignatures] {
+        &self.epoch_change_proof
+    }
+
+    /// Hands the safety rules over, e.g. to the event processor of the next epoch.
+    pub fn into_safety_rules(self) -> Box<dyn TSafetyRules<T> + Send + Sync> {
+        self.safety_rules
+    }
+
     /// Inspect the current consensus state.
     #[cfg(test)]
     pub fn consensus_state(&self) -> ConsensusState {
-        self.safety_rules.consensus_state()
+        self.safety_rules
+            .consensus_state()
+            .expect("Failed to get the consensus state")
     }
 }
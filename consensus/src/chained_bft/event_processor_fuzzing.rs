@@ -12,7 +12,9 @@ use crate::{
         },
         network::ConsensusNetworkImpl,
         persistent_storage::{PersistentStorage, RecoveryData},
-        safety::safety_rules::SafetyRules,
+        safety::{
+            local_safety_rules::LocalSafetyRules, persistent_safety_storage::InMemoryStorage,
+        },
         test_utils::{EmptyStateComputer, MockStorage, MockTransactionManager, TestPayload},
     },
     util::mock_time_service::SimulatedTimeService,
@@ -31,7 +33,7 @@ use types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, Validator
 
 // This generates a proposal for round 1
 pub fn generate_corpus_proposal() -> Vec<u8> {
-    let event_processor = create_node_for_fuzzing();
+    let mut event_processor = create_node_for_fuzzing();
     block_on(async {
         let proposal = event_processor
             .generate_proposal(NewRoundEvent {
@@ -54,7 +56,6 @@ lazy_static! {
 
 // helpers
 fn build_empty_store(
-    signer: ValidatorSigner,
     storage: Arc<dyn PersistentStorage<TestPayload>>,
     initial_data: RecoveryData<TestPayload>,
 ) -> Arc<BlockStore<TestPayload>> {
@@ -63,7 +64,6 @@ fn build_empty_store(
     Arc::new(block_on(BlockStore::new(
         storage,
         initial_data,
-        signer,
         Arc::new(EmptyStateComputer),
         true,
         10, // max pruned blocks in mem
@@ -103,7 +103,10 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
     let consensus_state = initial_data.state();
 
     // TODO: remove
-    let safety_rules = SafetyRules::new(consensus_state);
+    let safety_rules = Box::new(LocalSafetyRules::new(
+        Arc::new(signer.clone()),
+        Box::new(InMemoryStorage::new(consensus_state)),
+    ));
 
     // TODO: mock channels
     let (network_reqs_tx, _network_reqs_rx) = channel::new_test(8);
@@ -118,7 +121,7 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
     );
 
     // TODO: mock
-    let block_store = build_empty_store(signer.clone(), storage.clone(), initial_data);
+    let block_store = build_empty_store(storage.clone(), initial_data);
 
     // TODO: remove
     let time_service = Arc::new(SimulatedTimeService::new());
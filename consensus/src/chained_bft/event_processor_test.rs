@@ -26,7 +26,10 @@ use crate::{
         network::{BlockRetrievalRequest, BlockRetrievalResponse, ConsensusNetworkImpl},
         network_tests::NetworkPlayground,
         persistent_storage::{PersistentStorage, RecoveryData},
-        safety::safety_rules::{ConsensusState, SafetyRules},
+        safety::{
+            local_safety_rules::LocalSafetyRules, persistent_safety_storage::ConsensusDbStorage,
+            safety_rules::ConsensusState,
+        },
         test_utils::{
             consensus_runtime, placeholder_certificate_for_block, placeholder_ledger_info,
             MockStateComputer, MockStorage, MockTransactionManager, TestPayload, TreeInserter,
@@ -63,7 +66,6 @@ pub struct NodeSetup {
 
 impl NodeSetup {
     fn build_empty_store(
-        signer: ValidatorSigner,
         storage: Arc<dyn PersistentStorage<TestPayload>>,
         initial_data: RecoveryData<TestPayload>,
     ) -> Arc<BlockStore<TestPayload>> {
@@ -72,7 +74,6 @@ impl NodeSetup {
         Arc::new(block_on(BlockStore::new(
             storage,
             initial_data,
-            signer,
             Arc::new(MockStateComputer::new(commit_cb_sender)),
             true,
             10, // max pruned blocks in mem
@@ -156,7 +157,7 @@ impl NodeSetup {
         );
         let consensus_state = initial_data.state();
 
-        let block_store = Self::build_empty_store(signer.clone(), storage.clone(), initial_data);
+        let block_store = Self::build_empty_store(storage.clone(), initial_data);
         let time_service = Arc::new(ClockTimeService::new(executor.clone()));
         let proposal_generator = ProposalGenerator::new(
             block_store.clone(),
@@ -165,7 +166,13 @@ impl NodeSetup {
             1,
             true,
         );
-        let safety_rules = SafetyRules::new(consensus_state);
+        let safety_rules = Box::new(LocalSafetyRules::new(
+            Arc::new(signer.clone()),
+            Box::new(ConsensusDbStorage::<TestPayload>::new(
+                storage.clone(),
+                consensus_state,
+            )),
+        ));
 
         let pacemaker = Self::create_pacemaker(time_service.clone());
 
@@ -219,8 +226,8 @@ impl NodeSetup {
 fn basic_new_rank_event_test() {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
-    let nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 2);
-    let node = &nodes[0];
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 2);
+    let node = &mut nodes[0];
     let genesis = node.block_store.root();
     let mut inserter = TreeInserter::new(node.block_store.clone());
     let a1 =
@@ -273,9 +280,9 @@ fn basic_new_rank_event_test() {
                 a1.quorum_cert().grandparent_block_id(),
                 a1.quorum_cert().grandparent_block_round(),
             ),
-            node.block_store.signer().author(),
+            node.signer.author(),
             placeholder_ledger_info(),
-            node.block_store.signer(),
+            &node.signer,
         );
         node.block_store.insert_vote_and_qc(vote_msg, 0);
         node.event_processor
@@ -330,7 +337,7 @@ fn process_successful_proposal_test() {
             1,
             1,
             genesis_qc.clone(),
-            node.block_store.signer(),
+            &node.signer,
         );
         let proposal_id = proposal.id();
         node.event_processor.process_proposed_block(proposal).await;
@@ -370,7 +377,7 @@ fn process_old_proposal_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let new_block_id = new_block.id();
     let old_block = Block::make_block(
@@ -379,7 +386,7 @@ fn process_old_proposal_test() {
         1,
         2,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let old_block_id = old_block.id();
     block_on(async move {
@@ -421,7 +428,7 @@ fn process_round_mismatch_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_skip_round = Block::make_block(
         genesis.as_ref(),
@@ -429,7 +436,7 @@ fn process_round_mismatch_test() {
         2,
         2,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     block_on(async move {
         let bad_proposal = ProposalMsg::<TestPayload>::new(
@@ -554,7 +561,7 @@ fn process_proposer_mismatch_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_incorrect_proposer = Block::make_block(
         genesis.as_ref(),
@@ -562,7 +569,7 @@ fn process_proposer_mismatch_test() {
         1,
         1,
         genesis_qc.clone(),
-        incorrect_proposer.block_store.signer(),
+        &incorrect_proposer.signer,
     );
     block_on(async move {
         let bad_proposal = ProposalMsg::<TestPayload>::new(
@@ -607,7 +614,7 @@ fn process_timeout_certificate_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_skip_round = Block::make_block(
         genesis.as_ref(),
@@ -615,7 +622,7 @@ fn process_timeout_certificate_test() {
         2,
         2,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let tc =
         PacemakerTimeoutCertificate::new(1, vec![PacemakerTimeout::new(1, &node.signer, None)]);
@@ -671,9 +678,9 @@ fn process_votes_basic_test() {
     );
     let vote_msg = VoteMsg::new(
         vote_data,
-        node.block_store.signer().author(),
+        node.signer.author(),
         placeholder_ledger_info(),
-        node.block_store.signer(),
+        &node.signer,
     );
     block_on(async move {
         node.event_processor.process_vote(vote_msg).await;
@@ -703,7 +710,7 @@ fn process_block_retrieval() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_id = block.id();
 
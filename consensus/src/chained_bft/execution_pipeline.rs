@@ -0,0 +1,232 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{common::Payload, consensus_types::block::Block, QuorumCert},
+    state_replication::StateComputer,
+};
+use crypto::HashValue;
+use executor::{ExecutedState, StateComputeResult};
+use failure::prelude::*;
+use futures::{
+    channel::mpsc, compat::Future01CompatExt, future, Future, FutureExt, StreamExt, TryFutureExt,
+};
+use logger::prelude::*;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{runtime::TaskExecutor, timer::Delay};
+use types::crypto_proxies::LedgerInfoWithSignatures;
+
+type ComputeFuture = Pin<Box<dyn Future<Output = Result<StateComputeResult>> + Send>>;
+
+/// The delay before retrying a failed execution, doubled on every further attempt.
+const EXECUTION_RETRY_INITIAL_DELAY: Duration = Duration::from_millis(100);
+const EXECUTION_MAX_RETRIES: u32 = 4;
+
+/// The state that the blocks are voted for in the decoupled execution mode: the quorum certs
+/// only order the blocks, the executed state is certified by the commit votes.
+pub fn ordering_only_state() -> ExecutedState {
+    ExecutedState::state_for_genesis()
+}
+
+/// The blocks that a quorum cert newly orders, oldest first.
+pub struct OrderedBlocks<T> {
+    pub blocks: Vec<Arc<Block<T>>>,
+}
+
+/// The blocks that a quorum cert ordered, oldest first, along with the results of their
+/// execution.
+pub struct ExecutedBlocks<T> {
+    pub blocks: Vec<(Arc<Block<T>>, Arc<StateComputeResult>)>,
+}
+
+/// Takes the execution of the blocks off the voting path.
+///
+/// As a state computer, it starts the execution of each block inserted in the block store but
+/// hands the ordering-only state back right away, so that the validator can vote without waiting
+/// for it. The execution stage then awaits the results of the ordered blocks, in order, and
+/// hands them over to the commit phase.
+pub struct ExecutionPipeline<T> {
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
+    // The executions that are started but not awaited yet.
+    pending: Mutex<HashMap<HashValue, ComputeFuture>>,
+    // The results of the executions awaited so far, until the blocks are pruned.
+    executed: Arc<Mutex<HashMap<HashValue, Arc<StateComputeResult>>>>,
+}
+
+impl<T: Payload> ExecutionPipeline<T> {
+    pub fn new(state_computer: Arc<dyn StateComputer<Payload = T>>) -> Self {
+        Self {
+            state_computer,
+            pending: Mutex::new(HashMap::new()),
+            executed: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Spawns the execution stage: the blocks sent to the returned sender are executed and the
+    /// results come out of the returned receiver in the same order, or the error that executing
+    /// them led to.
+    pub fn start(
+        pipeline: Arc<Self>,
+        executor: &TaskExecutor,
+    ) -> (
+        mpsc::UnboundedSender<OrderedBlocks<T>>,
+        mpsc::UnboundedReceiver<Result<ExecutedBlocks<T>>>,
+    ) {
+        let (ordered_blocks_tx, ordered_blocks_rx) = mpsc::unbounded();
+        let (executed_blocks_tx, executed_blocks_rx) = mpsc::unbounded();
+        executor.spawn(
+            run_execution_stage(pipeline, ordered_blocks_rx, executed_blocks_tx)
+                .boxed()
+                .unit_error()
+                .compat(),
+        );
+        (ordered_blocks_tx, executed_blocks_rx)
+    }
+
+    /// Returns the result of executing the given block, if the execution stage awaited it.
+    pub fn executed_result(&self, block_id: HashValue) -> Option<Arc<StateComputeResult>> {
+        self.executed.lock().unwrap().get(&block_id).cloned()
+    }
+
+    /// Forgets the blocks that are pruned from the block store.
+    pub fn prune(&self, block_ids: impl IntoIterator<Item = HashValue>) {
+        let mut pending = self.pending.lock().unwrap();
+        let mut executed = self.executed.lock().unwrap();
+        for block_id in block_ids {
+            pending.remove(&block_id);
+            executed.remove(&block_id);
+        }
+    }
+
+    /// Awaits the result of executing the given block, starting the execution if the block was
+    /// never handed over to `compute` (e.g., it was recovered from the consensus db).
+    fn execute(
+        &self,
+        block: &Block<T>,
+    ) -> Pin<Box<dyn Future<Output = Result<Arc<StateComputeResult>>> + Send>> {
+        let block_id = block.id();
+        if let Some(result) = self.executed_result(block_id) {
+            return future::ok(result).boxed();
+        }
+        let compute = match self.pending.lock().unwrap().remove(&block_id) {
+            Some(compute) => compute,
+            None => self
+                .state_computer
+                .compute(block.parent_id(), block_id, block.get_payload()),
+        };
+        let executed = Arc::clone(&self.executed);
+        async move {
+            let result = Arc::new(compute.await?);
+            executed
+                .lock()
+                .unwrap()
+                .insert(block_id, Arc::clone(&result));
+            Ok(result)
+        }
+            .boxed()
+    }
+}
+
+impl<T: Payload> StateComputer for ExecutionPipeline<T> {
+    type Payload = T;
+
+    fn compute(
+        &self,
+        parent_block_id: HashValue,
+        block_id: HashValue,
+        transactions: &Self::Payload,
+    ) -> Pin<Box<dyn Future<Output = Result<StateComputeResult>> + Send>> {
+        // The execution is started right away so that the executor receives the blocks in the
+        // order they are inserted in the block store, i.e., always after their parent.
+        let compute = self
+            .state_computer
+            .compute(parent_block_id, block_id, transactions);
+        self.pending.lock().unwrap().insert(block_id, compute);
+        future::ok(StateComputeResult {
+            executed_state: ordering_only_state(),
+            compute_status: vec![],
+        })
+        .boxed()
+    }
+
+    fn commit(
+        &self,
+        commit: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        self.state_computer.commit(commit)
+    }
+
+    fn sync_to(&self, commit: QuorumCert) -> Pin<Box<dyn Future<Output = Result<bool>> + Send>> {
+        // The executions of the local blocks are superseded by the synced state.
+        self.pending.lock().unwrap().clear();
+        self.executed.lock().unwrap().clear();
+        self.state_computer.sync_to(commit)
+    }
+}
+
+/// Executes the ordered blocks one after another. The blocks ordered by a quorum cert are handed
+/// over to the commit phase all at once, as they are committed together: if one of them fails to
+/// execute, even after a few retries, the error is handed over instead.
+async fn run_execution_stage<T: Payload>(
+    pipeline: Arc<ExecutionPipeline<T>>,
+    mut ordered_blocks_rx: mpsc::UnboundedReceiver<OrderedBlocks<T>>,
+    executed_blocks_tx: mpsc::UnboundedSender<Result<ExecutedBlocks<T>>>,
+) {
+    while let Some(ordered) = ordered_blocks_rx.next().await {
+        let mut blocks = vec![];
+        let mut error = None;
+        for block in ordered.blocks {
+            match execute_with_retries(&pipeline, &block).await {
+                Ok(result) => blocks.push((block, result)),
+                Err(e) => {
+                    error = Some(format_err!(
+                        "Failed to execute ordered block {}: {:?}",
+                        block,
+                        e
+                    ));
+                    break;
+                }
+            }
+        }
+        let executed_blocks = match error {
+            Some(error) => Err(error),
+            None => Ok(ExecutedBlocks { blocks }),
+        };
+        if executed_blocks_tx.unbounded_send(executed_blocks).is_err() {
+            debug!("The commit phase is gone, stopping the execution stage");
+            return;
+        }
+    }
+}
+
+/// Executes the block, retrying with an increasing delay if it fails: e.g., the executor doesn't
+/// execute any block while it syncs up.
+async fn execute_with_retries<T: Payload>(
+    pipeline: &ExecutionPipeline<T>,
+    block: &Block<T>,
+) -> Result<Arc<StateComputeResult>> {
+    let mut delay = EXECUTION_RETRY_INITIAL_DELAY;
+    let mut attempt = 0;
+    loop {
+        match pipeline.execute(block).await {
+            Ok(result) => return Ok(result),
+            Err(e) if attempt < EXECUTION_MAX_RETRIES => {
+                warn!(
+                    "Failed to execute ordered block {}, retrying in {:?}: {:?}",
+                    block, delay, e
+                );
+            }
+            Err(e) => return Err(e),
+        }
+        if let Err(e) = Delay::new(Instant::now() + delay).compat().await {
+            error!("Failed to wait before retrying the execution: {:?}", e);
+        }
+        delay *= 2;
+        attempt += 1;
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{common::Payload, QuorumCert},
+    counters,
+    state_replication::StateComputer,
+};
+use config::config::ProposalBackpressureConfig;
+use crypto::HashValue;
+use executor::StateComputeResult;
+use failure::Result;
+use futures::{Future, FutureExt};
+use std::{
+    cmp::{max, min},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use types::crypto_proxies::LedgerInfoWithSignatures;
+
+/// Keeps a moving average of the time it takes to execute a block.
+#[derive(Default)]
+pub struct ExecutionLatencyTracker {
+    inner: Mutex<ExecutionLatency>,
+}
+
+#[derive(Default)]
+struct ExecutionLatency {
+    average: Option<Duration>,
+    num_observations: u64,
+}
+
+impl ExecutionLatencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accounts for the execution of one more block, the latest ones weighing the most.
+    pub fn observe(&self, latency: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.average = Some(match inner.average {
+            Some(average) => average * 3 / 4 + latency / 4,
+            None => latency,
+        });
+        inner.num_observations += 1;
+    }
+
+    /// Returns the average latency along with the number of executions observed so far.
+    fn get(&self) -> (Option<Duration>, u64) {
+        let inner = self.inner.lock().unwrap();
+        (inner.average, inner.num_observations)
+    }
+}
+
+/// Measures the executions of the wrapped state computer, from the time the execution of a block
+/// is requested until its result is delivered.
+pub struct LatencyTrackingStateComputer<T> {
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
+    execution_latency: Arc<ExecutionLatencyTracker>,
+}
+
+impl<T> LatencyTrackingStateComputer<T> {
+    pub fn new(
+        state_computer: Arc<dyn StateComputer<Payload = T>>,
+        execution_latency: Arc<ExecutionLatencyTracker>,
+    ) -> Self {
+        Self {
+            state_computer,
+            execution_latency,
+        }
+    }
+}
+
+impl<T: Payload> StateComputer for LatencyTrackingStateComputer<T> {
+    type Payload = T;
+
+    fn compute(
+        &self,
+        parent_block_id: HashValue,
+        block_id: HashValue,
+        transactions: &Self::Payload,
+    ) -> Pin<Box<dyn Future<Output = Result<StateComputeResult>> + Send>> {
+        let pre_execution_instant = Instant::now();
+        let compute = self
+            .state_computer
+            .compute(parent_block_id, block_id, transactions);
+        let execution_latency = Arc::clone(&self.execution_latency);
+        async move {
+            let result = compute.await;
+            if result.is_ok() {
+                execution_latency.observe(pre_execution_instant.elapsed());
+            }
+            result
+        }
+            .boxed()
+    }
+
+    fn commit(
+        &self,
+        commit: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        self.state_computer.commit(commit)
+    }
+
+    fn sync_to(&self, commit: QuorumCert) -> Pin<Box<dyn Future<Output = Result<bool>> + Send>> {
+        self.state_computer.sync_to(commit)
+    }
+}
+
+/// Chooses the number of transactions the proposer pulls from mempool, so that the blocks don't
+/// pile up faster than they are executed and committed:
+/// - while the branch to extend has too many uncommitted blocks, the proposals are empty: they
+/// keep the rounds going (and the pending blocks getting committed) without adding to the work
+/// of the executor,
+/// - the block size is halved every time a block executes slower than the target on average,
+/// - the block size grows linearly, up to `max_block_size`, every time a block executes within
+/// the target while mempool has more transactions than the last block could take.
+pub struct BlockSizeController {
+    config: ProposalBackpressureConfig,
+    max_block_size: u64,
+    min_block_size: u64,
+    execution_latency: Arc<ExecutionLatencyTracker>,
+    state: Mutex<BlockSizeState>,
+}
+
+struct BlockSizeState {
+    block_size: u64,
+    // Each execution adjusts the block size only once, however many proposals follow it.
+    num_observations_seen: u64,
+}
+
+impl BlockSizeController {
+    pub fn new(
+        config: ProposalBackpressureConfig,
+        max_block_size: u64,
+        execution_latency: Arc<ExecutionLatencyTracker>,
+    ) -> Self {
+        let min_block_size = min(config.min_block_size, max_block_size);
+        let (_, num_observations_seen) = execution_latency.get();
+        Self {
+            config,
+            max_block_size,
+            min_block_size,
+            execution_latency,
+            state: Mutex::new(BlockSizeState {
+                block_size: max_block_size,
+                num_observations_seen,
+            }),
+        }
+    }
+
+    /// Returns the max number of transactions of the next proposal, given the number of
+    /// uncommitted blocks in the branch it extends and the number of transactions mempool has
+    /// left after the last pull (if known). Zero means the proposal should be empty.
+    pub fn next_block_size(&self, pending_blocks: usize, mempool_depth: Option<u64>) -> u64 {
+        counters::PROPOSAL_PENDING_BLOCKS.set(pending_blocks as i64);
+        if pending_blocks as u64 >= self.config.max_pending_blocks {
+            counters::PROPOSAL_BACKPRESSURE_EMPTY_COUNT.inc();
+            return 0;
+        }
+        let (average_latency, num_observations) = self.execution_latency.get();
+        let mut state = self.state.lock().unwrap();
+        if num_observations > state.num_observations_seen {
+            state.num_observations_seen = num_observations;
+            let target_latency = Duration::from_millis(self.config.target_execution_latency_ms);
+            if average_latency.map_or(false, |latency| latency > target_latency) {
+                state.block_size = max(state.block_size / 2, self.min_block_size);
+            } else if mempool_depth.map_or(true, |depth| depth > 0) {
+                state.block_size = min(
+                    state.block_size + self.config.block_size_increase,
+                    self.max_block_size,
+                );
+            }
+        }
+        counters::PROPOSAL_BLOCK_SIZE_LIMIT.set(state.block_size as i64);
+        state.block_size
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        liveness::block_size_controller::{
+            BlockSizeController, ExecutionLatencyTracker, LatencyTrackingStateComputer,
+        },
+        test_utils::EmptyStateComputer,
+    },
+    state_replication::StateComputer,
+};
+use config::config::ProposalBackpressureConfig;
+use crypto::HashValue;
+use futures::executor::block_on;
+use std::{sync::Arc, time::Duration};
+
+fn config() -> ProposalBackpressureConfig {
+    ProposalBackpressureConfig {
+        enabled: true,
+        min_block_size: 10,
+        target_execution_latency_ms: 100,
+        block_size_increase: 5,
+        max_pending_blocks: 4,
+    }
+}
+
+const SLOW: Duration = Duration::from_millis(500);
+const FAST: Duration = Duration::from_millis(10);
+
+#[test]
+fn test_empty_blocks_when_too_many_pending() {
+    let controller =
+        BlockSizeController::new(config(), 100, Arc::new(ExecutionLatencyTracker::new()));
+    assert_eq!(controller.next_block_size(0, None), 100);
+    assert_eq!(controller.next_block_size(3, None), 100);
+    assert_eq!(controller.next_block_size(4, Some(1000)), 0);
+    assert_eq!(controller.next_block_size(10, None), 0);
+    // Back to normal once the pending blocks get committed.
+    assert_eq!(controller.next_block_size(1, None), 100);
+}
+
+#[test]
+fn test_block_size_halves_when_execution_is_slow() {
+    let execution_latency = Arc::new(ExecutionLatencyTracker::new());
+    let controller = BlockSizeController::new(config(), 100, Arc::clone(&execution_latency));
+
+    execution_latency.observe(SLOW);
+    assert_eq!(controller.next_block_size(0, None), 50);
+    // No new execution, no new adjustment.
+    assert_eq!(controller.next_block_size(0, None), 50);
+    execution_latency.observe(SLOW);
+    assert_eq!(controller.next_block_size(0, None), 25);
+    execution_latency.observe(SLOW);
+    assert_eq!(controller.next_block_size(0, None), 12);
+    execution_latency.observe(SLOW);
+    assert_eq!(controller.next_block_size(0, None), 10);
+    execution_latency.observe(SLOW);
+    assert_eq!(controller.next_block_size(0, None), 10);
+}
+
+#[test]
+fn test_block_size_grows_with_mempool_depth() {
+    let execution_latency = Arc::new(ExecutionLatencyTracker::new());
+    let controller = BlockSizeController::new(config(), 30, Arc::clone(&execution_latency));
+    execution_latency.observe(SLOW);
+    assert_eq!(controller.next_block_size(0, None), 15);
+
+    // The average goes below the target after a few fast executions.
+    for _ in 0..10 {
+        execution_latency.observe(FAST);
+    }
+    // Mempool had nothing left after the last block: larger blocks wouldn't be filled.
+    assert_eq!(controller.next_block_size(0, Some(0)), 15);
+    execution_latency.observe(FAST);
+    assert_eq!(controller.next_block_size(0, Some(7)), 20);
+    execution_latency.observe(FAST);
+    assert_eq!(controller.next_block_size(0, None), 25);
+    execution_latency.observe(FAST);
+    assert_eq!(controller.next_block_size(0, Some(7)), 30);
+    execution_latency.observe(FAST);
+    assert_eq!(controller.next_block_size(0, Some(7)), 30);
+}
+
+#[test]
+fn test_min_block_size_above_max() {
+    let execution_latency = Arc::new(ExecutionLatencyTracker::new());
+    let controller = BlockSizeController::new(config(), 4, Arc::clone(&execution_latency));
+    assert_eq!(controller.next_block_size(0, None), 4);
+    execution_latency.observe(SLOW);
+    assert_eq!(controller.next_block_size(0, None), 4);
+}
+
+#[test]
+fn test_latency_tracking_state_computer() {
+    let execution_latency = Arc::new(ExecutionLatencyTracker::new());
+    let state_computer = LatencyTrackingStateComputer::new(
+        Arc::new(EmptyStateComputer),
+        Arc::clone(&execution_latency),
+    );
+    let controller = BlockSizeController::new(config(), 100, Arc::clone(&execution_latency));
+
+    // The execution is fast, the block size would grow if it wasn't at the max already.
+    block_on(state_computer.compute(HashValue::zero(), HashValue::random(), &vec![])).unwrap();
+    assert_eq!(controller.next_block_size(0, None), 100);
+
+    // Only the executions that completed are accounted for.
+    execution_latency.observe(SLOW);
+    let compute = state_computer.compute(HashValue::zero(), HashValue::random(), &vec![]);
+    assert_eq!(controller.next_block_size(0, None), 50);
+    block_on(compute).unwrap();
+    assert_eq!(controller.next_block_size(0, None), 55);
+}
@@ -0,0 +1,290 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::BlockReader,
+    common::{Author, Payload, Round},
+    consensus_types::block::Block,
+    liveness::proposer_election::ProposerElection,
+    persistent_storage::PersistentStorage,
+};
+use crypto::HashValue;
+use logger::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    convert::TryInto,
+    sync::Arc,
+};
+
+/// What leader reputation needs to know about a committed block.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockMetadata {
+    round: Round,
+    /// None for NIL blocks.
+    proposer: Option<Author>,
+    /// The authors of the votes gathered in the quorum certificate carried by the block.
+    voters: Vec<Author>,
+}
+
+impl BlockMetadata {
+    pub fn new(round: Round, proposer: Option<Author>, voters: Vec<Author>) -> Self {
+        Self {
+            round,
+            proposer,
+            voters,
+        }
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+}
+
+impl<T> From<&Block<T>> for BlockMetadata {
+    fn from(block: &Block<T>) -> Self {
+        Self::new(block.round(), block.author(), block.quorum_cert().signers())
+    }
+}
+
+/// Source of the committed block metadata.
+pub trait MetadataBackend: Send + Sync {
+    /// Returns the metadata of the latest committed blocks whose round is at most `target_round`,
+    /// from the most recent one backwards.
+    fn get_block_metadata(&self, target_round: Round) -> Vec<BlockMetadata>;
+
+    /// Records the metadata of newly committed blocks, from the oldest one.
+    fn record_block_metadata(&mut self, block_metadata: Vec<BlockMetadata>);
+}
+
+/// Keeps the metadata of the blocks committed in the current epoch in the consensus storage, so
+/// that it survives restarts, along with a copy in memory to read from. Only the latest blocks
+/// that may still be part of the window are kept.
+pub struct StorageBackend<T> {
+    storage: Arc<dyn PersistentStorage<T>>,
+    epoch: u64,
+    window_size: usize,
+    // The window plus the blocks that may be too recent to be taken into account.
+    num_blocks_kept: usize,
+    block_metadata: VecDeque<BlockMetadata>,
+}
+
+impl<T> StorageBackend<T> {
+    pub fn new(
+        storage: Arc<dyn PersistentStorage<T>>,
+        epoch: u64,
+        window_size: usize,
+        exclude_rounds: u64,
+    ) -> Self {
+        let mut block_metadata: VecDeque<_> = storage
+            .get_block_metadata(epoch)
+            .unwrap_or_else(|e| {
+                error!("Failed to read the committed block metadata: {:?}", e);
+                vec![]
+            })
+            .into();
+        // There is at most one block committed per round.
+        let num_blocks_kept = window_size + exclude_rounds as usize;
+        while block_metadata.len() > num_blocks_kept {
+            block_metadata.pop_front();
+        }
+        Self {
+            storage,
+            epoch,
+            window_size,
+            num_blocks_kept,
+            block_metadata,
+        }
+    }
+}
+
+impl<T: Payload> MetadataBackend for StorageBackend<T> {
+    fn get_block_metadata(&self, target_round: Round) -> Vec<BlockMetadata> {
+        self.block_metadata
+            .iter()
+            .rev()
+            .filter(|metadata| metadata.round() <= target_round)
+            .take(self.window_size)
+            .cloned()
+            .collect()
+    }
+
+    fn record_block_metadata(&mut self, block_metadata: Vec<BlockMetadata>) {
+        self.block_metadata.extend(block_metadata.iter().cloned());
+        while self.block_metadata.len() > self.num_blocks_kept {
+            self.block_metadata.pop_front();
+        }
+        let min_round = self.block_metadata.front().map_or(0, BlockMetadata::round);
+        if let Err(e) = self
+            .storage
+            .save_block_metadata(self.epoch, block_metadata, min_round)
+        {
+            error!("Failed to save the committed block metadata: {:?}", e);
+        }
+    }
+}
+
+/// Leader reputation picks the proposer of a round among the candidates at random, weighting them
+/// by their record in a window of recent blocks: candidates that proposed or voted for any of these
+/// blocks are active and much more likely to be picked than the inactive ones, so an offline
+/// validator rarely costs a round timeout.
+///
+/// The window is made of the latest `window_size` blocks of the chain that the proposals of the
+/// round extend, up to `exclude_rounds` rounds before the round. The blocks of the chain that
+/// aren't committed yet are taken from the block tree, the others from the committed history, so
+/// all the honest replicas that have the block being extended agree on the proposer, whether they
+/// have committed the same blocks or not. The proposer of a round can't be told from the round
+/// alone: `is_valid_proposer` and `get_valid_proposers` accept every candidate, the blocks must be
+/// taken into account with `is_valid_proposer_extending` and `get_valid_proposers_extending`.
+///
+/// The committed history misses the blocks that a replica skipped by syncing its state to a later
+/// block, e.g. after being offline. Until the window moves past them, such a replica may elect
+/// another proposer than the others and ignore the proposals of the elected one: it then only
+/// catches up with the others through their quorum certs, without voting.
+pub struct LeaderReputation<T> {
+    // Ordering of the candidates (all honest replicas must agree on this)
+    proposers: Vec<Author>,
+    backend: Box<dyn MetadataBackend>,
+    block_reader: Arc<dyn BlockReader<Payload = T>>,
+    window_size: usize,
+    exclude_rounds: u64,
+    active_weight: u64,
+    inactive_weight: u64,
+}
+
+impl<T: Payload> LeaderReputation<T> {
+    pub fn new(
+        proposers: Vec<Author>,
+        backend: Box<dyn MetadataBackend>,
+        block_reader: Arc<dyn BlockReader<Payload = T>>,
+        window_size: usize,
+        exclude_rounds: u64,
+        active_weight: u64,
+        inactive_weight: u64,
+    ) -> Self {
+        assert!(
+            inactive_weight > 0,
+            "Every proposer must have a chance to be elected"
+        );
+        Self {
+            proposers,
+            backend,
+            block_reader,
+            window_size,
+            exclude_rounds,
+            active_weight,
+            inactive_weight,
+        }
+    }
+
+    /// Returns the metadata of the blocks in the window of the given round, for the proposals
+    /// extending the given block, from the most recent one backwards. If the block isn't in the
+    /// block tree anymore, only the committed history is taken into account.
+    fn get_window(&self, round: Round, parent: &Block<T>) -> Vec<BlockMetadata> {
+        let target_round = round.saturating_sub(self.exclude_rounds);
+        // The path excludes the root, which is the latest committed block.
+        let mut window: Vec<_> = self
+            .block_reader
+            .get_block(parent.id())
+            .and_then(|parent| self.block_reader.path_from_root(parent))
+            .unwrap_or_default()
+            .iter()
+            .filter(|block| block.round() <= target_round)
+            .map(|block| BlockMetadata::from(block.as_ref()))
+            .collect();
+        window.extend(self.backend.get_block_metadata(target_round));
+        window.truncate(self.window_size);
+        window
+    }
+
+    fn get_weights(&self, history: &[BlockMetadata]) -> Vec<u64> {
+        let active: HashSet<_> = history
+            .iter()
+            .flat_map(|metadata| metadata.proposer.iter().chain(metadata.voters.iter()))
+            .collect();
+        self.proposers
+            .iter()
+            .map(|author| {
+                if active.contains(author) {
+                    self.active_weight
+                } else {
+                    self.inactive_weight
+                }
+            })
+            .collect()
+    }
+
+    fn get_proposer(&self, round: Round, parent: &Block<T>) -> Author {
+        let weights = self.get_weights(&self.get_window(round, parent));
+        self.proposers[choose_index(&weights, round)]
+    }
+}
+
+/// Picks an index at random with probability proportional to its weight, using the round as the
+/// source of randomness.
+fn choose_index(weights: &[u64], round: Round) -> usize {
+    let total_weight: u128 = weights.iter().map(|weight| u128::from(*weight)).sum();
+    let hash = HashValue::from_sha3_256(&round.to_le_bytes());
+    let mut value = u128::from_le_bytes(
+        hash.as_ref()[..16]
+            .try_into()
+            .expect("Hash value is shorter than 16 bytes"),
+    ) % total_weight;
+    for (index, weight) in weights.iter().enumerate() {
+        let weight = u128::from(*weight);
+        if value < weight {
+            return index;
+        }
+        value -= weight;
+    }
+    unreachable!("The value is less than the total weight")
+}
+
+impl<T: Payload> ProposerElection<T> for LeaderReputation<T> {
+    fn is_valid_proposer(&self, author: Author, _round: Round) -> Option<Author> {
+        if self.proposers.contains(&author) {
+            Some(author)
+        } else {
+            None
+        }
+    }
+
+    fn get_valid_proposers(&self, _round: Round) -> Vec<Author> {
+        self.proposers.clone()
+    }
+
+    fn is_valid_proposer_extending(
+        &self,
+        author: Author,
+        round: Round,
+        parent: &Block<T>,
+    ) -> Option<Author> {
+        if self.get_proposer(round, parent) == author {
+            Some(author)
+        } else {
+            None
+        }
+    }
+
+    fn get_valid_proposers_extending(&self, round: Round, parent: &Block<T>) -> Vec<Author> {
+        vec![self.get_proposer(round, parent)]
+    }
+
+    fn process_proposal(&mut self, proposal: Block<T>) -> Option<Block<T>> {
+        // The proposer is checked by the event processor, which knows the parent of the proposal.
+        Some(proposal)
+    }
+
+    fn take_backup_proposal(&mut self, _round: Round) -> Option<Block<T>> {
+        None
+    }
+
+    fn process_committed_blocks(&mut self, blocks: &[Arc<Block<T>>]) {
+        self.backend.record_block_metadata(
+            blocks
+                .iter()
+                .map(|block| BlockMetadata::from(block.as_ref()))
+                .collect(),
+        );
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::{BlockReader, BlockStore},
+    common::{Author, Round},
+    consensus_types::block::Block,
+    liveness::{
+        leader_reputation::{BlockMetadata, LeaderReputation, MetadataBackend, StorageBackend},
+        proposer_election::ProposerElection,
+    },
+    test_utils::{build_empty_tree, MockStorage, TestPayload, TreeInserter},
+};
+use futures::executor::block_on;
+use std::{collections::HashMap, sync::Arc};
+use types::validator_signer::ValidatorSigner;
+
+struct MockHistory {
+    history: Vec<BlockMetadata>,
+}
+
+impl MetadataBackend for MockHistory {
+    fn get_block_metadata(&self, target_round: Round) -> Vec<BlockMetadata> {
+        self.history
+            .iter()
+            .rev()
+            .filter(|metadata| metadata.round() <= target_round)
+            .cloned()
+            .collect()
+    }
+
+    fn record_block_metadata(&mut self, block_metadata: Vec<BlockMetadata>) {
+        self.history.extend(block_metadata);
+    }
+}
+
+fn make_authors(num_authors: u8) -> Vec<Author> {
+    (0..num_authors)
+        .map(|i| ValidatorSigner::random([i; 32]).author())
+        .collect()
+}
+
+fn make_leader_reputation(
+    proposers: Vec<Author>,
+    history: Vec<BlockMetadata>,
+    block_store: Arc<BlockStore<TestPayload>>,
+) -> LeaderReputation<TestPayload> {
+    LeaderReputation::new(
+        proposers,
+        Box::new(MockHistory { history }),
+        block_store,
+        10,   /* window_size */
+        10,   /* exclude_rounds */
+        1000, /* active_weight */
+        1,    /* inactive_weight */
+    )
+}
+
+fn count_proposers(
+    pe: &dyn ProposerElection<TestPayload>,
+    parent: &Block<TestPayload>,
+    rounds: Vec<Round>,
+) -> HashMap<Author, usize> {
+    let mut counts = HashMap::new();
+    for round in rounds {
+        let proposers = pe.get_valid_proposers_extending(round, parent);
+        assert_eq!(proposers.len(), 1);
+        *counts.entry(proposers[0]).or_insert(0) += 1;
+    }
+    counts
+}
+
+#[test]
+fn test_deterministic_without_history() {
+    let authors = make_authors(4);
+    let block_store = build_empty_tree();
+    let genesis = block_store.root();
+    let pe1: Box<dyn ProposerElection<TestPayload>> = Box::new(make_leader_reputation(
+        authors.clone(),
+        vec![],
+        block_store.clone(),
+    ));
+    let pe2: Box<dyn ProposerElection<TestPayload>> =
+        Box::new(make_leader_reputation(authors.clone(), vec![], block_store));
+    for round in 1..100 {
+        let proposer = pe1.get_valid_proposers_extending(round, &genesis)[0];
+        assert!(authors.contains(&proposer));
+        assert_eq!(
+            pe2.get_valid_proposers_extending(round, &genesis),
+            vec![proposer]
+        );
+        assert_eq!(
+            pe1.is_valid_proposer_extending(proposer, round, &genesis),
+            Some(proposer)
+        );
+        for author in authors.iter().filter(|author| **author != proposer) {
+            assert_eq!(
+                pe1.is_valid_proposer_extending(*author, round, &genesis),
+                None
+            );
+            // The proposer can't be told without the block to extend.
+            assert_eq!(pe1.is_valid_proposer(*author, round), Some(*author));
+        }
+    }
+}
+
+#[test]
+fn test_inactive_proposer_skipped() {
+    let authors = make_authors(4);
+    // The last author neither proposed nor voted for any of the recent blocks.
+    let history = (1..=10)
+        .map(|round| {
+            BlockMetadata::new(
+                round,
+                Some(authors[(round % 3) as usize]),
+                authors[..3].to_vec(),
+            )
+        })
+        .collect();
+    let block_store = build_empty_tree();
+    let genesis = block_store.root();
+    let pe = make_leader_reputation(authors.clone(), history, block_store);
+
+    let counts = count_proposers(&pe, &genesis, (20..220).collect());
+    // The chance of the inactive author to be picked is 1/3001 per round.
+    assert!(counts.get(&authors[3]).cloned().unwrap_or(0) <= 1);
+    for author in &authors[..3] {
+        assert!(counts[author] > 0);
+    }
+}
+
+#[test]
+fn test_recent_blocks_excluded() {
+    let authors = make_authors(2);
+    // The first author only becomes active at round 50.
+    let history = vec![
+        BlockMetadata::new(10, Some(authors[1]), vec![authors[1]]),
+        BlockMetadata::new(50, Some(authors[0]), vec![authors[0], authors[1]]),
+    ];
+    let block_store = build_empty_tree();
+    let genesis = block_store.root();
+    let pe = make_leader_reputation(authors.clone(), history, block_store);
+
+    // Up to round 59, the block of round 50 isn't taken into account yet.
+    let before = count_proposers(&pe, &genesis, (20..60).collect());
+    assert!(before.get(&authors[0]).cloned().unwrap_or(0) <= 1);
+    let after = count_proposers(&pe, &genesis, (60..100).collect());
+    assert!(after[&authors[0]] > 1);
+}
+
+#[test]
+fn test_uncommitted_blocks_in_window() {
+    let signer = ValidatorSigner::random([4; 32]);
+    let mut authors = make_authors(3);
+    authors.push(signer.author());
+    // A lagging replica has the blocks of the window in its block tree, none of them committed.
+    let lagging_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new_with_signer(signer.clone(), lagging_tree.clone());
+    let mut blocks = vec![];
+    let mut parent = lagging_tree.root();
+    for round in 1..=10 {
+        parent = inserter.insert_block(&parent, round);
+        blocks.push(Arc::clone(&parent));
+    }
+    // Another replica has committed all of them but the last one.
+    let committed_tree = build_empty_tree();
+    for block in &blocks {
+        block_on(committed_tree.insert_block_with_qc(block.as_ref().clone())).unwrap();
+    }
+    committed_tree.prune_tree(blocks[8].id());
+    let history = blocks[..9]
+        .iter()
+        .map(|block| BlockMetadata::from(block.as_ref()))
+        .collect();
+
+    let lagging = make_leader_reputation(authors.clone(), vec![], lagging_tree);
+    let committed = make_leader_reputation(authors.clone(), history, committed_tree);
+    for round in 11..100 {
+        assert_eq!(
+            lagging.get_valid_proposers_extending(round, &parent),
+            committed.get_valid_proposers_extending(round, &parent)
+        );
+    }
+    // The author of the blocks is the only active one.
+    let counts = count_proposers(&lagging, &parent, (20..220).collect());
+    assert!(counts[&signer.author()] > 190);
+}
+
+#[test]
+fn test_storage_backend() {
+    let (storage, _) = MockStorage::<u32>::start_for_testing();
+    let authors = make_authors(2);
+    let metadata = |round: Round| {
+        BlockMetadata::new(round, Some(authors[(round % 2) as usize]), authors.clone())
+    };
+    // The metadata of 3 blocks is kept: the window of 2 blocks, plus one that may be too recent.
+    let mut backend = StorageBackend::<u32>::new(storage.clone(), 0, 2, 1);
+    backend.record_block_metadata(vec![metadata(1), metadata(2)]);
+    backend.record_block_metadata(vec![metadata(4)]);
+    assert_eq!(
+        backend.get_block_metadata(10),
+        vec![metadata(4), metadata(2)]
+    );
+    assert_eq!(
+        backend.get_block_metadata(3),
+        vec![metadata(2), metadata(1)]
+    );
+    backend.record_block_metadata(vec![metadata(5)]);
+
+    // The metadata is read back from the storage on restart.
+    let backend = StorageBackend::<u32>::new(storage.clone(), 0, 2, 1);
+    assert_eq!(
+        backend.get_block_metadata(10),
+        vec![metadata(5), metadata(4)]
+    );
+    assert_eq!(backend.get_block_metadata(3), vec![metadata(2)]);
+    // Only the blocks of the current epoch are taken into account.
+    assert!(StorageBackend::<u32>::new(storage, 1, 2, 1)
+        .get_block_metadata(10)
+        .is_empty());
+}
@@ -15,7 +15,7 @@ use std::collections::HashMap;
 mod pacemaker_timeout_manager_test;
 
 /// Tracks the highest round known local and received timeout certificates
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct HighestTimeoutCertificates {
     // Highest timeout certificate gathered locally
     highest_local_timeout_certificate: Option<PacemakerTimeoutCertificate>,
@@ -74,12 +74,34 @@ pub struct PacemakerTimeoutManager {
 
 impl PacemakerTimeoutManager {
     pub fn new(
-        highest_timeout_certificates: HighestTimeoutCertificates,
+        mut highest_timeout_certificates: HighestTimeoutCertificates,
         persistent_liveness_storage: Box<dyn PersistentLivenessStorage>,
     ) -> Self {
         // This struct maintains the invariant that the highest round timeout certificate
         // that author_to_received_timeouts can generate is always equal to
         // highest_timeout_certificates.highest_local_timeout_certificate.
+        // A certificate that aggregates the signatures of its timeouts doesn't carry them, so
+        // it can't be regenerated: it is kept as the highest received one instead.
+        let local_tc_is_aggregated = highest_timeout_certificates
+            .highest_local_timeout_certificate
+            .as_ref()
+            .map_or(false, |tc| tc.timeouts().is_empty());
+        if local_tc_is_aggregated {
+            let local_tc = highest_timeout_certificates
+                .highest_local_timeout_certificate
+                .take();
+            let received_round = highest_timeout_certificates
+                .highest_received_timeout_certificate
+                .as_ref()
+                .map_or(0, PacemakerTimeoutCertificate::round);
+            if local_tc
+                .as_ref()
+                .map_or(0, PacemakerTimeoutCertificate::round)
+                > received_round
+            {
+                highest_timeout_certificates.highest_received_timeout_certificate = local_tc;
+            }
+        }
         let mut author_to_received_timeouts = HashMap::new();
         if let Some(tc) = &highest_timeout_certificates.highest_local_timeout_certificate {
             author_to_received_timeouts = tc
@@ -104,6 +126,9 @@ impl PacemakerTimeoutManager {
     /// For example, if timeout_certificate_quorum_size=3 and we received unique author timeouts
     /// for rounds (1,2,3,4), then rounds (2,3,4) would form PacemakerTimeoutCertificate with
     /// round=2.
+    ///
+    /// The timeouts carry BLS12-381 signatures if and only if the validators aggregate their
+    /// signatures, in which case the certificate carries their aggregates instead of the timeouts.
     fn generate_timeout_certificate(
         author_to_received_timeouts: &HashMap<Author, PacemakerTimeout>,
         quorum_size: usize,
@@ -114,14 +139,25 @@ impl PacemakerTimeoutManager {
         let mut values: Vec<&PacemakerTimeout> = author_to_received_timeouts.values().collect();
         values.sort_by(|x, y| y.round().cmp(&x.round()));
         let slice = &values[..quorum_size];
-        Some(PacemakerTimeoutCertificate::new(
-            // expect does not panic here because code above verifies values length
-            slice
-                .last()
-                .expect("Slice for timeout certificate is empty")
-                .round(),
-            slice.iter().map(|x| (*x).clone()).collect(),
-        ))
+        // expect does not panic here because code above verifies values length
+        let round = slice
+            .last()
+            .expect("Slice for timeout certificate is empty")
+            .round();
+        let timeouts: Vec<PacemakerTimeout> = slice.iter().map(|x| (*x).clone()).collect();
+        if timeouts.iter().all(|t| t.bls_signature().is_none()) {
+            return Some(PacemakerTimeoutCertificate::new(round, timeouts));
+        }
+        match PacemakerTimeoutCertificate::aggregate(round, &timeouts) {
+            Ok(tc) => Some(tc),
+            Err(e) => {
+                error!(
+                    "Failed to aggregate the timeout certificate of round {}: {}",
+                    round, e
+                );
+                None
+            }
+        }
     }
 
     /// Updates internal state according to received message from remote pacemaker and returns true
@@ -2,12 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
-    consensus_types::timeout_msg::{PacemakerTimeout, PacemakerTimeoutCertificate},
+    consensus_types::timeout_msg::{
+        PacemakerTimeout, PacemakerTimeoutCertificate, PacemakerTimeoutCertificateVerificationError,
+    },
     liveness::pacemaker_timeout_manager::{HighestTimeoutCertificates, PacemakerTimeoutManager},
     persistent_storage::PersistentStorage,
-    test_utils::{MockStorage, TestPayload},
+    test_utils::{aggregating_validator, with_bls_key, MockStorage, TestPayload},
 };
-use types::validator_signer::ValidatorSigner;
+use types::{crypto_proxies::ValidatorVerifier, validator_signer::ValidatorSigner};
 
 #[test]
 fn test_basic() {
@@ -128,3 +130,54 @@ fn test_recovery_from_highest_timeout_certificate() {
         true
     );
 }
+
+#[test]
+fn test_aggregated_timeout_certificate() {
+    let signers: Vec<_> = (0..4)
+        .map(|i| with_bls_key(ValidatorSigner::random([i as u8; 32]), [i as u8 + 4; 32]))
+        .collect();
+    let validator = aggregating_validator(&signers.iter().collect::<Vec<_>>());
+    let quorum_size = validator.quorum_size();
+    let mut timeout_manager = PacemakerTimeoutManager::new(
+        HighestTimeoutCertificates::new(None, None),
+        MockStorage::<TestPayload>::start_for_testing()
+            .0
+            .persistent_liveness_storage(),
+    );
+    for (signer, round) in signers.iter().zip(vec![1, 2, 2]) {
+        let timeout = PacemakerTimeout::new(round, signer, None);
+        assert_eq!(timeout.verify(&validator), Ok(()));
+        timeout_manager.update_received_timeout(timeout, quorum_size);
+    }
+
+    // The certificate carries the aggregates of the signatures of each round instead of the
+    // timeouts, which only the validators aggregating their signatures accept.
+    let tc = timeout_manager
+        .highest_timeout_certificate()
+        .unwrap()
+        .clone();
+    assert_eq!(tc.round(), 1);
+    assert!(tc.timeouts().is_empty());
+    assert_eq!(tc.verify(&validator), Ok(()));
+    let non_aggregating_validator = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    );
+    assert_eq!(
+        tc.verify(&non_aggregating_validator),
+        Err(PacemakerTimeoutCertificateVerificationError::SignatureSchemeMismatch)
+    );
+
+    // The aggregated certificate can't be regenerated from its timeouts upon recovery, it is
+    // kept as the highest received one.
+    let timeout_manager = PacemakerTimeoutManager::new(
+        HighestTimeoutCertificates::new(Some(tc.clone()), None),
+        MockStorage::<TestPayload>::start_for_testing()
+            .0
+            .persistent_liveness_storage(),
+    );
+    assert!(timeout_manager.author_to_received_timeouts.is_empty());
+    assert_eq!(timeout_manager.highest_timeout_certificate(), Some(&tc));
+}
@@ -1,10 +1,16 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::chained_bft::{common::Round, consensus_types::block::Block};
+use crate::chained_bft::{
+    common::Round,
+    consensus_types::block::{Block, BlockData},
+};
 
 use crate::{
-    chained_bft::{block_storage::BlockReader, common::Payload},
+    chained_bft::{
+        block_storage::BlockReader, common::Payload,
+        liveness::block_size_controller::BlockSizeController,
+    },
     counters,
     state_replication::TxnManager,
     util::time_service::{wait_if_possible, TimeService, WaitingError, WaitingSuccess},
@@ -59,6 +65,8 @@ pub struct ProposalGenerator<T> {
     time_service: Arc<dyn TimeService>,
     // Max number of transactions to be added to a proposed block.
     max_block_size: u64,
+    // If set, adapts the number of transactions to how fast the blocks get executed.
+    block_size_controller: Option<BlockSizeController>,
     // Support increasing block timestamps
     enforce_increasing_timestamps: bool,
     // Last round that a proposal was generated
@@ -78,11 +86,22 @@ impl<T: Payload> ProposalGenerator<T> {
             txn_manager,
             time_service,
             max_block_size,
+            block_size_controller: None,
             enforce_increasing_timestamps,
             last_round_generated: Mutex::new(0),
         }
     }
 
+    /// Lets the given controller choose the number of transactions of each proposal rather than
+    /// always pulling up to max_block_size.
+    pub fn with_block_size_controller(
+        mut self,
+        block_size_controller: BlockSizeController,
+    ) -> Self {
+        self.block_size_controller = Some(block_size_controller);
+        self
+    }
+
     /// Creates a NIL block proposal extending the highest certified block from the block store.
     pub fn generate_nil_block(&self, round: Round) -> Result<Block<T>, ProposalGenerationError> {
         let hqc_block = self.block_store.highest_certified_block();
@@ -101,8 +120,9 @@ impl<T: Payload> ProposalGenerator<T> {
         ))
     }
 
-    /// The function generates a new proposal block: the returned future is fulfilled when the
-    /// payload is delivered by the TxnManager implementation.  At most one proposal can be
+    /// The function generates the data of a new proposal block, which the caller gets signed:
+    /// the returned future is fulfilled when the payload is delivered by the TxnManager
+    /// implementation.  At most one proposal can be
     /// generated per round (no proposal equivocation allowed).
     /// Errors returned by the TxnManager implementation are propagated to the caller.
     /// The logic for choosing the branch to extend is as follows:
@@ -116,7 +136,7 @@ impl<T: Payload> ProposalGenerator<T> {
         &self,
         round: Round,
         round_deadline: Instant,
-    ) -> Result<Block<T>, ProposalGenerationError> {
+    ) -> Result<BlockData<T>, ProposalGenerationError> {
         {
             let mut last_round_generated = self.last_round_generated.lock().unwrap();
             if *last_round_generated < round {
@@ -219,19 +239,22 @@ impl<T: Payload> ProposalGenerator<T> {
             }
         };
 
+        let max_block_size = match &self.block_size_controller {
+            Some(block_size_controller) => block_size_controller
+                .next_block_size(pending_blocks.len(), self.txn_manager.take_mempool_depth()),
+            None => self.max_block_size,
+        };
+        let txns = if max_block_size == 0 {
+            // The execution fell behind: an empty block still lets the pending blocks commit. The
+            // mempool depth is unknown to the next proposal, which doesn't rely on a stale one.
+            T::default()
+        } else {
+            self.txn_manager
+                .pull_txns(max_block_size, exclude_payload)
+                .await
+                .map_err(|_| ProposalGenerationError::TxnRetrievalError)?
+        };
         let block_store = Arc::clone(&self.block_store);
-        match self
-            .txn_manager
-            .pull_txns(self.max_block_size, exclude_payload)
-            .await
-        {
-            Ok(txns) => Ok(block_store.create_block(
-                &hqc_block,
-                txns,
-                round,
-                block_timestamp.as_micros() as u64,
-            )),
-            Err(_) => Err(ProposalGenerationError::TxnRetrievalError),
-        }
+        Ok(block_store.create_block(&hqc_block, txns, round, block_timestamp.as_micros() as u64))
     }
 }
@@ -5,13 +5,17 @@ use crate::{
     chained_bft::{
         block_storage::BlockReader,
         consensus_types::{quorum_cert::QuorumCert, vote_data::VoteData, vote_msg::VoteMsg},
-        liveness::proposal_generator::{ProposalGenerationError, ProposalGenerator},
+        liveness::{
+            block_size_controller::{BlockSizeController, ExecutionLatencyTracker},
+            proposal_generator::{ProposalGenerationError, ProposalGenerator},
+        },
         test_utils::{
             build_empty_tree, placeholder_ledger_info, MockTransactionManager, TreeInserter,
         },
     },
     util::mock_time_service::SimulatedTimeService,
 };
+use config::config::ProposalBackpressureConfig;
 use futures::executor::block_on;
 use std::{
     sync::Arc,
@@ -90,9 +94,9 @@ fn test_proposal_generation_parent() {
             a1.quorum_cert().grandparent_block_id(),
             a1.quorum_cert().grandparent_block_round(),
         ),
-        block_store.signer().author(),
+        inserter.signer().author(),
         placeholder_ledger_info(),
-        block_store.signer(),
+        inserter.signer(),
     );
     block_store.insert_vote_and_qc(vote_msg_a1, 1);
     let a1_child_res =
@@ -117,9 +121,9 @@ fn test_proposal_generation_parent() {
             b1.quorum_cert().grandparent_block_id(),
             b1.quorum_cert().grandparent_block_round(),
         ),
-        block_store.signer().author(),
+        inserter.signer().author(),
         placeholder_ledger_info(),
-        block_store.signer(),
+        inserter.signer(),
     );
 
     block_store.insert_vote_and_qc(vote_msg_b1, 1);
@@ -159,9 +163,9 @@ fn test_old_proposal_generation() {
             a1.quorum_cert().grandparent_block_id(),
             a1.quorum_cert().grandparent_block_round(),
         ),
-        block_store.signer().author(),
+        inserter.signer().author(),
         placeholder_ledger_info(),
-        block_store.signer(),
+        inserter.signer(),
     );
     block_store.insert_vote_and_qc(vote_msg_a1, 1);
 
@@ -171,3 +175,57 @@ fn test_old_proposal_generation() {
         ProposalGenerationError::GivenRoundTooLow(1)
     );
 }
+
+#[test]
+fn test_proposal_generation_backpressure() {
+    let block_store = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_store.clone());
+    let config = ProposalBackpressureConfig {
+        enabled: true,
+        max_pending_blocks: 1,
+        ..ProposalBackpressureConfig::default()
+    };
+    let proposal_generator = ProposalGenerator::new(
+        block_store.clone(),
+        Arc::new(MockTransactionManager::new()),
+        Arc::new(SimulatedTimeService::new()),
+        5,
+        true,
+    )
+    .with_block_size_controller(BlockSizeController::new(
+        config,
+        5,
+        Arc::new(ExecutionLatencyTracker::new()),
+    ));
+    let genesis = block_store.root();
+
+    // Nothing is pending on top of the root.
+    let proposal = block_on(proposal_generator.generate_proposal(1, minute_from_now())).unwrap();
+    assert_eq!(proposal.payload().len(), 5);
+
+    // Once a1 is certified, the branch to extend has as many uncommitted blocks as allowed.
+    let a1 =
+        inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), genesis.as_ref(), 2);
+    let vote_msg_a1 = VoteMsg::new(
+        VoteData::new(
+            a1.id(),
+            block_store
+                .get_compute_result(a1.id())
+                .unwrap()
+                .executed_state
+                .state_id,
+            a1.round(),
+            a1.quorum_cert().parent_block_id(),
+            a1.quorum_cert().parent_block_round(),
+            a1.quorum_cert().grandparent_block_id(),
+            a1.quorum_cert().grandparent_block_round(),
+        ),
+        inserter.signer().author(),
+        placeholder_ledger_info(),
+        inserter.signer(),
+    );
+    block_store.insert_vote_and_qc(vote_msg_a1, 1);
+    let proposal = block_on(proposal_generator.generate_proposal(3, minute_from_now())).unwrap();
+    assert_eq!(proposal.parent_id(), a1.id());
+    assert!(proposal.payload().is_empty());
+}
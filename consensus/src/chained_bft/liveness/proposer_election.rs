@@ -3,8 +3,9 @@
 
 use crate::chained_bft::{
     common::{Author, Round},
-    consensus_types::block::Block,
+    consensus_types::block::{Block, BlockVerificationError},
 };
+use std::sync::Arc;
 
 /// ProposerElection incorporates the logic of choosing a leader among multiple candidates.
 /// We are open to a possibility for having multiple proposers per round, the ultimate choice
@@ -19,6 +20,30 @@ pub trait ProposerElection<T> {
     /// used by e.g., voters for choosing the destinations for sending their votes to).
     fn get_valid_proposers(&self, round: Round) -> Vec<Author>;
 
+    /// Same as `is_valid_proposer`, for a proposal of the given round extending the given block.
+    /// The proposers usually only depend on the round, which is what the default implementation
+    /// assumes.
+    fn is_valid_proposer_extending(
+        &self,
+        author: Author,
+        round: Round,
+        _parent: &Block<T>,
+    ) -> Option<Author> {
+        self.is_valid_proposer(author, round)
+    }
+
+    /// Same as `get_valid_proposers`, for the proposals of the given round extending the given
+    /// block.
+    fn get_valid_proposers_extending(&self, round: Round, _parent: &Block<T>) -> Vec<Author> {
+        self.get_valid_proposers(round)
+    }
+
+    /// Checks what this election requires from the proposals besides their author, e.g. a proof
+    /// of the randomness the next proposers are drawn from. Most elections don't require anything.
+    fn verify_proposal(&self, _proposal: &Block<T>) -> Result<(), BlockVerificationError> {
+        Ok(())
+    }
+
     /// Notify proposer election about a new proposal. The function doesn't return any information:
     /// proposer election is going to notify the client about the chosen proposal via a dedicated
     /// channel (to be passed in constructor).
@@ -33,4 +58,8 @@ pub trait ProposerElection<T> {
     /// Note that once the backup proposal is taken and no other proposals are submitted, the
     /// following take requests are going to return None.
     fn take_backup_proposal(&mut self, round: Round) -> Option<Block<T>>;
+
+    /// Notify proposer election about the newly committed blocks, from the oldest one. Only the
+    /// elections depending on the history of the committed blocks care about them.
+    fn process_committed_blocks(&mut self, _blocks: &[Arc<Block<T>>]) {}
 }
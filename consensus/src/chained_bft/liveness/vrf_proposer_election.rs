@@ -0,0 +1,101 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Author, Payload, Round},
+    consensus_types::block::{Block, BlockVerificationError},
+    liveness::proposer_election::ProposerElection,
+};
+use crypto::HashValue;
+use std::{convert::TryInto, sync::Arc};
+use types::crypto_proxies::ValidatorVerifier;
+
+/// The VRF proposer picks the proposer of a round at random, using the VRF output carried by the
+/// block that the proposals of the round extend (i.e. the block certified by the previous QC) as
+/// the source of randomness. The output is unique for the author, the parent and the round of a
+/// block, so its author can't choose who is going to propose after it, and nobody can tell who is
+/// going to propose in a round before the block to extend is proposed. Unlike a round-robin
+/// rotation, this doesn't let anybody target the upcoming proposers in advance.
+///
+/// The proposer of a round can't be told from the round alone: `is_valid_proposer` and
+/// `get_valid_proposers` accept every candidate, the blocks must be taken into account with
+/// `is_valid_proposer_extending` and `get_valid_proposers_extending`.
+///
+/// The proposals must carry the VRF proof of their output, made with the VRF key their author
+/// registered in the validator set.
+pub struct VrfProposer {
+    // Candidates to choose from (all honest replicas must agree on this)
+    proposers: Vec<Author>,
+    // The validators of the epoch, to verify the VRF proofs with
+    validators: Arc<ValidatorVerifier>,
+}
+
+impl VrfProposer {
+    pub fn new(proposers: Vec<Author>, validators: Arc<ValidatorVerifier>) -> Self {
+        assert!(!proposers.is_empty());
+        Self {
+            proposers,
+            validators,
+        }
+    }
+
+    fn get_proposer<T: Payload>(&self, round: Round, parent: &Block<T>) -> Author {
+        // NIL and genesis blocks don't carry a VRF output, but nobody can choose their ids either.
+        let mut seed = match parent.vrf_output() {
+            Some(output) => output.to_bytes().to_vec(),
+            None => parent.id().to_vec(),
+        };
+        seed.extend_from_slice(&round.to_le_bytes());
+        let hash = HashValue::from_sha3_256(&seed);
+        let value = u64::from_le_bytes(
+            hash.as_ref()[..8]
+                .try_into()
+                .expect("Hash value is shorter than 8 bytes"),
+        );
+        self.proposers[(value % self.proposers.len() as u64) as usize]
+    }
+}
+
+impl<T: Payload> ProposerElection<T> for VrfProposer {
+    fn is_valid_proposer(&self, author: Author, _round: Round) -> Option<Author> {
+        if self.proposers.contains(&author) {
+            Some(author)
+        } else {
+            None
+        }
+    }
+
+    fn get_valid_proposers(&self, _round: Round) -> Vec<Author> {
+        self.proposers.clone()
+    }
+
+    fn is_valid_proposer_extending(
+        &self,
+        author: Author,
+        round: Round,
+        parent: &Block<T>,
+    ) -> Option<Author> {
+        if self.get_proposer(round, parent) == author {
+            Some(author)
+        } else {
+            None
+        }
+    }
+
+    fn get_valid_proposers_extending(&self, round: Round, parent: &Block<T>) -> Vec<Author> {
+        vec![self.get_proposer(round, parent)]
+    }
+
+    fn verify_proposal(&self, proposal: &Block<T>) -> Result<(), BlockVerificationError> {
+        proposal.verify_vrf_proof(&self.validators)
+    }
+
+    fn process_proposal(&mut self, proposal: Block<T>) -> Option<Block<T>> {
+        // The proposer is checked by the event processor, which knows the parent of the proposal.
+        Some(proposal)
+    }
+
+    fn take_backup_proposal(&mut self, _round: Round) -> Option<Block<T>> {
+        None
+    }
+}
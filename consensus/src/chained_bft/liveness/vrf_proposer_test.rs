@@ -0,0 +1,143 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::Author,
+    consensus_types::{block::Block, quorum_cert::QuorumCert},
+    liveness::{proposer_election::ProposerElection, vrf_proposer_election::VrfProposer},
+    test_utils::with_vrf_key,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
+
+fn make_signers(num_signers: u8) -> (Vec<ValidatorSigner>, Vec<Author>) {
+    let signers: Vec<_> = (0..num_signers)
+        .map(|i| with_vrf_key(ValidatorSigner::random([i; 32]), [100 + i; 32]))
+        .collect();
+    let authors = signers.iter().map(ValidatorSigner::author).collect();
+    (signers, authors)
+}
+
+fn make_validators(signers: &[ValidatorSigner]) -> Arc<ValidatorVerifier> {
+    let author_to_public_keys: HashMap<_, _> = signers
+        .iter()
+        .map(|signer| (signer.author(), signer.public_key()))
+        .collect();
+    let author_to_vrf_public_keys = signers
+        .iter()
+        .map(|signer| (signer.author(), signer.vrf_public_key().unwrap()))
+        .collect();
+    Arc::new(
+        ValidatorVerifier::new(author_to_public_keys)
+            .with_vrf_public_keys(author_to_vrf_public_keys),
+    )
+}
+
+#[test]
+fn test_vrf_proposer() {
+    let (signers, authors) = make_signers(4);
+    let validators = make_validators(&signers);
+    let mut pe: Box<dyn ProposerElection<u32>> =
+        Box::new(VrfProposer::new(authors.clone(), Arc::clone(&validators)));
+    let another_pe: Box<dyn ProposerElection<u32>> =
+        Box::new(VrfProposer::new(authors.clone(), validators));
+    let genesis_block = Block::make_genesis_block();
+    let quorum_cert = QuorumCert::certificate_for_genesis();
+
+    // Without the parent, any candidate may be the proposer.
+    for author in &authors {
+        assert_eq!(pe.is_valid_proposer(*author, 1), Some(*author));
+    }
+    assert_eq!(pe.get_valid_proposers(1), authors);
+    let outsider = ValidatorSigner::random([100u8; 32]).author();
+    assert_eq!(pe.is_valid_proposer(outsider, 1), None);
+
+    // Given the parent, there is a single one on which everybody agrees.
+    let proposers = pe.get_valid_proposers_extending(1, &genesis_block);
+    assert_eq!(proposers.len(), 1);
+    let proposer = proposers[0];
+    assert_eq!(
+        another_pe.get_valid_proposers_extending(1, &genesis_block),
+        proposers
+    );
+    for author in &authors {
+        let expected = if *author == proposer {
+            Some(proposer)
+        } else {
+            None
+        };
+        assert_eq!(
+            pe.is_valid_proposer_extending(*author, 1, &genesis_block),
+            expected
+        );
+    }
+
+    let proposer_index = signers
+        .iter()
+        .position(|signer| signer.author() == proposer)
+        .unwrap();
+    let proposer_signer = &signers[proposer_index];
+    let proposal = Block::make_block(
+        &genesis_block,
+        1,
+        1,
+        1,
+        quorum_cert.clone(),
+        proposer_signer,
+    );
+    assert!(pe.verify_proposal(&proposal).is_ok());
+    assert_eq!(pe.process_proposal(proposal.clone()), Some(proposal));
+    assert_eq!(pe.take_backup_proposal(1), None);
+
+    // The proposals must prove their VRF output.
+    let signer_without_vrf_key = ValidatorSigner::random([proposer_index as u8; 32]);
+    assert_eq!(signer_without_vrf_key.author(), proposer);
+    let proposal_without_proof = Block::make_block(
+        &genesis_block,
+        1,
+        1,
+        1,
+        quorum_cert.clone(),
+        &signer_without_vrf_key,
+    );
+    assert!(pe.verify_proposal(&proposal_without_proof).is_err());
+}
+
+#[test]
+fn test_vrf_proposer_depends_on_parent() {
+    let (signers, authors) = make_signers(4);
+    let pe: Box<dyn ProposerElection<u32>> =
+        Box::new(VrfProposer::new(authors.clone(), make_validators(&signers)));
+    let genesis_block = Block::make_genesis_block();
+    let quorum_cert = QuorumCert::certificate_for_genesis();
+    let parents: Vec<_> = signers
+        .iter()
+        .map(|signer| Block::make_block(&genesis_block, 1, 1, 1, quorum_cert.clone(), signer))
+        .collect();
+
+    // The author of the parent can't influence the proposers by changing the block content.
+    let another_parent =
+        Block::make_block(&genesis_block, 2, 1, 2, quorum_cert.clone(), &signers[0]);
+    for round in 2..20 {
+        assert_eq!(
+            pe.get_valid_proposers_extending(round, &parents[0]),
+            pe.get_valid_proposers_extending(round, &another_parent)
+        );
+    }
+
+    // Every candidate gets elected, and the sequences of proposers differ from one parent to
+    // another.
+    let mut sequences = HashSet::new();
+    for parent in &parents {
+        let sequence: Vec<_> = (2..100)
+            .map(|round| pe.get_valid_proposers_extending(round, parent)[0])
+            .collect();
+        let elected: HashSet<_> = sequence.iter().cloned().collect();
+        assert_eq!(elected.len(), authors.len());
+        sequences.insert(sequence);
+    }
+    assert_eq!(sequences.len(), parents.len());
+}
@@ -9,8 +9,17 @@ mod safety;
 mod block_storage;
 pub mod chained_bft_consensus_provider;
 pub use consensus_types::quorum_cert::QuorumCert;
+pub use consensusdb::inspector::{ConsensusDbInspector, ConsensusDbReport};
+pub use observer::{ConsensusObserver, ConsensusPublisher};
+pub use recorder::read_recorded_runs;
+pub use replay::ReplayNode;
+pub use safety::safety_rules_node::SafetyRulesNode;
 mod chained_bft_smr;
+mod execution_pipeline;
 mod network;
+mod observer;
+mod recorder;
+mod replay;
 
 pub mod epoch_manager;
 pub mod persistent_storage;
@@ -7,12 +7,15 @@ use crate::{
         common::{Author, Payload},
         consensus_types::{
             block::Block,
+            commit_vote::CommitVote,
             proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
             sync_info::SyncInfo,
             timeout_msg::TimeoutMsg,
             vote_msg::VoteMsg,
         },
         epoch_manager::EpochManager,
+        observer::ConsensusPublisher,
+        recorder::ConsensusRecorder,
     },
     counters,
 };
@@ -26,7 +29,10 @@ use futures::{
 };
 use logger::prelude::*;
 use network::{
-    proto::{BlockRetrievalStatus, ConsensusMsg, RequestBlock, RespondBlock},
+    proto::{
+        BlockRetrievalStatus, ConsensusMsg, EpochChangeProof, RequestBlock, RequestEpoch,
+        RespondBlock,
+    },
     validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event, RpcError},
 };
 use proto_conv::{FromProto, IntoProto};
@@ -36,7 +42,7 @@ use std::{
     time::{Duration, Instant},
 };
 use tokio::runtime::TaskExecutor;
-use types::account_address::AccountAddress;
+use types::{account_address::AccountAddress, crypto_proxies::LedgerInfoWithSignatures};
 
 /// The response sent back from EventProcessor for the BlockRetrievalRequest.
 #[derive(Debug)]
@@ -86,6 +92,11 @@ pub struct NetworkReceivers<T> {
     pub block_retrieval: channel::Receiver<BlockRetrievalRequest<T>>,
     pub timeout_msgs: channel::Receiver<TimeoutMsg>,
     pub sync_info_msgs: channel::Receiver<(SyncInfo, AccountAddress)>,
+    pub commit_votes: channel::Receiver<CommitVote>,
+    /// The epochs of the peers which sent messages of another epoch or requested the ledger infos
+    /// ending the epochs from theirs on.
+    pub different_epoch: channel::Receiver<(u64, AccountAddress)>,
+    pub epoch_change_proofs: channel::Receiver<(Vec<LedgerInfoWithSignatures>, AccountAddress)>,
 }
 
 /// Implements the actual networking support for all consensus messaging.
@@ -99,6 +110,10 @@ pub struct ConsensusNetworkImpl {
     self_sender: channel::Sender<Result<Event<ConsensusMsg>, failure::Error>>,
     self_receiver: Option<channel::Receiver<Result<Event<ConsensusMsg>, failure::Error>>>,
     epoch_mgr: Arc<EpochManager>,
+    // Records the outbound messages and the responses to the block retrieval requests if set.
+    recorder: Option<Arc<ConsensusRecorder>>,
+    // Pushes the committed blocks to the subscribed full nodes if set.
+    publisher: Option<Arc<ConsensusPublisher>>,
 }
 
 impl Clone for ConsensusNetworkImpl {
@@ -110,6 +125,8 @@ impl Clone for ConsensusNetworkImpl {
             self_sender: self.self_sender.clone(),
             self_receiver: None,
             epoch_mgr: Arc::clone(&self.epoch_mgr),
+            recorder: self.recorder.clone(),
+            publisher: self.publisher.clone(),
         }
     }
 }
@@ -129,9 +146,32 @@ impl ConsensusNetworkImpl {
             self_sender,
             self_receiver: Some(self_receiver),
             epoch_mgr,
+            recorder: None,
+            publisher: None,
         }
     }
 
+    /// Turns on the recording mode: the recorder gets all the messages sent by the validator and
+    /// the responses to its block retrieval requests.
+    pub fn with_recorder(mut self, recorder: Arc<ConsensusRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn recorder(&self) -> Option<Arc<ConsensusRecorder>> {
+        self.recorder.clone()
+    }
+
+    /// Pushes the blocks that the validator commits to the full nodes subscribed to it.
+    pub fn with_publisher(mut self, publisher: Arc<ConsensusPublisher>) -> Self {
+        self.publisher = Some(publisher);
+        self
+    }
+
+    pub fn publisher(&self) -> Option<Arc<ConsensusPublisher>> {
+        self.publisher.clone()
+    }
+
     /// Establishes the initial connections with the peers and returns the receivers.
     pub fn start<T: Payload>(&mut self, executor: &TaskExecutor) -> NetworkReceivers<T> {
         let (proposal_tx, proposal_rx) = channel::new(1_024, &counters::PENDING_PROPOSAL);
@@ -141,6 +181,11 @@ impl ConsensusNetworkImpl {
         let (timeout_msg_tx, timeout_msg_rx) =
             channel::new(1_024, &counters::PENDING_NEW_ROUND_MESSAGES);
         let (sync_info_tx, sync_info_rx) = channel::new(1_024, &counters::PENDING_SYNC_INFO_MSGS);
+        let (commit_vote_tx, commit_vote_rx) = channel::new(1_024, &counters::PENDING_COMMIT_VOTES);
+        let (different_epoch_tx, different_epoch_rx) =
+            channel::new(1_024, &counters::PENDING_DIFFERENT_EPOCH_MSGS);
+        let (epoch_change_proof_tx, epoch_change_proof_rx) =
+            channel::new(1_024, &counters::PENDING_EPOCH_CHANGE_PROOFS);
         let network_events = self
             .network_events
             .take()
@@ -158,6 +203,9 @@ impl ConsensusNetworkImpl {
                 block_request_tx,
                 timeout_msg_tx,
                 sync_info_tx,
+                commit_vote_tx,
+                different_epoch_tx,
+                epoch_change_proof_tx,
                 all_events,
                 epoch_mgr: Arc::clone(&self.epoch_mgr),
             }
@@ -172,6 +220,9 @@ impl ConsensusNetworkImpl {
             block_retrieval: block_request_rx,
             timeout_msgs: timeout_msg_rx,
             sync_info_msgs: sync_info_rx,
+            commit_votes: commit_vote_rx,
+            different_epoch: different_epoch_rx,
+            epoch_change_proofs: epoch_change_proof_rx,
         }
     }
 
@@ -194,20 +245,30 @@ impl ConsensusNetworkImpl {
         counters::BLOCK_RETRIEVAL_COUNT.inc_by(num_blocks as i64);
         let pre_retrieval_instant = Instant::now();
 
-        let mut res_block = self
+        let response = self
             .network_sender
             .request_block(from, req_msg, timeout)
-            .await?;
+            .await;
+        if let Some(recorder) = &self.recorder {
+            recorder.record_block_retrieval_response(response.as_ref().ok());
+        }
+        let mut res_block = response?;
+        let validators = self.epoch_mgr.validators();
+        let epoch_ending_ledger_info = self.epoch_mgr.epoch_ending_ledger_info();
+        let epoch_ending_ledger_info = epoch_ending_ledger_info.as_ref().map(Arc::as_ref);
         let mut blocks = vec![];
         for block in res_block.take_blocks().into_iter() {
             match Block::from_proto(block) {
                 Ok(block) => {
                     block
-                        .validate_signatures(self.epoch_mgr.validators().as_ref())
+                        .validate_signatures(validators.as_ref(), epoch_ending_ledger_info)
                         .map_err(|_| BlockRetrievalFailure::InvalidSignature)?;
-                    block
-                        .verify_well_formed()
-                        .map_err(|_| BlockRetrievalFailure::InvalidResponse)?;
+                    // The genesis of the epoch isn't built like the following blocks.
+                    if !block.is_epoch_genesis_block(epoch_ending_ledger_info) {
+                        block
+                            .verify_well_formed()
+                            .map_err(|_| BlockRetrievalFailure::InvalidResponse)?;
+                    }
                     blocks.push(block);
                 }
                 _ => {
@@ -241,7 +302,11 @@ impl ConsensusNetworkImpl {
     }
 
     async fn broadcast(&mut self, msg: ConsensusMsg) {
-        for peer in self.epoch_mgr.validators().get_ordered_account_addresses() {
+        let peers = self.epoch_mgr.validators().get_ordered_account_addresses();
+        if let Some(recorder) = &self.recorder {
+            recorder.record_outbound(peers.clone(), &msg);
+        }
+        for peer in peers {
             if self.author == peer {
                 let self_msg = Event::Message((self.author, msg.clone()));
                 if let Err(err) = self.self_sender.send(Ok(self_msg)).await {
@@ -271,6 +336,9 @@ impl ConsensusNetworkImpl {
         let mut self_sender = self.self_sender.clone();
         let mut msg = ConsensusMsg::new();
         msg.set_vote(vote_msg.into_proto());
+        if let Some(recorder) = &self.recorder {
+            recorder.record_outbound(recipients.clone(), &msg);
+        }
         for peer in recipients {
             if self.author == peer {
                 let self_msg = Event::Message((self.author, msg.clone()));
@@ -292,6 +360,14 @@ impl ConsensusNetworkImpl {
         self.broadcast(msg).await
     }
 
+    /// Broadcasts the commit vote to all validators (including itself), in the decoupled execution
+    /// mode.
+    pub async fn broadcast_commit_vote(&mut self, commit_vote: CommitVote) {
+        let mut msg = ConsensusMsg::new();
+        msg.set_commit_vote(commit_vote.into_proto());
+        self.broadcast(msg).await
+    }
+
     /// Sends the given sync info to the given author.
     /// The future is fulfilled as soon as the message is added to the internal network channel
     /// (does not indicate whether the message is delivered or sent out).
@@ -302,6 +378,9 @@ impl ConsensusNetworkImpl {
         }
         let mut msg = ConsensusMsg::new();
         msg.set_sync_info(sync_info.into_proto());
+        if let Some(recorder) = &self.recorder {
+            recorder.record_outbound(vec![recipient], &msg);
+        }
         let mut network_sender = self.network_sender.clone();
         if let Err(e) = network_sender.send_to(recipient, msg).await {
             warn!(
@@ -310,6 +389,52 @@ impl ConsensusNetworkImpl {
             );
         }
     }
+
+    /// Requests the ledger infos ending the epochs from the given one on to the given author,
+    /// which is in a later epoch.
+    pub async fn request_epoch(&self, start_epoch: u64, recipient: Author) {
+        let mut request = RequestEpoch::new();
+        request.set_start_epoch(start_epoch);
+        let mut msg = ConsensusMsg::new();
+        msg.set_request_epoch(request);
+        self.send_to(msg, recipient).await
+    }
+
+    /// Sends the given ledger infos ending consecutive epochs to the given author, which is in an
+    /// earlier epoch.
+    pub async fn send_epoch_change_proof(
+        &self,
+        epoch_change_proof: Vec<LedgerInfoWithSignatures>,
+        recipient: Author,
+    ) {
+        let mut proof = EpochChangeProof::new();
+        proof.set_ledger_info_with_sigs(::protobuf::RepeatedField::from_vec(
+            epoch_change_proof
+                .into_iter()
+                .map(IntoProto::into_proto)
+                .collect(),
+        ));
+        let mut msg = ConsensusMsg::new();
+        msg.set_epoch_change_proof(proof);
+        self.send_to(msg, recipient).await
+    }
+
+    async fn send_to(&self, msg: ConsensusMsg, recipient: Author) {
+        if recipient == self.author {
+            error!("An attempt to deliver an epoch msg to itself: ignore.");
+            return;
+        }
+        if let Some(recorder) = &self.recorder {
+            recorder.record_outbound(vec![recipient], &msg);
+        }
+        let mut network_sender = self.network_sender.clone();
+        if let Err(e) = network_sender.send_to(recipient, msg).await {
+            warn!(
+                "Failed to send an epoch msg to peer {:?}: {:?}",
+                recipient, e
+            );
+        }
+    }
 }
 
 struct NetworkTask<T, S> {
@@ -318,6 +443,9 @@ struct NetworkTask<T, S> {
     block_request_tx: channel::Sender<BlockRetrievalRequest<T>>,
     timeout_msg_tx: channel::Sender<TimeoutMsg>,
     sync_info_tx: channel::Sender<(SyncInfo, AccountAddress)>,
+    commit_vote_tx: channel::Sender<CommitVote>,
+    different_epoch_tx: channel::Sender<(u64, AccountAddress)>,
+    epoch_change_proof_tx: channel::Sender<(Vec<LedgerInfoWithSignatures>, AccountAddress)>,
     all_events: S,
     epoch_mgr: Arc<EpochManager>,
 }
@@ -332,7 +460,7 @@ where
             match message {
                 Event::Message((peer_id, mut msg)) => {
                     let r = if msg.has_proposal() {
-                        self.process_proposal(&mut msg).await.map_err(|e| {
+                        self.process_proposal(&mut msg, peer_id).await.map_err(|e| {
                             security_log(SecurityEvent::InvalidConsensusProposal)
                                 .error(&e)
                                 .data(&msg)
@@ -342,9 +470,19 @@ where
                     } else if msg.has_vote() {
                         self.process_vote(&mut msg).await
                     } else if msg.has_timeout_msg() {
-                        self.process_timeout_msg(&mut msg).await
+                        self.process_timeout_msg(&mut msg, peer_id).await
                     } else if msg.has_sync_info() {
                         self.process_sync_info(&mut msg, peer_id).await
+                    } else if msg.has_commit_vote() {
+                        self.process_commit_vote(&mut msg).await
+                    } else if msg.has_request_epoch() {
+                        let start_epoch = msg.get_request_epoch().get_start_epoch();
+                        self.different_epoch_tx
+                            .send((start_epoch, peer_id))
+                            .await
+                            .map_err(Into::into)
+                    } else if msg.has_epoch_change_proof() {
+                        self.process_epoch_change_proof(&mut msg, peer_id).await
                     } else {
                         warn!("Unexpected msg from {}: {:?}", peer_id, msg);
                         continue;
@@ -374,10 +512,41 @@ where
         }
     }
 
-    async fn process_proposal<'a>(&'a mut self, msg: &'a mut ConsensusMsg) -> failure::Result<()> {
+    /// Hands the message of another epoch, which can't be verified in the current one, over to
+    /// the event loop so that either the peer or the validator catches up with the epochs.
+    /// Returns whether the message is of another epoch.
+    async fn process_different_epoch(
+        &mut self,
+        epoch: u64,
+        peer: AccountAddress,
+    ) -> failure::Result<bool> {
+        let current_epoch = self.epoch_mgr.epoch() as u64;
+        if epoch == current_epoch {
+            return Ok(false);
+        }
+        debug!(
+            "Received a msg of epoch {} from {} in epoch {}",
+            epoch, peer, current_epoch
+        );
+        self.different_epoch_tx.send((epoch, peer)).await?;
+        Ok(true)
+    }
+
+    async fn process_proposal<'a>(
+        &'a mut self,
+        msg: &'a mut ConsensusMsg,
+        peer: AccountAddress,
+    ) -> failure::Result<()> {
         let proposal = ProposalUncheckedSignatures::<T>::from_proto(msg.take_proposal())?;
+        if self.process_different_epoch(proposal.epoch(), peer).await? {
+            return Ok(());
+        }
+        let epoch_ending_ledger_info = self.epoch_mgr.epoch_ending_ledger_info();
         let proposal = proposal
-            .validate_signatures(self.epoch_mgr.validators().as_ref())?
+            .validate_signatures(
+                self.epoch_mgr.validators().as_ref(),
+                epoch_ending_ledger_info.as_ref().map(Arc::as_ref),
+            )?
             .verify_well_formed()?;
         debug!("Received proposal {}", proposal);
         self.proposal_tx.send(proposal).await?;
@@ -402,8 +571,15 @@ where
     async fn process_timeout_msg<'a>(
         &'a mut self,
         msg: &'a mut ConsensusMsg,
+        peer: AccountAddress,
     ) -> failure::Result<()> {
         let timeout_msg = TimeoutMsg::from_proto(msg.take_timeout_msg())?;
+        if self
+            .process_different_epoch(timeout_msg.sync_info().epoch(), peer)
+            .await?
+        {
+            return Ok(());
+        }
         timeout_msg
             .verify(self.epoch_mgr.validators().as_ref())
             .map_err(|e| {
@@ -423,8 +599,18 @@ where
         peer: AccountAddress,
     ) -> failure::Result<()> {
         let sync_info = SyncInfo::from_proto(msg.take_sync_info())?;
+        if self
+            .process_different_epoch(sync_info.epoch(), peer)
+            .await?
+        {
+            return Ok(());
+        }
+        let epoch_ending_ledger_info = self.epoch_mgr.epoch_ending_ledger_info();
         sync_info
-            .verify(self.epoch_mgr.validators().as_ref())
+            .verify(
+                self.epoch_mgr.validators().as_ref(),
+                epoch_ending_ledger_info.as_ref().map(Arc::as_ref),
+            )
             .map_err(|e| {
                 security_log(SecurityEvent::InvalidSyncInfoMsg)
                     .error(&e)
@@ -436,6 +622,44 @@ where
         Ok(())
     }
 
+    async fn process_commit_vote<'a>(
+        &'a mut self,
+        msg: &'a mut ConsensusMsg,
+    ) -> failure::Result<()> {
+        let commit_vote = CommitVote::from_proto(msg.take_commit_vote())?;
+        debug!("Received {}", commit_vote);
+        commit_vote
+            .verify(self.epoch_mgr.validators().as_ref())
+            .map_err(|e| {
+                security_log(SecurityEvent::InvalidConsensusVote)
+                    .error(&e)
+                    .data(&commit_vote)
+                    .log();
+                e
+            })?;
+        self.commit_vote_tx.send(commit_vote).await?;
+        Ok(())
+    }
+
+    /// The ledger infos are verified by the event loop against the validators of the epoch they
+    /// start from.
+    async fn process_epoch_change_proof<'a>(
+        &'a mut self,
+        msg: &'a mut ConsensusMsg,
+        peer: AccountAddress,
+    ) -> failure::Result<()> {
+        let epoch_change_proof = msg
+            .take_epoch_change_proof()
+            .take_ledger_info_with_sigs()
+            .into_iter()
+            .map(LedgerInfoWithSignatures::from_proto)
+            .collect::<failure::Result<Vec<_>>>()?;
+        self.epoch_change_proof_tx
+            .send((epoch_change_proof, peer))
+            .await?;
+        Ok(())
+    }
+
     async fn process_request_block<'a>(
         &'a mut self,
         msg: &'a mut ConsensusMsg,
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
-    common::Author,
+    common::{Author, Round},
     consensus_types::{
         block::Block, proposal_msg::ProposalMsg, quorum_cert::QuorumCert, sync_info::SyncInfo,
         vote_data::VoteData, vote_msg::VoteMsg,
@@ -18,7 +18,7 @@ use futures::{channel::mpsc, executor::block_on, FutureExt, SinkExt, StreamExt,
 use network::{
     interface::{NetworkNotification, NetworkRequest},
     proto::{BlockRetrievalStatus, ConsensusMsg},
-    protocols::rpc::InboundRpcRequest,
+    protocols::{direct_send::Message, rpc::InboundRpcRequest},
     validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender},
 };
 use std::{
@@ -29,26 +29,50 @@ use std::{
 use tokio::runtime::TaskExecutor;
 use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
 
+/// Identifies a node of the `NetworkPlayground`. A validator usually runs a single node, but a
+/// test can run several instances of it (twins) that share its author and keys in order to
+/// simulate equivocation: each twin is a separate node, which can be partitioned on its own.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TwinId {
+    /// Distinguishes the twins of a validator, 0 for its original node.
+    pub id: usize,
+    pub author: Author,
+}
+
+impl From<Author> for TwinId {
+    fn from(author: Author) -> Self {
+        TwinId { id: 0, author }
+    }
+}
+
 /// `NetworkPlayground` mocks the network implementation and provides convenience
 /// methods for testing. Test clients can use `wait_for_messages` or
 /// `deliver_messages` to inspect the direct-send messages sent between peers.
-/// They can also configure network messages to be dropped between specific peers.
+/// They can also configure network messages to be dropped between specific peers,
+/// either for all the rounds or for given rounds only.
+///
+/// A direct-send message to an author is delivered to each of its twins that doesn't drop it.
+/// The messages that a node sends to its own author stay local to the node, hence twins don't
+/// hear from each other.
 ///
 /// Currently, RPC messages are delivered immediately and are not controlled by
 /// `wait_for_messages` or `deliver_messages` for delivery. They are also not
-/// currently dropped according to the `NetworkPlayground`'s drop config.
+/// dropped according to the per round drop config, and go to the original node of the
+/// destination.
 pub struct NetworkPlayground {
-    /// Maps each Author to a Sender of their inbound network notifications.
+    /// Maps each node to a Sender of their inbound network notifications.
     /// These events will usually be handled by the event loop spawned in
     /// `ConsensusNetworkImpl`.
-    node_consensus_txs: Arc<Mutex<HashMap<Author, channel::Sender<NetworkNotification>>>>,
+    node_consensus_txs: Arc<Mutex<HashMap<TwinId, channel::Sender<NetworkNotification>>>>,
     /// Nodes' outbound handlers forward their outbound non-rpc messages to this
     /// queue.
-    outbound_msgs_tx: mpsc::Sender<(Author, NetworkRequest)>,
+    outbound_msgs_tx: mpsc::Sender<(TwinId, NetworkRequest)>,
     /// NetworkPlayground reads all nodes' outbound messages through this queue.
-    outbound_msgs_rx: mpsc::Receiver<(Author, NetworkRequest)>,
+    outbound_msgs_rx: mpsc::Receiver<(TwinId, NetworkRequest)>,
     /// Allow test code to drop direct-send messages between peers.
     drop_config: Arc<RwLock<DropConfig>>,
+    /// Allow test code to drop the direct-send messages of given rounds between nodes.
+    drop_config_round: Arc<RwLock<DropConfigRound>>,
     /// An executor for spawning node outbound network event handlers
     executor: TaskExecutor,
 }
@@ -62,6 +86,7 @@ impl NetworkPlayground {
             outbound_msgs_tx,
             outbound_msgs_rx,
             drop_config: Arc::new(RwLock::new(DropConfig(HashMap::new()))),
+            drop_config_round: Arc::new(RwLock::new(DropConfigRound(HashMap::new()))),
             executor,
         }
     }
@@ -76,16 +101,16 @@ impl NetworkPlayground {
     /// they don't block.
     async fn start_node_outbound_handler(
         drop_config: Arc<RwLock<DropConfig>>,
-        src: Author,
+        src: TwinId,
         mut network_reqs_rx: channel::Receiver<NetworkRequest>,
-        mut outbound_msgs_tx: mpsc::Sender<(Author, NetworkRequest)>,
-        node_consensus_txs: Arc<Mutex<HashMap<Author, channel::Sender<NetworkNotification>>>>,
+        mut outbound_msgs_tx: mpsc::Sender<(TwinId, NetworkRequest)>,
+        node_consensus_txs: Arc<Mutex<HashMap<TwinId, channel::Sender<NetworkNotification>>>>,
     ) {
         while let Some(net_req) = network_reqs_rx.next().await {
             let drop_rpc = drop_config
                 .read()
                 .unwrap()
-                .is_message_dropped(&src, &net_req);
+                .is_message_dropped(&src.author, &net_req);
             match net_req {
                 // Immediately forward rpc requests for handling. Unfortunately,
                 // we can't handle rpc requests in `deliver_messages` due to
@@ -104,7 +129,7 @@ impl NetworkPlayground {
                     let mut node_consensus_tx = node_consensus_txs
                         .lock()
                         .unwrap()
-                        .get(&dst)
+                        .get(&TwinId::from(dst))
                         .unwrap()
                         .clone();
 
@@ -115,7 +140,7 @@ impl NetworkPlayground {
                     };
 
                     node_consensus_tx
-                        .send(NetworkNotification::RecvRpc(src, inbound_req))
+                        .send(NetworkNotification::RecvRpc(src.author, inbound_req))
                         .await
                         .unwrap();
                 }
@@ -139,16 +164,27 @@ impl NetworkPlayground {
         // `Sender` side of this queue is usually wrapped in a
         // `ConsensusNetworkSender` adapter.
         network_reqs_rx: channel::Receiver<NetworkRequest>,
+    ) {
+        self.add_twin(TwinId::from(author), consensus_tx, network_reqs_rx);
+    }
+
+    /// Add a new node to the NetworkPlayground, possibly a twin of a validator that already
+    /// runs a node. The drop config of the author applies to all of its twins.
+    pub fn add_twin(
+        &mut self,
+        twin_id: TwinId,
+        consensus_tx: channel::Sender<NetworkNotification>,
+        network_reqs_rx: channel::Receiver<NetworkRequest>,
     ) {
         self.node_consensus_txs
             .lock()
             .unwrap()
-            .insert(author, consensus_tx);
-        self.drop_config.write().unwrap().add_node(author);
+            .insert(twin_id, consensus_tx);
+        self.drop_config.write().unwrap().add_node(twin_id.author);
 
         let fut = NetworkPlayground::start_node_outbound_handler(
             Arc::clone(&self.drop_config),
-            author,
+            twin_id,
             network_reqs_rx,
             self.outbound_msgs_tx.clone(),
             self.node_consensus_txs.clone(),
@@ -156,19 +192,28 @@ impl NetworkPlayground {
         self.executor.spawn(fut.boxed().unit_error().compat());
     }
 
-    /// Deliver a `NetworkRequest` from peer `src` to the destination peer.
+    /// Returns the nodes that run for the given author, ordered by twin id.
+    fn twins_of(&self, author: Author) -> Vec<TwinId> {
+        let mut twin_ids: Vec<_> = self
+            .node_consensus_txs
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|twin_id| twin_id.author == author)
+            .cloned()
+            .collect();
+        twin_ids.sort_by_key(|twin_id| twin_id.id);
+        twin_ids
+    }
+
+    /// Deliver a direct-send message from peer `src` to the destination node.
     /// Returns a copy of the delivered message and the sending peer id.
     async fn deliver_message(
         &mut self,
         src: Author,
-        msg: NetworkRequest,
+        dst: TwinId,
+        msg: Message,
     ) -> (Author, ConsensusMsg) {
-        // extract destination peer
-        let dst = match &msg {
-            NetworkRequest::SendMessage(dst, _) => *dst,
-            msg => panic!("[network playground] Unexpected NetworkRequest: {:?}", msg),
-        };
-
         // get his sender
         let mut node_consensus_tx = self
             .node_consensus_txs
@@ -178,32 +223,21 @@ impl NetworkPlayground {
             .unwrap()
             .clone();
 
-        // convert NetworkRequest to corresponding NetworkNotification
-        let msg_notif = match msg {
-            NetworkRequest::SendMessage(_dst, msg) => NetworkNotification::RecvMessage(src, msg),
-            msg => panic!("[network playground] Unexpected NetworkRequest: {:?}", msg),
-        };
-
         // copy message data
-        let msg_copy = match &msg_notif {
-            NetworkNotification::RecvMessage(src, msg) => {
-                let msg: ConsensusMsg = ::protobuf::parse_from_bytes(msg.mdata.as_ref()).unwrap();
-                (*src, msg)
-            }
-            msg_notif => panic!(
-                "[network playground] Unexpected NetworkNotification: {:?}",
-                msg_notif
-            ),
-        };
+        let msg_copy: ConsensusMsg = ::protobuf::parse_from_bytes(msg.mdata.as_ref()).unwrap();
 
-        node_consensus_tx.send(msg_notif).await.unwrap();
-        msg_copy
+        node_consensus_tx
+            .send(NetworkNotification::RecvMessage(src, msg))
+            .await
+            .unwrap();
+        (src, msg_copy)
     }
 
     /// Wait for exactly `num_messages` to be enqueued and delivered. Return a
     /// copy of all messages for verification.
     /// While all the sent messages are delivered, only the messages that satisfy the given
-    /// msg inspector are counted.
+    /// msg inspector are counted. A message delivered to several twins is counted once per twin,
+    /// so that a few more messages can be returned when the last one has twins.
     pub async fn wait_for_messages<F>(
         &mut self,
         num_messages: usize,
@@ -219,14 +253,32 @@ impl NetworkPlayground {
                 .expect("[network playground] waiting for messages, but message queue has shutdown unexpectedly");
 
             // Deliver and copy message it if it's not dropped
-            if !self.is_message_dropped(&src, &net_req) {
-                let msg_copy = self.deliver_message(src, net_req).await;
+            if self.is_message_dropped(&src.author, &net_req) {
+                continue;
+            }
+            let (dst, msg) = match net_req {
+                NetworkRequest::SendMessage(dst, msg) => (dst, msg),
+                msg => panic!("[network playground] Unexpected NetworkRequest: {:?}", msg),
+            };
+            let round = message_round(&::protobuf::parse_from_bytes(msg.mdata.as_ref()).unwrap());
+            for dst_twin in self.twins_of(dst) {
+                if round.map_or(false, |round| {
+                    self.drop_config_round
+                        .read()
+                        .unwrap()
+                        .is_message_dropped(&src, &dst_twin, round)
+                }) {
+                    continue;
+                }
+                let msg_copy = self
+                    .deliver_message(src.author, dst_twin, msg.clone())
+                    .await;
                 if msg_inspector(&msg_copy) {
                     msg_copies.push(msg_copy);
                 }
             }
         }
-        assert_eq!(msg_copies.len(), num_messages);
+        assert!(msg_copies.len() >= num_messages);
         msg_copies
     }
 
@@ -277,6 +329,63 @@ impl NetworkPlayground {
             .unwrap()
             .stop_drop_message_for(src, dst)
     }
+
+    /// Drops the direct-send messages of the given round from node `src` to node `dst`.
+    pub fn drop_message_for_round(&mut self, round: Round, src: TwinId, dst: TwinId) -> bool {
+        self.drop_config_round
+            .write()
+            .unwrap()
+            .drop_message_for(round, src, dst)
+    }
+
+    /// Splits the nodes into the given partitions for the given round: the messages of the
+    /// round are dropped between nodes that don't share a partition. The partitions may overlap,
+    /// in which case the nodes they share communicate with all the nodes of each. The nodes that
+    /// aren't part of any partition are not affected.
+    pub fn split_network_round(&mut self, round: Round, partitions: &[Vec<TwinId>]) {
+        let nodes: HashSet<TwinId> = partitions.iter().flatten().cloned().collect();
+        for src in &nodes {
+            for dst in &nodes {
+                if !partitions
+                    .iter()
+                    .any(|partition| partition.contains(src) && partition.contains(dst))
+                {
+                    self.drop_message_for_round(round, *src, *dst);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the round that a message belongs to: the round of the proposed block, of the vote or
+/// of the timeout, and the round that the sender of a sync info is at. The commit votes belong to
+/// no round.
+fn message_round(msg: &ConsensusMsg) -> Option<Round> {
+    if msg.has_proposal() {
+        Some(msg.get_proposal().get_proposed_block().get_round())
+    } else if msg.has_vote() {
+        Some(msg.get_vote().get_vote_data().get_round())
+    } else if msg.has_timeout_msg() {
+        Some(msg.get_timeout_msg().get_pacemaker_timeout().get_round())
+    } else if msg.has_sync_info() {
+        let sync_info = msg.get_sync_info();
+        Some(
+            std::cmp::max(
+                sync_info
+                    .get_highest_quorum_cert()
+                    .get_vote_data()
+                    .get_round(),
+                sync_info.get_highest_timeout_cert().get_round(),
+            ) + 1,
+        )
+    } else if msg.has_commit_vote() {
+        None
+    } else {
+        panic!(
+            "[network playground] Unexpected direct-send message: {:?}",
+            msg
+        )
+    }
 }
 
 struct DropConfig(HashMap<Author, HashSet<Author>>);
@@ -303,6 +412,27 @@ impl DropConfig {
     }
 }
 
+/// The messages dropped between nodes, per round.
+struct DropConfigRound(HashMap<Round, HashMap<TwinId, HashSet<TwinId>>>);
+
+impl DropConfigRound {
+    fn is_message_dropped(&self, src: &TwinId, dst: &TwinId, round: Round) -> bool {
+        self.0
+            .get(&round)
+            .and_then(|drops| drops.get(src))
+            .map_or(false, |dsts| dsts.contains(dst))
+    }
+
+    fn drop_message_for(&mut self, round: Round, src: TwinId, dst: TwinId) -> bool {
+        self.0
+            .entry(round)
+            .or_insert_with(HashMap::new)
+            .entry(src)
+            .or_insert_with(HashSet::new)
+            .insert(dst)
+    }
+}
+
 #[test]
 fn test_network_api() {
     let runtime = consensus_runtime();
@@ -448,3 +578,89 @@ fn test_rpc() {
         assert_eq!(response.blocks[0], *genesis);
     });
 }
+
+#[test]
+fn test_twins_round_partitions() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let signers: Vec<_> = (0..2)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect();
+    let author_to_public_keys = signers
+        .iter()
+        .map(|signer| (signer.author(), signer.public_key()))
+        .collect();
+    let epoch_mgr = Arc::new(EpochManager::new(
+        0,
+        ValidatorVerifier::new(author_to_public_keys),
+    ));
+    // The first validator runs two twins.
+    let twin_ids = vec![
+        TwinId {
+            id: 0,
+            author: signers[0].author(),
+        },
+        TwinId {
+            id: 1,
+            author: signers[0].author(),
+        },
+        TwinId::from(signers[1].author()),
+    ];
+    let mut nodes = Vec::new();
+    let mut receivers: Vec<NetworkReceivers<u64>> = Vec::new();
+    for twin_id in &twin_ids {
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        let network_sender = ConsensusNetworkSender::new(network_reqs_tx);
+        let network_events = ConsensusNetworkEvents::new(consensus_rx);
+
+        playground.add_twin(*twin_id, consensus_tx, network_reqs_rx);
+        let mut node = ConsensusNetworkImpl::new(
+            twin_id.author,
+            network_sender,
+            network_events,
+            Arc::clone(&epoch_mgr),
+        );
+        receivers.push(node.start(&runtime.executor()));
+        nodes.push(node);
+    }
+    // The second twin is cut from the other validator in round 1 only.
+    playground.split_network_round(1, &[vec![twin_ids[0], twin_ids[2]], vec![twin_ids[1]]]);
+
+    let vote_for_round = |round| {
+        VoteMsg::new(
+            VoteData::new(
+                HashValue::random(),
+                ExecutedState::state_for_genesis().state_id,
+                round,
+                HashValue::random(),
+                0,
+                HashValue::random(),
+                0,
+            ),
+            signers[1].author(),
+            placeholder_ledger_info(),
+            &signers[1],
+        )
+    };
+    let vote_1 = vote_for_round(1);
+    let vote_2 = vote_for_round(2);
+    let recipients = vec![signers[0].author()];
+    block_on(async move {
+        nodes[2].send_vote(vote_1.clone(), recipients.clone()).await;
+        let msgs = playground
+            .wait_for_messages(1, NetworkPlayground::take_all)
+            .await;
+        assert_eq!(msgs.len(), 1);
+        nodes[2].send_vote(vote_2.clone(), recipients).await;
+        let msgs = playground
+            .wait_for_messages(2, NetworkPlayground::take_all)
+            .await;
+        assert_eq!(msgs.len(), 2);
+
+        assert_eq!(receivers[0].votes.next().await.unwrap(), vote_1);
+        assert_eq!(receivers[0].votes.next().await.unwrap(), vote_2);
+        // The second twin only gets the vote of round 2.
+        assert_eq!(receivers[1].votes.next().await.unwrap(), vote_2);
+    });
+}
@@ -0,0 +1,14 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The consensus observer stream: validators push the blocks they commit, along with their
+//! quorum certs and the ledger info committing them, to the full nodes that subscribed to them.
+//! The full nodes verify the signatures, execute and commit the blocks right away, which takes
+//! roughly one round instead of the seconds it takes to poll state sync. State sync remains the
+//! fallback whenever a full node misses some blocks.
+
+mod publisher;
+mod subscriber;
+
+pub use publisher::ConsensusPublisher;
+pub use subscriber::ConsensusObserver;
@@ -0,0 +1,191 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        common::Payload,
+        consensus_types::{block::Block, quorum_cert::QuorumCert},
+    },
+    consensus_provider::create_storage_read_client,
+};
+use config::config::NodeConfig;
+use failure::prelude::*;
+use futures::{channel::mpsc, FutureExt, StreamExt, TryFutureExt};
+use logger::prelude::*;
+use network::{
+    proto::{CommittedBlock, CommittedBlocks, ConsensusObserverMsg, EpochChangeProof},
+    validator_network::{ConsensusObserverEvents, ConsensusObserverSender, Event},
+};
+use proto_conv::IntoProto;
+use std::{collections::HashSet, sync::Arc};
+use storage_client::StorageRead;
+use tokio::runtime::{Builder, Runtime};
+use types::{crypto_proxies::LedgerInfoWithSignatures, PeerId};
+
+/// Pushes the blocks that the validator commits to the full nodes subscribed to it, on each of
+/// the networks facing full nodes.
+pub struct ConsensusPublisher {
+    _runtime: Runtime,
+    committed_blocks_txs: Vec<mpsc::UnboundedSender<CommittedBlocks>>,
+}
+
+impl ConsensusPublisher {
+    /// Starts serving the subscriptions of the full nodes on the given networks, as well as their
+    /// requests for the ledger infos ending the epochs, which are read from the storage of the
+    /// node config.
+    pub fn bootstrap(
+        network: Vec<(ConsensusObserverSender, ConsensusObserverEvents)>,
+        config: &NodeConfig,
+    ) -> Self {
+        let runtime = Builder::new()
+            .name_prefix("consensus-publisher-")
+            .build()
+            .expect("[consensus publisher] failed to create runtime");
+        let executor = runtime.executor();
+        let storage_read_client = create_storage_read_client(config);
+        let committed_blocks_txs = network
+            .into_iter()
+            .map(|(sender, events)| {
+                let (committed_blocks_tx, committed_blocks_rx) = mpsc::unbounded();
+                executor.spawn(
+                    Self::serve_subscriptions(
+                        sender,
+                        events,
+                        committed_blocks_rx,
+                        Arc::clone(&storage_read_client),
+                    )
+                    .boxed()
+                    .unit_error()
+                    .compat(),
+                );
+                committed_blocks_tx
+            })
+            .collect();
+        Self {
+            _runtime: runtime,
+            committed_blocks_txs,
+        }
+    }
+
+    /// Pushes the blocks committed by the given ledger info, ordered from the child of the
+    /// previously committed block, with the quorum cert of each of them.
+    pub fn publish<T: Payload>(
+        &self,
+        blocks: Vec<(Block<T>, QuorumCert)>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) {
+        let committed_blocks = committed_blocks_to_proto(blocks, ledger_info_with_sigs);
+        for committed_blocks_tx in &self.committed_blocks_txs {
+            if let Err(e) = committed_blocks_tx.unbounded_send(committed_blocks.clone()) {
+                error!(
+                    "[consensus publisher] failed to publish committed blocks: {:?}",
+                    e
+                );
+            }
+        }
+    }
+
+    /// Keeps track of the full nodes subscribed on a network and sends them the committed blocks.
+    async fn serve_subscriptions(
+        mut sender: ConsensusObserverSender,
+        events: ConsensusObserverEvents,
+        committed_blocks_rx: mpsc::UnboundedReceiver<CommittedBlocks>,
+        storage_read_client: Arc<dyn StorageRead>,
+    ) {
+        let mut events = events.fuse();
+        let mut committed_blocks_rx = committed_blocks_rx.fuse();
+        let mut subscribers: HashSet<PeerId> = HashSet::new();
+        loop {
+            ::futures::select! {
+                event = events.select_next_some() => {
+                    match event {
+                        Ok(Event::Message((peer_id, msg))) => {
+                            if msg.has_subscribe_request() {
+                                debug!("[consensus publisher] {} subscribed", peer_id);
+                                subscribers.insert(peer_id);
+                            } else if msg.has_unsubscribe_request() {
+                                debug!("[consensus publisher] {} unsubscribed", peer_id);
+                                subscribers.remove(&peer_id);
+                            } else if msg.has_request_epoch() {
+                                let start_epoch = msg.get_request_epoch().get_start_epoch();
+                                if let Err(e) = Self::send_epoch_change_proof(
+                                    &mut sender,
+                                    storage_read_client.as_ref(),
+                                    start_epoch,
+                                    peer_id,
+                                )
+                                .await
+                                {
+                                    warn!(
+                                        "[consensus publisher] failed to send the ledger infos ending the epochs from {} to {}: {:?}",
+                                        start_epoch, peer_id, e
+                                    );
+                                }
+                            } else {
+                                warn!("[consensus publisher] unexpected message from {}", peer_id);
+                            }
+                        }
+                        Ok(Event::LostPeer(peer_id)) => {
+                            subscribers.remove(&peer_id);
+                        }
+                        Ok(_) => {}
+                        Err(e) => error!("[consensus publisher] network error {:?}", e),
+                    }
+                },
+                committed_blocks = committed_blocks_rx.select_next_some() => {
+                    let mut msg = ConsensusObserverMsg::new();
+                    msg.set_committed_blocks(committed_blocks);
+                    for peer_id in &subscribers {
+                        if let Err(e) = sender.send_to(*peer_id, msg.clone()).await {
+                            warn!(
+                                "[consensus publisher] failed to push committed blocks to {}: {:?}",
+                                peer_id, e
+                            );
+                        }
+                    }
+                },
+                complete => break,
+            }
+        }
+    }
+
+    /// Sends the ledger infos ending the given epoch and the following ones that are committed
+    /// locally to a full node that was pushed blocks from a later epoch than its own.
+    async fn send_epoch_change_proof(
+        sender: &mut ConsensusObserverSender,
+        storage_read_client: &dyn StorageRead,
+        start_epoch: u64,
+        peer_id: PeerId,
+    ) -> Result<()> {
+        let mut epoch_change_proof = EpochChangeProof::new();
+        for ledger_info in storage_read_client
+            .get_latest_ledger_infos_per_epoch_async(start_epoch)
+            .await?
+        {
+            if ledger_info.ledger_info().next_validator_set().is_some() {
+                epoch_change_proof
+                    .mut_ledger_info_with_sigs()
+                    .push(ledger_info.into_proto());
+            }
+        }
+        let mut msg = ConsensusObserverMsg::new();
+        msg.set_epoch_change_proof(epoch_change_proof);
+        sender.send_to(peer_id, msg).await?;
+        Ok(())
+    }
+}
+
+pub(super) fn committed_blocks_to_proto<T: Payload>(
+    blocks: Vec<(Block<T>, QuorumCert)>,
+    ledger_info_with_sigs: LedgerInfoWithSignatures,
+) -> CommittedBlocks {
+    let mut committed_blocks = CommittedBlocks::new();
+    for (block, quorum_cert) in blocks {
+        let mut committed_block = CommittedBlock::new();
+        committed_block.set_block(block.into_proto());
+        committed_block.set_quorum_cert(quorum_cert.into_proto());
+        committed_blocks.mut_blocks().push(committed_block);
+    }
+    committed_blocks.set_ledger_info_with_sigs(ledger_info_with_sigs.into_proto());
+    committed_blocks
+}
@@ -0,0 +1,422 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        chained_bft_consensus_provider::ChainedBftProvider,
+        common::{Payload, Round},
+        consensus_types::{block::Block, quorum_cert::QuorumCert, vote_data::VoteData},
+        epoch_manager::EpochManager,
+        execution_pipeline::ordering_only_state,
+    },
+    consensus_provider::create_storage_read_client,
+    state_computer::ExecutionProxy,
+    state_replication::StateComputer,
+};
+use config::config::NodeConfig;
+use executor::Executor;
+use failure::prelude::*;
+use futures::{stream::select_all, FutureExt, StreamExt, TryFutureExt};
+use logger::prelude::*;
+use network::{
+    proto::{
+        CommittedBlocks, ConsensusObserverMsg, EpochChangeProof, RequestEpoch, SubscribeRequest,
+    },
+    validator_network::{ConsensusObserverEvents, ConsensusObserverSender, Event},
+};
+use proto_conv::FromProto;
+use state_synchronizer::StateSyncClient;
+use std::{collections::HashSet, str::FromStr, sync::Arc};
+use tokio::runtime::{Builder, Runtime};
+use types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+    transaction::SignedTransaction,
+    PeerId,
+};
+use vm_runtime::MoveVM;
+
+#[cfg(test)]
+#[path = "subscriber_test.rs"]
+mod subscriber_test;
+
+/// Subscribes a full node to the blocks that its upstream validators commit, then verifies,
+/// executes and commits the blocks they push.
+pub struct ConsensusObserver {
+    _runtime: Runtime,
+}
+
+impl ConsensusObserver {
+    /// Subscribes to the upstream peers of the node config as soon as they connect on any of the
+    /// given networks.
+    pub fn bootstrap(
+        network: Vec<(ConsensusObserverSender, ConsensusObserverEvents)>,
+        executor: Arc<Executor<MoveVM>>,
+        state_sync_client: Arc<StateSyncClient>,
+        config: &NodeConfig,
+    ) -> Self {
+        let runtime = Builder::new()
+            .name_prefix("consensus-observer-")
+            .build()
+            .expect("[consensus observer] failed to create runtime");
+        let upstream_peers = config
+            .state_sync
+            .upstream_peers
+            .upstream_peers
+            .iter()
+            .map(|peer_id_str| {
+                PeerId::from_str(peer_id_str).unwrap_or_else(|_| {
+                    panic!("Failed to parse peer_id from string: {}", peer_id_str)
+                })
+            })
+            .collect();
+        // The validators of the current epoch are the ones of the latest epoch change committed
+        // locally, or the ones of the config before any epoch change.
+        let epoch_change_proof: Vec<_> = create_storage_read_client(config)
+            .get_latest_ledger_infos_per_epoch(0)
+            .expect("[consensus observer] unable to read the ledger infos per epoch from storage")
+            .into_iter()
+            .filter(|ledger_info| ledger_info.ledger_info().next_validator_set().is_some())
+            .collect();
+        let mut committer = BlockCommitter::<Vec<SignedTransaction>>::new(
+            ChainedBftProvider::first_epoch_validator(&config.consensus)
+                .expect("[consensus observer] invalid validators of the first epoch"),
+            Arc::new(ExecutionProxy::new(
+                executor,
+                Arc::clone(&state_sync_client),
+            )),
+        );
+        if let Some(ledger_info) = epoch_change_proof.last() {
+            committer.start_new_epoch(ledger_info.ledger_info());
+        }
+        runtime.executor().spawn(
+            async move {
+                // The committer is the only one to commit: state sync no longer fetches chunks on
+                // its own, it only catches up with the ledger infos the committer can't execute
+                // the blocks of.
+                if let Err(e) = state_sync_client.set_autosync(false).await {
+                    error!(
+                        "[consensus observer] failed to turn off the autosync of state sync: {:?}",
+                        e
+                    );
+                }
+                Self::observe(network, upstream_peers, committer).await
+            }
+                .boxed()
+                .unit_error()
+                .compat(),
+        );
+        Self { _runtime: runtime }
+    }
+
+    async fn observe<T: Payload>(
+        network: Vec<(ConsensusObserverSender, ConsensusObserverEvents)>,
+        upstream_peers: HashSet<PeerId>,
+        mut committer: BlockCommitter<T>,
+    ) {
+        let mut network_senders: Vec<ConsensusObserverSender> =
+            network.iter().map(|t| t.0.clone()).collect();
+        let events: Vec<_> = network
+            .into_iter()
+            .enumerate()
+            .map(|(idx, t)| t.1.map(move |e| (idx, e)))
+            .collect();
+        let mut network_events = select_all(events);
+
+        while let Some((idx, network_event)) = network_events.next().await {
+            match network_event {
+                Ok(Event::NewPeer(peer_id)) => {
+                    if !upstream_peers.contains(&peer_id) {
+                        continue;
+                    }
+                    debug!("[consensus observer] subscribing to {}", peer_id);
+                    let mut msg = ConsensusObserverMsg::new();
+                    msg.set_subscribe_request(SubscribeRequest::new());
+                    if let Err(e) = network_senders[idx].send_to(peer_id, msg).await {
+                        error!(
+                            "[consensus observer] failed to subscribe to {}: {:?}",
+                            peer_id, e
+                        );
+                    }
+                }
+                Ok(Event::Message((peer_id, mut msg))) => {
+                    let result = if msg.has_committed_blocks() {
+                        match committer.process(msg.take_committed_blocks()).await {
+                            // The blocks are from a later epoch: ask the peer that pushed them
+                            // for the ledger infos ending the epochs up to theirs.
+                            Ok(false) => {
+                                let mut request = RequestEpoch::new();
+                                request.set_start_epoch(committer.epoch());
+                                let mut msg = ConsensusObserverMsg::new();
+                                msg.set_request_epoch(request);
+                                network_senders[idx]
+                                    .send_to(peer_id, msg)
+                                    .await
+                                    .map_err(Into::into)
+                            }
+                            result => result.map(|_| ()),
+                        }
+                    } else if msg.has_epoch_change_proof() {
+                        committer
+                            .process_epoch_change_proof(msg.take_epoch_change_proof())
+                            .await
+                    } else {
+                        warn!("[consensus observer] unexpected message from {}", peer_id);
+                        continue;
+                    };
+                    if let Err(e) = result {
+                        error!(
+                            "[consensus observer] failed to process the message from {}: {:?}",
+                            peer_id, e
+                        );
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => error!("[consensus observer] network error {:?}", e),
+            }
+        }
+    }
+}
+
+/// Verifies, executes and commits the blocks pushed by the validators, following the epoch
+/// changes they commit. The blocks that don't extend the local ledger are caught up with through
+/// state sync, and so are the epochs that ended before the blocks pushed from a later epoch.
+struct BlockCommitter<T> {
+    // The current epoch and its validators.
+    epoch_mgr: EpochManager,
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
+    // The epoch and round of the last block committed, so that the blocks that several upstream
+    // peers push are committed once.
+    last_committed: Option<(u64, Round)>,
+    // The last blocks pushed from a later epoch, which are committed once the epochs up to theirs
+    // are caught up with.
+    pending_committed_blocks: Option<CommittedBlocks>,
+}
+
+impl<T: Payload> BlockCommitter<T> {
+    /// The given validators are the ones of the first epoch.
+    fn new(
+        validator: ValidatorVerifier,
+        state_computer: Arc<dyn StateComputer<Payload = T>>,
+    ) -> Self {
+        Self {
+            epoch_mgr: EpochManager::new(0, validator),
+            state_computer,
+            last_committed: None,
+            pending_committed_blocks: None,
+        }
+    }
+
+    fn epoch(&self) -> u64 {
+        self.epoch_mgr.epoch() as u64
+    }
+
+    /// Moves to the epoch following the one the given ledger info ends, whose validators it
+    /// carries.
+    fn start_new_epoch(&mut self, epoch_ending_ledger_info: &LedgerInfo) {
+        if epoch_ending_ledger_info.next_validator_set().is_some() {
+            self.epoch_mgr.start_new_epoch(epoch_ending_ledger_info);
+        }
+    }
+
+    /// Checks that the ledger info is signed by a quorum of the validators of the current epoch
+    /// and that it commits the chain of blocks, each block being certified by its quorum cert.
+    fn verify(
+        &self,
+        mut committed_blocks: CommittedBlocks,
+    ) -> Result<(Vec<(Block<T>, QuorumCert)>, LedgerInfoWithSignatures)> {
+        let ledger_info_with_sigs =
+            LedgerInfoWithSignatures::from_proto(committed_blocks.take_ledger_info_with_sigs())?;
+        ensure!(
+            ledger_info_with_sigs.ledger_info().epoch_num() == self.epoch(),
+            "The ledger info is from epoch {}, while the current epoch is {}",
+            ledger_info_with_sigs.ledger_info().epoch_num(),
+            self.epoch()
+        );
+        let validator = self.epoch_mgr.validators();
+        let epoch_ending_ledger_info = self.epoch_mgr.epoch_ending_ledger_info();
+        ledger_info_with_sigs.verify(validator.as_ref())?;
+        let mut blocks: Vec<(Block<T>, QuorumCert)> = vec![];
+        for mut committed_block in committed_blocks.take_blocks().into_iter() {
+            let block = Block::<T>::from_proto(committed_block.take_block())?;
+            let quorum_cert = QuorumCert::from_proto(committed_block.take_quorum_cert())?;
+            block
+                .verify_well_formed()
+                .map_err(|e| format_err!("Invalid block {}: {:?}", block.id(), e))?;
+            ensure!(
+                quorum_cert.certified_block_id() == block.id(),
+                "The quorum cert doesn't certify block {}",
+                block.id()
+            );
+            quorum_cert
+                .verify(
+                    validator.as_ref(),
+                    epoch_ending_ledger_info.as_ref().map(Arc::as_ref),
+                )
+                .map_err(|e| format_err!("Invalid quorum cert for {}: {:?}", block.id(), e))?;
+            if let Some((parent, _)) = blocks.last() {
+                ensure!(
+                    block.parent_id() == parent.id(),
+                    "Block {} isn't a child of block {}",
+                    block.id(),
+                    parent.id()
+                );
+            }
+            blocks.push((block, quorum_cert));
+        }
+        match blocks.last() {
+            Some((block, _)) => ensure!(
+                block.id() == ledger_info_with_sigs.ledger_info().consensus_block_id(),
+                "The ledger info doesn't commit block {}",
+                block.id()
+            ),
+            None => bail!("No committed blocks"),
+        }
+        Ok((blocks, ledger_info_with_sigs))
+    }
+
+    /// Executes the blocks, checks that the local execution matches the state the validators
+    /// certified, then commits the blocks. If the blocks can't be executed, e.g. because the
+    /// previous ones were missed, state sync catches up with the ledger info instead.
+    /// Returns false if the blocks are from a later epoch, in which case they're kept until the
+    /// epochs up to theirs are caught up with (see `process_epoch_change_proof`).
+    async fn process(&mut self, committed_blocks: CommittedBlocks) -> Result<bool> {
+        if committed_blocks
+            .get_ledger_info_with_sigs()
+            .get_ledger_info()
+            .get_epoch_num()
+            > self.epoch()
+        {
+            self.pending_committed_blocks = Some(committed_blocks);
+            return Ok(false);
+        }
+        let (blocks, ledger_info_with_sigs) = self.verify(committed_blocks)?;
+        let last_round = blocks.last().map_or(0, |(block, _)| block.round());
+        let position = (ledger_info_with_sigs.ledger_info().epoch_num(), last_round);
+        if self
+            .last_committed
+            .map_or(false, |last_committed| position <= last_committed)
+        {
+            return Ok(true);
+        }
+        if self.execute(&blocks).await? {
+            self.state_computer
+                .commit(ledger_info_with_sigs.clone())
+                .await?;
+        } else {
+            let (_, quorum_cert) = &blocks[blocks.len() - 1];
+            self.sync_to(
+                VoteData::new(
+                    quorum_cert.certified_block_id(),
+                    quorum_cert.certified_state_id(),
+                    quorum_cert.certified_block_round(),
+                    quorum_cert.parent_block_id(),
+                    quorum_cert.parent_block_round(),
+                    quorum_cert.grandparent_block_id(),
+                    quorum_cert.grandparent_block_round(),
+                ),
+                ledger_info_with_sigs.clone(),
+            )
+            .await?;
+        }
+        self.last_committed = Some(position);
+        self.start_new_epoch(ledger_info_with_sigs.ledger_info());
+        Ok(true)
+    }
+
+    /// Catches up with the epochs that the given ledger infos end, starting with the current one,
+    /// each of which must be signed by the validators of the epoch it ends: state sync catches up
+    /// with the end of the last of them. The blocks pushed from a later epoch are then processed
+    /// again.
+    async fn process_epoch_change_proof(
+        &mut self,
+        mut epoch_change_proof: EpochChangeProof,
+    ) -> Result<()> {
+        let epoch_change_proof = epoch_change_proof
+            .take_ledger_info_with_sigs()
+            .into_iter()
+            .map(LedgerInfoWithSignatures::from_proto)
+            .collect::<Result<Vec<_>>>()?;
+        let epoch_change_proof = self
+            .epoch_mgr
+            .verify_epoch_change_proof(&epoch_change_proof)?;
+        let epoch_ending_ledger_info = match epoch_change_proof.last() {
+            Some(ledger_info) => ledger_info.clone(),
+            None => return Ok(()),
+        };
+        let block_id = epoch_ending_ledger_info.ledger_info().consensus_block_id();
+        self.sync_to(
+            VoteData::new(
+                block_id,
+                epoch_ending_ledger_info
+                    .ledger_info()
+                    .transaction_accumulator_hash(),
+                0,
+                block_id,
+                0,
+                block_id,
+                0,
+            ),
+            epoch_ending_ledger_info,
+        )
+        .await?;
+        for ledger_info in &epoch_change_proof {
+            self.start_new_epoch(ledger_info.ledger_info());
+        }
+        if let Some(committed_blocks) = self.pending_committed_blocks.take() {
+            self.process(committed_blocks).await?;
+        }
+        Ok(())
+    }
+
+    /// Catches up with the given ledger info through state sync, which only needs the ledger info
+    /// out of the quorum cert.
+    async fn sync_to(
+        &self,
+        vote_data: VoteData,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<()> {
+        let version = ledger_info_with_sigs.ledger_info().version();
+        ensure!(
+            self.state_computer
+                .sync_to(QuorumCert::new(vote_data, ledger_info_with_sigs))
+                .await?,
+            "Failed to sync to version {}",
+            version
+        );
+        Ok(())
+    }
+
+    /// Executes the blocks in order, returning false if one of them can't be executed on top of
+    /// the local ledger.
+    async fn execute(&self, blocks: &[(Block<T>, QuorumCert)]) -> Result<bool> {
+        for (block, quorum_cert) in blocks {
+            let compute_result = match self
+                .state_computer
+                .compute(block.parent_id(), block.id(), block.get_payload())
+                .await
+            {
+                Ok(compute_result) => compute_result,
+                Err(e) => {
+                    warn!(
+                        "[consensus observer] failed to execute block {}: {:?}",
+                        block.id(),
+                        e
+                    );
+                    return Ok(false);
+                }
+            };
+            // In the decoupled execution mode, the quorum certs only order the blocks: the state
+            // is certified by the ledger info committing them, which the storage checks.
+            ensure!(
+                quorum_cert.certified_state_id() == ordering_only_state().state_id
+                    || compute_result.executed_state.state_id == quorum_cert.certified_state_id(),
+                "Executing block {} led to state {}, while the validators certified state {}",
+                block.id(),
+                compute_result.executed_state.state_id,
+                quorum_cert.certified_state_id()
+            );
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,342 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        consensus_types::{block::Block, quorum_cert::QuorumCert},
+        observer::{publisher::committed_blocks_to_proto, subscriber::BlockCommitter},
+        test_utils::{placeholder_certificate_for_block, MockStateComputer, TestPayload},
+    },
+    state_replication::StateComputer,
+};
+use crypto::{hash::CryptoHash, x25519, HashValue};
+use executor::StateComputeResult;
+use failure::prelude::*;
+use futures::{channel::mpsc, executor::block_on, future, Future, FutureExt};
+use network::proto::EpochChangeProof;
+use proto_conv::IntoProto;
+use std::{collections::HashMap, pin::Pin, sync::Arc};
+use types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+    validator_public_keys::ValidatorPublicKeys,
+    validator_set::ValidatorSet,
+};
+
+/// Returns two blocks chained to the given genesis, each along with its quorum cert.
+fn make_chain(
+    signer: &ValidatorSigner,
+    genesis: &Block<TestPayload>,
+    genesis_qc: QuorumCert,
+) -> Vec<(Block<TestPayload>, QuorumCert)> {
+    let b1 = Block::make_block(genesis, vec![1], 1, 1, genesis_qc, signer);
+    let qc1 = placeholder_certificate_for_block(
+        vec![signer],
+        b1.id(),
+        1,
+        genesis.id(),
+        0,
+        genesis.id(),
+        0,
+    );
+    let b2 = Block::make_block(&b1, vec![2], 2, 2, qc1.clone(), signer);
+    let qc2 =
+        placeholder_certificate_for_block(vec![signer], b2.id(), 2, b1.id(), 1, genesis.id(), 0);
+    vec![(b1, qc1), (b2, qc2)]
+}
+
+/// Returns two blocks chained to the genesis of the first epoch.
+fn make_genesis_chain(signer: &ValidatorSigner) -> Vec<(Block<TestPayload>, QuorumCert)> {
+    make_chain(
+        signer,
+        &Block::make_genesis_block(),
+        QuorumCert::certificate_for_genesis(),
+    )
+}
+
+/// The ledger info committing the given block in the given epoch, which it ends if a next
+/// validator is given.
+fn commit_ledger_info(
+    signer: &ValidatorSigner,
+    block_id: HashValue,
+    epoch: u64,
+    next_validator: Option<&ValidatorSigner>,
+) -> LedgerInfoWithSignatures {
+    let next_validator_set = next_validator.map(|next_validator| {
+        ValidatorSet::new(vec![ValidatorPublicKeys::new(
+            next_validator.author(),
+            next_validator.public_key(),
+            next_validator.public_key(),
+            x25519::compat::generate_keypair(None).1,
+            next_validator.vrf_public_key(),
+        )])
+    });
+    let ledger_info = LedgerInfo::new(
+        epoch + 1,
+        HashValue::zero(),
+        HashValue::zero(),
+        block_id,
+        epoch,
+        2,
+        next_validator_set,
+    );
+    let mut signatures = HashMap::new();
+    signatures.insert(
+        signer.author(),
+        signer.sign_message(ledger_info.hash()).unwrap(),
+    );
+    LedgerInfoWithSignatures::new(ledger_info, signatures)
+}
+
+fn make_committer(
+    signer: &ValidatorSigner,
+) -> (
+    BlockCommitter<TestPayload>,
+    mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+) {
+    let mut author_to_public_keys = HashMap::new();
+    author_to_public_keys.insert(signer.author(), signer.public_key());
+    let (commit_tx, commit_rx) = mpsc::unbounded();
+    (
+        BlockCommitter::new(
+            ValidatorVerifier::new(author_to_public_keys),
+            Arc::new(MockStateComputer::new(commit_tx)),
+        ),
+        commit_rx,
+    )
+}
+
+#[test]
+fn test_commit_pushed_blocks() {
+    let signer = ValidatorSigner::from_int(0);
+    let (mut committer, mut commit_rx) = make_committer(&signer);
+    let chain = make_genesis_chain(&signer);
+    let ledger_info = commit_ledger_info(&signer, chain[1].0.id(), 0, None);
+
+    block_on(committer.process(committed_blocks_to_proto(
+        chain.clone(),
+        ledger_info.clone(),
+    )))
+    .unwrap();
+    assert_eq!(commit_rx.try_next().unwrap(), Some(ledger_info.clone()));
+
+    // The same blocks pushed by another upstream peer are committed once.
+    block_on(committer.process(committed_blocks_to_proto(chain, ledger_info))).unwrap();
+    assert!(commit_rx.try_next().is_err());
+}
+
+#[test]
+fn test_reject_unverified_blocks() {
+    let signer = ValidatorSigner::from_int(0);
+    let (mut committer, mut commit_rx) = make_committer(&signer);
+    let chain = make_genesis_chain(&signer);
+
+    // The ledger info isn't signed by the validators.
+    let other_signer = ValidatorSigner::from_int(1);
+    let ledger_info = commit_ledger_info(&other_signer, chain[1].0.id(), 0, None);
+    assert!(
+        block_on(committer.process(committed_blocks_to_proto(chain.clone(), ledger_info))).is_err()
+    );
+
+    // The ledger info doesn't commit the last block.
+    let ledger_info = commit_ledger_info(&signer, chain[0].0.id(), 0, None);
+    assert!(
+        block_on(committer.process(committed_blocks_to_proto(chain.clone(), ledger_info))).is_err()
+    );
+
+    // The blocks aren't chained.
+    let ledger_info = commit_ledger_info(&signer, chain[0].0.id(), 0, None);
+    assert!(block_on(committer.process(committed_blocks_to_proto(
+        vec![chain[1].clone(), chain[0].clone()],
+        ledger_info
+    )))
+    .is_err());
+
+    // The quorum cert doesn't certify the block.
+    let ledger_info = commit_ledger_info(&signer, chain[1].0.id(), 0, None);
+    assert!(block_on(committer.process(committed_blocks_to_proto(
+        vec![chain[0].clone(), (chain[1].0.clone(), chain[0].1.clone())],
+        ledger_info
+    )))
+    .is_err());
+
+    assert!(commit_rx.try_next().is_err());
+}
+
+#[test]
+fn test_follow_epoch_changes() {
+    let signer = ValidatorSigner::from_int(0);
+    let next_signer = ValidatorSigner::from_int(1);
+    let (mut committer, mut commit_rx) = make_committer(&signer);
+    let chain = make_genesis_chain(&signer);
+    let epoch_ending_ledger_info =
+        commit_ledger_info(&signer, chain[1].0.id(), 0, Some(&next_signer));
+    block_on(committer.process(committed_blocks_to_proto(
+        chain,
+        epoch_ending_ledger_info.clone(),
+    )))
+    .unwrap();
+    assert_eq!(
+        commit_rx.try_next().unwrap(),
+        Some(epoch_ending_ledger_info.clone())
+    );
+
+    // The blocks of the next epoch chain to its genesis and are signed by its validators.
+    let genesis =
+        Block::make_genesis_block_from_ledger_info(epoch_ending_ledger_info.ledger_info());
+    let genesis_qc = QuorumCert::certificate_for_genesis_from_ledger_info(
+        epoch_ending_ledger_info.ledger_info(),
+    );
+    let old_chain = make_chain(&signer, &genesis, genesis_qc.clone());
+    let ledger_info = commit_ledger_info(&signer, old_chain[1].0.id(), 1, None);
+    assert!(
+        block_on(committer.process(committed_blocks_to_proto(old_chain, ledger_info))).is_err()
+    );
+    let chain = make_chain(&next_signer, &genesis, genesis_qc);
+    let ledger_info = commit_ledger_info(&next_signer, chain[1].0.id(), 1, None);
+    block_on(committer.process(committed_blocks_to_proto(chain, ledger_info.clone()))).unwrap();
+    assert_eq!(commit_rx.try_next().unwrap(), Some(ledger_info));
+}
+
+#[test]
+fn test_sync_to_blocks_not_executed() {
+    let signer = ValidatorSigner::from_int(0);
+    let (commit_tx, mut commit_rx) = mpsc::unbounded();
+    let mut committer = BlockCommitter::new(
+        ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+        Arc::new(UnexecutableStateComputer::new(commit_tx)),
+    );
+    let chain = make_genesis_chain(&signer);
+    let ledger_info = commit_ledger_info(&signer, chain[1].0.id(), 0, None);
+
+    // State sync catches up with the ledger info, which only counts as committed if it did.
+    block_on(committer.process(committed_blocks_to_proto(
+        chain.clone(),
+        ledger_info.clone(),
+    )))
+    .unwrap();
+    assert_eq!(
+        commit_rx.try_next().unwrap(),
+        Some(SyncOrCommit::Sync(ledger_info.clone()))
+    );
+    block_on(committer.process(committed_blocks_to_proto(chain, ledger_info))).unwrap();
+    assert!(commit_rx.try_next().is_err());
+}
+
+#[test]
+fn test_catch_up_with_epochs() {
+    let signer = ValidatorSigner::from_int(0);
+    let next_signer = ValidatorSigner::from_int(1);
+    let (commit_tx, mut commit_rx) = mpsc::unbounded();
+    let mut committer = BlockCommitter::new(
+        ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+        Arc::new(UnexecutableStateComputer::new(commit_tx)),
+    );
+    let epoch_ending_ledger_info =
+        commit_ledger_info(&signer, HashValue::random(), 0, Some(&next_signer));
+    let genesis =
+        Block::make_genesis_block_from_ledger_info(epoch_ending_ledger_info.ledger_info());
+    let genesis_qc = QuorumCert::certificate_for_genesis_from_ledger_info(
+        epoch_ending_ledger_info.ledger_info(),
+    );
+    let chain = make_chain(&next_signer, &genesis, genesis_qc);
+    let ledger_info = commit_ledger_info(&next_signer, chain[1].0.id(), 1, None);
+
+    // The blocks of a later epoch wait for the epoch change proof.
+    assert_eq!(
+        block_on(committer.process(committed_blocks_to_proto(chain, ledger_info.clone()))).unwrap(),
+        false
+    );
+    assert!(commit_rx.try_next().is_err());
+
+    // A proof that the validators of the current epoch didn't sign is rejected.
+    let other_signer = ValidatorSigner::from_int(2);
+    let invalid_proof = commit_ledger_info(
+        &other_signer,
+        epoch_ending_ledger_info.ledger_info().consensus_block_id(),
+        0,
+        Some(&next_signer),
+    );
+    assert!(block_on(
+        committer.process_epoch_change_proof(epoch_change_proof_to_proto(vec![invalid_proof]))
+    )
+    .is_err());
+    assert_eq!(committer.epoch(), 0);
+    assert!(commit_rx.try_next().is_err());
+
+    // State sync catches up with the end of the epoch, then with the pending blocks.
+    block_on(
+        committer.process_epoch_change_proof(epoch_change_proof_to_proto(vec![
+            epoch_ending_ledger_info.clone(),
+        ])),
+    )
+    .unwrap();
+    assert_eq!(committer.epoch(), 1);
+    assert_eq!(
+        commit_rx.try_next().unwrap(),
+        Some(SyncOrCommit::Sync(epoch_ending_ledger_info))
+    );
+    assert_eq!(
+        commit_rx.try_next().unwrap(),
+        Some(SyncOrCommit::Sync(ledger_info))
+    );
+}
+
+fn epoch_change_proof_to_proto(
+    epoch_change_proof: Vec<LedgerInfoWithSignatures>,
+) -> EpochChangeProof {
+    let mut proof = EpochChangeProof::new();
+    for ledger_info in epoch_change_proof {
+        proof
+            .mut_ledger_info_with_sigs()
+            .push(ledger_info.into_proto());
+    }
+    proof
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum SyncOrCommit {
+    Sync(LedgerInfoWithSignatures),
+    Commit(LedgerInfoWithSignatures),
+}
+
+/// A state computer whose blocks never extend the ledger, as if the previous ones were missed.
+struct UnexecutableStateComputer {
+    callback: mpsc::UnboundedSender<SyncOrCommit>,
+}
+
+impl UnexecutableStateComputer {
+    fn new(callback: mpsc::UnboundedSender<SyncOrCommit>) -> Self {
+        Self { callback }
+    }
+}
+
+impl StateComputer for UnexecutableStateComputer {
+    type Payload = TestPayload;
+
+    fn compute(
+        &self,
+        parent_id: HashValue,
+        _block_id: HashValue,
+        _transactions: &Self::Payload,
+    ) -> Pin<Box<dyn Future<Output = Result<StateComputeResult>> + Send>> {
+        future::err(format_err!("Parent block {:x} was not found.", parent_id)).boxed()
+    }
+
+    fn commit(
+        &self,
+        commit: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        self.callback
+            .unbounded_send(SyncOrCommit::Commit(commit))
+            .unwrap();
+        future::ok(()).boxed()
+    }
+
+    fn sync_to(&self, commit: QuorumCert) -> Pin<Box<dyn Future<Output = Result<bool>> + Send>> {
+        self.callback
+            .unbounded_send(SyncOrCommit::Sync(commit.ledger_info().clone()))
+            .unwrap();
+        future::ok(true).boxed()
+    }
+}
@@ -3,10 +3,12 @@
 
 use crate::{
     chained_bft::{
-        common::Payload,
+        common::{Payload, Round},
         consensus_types::{block::Block, quorum_cert::QuorumCert},
         consensusdb::ConsensusDB,
-        liveness::pacemaker_timeout_manager::HighestTimeoutCertificates,
+        liveness::{
+            leader_reputation::BlockMetadata, pacemaker_timeout_manager::HighestTimeoutCertificates,
+        },
         safety::safety_rules::ConsensusState,
     },
     consensus_provider::create_storage_read_client,
@@ -20,6 +22,7 @@ use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
 };
+use types::{crypto_proxies::LedgerInfoWithSignatures, ledger_info::LedgerInfo};
 
 /// Persistent storage for liveness data
 pub trait PersistentLivenessStorage: Send + Sync {
@@ -51,6 +54,19 @@ pub trait PersistentStorage<T>: PersistentLivenessStorage + Send + Sync {
     /// Persist the consensus state.
     fn save_consensus_state(&self, state: ConsensusState) -> Result<()>;
 
+    /// Persist the metadata of the given blocks committed in the given epoch, and delete the
+    /// metadata of the blocks committed before the given round of the epoch or in the previous
+    /// epochs.
+    fn save_block_metadata(
+        &self,
+        epoch: u64,
+        block_metadata: Vec<BlockMetadata>,
+        min_round: Round,
+    ) -> Result<()>;
+
+    /// Get the metadata of the committed blocks of the given epoch kept in storage, by round.
+    fn get_block_metadata(&self, epoch: u64) -> Result<Vec<BlockMetadata>>;
+
     /// When the node restart, construct the instance and returned the data read from db.
     /// This could guarantee we only read once during start, and we would panic if the
     /// read fails.
@@ -76,9 +92,14 @@ pub struct RecoveryData<T> {
     // Liveness data
     highest_timeout_certificates: HighestTimeoutCertificates,
 
-    // If root is not consistent with StateComputer, need to state synchronize before
-    // starting
-    need_sync: bool,
+    // The block committed by the latest ledger info in storage: if it's not the root, need to
+    // state synchronize before starting
+    root_from_storage: HashValue,
+
+    // The ledger infos ending the epochs committed to the ledger, in order: the current epoch and
+    // its validators are recovered from the latest one, and the whole chain proves them to the
+    // safety rules.
+    epoch_change_proof: Vec<LedgerInfoWithSignatures>,
 }
 
 impl<T: Payload> RecoveryData<T> {
@@ -88,6 +109,7 @@ impl<T: Payload> RecoveryData<T> {
         mut quorum_certs: Vec<QuorumCert>,
         root_from_storage: HashValue,
         highest_timeout_certificates: HighestTimeoutCertificates,
+        epoch_change_proof: Vec<LedgerInfoWithSignatures>,
     ) -> Result<Self> {
         let root =
             Self::find_root(&mut blocks, &mut quorum_certs, root_from_storage).map_err(|e| {
@@ -111,8 +133,6 @@ impl<T: Payload> RecoveryData<T> {
             &mut blocks,
             &mut quorum_certs,
         ));
-        // if the root is different than the LI(S).block, we need to sync before start
-        let need_sync = root_from_storage != root.0.id();
         Ok(RecoveryData {
             state,
             root,
@@ -120,7 +140,8 @@ impl<T: Payload> RecoveryData<T> {
             quorum_certs,
             blocks_to_prune,
             highest_timeout_certificates,
-            need_sync,
+            root_from_storage,
+            epoch_change_proof,
         })
     }
 
@@ -128,6 +149,21 @@ impl<T: Payload> RecoveryData<T> {
         self.state.clone()
     }
 
+    /// The root block along with its quorum cert and the quorum cert carrying its ledger info.
+    pub fn root(&self) -> &(Block<T>, QuorumCert, QuorumCert) {
+        &self.root
+    }
+
+    /// The blocks descending from the root.
+    pub fn blocks(&self) -> &[Block<T>] {
+        &self.blocks
+    }
+
+    /// The quorum certs of the root and of the blocks descending from it.
+    pub fn quorum_certs(&self) -> &[QuorumCert] {
+        &self.quorum_certs
+    }
+
     pub fn take(
         self,
     ) -> (
@@ -152,8 +188,30 @@ impl<T: Payload> RecoveryData<T> {
         self.root.2.clone()
     }
 
+    pub fn root_from_storage(&self) -> HashValue {
+        self.root_from_storage
+    }
+
+    /// Whether the root is different than the LI(S).block, in which case we need to sync before
+    /// start.
     pub fn need_sync(&self) -> bool {
-        self.need_sync
+        self.root_from_storage != self.root.0.id()
+    }
+
+    pub fn epoch_change_proof(&self) -> &[LedgerInfoWithSignatures] {
+        &self.epoch_change_proof
+    }
+
+    pub fn epoch_ending_ledger_info(&self) -> Option<&LedgerInfo> {
+        self.epoch_change_proof
+            .last()
+            .map(|ledger_info| ledger_info.ledger_info())
+    }
+
+    /// The epoch following the latest one ended in the ledger.
+    pub fn epoch(&self) -> u64 {
+        self.epoch_ending_ledger_info()
+            .map_or(0, |ledger_info| ledger_info.epoch_num() + 1)
     }
 
     /// Finds the root (last committed block) and returns the root block, the QC to the root block
@@ -307,6 +365,20 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
         self.db.save_state(to_vec_named(&state)?)
     }
 
+    fn save_block_metadata(
+        &self,
+        epoch: u64,
+        block_metadata: Vec<BlockMetadata>,
+        min_round: Round,
+    ) -> Result<()> {
+        self.db.save_block_metadata(epoch, &block_metadata)?;
+        self.db.delete_block_metadata_before(epoch, min_round)
+    }
+
+    fn get_block_metadata(&self, epoch: u64) -> Result<Vec<BlockMetadata>> {
+        self.db.get_block_metadata(epoch)
+    }
+
     fn start(config: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
         info!("Start consensus recovery.");
         let read_client = create_storage_read_client(config);
@@ -347,7 +419,7 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
         );
 
         // find the block corresponding to storage latest ledger info
-        let (_, ledger_info, _) = read_client
+        let (_, ledger_info, _, _) = read_client
             .update_to_latest_ledger(0, vec![])
             .expect("unable to read ledger info from storage");
         let root_from_storage = ledger_info.ledger_info().consensus_block_id();
@@ -355,6 +427,18 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
             "The last committed block id as recorded in storage: {}",
             root_from_storage
         );
+        // Among the latest ledger infos of the epochs, the ones carrying the validator set of the
+        // next epoch end theirs.
+        let epoch_change_proof: Vec<_> = read_client
+            .get_latest_ledger_infos_per_epoch(0)
+            .expect("unable to read the ledger infos per epoch from storage")
+            .into_iter()
+            .filter(|ledger_info| ledger_info.ledger_info().next_validator_set().is_some())
+            .collect();
+        debug!(
+            "The latest ledger info ending an epoch as recorded in storage: {:?}",
+            epoch_change_proof.last()
+        );
 
         let mut initial_data = RecoveryData::new(
             consensus_state,
@@ -362,6 +446,7 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
             quorum_certs,
             root_from_storage,
             highest_timeout_certificates,
+            epoch_change_proof,
         )
         .unwrap_or_else(|e| panic!("Can not construct recovery data due to {}", e));
 
@@ -370,7 +455,7 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
 
         debug!("Consensus root to start with: {}", initial_data.root.0);
 
-        if initial_data.need_sync {
+        if initial_data.need_sync() {
             info!("Consensus recovery done but additional state synchronization is required.");
         } else {
             info!("Consensus recovery completed.")
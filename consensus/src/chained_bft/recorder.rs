@@ -0,0 +1,405 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        common::{Author, Payload, Round},
+        consensus_types::{
+            block::Block, proposal_msg::ProposalMsg, sync_info::SyncInfo, timeout_msg::TimeoutMsg,
+            vote_msg::VoteMsg,
+        },
+        liveness::{
+            leader_reputation::BlockMetadata, pacemaker_timeout_manager::HighestTimeoutCertificates,
+        },
+        persistent_storage::{PersistentStorage, RecoveryData},
+        safety::safety_rules::ConsensusState,
+        QuorumCert,
+    },
+    state_replication::StateComputer,
+    util::time_service::duration_since_epoch,
+};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use crypto::HashValue;
+use executor::{ExecutedState, StateComputeResult};
+use failure::prelude::*;
+use futures::{Future, FutureExt};
+use logger::prelude::*;
+use network::proto::{ConsensusMsg, RespondBlock};
+use proto_conv::{FromProtoBytes, IntoProto, IntoProtoBytes};
+use protobuf::Message;
+use rmp_serde::to_vec_named;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufReader, Read, Write},
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+use types::crypto_proxies::LedgerInfoWithSignatures;
+
+#[cfg(test)]
+#[path = "recorder_test.rs"]
+mod recorder_test;
+
+/// The data that consensus starts from, as recovered from the storage of the validator. The
+/// blocks, quorum certs and ledger infos are kept as serialized protobufs.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct RecordedStartup {
+    pub author: Author,
+    pub consensus_state: ConsensusState,
+    // The root comes first.
+    pub blocks: Vec<Vec<u8>>,
+    pub quorum_certs: Vec<Vec<u8>>,
+    pub root_from_storage: HashValue,
+    pub highest_timeout_certificates: HighestTimeoutCertificates,
+    pub epoch_change_proof: Vec<Vec<u8>>,
+    // The metadata of the blocks committed in the epoch, which the leader reputation depends on.
+    pub block_metadata: Vec<BlockMetadata>,
+}
+
+impl RecordedStartup {
+    pub fn new<T: Payload>(
+        author: Author,
+        initial_data: &RecoveryData<T>,
+        storage: &dyn PersistentStorage<T>,
+    ) -> Result<Self> {
+        Ok(Self {
+            author,
+            consensus_state: initial_data.state(),
+            blocks: std::iter::once(&initial_data.root().0)
+                .chain(initial_data.blocks())
+                .map(|block| block.clone().into_proto_bytes())
+                .collect::<Result<_>>()?,
+            quorum_certs: initial_data
+                .quorum_certs()
+                .iter()
+                .map(|qc| qc.clone().into_proto_bytes())
+                .collect::<Result<_>>()?,
+            root_from_storage: initial_data.root_from_storage(),
+            highest_timeout_certificates: initial_data.highest_timeout_certificates().clone(),
+            epoch_change_proof: initial_data
+                .epoch_change_proof()
+                .iter()
+                .map(|ledger_info| ledger_info.clone().into_proto_bytes())
+                .collect::<Result<_>>()?,
+            block_metadata: storage.get_block_metadata(initial_data.epoch())?,
+        })
+    }
+
+    /// Recovers the same root, blocks and quorum certs as the validator did.
+    pub fn recovery_data<T: Payload>(&self) -> Result<RecoveryData<T>> {
+        RecoveryData::new(
+            self.consensus_state.clone(),
+            self.blocks
+                .iter()
+                .map(|bytes| Block::from_proto_bytes(bytes))
+                .collect::<Result<_>>()?,
+            self.quorum_certs
+                .iter()
+                .map(|bytes| QuorumCert::from_proto_bytes(bytes))
+                .collect::<Result<_>>()?,
+            self.root_from_storage,
+            self.highest_timeout_certificates.clone(),
+            self.epoch_change_proof
+                .iter()
+                .map(|bytes| LedgerInfoWithSignatures::from_proto_bytes(bytes))
+                .collect::<Result<_>>()?,
+        )
+    }
+}
+
+/// An event that the decisions of a validator depend on, or one of these decisions (the outbound
+/// messages). The messages are kept as serialized protobufs, just as they go over the network.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum RecordedEvent {
+    /// A start of the validator, which the events up to the next start follow.
+    Start(RecordedStartup),
+    /// A `ConsensusMsg` handed over to the event processor, from the given author (or sender of a
+    /// sync info). The messages that the validator sends to itself are recorded both ways.
+    Inbound { from: Author, msg: Vec<u8> },
+    /// A block retrieval request from a peer.
+    BlockRetrievalRequest {
+        block_id: HashValue,
+        num_blocks: u64,
+    },
+    /// A `ConsensusMsg` sent to the given peers.
+    Outbound { to: Vec<Author>, msg: Vec<u8> },
+    /// The `RespondBlock` to a block retrieval request sent to a peer, none if the request
+    /// failed.
+    BlockRetrievalResponse { response: Option<Vec<u8>> },
+    /// A timeout of the local pacemaker.
+    LocalTimeout { round: Round },
+    /// The state that executing a block led to, none if the execution failed. The statuses of
+    /// the transactions aren't recorded: consensus merely hands them over to the txn manager.
+    StateCompute {
+        block_id: HashValue,
+        executed_state: Option<ExecutedState>,
+    },
+    /// Whether the state synchronizer synced to the given block, none if it failed.
+    SyncTo {
+        block_id: HashValue,
+        synced: Option<bool>,
+    },
+    /// Whether the block committed by a ledger info was committed to the ledger.
+    Commit {
+        block_id: HashValue,
+        committed: bool,
+    },
+}
+
+/// A recorded event along with the time it occurred at.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct RecordedEntry {
+    pub timestamp_usecs: u64,
+    pub event: RecordedEvent,
+}
+
+enum RecordingSink {
+    File(File),
+    Memory(Vec<RecordedEntry>),
+}
+
+/// Records the events of the consensus of a validator, either to a file that `read_recording`
+/// loads back or in memory.
+///
+/// Each entry is written as its length (4 bytes, little endian) followed by its MessagePack
+/// serialization, and the file is flushed after each entry so that a crash loses at most the
+/// last one.
+pub struct ConsensusRecorder {
+    sink: Mutex<RecordingSink>,
+}
+
+impl ConsensusRecorder {
+    /// Records to the given file, after the runs of the validator already recorded to it. An
+    /// entry that the validator didn't finish to write before stopping is dropped.
+    pub fn new(path: &Path) -> Result<Self> {
+        let len = if path.exists() {
+            read_entries(File::open(path)?)?.1
+        } else {
+            0
+        };
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.set_len(len)?;
+        Ok(Self {
+            sink: Mutex::new(RecordingSink::File(file)),
+        })
+    }
+
+    pub fn in_memory() -> Self {
+        Self {
+            sink: Mutex::new(RecordingSink::Memory(vec![])),
+        }
+    }
+
+    /// Returns the entries recorded in memory so far (none if recording to a file).
+    pub fn entries(&self) -> Vec<RecordedEntry> {
+        match &*self.sink.lock().unwrap() {
+            RecordingSink::File(_) => vec![],
+            RecordingSink::Memory(entries) => entries.clone(),
+        }
+    }
+
+    pub fn record(&self, event: RecordedEvent) {
+        let entry = RecordedEntry {
+            timestamp_usecs: duration_since_epoch().as_micros() as u64,
+            event,
+        };
+        match &mut *self.sink.lock().unwrap() {
+            RecordingSink::File(file) => {
+                if let Err(e) = write_entry(file, &entry) {
+                    error!("Failed to record consensus event: {:?}", e);
+                }
+            }
+            RecordingSink::Memory(entries) => entries.push(entry),
+        }
+    }
+
+    /// Starts a new run with the data that consensus recovered from storage.
+    pub fn record_startup<T: Payload>(
+        &self,
+        author: Author,
+        initial_data: &RecoveryData<T>,
+        storage: &dyn PersistentStorage<T>,
+    ) {
+        match RecordedStartup::new(author, initial_data, storage) {
+            Ok(startup) => self.record(RecordedEvent::Start(startup)),
+            Err(e) => error!("Failed to record the consensus startup data: {:?}", e),
+        }
+    }
+
+    pub fn record_proposal<T: Payload>(&self, proposal: &ProposalMsg<T>) {
+        let mut msg = ConsensusMsg::new();
+        msg.set_proposal(proposal.clone().into_proto());
+        self.record_inbound(proposal.proposer(), &msg);
+    }
+
+    pub fn record_vote(&self, vote: &VoteMsg) {
+        let mut msg = ConsensusMsg::new();
+        msg.set_vote(vote.clone().into_proto());
+        self.record_inbound(vote.author(), &msg);
+    }
+
+    pub fn record_timeout_msg(&self, timeout_msg: &TimeoutMsg) {
+        let mut msg = ConsensusMsg::new();
+        msg.set_timeout_msg(timeout_msg.clone().into_proto());
+        self.record_inbound(timeout_msg.author(), &msg);
+    }
+
+    pub fn record_sync_info(&self, sync_info: &SyncInfo, from: Author) {
+        let mut msg = ConsensusMsg::new();
+        msg.set_sync_info(sync_info.clone().into_proto());
+        self.record_inbound(from, &msg);
+    }
+
+    fn record_inbound(&self, from: Author, msg: &ConsensusMsg) {
+        self.record(RecordedEvent::Inbound {
+            from,
+            msg: msg.write_to_bytes().expect("fail to serialize proto"),
+        });
+    }
+
+    pub fn record_outbound(&self, to: Vec<Author>, msg: &ConsensusMsg) {
+        self.record(RecordedEvent::Outbound {
+            to,
+            msg: msg.write_to_bytes().expect("fail to serialize proto"),
+        });
+    }
+
+    pub fn record_block_retrieval_response(&self, response: Option<&RespondBlock>) {
+        self.record(RecordedEvent::BlockRetrievalResponse {
+            response: response
+                .map(|response| response.write_to_bytes().expect("fail to serialize proto")),
+        });
+    }
+}
+
+fn write_entry(file: &mut File, entry: &RecordedEntry) -> Result<()> {
+    let bytes = to_vec_named(entry)?;
+    let mut buf = Vec::with_capacity(4 + bytes.len());
+    buf.write_u32::<LittleEndian>(bytes.len() as u32)?;
+    buf.extend_from_slice(&bytes);
+    file.write_all(&buf)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Loads the entries recorded to the given file. A truncated last entry, which the validator
+/// didn't finish to write, is ignored.
+pub fn read_recording(path: &Path) -> Result<Vec<RecordedEntry>> {
+    Ok(read_entries(File::open(path)?)?.0)
+}
+
+/// Loads the runs recorded to the given file, each one starting with a start of the validator.
+pub fn read_recorded_runs(path: &Path) -> Result<Vec<Vec<RecordedEntry>>> {
+    let mut runs: Vec<Vec<RecordedEntry>> = vec![];
+    for entry in read_recording(path)? {
+        match (&entry.event, runs.last_mut()) {
+            (RecordedEvent::Start(_), _) => runs.push(vec![entry]),
+            (_, Some(run)) => run.push(entry),
+            (_, None) => bail!("The recording doesn't start with a start of the validator"),
+        }
+    }
+    Ok(runs)
+}
+
+/// Returns the complete entries along with their length in bytes.
+fn read_entries(file: File) -> Result<(Vec<RecordedEntry>, u64)> {
+    let mut reader = BufReader::new(file);
+    let mut entries = vec![];
+    let mut len = 0;
+    loop {
+        let entry_len = match reader.read_u32::<LittleEndian>() {
+            Ok(entry_len) => entry_len,
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        };
+        let mut bytes = vec![0; entry_len as usize];
+        match reader.read_exact(&mut bytes) {
+            Ok(()) => entries.push(rmp_serde::from_slice(&bytes)?),
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        len += 4 + u64::from(entry_len);
+    }
+    Ok((entries, len))
+}
+
+/// Records the results of the wrapped state computer.
+pub struct RecordingStateComputer<T> {
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
+    recorder: Arc<ConsensusRecorder>,
+}
+
+impl<T> RecordingStateComputer<T> {
+    pub fn new(
+        state_computer: Arc<dyn StateComputer<Payload = T>>,
+        recorder: Arc<ConsensusRecorder>,
+    ) -> Self {
+        Self {
+            state_computer,
+            recorder,
+        }
+    }
+}
+
+impl<T: Payload> StateComputer for RecordingStateComputer<T> {
+    type Payload = T;
+
+    fn compute(
+        &self,
+        parent_block_id: HashValue,
+        block_id: HashValue,
+        transactions: &Self::Payload,
+    ) -> Pin<Box<dyn Future<Output = Result<StateComputeResult>> + Send>> {
+        let compute = self
+            .state_computer
+            .compute(parent_block_id, block_id, transactions);
+        let recorder = Arc::clone(&self.recorder);
+        async move {
+            let result = compute.await;
+            recorder.record(RecordedEvent::StateCompute {
+                block_id,
+                executed_state: result
+                    .as_ref()
+                    .ok()
+                    .map(|result| result.executed_state.clone()),
+            });
+            result
+        }
+            .boxed()
+    }
+
+    fn commit(
+        &self,
+        commit: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        let block_id = commit.ledger_info().consensus_block_id();
+        let commit = self.state_computer.commit(commit);
+        let recorder = Arc::clone(&self.recorder);
+        async move {
+            let result = commit.await;
+            recorder.record(RecordedEvent::Commit {
+                block_id,
+                committed: result.is_ok(),
+            });
+            result
+        }
+            .boxed()
+    }
+
+    fn sync_to(&self, commit: QuorumCert) -> Pin<Box<dyn Future<Output = Result<bool>> + Send>> {
+        let block_id = commit.certified_block_id();
+        let sync_to = self.state_computer.sync_to(commit);
+        let recorder = Arc::clone(&self.recorder);
+        async move {
+            let result = sync_to.await;
+            recorder.record(RecordedEvent::SyncTo {
+                block_id,
+                synced: result.as_ref().ok().cloned(),
+            });
+            result
+        }
+            .boxed()
+    }
+}
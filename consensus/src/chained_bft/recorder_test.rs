@@ -0,0 +1,149 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    recorder::{
+        read_recorded_runs, read_recording, ConsensusRecorder, RecordedEvent, RecordedStartup,
+    },
+    test_utils::{MockStorage, TestPayload},
+};
+use crypto::HashValue;
+use executor::ExecutedState;
+use std::{fs::OpenOptions, io::Write};
+use tools::tempdir::TempPath;
+use types::account_address::AccountAddress;
+
+fn startup() -> RecordedEvent {
+    let (storage, initial_data) = MockStorage::<TestPayload>::start_for_testing();
+    RecordedEvent::Start(
+        RecordedStartup::new(AccountAddress::random(), &initial_data, storage.as_ref()).unwrap(),
+    )
+}
+
+fn events() -> Vec<RecordedEvent> {
+    vec![
+        startup(),
+        RecordedEvent::Inbound {
+            from: AccountAddress::random(),
+            msg: vec![1, 2, 3],
+        },
+        RecordedEvent::StateCompute {
+            block_id: HashValue::random(),
+            executed_state: Some(ExecutedState::state_for_genesis()),
+        },
+        RecordedEvent::Outbound {
+            to: vec![AccountAddress::random(), AccountAddress::random()],
+            msg: vec![4, 5],
+        },
+        RecordedEvent::BlockRetrievalResponse { response: None },
+        RecordedEvent::LocalTimeout { round: 7 },
+        RecordedEvent::SyncTo {
+            block_id: HashValue::random(),
+            synced: Some(true),
+        },
+        RecordedEvent::Commit {
+            block_id: HashValue::random(),
+            committed: false,
+        },
+    ]
+}
+
+#[test]
+fn test_in_memory_recording() {
+    let recorder = ConsensusRecorder::in_memory();
+    let events = events();
+    for event in &events {
+        recorder.record(event.clone());
+    }
+    let entries = recorder.entries();
+    assert_eq!(
+        entries
+            .into_iter()
+            .map(|entry| entry.event)
+            .collect::<Vec<_>>(),
+        events
+    );
+}
+
+#[test]
+fn test_file_recording() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let path = dir.path().join("consensus_recording");
+
+    let recorder = ConsensusRecorder::new(&path).unwrap();
+    let events = events();
+    for event in &events {
+        recorder.record(event.clone());
+    }
+    // Nothing is kept in memory.
+    assert!(recorder.entries().is_empty());
+
+    let entries = read_recording(&path).unwrap();
+    assert!(entries
+        .windows(2)
+        .all(|pair| pair[0].timestamp_usecs <= pair[1].timestamp_usecs));
+    assert_eq!(
+        entries
+            .into_iter()
+            .map(|entry| entry.event)
+            .collect::<Vec<_>>(),
+        events
+    );
+
+    // An entry that the validator didn't finish to write is ignored.
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[100, 0, 0, 0, 1, 2]).unwrap();
+    assert_eq!(read_recording(&path).unwrap().len(), events.len());
+}
+
+#[test]
+fn test_recovered_startup() {
+    let (storage, initial_data) = MockStorage::<TestPayload>::start_for_testing();
+    let startup =
+        RecordedStartup::new(AccountAddress::random(), &initial_data, storage.as_ref()).unwrap();
+    let recovered = startup.recovery_data::<TestPayload>().unwrap();
+    assert_eq!(recovered.root(), initial_data.root());
+    assert_eq!(recovered.blocks(), initial_data.blocks());
+    assert_eq!(recovered.quorum_certs(), initial_data.quorum_certs());
+    assert_eq!(recovered.need_sync(), initial_data.need_sync());
+}
+
+#[test]
+fn test_recording_across_restarts() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let path = dir.path().join("consensus_recording");
+    let events = events();
+
+    let recorder = ConsensusRecorder::new(&path).unwrap();
+    for event in &events {
+        recorder.record(event.clone());
+    }
+    drop(recorder);
+    // The validator stops in the middle of an entry.
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[100, 0, 0, 0, 1, 2]).unwrap();
+
+    // The next start appends a new run, after the entries that were written completely.
+    let recorder = ConsensusRecorder::new(&path).unwrap();
+    for event in &events[..2] {
+        recorder.record(event.clone());
+    }
+    let runs = read_recorded_runs(&path).unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(
+        runs[0]
+            .iter()
+            .map(|entry| entry.event.clone())
+            .collect::<Vec<_>>(),
+        events
+    );
+    assert_eq!(
+        runs[1]
+            .iter()
+            .map(|entry| entry.event.clone())
+            .collect::<Vec<_>>(),
+        &events[..2]
+    );
+}
@@ -0,0 +1,667 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replays the events that a `ConsensusRecorder` recorded for a validator (see the
+//! `recording_path` of the consensus config) into a fresh event processor, so that the decisions
+//! of the validator can be reproduced, e.g. to debug a liveness issue.
+//!
+//! Each start of the validator begins a run of the recording, which is replayed on its own: the
+//! block tree, the consensus state and the epoch are recovered from the recorded startup data.
+//! The time follows the recorded timestamps, the blocks are "executed" to the recorded states,
+//! the state synchronizer and the commits to the ledger get the recorded results, and the peers
+//! answer the block retrieval requests with the recorded responses. The validator must be set up
+//! as it was when recording: same signer, config and validators of the first epoch. The runs in
+//! the decoupled execution mode can't be replayed yet.
+
+use crate::{
+    chained_bft::{
+        block_storage::BlockStore,
+        chained_bft_consensus_provider::ChainedBftProvider,
+        chained_bft_smr::{ChainedBftSMRConfig, EpochContext},
+        common::{Author, Payload, Round},
+        consensus_types::{
+            block::Block,
+            proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
+            sync_info::SyncInfo,
+            timeout_msg::TimeoutMsg,
+            vote_msg::VoteMsg,
+        },
+        epoch_manager::EpochManager,
+        event_processor::EventProcessor,
+        liveness::{
+            leader_reputation::BlockMetadata, pacemaker_timeout_manager::HighestTimeoutCertificates,
+        },
+        network::{BlockRetrievalRequest, ConsensusNetworkImpl},
+        persistent_storage::{PersistentLivenessStorage, PersistentStorage, RecoveryData},
+        recorder::{ConsensusRecorder, RecordedEntry, RecordedEvent},
+        safety::{
+            local_safety_rules::LocalSafetyRules, persistent_safety_storage::InMemoryStorage,
+            safety_rules::ConsensusState,
+        },
+        QuorumCert,
+    },
+    state_replication::{StateComputer, TxnManager},
+    util::time_service::{ScheduledTask, TimeService},
+};
+use bytes::Bytes;
+use config::config::NodeConfig;
+use crypto::HashValue;
+use executor::{ExecutedState, StateComputeResult};
+use failure::prelude::*;
+use futures::{
+    channel::oneshot, executor::block_on, future, Future, FutureExt, StreamExt, TryFutureExt,
+};
+use logger::prelude::*;
+use network::{
+    interface::NetworkRequest,
+    proto::ConsensusMsg,
+    validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender},
+};
+use proto_conv::FromProto;
+use protobuf::Message;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    convert::TryFrom,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::runtime::Runtime;
+use types::{
+    account_address::AccountAddress,
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier},
+};
+
+/// The decision that an outbound message carries, leaving out what a replay can't reproduce:
+/// the signatures, and the timestamps (hence the ids) of the blocks that the validator proposes.
+#[derive(Debug, PartialEq)]
+pub enum Decision {
+    Propose {
+        round: Round,
+        parent_id: HashValue,
+    },
+    Vote {
+        block_id: HashValue,
+        executed_state_id: HashValue,
+        committed_block_id: HashValue,
+    },
+    Timeout {
+        round: Round,
+    },
+    SyncInfo {
+        hqc_block_id: HashValue,
+        highest_ledger_info_block_id: HashValue,
+        htc_round: Round,
+    },
+}
+
+impl Decision {
+    fn from_msg<T: Payload>(msg: &[u8]) -> Result<Self> {
+        let mut msg: ConsensusMsg = protobuf::parse_from_bytes(msg)?;
+        Ok(if msg.has_proposal() {
+            let proposal: ProposalMsg<T> =
+                ProposalUncheckedSignatures::<T>::from_proto(msg.take_proposal())?.into();
+            Decision::Propose {
+                round: proposal.round(),
+                parent_id: proposal.proposal().parent_id(),
+            }
+        } else if msg.has_vote() {
+            let vote = VoteMsg::from_proto(msg.take_vote())?;
+            Decision::Vote {
+                block_id: vote.block_id(),
+                executed_state_id: vote.executed_state_id(),
+                committed_block_id: vote.ledger_info().consensus_block_id(),
+            }
+        } else if msg.has_timeout_msg() {
+            let timeout_msg = TimeoutMsg::from_proto(msg.take_timeout_msg())?;
+            Decision::Timeout {
+                round: timeout_msg.pacemaker_timeout().round(),
+            }
+        } else if msg.has_sync_info() {
+            let sync_info = SyncInfo::from_proto(msg.take_sync_info())?;
+            Decision::SyncInfo {
+                hqc_block_id: sync_info.highest_quorum_cert().certified_block_id(),
+                highest_ledger_info_block_id: sync_info.highest_ledger_info().certified_block_id(),
+                htc_round: sync_info.htc_round(),
+            }
+        } else {
+            bail!("Unexpected outbound msg: {:?}", msg);
+        })
+    }
+}
+
+fn outbound_decisions<T: Payload>(
+    entries: &[RecordedEntry],
+) -> Result<Vec<(Vec<Author>, Decision)>> {
+    entries
+        .iter()
+        .filter_map(|entry| match &entry.event {
+            RecordedEvent::Outbound { to, msg } => {
+                Some(Decision::from_msg::<T>(msg).map(|decision| (to.clone(), decision)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// "Executes" the blocks to the recorded states and hands out the recorded results of the state
+/// synchronizer and of the commits, in order.
+struct ReplayStateComputer<T> {
+    executed_states: HashMap<HashValue, Option<ExecutedState>>,
+    sync_results: Mutex<VecDeque<(HashValue, Option<bool>)>>,
+    commit_results: Mutex<VecDeque<(HashValue, bool)>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Payload> StateComputer for ReplayStateComputer<T> {
+    type Payload = T;
+
+    fn compute(
+        &self,
+        _parent_block_id: HashValue,
+        block_id: HashValue,
+        _transactions: &Self::Payload,
+    ) -> Pin<Box<dyn Future<Output = Result<StateComputeResult>> + Send>> {
+        let result = match self.executed_states.get(&block_id) {
+            Some(Some(executed_state)) => Ok(StateComputeResult {
+                executed_state: executed_state.clone(),
+                compute_status: vec![],
+            }),
+            Some(None) => Err(format_err!(
+                "Recorded failure to execute block {}",
+                block_id
+            )),
+            None => Err(format_err!("No recorded execution of block {}", block_id)),
+        };
+        future::ready(result).boxed()
+    }
+
+    fn commit(
+        &self,
+        commit: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        let block_id = commit.ledger_info().consensus_block_id();
+        let result = match self.commit_results.lock().unwrap().pop_front() {
+            Some((recorded_block_id, committed)) if recorded_block_id == block_id => {
+                if committed {
+                    Ok(())
+                } else {
+                    Err(format_err!("Recorded failure to commit block {}", block_id))
+                }
+            }
+            Some((recorded_block_id, _)) => Err(format_err!(
+                "Commit diverges: recorded block {}, replayed block {}",
+                recorded_block_id,
+                block_id
+            )),
+            None => Err(format_err!("No recorded commit of block {}", block_id)),
+        };
+        future::ready(result).boxed()
+    }
+
+    fn sync_to(&self, commit: QuorumCert) -> Pin<Box<dyn Future<Output = Result<bool>> + Send>> {
+        let block_id = commit.certified_block_id();
+        let result = match self.sync_results.lock().unwrap().pop_front() {
+            Some((recorded_block_id, synced)) if recorded_block_id == block_id => {
+                synced.ok_or_else(|| format_err!("Recorded failure to sync to block {}", block_id))
+            }
+            Some((recorded_block_id, _)) => Err(format_err!(
+                "Sync diverges: recorded block {}, replayed block {}",
+                recorded_block_id,
+                block_id
+            )),
+            None => Err(format_err!("No recorded sync to block {}", block_id)),
+        };
+        future::ready(result).boxed()
+    }
+}
+
+/// Hands out the payloads of the recorded proposals of the validator, in order.
+struct ReplayTxnManager<T> {
+    payloads: Mutex<VecDeque<T>>,
+}
+
+impl<T: Payload> TxnManager for ReplayTxnManager<T> {
+    type Payload = T;
+
+    fn pull_txns(
+        &self,
+        _max_size: u64,
+        _exclude_txns: Vec<&Self::Payload>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>> {
+        let payload = self
+            .payloads
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_default();
+        future::ok(payload).boxed()
+    }
+
+    fn commit_txns<'a>(
+        &'a self,
+        _txns: &Self::Payload,
+        _compute_result: &StateComputeResult,
+        _timestamp_usecs: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        future::ok(()).boxed()
+    }
+}
+
+/// Keeps the metadata of the committed blocks, which the leader reputation depends on. The rest
+/// isn't read back during a replay.
+struct ReplayStorage {
+    block_metadata: Mutex<BTreeMap<(u64, Round), BlockMetadata>>,
+}
+
+impl ReplayStorage {
+    fn new(epoch: u64, block_metadata: Vec<BlockMetadata>) -> Self {
+        Self {
+            block_metadata: Mutex::new(
+                block_metadata
+                    .into_iter()
+                    .map(|metadata| ((epoch, metadata.round()), metadata))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl PersistentLivenessStorage for ReplayStorage {
+    fn save_highest_timeout_cert(&self, _: HighestTimeoutCertificates) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: Payload> PersistentStorage<T> for ReplayStorage {
+    fn persistent_liveness_storage(&self) -> Box<dyn PersistentLivenessStorage> {
+        Box::new(ReplayStorage::new(0, vec![]))
+    }
+
+    fn save_tree(&self, _: Vec<Block<T>>, _: Vec<QuorumCert>) -> Result<()> {
+        Ok(())
+    }
+
+    fn prune_tree(&self, _: Vec<HashValue>) -> Result<()> {
+        Ok(())
+    }
+
+    fn save_consensus_state(&self, _: ConsensusState) -> Result<()> {
+        Ok(())
+    }
+
+    fn save_block_metadata(
+        &self,
+        epoch: u64,
+        block_metadata: Vec<BlockMetadata>,
+        min_round: Round,
+    ) -> Result<()> {
+        let mut stored = self.block_metadata.lock().unwrap();
+        for metadata in block_metadata {
+            stored.insert((epoch, metadata.round()), metadata);
+        }
+        *stored = stored.split_off(&(epoch, min_round));
+        Ok(())
+    }
+
+    fn get_block_metadata(&self, epoch: u64) -> Result<Vec<BlockMetadata>> {
+        Ok(self
+            .block_metadata
+            .lock()
+            .unwrap()
+            .range((epoch, 0)..=(epoch, Round::max_value()))
+            .map(|(_, metadata)| metadata.clone())
+            .collect())
+    }
+
+    fn start(_: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
+        unreachable!("A replay starts from the recorded startup data");
+    }
+}
+
+/// Follows the times of the recorded events. The tasks scheduled to run later never run: the
+/// local timeouts they lead to are replayed from the recording.
+struct ReplayTimeService {
+    now: Mutex<Duration>,
+}
+
+impl ReplayTimeService {
+    fn advance_to(&self, time: Duration) {
+        let mut now = self.now.lock().unwrap();
+        if time > *now {
+            *now = time;
+        }
+    }
+}
+
+impl TimeService for ReplayTimeService {
+    fn run_after(&self, _timeout: Duration, _task: Box<dyn ScheduledTask>) {}
+
+    fn get_current_timestamp(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+
+    fn sleep(&self, t: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        *self.now.lock().unwrap() += t;
+        future::ready(()).boxed()
+    }
+}
+
+/// Answers the block retrieval requests with the recorded responses, in order: the request
+/// fails if it did when recording. The other messages are dropped, the recorder of the network
+/// already got them.
+async fn answer_network_requests(
+    mut network_reqs_rx: channel::Receiver<NetworkRequest>,
+    mut responses: VecDeque<Option<Vec<u8>>>,
+) {
+    while let Some(request) = network_reqs_rx.next().await {
+        if let NetworkRequest::SendRpc(_, rpc) = request {
+            if let Some(Some(response)) = responses.pop_front() {
+                let respond_block = match protobuf::parse_from_bytes(&response) {
+                    Ok(respond_block) => respond_block,
+                    Err(e) => {
+                        error!("Invalid recorded block retrieval response: {:?}", e);
+                        continue;
+                    }
+                };
+                let mut msg = ConsensusMsg::new();
+                msg.set_respond_block(respond_block);
+                let data = Bytes::from(msg.write_to_bytes().expect("fail to serialize proto"));
+                let _ = rpc.res_tx.send(Ok(data));
+            }
+        }
+    }
+}
+
+/// A validator whose event processor is fed the recorded events of a run instead of those of a
+/// live network.
+pub struct ReplayNode<T> {
+    context: EpochContext<T>,
+    event_processor: EventProcessor<T>,
+    epoch_change_proof: Vec<LedgerInfoWithSignatures>,
+    time_service: Arc<ReplayTimeService>,
+    // Records what the event processor sends, to compare it with the recording.
+    recorder: Arc<ConsensusRecorder>,
+    recording: Vec<RecordedEntry>,
+    _runtime: Runtime,
+}
+
+impl<T: Payload> ReplayNode<T> {
+    /// Sets up the validator of the given node config to replay the given run, as it was set up
+    /// when recording.
+    pub fn from_node_config(
+        node_config: &mut NodeConfig,
+        recording: Vec<RecordedEntry>,
+    ) -> Result<Self> {
+        let peer_id = node_config
+            .get_validator_network_config()
+            .ok_or_else(|| format_err!("The node config is not the one of a validator"))?
+            .peer_id
+            .clone();
+        let author = AccountAddress::try_from(peer_id)?;
+        let signer = ChainedBftProvider::take_signer(node_config, author);
+        let validators = ChainedBftProvider::first_epoch_validator(&node_config.consensus)?;
+        let config = ChainedBftSMRConfig::from_node_config(&node_config.consensus);
+        Self::new(signer, validators, config, recording)
+    }
+
+    /// The validators are the ones of the first epoch, the recording holds the ledger infos ending
+    /// the next ones.
+    pub fn new(
+        signer: ValidatorSigner,
+        validators: ValidatorVerifier,
+        config: ChainedBftSMRConfig,
+        recording: Vec<RecordedEntry>,
+    ) -> Result<Self> {
+        let author = signer.author();
+        let (start_time, startup) = match recording.first() {
+            Some(RecordedEntry {
+                timestamp_usecs,
+                event: RecordedEvent::Start(startup),
+            }) => (Duration::from_micros(*timestamp_usecs), startup),
+            _ => bail!("The recording doesn't start with a start of the validator"),
+        };
+        ensure!(
+            startup.author == author,
+            "The recording is the one of validator {}, not {}",
+            startup.author,
+            author
+        );
+        ensure!(
+            !config.decoupled_execution,
+            "The runs in the decoupled execution mode can't be replayed"
+        );
+        let initial_data = startup.recovery_data::<T>()?;
+        let storage = Arc::new(ReplayStorage::new(
+            initial_data.epoch(),
+            startup.block_metadata.clone(),
+        ));
+
+        let mut executed_states = HashMap::new();
+        let mut sync_results = VecDeque::new();
+        let mut commit_results = VecDeque::new();
+        let mut payloads = VecDeque::new();
+        let mut responses = VecDeque::new();
+        for entry in &recording {
+            match &entry.event {
+                RecordedEvent::StateCompute {
+                    block_id,
+                    executed_state,
+                } => {
+                    executed_states.insert(*block_id, executed_state.clone());
+                }
+                RecordedEvent::SyncTo { block_id, synced } => {
+                    sync_results.push_back((*block_id, *synced));
+                }
+                RecordedEvent::Commit {
+                    block_id,
+                    committed,
+                } => {
+                    commit_results.push_back((*block_id, *committed));
+                }
+                RecordedEvent::Outbound { msg, .. } => {
+                    let mut msg: ConsensusMsg = protobuf::parse_from_bytes(msg)?;
+                    if msg.has_proposal() {
+                        let proposal: ProposalMsg<T> =
+                            ProposalUncheckedSignatures::<T>::from_proto(msg.take_proposal())?
+                                .into();
+                        payloads.push_back(proposal.proposal().get_payload().clone());
+                    }
+                }
+                RecordedEvent::BlockRetrievalResponse { response } => {
+                    responses.push_back(response.clone());
+                }
+                _ => (),
+            }
+        }
+        let state_computer = Arc::new(ReplayStateComputer {
+            executed_states,
+            sync_results: Mutex::new(sync_results),
+            commit_results: Mutex::new(commit_results),
+            phantom: PhantomData,
+        });
+        let txn_manager = Arc::new(ReplayTxnManager {
+            payloads: Mutex::new(payloads),
+        });
+
+        let runtime = Runtime::new()?;
+        let epoch_mgr = Arc::new(EpochManager::new(0, validators));
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(1_024);
+        let (_consensus_tx, consensus_rx) = channel::new_test(1_024);
+        let recorder = Arc::new(ConsensusRecorder::in_memory());
+        let mut network = ConsensusNetworkImpl::new(
+            author,
+            ConsensusNetworkSender::new(network_reqs_tx),
+            ConsensusNetworkEvents::new(consensus_rx),
+            Arc::clone(&epoch_mgr),
+        )
+        .with_recorder(Arc::clone(&recorder));
+        // The recorded inbound messages are fed to the event processor directly: the messages
+        // that the event processor sends to itself are dropped along with the receivers.
+        let _network_receivers = network.start::<T>(&runtime.executor());
+        runtime.executor().spawn(
+            answer_network_requests(network_reqs_rx, responses)
+                .boxed()
+                .unit_error()
+                .compat(),
+        );
+
+        // The validator starts as it did when recording.
+        if initial_data.need_sync() {
+            ensure!(
+                block_on(state_computer.sync_to(initial_data.root_ledger_info()))?,
+                "The state synchronizer didn't sync to the root"
+            );
+        }
+        let safety_rules = Box::new(LocalSafetyRules::new(
+            Arc::new(signer),
+            Box::new(InMemoryStorage::new(initial_data.state())),
+        ));
+        let highest_timeout_certificates = initial_data.highest_timeout_certificates().clone();
+        let mut epoch_change_proof = initial_data.epoch_change_proof().to_vec();
+        let root_ledger_info = initial_data.root_ledger_info().ledger_info().clone();
+        let time_service = Arc::new(ReplayTimeService {
+            now: Mutex::new(start_time),
+        });
+        let block_store = Arc::new(block_on(BlockStore::new(
+            storage.clone(),
+            initial_data,
+            state_computer.clone(),
+            true,
+            config.max_pruned_blocks_in_mem,
+        )));
+        let context = EpochContext {
+            author,
+            config,
+            block_store,
+            txn_manager,
+            state_computer,
+            network,
+            storage,
+            time_service: time_service.clone(),
+            epoch_mgr,
+            execution_pipeline: None,
+            execution_latency: None,
+        };
+        // The local timeouts are recorded, the ones of the pacemaker never fire.
+        let (event_processor, _timeout_receiver) = block_on(context.start_from_root(
+            root_ledger_info,
+            &mut epoch_change_proof,
+            safety_rules,
+            highest_timeout_certificates,
+            context.epoch_proposers(),
+        ));
+        Ok(Self {
+            context,
+            event_processor,
+            epoch_change_proof,
+            time_service,
+            recorder,
+            recording,
+            _runtime: runtime,
+        })
+    }
+
+    /// Feeds the recorded events to the event processor, moving to the next epochs as the
+    /// validator did, and checks that it takes the recorded decisions, in the same order. The
+    /// recording may stop in the middle of the processing of an event (e.g. when the validator is
+    /// stopped): the messages sent after the last recorded one are ignored.
+    pub fn replay(self) -> Result<()> {
+        let ReplayNode {
+            context,
+            mut event_processor,
+            mut epoch_change_proof,
+            time_service,
+            recorder,
+            recording,
+            _runtime,
+        } = self;
+        block_on(event_processor.start());
+        for entry in &recording {
+            time_service.advance_to(Duration::from_micros(entry.timestamp_usecs));
+            match &entry.event {
+                RecordedEvent::Inbound { from, msg } => {
+                    process_inbound(&mut event_processor, *from, msg)?
+                }
+                RecordedEvent::BlockRetrievalRequest {
+                    block_id,
+                    num_blocks,
+                } => {
+                    // Nobody waits for the blocks anymore.
+                    let (response_sender, _) = oneshot::channel();
+                    block_on(
+                        event_processor.process_block_retrieval(BlockRetrievalRequest {
+                            block_id: *block_id,
+                            num_blocks: *num_blocks,
+                            response_sender,
+                        }),
+                    );
+                }
+                RecordedEvent::LocalTimeout { round } => {
+                    block_on(event_processor.process_local_timeout(*round))
+                }
+                // The startup data was recovered from already, the results of the state computer
+                // and the block retrieval responses are served on demand, the outbound messages
+                // are compared below.
+                RecordedEvent::Start(_)
+                | RecordedEvent::Outbound { .. }
+                | RecordedEvent::BlockRetrievalResponse { .. }
+                | RecordedEvent::StateCompute { .. }
+                | RecordedEvent::SyncTo { .. }
+                | RecordedEvent::Commit { .. } => continue,
+            }
+            if !event_processor.epoch_change_proof().is_empty() {
+                epoch_change_proof.extend_from_slice(event_processor.epoch_change_proof());
+                event_processor = block_on(
+                    context
+                        .start_new_epoch(&epoch_change_proof, event_processor.into_safety_rules()),
+                )
+                .0;
+                block_on(event_processor.start());
+            }
+        }
+
+        let recorded = outbound_decisions::<T>(&recording)?;
+        let replayed = outbound_decisions::<T>(&recorder.entries())?;
+        for (i, recorded) in recorded.iter().enumerate() {
+            let replayed = replayed.get(i).ok_or_else(|| {
+                format_err!("Outbound message {} {:?} is not replayed", i, recorded)
+            })?;
+            ensure!(
+                recorded == replayed,
+                "Outbound message {} diverges: recorded {:?}, replayed {:?}",
+                i,
+                recorded,
+                replayed
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The recorded messages were verified before being handed over to the event processor.
+fn process_inbound<T: Payload>(
+    event_processor: &mut EventProcessor<T>,
+    from: Author,
+    msg: &[u8],
+) -> Result<()> {
+    let mut msg: ConsensusMsg = protobuf::parse_from_bytes(msg)?;
+    if msg.has_proposal() {
+        let proposal: ProposalMsg<T> =
+            ProposalUncheckedSignatures::<T>::from_proto(msg.take_proposal())?.into();
+        block_on(event_processor.process_proposal_msg(proposal));
+    } else if msg.has_vote() {
+        let vote = VoteMsg::from_proto(msg.take_vote())?;
+        block_on(event_processor.process_vote(vote));
+    } else if msg.has_timeout_msg() {
+        let timeout_msg = TimeoutMsg::from_proto(msg.take_timeout_msg())?;
+        block_on(event_processor.process_remote_timeout_msg(timeout_msg));
+    } else if msg.has_sync_info() {
+        let sync_info = SyncInfo::from_proto(msg.take_sync_info())?;
+        block_on(event_processor.process_sync_info_msg(sync_info, from));
+    } else {
+        bail!("Unexpected inbound msg: {:?}", msg);
+    }
+    Ok(())
+}
@@ -0,0 +1,180 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Payload, Round},
+    consensus_types::{
+        block::{Block, BlockData},
+        commit_vote::CommitVote,
+        quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
+        timeout_msg::{PacemakerTimeout, TimeoutMsg},
+        vote_data::VoteData,
+        vote_msg::VoteMsg,
+    },
+    safety::{
+        persistent_safety_storage::PersistentSafetyStorage,
+        safety_rules::{ConsensusState, SafetyRules},
+        t_safety_rules::TSafetyRules,
+    },
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use std::sync::Arc;
+use types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner},
+    ledger_info::LedgerInfo,
+};
+
+#[cfg(test)]
+#[path = "local_safety_rules_test.rs"]
+mod local_safety_rules_test;
+
+/// The safety rules running in the current process, either the consensus one or the safety rules
+/// service (see `SafetyRulesServer`).
+pub struct LocalSafetyRules {
+    safety_rules: SafetyRules,
+    validator_signer: Arc<ValidatorSigner>,
+    storage: Box<dyn PersistentSafetyStorage>,
+}
+
+impl LocalSafetyRules {
+    /// Starts from the consensus state kept in the given storage.
+    pub fn new(
+        validator_signer: Arc<ValidatorSigner>,
+        storage: Box<dyn PersistentSafetyStorage>,
+    ) -> Self {
+        Self {
+            safety_rules: SafetyRules::new(storage.consensus_state()),
+            validator_signer,
+            storage,
+        }
+    }
+}
+
+impl<T: Payload> TSafetyRules<T> for LocalSafetyRules {
+    fn consensus_state(&self) -> Result<ConsensusState> {
+        Ok(self.safety_rules.consensus_state())
+    }
+
+    fn update(&mut self, qc: &QuorumCert) -> Result<()> {
+        self.safety_rules.update(qc);
+        Ok(())
+    }
+
+    fn start_new_epoch(&mut self, epoch_change_proof: &[LedgerInfoWithSignatures]) -> Result<()> {
+        let epoch = match epoch_change_proof.last() {
+            Some(ledger_info) => ledger_info.ledger_info().epoch_num() + 1,
+            None => return Ok(()),
+        };
+        if let Some(consensus_state) = self.safety_rules.start_new_epoch(epoch) {
+            self.storage.set_consensus_state(consensus_state)?;
+        }
+        Ok(())
+    }
+
+    fn sign_proposal(&mut self, block_data: BlockData<T>) -> Result<Block<T>> {
+        let consensus_state = self.safety_rules.consensus_state();
+        ensure!(
+            block_data.round() > consensus_state.last_vote_round(),
+            "Proposal round {} is not higher than the last vote round {}",
+            block_data.round(),
+            consensus_state.last_vote_round()
+        );
+        ensure!(
+            block_data.quorum_cert().certified_block_round()
+                >= consensus_state.preferred_block_round(),
+            "Proposal extends round {} below the preferred block round {}",
+            block_data.quorum_cert().certified_block_round(),
+            consensus_state.preferred_block_round()
+        );
+        Ok(block_data.sign(&self.validator_signer))
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        proposed_block: &Block<T>,
+        executed_state_id: HashValue,
+        ledger_info_placeholder: LedgerInfo,
+    ) -> Result<VoteMsg> {
+        // The signature covers the ledger info too: it must not commit anything but what the
+        // commit rule allows.
+        let potential_commit_id = SafetyRules::commit_rule_for_certified_block(
+            proposed_block.quorum_cert(),
+            proposed_block.round(),
+        );
+        let committed_block_id = ledger_info_placeholder.consensus_block_id();
+        ensure!(
+            committed_block_id == HashValue::zero()
+                || Some(committed_block_id) == potential_commit_id,
+            "Ledger info placeholder commits {} instead of {:?}",
+            committed_block_id,
+            potential_commit_id
+        );
+
+        let vote_info = self.safety_rules.voting_rule(proposed_block)?;
+        self.storage
+            .set_consensus_state(vote_info.consensus_state().clone())?;
+
+        Ok(VoteMsg::new(
+            VoteData::new(
+                vote_info.proposal_id(),
+                executed_state_id,
+                proposed_block.round(),
+                vote_info.parent_block_id(),
+                vote_info.parent_block_round(),
+                vote_info.grandparent_block_id(),
+                vote_info.grandparent_block_round(),
+            ),
+            self.validator_signer.author(),
+            ledger_info_placeholder,
+            &self.validator_signer,
+        ))
+    }
+
+    fn sign_timeout(
+        &mut self,
+        sync_info: SyncInfo,
+        round: Round,
+        vote: Option<VoteMsg>,
+    ) -> Result<TimeoutMsg> {
+        // Stop voting at this round, the state is also persisted to support restarting from a
+        // recent round (i.e. > the last vote round).
+        if let Some(consensus_state) = self.safety_rules.increase_last_vote_round(round) {
+            self.storage.set_consensus_state(consensus_state)?;
+        }
+        Ok(TimeoutMsg::new(
+            sync_info,
+            PacemakerTimeout::new(round, &self.validator_signer, vote),
+            &self.validator_signer,
+        ))
+    }
+
+    fn sign_commit_vote(&mut self, ledger_info: LedgerInfo) -> Result<CommitVote> {
+        let epoch = self.safety_rules.consensus_state().epoch();
+        ensure!(
+            ledger_info.epoch_num() == epoch,
+            "Commit vote for epoch {} in epoch {}",
+            ledger_info.epoch_num(),
+            epoch
+        );
+        // Only the blocks that a quorum cert ordered get committed, each at most once and in
+        // order: the ledger info of a block at or below the last commit-voted round, be it the
+        // same block or a conflicting one, is never signed.
+        let block_id = ledger_info.consensus_block_id();
+        let round = self
+            .safety_rules
+            .ordered_block_round(block_id)
+            .ok_or_else(|| {
+                format_err!(
+                    "Block {} isn't ordered after the last commit vote round {}",
+                    block_id,
+                    self.safety_rules.consensus_state().last_commit_vote_round()
+                )
+            })?;
+        if let Some(consensus_state) = self.safety_rules.increase_last_commit_vote_round(round) {
+            self.storage.set_consensus_state(consensus_state)?;
+        }
+        Ok(CommitVote::new(ledger_info, &self.validator_signer))
+    }
+}
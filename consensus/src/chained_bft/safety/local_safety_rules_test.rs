@@ -0,0 +1,300 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::BlockReader,
+    consensus_types::sync_info::SyncInfo,
+    safety::{
+        local_safety_rules::LocalSafetyRules,
+        persistent_safety_storage::{InMemoryStorage, OnDiskStorage, PersistentSafetyStorage},
+        safety_rules::ConsensusState,
+        t_safety_rules::TSafetyRules,
+    },
+    test_utils::{
+        build_empty_tree, epoch_ending_ledger_info, ordering_certificate_for_block,
+        placeholder_ledger_info, TestPayload, TreeInserter,
+    },
+};
+use crypto::HashValue;
+use std::sync::Arc;
+use tools::tempdir::TempPath;
+use types::{crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo};
+
+fn make_safety_rules(
+    storage: Box<dyn PersistentSafetyStorage>,
+) -> Box<dyn TSafetyRules<TestPayload>> {
+    make_safety_rules_with_signer(Arc::new(ValidatorSigner::random(None)), storage)
+}
+
+fn make_safety_rules_with_signer(
+    signer: Arc<ValidatorSigner>,
+    storage: Box<dyn PersistentSafetyStorage>,
+) -> Box<dyn TSafetyRules<TestPayload>> {
+    Box::new(LocalSafetyRules::new(signer, storage))
+}
+
+#[test]
+fn test_vote_once_per_round() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(&genesis, 1);
+
+    let mut safety_rules = make_safety_rules(Box::new(InMemoryStorage::default()));
+    let executed_state_id = HashValue::random();
+    let vote = safety_rules
+        .construct_and_sign_vote(&a1, executed_state_id, placeholder_ledger_info())
+        .unwrap();
+    assert_eq!(vote.block_id(), a1.id());
+    assert_eq!(vote.block_round(), 1);
+    assert_eq!(vote.executed_state_id(), executed_state_id);
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 1);
+
+    // Never vote twice at the same round.
+    assert!(safety_rules
+        .construct_and_sign_vote(&a1, executed_state_id, placeholder_ledger_info())
+        .is_err());
+}
+
+#[test]
+fn test_ledger_info_placeholder_must_follow_commit_rule() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(&genesis, 1);
+
+    let mut safety_rules = make_safety_rules(Box::new(InMemoryStorage::default()));
+    // Voting for a1 doesn't commit anything, let alone a random block.
+    let ledger_info = LedgerInfo::new(
+        0,
+        HashValue::zero(),
+        HashValue::zero(),
+        HashValue::random(),
+        0,
+        0,
+        None,
+    );
+    assert!(safety_rules
+        .construct_and_sign_vote(&a1, HashValue::zero(), ledger_info)
+        .is_err());
+    // Nothing is signed, the round is still available.
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 0);
+}
+
+#[test]
+fn test_sign_timeout_stops_voting() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(&genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2);
+
+    let mut safety_rules = make_safety_rules(Box::new(InMemoryStorage::default()));
+    let sync_info = SyncInfo::new(
+        block_tree.highest_quorum_cert().as_ref().clone(),
+        block_tree.highest_ledger_info().as_ref().clone(),
+        None,
+    );
+    let timeout_msg = safety_rules.sign_timeout(sync_info, 2, None).unwrap();
+    assert_eq!(timeout_msg.pacemaker_timeout().round(), 2);
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 2);
+
+    assert!(safety_rules
+        .construct_and_sign_vote(&a2, HashValue::zero(), placeholder_ledger_info())
+        .is_err());
+}
+
+#[test]
+fn test_sign_proposal_in_new_round() {
+    let block_tree = build_empty_tree();
+    let genesis = block_tree.root();
+
+    let mut safety_rules = make_safety_rules(Box::new(InMemoryStorage::default()));
+    let proposal = safety_rules
+        .sign_proposal(block_tree.create_block(&genesis, vec![1], 1, 1))
+        .unwrap();
+    assert_eq!(proposal.round(), 1);
+    assert!(proposal.author().is_some());
+    // The proposer can still vote for its own proposal.
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 0);
+
+    // No proposal in a round that was voted on already.
+    let sync_info = SyncInfo::new(
+        block_tree.highest_quorum_cert().as_ref().clone(),
+        block_tree.highest_ledger_info().as_ref().clone(),
+        None,
+    );
+    safety_rules.sign_timeout(sync_info, 2, None).unwrap();
+    assert!(safety_rules
+        .sign_proposal(block_tree.create_block(&genesis, vec![2], 2, 2))
+        .is_err());
+}
+
+#[test]
+fn test_sign_commit_vote_for_ordered_blocks_only() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(&genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2);
+    let a3 = inserter.insert_block(&a2, 3);
+    let a4 = inserter.insert_block(&a3, 4);
+    let ledger_info_for = |block_id, epoch| {
+        LedgerInfo::new(
+            1,
+            HashValue::zero(),
+            HashValue::zero(),
+            block_id,
+            epoch,
+            0,
+            None,
+        )
+    };
+
+    let mut safety_rules = make_safety_rules(Box::new(InMemoryStorage::default()));
+    // Nothing is ordered yet.
+    assert!(safety_rules
+        .sign_commit_vote(ledger_info_for(a1.id(), 0))
+        .is_err());
+
+    // The QC certifying a3 orders a1.
+    safety_rules
+        .update(&ordering_certificate_for_block(vec![], &a3))
+        .unwrap();
+    assert!(safety_rules
+        .sign_commit_vote(ledger_info_for(a2.id(), 0))
+        .is_err());
+    assert!(safety_rules
+        .sign_commit_vote(ledger_info_for(a1.id(), 1))
+        .is_err());
+    let commit_vote = safety_rules
+        .sign_commit_vote(ledger_info_for(a1.id(), 0))
+        .unwrap();
+    assert_eq!(commit_vote.ledger_info(), &ledger_info_for(a1.id(), 0));
+    assert_eq!(
+        safety_rules
+            .consensus_state()
+            .unwrap()
+            .last_commit_vote_round(),
+        1
+    );
+    // A block is commit voted for only once.
+    assert!(safety_rules
+        .sign_commit_vote(ledger_info_for(a1.id(), 0))
+        .is_err());
+
+    // Once a later block is commit voted for, the earlier ones aren't anymore.
+    safety_rules
+        .update(&ordering_certificate_for_block(vec![], &a4))
+        .unwrap();
+    let a5 = inserter.insert_block(&a4, 5);
+    safety_rules
+        .update(&ordering_certificate_for_block(vec![], &a5))
+        .unwrap();
+    safety_rules
+        .sign_commit_vote(ledger_info_for(a3.id(), 0))
+        .unwrap();
+    assert!(safety_rules
+        .sign_commit_vote(ledger_info_for(a2.id(), 0))
+        .is_err());
+}
+
+#[test]
+fn test_last_commit_vote_round_persisted() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(&genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2);
+    let a3 = inserter.insert_block(&a2, 3);
+    let qc = ordering_certificate_for_block(vec![], &a3);
+    let ledger_info = LedgerInfo::new(1, HashValue::zero(), HashValue::zero(), a1.id(), 0, 0, None);
+
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let file_path = dir.path().join("safety_rules.json");
+    let signer = Arc::new(ValidatorSigner::random(None));
+    let make_on_disk_safety_rules = || {
+        make_safety_rules_with_signer(
+            Arc::clone(&signer),
+            Box::new(
+                OnDiskStorage::new_with_default(
+                    file_path.clone(),
+                    ConsensusState::default(),
+                    Arc::clone(&signer),
+                )
+                .unwrap(),
+            ),
+        )
+    };
+
+    let mut safety_rules = make_on_disk_safety_rules();
+    safety_rules.update(&qc).unwrap();
+    safety_rules.sign_commit_vote(ledger_info.clone()).unwrap();
+
+    // The same block isn't commit voted for again after a restart, even if it's ordered again.
+    let mut safety_rules = make_on_disk_safety_rules();
+    safety_rules.update(&qc).unwrap();
+    assert!(safety_rules.sign_commit_vote(ledger_info).is_err());
+}
+
+#[test]
+fn test_start_new_epoch() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(&genesis, 1);
+
+    let mut safety_rules = make_safety_rules(Box::new(InMemoryStorage::default()));
+    safety_rules
+        .construct_and_sign_vote(&a1, HashValue::zero(), placeholder_ledger_info())
+        .unwrap();
+
+    // Rounds start over in the new epoch.
+    let epoch_change_proof = vec![epoch_ending_ledger_info(
+        vec![&ValidatorSigner::random(None)],
+        0,
+    )];
+    safety_rules.start_new_epoch(&epoch_change_proof).unwrap();
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 0);
+    safety_rules
+        .construct_and_sign_vote(&a1, HashValue::zero(), placeholder_ledger_info())
+        .unwrap();
+
+    // But only once.
+    safety_rules.start_new_epoch(&epoch_change_proof).unwrap();
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 1);
+}
+
+#[test]
+fn test_state_persisted_before_signing() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let file_path = dir.path().join("safety_rules.json");
+
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(&genesis, 1);
+
+    let signer = Arc::new(ValidatorSigner::random(None));
+    let storage = OnDiskStorage::new_with_default(
+        file_path.clone(),
+        ConsensusState::default(),
+        Arc::clone(&signer),
+    );
+    let mut safety_rules =
+        make_safety_rules_with_signer(Arc::clone(&signer), Box::new(storage.unwrap()));
+    safety_rules
+        .construct_and_sign_vote(&a1, HashValue::zero(), placeholder_ledger_info())
+        .unwrap();
+
+    // A restart doesn't allow voting again at the same round.
+    let storage =
+        OnDiskStorage::new_with_default(file_path, ConsensusState::default(), Arc::clone(&signer));
+    let mut safety_rules = make_safety_rules_with_signer(signer, Box::new(storage.unwrap()));
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 1);
+    assert!(safety_rules
+        .construct_and_sign_vote(&a1, HashValue::zero(), placeholder_ledger_info())
+        .is_err());
+}
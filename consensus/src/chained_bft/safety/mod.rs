@@ -1,4 +1,11 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod local_safety_rules;
+pub(crate) mod persistent_safety_storage;
+mod proto;
+pub(crate) mod remote_safety_rules;
 pub(crate) mod safety_rules;
+pub(crate) mod safety_rules_node;
+pub(crate) mod safety_rules_server;
+pub(crate) mod t_safety_rules;
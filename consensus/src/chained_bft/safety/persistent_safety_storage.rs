@@ -0,0 +1,170 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::Payload, persistent_storage::PersistentStorage, safety::safety_rules::ConsensusState,
+};
+use crypto::hash::CryptoHash;
+use failure::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use types::crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier};
+
+#[cfg(test)]
+#[path = "persistent_safety_storage_test.rs"]
+mod persistent_safety_storage_test;
+
+/// The storage of the consensus state of the safety rules. Signatures are released as soon as the
+/// new state is set, so it must be durable by the time `set_consensus_state` returns.
+pub trait PersistentSafetyStorage: Send + Sync {
+    /// Returns the last consensus state that was set.
+    fn consensus_state(&self) -> ConsensusState;
+
+    /// Persists the given consensus state.
+    fn set_consensus_state(&mut self, consensus_state: ConsensusState) -> Result<()>;
+}
+
+/// Keeps the consensus state in memory only, which is lost on restart: this is meant for testing.
+#[derive(Default)]
+pub struct InMemoryStorage {
+    consensus_state: ConsensusState,
+}
+
+impl InMemoryStorage {
+    pub fn new(consensus_state: ConsensusState) -> Self {
+        Self { consensus_state }
+    }
+}
+
+impl PersistentSafetyStorage for InMemoryStorage {
+    fn consensus_state(&self) -> ConsensusState {
+        self.consensus_state.clone()
+    }
+
+    fn set_consensus_state(&mut self, consensus_state: ConsensusState) -> Result<()> {
+        self.consensus_state = consensus_state;
+        Ok(())
+    }
+}
+
+/// The content of the file of `OnDiskStorage`: the consensus state along with the signature of
+/// the validator over it, so that a state that the safety rules didn't write, e.g. one edited to
+/// roll back the rounds, is detected.
+#[derive(Serialize, Deserialize)]
+struct SignedConsensusState {
+    consensus_state: ConsensusState,
+    signature: Signature,
+}
+
+/// Keeps the consensus state in its own file, which is replaced atomically on every update.
+pub struct OnDiskStorage {
+    file_path: PathBuf,
+    validator_signer: Arc<ValidatorSigner>,
+    consensus_state: ConsensusState,
+}
+
+impl OnDiskStorage {
+    /// Loads the consensus state from the given file, which is created with the given initial
+    /// state if it doesn't exist yet. The state is signed by the given validator, and it's an
+    /// error if the one in the file isn't.
+    pub fn new_with_default(
+        file_path: PathBuf,
+        initial_state: ConsensusState,
+        validator_signer: Arc<ValidatorSigner>,
+    ) -> Result<Self> {
+        if file_path.exists() {
+            let signed_state: SignedConsensusState =
+                serde_json::from_slice(&fs::read(&file_path)?)?;
+            let author = validator_signer.author();
+            let validator = ValidatorVerifier::new_single(author, validator_signer.public_key());
+            signed_state
+                .signature
+                .verify(&validator, author, signed_state.consensus_state.hash())
+                .map_err(|e| {
+                    format_err!(
+                        "Consensus state in {:?} isn't signed by {}: {}",
+                        file_path,
+                        author,
+                        e
+                    )
+                })?;
+            return Ok(Self {
+                file_path,
+                validator_signer,
+                consensus_state: signed_state.consensus_state,
+            });
+        }
+        let mut storage = Self {
+            file_path,
+            validator_signer,
+            consensus_state: ConsensusState::default(),
+        };
+        storage.set_consensus_state(initial_state)?;
+        Ok(storage)
+    }
+}
+
+impl PersistentSafetyStorage for OnDiskStorage {
+    fn consensus_state(&self) -> ConsensusState {
+        self.consensus_state.clone()
+    }
+
+    fn set_consensus_state(&mut self, consensus_state: ConsensusState) -> Result<()> {
+        let signature = self
+            .validator_signer
+            .sign_message(consensus_state.hash())?
+            .into();
+        let signed_state = SignedConsensusState {
+            consensus_state,
+            signature,
+        };
+        // Write the new state aside first so that a crash never leaves a partial file behind.
+        let tmp_path = self.file_path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&serde_json::to_vec(&signed_state)?)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.file_path)?;
+        // The rename itself is only durable once the directory is synced.
+        let dir = match self.file_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        File::open(dir)?.sync_all()?;
+        self.consensus_state = signed_state.consensus_state;
+        Ok(())
+    }
+}
+
+/// Keeps the consensus state in the consensus DB along with the blocks, which is only possible
+/// for the safety rules running in the consensus process.
+pub struct ConsensusDbStorage<T> {
+    storage: Arc<dyn PersistentStorage<T>>,
+    consensus_state: ConsensusState,
+}
+
+impl<T> ConsensusDbStorage<T> {
+    /// The consensus state is the one recovered from the consensus DB on startup.
+    pub fn new(storage: Arc<dyn PersistentStorage<T>>, consensus_state: ConsensusState) -> Self {
+        Self {
+            storage,
+            consensus_state,
+        }
+    }
+}
+
+impl<T: Payload> PersistentSafetyStorage for ConsensusDbStorage<T> {
+    fn consensus_state(&self) -> ConsensusState {
+        self.consensus_state.clone()
+    }
+
+    fn set_consensus_state(&mut self, consensus_state: ConsensusState) -> Result<()> {
+        self.storage.save_consensus_state(consensus_state.clone())?;
+        self.consensus_state = consensus_state;
+        Ok(())
+    }
+}
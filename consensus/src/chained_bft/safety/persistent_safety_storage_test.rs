@@ -0,0 +1,77 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::safety::{
+    persistent_safety_storage::{InMemoryStorage, OnDiskStorage, PersistentSafetyStorage},
+    safety_rules::ConsensusState,
+};
+use std::{fs, sync::Arc};
+use tools::tempdir::TempPath;
+use types::crypto_proxies::ValidatorSigner;
+
+#[test]
+fn test_in_memory_storage() {
+    let mut storage = InMemoryStorage::default();
+    assert_eq!(storage.consensus_state(), ConsensusState::default());
+    storage
+        .set_consensus_state(ConsensusState::new(2, 1))
+        .unwrap();
+    assert_eq!(storage.consensus_state(), ConsensusState::new(2, 1));
+}
+
+#[test]
+fn test_on_disk_storage() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let file_path = dir.path().join("safety_rules.json");
+    let signer = Arc::new(ValidatorSigner::random(None));
+
+    let mut storage = OnDiskStorage::new_with_default(
+        file_path.clone(),
+        ConsensusState::new(1, 0),
+        Arc::clone(&signer),
+    )
+    .unwrap();
+    assert_eq!(storage.consensus_state(), ConsensusState::new(1, 0));
+    storage
+        .set_consensus_state(ConsensusState::new(5, 3))
+        .unwrap();
+    assert_eq!(storage.consensus_state(), ConsensusState::new(5, 3));
+
+    // The initial state is only used if there is no file yet.
+    let storage =
+        OnDiskStorage::new_with_default(file_path, ConsensusState::default(), signer).unwrap();
+    assert_eq!(storage.consensus_state(), ConsensusState::new(5, 3));
+}
+
+#[test]
+fn test_on_disk_storage_rejects_unsigned_state() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let file_path = dir.path().join("safety_rules.json");
+    let signer = Arc::new(ValidatorSigner::random(None));
+    OnDiskStorage::new_with_default(
+        file_path.clone(),
+        ConsensusState::new(5, 3),
+        Arc::clone(&signer),
+    )
+    .unwrap();
+
+    // Another validator's key doesn't verify the state.
+    assert!(OnDiskStorage::new_with_default(
+        file_path.clone(),
+        ConsensusState::default(),
+        Arc::new(ValidatorSigner::random(None)),
+    )
+    .is_err());
+
+    // Neither does a state rolled back in the file.
+    let content = String::from_utf8(fs::read(&file_path).unwrap()).unwrap();
+    let rolled_back = content.replace("\"last_vote_round\":5", "\"last_vote_round\":1");
+    assert_ne!(content, rolled_back);
+    fs::write(&file_path, rolled_back).unwrap();
+    assert!(
+        OnDiskStorage::new_with_default(file_path.clone(), ConsensusState::default(), signer)
+            .is_err()
+    );
+}
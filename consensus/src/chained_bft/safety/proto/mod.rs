@@ -0,0 +1,8 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(bare_trait_objects)]
+#![allow(missing_docs)]
+
+pub mod safety_rules;
+pub mod safety_rules_grpc;
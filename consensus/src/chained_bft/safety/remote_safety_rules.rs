@@ -0,0 +1,147 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Payload, Round},
+    consensus_types::{
+        block::{Block, BlockData},
+        commit_vote::CommitVote,
+        quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
+        timeout_msg::TimeoutMsg,
+        vote_msg::VoteMsg,
+    },
+    safety::{
+        proto::{
+            safety_rules::{
+                ConstructAndSignVoteRequest, GetConsensusStateRequest, SignCommitVoteRequest,
+                SignProposalRequest, SignTimeoutRequest, StartNewEpochRequest, UpdateRequest,
+            },
+            safety_rules_grpc::SafetyRulesClient,
+        },
+        safety_rules::ConsensusState,
+        t_safety_rules::TSafetyRules,
+    },
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use grpcio::{CallOption, ChannelBuilder, EnvBuilder};
+use proto_conv::{FromProtoBytes, IntoProtoBytes};
+use rmp_serde::{from_slice, to_vec_named};
+use std::{sync::Arc, time::Duration};
+use types::{crypto_proxies::LedgerInfoWithSignatures, ledger_info::LedgerInfo};
+
+#[cfg(test)]
+#[path = "remote_safety_rules_test.rs"]
+mod remote_safety_rules_test;
+
+/// The safety rules running in their own process (see `SafetyRulesNode`): the signing key never
+/// leaves that process, which only releases the proposals, votes and timeouts that are safe to
+/// sign.
+pub struct RemoteSafetyRules {
+    client: SafetyRulesClient,
+    request_timeout: Duration,
+}
+
+impl RemoteSafetyRules {
+    /// Every request fails if the safety rules process doesn't answer within the given timeout,
+    /// rather than blocking the event processor.
+    pub fn new(client: SafetyRulesClient, request_timeout: Duration) -> Self {
+        Self {
+            client,
+            request_timeout,
+        }
+    }
+
+    /// Connects to the safety rules process listening on the given address and port.
+    pub fn connect(address: &str, port: u16, request_timeout: Duration) -> Self {
+        let env = Arc::new(EnvBuilder::new().name_prefix("grpc-safety-rules-").build());
+        let channel = ChannelBuilder::new(env).connect(&format!("{}:{}", address, port));
+        Self::new(SafetyRulesClient::new(channel), request_timeout)
+    }
+
+    fn call_option(&self) -> CallOption {
+        CallOption::default()
+            .wait_for_ready(true)
+            .timeout(self.request_timeout)
+    }
+}
+
+impl<T: Payload> TSafetyRules<T> for RemoteSafetyRules {
+    fn consensus_state(&self) -> Result<ConsensusState> {
+        let response = self
+            .client
+            .get_consensus_state_opt(&GetConsensusStateRequest::new(), self.call_option())?;
+        Ok(from_slice(response.get_consensus_state())?)
+    }
+
+    fn update(&mut self, qc: &QuorumCert) -> Result<()> {
+        let mut request = UpdateRequest::new();
+        request.set_quorum_cert(qc.clone().into_proto_bytes()?);
+        self.client.update_opt(&request, self.call_option())?;
+        Ok(())
+    }
+
+    fn start_new_epoch(&mut self, epoch_change_proof: &[LedgerInfoWithSignatures]) -> Result<()> {
+        let mut request = StartNewEpochRequest::new();
+        request.set_epoch_change_proof(::protobuf::RepeatedField::from_vec(
+            epoch_change_proof
+                .iter()
+                .map(|ledger_info| ledger_info.clone().into_proto_bytes())
+                .collect::<Result<_>>()?,
+        ));
+        self.client
+            .start_new_epoch_opt(&request, self.call_option())?;
+        Ok(())
+    }
+
+    fn sign_proposal(&mut self, block_data: BlockData<T>) -> Result<Block<T>> {
+        let mut request = SignProposalRequest::new();
+        request.set_block_data(to_vec_named(&block_data)?);
+        let response = self
+            .client
+            .sign_proposal_opt(&request, self.call_option())?;
+        Block::from_proto_bytes(response.get_block())
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        proposed_block: &Block<T>,
+        executed_state_id: HashValue,
+        ledger_info_placeholder: LedgerInfo,
+    ) -> Result<VoteMsg> {
+        let mut request = ConstructAndSignVoteRequest::new();
+        request.set_proposed_block(proposed_block.clone().into_proto_bytes()?);
+        request.set_executed_state_id(executed_state_id.to_vec());
+        request.set_ledger_info_placeholder(ledger_info_placeholder.into_proto_bytes()?);
+        let response = self
+            .client
+            .construct_and_sign_vote_opt(&request, self.call_option())?;
+        VoteMsg::from_proto_bytes(response.get_vote())
+    }
+
+    fn sign_timeout(
+        &mut self,
+        sync_info: SyncInfo,
+        round: Round,
+        vote: Option<VoteMsg>,
+    ) -> Result<TimeoutMsg> {
+        let mut request = SignTimeoutRequest::new();
+        request.set_sync_info(sync_info.into_proto_bytes()?);
+        request.set_round(round);
+        if let Some(vote) = vote {
+            request.set_vote(vote.into_proto_bytes()?);
+        }
+        let response = self.client.sign_timeout_opt(&request, self.call_option())?;
+        TimeoutMsg::from_proto_bytes(response.get_timeout_msg())
+    }
+
+    fn sign_commit_vote(&mut self, ledger_info: LedgerInfo) -> Result<CommitVote> {
+        let mut request = SignCommitVoteRequest::new();
+        request.set_ledger_info(ledger_info.into_proto_bytes()?);
+        let response = self
+            .client
+            .sign_commit_vote_opt(&request, self.call_option())?;
+        CommitVote::from_proto_bytes(response.get_commit_vote())
+    }
+}
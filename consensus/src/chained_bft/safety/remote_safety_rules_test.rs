@@ -0,0 +1,154 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::BlockReader,
+    consensus_types::sync_info::SyncInfo,
+    safety::{
+        local_safety_rules::LocalSafetyRules, persistent_safety_storage::InMemoryStorage,
+        proto::safety_rules_grpc, remote_safety_rules::RemoteSafetyRules,
+        safety_rules_server::SafetyRulesServer, t_safety_rules::TSafetyRules,
+    },
+    test_utils::{
+        build_empty_tree, epoch_ending_ledger_info, ordering_certificate_for_block,
+        placeholder_ledger_info, TestPayload, TreeInserter,
+    },
+};
+use config::utils::get_available_port;
+use crypto::HashValue;
+use grpc_helpers::{spawn_service_thread, ServerHandle};
+use std::{sync::Arc, time::Duration};
+use types::{
+    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+};
+
+/// Serves the safety rules of the given signer, which is the only validator of the first epoch.
+fn start_safety_rules(
+    signer: ValidatorSigner,
+) -> (ServerHandle, Box<dyn TSafetyRules<TestPayload>>) {
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let server = SafetyRulesServer::<TestPayload>::new(
+        LocalSafetyRules::new(Arc::new(signer), Box::new(InMemoryStorage::default())),
+        validator,
+    );
+    let port = get_available_port();
+    let handle = spawn_service_thread(
+        safety_rules_grpc::create_safety_rules(server),
+        "localhost".to_string(),
+        port,
+        "test_safety_rules",
+    );
+    let safety_rules = Box::new(RemoteSafetyRules::connect(
+        "localhost",
+        port,
+        Duration::from_secs(5),
+    ));
+    (handle, safety_rules)
+}
+
+#[test]
+fn test_remote_safety_rules() {
+    let signer = ValidatorSigner::random(None);
+    let author = signer.author();
+    let (_handle, mut safety_rules) = start_safety_rules(signer.clone());
+
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new_with_signer(signer.clone(), block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(&genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2);
+
+    let executed_state_id = HashValue::random();
+    let vote = safety_rules
+        .construct_and_sign_vote(&a1, executed_state_id, placeholder_ledger_info())
+        .unwrap();
+    assert_eq!(vote.block_id(), a1.id());
+    assert_eq!(vote.executed_state_id(), executed_state_id);
+    assert_eq!(vote.author(), author);
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 1);
+
+    // Errors of the safety rules make it through.
+    assert!(safety_rules
+        .construct_and_sign_vote(&a1, executed_state_id, placeholder_ledger_info())
+        .is_err());
+
+    safety_rules.update(a2.quorum_cert()).unwrap();
+    let proposal = safety_rules
+        .sign_proposal(block_tree.create_block(&a1, vec![3], 3, a1.timestamp_usecs() + 1))
+        .unwrap();
+    assert_eq!(proposal.author(), Some(author));
+
+    let sync_info = SyncInfo::new(
+        block_tree.highest_quorum_cert().as_ref().clone(),
+        block_tree.highest_ledger_info().as_ref().clone(),
+        None,
+    );
+    let timeout_msg = safety_rules
+        .sign_timeout(sync_info, 2, Some(vote.clone()))
+        .unwrap();
+    assert_eq!(timeout_msg.author(), author);
+    assert_eq!(timeout_msg.pacemaker_timeout().vote_msg(), Some(&vote));
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 2);
+
+    // The ledger info of a1 can be signed once a verified QC orders it.
+    let a3 = inserter.insert_block(&a2, 3);
+    let ledger_info = LedgerInfo::new(1, HashValue::zero(), HashValue::zero(), a1.id(), 0, 0, None);
+    assert!(safety_rules.sign_commit_vote(ledger_info.clone()).is_err());
+    safety_rules
+        .update(&ordering_certificate_for_block(vec![&signer], &a3))
+        .unwrap();
+    let commit_vote = safety_rules.sign_commit_vote(ledger_info).unwrap();
+    assert_eq!(commit_vote.author(), author);
+
+    safety_rules
+        .start_new_epoch(&[epoch_ending_ledger_info(vec![&signer], 0)])
+        .unwrap();
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 0);
+}
+
+#[test]
+fn test_remote_safety_rules_verify_requests() {
+    let signer = ValidatorSigner::random(None);
+    let (_handle, mut safety_rules) = start_safety_rules(signer.clone());
+
+    // The blocks and certificates of a validator from another epoch don't verify.
+    let other_signer = ValidatorSigner::random(None);
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new_with_signer(other_signer.clone(), block_tree.clone());
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(&genesis, 1);
+    let a2 = inserter.insert_block(&a1, 2);
+    assert!(safety_rules
+        .construct_and_sign_vote(&a1, HashValue::zero(), placeholder_ledger_info())
+        .is_err());
+    assert!(safety_rules.update(a2.quorum_cert()).is_err());
+    assert!(safety_rules
+        .sign_proposal(block_tree.create_block(&a1, vec![3], 3, a1.timestamp_usecs() + 1))
+        .is_err());
+    assert_eq!(safety_rules.consensus_state().unwrap().last_vote_round(), 0);
+
+    // Nor does an epoch change that the validators of the current epoch didn't sign.
+    assert!(safety_rules
+        .start_new_epoch(&[epoch_ending_ledger_info(vec![&other_signer], 0)])
+        .is_err());
+    assert_eq!(safety_rules.consensus_state().unwrap().epoch(), 0);
+
+    // The proof is only taken into account if it verifies as a whole.
+    let epoch_change_proof = vec![
+        epoch_ending_ledger_info(vec![&signer], 0),
+        epoch_ending_ledger_info(vec![&other_signer], 1),
+    ];
+    assert!(safety_rules.start_new_epoch(&epoch_change_proof).is_err());
+    assert_eq!(safety_rules.consensus_state().unwrap().epoch(), 0);
+    safety_rules
+        .start_new_epoch(&epoch_change_proof[..1])
+        .unwrap();
+    assert_eq!(safety_rules.consensus_state().unwrap().epoch(), 1);
+
+    // The ledger infos ending the epochs before the current one are verified already.
+    safety_rules
+        .start_new_epoch(&epoch_change_proof[..1])
+        .unwrap();
+    assert_eq!(safety_rules.consensus_state().unwrap().epoch(), 1);
+}
@@ -9,9 +9,16 @@ use crate::{
     counters,
 };
 
-use crypto::HashValue;
+use canonical_serialization::{CanonicalSerialize, CanonicalSerializer, SimpleSerializer};
+use crypto::{
+    hash::{ConsensusStateHasher, CryptoHash, CryptoHasher},
+    HashValue,
+};
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Formatter};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
 
 #[cfg(test)]
 #[path = "safety_rules_test.rs"]
@@ -101,6 +108,9 @@ pub enum ProposalReject {
 /// (e.g., last vote round and preferred block round).
 #[derive(Serialize, Default, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct ConsensusState {
+    // Rounds start over in every epoch, so does the rest of the state.
+    #[serde(default)]
+    epoch: u64,
     last_vote_round: Round,
 
     // A "preferred block" is the two-chain head with the highest block round.
@@ -113,6 +123,11 @@ pub struct ConsensusState {
     // parent. 2) The "max round" rule applies to the HEAD of the chain and not its TAIL (one
     // does not necessarily apply the other).
     preferred_block_round: Round,
+
+    // In the decoupled execution mode, the round of the last ordered block whose ledger info was
+    // signed in a commit vote: no ledger info is signed for it or any block before it anymore.
+    #[serde(default)]
+    last_commit_vote_round: Round,
 }
 
 impl Display for ConsensusState {
@@ -120,23 +135,56 @@ impl Display for ConsensusState {
         write!(
             f,
             "ConsensusState: [\n\
+             \tepoch = {},\n\
              \tlast_vote_round = {},\n\
-             \tpreferred_block_round = {}\n\
+             \tpreferred_block_round = {},\n\
+             \tlast_commit_vote_round = {}\n\
              ]",
-            self.last_vote_round, self.preferred_block_round
+            self.epoch,
+            self.last_vote_round,
+            self.preferred_block_round,
+            self.last_commit_vote_round
         )
     }
 }
 
+impl CanonicalSerialize for ConsensusState {
+    fn serialize(&self, serializer: &mut impl CanonicalSerializer) -> failure::Result<()> {
+        serializer
+            .encode_u64(self.epoch)?
+            .encode_u64(self.last_vote_round)?
+            .encode_u64(self.preferred_block_round)?
+            .encode_u64(self.last_commit_vote_round)?;
+        Ok(())
+    }
+}
+
+impl CryptoHash for ConsensusState {
+    type Hasher = ConsensusStateHasher;
+
+    fn hash(&self) -> HashValue {
+        let mut state = Self::Hasher::default();
+        state.write(&SimpleSerializer::<Vec<u8>>::serialize(self).expect("Should serialize."));
+        state.finish()
+    }
+}
+
 impl ConsensusState {
     #[cfg(test)]
     pub fn new(last_vote_round: Round, preferred_block_round: Round) -> Self {
         Self {
+            epoch: 0,
             last_vote_round,
             preferred_block_round,
+            last_commit_vote_round: 0,
         }
     }
 
+    /// Returns the epoch the state belongs to
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
     /// Returns the last round that was voted on
     pub fn last_vote_round(&self) -> Round {
         self.last_vote_round
@@ -147,6 +195,11 @@ impl ConsensusState {
         self.preferred_block_round
     }
 
+    /// Returns the round of the last block whose ledger info was signed in a commit vote
+    pub fn last_commit_vote_round(&self) -> Round {
+        self.last_commit_vote_round
+    }
+
     /// Set the last vote round that ensures safety.  If the last vote round increases, return
     /// the new consensus state based with the updated last vote round.  Otherwise, return None.
     fn set_last_vote_round(&mut self, last_vote_round: Round) -> Option<ConsensusState> {
@@ -174,12 +227,19 @@ impl ConsensusState {
 pub struct SafetyRules {
     // Keeps the state.
     state: ConsensusState,
+    // In the decoupled execution mode, the blocks ordered by the quorum certs learnt about that
+    // are yet to be commit voted for, along with their rounds. They're only kept in memory: after
+    // a restart, the next quorum cert orders the blocks pending execution again.
+    ordered_blocks: HashMap<HashValue, Round>,
 }
 
 impl SafetyRules {
     /// Constructs a new instance of SafetyRules given the BlockTree and ConsensusState.
     pub fn new(state: ConsensusState) -> Self {
-        Self { state }
+        Self {
+            state,
+            ordered_blocks: HashMap::new(),
+        }
     }
 
     /// Learn about a new quorum certificate. Several things can happen as a result of that:
@@ -195,12 +255,42 @@ impl SafetyRules {
             self.state
                 .set_preferred_block_round(qc.parent_block_round());
         }
+        // Ordering rule: the block a QC commits, which is the grandparent of the certified block
+        // per the commit rule, is only ordered in the decoupled execution mode.
+        if let Some(ordered_block_id) = qc.committed_block_id() {
+            if ordered_block_id == qc.grandparent_block_id()
+                && qc.grandparent_block_round() > self.state.last_commit_vote_round()
+            {
+                self.ordered_blocks
+                    .insert(ordered_block_id, qc.grandparent_block_round());
+            }
+        }
+    }
+
+    /// Returns the round of the given block if a QC learnt about ordered it and its ledger info
+    /// can still be signed in a commit vote, i.e. it's after the last commit-voted block.
+    pub fn ordered_block_round(&self, block_id: HashValue) -> Option<Round> {
+        self.ordered_blocks
+            .get(&block_id)
+            .cloned()
+            .filter(|round| *round > self.state.last_commit_vote_round())
+    }
+
+    /// Return the new state if the last commit vote round was increased, otherwise ignore. The
+    /// ordered blocks up to that round are forgotten.
+    pub fn increase_last_commit_vote_round(&mut self, round: Round) -> Option<ConsensusState> {
+        if round <= self.state.last_commit_vote_round {
+            return None;
+        }
+        self.state.last_commit_vote_round = round;
+        self.ordered_blocks
+            .retain(|_, ordered_round| *ordered_round > round);
+        Some(self.state.clone())
     }
 
     /// Check if a one-chain at round r+2 causes a commit at round r and return the committed
     /// block id at round r if possible
-    fn commit_rule_for_certified_block(
-        &self,
+    pub fn commit_rule_for_certified_block(
         block_parent_qc: &QuorumCert,
         block_round: u64,
     ) -> Option<HashValue> {
@@ -225,6 +315,22 @@ impl SafetyRules {
         self.state.set_last_vote_round(round)
     }
 
+    /// Rounds start over in a new epoch: if the given epoch is newer than the current one, the
+    /// state is reset and the new state is returned.  Otherwise, return None.
+    pub fn start_new_epoch(&mut self, epoch: u64) -> Option<ConsensusState> {
+        if epoch <= self.state.epoch {
+            return None;
+        }
+        self.state = ConsensusState {
+            epoch,
+            ..ConsensusState::default()
+        };
+        self.ordered_blocks.clear();
+        counters::LAST_VOTE_ROUND.set(0);
+        counters::PREFERRED_BLOCK_ROUND.set(0);
+        Some(self.state.clone())
+    }
+
     /// Clones the up-to-date state of consensus (for monitoring / debugging purposes)
     pub fn consensus_state(&self) -> ConsensusState {
         self.state.clone()
@@ -255,7 +361,7 @@ impl SafetyRules {
             // If the vote for the given proposal is gathered into QC, then this QC might eventually
             // commit another block following the rules defined in
             // `commit_rule_for_certified_block()` function.
-            let potential_commit_id = self.commit_rule_for_certified_block(
+            let potential_commit_id = Self::commit_rule_for_certified_block(
                 proposed_block.quorum_cert(),
                 proposed_block.round(),
             );
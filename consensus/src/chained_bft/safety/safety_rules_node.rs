@@ -0,0 +1,134 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A safety rules node upon run creates its own process.
+//! It listens on the address and port read from node_config.consensus.safety_rules and holds the
+//! consensus signing key of the node config, which the consensus process then doesn't load: the
+//! proposals, votes, timeouts and commit votes are all signed here, once the certificates they
+//! build on are verified against the validators of the current epoch.
+//! The proto/safety_rules.proto file shows the requests that the service accepts and the
+//! responses that it gives back.
+
+use crate::chained_bft::{
+    chained_bft_consensus_provider::ChainedBftProvider,
+    safety::{
+        local_safety_rules::LocalSafetyRules,
+        persistent_safety_storage::{InMemoryStorage, OnDiskStorage, PersistentSafetyStorage},
+        proto::safety_rules_grpc,
+        safety_rules::ConsensusState,
+        safety_rules_server::SafetyRulesServer,
+    },
+};
+use config::config::{
+    ConsensusProposerType, ConsensusSignatureScheme, NodeConfig, SafetyRulesBackend,
+};
+use debug_interface::{node_debug_service::NodeDebugService, proto::node_debug_interface_grpc};
+use failure::prelude::*;
+use grpc_helpers::spawn_service_thread;
+use logger::prelude::*;
+use std::{convert::TryFrom, sync::Arc, thread};
+use types::{
+    account_address::AccountAddress, crypto_proxies::ValidatorSigner,
+    transaction::SignedTransaction,
+};
+
+/// Safety rules node is run in a separate process and handles the consensus signing key.
+pub struct SafetyRulesNode {
+    node_config: NodeConfig,
+}
+
+impl SafetyRulesNode {
+    /// Instantiates the service with a config file.
+    pub fn new(node_config: NodeConfig) -> Self {
+        SafetyRulesNode { node_config }
+    }
+
+    /// Starts the safety rules service.
+    pub fn run(&mut self) -> Result<()> {
+        info!("Starting safety rules node");
+
+        let peer_id = self
+            .node_config
+            .get_validator_network_config()
+            .ok_or_else(|| format_err!("Safety rules only run for validators"))?
+            .peer_id
+            .clone();
+        let author = AccountAddress::try_from(peer_id)?;
+        let private_key = self
+            .node_config
+            .consensus
+            .consensus_keypair
+            .take_consensus_private()
+            .ok_or_else(|| format_err!("Consensus private key absent or already read"))?;
+        let mut signer = ValidatorSigner::new(author, private_key);
+        // Only the VRF proposer needs the proposals to prove their VRF outputs.
+        if self.node_config.consensus.get_proposer_type() == ConsensusProposerType::VrfProposer {
+            let vrf_private_key = self
+                .node_config
+                .consensus
+                .consensus_keypair
+                .take_vrf_private()
+                .ok_or_else(|| format_err!("VRF private key absent or already read"))?;
+            signer = signer.with_vrf_private_key(vrf_private_key);
+        }
+        // Only the validators aggregating their signatures sign with a BLS12-381 key.
+        if self.node_config.consensus.get_signature_scheme() == ConsensusSignatureScheme::Bls12381 {
+            let bls_private_key = self
+                .node_config
+                .consensus
+                .consensus_keypair
+                .take_bls_private()
+                .ok_or_else(|| format_err!("BLS12-381 private key absent or already read"))?;
+            signer = signer.with_bls_private_key(bls_private_key);
+        }
+        // The validators of the first epoch, as the consensus process starts from: the later ones
+        // are only trusted once the ledger infos ending the epochs before are verified.
+        let validator = ChainedBftProvider::first_epoch_validator(&self.node_config.consensus)?;
+
+        let signer = Arc::new(signer);
+        let safety_rules_config = self.node_config.consensus.safety_rules();
+        let storage: Box<dyn PersistentSafetyStorage> = match safety_rules_config.get_backend() {
+            SafetyRulesBackend::OnDisk => Box::new(OnDiskStorage::new_with_default(
+                safety_rules_config.path.clone(),
+                ConsensusState::default(),
+                Arc::clone(&signer),
+            )?),
+            SafetyRulesBackend::InMemory => Box::new(InMemoryStorage::default()),
+            SafetyRulesBackend::ConsensusDb => {
+                bail!("The consensus DB is only available to the consensus process")
+            }
+        };
+        let handle = SafetyRulesServer::<Vec<SignedTransaction>>::new(
+            LocalSafetyRules::new(signer, storage),
+            validator,
+        );
+        let service = safety_rules_grpc::create_safety_rules(handle);
+        let _sr_service_handle = spawn_service_thread(
+            service,
+            safety_rules_config.address.clone(),
+            safety_rules_config.safety_rules_port,
+            "safety_rules",
+        );
+
+        // Start Debug interface
+        let debug_service =
+            node_debug_interface_grpc::create_node_debug_interface(NodeDebugService::new());
+        let _debug_handle = spawn_service_thread(
+            debug_service,
+            safety_rules_config.address.clone(),
+            self.node_config
+                .debug_interface
+                .safety_rules_node_debug_port,
+            "debug_safety_rules",
+        );
+
+        info!(
+            "Started SafetyRules node on port {}",
+            safety_rules_config.safety_rules_port
+        );
+
+        loop {
+            thread::park();
+        }
+    }
+}
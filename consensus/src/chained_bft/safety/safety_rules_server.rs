@@ -0,0 +1,302 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::Payload,
+    consensus_types::{
+        block::{Block, BlockData},
+        quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
+        vote_msg::VoteMsg,
+    },
+    safety::{
+        local_safety_rules::LocalSafetyRules,
+        proto::{
+            safety_rules::{
+                ConstructAndSignVoteRequest, ConstructAndSignVoteResponse,
+                GetConsensusStateRequest, GetConsensusStateResponse, SignCommitVoteRequest,
+                SignCommitVoteResponse, SignProposalRequest, SignProposalResponse,
+                SignTimeoutRequest, SignTimeoutResponse, StartNewEpochRequest,
+                StartNewEpochResponse, UpdateRequest, UpdateResponse,
+            },
+            safety_rules_grpc,
+        },
+        t_safety_rules::TSafetyRules,
+    },
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use grpc_helpers::provide_grpc_response;
+use proto_conv::{FromProtoBytes, IntoProtoBytes};
+use rmp_serde::{from_slice, to_vec_named};
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+use types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier},
+    ledger_info::LedgerInfo,
+};
+
+/// The safety rules along with the validators of the epoch they're in, which the requests are
+/// verified against: the consensus process isn't trusted to have verified them.
+struct VerifiedSafetyRules {
+    safety_rules: LocalSafetyRules,
+    validator: ValidatorVerifier,
+    epoch: u64,
+    // The ledger info ending the previous epoch, from which the genesis of the current one
+    // derives.
+    epoch_ending_ledger_info: Option<LedgerInfo>,
+}
+
+impl VerifiedSafetyRules {
+    fn verify_quorum_cert(&self, qc: &QuorumCert) -> Result<()> {
+        qc.verify(&self.validator, self.epoch_ending_ledger_info.as_ref())
+            .map_err(|e| format_err!("Invalid quorum certificate {}: {:?}", qc, e))
+    }
+
+    fn verify_block<T: Payload>(&self, block: &Block<T>) -> Result<()> {
+        block
+            .verify_well_formed()
+            .and_then(|_| {
+                block.validate_signatures(&self.validator, self.epoch_ending_ledger_info.as_ref())
+            })
+            .map_err(|e| format_err!("Invalid block {}: {:?}", block, e))
+    }
+
+    /// Moves to the epochs the given ledger infos end, each of which must be signed by the
+    /// validators of the epoch it ends, unless one of them doesn't verify. The ledger infos ending
+    /// epochs before the current one were already verified and are skipped.
+    fn verify_epoch_change_proof(
+        &mut self,
+        epoch_change_proof: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        let mut validator = None;
+        let mut epoch = self.epoch;
+        let mut epoch_ending_ledger_info = None;
+        for ledger_info in epoch_change_proof {
+            let ledger_info_epoch = ledger_info.ledger_info().epoch_num();
+            if ledger_info_epoch < epoch {
+                continue;
+            }
+            ensure!(
+                ledger_info_epoch == epoch,
+                "Missing the ledger infos ending the epochs {} to {}",
+                epoch,
+                ledger_info_epoch - 1
+            );
+            // A nominal ledger info verifies without any signature.
+            ensure!(
+                !ledger_info.ledger_info().is_zero(),
+                "Nominal ledger info can't end epoch {}",
+                epoch
+            );
+            ledger_info
+                .verify(validator.as_ref().unwrap_or(&self.validator))
+                .map_err(|e| format_err!("Invalid ledger info ending epoch {}: {}", epoch, e))?;
+            let next_validator_set = ledger_info
+                .ledger_info()
+                .next_validator_set()
+                .ok_or_else(|| format_err!("Ledger info doesn't end epoch {}", epoch))?;
+            validator = Some(
+                validator
+                    .as_ref()
+                    .unwrap_or(&self.validator)
+                    .for_next_epoch(next_validator_set)?,
+            );
+            epoch += 1;
+            epoch_ending_ledger_info = Some(ledger_info.ledger_info().clone());
+        }
+        if let Some(validator) = validator {
+            self.validator = validator;
+            self.epoch = epoch;
+            self.epoch_ending_ledger_info = epoch_ending_ledger_info;
+        }
+        Ok(())
+    }
+}
+
+/// Serves the safety rules of the current process to the consensus one (see
+/// `RemoteSafetyRules`), all the requests being handled one at a time.
+#[derive(Clone)]
+pub struct SafetyRulesServer<T> {
+    safety_rules: Arc<Mutex<VerifiedSafetyRules>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Payload> SafetyRulesServer<T> {
+    /// The given validators are the ones of the first epoch: the server only moves to the next
+    /// epochs on the proofs the consensus process sends on start and on every epoch change.
+    pub fn new(safety_rules: LocalSafetyRules, validator: ValidatorVerifier) -> Self {
+        Self {
+            safety_rules: Arc::new(Mutex::new(VerifiedSafetyRules {
+                safety_rules,
+                validator,
+                epoch: 0,
+                epoch_ending_ledger_info: None,
+            })),
+            phantom: PhantomData,
+        }
+    }
+
+    fn get_consensus_state_inner(&self) -> Result<GetConsensusStateResponse> {
+        let inner = self.safety_rules.lock().unwrap();
+        let consensus_state = TSafetyRules::<T>::consensus_state(&inner.safety_rules)?;
+        let mut response = GetConsensusStateResponse::new();
+        response.set_consensus_state(to_vec_named(&consensus_state)?);
+        Ok(response)
+    }
+
+    fn update_inner(&self, req: UpdateRequest) -> Result<UpdateResponse> {
+        let qc = QuorumCert::from_proto_bytes(req.get_quorum_cert())?;
+        let mut inner = self.safety_rules.lock().unwrap();
+        inner.verify_quorum_cert(&qc)?;
+        TSafetyRules::<T>::update(&mut inner.safety_rules, &qc)?;
+        Ok(UpdateResponse::new())
+    }
+
+    fn start_new_epoch_inner(&self, req: StartNewEpochRequest) -> Result<StartNewEpochResponse> {
+        let epoch_change_proof = req
+            .get_epoch_change_proof()
+            .iter()
+            .map(|bytes| LedgerInfoWithSignatures::from_proto_bytes(bytes))
+            .collect::<Result<Vec<_>>>()?;
+        let mut inner = self.safety_rules.lock().unwrap();
+        inner.verify_epoch_change_proof(&epoch_change_proof)?;
+        TSafetyRules::<T>::start_new_epoch(&mut inner.safety_rules, &epoch_change_proof)?;
+        Ok(StartNewEpochResponse::new())
+    }
+
+    fn sign_proposal_inner(&self, req: SignProposalRequest) -> Result<SignProposalResponse> {
+        let block_data: BlockData<T> = from_slice(req.get_block_data())?;
+        let mut inner = self.safety_rules.lock().unwrap();
+        inner.verify_quorum_cert(block_data.quorum_cert())?;
+        let block = inner.safety_rules.sign_proposal(block_data)?;
+        let mut response = SignProposalResponse::new();
+        response.set_block(block.into_proto_bytes()?);
+        Ok(response)
+    }
+
+    fn construct_and_sign_vote_inner(
+        &self,
+        req: ConstructAndSignVoteRequest,
+    ) -> Result<ConstructAndSignVoteResponse> {
+        let proposed_block = Block::<T>::from_proto_bytes(req.get_proposed_block())?;
+        let executed_state_id = HashValue::from_slice(req.get_executed_state_id())?;
+        let ledger_info_placeholder =
+            LedgerInfo::from_proto_bytes(req.get_ledger_info_placeholder())?;
+        let mut inner = self.safety_rules.lock().unwrap();
+        inner.verify_block(&proposed_block)?;
+        let vote = inner.safety_rules.construct_and_sign_vote(
+            &proposed_block,
+            executed_state_id,
+            ledger_info_placeholder,
+        )?;
+        let mut response = ConstructAndSignVoteResponse::new();
+        response.set_vote(vote.into_proto_bytes()?);
+        Ok(response)
+    }
+
+    fn sign_timeout_inner(&self, req: SignTimeoutRequest) -> Result<SignTimeoutResponse> {
+        let sync_info = SyncInfo::from_proto_bytes(req.get_sync_info())?;
+        let vote = if req.get_vote().is_empty() {
+            None
+        } else {
+            Some(VoteMsg::from_proto_bytes(req.get_vote())?)
+        };
+        let mut inner = self.safety_rules.lock().unwrap();
+        sync_info
+            .verify(&inner.validator, inner.epoch_ending_ledger_info.as_ref())
+            .map_err(|e| format_err!("Invalid sync info {}: {:?}", sync_info, e))?;
+        if let Some(vote) = &vote {
+            vote.verify(&inner.validator)
+                .map_err(|e| format_err!("Invalid vote {}: {:?}", vote, e))?;
+        }
+        let timeout_msg = TSafetyRules::<T>::sign_timeout(
+            &mut inner.safety_rules,
+            sync_info,
+            req.get_round(),
+            vote,
+        )?;
+        let mut response = SignTimeoutResponse::new();
+        response.set_timeout_msg(timeout_msg.into_proto_bytes()?);
+        Ok(response)
+    }
+
+    fn sign_commit_vote_inner(&self, req: SignCommitVoteRequest) -> Result<SignCommitVoteResponse> {
+        let ledger_info = LedgerInfo::from_proto_bytes(req.get_ledger_info())?;
+        let mut inner = self.safety_rules.lock().unwrap();
+        // The ledger info is only signed for a block ordered by one of the quorum certs verified
+        // in `update_inner`.
+        let commit_vote =
+            TSafetyRules::<T>::sign_commit_vote(&mut inner.safety_rules, ledger_info)?;
+        let mut response = SignCommitVoteResponse::new();
+        response.set_commit_vote(commit_vote.into_proto_bytes()?);
+        Ok(response)
+    }
+}
+
+impl<T: Payload> safety_rules_grpc::SafetyRules for SafetyRulesServer<T> {
+    fn get_consensus_state(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        _req: GetConsensusStateRequest,
+        sink: ::grpcio::UnarySink<GetConsensusStateResponse>,
+    ) {
+        provide_grpc_response(self.get_consensus_state_inner(), ctx, sink);
+    }
+
+    fn update(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: UpdateRequest,
+        sink: ::grpcio::UnarySink<UpdateResponse>,
+    ) {
+        provide_grpc_response(self.update_inner(req), ctx, sink);
+    }
+
+    fn start_new_epoch(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: StartNewEpochRequest,
+        sink: ::grpcio::UnarySink<StartNewEpochResponse>,
+    ) {
+        provide_grpc_response(self.start_new_epoch_inner(req), ctx, sink);
+    }
+
+    fn sign_proposal(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: SignProposalRequest,
+        sink: ::grpcio::UnarySink<SignProposalResponse>,
+    ) {
+        provide_grpc_response(self.sign_proposal_inner(req), ctx, sink);
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: ConstructAndSignVoteRequest,
+        sink: ::grpcio::UnarySink<ConstructAndSignVoteResponse>,
+    ) {
+        provide_grpc_response(self.construct_and_sign_vote_inner(req), ctx, sink);
+    }
+
+    fn sign_timeout(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: SignTimeoutRequest,
+        sink: ::grpcio::UnarySink<SignTimeoutResponse>,
+    ) {
+        provide_grpc_response(self.sign_timeout_inner(req), ctx, sink);
+    }
+
+    fn sign_commit_vote(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: SignCommitVoteRequest,
+        sink: ::grpcio::UnarySink<SignCommitVoteResponse>,
+    ) {
+        provide_grpc_response(self.sign_commit_vote_inner(req), ctx, sink);
+    }
+}
@@ -9,7 +9,7 @@ use crate::chained_bft::{
         quorum_cert::QuorumCert,
     },
     safety::safety_rules::{ConsensusState, ProposalReject, SafetyRules},
-    test_utils::{build_empty_tree, build_empty_tree_with_custom_signing, TreeInserter},
+    test_utils::{build_empty_tree, TreeInserter},
 };
 use cached::{cached_key, SizedCache};
 use crypto::HashValue;
@@ -74,8 +74,8 @@ proptest! {
             qc_signers.push(signer);
         }
 
-        let block_tree = build_empty_tree_with_custom_signing(first_signer.clone());
-        let mut inserter = TreeInserter::new(block_tree.clone());
+        let block_tree = build_empty_tree();
+        let mut inserter = TreeInserter::new_with_signer(first_signer.clone(), block_tree.clone());
         let mut safety_rules = SafetyRules::new(ConsensusState::default());
 
         // This commit_candidate tracks the commit that would get
@@ -364,7 +364,6 @@ fn test_voting_potential_commit_id() {
 fn test_commit_rule_consecutive_rounds() {
     let block_tree = build_empty_tree();
     let mut inserter = TreeInserter::new(block_tree.clone());
-    let safety_rules = SafetyRules::new(ConsensusState::default());
 
     // build a tree of the following form:
     //             ___________
@@ -386,27 +385,27 @@ fn test_commit_rule_consecutive_rounds() {
     let a4 = inserter.insert_block(a3.as_ref(), 6);
 
     assert_eq!(
-        safety_rules.commit_rule_for_certified_block(a1.quorum_cert(), a1.round()),
+        SafetyRules::commit_rule_for_certified_block(a1.quorum_cert(), a1.round()),
         None
     );
     assert_eq!(
-        safety_rules.commit_rule_for_certified_block(b1.quorum_cert(), b1.round()),
+        SafetyRules::commit_rule_for_certified_block(b1.quorum_cert(), b1.round()),
         None
     );
     assert_eq!(
-        safety_rules.commit_rule_for_certified_block(b2.quorum_cert(), b2.round()),
+        SafetyRules::commit_rule_for_certified_block(b2.quorum_cert(), b2.round()),
         None
     );
     assert_eq!(
-        safety_rules.commit_rule_for_certified_block(a2.quorum_cert(), a2.round()),
+        SafetyRules::commit_rule_for_certified_block(a2.quorum_cert(), a2.round()),
         None
     );
     assert_eq!(
-        safety_rules.commit_rule_for_certified_block(a3.quorum_cert(), a3.round()),
+        SafetyRules::commit_rule_for_certified_block(a3.quorum_cert(), a3.round()),
         None
     );
     assert_eq!(
-        safety_rules.commit_rule_for_certified_block(a4.quorum_cert(), a4.round()),
+        SafetyRules::commit_rule_for_certified_block(a4.quorum_cert(), a4.round()),
         Some(a2.id())
     );
 }
@@ -0,0 +1,67 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::Round,
+    consensus_types::{
+        block::{Block, BlockData},
+        commit_vote::CommitVote,
+        quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
+        timeout_msg::TimeoutMsg,
+        vote_msg::VoteMsg,
+    },
+    safety::safety_rules::ConsensusState,
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use types::{crypto_proxies::LedgerInfoWithSignatures, ledger_info::LedgerInfo};
+
+/// Interface to the safety rules, which hold the consensus signing key: proposals, votes and
+/// timeouts are only signed if they can't break the safety of the protocol, and only once the
+/// consensus state they imply is persisted.
+///
+/// The certificates passed in are expected to be verified by the caller already when the safety
+/// rules run in the consensus process. The safety rules running in their own process verify them
+/// again against the validators of their current epoch, which only changes on a verified proof.
+pub trait TSafetyRules<T> {
+    /// Returns the up-to-date consensus state.
+    fn consensus_state(&self) -> Result<ConsensusState>;
+
+    /// Learns about a new quorum certificate, which might update the preferred block round.
+    fn update(&mut self, qc: &QuorumCert) -> Result<()>;
+
+    /// Rounds start over in a new epoch: moves to the epoch following the one ended by the last of
+    /// the given ledger infos, each of which ends an epoch and is signed by the validators of the
+    /// epoch it ends, and resets the consensus state if that epoch is newer than the current one.
+    fn start_new_epoch(&mut self, epoch_change_proof: &[LedgerInfoWithSignatures]) -> Result<()>;
+
+    /// Signs the given proposal if it's in a round that wasn't voted on yet and extends a block
+    /// the voting rules allow to extend: the proposer never equivocates. The consensus state is
+    /// left as is, so the proposer can still vote for its own proposal.
+    fn sign_proposal(&mut self, block_data: BlockData<T>) -> Result<Block<T>>;
+
+    /// Votes for the given executed proposal if it passes the voting rules. The ledger info
+    /// placeholder is the one that gets committed if the vote gathers a QC: it must either be
+    /// for the block that the proposal potentially commits or be empty.
+    fn construct_and_sign_vote(
+        &mut self,
+        proposed_block: &Block<T>,
+        executed_state_id: HashValue,
+        ledger_info_placeholder: LedgerInfo,
+    ) -> Result<VoteMsg>;
+
+    /// Stops voting at the given round and signs a timeout for it, which carries the vote of the
+    /// round if any.
+    fn sign_timeout(
+        &mut self,
+        sync_info: SyncInfo,
+        round: Round,
+        vote: Option<VoteMsg>,
+    ) -> Result<TimeoutMsg>;
+
+    /// In the decoupled execution mode, signs the ledger info committing an ordered block along
+    /// with the state that executing it led to, if it's in the current epoch. The block must be
+    /// one that a quorum cert passed to `update` ordered, after the last one commit voted for.
+    fn sign_commit_vote(&mut self, ledger_info: LedgerInfo) -> Result<CommitVote>;
+}
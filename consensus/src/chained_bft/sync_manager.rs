@@ -5,14 +5,17 @@ use crate::{
     chained_bft::{
         block_storage::{BlockReader, BlockStore, InsertError, NeedFetchResult},
         common::{Author, Payload},
-        consensus_types::{block::Block, quorum_cert::QuorumCert, sync_info::SyncInfo},
+        consensus_types::{
+            block::Block, quorum_cert::QuorumCert, sync_info::SyncInfo, vote_data::VoteData,
+        },
+        execution_pipeline::ordering_only_state,
         network::ConsensusNetworkImpl,
         persistent_storage::PersistentStorage,
     },
     counters,
     state_replication::StateComputer,
 };
-use failure::{self, Fail};
+use failure::{self, ensure, format_err, Fail};
 use logger::prelude::*;
 use network::proto::BlockRetrievalStatus;
 use rand::{prelude::*, Rng};
@@ -23,7 +26,7 @@ use std::{
     time::{Duration, Instant},
 };
 use termion::color::*;
-use types::account_address::AccountAddress;
+use types::{account_address::AccountAddress, crypto_proxies::LedgerInfoWithSignatures};
 
 /// SyncManager is responsible for fetching dependencies and 'catching up' for given qc/ledger info
 pub struct SyncManager<T> {
@@ -31,6 +34,10 @@ pub struct SyncManager<T> {
     storage: Arc<dyn PersistentStorage<T>>,
     network: ConsensusNetworkImpl,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
+    // In the decoupled execution mode, the highest ledger info that a quorum of commit votes
+    // signed for a block the validator couldn't commit: unlike the ledger infos of the quorum
+    // certs, it certifies the executed state, so it's the one to sync to.
+    highest_commit_proof: Option<LedgerInfoWithSignatures>,
 }
 
 /// Keeps the necessary context for `SyncMgr` to bring the missing information.
@@ -73,9 +80,55 @@ where
             storage,
             network,
             state_computer,
+            highest_commit_proof: None,
         }
     }
 
+    /// Keeps the given finality proof from the commit votes to sync to if it's the highest one.
+    pub fn update_highest_commit_proof(&mut self, commit_proof: LedgerInfoWithSignatures) {
+        if self.highest_commit_proof.as_ref().map_or(true, |highest| {
+            highest.ledger_info().timestamp_usecs() < commit_proof.ledger_info().timestamp_usecs()
+        }) {
+            self.highest_commit_proof = Some(commit_proof);
+        }
+    }
+
+    /// Syncs the state to the given ledger info ending an epoch that the validator missed the end
+    /// of, e.g. while it was down: the blocks of the epoch aren't fetched since the block tree
+    /// starts over from the genesis of the next epoch anyway.
+    pub async fn sync_to_epoch_ending(
+        &self,
+        epoch_ending_ledger_info: LedgerInfoWithSignatures,
+    ) -> failure::Result<()> {
+        let ledger_info = epoch_ending_ledger_info.ledger_info();
+        let block_id = ledger_info.consensus_block_id();
+        debug!(
+            "Start state sync to block {} ending epoch {}",
+            block_id,
+            ledger_info.epoch_num()
+        );
+        // State sync only needs the ledger info out of the quorum cert.
+        let sync_target = QuorumCert::new(
+            VoteData::new(
+                block_id,
+                ledger_info.transaction_accumulator_hash(),
+                0,
+                block_id,
+                0,
+                block_id,
+                0,
+            ),
+            epoch_ending_ledger_info,
+        );
+        let pre_sync_instance = Instant::now();
+        ensure!(
+            self.state_computer.sync_to(sync_target).await?,
+            "State synchronizer failed to sync to the end of the epoch"
+        );
+        counters::STATE_SYNC_DURATION_S.observe_duration(pre_sync_instance.elapsed());
+        Ok(())
+    }
+
     /// Fetches dependencies for given sync_info.quorum_cert
     /// If gap is large, performs state sync using process_highest_ledger_info
     /// Inserts sync_info.quorum_cert into block store as the last step
@@ -182,6 +235,18 @@ where
         {
             return Ok(());
         }
+        // In the decoupled execution mode, the ledger infos of the quorum certs certify no state
+        // to sync to: sync to the highest finality proof of the commit votes instead.
+        if highest_ledger_info
+            .ledger_info()
+            .ledger_info()
+            .transaction_accumulator_hash()
+            == ordering_only_state().state_id
+        {
+            return self
+                .sync_to_commit_proof(highest_ledger_info, peer, deadline)
+                .await;
+        }
         debug!(
             "Start state sync with peer: {}, to block: {}, round: {} from {}",
             peer.short_str(),
@@ -241,6 +306,125 @@ where
         self.block_store.rebuild(root, blocks, quorum_certs).await;
         Ok(())
     }
+
+    /// In the decoupled execution mode, fast forwards to the block of the highest finality proof
+    /// of the commit votes, if the validator misses it: the chain from the block certified by the
+    /// highest ledger info down to it is fetched and becomes the new tree, rooted at it, once the
+    /// state is synced to the finality proof. If the validator knows of no such finality proof
+    /// yet, the missing blocks are fetched along with the highest quorum cert and executed
+    /// instead, as long as the peers still have them.
+    async fn sync_to_commit_proof(
+        &self,
+        highest_ledger_info: QuorumCert,
+        peer: Author,
+        deadline: Instant,
+    ) -> failure::Result<()> {
+        let commit_proof = match &self.highest_commit_proof {
+            Some(commit_proof)
+                if !self
+                    .block_store
+                    .block_exists(commit_proof.ledger_info().consensus_block_id())
+                    && commit_proof.ledger_info().timestamp_usecs()
+                        > self.block_store.root().timestamp_usecs() =>
+            {
+                commit_proof.clone()
+            }
+            _ => return Ok(()),
+        };
+        let committed_block_id = commit_proof.ledger_info().consensus_block_id();
+        debug!(
+            "Start state sync with peer: {}, to block: {} from {}",
+            peer.short_str(),
+            committed_block_id,
+            self.block_store.root()
+        );
+        let network = self.network.clone();
+        let mut retriever = BlockRetriever {
+            network,
+            deadline,
+            preferred_peer: peer,
+        };
+        // The blocks from the one certified by the highest ledger info down to the committed
+        // one, each along with the quorum cert certifying it.
+        let mut blocks = vec![];
+        let mut quorum_certs = vec![highest_ledger_info.clone()];
+        loop {
+            let retrieve_qc = quorum_certs.last().expect("should have a quorum cert");
+            let block: Block<T> = retriever
+                .retrieve_block_for_qc(retrieve_qc, 1)
+                .await?
+                .remove(0);
+            ensure!(
+                block.round() > self.block_store.root().round(),
+                "Block {} of the finality proof {} isn't an ancestor of {}",
+                committed_block_id,
+                commit_proof,
+                highest_ledger_info
+            );
+            let found = block.id() == committed_block_id;
+            blocks.push(block);
+            if found {
+                break;
+            }
+            quorum_certs.push(
+                blocks
+                    .last()
+                    .expect("should have a block")
+                    .quorum_cert()
+                    .clone(),
+            );
+        }
+        // The root is committed by a quorum cert certifying a descendant.
+        let root_ledger_info = quorum_certs
+            .iter()
+            .find(|qc| qc.committed_block_id() == Some(committed_block_id))
+            .cloned()
+            .ok_or_else(|| format_err!("No quorum cert commits block {}", committed_block_id))?;
+        self.storage
+            .save_tree(blocks.clone(), quorum_certs.clone())?;
+        // State sync only needs the ledger info out of the quorum cert.
+        let sync_target = QuorumCert::new(
+            VoteData::new(
+                root_ledger_info.certified_block_id(),
+                root_ledger_info.certified_state_id(),
+                root_ledger_info.certified_block_round(),
+                root_ledger_info.parent_block_id(),
+                root_ledger_info.parent_block_round(),
+                root_ledger_info.grandparent_block_id(),
+                root_ledger_info.grandparent_block_round(),
+            ),
+            commit_proof,
+        );
+        let pre_sync_instance = Instant::now();
+        match self.state_computer.sync_to(sync_target).await {
+            Ok(true) => (),
+            Ok(false) => panic!(
+                "state synchronizer failure, this validator will be killed as it can not \
+                 recover from this error.  After the validator is restarted, synchronization will \
+                 be retried.",
+            ),
+            Err(e) => panic!(
+                "state synchronizer failure: {:?}, this validator will be killed as it can not \
+                 recover from this error.  After the validator is restarted, synchronization will \
+                 be retried.",
+                e
+            ),
+        };
+        counters::STATE_SYNC_DURATION_S.observe_duration(pre_sync_instance.elapsed());
+        let root = (
+            blocks.pop().expect("should have the committed block"),
+            quorum_certs
+                .last()
+                .expect("should have a quorum cert")
+                .clone(),
+            root_ledger_info,
+        );
+        debug!("{}Sync to{} {}", Fg(Blue), Fg(Reset), root.0);
+        // Oldest first.
+        blocks.reverse();
+        self.block_store.rebuild(root, blocks, quorum_certs).await;
+        Ok(())
+    }
 }
 
 /// BlockRetriever is used internally to retrieve blocks
@@ -286,7 +470,8 @@ impl BlockRetriever {
         T: Payload,
     {
         let block_id = qc.certified_block_id();
-        let mut peers: Vec<&AccountAddress> = qc.ledger_info().signatures().keys().collect();
+        let signers = qc.signers();
+        let mut peers: Vec<&AccountAddress> = signers.iter().collect();
         let mut attempt = 0_u32;
         loop {
             if peers.is_empty() {
@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    chained_bft::{consensus_types::quorum_cert::QuorumCert, test_utils::TestPayload},
+    chained_bft::{
+        consensus_types::quorum_cert::QuorumCert,
+        test_utils::{mock_storage::MockSharedStorage, TestPayload},
+    },
     state_replication::StateComputer,
 };
 use crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
@@ -10,17 +13,58 @@ use executor::{ExecutedState, StateComputeResult};
 use failure::Result;
 use futures::{channel::mpsc, future, Future, FutureExt};
 use logger::prelude::*;
-use std::pin::Pin;
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
 use termion::color::*;
-use types::crypto_proxies::LedgerInfoWithSignatures;
+use types::{crypto_proxies::LedgerInfoWithSignatures, validator_set::ValidatorSet};
 
 pub struct MockStateComputer {
     commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>,
+    // The validator set the computed blocks change to, until one of them is committed.
+    next_validator_set: Mutex<Option<ValidatorSet>>,
+    // Where the committed ledger info ending an epoch is recorded, as the ledger would.
+    storage: Option<Arc<MockSharedStorage<TestPayload>>>,
 }
 
 impl MockStateComputer {
     pub fn new(commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>) -> Self {
-        MockStateComputer { commit_callback }
+        MockStateComputer {
+            commit_callback,
+            next_validator_set: Mutex::new(None),
+            storage: None,
+        }
+    }
+
+    /// Records the committed ledger info ending an epoch in the given storage, which the epoch is
+    /// recovered from on restart.
+    pub fn with_storage(mut self, storage: Arc<MockSharedStorage<TestPayload>>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Ends the current epoch: the blocks computed from now on change the validator set to the
+    /// given one, until one of them is committed.
+    pub fn reconfigure(&self, next_validator_set: ValidatorSet) {
+        *self.next_validator_set.lock().unwrap() = Some(next_validator_set);
+    }
+
+    fn record_epoch_end(&self, ledger_info: &LedgerInfoWithSignatures) {
+        let epoch = ledger_info.ledger_info().epoch_num();
+        if ledger_info.ledger_info().next_validator_set().is_none() {
+            return;
+        }
+        *self.next_validator_set.lock().unwrap() = None;
+        if let Some(storage) = &self.storage {
+            let mut epoch_change_proof = storage.epoch_change_proof.lock().unwrap();
+            if epoch_change_proof
+                .last()
+                .map_or(true, |last| last.ledger_info().epoch_num() < epoch)
+            {
+                epoch_change_proof.push(ledger_info.clone());
+            }
+        }
     }
 }
 
@@ -36,7 +80,7 @@ impl StateComputer for MockStateComputer {
             executed_state: ExecutedState {
                 state_id: *ACCUMULATOR_PLACEHOLDER_HASH,
                 version: 0,
-                validators: None,
+                validators: self.next_validator_set.lock().unwrap().clone(),
             },
             compute_status: vec![],
         })
@@ -47,6 +91,7 @@ impl StateComputer for MockStateComputer {
         &self,
         commit: LedgerInfoWithSignatures,
     ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        self.record_epoch_end(&commit);
         self.commit_callback
             .unbounded_send(commit)
             .expect("Fail to notify about commit.");
@@ -60,6 +105,7 @@ impl StateComputer for MockStateComputer {
             Fg(Reset),
             commit.ledger_info().ledger_info().consensus_block_id()
         );
+        self.record_epoch_end(commit.ledger_info());
         self.commit_callback
             .unbounded_send(commit.ledger_info().clone())
             .expect("Fail to notify about sync");
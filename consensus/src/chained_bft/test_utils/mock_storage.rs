@@ -2,9 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
-    common::Payload,
+    common::{Payload, Round},
     consensus_types::{block::Block, quorum_cert::QuorumCert},
-    liveness::pacemaker_timeout_manager::HighestTimeoutCertificates,
+    liveness::{
+        leader_reputation::BlockMetadata, pacemaker_timeout_manager::HighestTimeoutCertificates,
+    },
     persistent_storage::{PersistentLivenessStorage, PersistentStorage, RecoveryData},
     safety::safety_rules::ConsensusState,
 };
@@ -12,9 +14,10 @@ use config::config::{NodeConfig, NodeConfigHelpers};
 use crypto::HashValue;
 use failure::Result;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     sync::{Arc, Mutex},
 };
+use types::crypto_proxies::LedgerInfoWithSignatures;
 
 pub struct MockSharedStorage<T> {
     // Safety state
@@ -24,6 +27,10 @@ pub struct MockSharedStorage<T> {
 
     // Liveness state
     pub highest_timeout_certificates: Mutex<HighestTimeoutCertificates>,
+    pub block_metadata: Mutex<BTreeMap<(u64, Round), BlockMetadata>>,
+
+    // Ledger state: the ledger infos ending the epochs committed by the state computer
+    pub epoch_change_proof: Mutex<Vec<LedgerInfoWithSignatures>>,
 }
 
 /// A storage that simulates the operations in-memory, used in the tests that cares about storage
@@ -70,6 +77,11 @@ impl<T: Payload> MockStorage<T> {
                 .lock()
                 .unwrap()
                 .clone(),
+            self.shared_storage
+                .epoch_change_proof
+                .lock()
+                .unwrap()
+                .clone(),
         )
     }
 
@@ -130,6 +142,10 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
             self.shared_storage.block.lock().unwrap().remove(&id);
             self.shared_storage.qc.lock().unwrap().remove(&id);
         }
+        // The whole tree is pruned when a new epoch starts over from its genesis.
+        if self.shared_storage.block.lock().unwrap().is_empty() {
+            return Ok(());
+        }
         if let Err(e) = self.verify_consistency() {
             panic!("invalid db after prune tree: {}", e);
         }
@@ -141,12 +157,39 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
         Ok(())
     }
 
+    fn save_block_metadata(
+        &self,
+        epoch: u64,
+        block_metadata: Vec<BlockMetadata>,
+        min_round: Round,
+    ) -> Result<()> {
+        let mut stored = self.shared_storage.block_metadata.lock().unwrap();
+        for metadata in block_metadata {
+            stored.insert((epoch, metadata.round()), metadata);
+        }
+        *stored = stored.split_off(&(epoch, min_round));
+        Ok(())
+    }
+
+    fn get_block_metadata(&self, epoch: u64) -> Result<Vec<BlockMetadata>> {
+        Ok(self
+            .shared_storage
+            .block_metadata
+            .lock()
+            .unwrap()
+            .range((epoch, 0)..=(epoch, Round::max_value()))
+            .map(|(_, metadata)| metadata.clone())
+            .collect())
+    }
+
     fn start(_config: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
         let shared_storage = Arc::new(MockSharedStorage {
             block: Mutex::new(HashMap::new()),
             qc: Mutex::new(HashMap::new()),
             state: Mutex::new(ConsensusState::default()),
             highest_timeout_certificates: Mutex::new(HighestTimeoutCertificates::new(None, None)),
+            block_metadata: Mutex::new(BTreeMap::new()),
+            epoch_change_proof: Mutex::new(vec![]),
         });
         let storage = MockStorage {
             shared_storage: Arc::clone(&shared_storage),
@@ -198,6 +241,14 @@ impl<T: Payload> PersistentStorage<T> for EmptyStorage {
         Ok(())
     }
 
+    fn save_block_metadata(&self, _: u64, _: Vec<BlockMetadata>, _: Round) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_block_metadata(&self, _: u64) -> Result<Vec<BlockMetadata>> {
+        Ok(vec![])
+    }
+
     fn start(_: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
         let genesis = Block::make_genesis_block();
         let genesis_qc = QuorumCert::certificate_for_genesis();
@@ -210,6 +261,7 @@ impl<T: Payload> PersistentStorage<T> for EmptyStorage {
                 vec![genesis_qc],
                 HashValue::random(),
                 htc,
+                vec![],
             )
             .unwrap(),
         )
@@ -6,16 +6,22 @@ use crate::chained_bft::{
     common::Round,
     consensus_types::{block::Block, quorum_cert::QuorumCert, vote_data::VoteData},
 };
-use crypto::{hash::CryptoHash, HashValue};
+use crypto::{
+    bls12381::BLS12381PrivateKey, hash::CryptoHash, traits::Uniform, vrf::ecvrf::VRFPrivateKey,
+    x25519, HashValue,
+};
 use executor::ExecutedState;
 use futures::{channel::mpsc, executor::block_on};
 use logger::{set_simple_logger, set_simple_logger_prefix};
+use rand::{rngs::StdRng, SeedableRng};
 use std::{collections::HashMap, sync::Arc};
 use termion::color::*;
 use tokio::runtime;
 use types::{
-    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner},
+    crypto_proxies::{bls12381, LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier},
     ledger_info::LedgerInfo,
+    validator_public_keys::ValidatorPublicKeys,
+    validator_set::ValidatorSet,
 };
 
 mod mock_state_computer;
@@ -29,40 +35,89 @@ pub use mock_txn_manager::MockTransactionManager;
 pub type TestPayload = Vec<usize>;
 
 pub fn build_empty_tree() -> Arc<BlockStore<Vec<usize>>> {
-    let signer = ValidatorSigner::random(None);
-    build_empty_tree_with_custom_signing(signer)
-}
-
-pub fn build_empty_tree_with_custom_signing(
-    my_signer: ValidatorSigner,
-) -> Arc<BlockStore<Vec<usize>>> {
     let (commit_cb_sender, _commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
     let (storage, initial_data) = EmptyStorage::start_for_testing();
     Arc::new(block_on(BlockStore::new(
         storage,
         initial_data,
-        my_signer,
         Arc::new(MockStateComputer::new(commit_cb_sender)),
         true,
         10, // max pruned blocks in mem
     )))
 }
 
+/// Gives the signer a VRF key generated from the seed, as the validators proving the VRF outputs
+/// of their proposals have.
+pub fn with_vrf_key(signer: ValidatorSigner, seed: [u8; 32]) -> ValidatorSigner {
+    signer.with_vrf_private_key(VRFPrivateKey::generate_for_testing(&mut StdRng::from_seed(
+        seed,
+    )))
+}
+
+/// Gives the signer a BLS12-381 key generated from the seed, as the validators aggregating their
+/// signatures in the certificates have.
+pub fn with_bls_key(signer: ValidatorSigner, seed: [u8; 32]) -> ValidatorSigner {
+    signer.with_bls_private_key(BLS12381PrivateKey::generate_for_testing(
+        &mut StdRng::from_seed(seed),
+    ))
+}
+
+/// The verifier of the given signers, which aggregate their signatures in the certificates with
+/// the BLS12-381 keys they have.
+pub fn aggregating_validator(signers: &[&ValidatorSigner]) -> ValidatorVerifier {
+    ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect(),
+    )
+    .with_aggregation_verifier(
+        bls12381::ValidatorVerifier::new_with_proofs_of_possession(
+            signers
+                .iter()
+                .map(|signer| {
+                    (
+                        signer.author(),
+                        signer
+                            .bls_public_key()
+                            .expect("Signer without BLS12-381 key"),
+                    )
+                })
+                .collect(),
+        )
+        .expect("Invalid proof of possession"),
+    )
+}
+
 pub struct TreeInserter {
     payload_val: usize,
+    signer: ValidatorSigner,
     block_store: Arc<BlockStore<Vec<usize>>>,
 }
 
 impl TreeInserter {
     pub fn new(block_store: Arc<BlockStore<Vec<usize>>>) -> Self {
+        Self::new_with_signer(ValidatorSigner::random(None), block_store)
+    }
+
+    /// The given signer signs the blocks inserted and their placeholder QCs.
+    pub fn new_with_signer(
+        signer: ValidatorSigner,
+        block_store: Arc<BlockStore<Vec<usize>>>,
+    ) -> Self {
         Self {
             payload_val: 0,
+            signer,
             block_store,
         }
     }
 
+    pub fn signer(&self) -> &ValidatorSigner {
+        &self.signer
+    }
+
     /// This function is generating a placeholder QC for a block's parent that is signed by a single
-    /// signer kept by the block store. If more sophisticated QC required, please use
+    /// signer kept by the inserter. If more sophisticated QC required, please use
     /// `insert_block_with_qc`.
     pub fn insert_block(
         &mut self,
@@ -71,7 +126,7 @@ impl TreeInserter {
     ) -> Arc<Block<Vec<usize>>> {
         // Node must carry a QC to its parent
         let parent_qc = placeholder_certificate_for_block(
-            vec![self.block_store.signer()],
+            vec![&self.signer],
             parent.id(),
             parent.round(),
             parent.quorum_cert().certified_block_id(),
@@ -96,7 +151,7 @@ impl TreeInserter {
             round,
             parent.timestamp_usecs() + 1,
             parent_qc,
-            self.block_store.signer(),
+            &self.signer,
         )))
         .unwrap()
     }
@@ -201,6 +256,86 @@ pub fn placeholder_certificate_for_block(
     )
 }
 
+/// The quorum cert for the given block, signed by the given validators, whose ledger info
+/// commits the grandparent of the block as per the 3-chain commit rule (or only orders it in the
+/// decoupled execution mode).
+pub fn ordering_certificate_for_block<T>(
+    signers: Vec<&ValidatorSigner>,
+    block: &Block<T>,
+) -> QuorumCert {
+    let vote_data = VoteData::new(
+        block.id(),
+        ExecutedState::state_for_genesis().state_id,
+        block.round(),
+        block.quorum_cert().certified_block_id(),
+        block.quorum_cert().certified_block_round(),
+        block.quorum_cert().parent_block_id(),
+        block.quorum_cert().parent_block_round(),
+    );
+    let ledger_info = LedgerInfo::new(
+        0,
+        HashValue::zero(),
+        vote_data.hash(),
+        block.quorum_cert().parent_block_id(),
+        0,
+        0,
+        None,
+    );
+    let mut signatures = HashMap::new();
+    for signer in signers {
+        let li_sig = signer
+            .sign_message(ledger_info.hash())
+            .expect("Failed to sign LedgerInfo");
+        signatures.insert(signer.author(), li_sig);
+    }
+    QuorumCert::new(
+        vote_data,
+        LedgerInfoWithSignatures::new(ledger_info, signatures),
+    )
+}
+
+/// The ledger info ending the given epoch, signed by the given validators, which stay the
+/// validators of the next epoch.
+pub fn epoch_ending_ledger_info(
+    signers: Vec<&ValidatorSigner>,
+    epoch: u64,
+) -> LedgerInfoWithSignatures {
+    let next_validator_set = ValidatorSet::new(
+        signers
+            .iter()
+            .map(|signer| {
+                ValidatorPublicKeys::new(
+                    signer.author(),
+                    signer.public_key(),
+                    signer.public_key(),
+                    x25519::compat::generate_keypair(None).1,
+                    signer.vrf_public_key(),
+                )
+            })
+            .collect(),
+    );
+    // Any version but 0, which would make the ledger info nominal.
+    let ledger_info = LedgerInfo::new(
+        epoch + 1,
+        HashValue::zero(),
+        HashValue::zero(),
+        HashValue::zero(),
+        epoch,
+        0,
+        Some(next_validator_set),
+    );
+    let signatures = signers
+        .iter()
+        .map(|signer| {
+            let signature = signer
+                .sign_message(ledger_info.hash())
+                .expect("Failed to sign LedgerInfo");
+            (signer.author(), signature)
+        })
+        .collect();
+    LedgerInfoWithSignatures::new(ledger_info, signatures)
+}
+
 fn nocapture() -> bool {
     ::std::env::args().any(|arg| arg == "--nocapture")
 }
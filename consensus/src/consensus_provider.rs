@@ -5,7 +5,7 @@ use config::config::NodeConfig;
 use failure::prelude::*;
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
 
-use crate::chained_bft::chained_bft_consensus_provider::ChainedBftProvider;
+use crate::chained_bft::{chained_bft_consensus_provider::ChainedBftProvider, ConsensusPublisher};
 use executor::Executor;
 use grpcio::{ChannelBuilder, EnvBuilder};
 use mempool::proto::mempool_grpc::MempoolClient;
@@ -27,13 +27,15 @@ pub trait ConsensusProvider {
     fn stop(&mut self);
 }
 
-/// Helper function to create a ConsensusProvider based on configuration
+/// Helper function to create a ConsensusProvider based on configuration. The blocks committed
+/// are pushed to the full nodes subscribed to the consensus publisher, if any.
 pub fn make_consensus_provider(
     node_config: &mut NodeConfig,
     network_sender: ConsensusNetworkSender,
     network_receiver: ConsensusNetworkEvents,
     executor: Arc<Executor<MoveVM>>,
     state_sync_client: Arc<StateSyncClient>,
+    consensus_publisher: Option<ConsensusPublisher>,
 ) -> Box<dyn ConsensusProvider> {
     Box::new(ChainedBftProvider::new(
         node_config,
@@ -42,6 +44,7 @@ pub fn make_consensus_provider(
         create_mempool_client(node_config),
         executor,
         state_sync_client,
+        consensus_publisher,
     ))
 }
 /// Create a mempool client assuming the mempool is running on localhost
@@ -183,6 +183,18 @@ pub static ref VOTE_SUCCESS_WAIT_S: DurationHistogram = OP_COUNTERS.duration_his
 /// Histogram of time waited for failing to have the ability to vote (both those that waited and didn't wait) while trying to follow timestamp rules
 pub static ref VOTE_FAILURE_WAIT_S: DurationHistogram = OP_COUNTERS.duration_histogram("vote_failure_wait_s");
 
+////////////////////////////////
+// PROPOSAL BACKPRESSURE COUNTERS
+////////////////////////////////
+/// The max number of transactions the proposer pulls for its next proposal
+pub static ref PROPOSAL_BLOCK_SIZE_LIMIT: IntGauge = OP_COUNTERS.gauge("proposal_block_size_limit");
+
+/// Count of the uncommitted blocks in the branch extended by the last proposal
+pub static ref PROPOSAL_PENDING_BLOCKS: IntGauge = OP_COUNTERS.gauge("proposal_pending_blocks");
+
+/// Count of the empty blocks proposed because too many blocks were pending
+pub static ref PROPOSAL_BACKPRESSURE_EMPTY_COUNT: IntCounter = OP_COUNTERS.counter("proposal_backpressure_empty_count");
+
 ///////////////////
 // CHANNEL COUNTERS
 ///////////////////
@@ -210,6 +222,15 @@ pub static ref PENDING_NEW_ROUND_EVENTS: IntGauge = OP_COUNTERS.gauge("pending_n
 /// Count of the pending sync info messages.
 pub static ref PENDING_SYNC_INFO_MSGS: IntGauge = OP_COUNTERS.gauge("pending_sync_info_msgs");
 
+/// Count of the pending commit votes (decoupled execution mode).
+pub static ref PENDING_COMMIT_VOTES: IntGauge = OP_COUNTERS.gauge("pending_commit_votes");
+
+/// Count of the pending messages of other epochs.
+pub static ref PENDING_DIFFERENT_EPOCH_MSGS: IntGauge = OP_COUNTERS.gauge("pending_different_epoch_msgs");
+
+/// Count of the pending epoch change proofs.
+pub static ref PENDING_EPOCH_CHANGE_PROOFS: IntGauge = OP_COUNTERS.gauge("pending_epoch_change_proofs");
+
 /// Count of the pending winning proposals.
 pub static ref PENDING_WINNING_PROPOSALS: IntGauge = OP_COUNTERS.gauge("pending_winning_proposals");
 }
@@ -30,6 +30,21 @@ pub mod util;
 /// use in the Libra Core blockchain.
 pub mod consensus_provider;
 
+/// Runs the safety rules of a validator, which hold its consensus signing key, in their own
+/// process.
+pub use chained_bft::SafetyRulesNode;
+
+/// Pushes the blocks that a validator commits to the full nodes subscribed to it, and commits
+/// these blocks on the full nodes.
+pub use chained_bft::{ConsensusObserver, ConsensusPublisher};
+
+/// Replays the consensus events that a validator recorded to reproduce its decisions.
+pub use chained_bft::{read_recorded_runs, ReplayNode};
+
+/// Inspects the ConsensusDB of a stopped validator and deletes the blocks it can't recover
+/// from.
+pub use chained_bft::{ConsensusDbInspector, ConsensusDbReport};
+
 mod counters;
 
 mod state_computer;
@@ -22,6 +22,13 @@ pub trait TxnManager: Send + Sync {
         exclude_txns: Vec<&Self::Payload>,
     ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>>;
 
+    /// The number of transactions left in mempool after the last pull, if known: it tells the
+    /// proposer whether larger blocks would be filled. The depth is only reported once per pull,
+    /// so that it isn't taken for the current one after proposals that skipped pulling.
+    fn take_mempool_depth(&self) -> Option<u64> {
+        None
+    }
+
     /// Notifies TxnManager about the payload of the committed block including the state compute
     /// result, which includes the specifics of what transactions succeeded and failed.
     fn commit_txns<'a>(
@@ -13,18 +13,24 @@ use mempool::proto::{
     mempool_grpc::MempoolClient,
 };
 use proto_conv::FromProto;
-use std::{pin::Pin, sync::Arc};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
 use types::transaction::{SignedTransaction, TransactionStatus};
 
 /// Proxy interface to mempool
 pub struct MempoolProxy {
     mempool: Arc<MempoolClient>,
+    // The number of transactions mempool reported as remaining in the last pull, until taken.
+    remaining_txns: Arc<Mutex<Option<u64>>>,
 }
 
 impl MempoolProxy {
     pub fn new(mempool: Arc<MempoolClient>) -> Self {
         Self {
             mempool: Arc::clone(&mempool),
+            remaining_txns: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -97,26 +103,30 @@ impl TxnManager for MempoolProxy {
         let mut get_block_request = GetBlockRequest::new();
         get_block_request.set_max_block_size(max_size);
         get_block_request.set_transactions(::protobuf::RepeatedField::from_vec(exclude_txns));
+        let remaining_txns = Arc::clone(&self.remaining_txns);
         match self.mempool.get_block_async(&get_block_request) {
             Ok(receiver) => async move {
                 match receiver.compat().await {
-                    Ok(mut response) => Ok(response
-                        .take_block()
-                        .take_transactions()
-                        .into_iter()
-                        .filter_map(|proto_txn| {
-                            match SignedTransaction::from_proto(proto_txn.clone()) {
-                                Ok(t) => Some(t),
-                                Err(e) => {
-                                    security_log(SecurityEvent::InvalidTransactionConsensus)
-                                        .error(&e)
-                                        .data(&proto_txn)
-                                        .log();
-                                    None
+                    Ok(mut response) => {
+                        *remaining_txns.lock().unwrap() = Some(response.get_remaining_txns());
+                        Ok(response
+                            .take_block()
+                            .take_transactions()
+                            .into_iter()
+                            .filter_map(|proto_txn| {
+                                match SignedTransaction::from_proto(proto_txn.clone()) {
+                                    Ok(t) => Some(t),
+                                    Err(e) => {
+                                        security_log(SecurityEvent::InvalidTransactionConsensus)
+                                            .error(&e)
+                                            .data(&proto_txn)
+                                            .log();
+                                        None
+                                    }
                                 }
-                            }
-                        })
-                        .collect()),
+                            })
+                            .collect())
+                    }
                     Err(e) => Err(e.into()),
                 }
             }
@@ -125,6 +135,10 @@ impl TxnManager for MempoolProxy {
         }
     }
 
+    fn take_mempool_depth(&self) -> Option<u64> {
+        self.remaining_txns.lock().unwrap().take()
+    }
+
     fn commit_txns<'a>(
         &'a self,
         txns: &Self::Payload,
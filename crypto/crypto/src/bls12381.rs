@@ -27,17 +27,26 @@
 //! **Note**: The above example generates a private key using a private function intended only for
 //! testing purposes. Production code should generate the key according to the spec [draft-irtf-cfrg-bls-signature-00](https://tools.ietf.org/id/draft-irtf-cfrg-bls-signature-00.html#keygen).
 //!
-//! This module is not currently used, but could be included in the future for improved
-//! performance in consensus.
-
-use crate::{traits::*, HashValue};
+//! Signatures of the same message can be aggregated into a single one, which verifies against the
+//! aggregate of the signers' public keys (see [`BLS12381Signature::aggregate`] and
+//! [`BLS12381PublicKey::aggregate`]). This is what makes the scheme attractive for consensus
+//! certificates, whose size and verification cost then no longer grow with the number of signers.
+//! Aggregation is only safe for keys whose holders proved the possession of the private key (see
+//! [`BLS12381PrivateKey::prove_possession`]), otherwise a rogue key chosen as a function of the
+//! others can forge an aggregate on its own.
+
+use crate::{
+    hash::{CryptoHasher, ProofOfPossessionHasher},
+    traits::*,
+    HashValue,
+};
 use bincode::{deserialize, serialize};
 use core::convert::TryFrom;
 use crypto_derive::{Deref, SilentDebug, SilentDisplay};
 use failure::prelude::*;
 use pairing::{
-    bls12_381::{Fr, FrRepr},
-    PrimeField,
+    bls12_381::{Fr, FrRepr, G1Compressed, G2Compressed, G1, G2},
+    CurveAffine, CurveProjective, EncodedPoint, PrimeField,
 };
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -65,11 +74,88 @@ pub struct BLS12381PublicKey(threshold_crypto::PublicKey);
 #[derive(Clone, Hash, Serialize, Deserialize, Deref, Debug, PartialEq, Eq)]
 pub struct BLS12381Signature(threshold_crypto::Signature);
 
+/// A BLS12-381 proof of possession: the signature by a private key of its own public key, under a
+/// domain of its own so that it cannot be mistaken for the signature of any other message.
+#[derive(Clone, Hash, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct BLS12381ProofOfPossession(BLS12381Signature);
+
 impl BLS12381PublicKey {
     /// Serializes a BLS12381PublicKey.
     pub fn to_bytes(&self) -> [u8; BLS12381_PUBLIC_KEY_LENGTH] {
         self.0.to_bytes()
     }
+
+    /// Checks that `proof` was produced by the private key matching this public key.
+    pub fn verify_possession(&self, proof: &BLS12381ProofOfPossession) -> Result<()> {
+        proof
+            .0
+            .verify(&proof_of_possession_message(self), self)
+            .map_err(|_| format_err!("The proof of possession of {} is not valid", self))
+    }
+
+    /// Aggregates public keys into the one against which the aggregate of their signatures of a
+    /// given message verifies.
+    ///
+    /// **Warning**: the aggregation is only safe against rogue key attacks if the holder of each
+    /// key has proven the possession of the matching private key, see
+    /// [`BLS12381PublicKey::verify_possession`].
+    pub fn aggregate(public_keys: &[&BLS12381PublicKey]) -> Result<Self> {
+        ensure!(!public_keys.is_empty(), "No public key to aggregate");
+        let mut aggregate = G1::zero();
+        for public_key in public_keys {
+            let mut point = G1Compressed::empty();
+            point.as_mut().copy_from_slice(&public_key.to_bytes());
+            aggregate.add_assign_mixed(&point.into_affine()?);
+        }
+        ensure!(
+            !aggregate.is_zero(),
+            "The aggregated public key is the identity"
+        );
+        let mut bytes = [0u8; BLS12381_PUBLIC_KEY_LENGTH];
+        bytes.copy_from_slice(aggregate.into_affine().into_compressed().as_ref());
+        Ok(BLS12381PublicKey(threshold_crypto::PublicKey::from_bytes(
+            bytes,
+        )?))
+    }
+}
+
+impl BLS12381PrivateKey {
+    /// Proves the possession of this private key to whoever registers the matching public key.
+    pub fn prove_possession(&self) -> BLS12381ProofOfPossession {
+        BLS12381ProofOfPossession(
+            self.sign_message(&proof_of_possession_message(&BLS12381PublicKey::from(self))),
+        )
+    }
+}
+
+impl BLS12381ProofOfPossession {
+    /// Serializes a BLS12381ProofOfPossession.
+    pub fn to_bytes(&self) -> [u8; BLS12381_SIGNATURE_LENGTH] {
+        self.0.to_bytes()
+    }
+}
+
+impl ValidKey for BLS12381ProofOfPossession {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes().to_vec()
+    }
+}
+
+impl TryFrom<&[u8]> for BLS12381ProofOfPossession {
+    type Error = CryptoMaterialError;
+
+    fn try_from(
+        bytes: &[u8],
+    ) -> std::result::Result<BLS12381ProofOfPossession, CryptoMaterialError> {
+        BLS12381Signature::try_from(bytes).map(BLS12381ProofOfPossession)
+    }
+}
+
+/// The message signed by the proof of possession of `public_key`.
+fn proof_of_possession_message(public_key: &BLS12381PublicKey) -> HashValue {
+    let mut hasher = ProofOfPossessionHasher::default();
+    hasher.write(&public_key.to_bytes());
+    hasher.finish()
 }
 
 impl BLS12381Signature {
@@ -77,6 +163,23 @@ impl BLS12381Signature {
     pub fn to_bytes(&self) -> [u8; BLS12381_SIGNATURE_LENGTH] {
         self.0.to_bytes()
     }
+
+    /// Aggregates signatures of the same message into a single one, which verifies against the
+    /// aggregate of the signers' public keys.
+    pub fn aggregate(signatures: &[&BLS12381Signature]) -> Result<Self> {
+        ensure!(!signatures.is_empty(), "No signature to aggregate");
+        let mut aggregate = G2::zero();
+        for signature in signatures {
+            let mut point = G2Compressed::empty();
+            point.as_mut().copy_from_slice(&signature.to_bytes());
+            aggregate.add_assign_mixed(&point.into_affine()?);
+        }
+        let mut bytes = [0u8; BLS12381_SIGNATURE_LENGTH];
+        bytes.copy_from_slice(aggregate.into_affine().into_compressed().as_ref());
+        Ok(BLS12381Signature(threshold_crypto::Signature::from_bytes(
+            bytes,
+        )?))
+    }
 }
 
 ///////////////////////
@@ -165,6 +268,13 @@ impl ValidKey for BLS12381PrivateKey {
     }
 }
 
+#[cfg(any(test, feature = "testing"))]
+impl Clone for BLS12381PrivateKey {
+    fn clone(&self) -> Self {
+        BLS12381PrivateKey::try_from(&ValidKey::to_bytes(self)[..]).unwrap()
+    }
+}
+
 impl Genesis for BLS12381PrivateKey {
     fn genesis() -> Self {
         let mut buf = [0u8; BLS12381_PRIVATE_KEY_LENGTH];
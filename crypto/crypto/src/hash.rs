@@ -537,6 +537,22 @@ define_hasher! {
     (TimeoutMsgHasher, TIMEOUT_MSG_HASHER, b"TimeoutMsg")
 }
 
+define_hasher! {
+    /// The hasher used to compute the message signed by every validator timing out a round, whose
+    /// signatures can then be aggregated.
+    (TimeoutRoundHasher, TIMEOUT_ROUND_HASHER, b"TimeoutRound")
+}
+
+define_hasher! {
+    /// The hasher used to compute the digest of the consensus state that the safety rules sign
+    /// when they persist it.
+    (
+        ConsensusStateHasher,
+        CONSENSUS_STATE_HASHER,
+        b"ConsensusState"
+    )
+}
+
 define_hasher! {
     /// The hasher used to compute the hash of a VoteData object.
     (VoteDataHasher, VOTE_DATA_HASHER, b"VoteData")
@@ -547,6 +563,15 @@ define_hasher! {
     (ContractEventHasher, CONTRACT_EVENT_HASHER, b"ContractEvent")
 }
 
+define_hasher! {
+    /// The hasher used to compute the message signed by a BLS12-381 proof of possession.
+    (
+        ProofOfPossessionHasher,
+        PROOF_OF_POSSESSION_HASHER,
+        b"ProofOfPossession"
+    )
+}
+
 define_hasher! {
     /// The hasher used only for testing. It doesn't have a salt.
     (TestOnlyHasher, TEST_ONLY_HASHER, b"")
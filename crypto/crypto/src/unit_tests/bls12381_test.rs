@@ -3,15 +3,15 @@
 
 use crate::{
     bls12381::{
-        BLS12381PrivateKey, BLS12381PublicKey, BLS12381Signature, BLS12381_PRIVATE_KEY_LENGTH,
-        BLS12381_PUBLIC_KEY_LENGTH, BLS12381_SIGNATURE_LENGTH,
+        BLS12381PrivateKey, BLS12381ProofOfPossession, BLS12381PublicKey, BLS12381Signature,
+        BLS12381_PRIVATE_KEY_LENGTH, BLS12381_PUBLIC_KEY_LENGTH, BLS12381_SIGNATURE_LENGTH,
     },
     hash::HashValue,
     traits::*,
     unit_tests::uniform_keypair_strategy,
 };
 use bincode::{deserialize, serialize};
-use proptest::prelude::*;
+use proptest::{collection::vec, prelude::*};
 use std::convert::TryFrom;
 
 proptest! {
@@ -89,4 +89,52 @@ proptest! {
         let deserialized = deserialize::<BLS12381Signature>(&serialized).unwrap();
         prop_assert!(keypair.public_key.verify_signature(&hash, &deserialized).is_ok());
     }
+
+    #[test]
+    fn test_aggregate_and_verify(
+        hash in any::<HashValue>(),
+        other_hash in any::<HashValue>(),
+        keypairs in vec(uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>(), 2..5)
+    ) {
+        prop_assume!(hash != other_hash);
+        let signatures: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| keypair.private_key.sign_message(&hash))
+            .collect();
+        let public_keys: Vec<_> = keypairs.iter().map(|keypair| &keypair.public_key).collect();
+        let aggregated_public_key = BLS12381PublicKey::aggregate(&public_keys).unwrap();
+        let aggregated_signature =
+            BLS12381Signature::aggregate(&signatures.iter().collect::<Vec<_>>()).unwrap();
+        prop_assert!(aggregated_signature.verify(&hash, &aggregated_public_key).is_ok());
+        prop_assert!(aggregated_signature.verify(&other_hash, &aggregated_public_key).is_err());
+
+        // The signature of one of the keys is missing.
+        let partial_signature =
+            BLS12381Signature::aggregate(&signatures.iter().skip(1).collect::<Vec<_>>()).unwrap();
+        prop_assert!(partial_signature.verify(&hash, &aggregated_public_key).is_err());
+    }
+
+    #[test]
+    fn test_proof_of_possession(
+        keypair in uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>(),
+        other_keypair in uniform_keypair_strategy::<BLS12381PrivateKey, BLS12381PublicKey>()
+    ) {
+        prop_assume!(keypair.public_key != other_keypair.public_key);
+        let proof = keypair.private_key.prove_possession();
+        prop_assert!(keypair.public_key.verify_possession(&proof).is_ok());
+        prop_assert!(other_keypair.public_key.verify_possession(&proof).is_err());
+        let deserialized = BLS12381ProofOfPossession::try_from(&proof.to_bytes()[..]).unwrap();
+        prop_assert!(keypair.public_key.verify_possession(&deserialized).is_ok());
+
+        // A signature of something else by the key is not a proof of possession.
+        let signature = keypair.private_key.sign_message(&HashValue::random());
+        let forged = BLS12381ProofOfPossession::try_from(&signature.to_bytes()[..]).unwrap();
+        prop_assert!(keypair.public_key.verify_possession(&forged).is_err());
+    }
+}
+
+#[test]
+fn test_aggregate_nothing() {
+    assert!(BLS12381PublicKey::aggregate(&[]).is_err());
+    assert!(BLS12381Signature::aggregate(&[]).is_err());
 }
@@ -74,7 +74,7 @@ pub const PROOF_LENGTH: usize = 80;
 pub struct VRFPrivateKey(ed25519_PrivateKey);
 
 /// An ECVRF public key
-#[derive(Serialize, Deserialize, Debug, Deref, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Deref, PartialEq, Eq)]
 pub struct VRFPublicKey(ed25519_PublicKey);
 
 /// A longer private key which is slightly optimized for proof generation.
@@ -129,11 +129,25 @@ impl TryFrom<&[u8]> for VRFPrivateKey {
 
     fn try_from(bytes: &[u8]) -> std::result::Result<VRFPrivateKey, CryptoMaterialError> {
         Ok(VRFPrivateKey(
-            ed25519_PrivateKey::from_bytes(bytes).unwrap(),
+            ed25519_PrivateKey::from_bytes(bytes)
+                .map_err(|_| CryptoMaterialError::DeserializationError)?,
         ))
     }
 }
 
+impl ValidKey for VRFPrivateKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes().to_vec()
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl Clone for VRFPrivateKey {
+    fn clone(&self) -> Self {
+        VRFPrivateKey::try_from(&ValidKey::to_bytes(self)[..]).unwrap()
+    }
+}
+
 impl TryFrom<&[u8]> for VRFPublicKey {
     type Error = CryptoMaterialError;
 
@@ -160,6 +174,28 @@ impl TryFrom<&[u8]> for VRFPublicKey {
     }
 }
 
+impl ValidKey for VRFPublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes().to_vec()
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl proptest::arbitrary::Arbitrary for VRFPublicKey {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        use proptest::strategy::{LazyJust, Strategy};
+        use rand::{rngs::StdRng, SeedableRng};
+        LazyJust::new(|| {
+            let mut rng = StdRng::from_seed(crate::test_utils::TEST_SEED);
+            (&VRFPrivateKey::generate_for_testing(&mut rng)).into()
+        })
+        .boxed()
+    }
+}
+
 impl VRFPublicKey {
     /// Given a [`Proof`] and an input, returns whether or not the proof is valid for the input
     /// and public key
@@ -219,7 +255,7 @@ impl<'a> From<&'a VRFPrivateKey> for VRFExpandedPrivateKey {
         let mut lower: [u8; 32] = [0u8; 32];
         let mut upper: [u8; 32] = [0u8; 32];
 
-        h.input(private_key.to_bytes());
+        h.input(private_key.0.to_bytes());
         hash.copy_from_slice(h.result().as_slice());
 
         lower.copy_from_slice(&hash[00..32]);
@@ -263,6 +299,10 @@ impl TryFrom<&[u8]> for Proof {
     type Error = CryptoMaterialError;
 
     fn try_from(bytes: &[u8]) -> std::result::Result<Proof, CryptoMaterialError> {
+        // Proofs may come from untrusted sources, so they must not make us panic.
+        if bytes.len() != PROOF_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
         let mut c_buf = [0u8; 32];
         c_buf[..16].copy_from_slice(&bytes[32..48]);
         let mut s_buf = [0u8; 32];
@@ -270,7 +310,7 @@ impl TryFrom<&[u8]> for Proof {
         Ok(Proof {
             gamma: CompressedEdwardsY::from_slice(&bytes[..32])
                 .decompress()
-                .unwrap(),
+                .ok_or(CryptoMaterialError::DeserializationError)?,
             c: ed25519_Scalar::from_bits(c_buf),
             s: ed25519_Scalar::from_bits(s_buf),
         })
@@ -177,6 +177,18 @@ fn test_output_from_proof() {
     }
 }
 
+#[test]
+fn test_malformed_proof() {
+    let pi: &[u8] = &::hex::decode(TESTVECTORS[0].pi).unwrap();
+    assert!(Proof::try_from(&pi[..PROOF_LENGTH - 1]).is_err());
+    assert!(Proof::try_from(&[pi, &[0u8]].concat()[..]).is_err());
+    // y = 2 is not the y-coordinate of a curve point
+    let mut bad_gamma = pi.to_vec();
+    bad_gamma[..32].copy_from_slice(&[0u8; 32]);
+    bad_gamma[0] = 2;
+    assert!(Proof::try_from(&bad_gamma[..]).is_err());
+}
+
 proptest! {
     #[test]
     fn test_prove_and_verify(
@@ -7,6 +7,7 @@ use crate::{
     Command, ExecutedState, ExecutedTrees, StateComputeResult, OP_COUNTERS,
 };
 use backoff::{ExponentialBackoff, Operation};
+use canonical_serialization::SimpleDeserializer;
 use config::config::VMConfig;
 use crypto::{
     hash::{CryptoHash, EventAccumulatorHasher},
@@ -25,6 +26,7 @@ use std::{
 };
 use storage_client::{StorageRead, StorageWrite, VerifiedStateView};
 use types::{
+    access_path::VALIDATOR_SET_ACCESS_PATH,
     account_address::AccountAddress,
     account_state_blob::AccountStateBlob,
     crypto_proxies::LedgerInfoWithSignatures,
@@ -33,6 +35,7 @@ use types::{
         SignedTransaction, TransactionInfo, TransactionListWithProof, TransactionOutput,
         TransactionPayload, TransactionStatus, TransactionToCommit, Version,
     },
+    validator_set::ValidatorSet,
     write_set::{WriteOp, WriteSet},
 };
 use vm_runtime::VMExecutor;
@@ -587,6 +590,7 @@ where
 
     fn execute_block(&mut self, id: HashValue) {
         let parent_trees = self.get_trees_from_parent(id);
+        let parent_validators = self.get_validators_from_parent(id);
 
         let block_to_execute = self
             .block_tree
@@ -628,17 +632,18 @@ where
             Ok(output) => {
                 let accu_root_hash = output.executed_trees().txn_accumulator().root_hash();
                 let version = output.executed_trees().txn_accumulator().num_leaves() - 1;
+                // The descendants of a block changing the validator set carry the change as well,
+                // so that it's not lost if they get committed together.
+                let validators = output.validators().clone().or(parent_validators);
                 block_to_execute.set_output(output);
 
                 // Now that we have the root hash and execution status we can send the response to
                 // consensus.
-                // TODO: The VM will support a special transaction to set the validators for the
-                // next epoch that is part of a block execution.
                 let state_compute_result = StateComputeResult {
                     executed_state: ExecutedState {
                         state_id: accu_root_hash,
                         version,
-                        validators: None,
+                        validators,
                     },
                     compute_status: status,
                 };
@@ -670,6 +675,25 @@ where
         }
     }
 
+    /// Given id of the block that is about to be executed, returns the validator set for the next
+    /// epoch if the parent block or one of its uncommitted ancestors changes it. It's `None` if the
+    /// parent block is already committed, since the change has then taken effect.
+    fn get_validators_from_parent(&self, id: HashValue) -> Option<ValidatorSet> {
+        let parent_id = self
+            .block_tree
+            .get_block(id)
+            .expect("Block should exist.")
+            .parent_id();
+        self.block_tree
+            .get_block(parent_id)
+            .and_then(|parent_block| {
+                parent_block
+                    .execute_response()
+                    .as_ref()
+                    .and_then(|response| response.executed_state.validators.clone())
+            })
+    }
+
     /// Post-processing of what the VM outputs. Returns the entire block's output.
     fn process_vm_outputs(
         mut account_to_btree: HashMap<AccountAddress, BTreeMap<Vec<u8>, Vec<u8>>>,
@@ -687,6 +711,8 @@ where
         // transactions that will be discarded, since they do not go into the transaction
         // accumulator.
         let mut txn_info_hashes = vec![];
+        // The validator set for the next epoch, if any transaction changes it.
+        let mut validators = None;
 
         let proof_reader = ProofReader::new(account_to_proof);
         for (vm_output, signed_txn) in
@@ -719,6 +745,11 @@ where
                         status.major_status,
                     );
                     txn_info_hashes.push(txn_info.hash());
+                    if let Some(validator_set) =
+                        Self::find_validator_set_change(vm_output.write_set())?
+                    {
+                        validators = Some(validator_set);
+                    }
                 }
                 TransactionStatus::Discard(_) => {
                     ensure!(
@@ -752,9 +783,22 @@ where
                 state_tree: current_state_tree,
                 transaction_accumulator: Rc::new(current_transaction_accumulator),
             },
+            validators,
         ))
     }
 
+    /// Returns the new validator set if the write set updates the on-chain validator set.
+    fn find_validator_set_change(write_set: &WriteSet) -> Result<Option<ValidatorSet>> {
+        for (access_path, write_op) in write_set {
+            if *access_path == *VALIDATOR_SET_ACCESS_PATH {
+                if let WriteOp::Value(blob) = write_op {
+                    return Ok(Some(SimpleDeserializer::deserialize(blob)?));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     /// For all accounts modified by this transaction, find the previous blob and update it based
     /// on the write set. Returns the blob value of all these accounts as well as the newly
     /// constructed state tree.
@@ -88,7 +88,8 @@ pub struct ExecutedState {
     /// that on restart that the version is calculated correctly
     pub version: Version,
     /// If set, this is the validator set that should be changed to if this block is committed.
-    /// TODO [Reconfiguration] the validators are currently ignored, no reconfiguration yet.
+    /// It's set if a transaction of this block or of one of its uncommitted ancestors updates the
+    /// on-chain validator set.
     pub validators: Option<ValidatorSet>,
 }
 
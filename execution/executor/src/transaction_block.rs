@@ -18,6 +18,7 @@ use types::{
     crypto_proxies::LedgerInfoWithSignatures,
     proof::accumulator::Accumulator,
     transaction::{SignedTransaction, TransactionStatus},
+    validator_set::ValidatorSet,
 };
 
 /// `TransactionBlock` holds everything about the block of transactions.
@@ -88,6 +89,11 @@ impl TransactionBlock {
         &self.output
     }
 
+    /// Returns the result for `execute_block` request, if the block has been executed.
+    pub fn execute_response(&self) -> &Option<StateComputeResult> {
+        &self.execute_response
+    }
+
     /// Returns the signatures on this block.
     pub fn ledger_info_with_sigs(&self) -> &Option<LedgerInfoWithSignatures> {
         &self.ledger_info_with_sigs
@@ -324,13 +330,21 @@ pub struct ProcessedVMOutput {
     /// The in-memory Merkle Accumulator and state Sparse Merkle Tree after appending all the
     /// transactions in this set.
     executed_trees: ExecutedTrees,
+
+    /// The validator set for the next epoch if one of the transactions in this set changes it.
+    validators: Option<ValidatorSet>,
 }
 
 impl ProcessedVMOutput {
-    pub fn new(transaction_data: Vec<TransactionData>, executed_trees: ExecutedTrees) -> Self {
+    pub fn new(
+        transaction_data: Vec<TransactionData>,
+        executed_trees: ExecutedTrees,
+        validators: Option<ValidatorSet>,
+    ) -> Self {
         ProcessedVMOutput {
             transaction_data,
             executed_trees,
+            validators,
         }
     }
 
@@ -341,4 +355,8 @@ impl ProcessedVMOutput {
     pub fn executed_trees(&self) -> &ExecutedTrees {
         &self.executed_trees
     }
+
+    pub fn validators(&self) -> &Option<ValidatorSet> {
+        &self.validators
+    }
 }
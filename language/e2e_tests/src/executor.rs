@@ -19,7 +19,7 @@ use types::{
     write_set::WriteSet,
 };
 use vm::CompiledModule;
-use vm_runtime::{MoveVM, VMExecutor, VMVerifier};
+use vm_runtime::{execution_trace::ExecutionTrace, MoveVM, VMExecutor, VMTracer, VMVerifier};
 
 /// Provides an environment to run a VM instance.
 ///
@@ -82,6 +82,11 @@ impl FakeExecutor {
         accounts
     }
 
+    /// Sets whether the blocks are executed speculatively on multiple threads.
+    pub fn set_parallel_execution(&mut self, parallel_execution: bool) {
+        self.config.vm_config.parallel_execution = parallel_execution;
+    }
+
     /// Applies a [`WriteSet`] to this executor's data store.
     pub fn apply_write_set(&mut self, write_set: &WriteSet) {
         self.data_store.add_write_set(write_set);
@@ -124,6 +129,16 @@ impl FakeExecutor {
             .expect("A block with one transaction should have one output")
     }
 
+    /// Executes the given transaction like `execute_transaction`, and also returns the trace of
+    /// every instruction the VM executed for it. Print the trace to see where a transaction
+    /// aborts.
+    pub fn trace_transaction(&self, txn: SignedTransaction) -> (TransactionOutput, ExecutionTrace) {
+        let trace = ExecutionTrace::new();
+        let output =
+            MoveVM::trace_transaction(txn, &self.config.vm_config, &self.data_store, &trace);
+        (output, trace)
+    }
+
     /// Get the blob for the associated AccessPath
     pub fn read_from_access_path(&self, path: &AccessPath) -> Option<Vec<u8>> {
         StateView::get(&self.data_store, path).unwrap()
@@ -12,11 +12,13 @@
 mod account_universe;
 mod arithmetic;
 mod create_account;
+mod execution_trace;
 mod function_call;
 mod genesis;
 mod mint;
 mod module_publishing;
 mod pack_unpack;
+mod parallel_execution;
 mod peer_to_peer;
 mod rotate_key;
 mod verify_txn;
@@ -0,0 +1,67 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::AccountData, common_transactions::peer_to_peer_txn, executor::FakeExecutor,
+    transaction_status_eq,
+};
+use types::{
+    transaction::TransactionStatus,
+    vm_error::{StatusCode, VMStatus},
+};
+use vm::file_format::Bytecode;
+use vm_runtime_types::value::Value;
+
+#[test]
+fn trace_peer_to_peer() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let sender = AccountData::new(1_000_000, 10);
+    let receiver = AccountData::new(100_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+
+    let txn = peer_to_peer_txn(sender.account(), receiver.account(), 10, 1_000);
+    let (output, trace) = executor.trace_transaction(txn.clone());
+    // Tracing doesn't change the execution.
+    assert_eq!(output, executor.execute_transaction(txn));
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+    );
+
+    let records = trace.into_records();
+    assert_eq!(records[0].function, "LibraAccount::prologue");
+    assert_eq!(records[0].offset, 0);
+    assert!(records
+        .iter()
+        .any(|record| record.function == "LibraAccount::pay_from_sender"));
+    assert_eq!(records.last().unwrap().function, "LibraAccount::epilogue");
+    assert_eq!(records.last().unwrap().instruction, Bytecode::Ret);
+}
+
+#[test]
+fn trace_aborted_transaction() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let sender = AccountData::new(1_000_000, 10);
+    let receiver = AccountData::new(100_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+
+    // A zero-amount transfer aborts with code 7 in the deposit.
+    let txn = peer_to_peer_txn(sender.account(), receiver.account(), 10, 0);
+    let (output, trace) = executor.trace_transaction(txn);
+    assert!(transaction_status_eq(
+        output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::ABORTED).with_sub_status(7))
+    ));
+
+    // The script is interrupted by the abort, but the epilogue still runs to charge the gas.
+    let records = trace.into_records();
+    let abort = records
+        .iter()
+        .find(|record| record.instruction == Bytecode::Abort)
+        .expect("The trace should contain the abort");
+    assert_eq!(abort.function, "LibraAccount::deposit");
+    assert_eq!(abort.stack_top, Some(format!("{:?}", Value::u64(7))));
+    assert_eq!(records.last().unwrap().function, "LibraAccount::epilogue");
+}
@@ -0,0 +1,138 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::{Account, AccountData},
+    common_transactions::peer_to_peer_txn,
+    executor::FakeExecutor,
+};
+use types::{
+    transaction::{SignedTransaction, TransactionOutput, TransactionStatus},
+    vm_error::{StatusCode, VMStatus},
+};
+
+/// Executes the block both sequentially and in parallel, checks that the outputs are the same and
+/// returns them.
+fn execute_both_ways(
+    executor: &mut FakeExecutor,
+    txns: Vec<SignedTransaction>,
+) -> Vec<TransactionOutput> {
+    executor.set_parallel_execution(false);
+    let sequential_outputs = executor.execute_block(txns.clone());
+    executor.set_parallel_execution(true);
+    let parallel_outputs = executor.execute_block(txns);
+    assert_eq!(sequential_outputs, parallel_outputs);
+    parallel_outputs
+}
+
+fn create_accounts(executor: &mut FakeExecutor, count: usize) -> Vec<AccountData> {
+    (0..count)
+        .map(|_| {
+            let account = AccountData::new(1_000_000, 0);
+            executor.add_account_data(&account);
+            account
+        })
+        .collect()
+}
+
+#[test]
+fn parallel_independent_transfers() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let senders = create_accounts(&mut executor, 8);
+    let receivers = create_accounts(&mut executor, 8);
+
+    let txns = senders
+        .iter()
+        .zip(&receivers)
+        .map(|(sender, receiver)| peer_to_peer_txn(sender.account(), receiver.account(), 0, 1_000))
+        .collect();
+    for output in execute_both_ways(&mut executor, txns) {
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+        );
+    }
+}
+
+#[test]
+fn parallel_conflicting_transfers() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let senders = create_accounts(&mut executor, 4);
+    let receiver = create_accounts(&mut executor, 1).remove(0);
+
+    // Every transaction pays the same receiver, and each sender sends several transactions in a
+    // row: the speculative executions of all but the first transactions read stale values.
+    let mut txns = vec![];
+    for seq_num in 0..3 {
+        for sender in &senders {
+            txns.push(peer_to_peer_txn(
+                sender.account(),
+                receiver.account(),
+                seq_num,
+                1_000,
+            ));
+        }
+    }
+    // The receiver spends from a balance that just changed.
+    txns.push(peer_to_peer_txn(
+        receiver.account(),
+        senders[0].account(),
+        0,
+        500_000,
+    ));
+    let outputs = execute_both_ways(&mut executor, txns);
+    for output in &outputs {
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+        );
+    }
+
+    for output in outputs {
+        executor.apply_write_set(output.write_set());
+    }
+    let updated_receiver = executor
+        .read_account_resource(receiver.account())
+        .expect("receiver must exist");
+    assert_eq!(1, updated_receiver.sequence_number());
+    assert_eq!(12, updated_receiver.received_events().count());
+}
+
+#[test]
+fn parallel_block_with_failing_transactions() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let accounts = create_accounts(&mut executor, 4);
+    let unknown_sender = Account::new();
+
+    let txns = vec![
+        peer_to_peer_txn(accounts[0].account(), accounts[1].account(), 0, 1_000),
+        // Sequence number too new.
+        peer_to_peer_txn(accounts[0].account(), accounts[1].account(), 2, 1_000),
+        // Not enough coins.
+        peer_to_peer_txn(accounts[2].account(), accounts[3].account(), 0, 10_000_000),
+        // The sender doesn't exist.
+        peer_to_peer_txn(&unknown_sender, accounts[3].account(), 0, 1_000),
+        peer_to_peer_txn(accounts[0].account(), accounts[1].account(), 1, 1_000),
+    ];
+    let outputs = execute_both_ways(&mut executor, txns);
+    assert_eq!(
+        outputs[0].status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+    );
+    assert_eq!(
+        outputs[1].status(),
+        &TransactionStatus::Discard(VMStatus::new(StatusCode::SEQUENCE_NUMBER_TOO_NEW))
+    );
+    match outputs[2].status() {
+        TransactionStatus::Keep(status) => assert_ne!(status.major_status, StatusCode::EXECUTED),
+        status => panic!("Unexpected status {:?}", status),
+    }
+    assert_eq!(
+        outputs[3].status(),
+        &TransactionStatus::Discard(VMStatus::new(StatusCode::SENDING_ACCOUNT_DOES_NOT_EXIST))
+    );
+    assert_eq!(
+        outputs[4].status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+    );
+}
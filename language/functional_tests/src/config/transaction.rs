@@ -32,6 +32,7 @@ pub enum Entry {
     DisableStages(Vec<Stage>),
     Sender(String),
     Arguments(Vec<Argument>),
+    Trace,
 }
 
 impl FromStr for Entry {
@@ -70,6 +71,9 @@ impl FromStr for Entry {
                 .collect();
             return Ok(Entry::DisableStages(res?));
         }
+        if s == "trace" {
+            return Ok(Entry::Trace);
+        }
         Err(ErrorKind::Other(format!(
             "failed to parse '{}' as transaction config entry",
             s
@@ -104,6 +108,8 @@ pub struct Config {
     pub disabled_stages: BTreeSet<Stage>,
     pub sender: String,
     pub args: Vec<TransactionArgument>,
+    /// Whether to log the instructions executed for the transaction.
+    pub trace: bool,
 }
 
 impl Config {
@@ -112,6 +118,7 @@ impl Config {
         let mut disabled_stages = BTreeSet::new();
         let mut sender = None;
         let mut args = None;
+        let mut trace = false;
 
         for entry in entries {
             match entry {
@@ -157,6 +164,12 @@ impl Config {
                         .into())
                     }
                 },
+                Entry::Trace => {
+                    if trace {
+                        return Err(ErrorKind::Other("trace already set".to_string()).into());
+                    }
+                    trace = true;
+                }
                 Entry::DisableStages(stages) => {
                     for stage in stages {
                         if !disabled_stages.insert(*stage) {
@@ -175,6 +188,7 @@ impl Config {
             disabled_stages,
             sender: sender.unwrap_or_else(|| "default".to_string()),
             args: args.unwrap_or_else(|| vec![]),
+            trace,
         })
     }
 
@@ -72,11 +72,16 @@ pub enum OutputType {
     CompiledModule(CompiledModule),
     CompiledScript(CompiledScript),
     Ast(ScriptOrModule),
+    /// The instructions executed for a transaction, one per line.
+    ExecutionTrace(String),
 }
 
 impl OutputType {
     pub fn to_check_string(&self) -> String {
-        format!("{:?}", self)
+        match self {
+            OutputType::ExecutionTrace(trace) => trace.clone(),
+            _ => format!("{:?}", self),
+        }
     }
 }
 
@@ -128,6 +133,7 @@ impl fmt::Display for OutputType {
             CompiledModule(cm) => write!(f, "{:#?}", cm),
             CompiledScript(cs) => write!(f, "{:#?}", cs),
             Ast(ast) => write!(f, "{}", ast),
+            ExecutionTrace(trace) => write!(f, "{}", trace),
         }
     }
 }
@@ -229,23 +235,31 @@ fn make_module_transaction(
     .into_inner())
 }
 
-/// Runs a single transaction using the fake executor.
+/// Runs a single transaction using the fake executor. If `trace` is set, the instructions executed
+/// for the transaction are logged, whether it succeeds or not.
 fn run_transaction(
     exec: &mut FakeExecutor,
     transaction: SignedTransaction,
+    trace: bool,
+    log: &mut EvaluationResult,
 ) -> Result<TransactionOutput> {
-    let mut outputs = exec.execute_block(vec![transaction]);
-    if outputs.len() == 1 {
-        let output = outputs.pop().unwrap();
-        match output.status() {
-            TransactionStatus::Keep(status) if status.major_status == StatusCode::EXECUTED => {
-                Ok(output)
-            }
-            TransactionStatus::Keep(_) => Err(ErrorKind::VMExecutionFailure(output).into()),
-            TransactionStatus::Discard(_) => Err(ErrorKind::DiscardedTransaction(output).into()),
-        }
+    let output = if trace {
+        let (output, execution_trace) = exec.trace_transaction(transaction);
+        log.outputs.push(EvaluationOutput::Output(Box::new(
+            OutputType::ExecutionTrace(execution_trace.to_string()),
+        )));
+        output
     } else {
-        panic!("transaction outputs size mismatch");
+        let mut outputs = exec.execute_block(vec![transaction]);
+        assert_eq!(outputs.len(), 1, "transaction outputs size mismatch");
+        outputs.pop().unwrap()
+    };
+    match output.status() {
+        TransactionStatus::Keep(status) if status.major_status == StatusCode::EXECUTED => {
+            Ok(output)
+        }
+        TransactionStatus::Keep(_) => Err(ErrorKind::VMExecutionFailure(output).into()),
+        TransactionStatus::Discard(_) => Err(ErrorKind::DiscardedTransaction(output).into()),
     }
 }
 
@@ -379,8 +393,15 @@ pub fn eval(config: &GlobalConfig, transactions: &[Transaction]) -> Result<Evalu
                     compiled_script,
                     transaction.config.args.clone(),
                 )?;
-                let txn_output =
-                    unwrap_or_log!(run_transaction(&mut exec, script_transaction), res);
+                let txn_output = unwrap_or_log!(
+                    run_transaction(
+                        &mut exec,
+                        script_transaction,
+                        transaction.config.trace,
+                        &mut res,
+                    ),
+                    res
+                );
                 exec.apply_write_set(txn_output.write_set());
             }
             ScriptOrModule::Module(parsed_module) => {
@@ -422,8 +443,15 @@ pub fn eval(config: &GlobalConfig, transactions: &[Transaction]) -> Result<Evalu
                 }
                 res.outputs.push(EvaluationOutput::Stage(Stage::Runtime));
                 let module_transaction = make_module_transaction(&exec, data, compiled_module)?;
-                let txn_output =
-                    unwrap_or_log!(run_transaction(&mut exec, module_transaction), res);
+                let txn_output = unwrap_or_log!(
+                    run_transaction(
+                        &mut exec,
+                        module_transaction,
+                        transaction.config.trace,
+                        &mut res,
+                    ),
+                    res
+                );
                 exec.apply_write_set(txn_output.write_set());
             }
         }
@@ -25,6 +25,7 @@ fn parse_simple_positive() {
         "//! sender:foobar42",
         "//! sender\t:\tfoobar42",
         "//!\nsender\n:\nfoobar42",
+        "//! trace",
     ] {
         s.parse::<Entry>().unwrap();
     }
@@ -32,7 +33,13 @@ fn parse_simple_positive() {
 
 #[test]
 fn parse_simple_negative() {
-    for s in &["//!", "//! ", "//! garbage", "//! sender:"] {
+    for s in &[
+        "//!",
+        "//! ",
+        "//! garbage",
+        "//! sender:",
+        "//! trace: yes",
+    ] {
         s.parse::<Entry>().unwrap_err();
     }
 }
@@ -111,3 +118,22 @@ fn build_transaction_config_3() {
         //! args: {{bob}}
     ").unwrap_err();
 }
+
+#[rustfmt::skip]
+#[test]
+fn build_transaction_config_trace() {
+    let global = parse_and_build_global_config("").unwrap();
+
+    assert!(!parse_and_build_config(&global, r"
+        //! sender: default
+    ").unwrap().trace);
+
+    assert!(parse_and_build_config(&global, r"
+        //! trace
+    ").unwrap().trace);
+
+    parse_and_build_config(&global, r"
+        //! trace
+        //! trace
+    ").unwrap_err();
+}
@@ -0,0 +1,70 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use config::config::{NodeConfig, VMConfig};
+use failure::prelude::*;
+use libradb::LibraDB;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use transaction_replay::TransactionReplayer;
+use types::transaction::Version;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "transaction-replay",
+    author = "Libra",
+    about = "Tool to replay the transactions of a LibraDB and check the results are the same"
+)]
+struct Opt {
+    /// Directory holding the DB, i.e. `storage.dir` in the node config.
+    #[structopt(short = "d", long = "db-dir", parse(from_os_str))]
+    db_dir: PathBuf,
+    /// Node config to take the VM config from, the default VM config is used otherwise.
+    #[structopt(short = "f", long = "node-config", parse(from_os_str))]
+    node_config: Option<PathBuf>,
+    /// First version to replay.
+    #[structopt(short = "s", long = "start-version", default_value = "0")]
+    start_version: Version,
+    /// Last version to replay, the latest by default.
+    #[structopt(short = "e", long = "end-version")]
+    end_version: Option<Version>,
+}
+
+fn run(opt: Opt) -> Result<bool> {
+    let db = LibraDB::open_readonly(&opt.db_dir)?;
+    let vm_config = match opt.node_config {
+        Some(path) => NodeConfig::load(None, path)?.vm_config,
+        None => VMConfig::default(),
+    };
+    let end_version = match opt.end_version {
+        Some(version) => version,
+        None => {
+            db.get_startup_info()?
+                .ok_or_else(|| format_err!("DB is not bootstrapped."))?
+                .latest_version
+        }
+    };
+
+    let report = TransactionReplayer::new(&db, vm_config).replay(opt.start_version, end_version)?;
+    for (version, mismatches) in &report.mismatches {
+        println!("Version {}:", version);
+        for mismatch in mismatches {
+            println!("  {:?}", mismatch);
+        }
+    }
+    println!(
+        "Replayed versions {} to {}: {} transactions, {} with mismatches.",
+        opt.start_version,
+        end_version,
+        report.num_transactions,
+        report.mismatches.len()
+    );
+    Ok(report.is_deterministic())
+}
+
+fn main() {
+    let deterministic = run(Opt::from_args()).expect("Failed to replay the transactions.");
+    if !deterministic {
+        std::process::exit(1);
+    }
+}
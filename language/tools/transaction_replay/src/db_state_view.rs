@@ -0,0 +1,120 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crypto::{hash::CryptoHash, HashValue};
+use failure::prelude::*;
+use libradb::LibraDB;
+use scratchpad::ProofRead;
+use state_view::StateView;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    convert::TryInto,
+};
+use types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    proof::{verify_sparse_merkle_element, SparseMerkleProof},
+    transaction::Version,
+};
+
+/// The state committed in a LibraDB right after a given version, read from the account state tree
+/// of that version. Every account read is verified against the state root hash of the version,
+/// and its proof is kept so that the state tree can be updated with what the transaction writes.
+pub(crate) struct DbStateView<'a> {
+    db: &'a LibraDB,
+    /// `None` for the state before the genesis transaction, which is empty.
+    version: Option<Version>,
+    state_root_hash: HashValue,
+    account_to_btree_cache: RefCell<HashMap<AccountAddress, BTreeMap<Vec<u8>, Vec<u8>>>>,
+    account_to_proof_cache: RefCell<HashMap<HashValue, SparseMerkleProof>>,
+}
+
+impl<'a> DbStateView<'a> {
+    pub fn new(db: &'a LibraDB, version: Option<Version>, state_root_hash: HashValue) -> Self {
+        Self {
+            db,
+            version,
+            state_root_hash,
+            account_to_btree_cache: RefCell::new(HashMap::new()),
+            account_to_proof_cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the state of the account at `address`, empty if the account doesn't exist.
+    pub fn get_account_state(&self, address: AccountAddress) -> Result<BTreeMap<Vec<u8>, Vec<u8>>> {
+        self.load_account(address)?;
+        Ok(self.account_to_btree_cache.borrow()[&address].clone())
+    }
+
+    /// Returns the proofs of all the accounts read so far.
+    pub fn into_proof_reader(self) -> ProofReader {
+        ProofReader {
+            account_to_proof: self.account_to_proof_cache.into_inner(),
+        }
+    }
+
+    fn load_account(&self, address: AccountAddress) -> Result<()> {
+        if self.account_to_btree_cache.borrow().contains_key(&address) {
+            return Ok(());
+        }
+        let (blob, proof) = match self.version {
+            Some(version) => self
+                .db
+                .get_account_state_with_proof_by_version(address, version)?,
+            None => (None, SparseMerkleProof::new(None, vec![])),
+        };
+        verify_sparse_merkle_element(self.state_root_hash, address.hash(), &blob, &proof).map_err(
+            |err| {
+                format_err!(
+                    "Proof is invalid for address {:?} with state root hash {:?}: {}",
+                    address,
+                    self.state_root_hash,
+                    err
+                )
+            },
+        )?;
+        let account_btree = blob
+            .as_ref()
+            .map(TryInto::try_into)
+            .transpose()?
+            .unwrap_or_default();
+        self.account_to_proof_cache
+            .borrow_mut()
+            .insert(address.hash(), proof);
+        self.account_to_btree_cache
+            .borrow_mut()
+            .insert(address, account_btree);
+        Ok(())
+    }
+}
+
+impl<'a> StateView for DbStateView<'a> {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        self.load_account(access_path.address)?;
+        Ok(self.account_to_btree_cache.borrow()[&access_path.address]
+            .get(&access_path.path)
+            .cloned())
+    }
+
+    fn multi_get(&self, access_paths: &[AccessPath]) -> Result<Vec<Option<Vec<u8>>>> {
+        access_paths
+            .iter()
+            .map(|access_path| self.get(access_path))
+            .collect()
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.version.is_none()
+    }
+}
+
+pub(crate) struct ProofReader {
+    account_to_proof: HashMap<HashValue, SparseMerkleProof>,
+}
+
+impl ProofRead for ProofReader {
+    fn get_proof(&self, key: HashValue) -> Option<&SparseMerkleProof> {
+        self.account_to_proof.get(&key)
+    }
+}
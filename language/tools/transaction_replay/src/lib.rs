@@ -0,0 +1,288 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This crate replays the transactions committed in a [`LibraDB`] to check that executing them
+//! again gives the same results, e.g. before rolling out a change to the VM or the stdlib.
+//!
+//! Each transaction is executed on its own through the [`MoveVM`], against the state committed
+//! right before it, read from the account state tree of the previous version. Its outputs are
+//! then compared with what's stored: the gas used, the major status and the events, the states of
+//! the accounts it writes, the state root hash and finally the hash of the whole
+//! [`TransactionInfo`].
+
+mod db_state_view;
+
+#[cfg(test)]
+mod transaction_replay_test;
+
+use crate::db_state_view::DbStateView;
+use config::config::VMConfig;
+use crypto::{
+    hash::{CryptoHash, EventAccumulatorHasher, SPARSE_MERKLE_PLACEHOLDER_HASH},
+    HashValue,
+};
+use failure::prelude::*;
+use libradb::LibraDB;
+use scratchpad::SparseMerkleTree;
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    convert::TryFrom,
+};
+use types::{
+    account_address::AccountAddress,
+    account_state_blob::AccountStateBlob,
+    contract_event::ContractEvent,
+    proof::accumulator::Accumulator,
+    transaction::{
+        SignedTransaction, TransactionInfo, TransactionOutput, TransactionStatus, Version,
+    },
+    vm_error::{StatusCode, VMStatus},
+    write_set::WriteOp,
+};
+use vm_runtime::{MoveVM, VMExecutor};
+
+/// What executing a transaction again produced.
+#[derive(Clone, Debug)]
+pub struct ReplayedTransaction {
+    /// The output of the VM.
+    pub output: TransactionOutput,
+    /// The states of the accounts written by the transaction, once written.
+    pub account_states: HashMap<AccountAddress, AccountStateBlob>,
+    /// The info the transaction would be committed with, `None` if it is discarded.
+    pub txn_info: Option<TransactionInfo>,
+}
+
+/// A difference between what a transaction produced when it was committed and when it was
+/// replayed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Mismatch {
+    /// The transaction is discarded on replay, with the given status.
+    Discarded(VMStatus),
+    MajorStatus {
+        stored: StatusCode,
+        replayed: StatusCode,
+    },
+    GasUsed {
+        stored: u64,
+        replayed: u64,
+    },
+    Events {
+        stored: Vec<ContractEvent>,
+        replayed: Vec<ContractEvent>,
+    },
+    AccountState {
+        address: AccountAddress,
+        stored: Option<AccountStateBlob>,
+        replayed: AccountStateBlob,
+    },
+    StateRootHash {
+        stored: HashValue,
+        replayed: HashValue,
+    },
+    TransactionInfoHash {
+        stored: HashValue,
+        replayed: HashValue,
+    },
+}
+
+/// Summarizes a replay.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReplayReport {
+    /// Number of transactions replayed.
+    pub num_transactions: u64,
+    /// The mismatches found, by version of the transaction.
+    pub mismatches: BTreeMap<Version, Vec<Mismatch>>,
+}
+
+impl ReplayReport {
+    /// Whether every transaction replayed gave the same results as when it was committed.
+    pub fn is_deterministic(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Replays the transactions of a DB with a given VM configuration.
+pub struct TransactionReplayer<'a> {
+    db: &'a LibraDB,
+    vm_config: VMConfig,
+}
+
+impl<'a> TransactionReplayer<'a> {
+    pub fn new(db: &'a LibraDB, vm_config: VMConfig) -> Self {
+        Self { db, vm_config }
+    }
+
+    /// Replays the transactions of versions in `[start_version, end_version]` one by one and
+    /// compares the results with what's stored. The state trees of the versions before them must
+    /// not have been pruned.
+    pub fn replay(&self, start_version: Version, end_version: Version) -> Result<ReplayReport> {
+        ensure!(
+            start_version <= end_version,
+            "Start version {} is greater than end version {}.",
+            start_version,
+            end_version,
+        );
+        let handler = self.db.get_debug_handler();
+        let mut parent_state_root_hash = if start_version == 0 {
+            *SPARSE_MERKLE_PLACEHOLDER_HASH
+        } else {
+            let (_, txn_info, _) = handler.get_transaction(start_version - 1)?;
+            txn_info.state_root_hash()
+        };
+
+        let mut report = ReplayReport::default();
+        for version in start_version..=end_version {
+            let (signed_txn, txn_info, events) = handler.get_transaction(version)?;
+            let replayed = self.execute_transaction(version, parent_state_root_hash, signed_txn)?;
+            let mismatches = self.compare(version, &txn_info, &events, &replayed)?;
+            if !mismatches.is_empty() {
+                report.mismatches.insert(version, mismatches);
+            }
+            report.num_transactions += 1;
+            // The next transaction reads what's stored, whatever this one gave on replay.
+            parent_state_root_hash = txn_info.state_root_hash();
+        }
+        Ok(report)
+    }
+
+    /// Executes the transaction of `version` against the state committed right before it, whose
+    /// root hash is `parent_state_root_hash`.
+    pub fn execute_transaction(
+        &self,
+        version: Version,
+        parent_state_root_hash: HashValue,
+        signed_txn: SignedTransaction,
+    ) -> Result<ReplayedTransaction> {
+        let parent_version = version.checked_sub(1);
+        let state_view = DbStateView::new(self.db, parent_version, parent_state_root_hash);
+        let output = MoveVM::execute_block(vec![signed_txn.clone()], &self.vm_config, &state_view)
+            .pop()
+            .ok_or_else(|| format_err!("The VM didn't output anything."))?;
+        let status = match output.status() {
+            TransactionStatus::Keep(status) => status.clone(),
+            TransactionStatus::Discard(_) => {
+                return Ok(ReplayedTransaction {
+                    output,
+                    account_states: HashMap::new(),
+                    txn_info: None,
+                });
+            }
+        };
+
+        // Apply the writes on top of the states of the accounts they go to, which the VM may not
+        // have read for a write set transaction.
+        let mut account_to_btree = HashMap::new();
+        for (access_path, _) in output.write_set() {
+            if let Entry::Vacant(entry) = account_to_btree.entry(access_path.address) {
+                entry.insert(state_view.get_account_state(access_path.address)?);
+            }
+        }
+        for (access_path, write_op) in output.write_set() {
+            let account_btree = account_to_btree
+                .get_mut(&access_path.address)
+                .expect("Account should be loaded.");
+            match write_op {
+                WriteOp::Value(value) => {
+                    account_btree.insert(access_path.path.clone(), value.clone())
+                }
+                WriteOp::Deletion => account_btree.remove(&access_path.path),
+            };
+        }
+        let account_states = account_to_btree
+            .iter()
+            .map(|(address, account_btree)| {
+                Ok((*address, AccountStateBlob::try_from(account_btree)?))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        let state_tree = SparseMerkleTree::new(parent_state_root_hash)
+            .update(
+                account_states
+                    .iter()
+                    .map(|(address, blob)| (address.hash(), blob.clone()))
+                    .collect(),
+                &state_view.into_proof_reader(),
+            )
+            .map_err(|err| format_err!("Failed to update the state tree: {:?}", err))?;
+        let event_root_hash = Accumulator::<EventAccumulatorHasher>::default()
+            .append(output.events().iter().map(CryptoHash::hash).collect())
+            .root_hash();
+        let txn_info = TransactionInfo::new(
+            signed_txn.hash(),
+            state_tree.root_hash(),
+            event_root_hash,
+            output.gas_used(),
+            status.major_status,
+        );
+
+        Ok(ReplayedTransaction {
+            output,
+            account_states,
+            txn_info: Some(txn_info),
+        })
+    }
+
+    fn compare(
+        &self,
+        version: Version,
+        stored_txn_info: &TransactionInfo,
+        stored_events: &[ContractEvent],
+        replayed: &ReplayedTransaction,
+    ) -> Result<Vec<Mismatch>> {
+        let txn_info = match &replayed.txn_info {
+            Some(txn_info) => txn_info,
+            None => {
+                return Ok(vec![Mismatch::Discarded(
+                    replayed.output.status().vm_status().clone(),
+                )])
+            }
+        };
+
+        let mut mismatches = vec![];
+        if txn_info.major_status() != stored_txn_info.major_status() {
+            mismatches.push(Mismatch::MajorStatus {
+                stored: stored_txn_info.major_status(),
+                replayed: txn_info.major_status(),
+            });
+        }
+        if txn_info.gas_used() != stored_txn_info.gas_used() {
+            mismatches.push(Mismatch::GasUsed {
+                stored: stored_txn_info.gas_used(),
+                replayed: txn_info.gas_used(),
+            });
+        }
+        if replayed.output.events() != stored_events {
+            mismatches.push(Mismatch::Events {
+                stored: stored_events.to_vec(),
+                replayed: replayed.output.events().to_vec(),
+            });
+        }
+        let mut account_states: Vec<_> = replayed.account_states.iter().collect();
+        account_states.sort_by_key(|(address, _)| **address);
+        for (address, blob) in account_states {
+            let (stored_blob, _) = self
+                .db
+                .get_account_state_with_proof_by_version(*address, version)?;
+            if stored_blob.as_ref() != Some(blob) {
+                mismatches.push(Mismatch::AccountState {
+                    address: *address,
+                    stored: stored_blob,
+                    replayed: blob.clone(),
+                });
+            }
+        }
+        if txn_info.state_root_hash() != stored_txn_info.state_root_hash() {
+            mismatches.push(Mismatch::StateRootHash {
+                stored: stored_txn_info.state_root_hash(),
+                replayed: txn_info.state_root_hash(),
+            });
+        }
+        if txn_info.hash() != stored_txn_info.hash() {
+            mismatches.push(Mismatch::TransactionInfoHash {
+                stored: stored_txn_info.hash(),
+                replayed: txn_info.hash(),
+            });
+        }
+        Ok(mismatches)
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use config::config::NodeConfig;
+use config_builder::util::get_test_config;
+use crypto::hash::GENESIS_BLOCK_ID;
+use executor::Executor;
+use futures::executor::block_on;
+use grpc_helpers::ServerHandle;
+use grpcio::EnvBuilder;
+use proto_conv::FromProto;
+use state_view::StateView;
+use std::sync::Arc;
+use storage_client::{StorageRead, StorageReadServiceClient, StorageWriteServiceClient};
+use storage_service::start_storage_service;
+use transaction_builder::encode_transfer_script;
+use types::{
+    account_config::association_address, crypto_proxies::LedgerInfoWithSignatures,
+    ledger_info::LedgerInfo, test_helpers::transaction_test_helpers::get_test_signed_txn,
+};
+
+const NUM_TRANSFERS: u64 = 3;
+const TAMPERED_VERSION: Version = 2;
+
+/// Executes like the MoveVM, except that the transfer committed at `TAMPERED_VERSION` reports one
+/// more unit of gas used and no events, as a VM that doesn't execute deterministically would.
+struct TamperingVM;
+
+impl VMExecutor for TamperingVM {
+    fn execute_block(
+        transactions: Vec<SignedTransaction>,
+        config: &VMConfig,
+        state_view: &dyn StateView,
+    ) -> Vec<TransactionOutput> {
+        // The transfers are sent by the association, right after the genesis.
+        let tampered: Vec<_> = transactions
+            .iter()
+            .map(|txn| {
+                txn.sender() == association_address() && txn.sequence_number() == TAMPERED_VERSION
+            })
+            .collect();
+        MoveVM::execute_block(transactions, config, state_view)
+            .into_iter()
+            .zip(tampered)
+            .map(|(output, tampered)| {
+                if tampered {
+                    TransactionOutput::new(
+                        output.write_set().clone(),
+                        vec![],
+                        output.gas_used() + 1,
+                        output.status().clone(),
+                    )
+                } else {
+                    output
+                }
+            })
+            .collect()
+    }
+}
+
+/// Has an executor running `V` bootstrap the DB with the genesis transaction and commit a block of
+/// transfers on top of it, then opens the DB read-only. The storage service the executor commits
+/// through must be kept running along with the config, which owns the DB directory.
+fn commit_transactions<V: VMExecutor>() -> (NodeConfig, ServerHandle, LibraDB) {
+    let (config, genesis_keypair) = get_test_config();
+    let storage_server_handle = start_storage_service(&config);
+    let client_env = Arc::new(EnvBuilder::new().build());
+    let storage_read_client = Arc::new(StorageReadServiceClient::new(
+        Arc::clone(&client_env),
+        &config.storage.address,
+        config.storage.port,
+    ));
+    let storage_write_client = Arc::new(StorageWriteServiceClient::new(
+        Arc::clone(&client_env),
+        &config.storage.address,
+        config.storage.port,
+        None,
+    ));
+    let executor = Executor::<V>::new(
+        storage_read_client as Arc<dyn StorageRead>,
+        storage_write_client,
+        &config,
+    );
+
+    let txns = (1..=NUM_TRANSFERS)
+        .map(|sequence_number| {
+            SignedTransaction::from_proto(get_test_signed_txn(
+                association_address(),
+                sequence_number,
+                genesis_keypair.private_key.clone(),
+                genesis_keypair.public_key.clone(),
+                Some(encode_transfer_script(&AccountAddress::random(), 1_000)),
+            ))
+            .unwrap()
+        })
+        .collect();
+    let block_id = HashValue::random();
+    let state_compute_result = block_on(executor.execute_block(txns, *GENESIS_BLOCK_ID, block_id))
+        .unwrap()
+        .unwrap();
+    let ledger_info = LedgerInfo::new(
+        NUM_TRANSFERS,
+        state_compute_result.root_hash(),
+        /* consensus_data_hash = */ HashValue::zero(),
+        block_id,
+        /* epoch_num = */ 0,
+        /* timestamp = */ 0,
+        None,
+    );
+    block_on(executor.commit_block(LedgerInfoWithSignatures::new(ledger_info, HashMap::new())))
+        .unwrap()
+        .unwrap();
+
+    let db = LibraDB::open_readonly(&config.storage.dir).unwrap();
+    (config, storage_server_handle, db)
+}
+
+#[test]
+fn test_replay_deterministic() {
+    let (config, _storage_server_handle, db) = commit_transactions::<MoveVM>();
+    let replayer = TransactionReplayer::new(&db, config.vm_config.clone());
+
+    let report = replayer.replay(0, NUM_TRANSFERS).unwrap();
+    assert_eq!(report.num_transactions, NUM_TRANSFERS + 1);
+    assert!(report.is_deterministic());
+
+    // Starting after the genesis.
+    let report = replayer.replay(2, NUM_TRANSFERS).unwrap();
+    assert_eq!(report.num_transactions, NUM_TRANSFERS - 1);
+    assert!(report.is_deterministic());
+
+    assert!(replayer.replay(2, 1).is_err());
+    assert!(replayer.replay(0, NUM_TRANSFERS + 1).is_err());
+}
+
+#[test]
+fn test_replay_mismatches() {
+    let (config, _storage_server_handle, db) = commit_transactions::<TamperingVM>();
+    let replayer = TransactionReplayer::new(&db, config.vm_config.clone());
+
+    let report = replayer.replay(0, NUM_TRANSFERS).unwrap();
+    assert_eq!(report.num_transactions, NUM_TRANSFERS + 1);
+    assert_eq!(
+        report.mismatches.keys().collect::<Vec<_>>(),
+        vec![&TAMPERED_VERSION]
+    );
+    let mismatches = &report.mismatches[&TAMPERED_VERSION];
+    assert_eq!(mismatches.len(), 3);
+    match &mismatches[0] {
+        Mismatch::GasUsed { stored, replayed } => assert_eq!(*stored, *replayed + 1),
+        mismatch => panic!("Unexpected mismatch {:?}", mismatch),
+    }
+    match &mismatches[1] {
+        Mismatch::Events { stored, replayed } => {
+            assert!(stored.is_empty());
+            assert!(!replayed.is_empty());
+        }
+        mismatch => panic!("Unexpected mismatch {:?}", mismatch),
+    }
+    match &mismatches[2] {
+        Mismatch::TransactionInfoHash { .. } => (),
+        mismatch => panic!("Unexpected mismatch {:?}", mismatch),
+    }
+}
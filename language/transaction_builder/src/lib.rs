@@ -158,5 +158,6 @@ pub fn default_config() -> VMConfig {
         publishing_options: VMPublishingOption::Locked(HashSet::from_iter(
             allowing_script_hashes().into_iter(),
         )),
+        parallel_execution: false,
     }
 }
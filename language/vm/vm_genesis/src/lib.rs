@@ -283,6 +283,23 @@ pub fn encode_genesis_transaction_with_validator(
                             Value::byte_array(ByteArray::new(
                                 validator_keys.consensus_public_key().to_bytes().to_vec(),
                             )),
+                            Value::byte_array(ByteArray::new(
+                                validator_keys
+                                    .vrf_public_key()
+                                    .map_or_else(Vec::new, ValidKey::to_bytes),
+                            )),
+                            Value::byte_array(ByteArray::new(
+                                validator_keys
+                                    .bls_public_key()
+                                    .map_or_else(Vec::new, |(public_key, _)| {
+                                        ValidKey::to_bytes(public_key)
+                                    }),
+                            )),
+                            Value::byte_array(ByteArray::new(
+                                validator_keys
+                                    .bls_public_key()
+                                    .map_or_else(Vec::new, |(_, proof)| proof.to_bytes().to_vec()),
+                            )),
                         ],
                     )
                     .unwrap();
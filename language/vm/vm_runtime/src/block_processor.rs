@@ -8,8 +8,13 @@ use crate::{
         script_cache::ScriptCache,
     },
     counters::{report_block_count, report_execution_status},
-    data_cache::BlockDataCache,
-    process_txn::{execute::ExecutedTransaction, validate::ValidationMode, ProcessTransaction},
+    data_cache::{BlockDataCache, RemoteCache},
+    execution_trace::InstructionTracer,
+    parallel_block_processor::{can_execute_in_parallel, execute_block_in_parallel},
+    process_txn::{
+        execute::ExecutedTransaction, validate::ValidationMode, ProcessTransaction,
+        TransactionToProcess,
+    },
 };
 use config::config::VMPublishingOption;
 use logger::prelude::*;
@@ -30,6 +35,7 @@ pub fn execute_block<'alloc>(
     script_cache: &ScriptCache<'alloc>,
     data_view: &dyn StateView,
     publishing_option: &VMPublishingOption,
+    parallel_execution: bool,
 ) -> Vec<TransactionOutput> {
     trace!("[VM] Execute block, transaction count: {}", txn_block.len());
     report_block_count(txn_block.len());
@@ -56,10 +62,6 @@ pub fn execute_block<'alloc>(
         ValidationMode::Executing
     };
 
-    let module_cache = BlockModuleCache::new(code_cache, ModuleFetcherImpl::new(data_view));
-    let mut data_cache = BlockDataCache::new(data_view);
-    let mut result = vec![];
-
     let signature_verified_block: Vec<Result<SignatureCheckedTransaction, VMStatus>> = txn_block
         .into_par_iter()
         .map(|txn| {
@@ -68,6 +70,23 @@ pub fn execute_block<'alloc>(
         })
         .collect();
 
+    if parallel_execution
+        && mode == ValidationMode::Executing
+        && can_execute_in_parallel(&signature_verified_block)
+    {
+        return execute_block_in_parallel(
+            signature_verified_block,
+            code_cache,
+            script_cache,
+            data_view,
+            publishing_option,
+        );
+    }
+
+    let module_cache = BlockModuleCache::new(code_cache, ModuleFetcherImpl::new(data_view));
+    let mut data_cache = BlockDataCache::new(data_view);
+    let mut result = vec![];
+
     for transaction in signature_verified_block {
         let output = match transaction {
             Ok(t) => transaction_flow(
@@ -77,6 +96,7 @@ pub fn execute_block<'alloc>(
                 &data_cache,
                 mode,
                 publishing_option,
+                None,
             ),
             Err(vm_status) => ExecutedTransaction::discard_error_output(vm_status),
         };
@@ -105,19 +125,25 @@ pub fn execute_block<'alloc>(
 /// and this transaction is executed successfully, this function will update `module_cache` to
 /// include those newly published modules. This function will also update the `script_cache` to
 /// cache this `txn`
-fn transaction_flow<'alloc, P>(
-    txn: SignatureCheckedTransaction,
+///
+/// The instructions executed for the transaction are reported to `tracer`, if any.
+pub(crate) fn transaction_flow<'alloc, P>(
+    txn: impl Into<TransactionToProcess>,
     module_cache: P,
     script_cache: &ScriptCache<'alloc>,
-    data_cache: &BlockDataCache<'_>,
+    data_cache: &dyn RemoteCache,
     mode: ValidationMode,
     publishing_option: &VMPublishingOption,
+    tracer: Option<&dyn InstructionTracer>,
 ) -> TransactionOutput
 where
     P: ModuleCache<'alloc>,
 {
     let arena = Arena::new();
-    let process_txn = ProcessTransaction::new(txn, &module_cache, data_cache, &arena);
+    let mut process_txn = ProcessTransaction::new(txn, &module_cache, data_cache, &arena);
+    if let Some(tracer) = tracer {
+        process_txn = process_txn.with_tracer(tracer);
+    }
 
     let validated_txn = match process_txn.validate(mode, publishing_option) {
         Ok(validated_txn) => validated_txn,
@@ -557,3 +557,59 @@ where
         panic!("reclaim_cached_module should never be called on TransactionModuleCache");
     }
 }
+
+/// A cache for the modules of a transaction that is simulated rather than executed. The modules
+/// the transaction publishes are dropped instead of being reclaimed into `block_cache`, so that the
+/// VM it belongs to can go on executing other transactions as if the simulation never happened.
+pub struct SimulationModuleCache<'alloc, P>
+where
+    P: ModuleCache<'alloc>,
+{
+    block_cache: P,
+
+    phantom: PhantomData<&'alloc ()>,
+}
+
+impl<'alloc, P> SimulationModuleCache<'alloc, P>
+where
+    P: ModuleCache<'alloc>,
+{
+    pub fn new(block_cache: P) -> Self {
+        SimulationModuleCache {
+            block_cache,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'alloc, P> ModuleCache<'alloc> for SimulationModuleCache<'alloc, P>
+where
+    P: ModuleCache<'alloc>,
+{
+    fn resolve_function_ref(
+        &self,
+        caller_module: &LoadedModule,
+        idx: FunctionHandleIndex,
+    ) -> VMResult<Option<FunctionRef<'alloc>>> {
+        self.block_cache.resolve_function_ref(caller_module, idx)
+    }
+
+    fn resolve_struct_def(
+        &self,
+        module: &LoadedModule,
+        idx: StructDefinitionIndex,
+        gas_meter: &GasMeter,
+    ) -> VMResult<Option<StructDef>> {
+        self.block_cache.resolve_struct_def(module, idx, gas_meter)
+    }
+
+    fn get_loaded_module(&self, id: &ModuleId) -> VMResult<Option<&'alloc LoadedModule>> {
+        self.block_cache.get_loaded_module(id)
+    }
+
+    fn cache_module(&self, module: VerifiedModule) {
+        self.block_cache.cache_module(module)
+    }
+
+    fn reclaim_cached_module(&self, _v: Vec<LoadedModule>) {}
+}
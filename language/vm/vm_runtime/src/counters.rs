@@ -16,6 +16,8 @@ const TXN_EXECUTION_DISCARD: &str = "txn.execution.discard";
 const TXN_VERIFICATION_SUCCESS: &str = "txn.verification.success";
 const TXN_VERIFICATION_FAIL: &str = "txn.verification.fail";
 const TXN_BLOCK_COUNT: &str = "txn.block.count";
+const TXN_SPECULATION_VALID: &str = "txn.speculation.valid";
+const TXN_SPECULATION_REEXECUTED: &str = "txn.speculation.reexecuted";
 
 lazy_static::lazy_static! {
     // the main metric (move_vm)
@@ -44,6 +46,16 @@ pub fn report_execution_status(status: &TransactionStatus) {
     }
 }
 
+/// Reports whether the speculative execution of a transaction could be kept or whether the
+/// transaction had to be re-executed because it read data written earlier in the block.
+pub fn report_speculation_status(valid: bool) {
+    if valid {
+        VM_COUNTERS.inc(TXN_SPECULATION_VALID);
+    } else {
+        VM_COUNTERS.inc(TXN_SPECULATION_REEXECUTED);
+    }
+}
+
 /// Reports the result of a transaction verification.
 ///
 /// Counters are prefixed with `TXN_VERIFICATION_SUCCESS` or `TXN_VERIFICATION_FAIL`.
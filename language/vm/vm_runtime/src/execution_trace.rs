@@ -0,0 +1,75 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+//! Instruction-level tracing of the interpreter, to debug the execution of a transaction without
+//! changing the Move code it runs.
+
+use std::{cell::RefCell, fmt};
+use vm::file_format::{Bytecode, CodeOffset};
+
+/// One instruction executed by the interpreter.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceRecord {
+    /// The function the instruction belongs to, as `Module::function`, or `<unknown>` if there is
+    /// no frame to tell.
+    pub function: String,
+    /// The offset of the instruction in the code of the function.
+    pub offset: CodeOffset,
+    pub instruction: Bytecode,
+    /// The gas charged for the instruction, i.e. all the gas left if it ran out of gas.
+    pub gas: u64,
+    /// The value on top of the value stack when the instruction starts executing, if any, e.g. the
+    /// abort code for an `Abort`.
+    pub stack_top: Option<String>,
+}
+
+impl fmt::Display for TraceRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] {:?} gas: {}",
+            self.function, self.offset, self.instruction, self.gas
+        )?;
+        if let Some(stack_top) = &self.stack_top {
+            write!(f, " stack top: {}", stack_top)?;
+        }
+        Ok(())
+    }
+}
+
+/// A hook the `TransactionExecutor` calls for every instruction it executes, once the gas for the
+/// instruction is charged. The instruction that runs out of gas is reported too, as the last one.
+pub trait InstructionTracer {
+    fn trace_instruction(&self, record: TraceRecord);
+}
+
+/// An `InstructionTracer` keeping the instructions executed for a transaction, in order.
+#[derive(Debug, Default)]
+pub struct ExecutionTrace {
+    records: RefCell<Vec<TraceRecord>>,
+}
+
+impl ExecutionTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_records(self) -> Vec<TraceRecord> {
+        self.records.into_inner()
+    }
+}
+
+impl InstructionTracer for ExecutionTrace {
+    fn trace_instruction(&self, record: TraceRecord) {
+        self.records.borrow_mut().push(record);
+    }
+}
+
+/// One instruction per line.
+impl fmt::Display for ExecutionTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for record in self.records.borrow().iter() {
+            writeln!(f, "{}", record)?;
+        }
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@
 
 use crate::loaded_data::{function::FunctionReference, loaded_module::LoadedModule};
 use std::{fmt, marker::PhantomData};
+use types::identifier::IdentStr;
 use vm::{
     errors::{Location, VMResult},
     file_format::{Bytecode, CodeOffset, LocalIndex},
@@ -45,6 +46,10 @@ where
         self.function.module()
     }
 
+    pub fn function_name(&self) -> &'txn IdentStr {
+        self.function.name()
+    }
+
     pub fn copy_loc(&self, idx: LocalIndex) -> VMResult<Value> {
         self.locals.copy_loc(idx as usize)
     }
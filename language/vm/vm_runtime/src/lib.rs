@@ -115,6 +115,7 @@ mod counters;
 mod frame;
 mod gas_meter;
 mod move_vm;
+mod parallel_block_processor;
 mod process_txn;
 mod runtime;
 #[cfg(test)]
@@ -122,6 +123,7 @@ mod unit_tests;
 
 pub mod code_cache;
 pub mod data_cache;
+pub mod execution_trace;
 pub mod identifier;
 pub mod loaded_data;
 pub mod txn_executor;
@@ -135,6 +137,7 @@ pub use move_vm::MoveVM;
 pub use process_txn::verify::static_verify_program;
 pub use txn_executor::execute_function;
 
+use crate::execution_trace::InstructionTracer;
 use config::config::VMConfig;
 use state_view::StateView;
 use types::{
@@ -169,3 +172,28 @@ pub trait VMExecutor {
         state_view: &dyn StateView,
     ) -> Vec<TransactionOutput>;
 }
+
+pub trait VMSimulator {
+    /// Executes a transaction against the given state without committing it anywhere, e.g. to
+    /// find out how much gas it uses or whether it aborts before submitting it. The signature is
+    /// only checked if `check_signature` is set, so that the transaction doesn't need to be
+    /// signed. Nothing the transaction does is kept, so the VM can be reused for other
+    /// transactions.
+    fn simulate_transaction(
+        &self,
+        transaction: SignedTransaction,
+        check_signature: bool,
+        state_view: &dyn StateView,
+    ) -> TransactionOutput;
+}
+
+pub trait VMTracer {
+    /// Executes a transaction like a block of its own, reporting every instruction the
+    /// interpreter executes for it to `tracer`, e.g. to find out where and why it aborts.
+    fn trace_transaction(
+        transaction: SignedTransaction,
+        config: &VMConfig,
+        state_view: &dyn StateView,
+        tracer: &dyn InstructionTracer,
+    ) -> TransactionOutput;
+}
@@ -1,7 +1,10 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{loaded_data::loaded_module::LoadedModule, runtime::VMRuntime, VMExecutor, VMVerifier};
+use crate::{
+    execution_trace::InstructionTracer, loaded_data::loaded_module::LoadedModule,
+    runtime::VMRuntime, VMExecutor, VMSimulator, VMTracer, VMVerifier,
+};
 use state_view::StateView;
 use std::sync::Arc;
 use types::{
@@ -69,6 +72,33 @@ impl VMExecutor for MoveVM {
     }
 }
 
+impl VMSimulator for MoveVM {
+    fn simulate_transaction(
+        &self,
+        transaction: SignedTransaction,
+        check_signature: bool,
+        state_view: &dyn StateView,
+    ) -> TransactionOutput {
+        self.inner.rent(move |runtime| {
+            runtime.simulate_transaction(transaction, check_signature, state_view)
+        })
+    }
+}
+
+impl VMTracer for MoveVM {
+    fn trace_transaction(
+        transaction: SignedTransaction,
+        config: &VMConfig,
+        state_view: &dyn StateView,
+        tracer: &dyn InstructionTracer,
+    ) -> TransactionOutput {
+        let vm = MoveVMImpl::new(Box::new(Arena::new()), |arena| {
+            VMRuntime::new(&*arena, config)
+        });
+        vm.rent(|runtime| runtime.trace_transaction(transaction, state_view, tracer))
+    }
+}
+
 #[test]
 fn vm_thread_safe() {
     fn assert_send<T: Send>() {}
@@ -0,0 +1,277 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+//! Optimistic execution of the transactions of a block on multiple threads.
+//!
+//! Every transaction is first executed speculatively against the state before the block, while
+//! recording the values it reads. The outputs are then committed in order: a speculative output
+//! is kept if none of the values its transaction read was written by an earlier transaction of
+//! the block, otherwise the transaction is executed again on top of the earlier writes. Since the
+//! output of a transaction only depends on the values it reads, the outputs are the same as the
+//! ones of the sequential execution.
+
+use crate::{
+    block_processor::transaction_flow,
+    code_cache::{
+        module_adapter::ModuleFetcherImpl,
+        module_cache::{BlockModuleCache, VMModuleCache},
+        script_cache::ScriptCache,
+    },
+    counters::{report_execution_status, report_speculation_status},
+    data_cache::{BlockDataCache, RemoteCache},
+    process_txn::{execute::ExecutedTransaction, validate::ValidationMode},
+};
+use config::config::VMPublishingOption;
+use crossbeam::{channel, thread};
+use failure::prelude::*;
+use logger::prelude::*;
+use state_view::StateView;
+use std::{
+    cell::{Cell, RefCell},
+    cmp::min,
+    collections::BTreeMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use types::{
+    access_path::AccessPath,
+    transaction::{SignatureCheckedTransaction, TransactionOutput, TransactionPayload},
+    vm_error::VMStatus,
+};
+use vm::errors::VMResult;
+
+/// The values read by a transaction, `None` if reading some value failed.
+type ReadSet = Option<BTreeMap<AccessPath, Option<Vec<u8>>>>;
+
+/// Whether the transactions can be executed speculatively. The transactions publishing modules
+/// update the code cache that the other transactions load their code from, and the write set
+/// transactions are only allowed in the genesis block: such blocks are executed sequentially.
+pub(crate) fn can_execute_in_parallel(
+    signature_verified_block: &[Result<SignatureCheckedTransaction, VMStatus>],
+) -> bool {
+    signature_verified_block.len() > 1
+        && signature_verified_block.iter().all(|txn| match txn {
+            Ok(txn) => match txn.payload() {
+                TransactionPayload::Program(program) => program.modules().is_empty(),
+                TransactionPayload::Script(_) => true,
+                TransactionPayload::Module(_) | TransactionPayload::WriteSet(_) => false,
+            },
+            // Rejected without being executed.
+            Err(_) => true,
+        })
+}
+
+pub(crate) fn execute_block_in_parallel<'alloc>(
+    signature_verified_block: Vec<Result<SignatureCheckedTransaction, VMStatus>>,
+    code_cache: &VMModuleCache<'alloc>,
+    script_cache: &ScriptCache<'alloc>,
+    data_view: &dyn StateView,
+    publishing_option: &VMPublishingOption,
+) -> Vec<TransactionOutput> {
+    let mut speculative_outputs = execute_speculatively(
+        &signature_verified_block,
+        code_cache,
+        script_cache,
+        data_view,
+        publishing_option,
+    );
+
+    let module_cache = BlockModuleCache::new(code_cache, ModuleFetcherImpl::new(data_view));
+    let mut data_cache = BlockDataCache::new(data_view);
+    let mut result = Vec::with_capacity(signature_verified_block.len());
+    for (index, transaction) in signature_verified_block.into_iter().enumerate() {
+        let output = match transaction {
+            Ok(t) => {
+                let speculative_output = speculative_outputs[index]
+                    .take()
+                    .filter(|(_, read_set)| is_read_set_valid(read_set, &data_cache));
+                report_speculation_status(speculative_output.is_some());
+                match speculative_output {
+                    Some((output, _)) => output,
+                    None => transaction_flow(
+                        t,
+                        &module_cache,
+                        script_cache,
+                        &data_cache,
+                        ValidationMode::Executing,
+                        publishing_option,
+                        None,
+                    ),
+                }
+            }
+            Err(vm_status) => ExecutedTransaction::discard_error_output(vm_status),
+        };
+        report_execution_status(output.status());
+        data_cache.push_write_set(&output.write_set());
+        result.push(output);
+    }
+    trace!("[VM] Execute block in parallel finished");
+    result
+}
+
+/// Executes all the transactions against the state before the block. The state view isn't
+/// shared with the worker threads: the calling thread serves their reads until they are done.
+fn execute_speculatively<'alloc>(
+    signature_verified_block: &[Result<SignatureCheckedTransaction, VMStatus>],
+    code_cache: &VMModuleCache<'alloc>,
+    script_cache: &ScriptCache<'alloc>,
+    data_view: &dyn StateView,
+    publishing_option: &VMPublishingOption,
+) -> Vec<Option<(TransactionOutput, ReadSet)>> {
+    let num_workers = min(rayon::current_num_threads(), signature_verified_block.len());
+    let next_index = AtomicUsize::new(0);
+    let (request_tx, request_rx) = channel::unbounded::<ReadRequest>();
+
+    let worker_outputs = thread::scope(|s| {
+        let workers: Vec<_> = (0..num_workers)
+            .map(|_| {
+                let state_view = ForwardingStateView {
+                    request_tx: request_tx.clone(),
+                    is_genesis: data_view.is_genesis(),
+                };
+                let next_index = &next_index;
+                s.spawn(move |_| {
+                    let module_cache =
+                        BlockModuleCache::new(code_cache, ModuleFetcherImpl::new(&state_view));
+                    let mut outputs = vec![];
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::SeqCst);
+                        let txn = match signature_verified_block.get(index) {
+                            Some(Ok(txn)) => txn.clone(),
+                            Some(Err(_)) => continue,
+                            None => break,
+                        };
+                        let read_set_recorder = ReadSetRecorder::new(&state_view);
+                        let output = transaction_flow(
+                            txn,
+                            &module_cache,
+                            script_cache,
+                            &read_set_recorder,
+                            ValidationMode::Executing,
+                            publishing_option,
+                            None,
+                        );
+                        outputs.push((index, output, read_set_recorder.into_read_set()));
+                    }
+                    outputs
+                })
+            })
+            .collect();
+
+        // The requests stop coming once all the workers are done and their senders are dropped.
+        drop(request_tx);
+        for request in request_rx.iter() {
+            // The worker may be gone if it panicked, its output is lost anyways.
+            let _ = request
+                .response_tx
+                .send(data_view.get(&request.access_path));
+        }
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .expect("[VM] Speculative execution thread panicked")
+            })
+            .collect::<Vec<_>>()
+    })
+    .expect("[VM] Speculative execution thread panicked");
+
+    let mut speculative_outputs: Vec<_> = signature_verified_block.iter().map(|_| None).collect();
+    for (index, output, read_set) in worker_outputs.into_iter().flatten() {
+        speculative_outputs[index] = Some((output, read_set));
+    }
+    speculative_outputs
+}
+
+/// Whether the values read during a speculative execution are still the ones in the block data
+/// cache.
+fn is_read_set_valid(read_set: &ReadSet, data_cache: &BlockDataCache<'_>) -> bool {
+    match read_set {
+        Some(read_set) => {
+            read_set
+                .iter()
+                .all(|(access_path, value)| match data_cache.get(access_path) {
+                    Ok(current_value) => current_value == *value,
+                    Err(_) => false,
+                })
+        }
+        None => false,
+    }
+}
+
+struct ReadRequest {
+    access_path: AccessPath,
+    response_tx: channel::Sender<Result<Option<Vec<u8>>>>,
+}
+
+/// The state view of a worker thread, forwarding its reads to the thread owning the actual
+/// state view.
+struct ForwardingStateView {
+    request_tx: channel::Sender<ReadRequest>,
+    is_genesis: bool,
+}
+
+impl StateView for ForwardingStateView {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        let (response_tx, response_rx) = channel::bounded(1);
+        self.request_tx
+            .send(ReadRequest {
+                access_path: access_path.clone(),
+                response_tx,
+            })
+            .map_err(|_| format_err!("The state view is gone"))?;
+        response_rx
+            .recv()
+            .map_err(|_| format_err!("The state view is gone"))?
+    }
+
+    fn multi_get(&self, access_paths: &[AccessPath]) -> Result<Vec<Option<Vec<u8>>>> {
+        access_paths
+            .iter()
+            .map(|access_path| self.get(access_path))
+            .collect()
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.is_genesis
+    }
+}
+
+/// Reads the state before the block for a speculative execution and records the values read.
+struct ReadSetRecorder<'a> {
+    data_cache: BlockDataCache<'a>,
+    read_set: RefCell<BTreeMap<AccessPath, Option<Vec<u8>>>>,
+    read_failed: Cell<bool>,
+}
+
+impl<'a> ReadSetRecorder<'a> {
+    fn new(data_view: &'a dyn StateView) -> Self {
+        Self {
+            data_cache: BlockDataCache::new(data_view),
+            read_set: RefCell::new(BTreeMap::new()),
+            read_failed: Cell::new(false),
+        }
+    }
+
+    fn into_read_set(self) -> ReadSet {
+        if self.read_failed.get() {
+            None
+        } else {
+            Some(self.read_set.into_inner())
+        }
+    }
+}
+
+impl<'a> RemoteCache for ReadSetRecorder<'a> {
+    fn get(&self, access_path: &AccessPath) -> VMResult<Option<Vec<u8>>> {
+        let value = self.data_cache.get(access_path);
+        match &value {
+            Ok(value) => {
+                self.read_set
+                    .borrow_mut()
+                    .insert(access_path.clone(), value.clone());
+            }
+            Err(_) => self.read_failed.set(true),
+        }
+        value
+    }
+}
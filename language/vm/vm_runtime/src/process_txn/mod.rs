@@ -1,10 +1,10 @@
 use crate::{
     code_cache::module_cache::ModuleCache, data_cache::RemoteCache,
-    loaded_data::loaded_module::LoadedModule,
+    execution_trace::InstructionTracer, loaded_data::loaded_module::LoadedModule,
 };
 use config::config::VMPublishingOption;
 use std::marker::PhantomData;
-use types::transaction::SignatureCheckedTransaction;
+use types::transaction::{SignatureCheckedTransaction, SignedTransaction};
 use vm::errors::VMResult;
 use vm_cache_map::Arena;
 
@@ -14,6 +14,29 @@ pub mod verify;
 
 use validate::{ValidatedTransaction, ValidationMode};
 
+/// A transaction the VM is allowed to process. Its signature must have been checked, unless it's
+/// only simulated: the output of a simulated transaction is never committed.
+pub enum TransactionToProcess {
+    SignatureChecked(SignatureCheckedTransaction),
+    Simulated(SignedTransaction),
+}
+
+impl TransactionToProcess {
+    /// Returns the `SignedTransaction` within.
+    pub fn into_inner(self) -> SignedTransaction {
+        match self {
+            TransactionToProcess::SignatureChecked(txn) => txn.into_inner(),
+            TransactionToProcess::Simulated(txn) => txn,
+        }
+    }
+}
+
+impl From<SignatureCheckedTransaction> for TransactionToProcess {
+    fn from(txn: SignatureCheckedTransaction) -> Self {
+        TransactionToProcess::SignatureChecked(txn)
+    }
+}
+
 /// The starting point for processing a transaction. All the different states involved are described
 /// through the types present in submodules.
 pub struct ProcessTransaction<'alloc, 'txn, P>
@@ -21,10 +44,11 @@ where
     'alloc: 'txn,
     P: ModuleCache<'alloc>,
 {
-    txn: SignatureCheckedTransaction,
+    txn: SignedTransaction,
     module_cache: P,
     data_cache: &'txn dyn RemoteCache,
     allocator: &'txn Arena<LoadedModule>,
+    tracer: Option<&'txn dyn InstructionTracer>,
     phantom: PhantomData<&'alloc ()>,
 }
 
@@ -35,20 +59,28 @@ where
 {
     /// Creates a new instance of `ProcessTransaction`.
     pub fn new(
-        txn: SignatureCheckedTransaction,
+        txn: impl Into<TransactionToProcess>,
         module_cache: P,
         data_cache: &'txn dyn RemoteCache,
         allocator: &'txn Arena<LoadedModule>,
     ) -> Self {
         Self {
-            txn,
+            txn: txn.into().into_inner(),
             module_cache,
             data_cache,
             allocator,
+            tracer: None,
             phantom: PhantomData,
         }
     }
 
+    /// Reports every instruction executed for this transaction, including the prologue and the
+    /// epilogue, to `tracer`.
+    pub fn with_tracer(mut self, tracer: &'txn dyn InstructionTracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
     /// Validates this transaction. Returns a `ValidatedTransaction` on success or `VMStatus` on
     /// failure.
     pub fn validate(
@@ -4,6 +4,7 @@ use crate::{
         script_cache::ScriptCache,
     },
     data_cache::RemoteCache,
+    execution_trace::InstructionTracer,
     loaded_data::loaded_module::LoadedModule,
     process_txn::{verify::VerifiedTransaction, ProcessTransaction},
     txn_executor::TransactionExecutor,
@@ -12,7 +13,7 @@ use config::config::VMPublishingOption;
 use crypto::HashValue;
 use logger::prelude::*;
 use types::{
-    transaction::{SignatureCheckedTransaction, TransactionPayload, MAX_TRANSACTION_SIZE_IN_BYTES},
+    transaction::{SignedTransaction, TransactionPayload, MAX_TRANSACTION_SIZE_IN_BYTES},
     vm_error::{StatusCode, VMStatus},
 };
 use vm::{
@@ -39,7 +40,7 @@ where
     'alloc: 'txn,
     P: ModuleCache<'alloc>,
 {
-    txn: SignatureCheckedTransaction,
+    txn: SignedTransaction,
     txn_state: Option<ValidatedTransactionState<'alloc, 'txn, P>>,
 }
 
@@ -75,6 +76,7 @@ where
             module_cache,
             data_cache,
             allocator,
+            tracer,
             ..
         } = process_txn;
 
@@ -85,6 +87,7 @@ where
                     module_cache,
                     data_cache,
                     allocator,
+                    tracer,
                     mode,
                     || {
                         // Verify against whitelist if we are locked. Otherwise allow.
@@ -110,6 +113,7 @@ where
                     module_cache,
                     data_cache,
                     allocator,
+                    tracer,
                     mode,
                     || {
                         // Verify against whitelist if we are locked. Otherwise allow.
@@ -128,6 +132,7 @@ where
                     module_cache,
                     data_cache,
                     allocator,
+                    tracer,
                     mode,
                     || {
                         if !publishing_option.is_open() {
@@ -172,14 +177,14 @@ where
         VerifiedTransaction::new(self, script_cache)
     }
 
-    /// Returns a reference to the `SignatureCheckedTransaction` within.
-    pub fn as_inner(&self) -> &SignatureCheckedTransaction {
+    /// Returns a reference to the `SignedTransaction` within.
+    pub fn as_inner(&self) -> &SignedTransaction {
         &self.txn
     }
 
-    /// Consumes `self` and returns the `SignatureCheckedTransaction` within.
+    /// Consumes `self` and returns the `SignedTransaction` within.
     #[allow(dead_code)]
-    pub fn into_inner(self) -> SignatureCheckedTransaction {
+    pub fn into_inner(self) -> SignedTransaction {
         self.txn
     }
 
@@ -189,10 +194,11 @@ where
     }
 
     fn validate(
-        txn: &SignatureCheckedTransaction,
+        txn: &SignedTransaction,
         module_cache: P,
         data_cache: &'txn dyn RemoteCache,
         allocator: &'txn Arena<LoadedModule>,
+        tracer: Option<&'txn dyn InstructionTracer>,
         mode: ValidationMode,
         payload_check: impl Fn() -> Result<(), VMStatus>,
     ) -> Result<ValidatedTransactionState<'alloc, 'txn, P>, VMStatus> {
@@ -302,6 +308,9 @@ where
         let metadata = TransactionMetadata::new(&txn);
         let mut txn_state =
             ValidatedTransactionState::new(metadata, module_cache, data_cache, allocator);
+        if let Some(tracer) = tracer {
+            txn_state.txn_executor.set_tracer(tracer);
+        }
 
         // Run the prologue to ensure that clients have enough gas and aren't tricking us by
         // sending us garbage.
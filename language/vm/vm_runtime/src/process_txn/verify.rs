@@ -12,8 +12,7 @@ use logger::prelude::*;
 use types::{
     account_address::AccountAddress,
     transaction::{
-        Module, Program, Script, SignatureCheckedTransaction, TransactionArgument,
-        TransactionPayload,
+        Module, Program, Script, SignedTransaction, TransactionArgument, TransactionPayload,
     },
     vm_error::{StatusCode, VMStatus},
 };
@@ -31,7 +30,7 @@ where
     'alloc: 'txn,
     P: ModuleCache<'alloc>,
 {
-    txn: SignatureCheckedTransaction,
+    txn: SignedTransaction,
     #[allow(dead_code)]
     txn_state: Option<VerifiedTransactionState<'alloc, 'txn, P>>,
 }
@@ -204,14 +203,14 @@ where
         self.txn_state.take()
     }
 
-    /// Returns a reference to the `SignatureCheckedTransaction` within.
+    /// Returns a reference to the `SignedTransaction` within.
     #[allow(dead_code)]
-    pub fn as_inner(&self) -> &SignatureCheckedTransaction {
+    pub fn as_inner(&self) -> &SignedTransaction {
         &self.txn
     }
 
-    /// Consumes `self` and returns the `SignatureCheckedTransaction` within.
-    pub fn into_inner(self) -> SignatureCheckedTransaction {
+    /// Consumes `self` and returns the `SignedTransaction` within.
+    pub fn into_inner(self) -> SignedTransaction {
         self.txn
     }
 }
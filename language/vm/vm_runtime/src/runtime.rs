@@ -2,16 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    block_processor::execute_block,
+    block_processor::{execute_block, transaction_flow},
     code_cache::{
         module_adapter::ModuleFetcherImpl,
-        module_cache::{BlockModuleCache, VMModuleCache},
+        module_cache::{BlockModuleCache, ModuleCache, SimulationModuleCache, VMModuleCache},
         script_cache::ScriptCache,
     },
     counters::report_verification_status,
     data_cache::BlockDataCache,
+    execution_trace::InstructionTracer,
     loaded_data::loaded_module::LoadedModule,
-    process_txn::{validate::ValidationMode, ProcessTransaction},
+    process_txn::{
+        execute::ExecutedTransaction, validate::ValidationMode, ProcessTransaction,
+        TransactionToProcess,
+    },
 };
 use config::config::{VMConfig, VMPublishingOption};
 use logger::prelude::*;
@@ -30,10 +34,13 @@ use vm_cache_map::Arena;
 ///   in the whitelist, the VM will just reject it in `verify_transaction`.
 /// * Custom scripts, which will allow arbitrary valid scripts, but no module publishing
 /// * Open script and module publishing
+/// `parallel_execution` is whether the transactions of a block may be executed speculatively on
+/// multiple threads.
 pub struct VMRuntime<'alloc> {
     code_cache: VMModuleCache<'alloc>,
     script_cache: ScriptCache<'alloc>,
     publishing_option: VMPublishingOption,
+    parallel_execution: bool,
 }
 
 impl<'alloc> VMRuntime<'alloc> {
@@ -44,6 +51,7 @@ impl<'alloc> VMRuntime<'alloc> {
             code_cache: VMModuleCache::new(allocator),
             script_cache: ScriptCache::new(allocator),
             publishing_option: config.publishing_options.clone(),
+            parallel_execution: config.parallel_execution,
         }
     }
 
@@ -118,6 +126,85 @@ impl<'alloc> VMRuntime<'alloc> {
             &self.script_cache,
             data_view,
             &self.publishing_option,
+            self.parallel_execution,
+        )
+    }
+
+    /// Execute a single transaction as if it was the only one of a block, and return its output.
+    /// The signature isn't checked unless `check_signature` is set. Neither the output nor the
+    /// modules published by the transaction are kept, so the runtime and the modules it has
+    /// loaded can be reused for other transactions.
+    pub fn simulate_transaction(
+        &self,
+        txn: SignedTransaction,
+        check_signature: bool,
+        data_view: &dyn StateView,
+    ) -> TransactionOutput {
+        trace!("[VM] Simulate transaction: {:?}", txn);
+        let txn = if check_signature {
+            match txn.check_signature() {
+                Ok(t) => t.into(),
+                Err(_) => {
+                    return ExecutedTransaction::discard_error_output(VMStatus::new(
+                        StatusCode::INVALID_SIGNATURE,
+                    ))
+                }
+            }
+        } else {
+            TransactionToProcess::Simulated(txn)
+        };
+        let module_cache = SimulationModuleCache::new(BlockModuleCache::new(
+            &self.code_cache,
+            ModuleFetcherImpl::new(data_view),
+        ));
+        self.execute_single_transaction(txn, module_cache, data_view, None)
+    }
+
+    /// Execute a single transaction as if it was the only one of a block, and return its output.
+    /// Every instruction executed for the transaction is reported to `tracer`. Modules published
+    /// by the transaction end up in the code cache: the runtime shouldn't be reused for other
+    /// transactions.
+    pub fn trace_transaction(
+        &self,
+        txn: SignedTransaction,
+        data_view: &dyn StateView,
+        tracer: &dyn InstructionTracer,
+    ) -> TransactionOutput {
+        trace!("[VM] Trace transaction: {:?}", txn);
+        let txn = match txn.check_signature() {
+            Ok(t) => t,
+            Err(_) => {
+                return ExecutedTransaction::discard_error_output(VMStatus::new(
+                    StatusCode::INVALID_SIGNATURE,
+                ))
+            }
+        };
+        let module_cache =
+            BlockModuleCache::new(&self.code_cache, ModuleFetcherImpl::new(data_view));
+        self.execute_single_transaction(txn.into(), module_cache, data_view, Some(tracer))
+    }
+
+    fn execute_single_transaction<P: ModuleCache<'alloc>>(
+        &self,
+        txn: TransactionToProcess,
+        module_cache: P,
+        data_view: &dyn StateView,
+        tracer: Option<&dyn InstructionTracer>,
+    ) -> TransactionOutput {
+        let data_cache = BlockDataCache::new(data_view);
+        let mode = if data_view.is_genesis() {
+            ValidationMode::Genesis
+        } else {
+            ValidationMode::Executing
+        };
+        transaction_flow(
+            txn,
+            &module_cache,
+            &self.script_cache,
+            &data_cache,
+            mode,
+            &self.publishing_option,
+            tracer,
         )
     }
 }
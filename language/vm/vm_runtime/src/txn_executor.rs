@@ -6,6 +6,7 @@ use crate::{
     code_cache::module_cache::{ModuleCache, VMModuleCache},
     data_cache::{RemoteCache, TransactionDataCache},
     execution_stack::ExecutionStack,
+    execution_trace::{InstructionTracer, TraceRecord},
     gas_meter::GasMeter,
     identifier::{create_access_path, resource_storage_key},
     loaded_data::{
@@ -90,6 +91,7 @@ fn make_access_path(
 /// `txn_data` contains the information of this transaction, such as sender, sequence number, etc.
 /// `event_data` is the vector that stores all events emitted during execution.
 /// `data_view` is the scratchpad for the local writes emitted by this transaction.
+/// `tracer` is called for every instruction executed, if set.
 pub struct TransactionExecutor<'alloc, 'txn, P>
 where
     'alloc: 'txn,
@@ -104,6 +106,7 @@ where
     txn_data: TransactionMetadata,
     event_data: Vec<ContractEvent>,
     data_view: TransactionDataCache<'txn>,
+    tracer: Option<&'txn dyn InstructionTracer>,
 }
 
 impl<'alloc, 'txn, P> TransactionExecutor<'alloc, 'txn, P>
@@ -126,9 +129,15 @@ where
             txn_data,
             event_data: Vec::new(),
             data_view: TransactionDataCache::new(data_cache),
+            tracer: None,
         }
     }
 
+    /// Reports every instruction executed from now on to `tracer`.
+    pub fn set_tracer(&mut self, tracer: &'txn dyn InstructionTracer) {
+        self.tracer = Some(tracer);
+    }
+
     /// Returns the module cache for this executor.
     pub fn module_cache(&self) -> &P {
         &self.execution_stack.module_cache
@@ -182,13 +191,24 @@ where
     ) -> VMResult<CodeOffset> {
         let mut pc = beginning_offset;
         for instruction in &code[beginning_offset as usize..] {
+            let gas_before = self.gas_meter.remaining_gas();
             // FIXME: Once we add in memory ops, we will need to pass in the current memory size to
             // this function.
-            self.gas_meter.calculate_and_consume(
+            let charged = self.gas_meter.calculate_and_consume(
                 &instruction,
                 &self.execution_stack,
                 AbstractMemorySize::new(1),
-            )?;
+            );
+            // Traced even if charging failed, so that the trace ends with the instruction that
+            // ran out of gas.
+            if let Some(tracer) = self.tracer {
+                tracer.trace_instruction(self.trace_record(
+                    pc,
+                    instruction,
+                    gas_before.sub(self.gas_meter.remaining_gas()).get(),
+                ));
+            }
+            charged?;
 
             match instruction {
                 Bytecode::Pop => {
@@ -547,6 +567,29 @@ where
         }
     }
 
+    /// Tracing never fails the execution: what can't be described is left out of the record.
+    fn trace_record(&self, offset: CodeOffset, instruction: &Bytecode, gas: u64) -> TraceRecord {
+        let function = match self.execution_stack.top_frame() {
+            Ok(top_frame) => format!(
+                "{}::{}",
+                top_frame.module().self_id().name(),
+                top_frame.function_name()
+            ),
+            Err(_) => "<unknown>".to_string(),
+        };
+        TraceRecord {
+            function,
+            offset,
+            instruction: instruction.clone(),
+            gas,
+            stack_top: self
+                .execution_stack
+                .peek()
+                .ok()
+                .map(|value| format!("{:?}", value)),
+        }
+    }
+
     /// Convert the transaction arguments into move values and push them to the top of the stack.
     pub(crate) fn setup_main_args(&mut self, args: Vec<TransactionArgument>) {
         for arg in args.into_iter() {
@@ -814,6 +857,7 @@ pub fn execute_function(
         txn_data: txn_metadata,
         event_data: Vec::new(),
         data_view: TransactionDataCache::new(data_cache),
+        tracer: None,
     };
     vm.execute_function_impl(entry_func)
 }
@@ -5,6 +5,7 @@ use super::*;
 use crate::{
     code_cache::module_cache::{ModuleCache, VMModuleCache},
     data_cache::RemoteCache,
+    execution_trace::{ExecutionTrace, TraceRecord},
     loaded_data::{
         function::{FunctionRef, FunctionReference},
         loaded_module::LoadedModule,
@@ -22,10 +23,11 @@ use vm::{
     access::ModuleAccess,
     errors::VMResult,
     file_format::{
-        AddressPoolIndex, Bytecode, CodeUnit, CompiledModuleMut, CompiledScript, CompiledScriptMut,
-        FunctionDefinition, FunctionHandle, FunctionHandleIndex, FunctionSignature,
-        FunctionSignatureIndex, IdentifierIndex, LocalsSignature, LocalsSignatureIndex,
-        ModuleHandle, ModuleHandleIndex, SignatureToken, UserStringIndex, NO_TYPE_ACTUALS,
+        AddressPoolIndex, Bytecode, CodeOffset, CodeUnit, CompiledModuleMut, CompiledScript,
+        CompiledScriptMut, FunctionDefinition, FunctionHandle, FunctionHandleIndex,
+        FunctionSignature, FunctionSignatureIndex, IdentifierIndex, LocalsSignature,
+        LocalsSignatureIndex, ModuleHandle, ModuleHandleIndex, SignatureToken, UserStringIndex,
+        NO_TYPE_ACTUALS,
     },
     gas_schedule::{AbstractMemorySize, GasAlgebra, GasPrice, GasUnits},
     transaction_metadata::TransactionMetadata,
@@ -809,3 +811,83 @@ fn test_transaction_info() {
         1,
     );
 }
+
+/// Executes `code` in the main function of a fake script, tracing the instructions.
+fn trace_block(
+    code: &[Bytecode],
+    txn_data: TransactionMetadata,
+) -> (VMResult<CodeOffset>, Vec<TraceRecord>) {
+    let trace = ExecutionTrace::new();
+    let allocator = Arena::new();
+    let module_cache = VMModuleCache::new(&allocator);
+    let main_module = fake_script().into_module();
+    let loaded_main = LoadedModule::new(main_module);
+    let entry_func = FunctionRef::new(&loaded_main, CompiledScript::MAIN_INDEX);
+    let data_cache = FakeDataCache::new();
+    let mut vm = TransactionExecutor::new(module_cache, &data_cache, txn_data);
+    vm.set_tracer(&trace);
+    vm.execution_stack
+        .push_frame(entry_func)
+        .expect("push to empty execution stack should succeed");
+
+    let result = vm.execute_block(code, 0);
+    drop(vm);
+    (result, trace.into_records())
+}
+
+#[test]
+fn test_instruction_tracing() {
+    let code = vec![
+        Bytecode::LdConst(1),
+        Bytecode::LdConst(2),
+        Bytecode::Add,
+        Bytecode::Pop,
+    ];
+    let (result, records) = trace_block(&code, TransactionMetadata::default());
+    assert_eq!(result.unwrap(), 4);
+
+    assert_eq!(records.len(), code.len());
+    for (offset, (record, instruction)) in records.iter().zip(&code).enumerate() {
+        assert_eq!(record.function, "hello::hello");
+        assert_eq!(record.offset as usize, offset);
+        assert_eq!(&record.instruction, instruction);
+        assert!(record.gas > 0);
+    }
+    let stack_tops: Vec<_> = records.into_iter().map(|record| record.stack_top).collect();
+    assert_eq!(
+        stack_tops,
+        vec![
+            None,
+            Some(format!("{:?}", Value::u64(1))),
+            Some(format!("{:?}", Value::u64(2))),
+            Some(format!("{:?}", Value::u64(3))),
+        ]
+    );
+}
+
+#[test]
+fn test_instruction_tracing_out_of_gas() {
+    let code = vec![
+        Bytecode::LdConst(1),
+        Bytecode::LdConst(2),
+        Bytecode::Add,
+        Bytecode::Pop,
+    ];
+    let (_, records) = trace_block(&code, TransactionMetadata::default());
+    // Just not enough gas for the addition.
+    let max_gas_amount = records[..3].iter().map(|record| record.gas).sum::<u64>() - 1;
+    let txn_data = TransactionMetadata {
+        max_gas_amount: GasUnits::new(max_gas_amount),
+        ..TransactionMetadata::default()
+    };
+
+    let (result, records) = trace_block(&code, txn_data);
+    assert_eq!(result.unwrap_err().major_status, StatusCode::OUT_OF_GAS);
+    // The trace ends with the instruction that ran out of gas, charged all the gas left.
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[2].instruction, Bytecode::Add);
+    assert_eq!(
+        records.iter().map(|record| record.gas).sum::<u64>(),
+        max_gas_amount
+    );
+}
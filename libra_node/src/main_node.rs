@@ -6,7 +6,10 @@ use admission_control_proto::proto::admission_control_grpc::{
 };
 use admission_control_service::admission_control_service::AdmissionControlService;
 use config::config::{NetworkConfig, NodeConfig, RoleType};
-use consensus::consensus_provider::{make_consensus_provider, ConsensusProvider};
+use consensus::{
+    consensus_provider::{make_consensus_provider, ConsensusProvider},
+    ConsensusObserver, ConsensusPublisher,
+};
 use crypto::{ed25519::*, ValidKey};
 use debug_interface::{node_debug_service::NodeDebugService, proto::node_debug_interface_grpc};
 use executor::Executor;
@@ -20,8 +23,8 @@ use metrics::metric_server;
 use network::{
     validator_network::{
         network_builder::{NetworkBuilder, TransportType},
-        LibraNetworkProvider, CONSENSUS_DIRECT_SEND_PROTOCOL, CONSENSUS_RPC_PROTOCOL,
-        MEMPOOL_DIRECT_SEND_PROTOCOL, STATE_SYNCHRONIZER_MSG_PROTOCOL,
+        LibraNetworkProvider, CONSENSUS_DIRECT_SEND_PROTOCOL, CONSENSUS_OBSERVER_MSG_PROTOCOL,
+        CONSENSUS_RPC_PROTOCOL, MEMPOOL_DIRECT_SEND_PROTOCOL, STATE_SYNCHRONIZER_MSG_PROTOCOL,
     },
     NetworkPublicKeys, ProtocolId,
 };
@@ -45,6 +48,7 @@ pub struct LibraHandle {
     _ac: ServerHandle,
     _mempool: Option<MempoolRuntime>,
     _state_synchronizer: StateSynchronizer,
+    _consensus_observer: Option<ConsensusObserver>,
     _network_runtimes: Vec<Runtime>,
     consensus: Option<Box<dyn ConsensusProvider>>,
     _storage: ServerHandle,
@@ -95,6 +99,7 @@ fn setup_ac(config: &NodeConfig) -> (::grpcio::Server, AdmissionControlClient) {
         config
             .admission_control
             .need_to_check_mempool_before_validation,
+        config.admission_control.enable_simulate_transaction,
     );
     let service = create_admission_control(handle);
     let server = ServerBuilder::new(Arc::clone(&env))
@@ -167,6 +172,7 @@ pub fn setup_network(
             ProtocolId::from_static(CONSENSUS_DIRECT_SEND_PROTOCOL),
             ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL),
             ProtocolId::from_static(STATE_SYNCHRONIZER_MSG_PROTOCOL),
+            ProtocolId::from_static(CONSENSUS_OBSERVER_MSG_PROTOCOL),
         ])
         .rpc_protocols(vec![ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL)]);
     if config.is_permissioned {
@@ -244,6 +250,9 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
     debug!("Executor setup in {} ms", instant.elapsed().as_millis());
     let mut network_runtimes = vec![];
     let mut state_sync_network_handles = vec![];
+    // The networks with full nodes: validators push their committed blocks there, and full nodes
+    // receive the blocks from their upstream peers.
+    let mut consensus_observer_network_handles = vec![];
     let mut validator_network_provider = None;
 
     for mut network in &mut node_config.networks {
@@ -255,6 +264,9 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
         if let RoleType::Validator = (&network.role).into() {
             validator_network_provider = Some((peer_id, runtime, network_provider));
         } else {
+            consensus_observer_network_handles.push(network_provider.add_consensus_observer(vec![
+                ProtocolId::from_static(CONSENSUS_OBSERVER_MSG_PROTOCOL),
+            ]));
             // For non-validator roles, the peer_id should be derived from the network identity
             // key.
             assert_eq!(
@@ -289,6 +301,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
     );
     let mut mempool = None;
     let mut consensus = None;
+    let mut consensus_observer = None;
     if let Some((peer_id, runtime, mut network_provider)) = validator_network_provider {
         // Note: We need to start network provider before consensus, because the consensus
         // initialization is blocked on state synchronizer to sync to the initial root ledger
@@ -323,18 +336,36 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
 
         // Initialize and start consensus.
         instant = Instant::now();
+        let consensus_publisher = if consensus_observer_network_handles.is_empty() {
+            None
+        } else {
+            Some(ConsensusPublisher::bootstrap(
+                consensus_observer_network_handles,
+                &node_config,
+            ))
+        };
         let mut consensus_provider = make_consensus_provider(
             node_config,
             consensus_network_sender,
             consensus_network_events,
             executor,
             state_synchronizer.create_client(),
+            consensus_publisher,
         );
         consensus_provider
             .start()
             .expect("Failed to start consensus. Can't proceed.");
         consensus = Some(consensus_provider);
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
+    } else {
+        // Full nodes commit the blocks pushed by their upstream validators, and fall back to state
+        // sync for the blocks they miss.
+        consensus_observer = Some(ConsensusObserver::bootstrap(
+            consensus_observer_network_handles,
+            executor,
+            state_synchronizer.create_client(),
+            &node_config,
+        ));
     }
 
     // Initialize and start AC.
@@ -348,6 +379,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
         _ac: ac,
         _mempool: mempool,
         _state_synchronizer: state_synchronizer,
+        _consensus_observer: consensus_observer,
         consensus,
         _storage: storage,
         _debug: debug_if,
@@ -228,6 +228,12 @@ impl Mempool {
         block
     }
 
+    /// Number of transactions that are ready for consensus, including the ones that were sent to
+    /// Consensus but were not committed yet
+    pub(crate) fn num_ready_transactions(&self) -> usize {
+        self.transactions.num_ready_transactions()
+    }
+
     /// TTL based garbage collection. Remove all transactions that got expired
     pub(crate) fn gc_by_system_ttl(&mut self) {
         self.transactions.gc_by_system_ttl();
@@ -322,4 +322,9 @@ impl TransactionStore {
     pub(crate) fn iter_queue(&self) -> PriorityQueueIter {
         self.priority_index.iter()
     }
+
+    /// number of transactions that can be included in the next blocks
+    pub(crate) fn num_ready_transactions(&self) -> usize {
+        self.priority_index.size()
+    }
 }
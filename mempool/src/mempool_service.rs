@@ -91,11 +91,17 @@ impl Mempool for MempoolService {
             .map(|(address, seq)| (address.unwrap(), seq))
             .collect();
 
-        let mut txns = self
-            .core_mempool
-            .lock()
-            .expect("[get_block] acquire mempool lock")
-            .get_block(block_size, exclude_transactions);
+        let num_excluded = exclude_transactions.len();
+        let (mut txns, num_ready) = {
+            let mut core_mempool = self
+                .core_mempool
+                .lock()
+                .expect("[get_block] acquire mempool lock");
+            let txns = core_mempool.get_block(block_size, exclude_transactions);
+            (txns, core_mempool.num_ready_transactions())
+        };
+        // The excluded transactions are pending in consensus, they are mostly still ready here.
+        let remaining_txns = num_ready.saturating_sub(num_excluded + txns.len());
 
         let transactions = txns.drain(..).map(SignedTransaction::into_proto).collect();
 
@@ -104,6 +110,7 @@ impl Mempool for MempoolService {
         OP_COUNTERS.inc_by("get_block.returned", block.get_transactions().len());
         let mut response = crate::proto::mempool::GetBlockResponse::new();
         response.set_block(block);
+        response.set_remaining_txns(remaining_txns as u64);
         ctx.spawn(sink.success(response).map_err(default_reply_error_logger));
         SVC_COUNTERS.resp(&ctx, true);
     }
@@ -97,6 +97,42 @@ fn test_get_block() {
     assert_eq!(block.get_transactions()[0], *req.get_signed_txn(),);
 }
 
+#[test]
+fn test_get_block_remaining_txns() {
+    let (server, client) = setup_mempool();
+    let _handle = ServerHandle::setup(server);
+
+    for _ in 0..3 {
+        client
+            .add_transaction_with_validation(&create_add_transaction_request(0))
+            .unwrap();
+    }
+
+    let mut req = GetBlockRequest::new();
+    req.set_max_block_size(2);
+    let response = client.get_block(&req).unwrap();
+    assert_eq!(response.get_block().get_transactions().len(), 2);
+    assert_eq!(response.get_remaining_txns(), 1);
+
+    // The transactions pending in consensus aren't counted as remaining.
+    let exclusions = response
+        .get_block()
+        .get_transactions()
+        .iter()
+        .map(|proto_txn| {
+            let txn = SignedTransaction::from_proto(proto_txn.clone()).unwrap();
+            let mut exclusion = TransactionExclusion::new();
+            exclusion.set_sender(txn.sender().as_ref().to_vec());
+            exclusion.set_sequence_number(txn.sequence_number());
+            exclusion
+        })
+        .collect();
+    req.set_transactions(::protobuf::RepeatedField::from_vec(exclusions));
+    let response = client.get_block(&req).unwrap();
+    assert_eq!(response.get_block().get_transactions().len(), 1);
+    assert_eq!(response.get_remaining_txns(), 0);
+}
+
 #[test]
 fn test_consensus_callbacks() {
     let (server, client) = setup_mempool();
@@ -5,6 +5,7 @@
 fn main() {
     let proto_files = [
         "src/proto/consensus.proto",
+        "src/proto/consensus_observer.proto",
         "src/proto/network.proto",
         "src/proto/mempool.proto",
         "src/proto/state_synchronizer.proto",
@@ -70,6 +70,9 @@ lazy_static::lazy_static! {
     /// Counter of pending network events to Consensus
     pub static ref PENDING_STATE_SYNCHRONIZER_NETWORK_EVENTS: IntGauge = OP_COUNTERS.gauge("pending_state_sync_network_events");
 
+    /// Counter of pending network events to the Consensus Observer
+    pub static ref PENDING_CONSENSUS_OBSERVER_NETWORK_EVENTS: IntGauge = OP_COUNTERS.gauge("pending_consensus_observer_network_events");
+
     /// Counter of pending requests in Peer Manager
     pub static ref PENDING_PEER_MANAGER_REQUESTS: IntGauge = OP_COUNTERS.gauge("pending_peer_manager_requests");
 
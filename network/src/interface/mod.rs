@@ -21,7 +21,8 @@ use crate::{
         rpc::{InboundRpcRequest, OutboundRpcRequest, RpcNotification, RpcRequest},
     },
     validator_network::{
-        ConsensusNetworkEvents, ConsensusNetworkSender, MempoolNetworkEvents, MempoolNetworkSender,
+        ConsensusNetworkEvents, ConsensusNetworkSender, ConsensusObserverEvents,
+        ConsensusObserverSender, MempoolNetworkEvents, MempoolNetworkSender,
         StateSynchronizerEvents, StateSynchronizerSender,
     },
     ProtocolId,
@@ -35,6 +36,7 @@ use types::PeerId;
 pub const CONSENSUS_INBOUND_MSG_TIMEOUT_MS: u64 = 60 * 1000; // 1 minute
 pub const MEMPOOL_INBOUND_MSG_TIMEOUT_MS: u64 = 60 * 1000; // 1 minute
 pub const STATE_SYNCHRONIZER_INBOUND_MSG_TIMEOUT_MS: u64 = 60 * 1000; // 1 minute
+pub const CONSENSUS_OBSERVER_INBOUND_MSG_TIMEOUT_MS: u64 = 60 * 1000; // 1 minute
 
 /// Requests [`NetworkProvider`] receives from the network interface.
 #[derive(Debug)]
@@ -76,6 +78,10 @@ pub trait LibraNetworkProvider {
         &mut self,
         state_sync_protocols: Vec<ProtocolId>,
     ) -> (StateSynchronizerSender, StateSynchronizerEvents);
+    fn add_consensus_observer(
+        &mut self,
+        consensus_observer_protocols: Vec<ProtocolId>,
+    ) -> (ConsensusObserverSender, ConsensusObserverEvents);
     fn start(self: Box<Self>) -> BoxFuture<'static, ()>;
 }
 
@@ -170,6 +176,25 @@ where
         (state_sync_network_sender, state_sync_network_events)
     }
 
+    fn add_consensus_observer(
+        &mut self,
+        consensus_observer_protocols: Vec<ProtocolId>,
+    ) -> (ConsensusObserverSender, ConsensusObserverEvents) {
+        // Construct Consensus Observer network interfaces
+        let (observer_tx, observer_rx) = channel::new_with_timeout(
+            self.channel_size,
+            &counters::PENDING_CONSENSUS_OBSERVER_NETWORK_EVENTS,
+            Duration::from_millis(CONSENSUS_OBSERVER_INBOUND_MSG_TIMEOUT_MS),
+        );
+        let observer_network_sender = ConsensusObserverSender::new(self.requests_tx.clone());
+        let observer_network_events = ConsensusObserverEvents::new(observer_rx);
+        let observer_handlers = consensus_observer_protocols
+            .iter()
+            .map(|p| (p.clone(), observer_tx.clone()));
+        self.upstream_handlers.extend(observer_handlers);
+        (observer_network_sender, observer_network_events)
+    }
+
     fn start(self: Box<Self>) -> BoxFuture<'static, ()> {
         let f = async move {
             let rpc_reqs_tx = self.rpc_reqs_tx.clone();
@@ -5,6 +5,7 @@
 
 //! Protobuf definitions for data structures sent over the network
 mod consensus;
+mod consensus_observer;
 mod mempool;
 mod network;
 mod state_synchronizer;
@@ -13,8 +14,14 @@ use types::proto::{ledger_info, transaction};
 
 pub use self::{
     consensus::{
-        Block, BlockRetrievalStatus, ConsensusMsg, PacemakerTimeout, PacemakerTimeoutCertificate,
-        Proposal, QuorumCert, RequestBlock, RespondBlock, SyncInfo, TimeoutMsg, Vote, VoteData,
+        AggregatedSignature, AggregatedTimeouts, Block, BlockRetrievalStatus, CommitVote,
+        ConsensusMsg, EpochChangeProof, PacemakerTimeout, PacemakerTimeoutCertificate, Proposal,
+        QuorumCert, RequestBlock, RequestEpoch, RespondBlock, SyncInfo, TimeoutMsg, Vote,
+        VoteData,
+    },
+    consensus_observer::{
+        CommittedBlock, CommittedBlocks, ConsensusObserverMsg, SubscribeRequest,
+        UnsubscribeRequest,
     },
     mempool::MempoolSyncMsg,
     network::{
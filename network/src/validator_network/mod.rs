@@ -10,6 +10,7 @@ use futures::channel::oneshot;
 pub mod network_builder;
 
 mod consensus;
+mod consensus_observer;
 mod mempool;
 mod state_synchronizer;
 #[cfg(test)]
@@ -21,6 +22,9 @@ pub use consensus::{
     ConsensusNetworkEvents, ConsensusNetworkSender, CONSENSUS_DIRECT_SEND_PROTOCOL,
     CONSENSUS_RPC_PROTOCOL,
 };
+pub use consensus_observer::{
+    ConsensusObserverEvents, ConsensusObserverSender, CONSENSUS_OBSERVER_MSG_PROTOCOL,
+};
 pub use mempool::{MempoolNetworkEvents, MempoolNetworkSender, MEMPOOL_DIRECT_SEND_PROTOCOL};
 pub use state_synchronizer::{
     StateSynchronizerEvents, StateSynchronizerSender, STATE_SYNCHRONIZER_MSG_PROTOCOL,
@@ -36,6 +36,9 @@ pub enum CoordinatorMessage {
     // used to notify about new txn commit
     Commit(u64),
     GetState(oneshot::Sender<u64>),
+    // used to turn autosync on or off, e.g. when another component commits the blocks of a full
+    // node and only needs state sync to catch up with the targets it requests
+    SetAutosync(bool),
 }
 
 /// used to coordinate synchronization process
@@ -132,6 +135,10 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                         CoordinatorMessage::GetState(callback) => {
                             self.get_state(callback);
                         }
+                        CoordinatorMessage::SetAutosync(autosync) => {
+                            debug!("[state sync] autosync: {}", autosync);
+                            self.autosync = autosync;
+                        }
                     };
                 },
                 (idx, network_event) = network_events.select_next_some() => {
@@ -95,6 +95,17 @@ impl StateSyncClient {
         }
     }
 
+    /// Turns on or off fetching chunks from upstream peers when no target state was requested
+    pub fn set_autosync(&self, autosync: bool) -> impl Future<Output = Result<()>> {
+        let mut sender = self.coordinator_sender.clone();
+        async move {
+            sender
+                .send(CoordinatorMessage::SetAutosync(autosync))
+                .await?;
+            Ok(())
+        }
+    }
+
     /// Returns information about StateSynchronizer internal state
     pub fn get_state(&self) -> impl Future<Output = Result<u64>> {
         let mut sender = self.coordinator_sender.clone();
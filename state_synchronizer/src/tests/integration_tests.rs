@@ -38,7 +38,7 @@ use types::{
     account_address::AccountAddress,
     crypto_proxies::LedgerInfoWithSignatures,
     ledger_info::LedgerInfo as TypesLedgerInfo,
-    proof::AccumulatorProof,
+    proof::AccumulatorRangeProof,
     test_helpers::transaction_test_helpers::get_test_signed_txn,
     transaction::{SignedTransaction, TransactionInfo, TransactionListWithProof},
     vm_error::StatusCode,
@@ -101,7 +101,6 @@ impl MockExecutorProxy {
             0,
             StatusCode::EXECUTED,
         );
-        let accumulator_proof = AccumulatorProof::new(vec![]);
         let txns = TransactionListWithProof::new(
             vec![(
                 SignedTransaction::from_proto(transaction).unwrap(),
@@ -109,8 +108,7 @@ impl MockExecutorProxy {
             )],
             None,
             Some(version + 1),
-            Some(accumulator_proof),
-            None,
+            AccumulatorRangeProof::new_empty(),
         );
 
         let mut resp = GetChunkResponse::new();
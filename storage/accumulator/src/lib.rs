@@ -104,7 +104,10 @@
 use crypto::hash::{CryptoHash, CryptoHasher, HashValue, ACCUMULATOR_PLACEHOLDER_HASH};
 use failure::prelude::*;
 use std::marker::PhantomData;
-use types::proof::{position::Position, AccumulatorProof, MerkleTreeInternalNode};
+use types::proof::{
+    position::{FrozenSubtreeSiblingIterator, Position},
+    AccumulatorConsistencyProof, AccumulatorProof, AccumulatorRangeProof, MerkleTreeInternalNode,
+};
 
 /// Defines the interface between `MerkleAccumulator` and underlying storage.
 pub trait HashReader {
@@ -145,6 +148,36 @@ where
     pub fn get_proof(reader: &R, num_leaves: u64, leaf_index: u64) -> Result<AccumulatorProof> {
         MerkleAccumulatorView::<R, H>::new(reader, num_leaves).get_proof(leaf_index)
     }
+
+    /// Gets a proof for the `num_leaves_in_range` consecutive leaves starting from
+    /// `first_leaf_index` in this Merkle Accumulator of `num_leaves` leaves in total. If
+    /// `num_leaves_in_range` is 0, `first_leaf_index` is ignored and the proof is empty.
+    ///
+    /// See [`types::proof::AccumulatorRangeProof`] for proof format.
+    pub fn get_range_proof(
+        reader: &R,
+        num_leaves: u64,
+        first_leaf_index: u64,
+        num_leaves_in_range: u64,
+    ) -> Result<AccumulatorRangeProof> {
+        MerkleAccumulatorView::<R, H>::new(reader, num_leaves)
+            .get_range_proof(first_leaf_index, num_leaves_in_range)
+    }
+
+    /// Gets a proof that shows the full accumulator of `full_acc_leaves` leaves is consistent
+    /// with, i.e. extends, its prefix of `sub_acc_leaves` leaves. The proof consists of the roots
+    /// of the frozen subtrees that make up the leaves appended after the first `sub_acc_leaves`
+    /// ones.
+    ///
+    /// See [`types::proof::AccumulatorConsistencyProof`] for proof format.
+    pub fn get_consistency_proof(
+        reader: &R,
+        full_acc_leaves: u64,
+        sub_acc_leaves: u64,
+    ) -> Result<AccumulatorConsistencyProof> {
+        MerkleAccumulatorView::<R, H>::new(reader, full_acc_leaves)
+            .get_consistency_proof(sub_acc_leaves)
+    }
 }
 
 /// Actual implementation of Merkle Accumulator algorithms, which carries the `reader` and
@@ -316,6 +349,67 @@ where
 
         Ok(AccumulatorProof::new(siblings))
     }
+
+    /// implementation for pub interface `MerkleAccumulator::get_range_proof`
+    fn get_range_proof(
+        &self,
+        first_leaf_index: u64,
+        num_leaves_in_range: u64,
+    ) -> Result<AccumulatorRangeProof> {
+        if num_leaves_in_range == 0 {
+            return Ok(AccumulatorRangeProof::new_empty());
+        }
+        let last_leaf_index = first_leaf_index + num_leaves_in_range - 1;
+        ensure!(
+            last_leaf_index < self.num_leaves,
+            "invalid range [{}, {}], num_leaves {}",
+            first_leaf_index,
+            last_leaf_index,
+            self.num_leaves
+        );
+
+        let left_siblings = self.get_siblings(first_leaf_index, |p| p.is_left_child())?;
+        let right_siblings = self.get_siblings(last_leaf_index, |p| !p.is_left_child())?;
+
+        Ok(AccumulatorRangeProof::new(left_siblings, right_siblings))
+    }
+
+    /// Returns the siblings of the ancestors of the leaf at `leaf_index` that satisfy `filter`,
+    /// the ones near the root first.
+    fn get_siblings(
+        &self,
+        leaf_index: u64,
+        filter: impl Fn(Position) -> bool,
+    ) -> Result<Vec<HashValue>> {
+        let root_pos = Position::root_from_leaf_count(self.num_leaves);
+
+        let mut siblings = Position::from_leaf_index(leaf_index)
+            .iter_ancestor_sibling()
+            .take(root_pos.level() as usize)
+            .filter(|p| filter(*p))
+            .map(|p| self.get_hash(p))
+            .collect::<Result<Vec<HashValue>>>()?;
+        siblings.reverse();
+
+        Ok(siblings)
+    }
+
+    /// implementation for pub interface `MerkleAccumulator::get_consistency_proof`
+    fn get_consistency_proof(&self, sub_acc_leaves: u64) -> Result<AccumulatorConsistencyProof> {
+        ensure!(
+            sub_acc_leaves <= self.num_leaves,
+            "The accumulator of {} leaves can't be a prefix of the one of {} leaves.",
+            sub_acc_leaves,
+            self.num_leaves
+        );
+
+        // All the subtrees are within the first `num_leaves` leaves, hence frozen.
+        let subtrees = FrozenSubtreeSiblingIterator::new(sub_acc_leaves, self.num_leaves)
+            .map(|p| self.reader.get(p))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(AccumulatorConsistencyProof::new(subtrees))
+    }
 }
 
 #[cfg(test)]
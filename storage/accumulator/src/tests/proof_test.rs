@@ -2,14 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use proptest::{collection::vec, prelude::*};
-use types::proof::verify_test_accumulator_element;
+use proptest::{collection::vec, prelude::*, sample::Index};
+use types::proof::{
+    accumulator::Accumulator, verify_accumulator_consistency, verify_test_accumulator_element,
+    verify_test_accumulator_range,
+};
 
 #[test]
 fn test_error_on_bad_parameters() {
     let store = MockHashStore::new();
     assert!(TestAccumulator::get_proof(&store, 0, 0).is_err());
     assert!(TestAccumulator::get_proof(&store, 100, 101).is_err());
+    assert!(TestAccumulator::get_range_proof(&store, 100, 99, 2).is_err());
+    assert!(TestAccumulator::get_consistency_proof(&store, 100, 101).is_err());
 }
 
 #[test]
@@ -47,6 +52,73 @@ proptest! {
         // verify proofs for all leaves of a subtree towards subtree root
         verify(&store, batch1.len(), root_hash1, &batch1, 0);
     }
+
+    #[test]
+    fn test_range_proof(
+        leaves in vec(any::<HashValue>(), 1..100),
+        first in any::<Index>(),
+        len in any::<Index>(),
+    ) {
+        let num_leaves = leaves.len() as u64;
+        let first_leaf_index = first.index(leaves.len());
+        let num_leaves_in_range = len.index(leaves.len() - first_leaf_index) + 1;
+        let mut store = MockHashStore::new();
+        let (root_hash, writes) = TestAccumulator::append(&store, 0, &leaves).unwrap();
+        store.put_many(&writes);
+
+        let proof = TestAccumulator::get_range_proof(
+            &store,
+            num_leaves,
+            first_leaf_index as u64,
+            num_leaves_in_range as u64,
+        )
+        .unwrap();
+        let range = &leaves[first_leaf_index..first_leaf_index + num_leaves_in_range];
+        verify_test_accumulator_range(root_hash, first_leaf_index as u64, range, &proof).unwrap();
+        prop_assert!(verify_test_accumulator_range(
+            root_hash,
+            first_leaf_index as u64 + 1,
+            range,
+            &proof,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_consistency_proof(
+        batch1 in vec(any::<HashValue>(), 0..100),
+        batch2 in vec(any::<HashValue>(), 0..100),
+    ) {
+        let total_leaves = (batch1.len() + batch2.len()) as u64;
+        let batch1_size = batch1.len() as u64;
+        let mut store = MockHashStore::new();
+        let (_, writes1) = TestAccumulator::append(&store, 0, &batch1).unwrap();
+        store.put_many(&writes1);
+        let (root_hash, writes2) = TestAccumulator::append(&store, batch1_size, &batch2).unwrap();
+        store.put_many(&writes2);
+
+        let sub_accumulator = Accumulator::<TestOnlyHasher>::default().append(batch1.clone());
+        let proof =
+            TestAccumulator::get_consistency_proof(&store, total_leaves, batch1_size).unwrap();
+        let full_accumulator =
+            verify_accumulator_consistency(&sub_accumulator, &proof, total_leaves, root_hash)
+                .unwrap();
+        prop_assert_eq!(full_accumulator.num_leaves(), total_leaves);
+
+        // The proof doesn't verify against a different history.
+        if let Some((_, rest)) = batch1.split_first() {
+            let mut forked_leaves = vec![HashValue::random()];
+            forked_leaves.extend_from_slice(rest);
+            let forked_accumulator = Accumulator::<TestOnlyHasher>::default().append(forked_leaves);
+            prop_assert!(verify_accumulator_consistency(
+                &forked_accumulator,
+                &proof,
+                total_leaves,
+                root_hash,
+            )
+            .is_err());
+        }
+    }
 }
 
 fn verify(
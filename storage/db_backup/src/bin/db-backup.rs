@@ -0,0 +1,88 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use config::{config::PersistableConfig, trusted_peers::ConsensusPeersConfig};
+use libradb::LibraDB;
+use std::{path::PathBuf, str::FromStr};
+use structopt::StructOpt;
+use types::{account_address::AccountAddress, crypto_proxies::ValidatorVerifier};
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "db-backup",
+    author = "Libra",
+    about = "Tool to back up a LibraDB offline and restore it"
+)]
+enum Command {
+    /// Takes a backup of the DB.
+    #[structopt(name = "backup")]
+    Backup {
+        /// Directory holding the DB to back up, i.e. `storage.dir` in the node config.
+        #[structopt(short = "d", long = "db-dir", parse(from_os_str))]
+        db_dir: PathBuf,
+        /// Directory to write the backup into.
+        #[structopt(short = "o", long = "backup-dir", parse(from_os_str))]
+        backup_dir: PathBuf,
+        /// Take the backup against the ledger info ending this epoch instead of the latest one.
+        #[structopt(short = "e", long = "epoch")]
+        epoch: Option<u64>,
+        /// Version of the first transaction to include in the backup.
+        #[structopt(short = "f", long = "first-version", default_value = "0")]
+        first_version: u64,
+    },
+    /// Restores a backup into an empty DB.
+    #[structopt(name = "restore")]
+    Restore {
+        /// Directory holding the backup.
+        #[structopt(short = "i", long = "backup-dir", parse(from_os_str))]
+        backup_dir: PathBuf,
+        /// Directory to create the DB in.
+        #[structopt(short = "d", long = "db-dir", parse(from_os_str))]
+        db_dir: PathBuf,
+        /// Consensus peers config holding the genesis validator set, against which the ledger
+        /// infos in the backup are verified.
+        #[structopt(short = "t", long = "trusted-validators", parse(from_os_str))]
+        trusted_validators: PathBuf,
+    },
+}
+
+fn main() {
+    let manifest = match Command::from_args() {
+        Command::Backup {
+            db_dir,
+            backup_dir,
+            epoch,
+            first_version,
+        } => db_backup::backup(&LibraDB::new(db_dir), &backup_dir, epoch, first_version)
+            .expect("Failed to back up the DB."),
+        Command::Restore {
+            backup_dir,
+            db_dir,
+            trusted_validators,
+        } => db_backup::restore(
+            &LibraDB::new(db_dir),
+            &backup_dir,
+            &load_validator_verifier(trusted_validators),
+        )
+        .expect("Failed to restore the DB."),
+    };
+    println!(
+        "Done. Epoch: {}, version: {}, first version: {}, accounts: {}.",
+        manifest.epoch, manifest.version, manifest.first_version, manifest.num_accounts
+    );
+}
+
+fn load_validator_verifier(consensus_peers_file: PathBuf) -> ValidatorVerifier {
+    ValidatorVerifier::new(
+        ConsensusPeersConfig::load_config(consensus_peers_file)
+            .peers
+            .into_iter()
+            .map(|(peer_id, peer_info)| {
+                (
+                    AccountAddress::from_str(&peer_id).expect("Invalid peer id."),
+                    peer_info.consensus_pubkey,
+                )
+            })
+            .collect(),
+    )
+}
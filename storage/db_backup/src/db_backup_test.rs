@@ -0,0 +1,140 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crypto::x25519;
+use libradb::{mock_genesis::db_with_mock_genesis, test_helper::arb_blocks_to_commit};
+use proptest::prelude::*;
+use std::collections::HashMap;
+use tools::tempdir::TempPath;
+use types::{
+    crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo, transaction::TransactionToCommit,
+    validator_public_keys::ValidatorPublicKeys, validator_set::ValidatorSet,
+};
+
+fn validator_set(signer: &ValidatorSigner) -> ValidatorSet {
+    ValidatorSet::new(vec![ValidatorPublicKeys::new(
+        signer.author(),
+        signer.public_key(),
+        signer.public_key(),
+        x25519::compat::generate_keypair(None).1,
+        None,
+    )])
+}
+
+/// Puts each block in its own epoch, signed by a validator announced by the previous one.
+fn sign_blocks(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> (
+    Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+    ValidatorVerifier,
+) {
+    let num_blocks = input.len();
+    let signers = (0..num_blocks)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect::<Vec<_>>();
+    let blocks = input
+        .into_iter()
+        .enumerate()
+        .map(|(epoch, (txns_to_commit, ledger_info_with_sigs))| {
+            let li = ledger_info_with_sigs.ledger_info();
+            let ledger_info = LedgerInfo::new(
+                li.version(),
+                li.transaction_accumulator_hash(),
+                li.consensus_data_hash(),
+                li.consensus_block_id(),
+                epoch as u64,
+                li.timestamp_usecs(),
+                if epoch + 1 < num_blocks {
+                    Some(validator_set(&signers[epoch + 1]))
+                } else {
+                    None
+                },
+            );
+            let mut signatures = HashMap::new();
+            signatures.insert(
+                signers[epoch].author(),
+                signers[epoch].sign_message(ledger_info.hash()).unwrap(),
+            );
+            (
+                txns_to_commit,
+                LedgerInfoWithSignatures::new(ledger_info, signatures),
+            )
+        })
+        .collect();
+    (
+        blocks,
+        ValidatorVerifier::new_single(signers[0].author(), signers[0].public_key()),
+    )
+}
+
+fn backup_and_restore_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+    first_version_pct: u64,
+) -> Result<()> {
+    let (input, trusted_validators) = sign_blocks(input);
+    let tmp_dir = TempPath::new();
+    let src_db = db_with_mock_genesis(&tmp_dir)?;
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        src_db.save_transactions(
+            &txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs.clone()),
+        )?;
+        cur_ver += txns_to_commit.len() as u64;
+    }
+
+    let backup_dir = TempPath::new();
+    let first_version = cur_ver * first_version_pct / 100;
+    let manifest = backup(
+        &src_db,
+        backup_dir.path(),
+        None, /* epoch */
+        first_version,
+    )?;
+    assert_eq!(manifest.version, cur_ver);
+    assert_eq!(manifest.first_version, first_version);
+
+    let tmp_dir = TempPath::new();
+    let db = LibraDB::new(&tmp_dir);
+    // Nothing is restored unless the ledger infos verify against the trusted validators.
+    let signer = ValidatorSigner::random([u8::max_value(); 32]);
+    let untrusted_validators = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    assert!(restore(&db, backup_dir.path(), &untrusted_validators).is_err());
+    assert!(db.get_startup_info()?.is_none());
+
+    assert_eq!(
+        restore(&db, backup_dir.path(), &trusted_validators)?,
+        manifest
+    );
+
+    let expected = src_db.get_startup_info()?.unwrap();
+    let actual = db.get_startup_info()?.unwrap();
+    assert_eq!(actual.latest_version, expected.latest_version);
+    assert_eq!(
+        actual.account_state_root_hash,
+        expected.account_state_root_hash
+    );
+    assert_eq!(
+        actual.ledger_frozen_subtree_hashes,
+        expected.ledger_frozen_subtree_hashes
+    );
+
+    // Restoring again into the same DB is refused.
+    assert!(restore(&db, backup_dir.path(), &trusted_validators).is_err());
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_backup_and_restore(
+        input in arb_blocks_to_commit(),
+        first_version_pct in 0..=100u64,
+    ) {
+        backup_and_restore_impl(input, first_version_pct).unwrap();
+    }
+}
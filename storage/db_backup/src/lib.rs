@@ -0,0 +1,364 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This crate implements offline backup and restore of a [`LibraDB`].
+//!
+//! A backup is taken against a [`LedgerInfoWithSignatures`] in the DB, the latest one by default
+//! or the one ending a given epoch, and consists of everything needed to bring up a DB at the
+//! version of that ledger info. It is laid out in a directory as follows:
+//!
+//! ```text
+//! <backup_dir>/
+//! |- manifest.json   the BackupManifest in JSON
+//! |- ledger_infos    all the ledger infos up to the target one, ordered by epoch
+//! |- transactions    TransactionListWithProof chunks from `first_version` to `version`, with
+//! |                  events and proofs relative to the target ledger info
+//! |- account_states  (hashed account address | account state blob) of all the accounts at
+//! |                  `version`, ordered by the hashed address
+//! |- account_state_proofs
+//!                    a SparseMerkleRangeProof for each chunk of `account_state_chunk_size`
+//!                    accounts, proving all the accounts up to the end of the chunk
+//! ```
+//!
+//! The data files are sequences of length-prefixed records of protobuf bytes.
+//!
+//! On restore, the ledger infos are verified first, starting from a validator set trusted by the
+//! caller, typically the genesis one: each ledger info must be signed by a quorum of the validators
+//! of its epoch, which are announced by the ledger info ending the previous epoch, so nothing is
+//! written unless the target ledger info is. Then each transaction chunk is verified against the
+//! target ledger info before it's written, the transaction accumulator rebuilt on top of the frozen
+//! subtrees in the manifest must end up with the root hash carried by the ledger info, and each
+//! chunk of account states is verified against the state root hash of the last transaction as the
+//! account state tree is rebuilt, so that the whole snapshot never needs to be held in memory.
+
+mod record;
+
+#[cfg(test)]
+mod db_backup_test;
+
+use crate::record::{RecordReader, RecordWriter};
+use crypto::{hash::CryptoHash, HashValue};
+use failure::prelude::*;
+use libradb::LibraDB;
+use proto_conv::{FromProtoBytes, IntoProtoBytes};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+use types::{
+    account_state_blob::AccountStateBlob,
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier},
+    proof::SparseMerkleRangeProof,
+    transaction::{TransactionListWithProof, Version},
+};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const LEDGER_INFOS_FILE: &str = "ledger_infos";
+const TRANSACTIONS_FILE: &str = "transactions";
+const ACCOUNT_STATES_FILE: &str = "account_states";
+const ACCOUNT_STATE_PROOFS_FILE: &str = "account_state_proofs";
+
+/// Max number of transactions in each chunk in the transactions file.
+const TRANSACTION_CHUNK_SIZE: u64 = 1000;
+/// Number of accounts covered by each proof in the account state proofs file.
+const ACCOUNT_STATE_CHUNK_SIZE: u64 = 1000;
+
+/// Describes what's in a backup.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BackupManifest {
+    /// Epoch of the ledger info the backup is taken against.
+    pub epoch: u64,
+    /// Version of the ledger info the backup is taken against, at which the account state
+    /// snapshot is taken.
+    pub version: Version,
+    /// Version of the first transaction in the backup.
+    pub first_version: Version,
+    /// Hex encoded root hashes of the frozen subtrees of the transaction accumulator before
+    /// `first_version`, from left to right.
+    pub frozen_subtrees: Vec<String>,
+    /// Number of accounts in the account state snapshot.
+    pub num_accounts: u64,
+    /// Number of accounts covered by each proof in the account state proofs file. The last chunk
+    /// may be smaller.
+    pub account_state_chunk_size: u64,
+}
+
+/// Backs up `db` into `backup_dir`, which is created if it doesn't exist.
+///
+/// The backup is taken against the ledger info ending `epoch` if provided, otherwise the latest
+/// one. Transactions starting from `first_version` are included.
+pub fn backup(
+    db: &LibraDB,
+    backup_dir: &Path,
+    epoch: Option<u64>,
+    first_version: Version,
+) -> Result<BackupManifest> {
+    let handler = db.get_backup_handler();
+    let latest_ledger_info = handler.get_latest_ledger_info()?;
+    let target_epoch = epoch.unwrap_or_else(|| latest_ledger_info.ledger_info().epoch_num());
+    let ledger_infos = handler
+        .get_epoch_ledger_infos()?
+        .into_iter()
+        .filter(|li| li.ledger_info().epoch_num() <= target_epoch)
+        .collect::<Vec<_>>();
+    let target_ledger_info = ledger_infos
+        .iter()
+        .find(|li| li.ledger_info().epoch_num() == target_epoch)
+        .ok_or_else(|| format_err!("No ledger info for epoch {}.", target_epoch))?;
+    let version = target_ledger_info.ledger_info().version();
+    ensure!(
+        first_version <= version,
+        "First version {} is beyond the version to backup: {}.",
+        first_version,
+        version,
+    );
+
+    fs::create_dir_all(backup_dir)?;
+
+    let mut writer = new_record_writer(backup_dir, LEDGER_INFOS_FILE)?;
+    for ledger_info in &ledger_infos {
+        writer.write_record(&ledger_info.clone().into_proto_bytes()?)?;
+    }
+    writer.flush()?;
+
+    let mut writer = new_record_writer(backup_dir, TRANSACTIONS_FILE)?;
+    let mut start_version = first_version;
+    while start_version <= version {
+        let txn_list_with_proof =
+            handler.get_transactions(start_version, TRANSACTION_CHUNK_SIZE, version)?;
+        start_version += txn_list_with_proof.len() as u64;
+        writer.write_record(&txn_list_with_proof.into_proto_bytes()?)?;
+    }
+    writer.flush()?;
+
+    let mut writer = new_record_writer(backup_dir, ACCOUNT_STATES_FILE)?;
+    let mut proof_writer = new_record_writer(backup_dir, ACCOUNT_STATE_PROOFS_FILE)?;
+    let mut num_accounts = 0;
+    let mut last_key = None;
+    for res in handler.get_account_iter(version)? {
+        let (key, blob) = res?;
+        let mut record = key.to_vec();
+        record.extend_from_slice(blob.as_ref());
+        writer.write_record(&record)?;
+        num_accounts += 1;
+        last_key = Some(key);
+        if num_accounts % ACCOUNT_STATE_CHUNK_SIZE == 0 {
+            let proof = handler.get_account_state_range_proof(key, version)?;
+            proof_writer.write_record(&proof.into_proto_bytes()?)?;
+            last_key = None;
+        }
+    }
+    if let Some(key) = last_key {
+        // The last chunk is not full.
+        let proof = handler.get_account_state_range_proof(key, version)?;
+        proof_writer.write_record(&proof.into_proto_bytes()?)?;
+    }
+    writer.flush()?;
+    proof_writer.flush()?;
+
+    let manifest = BackupManifest {
+        epoch: target_epoch,
+        version,
+        first_version,
+        frozen_subtrees: handler
+            .get_frozen_subtree_hashes(first_version)?
+            .iter()
+            .map(|hash| hex::encode(hash.to_vec()))
+            .collect(),
+        num_accounts,
+        account_state_chunk_size: ACCOUNT_STATE_CHUNK_SIZE,
+    };
+    fs::write(
+        backup_dir.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    Ok(manifest)
+}
+
+/// Restores the backup in `backup_dir` into `db`, which must be empty.
+///
+/// `trusted_validators` are the validators of the first epoch in the backup, i.e. the genesis
+/// validator set, from which the validators of the following epochs are learned.
+pub fn restore(
+    db: &LibraDB,
+    backup_dir: &Path,
+    trusted_validators: &ValidatorVerifier,
+) -> Result<BackupManifest> {
+    let manifest: BackupManifest =
+        serde_json::from_str(&fs::read_to_string(backup_dir.join(MANIFEST_FILE))?)?;
+    let handler = db.get_restore_handler();
+    ensure!(
+        handler.get_next_expected_version()? == 0,
+        "Can only restore into an empty DB."
+    );
+    ensure!(
+        manifest.account_state_chunk_size > 0,
+        "Account state chunk size must be positive."
+    );
+
+    let ledger_infos = new_record_reader(backup_dir, LEDGER_INFOS_FILE)?
+        .map(|record| LedgerInfoWithSignatures::from_proto_bytes(&record?))
+        .collect::<Result<Vec<_>>>()?;
+    verify_ledger_infos(&ledger_infos, manifest.epoch, trusted_validators)?;
+    let target_ledger_info = ledger_infos
+        .last()
+        .expect("Ledger infos are verified to end with the target one.")
+        .ledger_info();
+    ensure!(
+        target_ledger_info.version() == manifest.version,
+        "Version of the ledger info ({}) doesn't match the manifest ({}).",
+        target_ledger_info.version(),
+        manifest.version,
+    );
+
+    if manifest.first_version > 0 {
+        let frozen_subtrees = manifest
+            .frozen_subtrees
+            .iter()
+            .map(|hash| Ok(HashValue::from_slice(&hex::decode(hash)?)?))
+            .collect::<Result<Vec<_>>>()?;
+        handler.save_frozen_subtrees(manifest.first_version, &frozen_subtrees)?;
+    }
+
+    let mut next_version = manifest.first_version;
+    let mut last_txn_list_with_proof = None;
+    for record in new_record_reader(backup_dir, TRANSACTIONS_FILE)? {
+        let txn_list_with_proof = TransactionListWithProof::from_proto_bytes(&record?)?;
+        txn_list_with_proof.verify(target_ledger_info, Some(next_version))?;
+        let root_hash = handler.save_transactions(&txn_list_with_proof)?;
+        next_version += txn_list_with_proof.len() as u64;
+        last_txn_list_with_proof = Some((txn_list_with_proof, root_hash));
+    }
+    ensure!(
+        next_version == manifest.version + 1,
+        "Transactions end at version {}, expected {}.",
+        next_version,
+        manifest.version + 1,
+    );
+    let (last_txn_list_with_proof, root_hash) = last_txn_list_with_proof
+        .ok_or_else(|| format_err!("No transaction found in the backup."))?;
+    ensure!(
+        root_hash == target_ledger_info.transaction_accumulator_hash(),
+        "Restored transaction accumulator root hash doesn't match the ledger info. {:?} vs {:?}",
+        root_hash,
+        target_ledger_info.transaction_accumulator_hash(),
+    );
+
+    let (_, last_txn_info) = last_txn_list_with_proof
+        .transaction_and_infos
+        .last()
+        .ok_or_else(|| format_err!("Last transaction chunk in the backup is empty."))?;
+    let mut receiver =
+        handler.get_state_restore_receiver(manifest.version, last_txn_info.state_root_hash());
+    let mut proofs = new_record_reader(backup_dir, ACCOUNT_STATE_PROOFS_FILE)?
+        .map(|record| SparseMerkleRangeProof::from_proto_bytes(&record?));
+    let mut chunk = Vec::with_capacity(manifest.account_state_chunk_size as usize);
+    let mut num_accounts = 0;
+    let mut accounts = new_record_reader(backup_dir, ACCOUNT_STATES_FILE)?.peekable();
+    while let Some(record) = accounts.next() {
+        let record = record?;
+        ensure!(
+            record.len() > HashValue::LENGTH,
+            "Account state record too short: {} bytes.",
+            record.len(),
+        );
+        chunk.push((
+            HashValue::from_slice(&record[..HashValue::LENGTH])?,
+            AccountStateBlob::from(record[HashValue::LENGTH..].to_vec()),
+        ));
+        num_accounts += 1;
+        if chunk.len() as u64 == manifest.account_state_chunk_size || accounts.peek().is_none() {
+            let proof = proofs
+                .next()
+                .ok_or_else(|| format_err!("Missing proof for account state chunk."))??;
+            receiver.add_chunk(std::mem::replace(&mut chunk, Vec::new()), proof)?;
+        }
+    }
+    ensure!(
+        proofs.next().is_none(),
+        "More account state proofs than account state chunks."
+    );
+    ensure!(
+        num_accounts == manifest.num_accounts,
+        "Number of accounts ({}) doesn't match the manifest ({}).",
+        num_accounts,
+        manifest.num_accounts,
+    );
+    receiver.finish()?;
+
+    // Saved at last so that a DB only partially restored doesn't look bootstrapped.
+    handler.save_ledger_infos(&ledger_infos)?;
+
+    Ok(manifest)
+}
+
+/// Verifies that `ledger_infos` form a chain of epochs from the one of `trusted_validators` to
+/// `target_epoch`: each ledger info is signed by a quorum of the validators of its epoch, and all
+/// but the last one end their epoch, announcing the validators of the next one.
+fn verify_ledger_infos(
+    ledger_infos: &[LedgerInfoWithSignatures],
+    target_epoch: u64,
+    trusted_validators: &ValidatorVerifier,
+) -> Result<()> {
+    ensure!(
+        !trusted_validators.is_empty(),
+        "The trusted validator set is empty."
+    );
+    let mut validators = trusted_validators.clone();
+    let mut expected_epoch = None;
+    for (i, ledger_info_with_sigs) in ledger_infos.iter().enumerate() {
+        let ledger_info = ledger_info_with_sigs.ledger_info();
+        let epoch = ledger_info.epoch_num();
+        if let Some(expected_epoch) = expected_epoch {
+            ensure!(
+                epoch == expected_epoch,
+                "Ledger infos are not in consecutive epochs: got epoch {}, expected {}.",
+                epoch,
+                expected_epoch,
+            );
+        }
+        expected_epoch = Some(epoch + 1);
+
+        // Not `LedgerInfoWithSignatures::verify`, which lets a ledger info at version 0 through.
+        validators
+            .batch_verify_aggregated_signature(
+                ledger_info.hash(),
+                ledger_info_with_sigs.signatures(),
+            )
+            .map_err(|e| {
+                format_err!(
+                    "Signatures on ledger info of epoch {} don't verify: {}",
+                    epoch,
+                    e,
+                )
+            })?;
+
+        if i + 1 < ledger_infos.len() {
+            let next_validator_set = ledger_info.next_validator_set().ok_or_else(|| {
+                format_err!("Ledger info of epoch {} doesn't end the epoch.", epoch)
+            })?;
+            validators = ValidatorVerifier::from(next_validator_set);
+        }
+    }
+    ensure!(
+        expected_epoch == Some(target_epoch + 1),
+        "Ledger infos don't end at the target epoch {}.",
+        target_epoch,
+    );
+    Ok(())
+}
+
+fn new_record_writer(dir: &Path, file_name: &str) -> Result<RecordWriter<BufWriter<File>>> {
+    Ok(RecordWriter::new(BufWriter::new(File::create(
+        dir.join(file_name),
+    )?)))
+}
+
+fn new_record_reader(dir: &Path, file_name: &str) -> Result<RecordReader<BufReader<File>>> {
+    Ok(RecordReader::new(BufReader::new(File::open(
+        dir.join(file_name),
+    )?)))
+}
@@ -0,0 +1,75 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Data files in a backup are sequences of records, each of which is the length of the payload
+//! as a big endian `u32` followed by the payload bytes:
+//!
+//! ```text
+//! | len (4 bytes) | payload (len bytes) | len (4 bytes) | payload (len bytes) | ...
+//! ```
+
+use failure::prelude::*;
+use std::{
+    convert::TryFrom,
+    io::{ErrorKind, Read, Write},
+};
+
+pub(crate) struct RecordWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_record(&mut self, payload: &[u8]) -> Result<()> {
+        let len = u32::try_from(payload.len())
+            .map_err(|_| format_err!("Record too large: {} bytes.", payload.len()))?;
+        self.writer.write_all(&len.to_be_bytes())?;
+        self.writer.write_all(payload)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+pub(crate) struct RecordReader<R> {
+    reader: R,
+}
+
+impl<R: Read> RecordReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Reads the next record, returning `None` if the end of the input is reached exactly at a
+    /// record boundary.
+    fn read_record(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut len_bytes = [0u8; 4];
+        let mut num_read = 0;
+        while num_read < len_bytes.len() {
+            match self.reader.read(&mut len_bytes[num_read..]) {
+                Ok(0) if num_read == 0 => return Ok(None),
+                Ok(0) => bail!("Truncated record length."),
+                Ok(n) => num_read += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let mut payload = vec![0u8; u32::from_be_bytes(len_bytes) as usize];
+        self.reader.read_exact(&mut payload)?;
+        Ok(Some(payload))
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tool to look inside a LibraDB offline and verify its integrity. The DB is opened read-only, so
+//! it's safe to run against the data directory of a running node, although what's written after
+//! the DB is opened is not seen.
+
+use failure::prelude::*;
+use libradb::LibraDB;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use types::{account_address::AccountAddress, transaction::Version};
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "db-tool",
+    author = "Libra",
+    about = "Tool to inspect a LibraDB offline"
+)]
+struct Opt {
+    /// Directory holding the DB, i.e. `storage.dir` in the node config.
+    #[structopt(short = "d", long = "db-dir", parse(from_os_str))]
+    db_dir: PathBuf,
+    #[structopt(subcommand)]
+    cmd: Command,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Prints what the executor and state synchronizer see on startup.
+    #[structopt(name = "startup-info")]
+    StartupInfo,
+    /// Lists the ledger infos ending each epoch and the latest one.
+    #[structopt(name = "ledger-infos")]
+    LedgerInfos,
+    /// Dumps a transaction with its info and events.
+    #[structopt(name = "transaction")]
+    Transaction {
+        /// Version of the transaction.
+        #[structopt(name = "VERSION")]
+        version: Version,
+    },
+    /// Fetches the state of an account at a version, with proof relative to the latest ledger
+    /// info.
+    #[structopt(name = "account")]
+    Account {
+        /// Hex encoded account address.
+        #[structopt(name = "ADDRESS")]
+        address: AccountAddress,
+        /// Version to read the account state at, the latest by default.
+        #[structopt(short = "v", long = "version")]
+        version: Option<Version>,
+    },
+    /// Reports the approximate size of each column family.
+    #[structopt(name = "cf-sizes")]
+    CfSizes,
+    /// Recomputes hashes and checks signatures of the ledger history against what's stored.
+    #[structopt(name = "verify")]
+    Verify {
+        /// First version to verify.
+        #[structopt(short = "s", long = "start-version", default_value = "0")]
+        start_version: Version,
+        /// Last version to verify, the latest by default.
+        #[structopt(short = "e", long = "end-version")]
+        end_version: Option<Version>,
+    },
+}
+
+fn run(opt: Opt) -> Result<()> {
+    let db = LibraDB::open_readonly(&opt.db_dir)?;
+    let handler = db.get_debug_handler();
+
+    match opt.cmd {
+        Command::StartupInfo => match db.get_startup_info()? {
+            Some(startup_info) => println!("{:#?}", startup_info),
+            None => println!("DB is not bootstrapped."),
+        },
+        Command::LedgerInfos => {
+            for ledger_info_with_sigs in handler.get_epoch_ledger_infos()? {
+                println!("{}", ledger_info_with_sigs);
+            }
+        }
+        Command::Transaction { version } => {
+            let (signed_txn, txn_info, events) = handler.get_transaction(version)?;
+            println!("Transaction: {:#?}", signed_txn);
+            println!("Info: {:#?}", txn_info);
+            println!("Events:");
+            for event in events {
+                println!("  {}", event);
+            }
+        }
+        Command::Account { address, version } => {
+            let latest_ledger_info = handler.get_latest_ledger_info()?;
+            let ledger_info = latest_ledger_info.ledger_info();
+            let version = version.unwrap_or_else(|| ledger_info.version());
+            let account_state_with_proof =
+                handler.get_account_state_with_proof(address, version)?;
+            println!("{:#?}", account_state_with_proof);
+            match account_state_with_proof.verify(ledger_info, version, address) {
+                Ok(()) => println!("Proof verified against {}.", ledger_info),
+                Err(e) => println!("Proof failed to verify against {}: {}", ledger_info, e),
+            }
+        }
+        Command::CfSizes => {
+            for (cf_name, size) in handler.get_column_family_sizes()? {
+                println!("{}: {} bytes", cf_name, size);
+            }
+        }
+        Command::Verify {
+            start_version,
+            end_version,
+        } => {
+            let end_version = match end_version {
+                Some(version) => version,
+                None => {
+                    db.get_startup_info()?
+                        .ok_or_else(|| format_err!("DB is not bootstrapped."))?
+                        .latest_version
+                }
+            };
+            let report = db.verify_integrity(start_version, end_version)?;
+            println!(
+                "Verified versions {} to {}: {:#?}",
+                start_version, end_version, report
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    run(Opt::from_args()).expect("Failed to inspect the DB.");
+}
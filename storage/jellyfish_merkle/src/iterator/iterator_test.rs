@@ -3,6 +3,7 @@
 
 use crate::{
     iterator::JellyfishMerkleIterator, mock_tree_store::MockTreeStore, JellyfishMerkleTree,
+    TreeWriter,
 };
 use crypto::HashValue;
 use failure::prelude::*;
@@ -6,8 +6,9 @@
 //! intermediate results in a batch for storage layer to commit and the read path will return
 //! results directly. The public APIs are only [`new`](JellyfishMerkleTree::new),
 //! [`put_blob_sets`](JellyfishMerkleTree::put_blob_sets),
-//! [`put_blob_set`](JellyfishMerkleTree::put_blob_set) and
-//! [`get_with_proof`](JellyfishMerkleTree::get_with_proof). After each put with a `blob_set`
+//! [`put_blob_set`](JellyfishMerkleTree::put_blob_set),
+//! [`get_with_proof`](JellyfishMerkleTree::get_with_proof) and
+//! [`get_range_proof`](JellyfishMerkleTree::get_range_proof). After each put with a `blob_set`
 //! based on a known version, the tree will return a new root hash with a [`TreeUpdateBatch`]
 //! containing all the new nodes and indices of stale nodes.
 //!
@@ -66,6 +67,7 @@ mod jellyfish_merkle_test;
 mod mock_tree_store;
 mod nibble;
 pub mod node_type;
+pub mod restore;
 mod tree_cache;
 
 use crypto::{hash::CryptoHash, HashValue};
@@ -75,7 +77,11 @@ use node_type::{Child, Children, InternalNode, LeafNode, Node, NodeKey};
 use proptest_derive::Arbitrary;
 use std::collections::{BTreeMap, BTreeSet};
 use tree_cache::TreeCache;
-use types::{account_state_blob::AccountStateBlob, proof::SparseMerkleProof, transaction::Version};
+use types::{
+    account_state_blob::AccountStateBlob,
+    proof::{SparseMerkleProof, SparseMerkleRangeProof},
+    transaction::Version,
+};
 
 /// The hardcoded maximum height of a [`JellyfishMerkleTree`] in nibbles.
 const ROOT_NIBBLE_HEIGHT: usize = HashValue::LENGTH * 2;
@@ -93,6 +99,13 @@ pub trait TreeReader {
     fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node>>;
 }
 
+/// `TreeWriter` defines the interface for persisting the nodes produced by
+/// [`JellyfishMerkleRestore`](restore::JellyfishMerkleRestore) to the underlying storage.
+pub trait TreeWriter {
+    /// Writes a batch of nodes, and stale node indices if any, to storage atomically.
+    fn write_tree_update_batch(&self, batch: TreeUpdateBatch) -> Result<()>;
+}
+
 /// Node batch that will be written into db atomically with other batches.
 pub type NodeBatch = BTreeMap<NodeKey, Node>;
 /// [`RetireNodeIndex`] batch that will be written into db atomically with other batches.
@@ -524,6 +537,40 @@ where
         bail!("Jellyfish Merkle tree has cyclic graph inside.");
     }
 
+    /// Gets the proof that shows all the keys from the leftmost one up to `rightmost_key_to_prove`
+    /// exist at `version`. `rightmost_key_to_prove` itself must exist.
+    pub fn get_range_proof(
+        &self,
+        rightmost_key_to_prove: HashValue,
+        version: Version,
+    ) -> Result<SparseMerkleRangeProof> {
+        let (account, proof) = self.get_with_proof(rightmost_key_to_prove, version)?;
+        ensure!(
+            account.is_some(),
+            "Rightmost key to prove {:x} does not exist at version {}.",
+            rightmost_key_to_prove,
+            version,
+        );
+
+        // The siblings in `proof` are ordered from the root down. We only need the ones on the
+        // right of the path, i.e. where the path goes left, and put the ones near the bottom
+        // first.
+        let right_siblings = proof
+            .siblings()
+            .iter()
+            .zip(rightmost_key_to_prove.iter_bits())
+            .filter_map(|(sibling, bit)| if bit { None } else { Some(*sibling) })
+            .rev()
+            .collect();
+        Ok(SparseMerkleRangeProof::new(right_siblings))
+    }
+
+    /// Returns the root hash of the tree at `version`.
+    pub fn get_root_hash(&self, version: Version) -> Result<HashValue> {
+        let root_node_key = NodeKey::new_empty_path(version);
+        Ok(self.reader.get_node(&root_node_key)?.hash())
+    }
+
     #[cfg(test)]
     pub fn get(&self, key: HashValue, version: Version) -> Result<Option<AccountStateBlob>> {
         Ok(self.get_with_proof(key, version)?.0)
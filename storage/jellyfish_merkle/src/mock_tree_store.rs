@@ -3,7 +3,7 @@
 
 use crate::{
     node_type::{Node, NodeKey},
-    StaleNodeIndex, TreeReader, TreeUpdateBatch,
+    StaleNodeIndex, TreeReader, TreeUpdateBatch, TreeWriter,
 };
 use failure::prelude::*;
 use std::{
@@ -21,6 +21,22 @@ impl TreeReader for MockTreeStore {
     }
 }
 
+impl TreeWriter for MockTreeStore {
+    fn write_tree_update_batch(&self, batch: TreeUpdateBatch) -> Result<()> {
+        batch
+            .node_batch
+            .into_iter()
+            .map(|(k, v)| self.put_node(k, v))
+            .collect::<Result<Vec<_>>>()?;
+        batch
+            .stale_node_index_batch
+            .into_iter()
+            .map(|i| self.put_stale_node_index(i))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(())
+    }
+}
+
 impl MockTreeStore {
     pub fn put_node(&self, node_key: NodeKey, node: Node) -> Result<()> {
         match self.0.write().unwrap().0.entry(node_key) {
@@ -38,20 +54,6 @@ impl MockTreeStore {
         Ok(())
     }
 
-    pub fn write_tree_update_batch(&self, batch: TreeUpdateBatch) -> Result<()> {
-        batch
-            .node_batch
-            .into_iter()
-            .map(|(k, v)| self.put_node(k, v))
-            .collect::<Result<Vec<_>>>()?;
-        batch
-            .stale_node_index_batch
-            .into_iter()
-            .map(|i| self.put_stale_node_index(i))
-            .collect::<Result<Vec<_>>>()?;
-        Ok(())
-    }
-
     pub fn purge_stale_nodes(&self, least_readable_version: Version) -> Result<()> {
         let mut wlocked = self.0.write().unwrap();
 
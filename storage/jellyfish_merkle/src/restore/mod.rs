@@ -0,0 +1,524 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module implements the functionality to restore a [`JellyfishMerkleTree`] from small
+//! chunks of accounts. The accounts must come in increasing order of their keys, e.g. as produced
+//! by [`JellyfishMerkleIterator`], and each chunk is accompanied by a
+//! [`SparseMerkleRangeProof`] generated by
+//! [`get_range_proof`](JellyfishMerkleTree::get_range_proof) that proves all the accounts
+//! received so far, from the leftmost one to the last one in the chunk, against the expected
+//! root hash.
+//!
+//! Since the accounts are sorted, every node that is entirely on the left of the path from root to
+//! the last account received will never change again. Only the internal nodes along that path are
+//! kept in memory and everything else is persisted via [`TreeWriter`] after each chunk is
+//! verified.
+//!
+//! [`JellyfishMerkleTree`]: crate::JellyfishMerkleTree
+//! [`JellyfishMerkleIterator`]: crate::iterator::JellyfishMerkleIterator
+
+#[cfg(test)]
+mod restore_test;
+
+use crate::{
+    nibble::{Nibble, NibbleIterator, NibblePath},
+    node_type::{Child, Children, InternalNode, LeafNode, Node, NodeKey},
+    NodeBatch, TreeUpdateBatch, TreeWriter, ROOT_NIBBLE_HEIGHT,
+};
+use crypto::{
+    hash::{CryptoHash, SPARSE_MERKLE_PLACEHOLDER_HASH},
+    HashValue,
+};
+use failure::prelude::*;
+use types::{
+    account_state_blob::AccountStateBlob,
+    proof::{verify_sparse_merkle_range, SparseMerkleInternalNode, SparseMerkleRangeProof},
+    transaction::Version,
+};
+
+/// A child of an internal node that is still being built.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum ChildInfo {
+    /// This child is an internal node. The hash of the internal node is stored here if it is
+    /// known, otherwise it's `None`. In the process of restoring a tree, we will only know the
+    /// hash of an internal node after we see all the keys that share the same prefix.
+    Internal { hash: Option<HashValue> },
+
+    /// This child is a leaf node.
+    Leaf { node: LeafNode },
+}
+
+impl ChildInfo {
+    /// Converts `self` to a child, assuming the hash is known if it's an internal node.
+    fn into_child(self, version: Version) -> Child {
+        match self {
+            ChildInfo::Internal { hash } => Child::new(
+                hash.expect("The hash must be known when freezing the parent."),
+                version,
+                false, /* is_leaf */
+            ),
+            ChildInfo::Leaf { node } => Child::new(node.hash(), version, true /* is_leaf */),
+        }
+    }
+}
+
+/// An internal node on the path from root to the last received account, whose children on the
+/// right are not all known yet.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct InternalInfo {
+    /// The node key of this internal node.
+    node_key: NodeKey,
+
+    /// The existing children. Every time a child appears, the corresponding position will be set
+    /// to `Some`.
+    children: [Option<ChildInfo>; 16],
+}
+
+impl InternalInfo {
+    /// Creates an empty internal node with no children.
+    fn new_empty(node_key: NodeKey) -> Self {
+        Self {
+            node_key,
+            children: Default::default(),
+        }
+    }
+
+    fn set_child(&mut self, index: usize, child_info: ChildInfo) {
+        self.children[index] = Some(child_info);
+    }
+
+    /// Returns the index of the rightmost existing child.
+    fn rightmost_child_index(&self) -> usize {
+        self.children
+            .iter()
+            .rposition(Option::is_some)
+            .expect("Must have at least one child.")
+    }
+
+    /// Converts `self` to an internal node, assuming all of its children are already known and
+    /// fully initialized.
+    fn into_internal_node(self, version: Version) -> (NodeKey, InternalNode) {
+        let mut children = Children::new();
+        for (index, child_info) in self.children.iter().enumerate() {
+            if let Some(child_info) = child_info {
+                children.insert(
+                    Nibble::from(index as u8),
+                    child_info.clone().into_child(version),
+                );
+            }
+        }
+        (self.node_key, InternalNode::new(children))
+    }
+}
+
+/// Restores a [`JellyfishMerkleTree`](crate::JellyfishMerkleTree) at a certain version from
+/// chunks of accounts sorted by key. All the nodes are created at that version.
+pub struct JellyfishMerkleRestore<'a, S> {
+    /// The underlying storage.
+    store: &'a S,
+
+    /// The version of the tree we are restoring.
+    version: Version,
+
+    /// The internal nodes we are keeping in memory, from the root down to the parent of the last
+    /// received account. Every one of them is the rightmost child of the previous one.
+    partial_nodes: Vec<InternalInfo>,
+
+    /// The nodes that will not change any more and are waiting to be written to storage.
+    frozen_nodes: NodeBatch,
+
+    /// The most recently added leaf. This is used to ensure the keys come in increasing order and
+    /// to verify the range proof.
+    previous_leaf: Option<LeafNode>,
+
+    /// The number of keys we have received since the restore started.
+    num_keys_received: u64,
+
+    /// When the restore is done, we expect the tree to have this root hash.
+    expected_root_hash: HashValue,
+}
+
+impl<'a, S> JellyfishMerkleRestore<'a, S>
+where
+    S: 'a + TreeWriter,
+{
+    /// Creates a `JellyfishMerkleRestore` that restores the tree at `version` into `store`. The
+    /// restored tree is expected to have `expected_root_hash`.
+    pub fn new(store: &'a S, version: Version, expected_root_hash: HashValue) -> Self {
+        Self {
+            store,
+            version,
+            partial_nodes: vec![InternalInfo::new_empty(NodeKey::new_empty_path(version))],
+            frozen_nodes: NodeBatch::new(),
+            previous_leaf: None,
+            num_keys_received: 0,
+            expected_root_hash,
+        }
+    }
+
+    /// Restores a chunk of accounts. The keys in `chunk` must be in increasing order and all of
+    /// them must be greater than the keys in the previous chunks. `proof` proves that all the
+    /// accounts received so far exist in the tree with the expected root hash. The nodes that will
+    /// not change any more are written to storage once the chunk is verified.
+    pub fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        ensure!(!chunk.is_empty(), "Should not add empty chunks.");
+
+        for (key, blob) in chunk {
+            if let Some(ref prev_leaf) = self.previous_leaf {
+                ensure!(
+                    key > prev_leaf.account_key(),
+                    "Account keys must come in increasing order. {:x} follows {:x}.",
+                    key,
+                    prev_leaf.account_key(),
+                );
+            }
+            let new_leaf = LeafNode::new(key, blob);
+            self.add_one(new_leaf.clone());
+            self.previous_leaf = Some(new_leaf);
+            self.num_keys_received += 1;
+        }
+
+        // Verify what we have added so far is all correct.
+        self.verify(&proof)?;
+
+        self.write_frozen_nodes()
+    }
+
+    /// Restores one account.
+    fn add_one(&mut self, new_leaf: LeafNode) {
+        let nibble_path = NibblePath::new(new_leaf.account_key().to_vec());
+        let mut nibbles = nibble_path.nibbles();
+
+        for i in 0..ROOT_NIBBLE_HEIGHT {
+            let child_index = u8::from(nibbles.next().expect("This nibble must exist.")) as usize;
+
+            assert!(i < self.partial_nodes.len());
+            match self.partial_nodes[i].children[child_index] {
+                // If the next node is an internal node, we just continue the loop with the next
+                // nibble. Here we deal with the leaf case.
+                Some(ChildInfo::Internal { .. }) => continue,
+                Some(ChildInfo::Leaf { ref node }) => {
+                    assert_eq!(
+                        i,
+                        self.partial_nodes.len() - 1,
+                        "If we see a leaf, there will be no more partial internal nodes on lower \
+                         level, since they would have been frozen.",
+                    );
+                    let existing_leaf = node.clone();
+                    self.insert_at_leaf(child_index, existing_leaf, new_leaf, nibbles);
+                    return;
+                }
+                None => {
+                    // This means that we are going to put a leaf in this position. All the
+                    // descendants on the left are now frozen.
+                    self.freeze(i + 1);
+
+                    // We do not add this leaf node to `self.frozen_nodes` because we don't know
+                    // its node key yet. We will know it when the next key comes.
+                    self.partial_nodes[i]
+                        .set_child(child_index, ChildInfo::Leaf { node: new_leaf });
+                    return;
+                }
+            }
+        }
+        unreachable!("Ran out of nibbles without reaching a leaf or an empty position.");
+    }
+
+    /// Inserts `new_leaf` at the position of `existing_leaf`, which is the child at
+    /// `child_index` of the lowest partial node. `remaining_nibbles` is at the position right
+    /// after `child_index` on the path of `new_leaf`.
+    fn insert_at_leaf(
+        &mut self,
+        child_index: usize,
+        existing_leaf: LeafNode,
+        new_leaf: LeafNode,
+        mut remaining_nibbles: NibbleIterator,
+    ) {
+        let num_existing_partial_nodes = self.partial_nodes.len();
+
+        // The node at this position becomes an internal node. Since we may insert more nodes at
+        // this position in the future, we do not know its hash yet.
+        self.partial_nodes[num_existing_partial_nodes - 1]
+            .set_child(child_index, ChildInfo::Internal { hash: None });
+
+        // Next we build the new internal nodes from top to bottom. All these internal nodes
+        // except the bottom one will now have a single internal node child.
+        let common_prefix_len = existing_leaf
+            .account_key()
+            .common_prefix_bits_len(new_leaf.account_key())
+            / 4;
+        for _ in num_existing_partial_nodes..common_prefix_len {
+            let visited_nibbles = remaining_nibbles.visited_nibbles().collect();
+            let next_nibble = remaining_nibbles.next().expect("This nibble must exist.");
+            let mut internal_info =
+                InternalInfo::new_empty(NodeKey::new(self.version, visited_nibbles));
+            internal_info.set_child(
+                u8::from(next_nibble) as usize,
+                ChildInfo::Internal { hash: None },
+            );
+            self.partial_nodes.push(internal_info);
+        }
+
+        // The last internal node will have two leaf node children. We put the existing leaf in
+        // first.
+        let visited_nibbles = remaining_nibbles.visited_nibbles().collect();
+        let mut internal_info =
+            InternalInfo::new_empty(NodeKey::new(self.version, visited_nibbles));
+        let existing_child_index = get_nibble(existing_leaf.account_key(), common_prefix_len);
+        internal_info.set_child(
+            existing_child_index,
+            ChildInfo::Leaf {
+                node: existing_leaf,
+            },
+        );
+        self.partial_nodes.push(internal_info);
+
+        // Freeze the existing leaf before setting the new one, so that `freeze` can always find the
+        // previous leaf as the rightmost child of the lowest partial node.
+        self.freeze(self.partial_nodes.len());
+
+        let new_child_index = get_nibble(new_leaf.account_key(), common_prefix_len);
+        assert!(
+            new_child_index > existing_child_index,
+            "New leaf must be on the right.",
+        );
+        self.partial_nodes
+            .last_mut()
+            .expect("This node must exist.")
+            .set_child(new_child_index, ChildInfo::Leaf { node: new_leaf });
+    }
+
+    /// Puts the nodes that will not be changed later in `self.frozen_nodes`, keeping only the top
+    /// `num_remaining_partial_nodes` partial nodes in memory.
+    fn freeze(&mut self, num_remaining_partial_nodes: usize) {
+        self.freeze_previous_leaf();
+        self.freeze_internal_nodes(num_remaining_partial_nodes);
+    }
+
+    /// Freezes the previously added leaf node. It is always the rightmost child of the lowest
+    /// partial node.
+    fn freeze_previous_leaf(&mut self) {
+        // If this is the very first key, there is no previous leaf to freeze.
+        if self.num_keys_received == 0 {
+            return;
+        }
+
+        let last_node = self
+            .partial_nodes
+            .last()
+            .expect("Must have at least one partial node.");
+        let rightmost_child_index = last_node.rightmost_child_index();
+        match last_node.children[rightmost_child_index] {
+            Some(ChildInfo::Leaf { ref node }) => {
+                let child_node_key = last_node
+                    .node_key
+                    .gen_child_node_key(self.version, Nibble::from(rightmost_child_index as u8));
+                self.frozen_nodes
+                    .insert(child_node_key, node.clone().into());
+            }
+            _ => panic!("The rightmost child of the lowest partial node must be a leaf."),
+        }
+    }
+
+    /// Freezes the partial internal nodes below the top `num_remaining_nodes` ones, from bottom
+    /// to top, filling in the hash of each one in its parent.
+    fn freeze_internal_nodes(&mut self, num_remaining_nodes: usize) {
+        while self.partial_nodes.len() > num_remaining_nodes {
+            let last_node = self.partial_nodes.pop().expect("This node must exist.");
+            let (node_key, internal_node) = last_node.into_internal_node(self.version);
+            let node_hash = internal_node.hash();
+            self.frozen_nodes.insert(node_key, internal_node.into());
+
+            // This internal node must be the rightmost child of its parent, if any.
+            if let Some(parent_node) = self.partial_nodes.last_mut() {
+                let rightmost_child_index = parent_node.rightmost_child_index();
+                match parent_node.children[rightmost_child_index] {
+                    Some(ChildInfo::Internal { ref mut hash }) => {
+                        assert_eq!(hash.replace(node_hash), None);
+                    }
+                    _ => panic!("The rightmost child of the parent must be an internal node."),
+                }
+            }
+        }
+    }
+
+    /// Verifies that all the accounts added so far, from the leftmost one to
+    /// `self.previous_leaf`, are correct, i.e. we are able to construct
+    /// `self.expected_root_hash` by combining all of them and `proof`.
+    fn verify(&self, proof: &SparseMerkleRangeProof) -> Result<()> {
+        let previous_leaf = self
+            .previous_leaf
+            .as_ref()
+            .expect("The previous leaf must exist.");
+        let previous_key = previous_leaf.account_key();
+
+        // The siblings on the right of the path from root to `previous_key` are in the proof. Here
+        // we compute the ones on the left from all the accounts added so far, down to the bottom
+        // level.
+        let mut left_siblings = vec![];
+        let mut num_visited_right_siblings = 0;
+        for (i, bit) in previous_key.iter_bits().enumerate() {
+            if bit {
+                let sibling = if i < self.partial_nodes.len() * 4 {
+                    Self::compute_left_sibling(
+                        &self.partial_nodes[i / 4],
+                        get_nibble(previous_key, i / 4),
+                        (3 - i % 4) as u8,
+                    )
+                } else {
+                    *SPARSE_MERKLE_PLACEHOLDER_HASH
+                };
+                left_siblings.push(sibling);
+            } else {
+                num_visited_right_siblings += 1;
+            }
+        }
+
+        // Now we remove the levels below the leaf. From the bottom up, a level is below the leaf if
+        // the left sibling is a placeholder, or the right sibling is beyond what the proof has,
+        // which means it's a placeholder as well.
+        for bit in previous_key.iter_bits().rev() {
+            if bit {
+                if *left_siblings.last().expect("This sibling must exist.")
+                    == *SPARSE_MERKLE_PLACEHOLDER_HASH
+                {
+                    left_siblings.pop();
+                } else {
+                    break;
+                }
+            } else if num_visited_right_siblings > proof.right_siblings().len() {
+                num_visited_right_siblings -= 1;
+            } else {
+                break;
+            }
+        }
+
+        // The left siblings must be ordered the same way as the right siblings in the proof.
+        left_siblings.reverse();
+
+        verify_sparse_merkle_range(
+            self.expected_root_hash,
+            (previous_key, previous_leaf.blob_hash()),
+            &left_siblings,
+            proof,
+        )
+    }
+
+    /// Computes the hash of the left sibling at `height` within `partial_node` on the path to
+    /// child `n`.
+    fn compute_left_sibling(partial_node: &InternalInfo, n: usize, height: u8) -> HashValue {
+        assert!(height < 4);
+        let width = 1usize << height;
+        let start = (n >> height << height) ^ width;
+        Self::compute_left_sibling_impl(&partial_node.children[start..start + width]).0
+    }
+
+    /// Returns the hash of the subtree formed by `children` and whether it should be treated as a
+    /// leaf, following the same rules as the hash of an internal node.
+    fn compute_left_sibling_impl(children: &[Option<ChildInfo>]) -> (HashValue, bool) {
+        assert!(children.len().is_power_of_two());
+
+        if children.len() == 1 {
+            match &children[0] {
+                Some(ChildInfo::Internal { hash }) => {
+                    (hash.expect("The hash must be known."), false)
+                }
+                Some(ChildInfo::Leaf { node }) => (node.hash(), true),
+                None => (*SPARSE_MERKLE_PLACEHOLDER_HASH, true),
+            }
+        } else {
+            let half = children.len() / 2;
+            let (left_hash, left_is_leaf) = Self::compute_left_sibling_impl(&children[..half]);
+            let (right_hash, right_is_leaf) = Self::compute_left_sibling_impl(&children[half..]);
+
+            if left_hash == *SPARSE_MERKLE_PLACEHOLDER_HASH && right_is_leaf {
+                (right_hash, true)
+            } else if left_is_leaf && right_hash == *SPARSE_MERKLE_PLACEHOLDER_HASH {
+                (left_hash, true)
+            } else {
+                (
+                    SparseMerkleInternalNode::new(left_hash, right_hash).hash(),
+                    false,
+                )
+            }
+        }
+    }
+
+    /// Finishes the restore by freezing and writing all the remaining nodes. Fails if the tree
+    /// doesn't have the expected root hash.
+    pub fn finish(mut self) -> Result<()> {
+        let root_node_key = NodeKey::new_empty_path(self.version);
+
+        if self.num_keys_received == 0 {
+            // Nothing was added, so the tree is empty.
+            ensure!(
+                self.expected_root_hash == *SPARSE_MERKLE_PLACEHOLDER_HASH,
+                "Expected a non-empty tree with root hash {:x}, but no account was received.",
+                self.expected_root_hash,
+            );
+            self.frozen_nodes.insert(root_node_key, Node::new_null());
+            return self.write_frozen_nodes();
+        }
+
+        // If the entire tree has a single leaf, the leaf itself is the root.
+        if self.partial_nodes.len() == 1 {
+            let only_leaf = {
+                let mut children = self.partial_nodes[0].children.iter().flatten();
+                match (children.next(), children.next()) {
+                    (Some(ChildInfo::Leaf { node }), None) => Some(node.clone()),
+                    _ => None,
+                }
+            };
+            if let Some(node) = only_leaf {
+                ensure!(
+                    node.hash() == self.expected_root_hash,
+                    "Root hashes do not match. Actual root hash: {:x}. Expected root hash: {:x}.",
+                    node.hash(),
+                    self.expected_root_hash,
+                );
+                self.frozen_nodes.insert(root_node_key, node.into());
+                return self.write_frozen_nodes();
+            }
+        }
+
+        self.freeze(0);
+        let root_hash = self
+            .frozen_nodes
+            .get(&root_node_key)
+            .expect("Root node must have been frozen.")
+            .hash();
+        ensure!(
+            root_hash == self.expected_root_hash,
+            "Root hashes do not match. Actual root hash: {:x}. Expected root hash: {:x}.",
+            root_hash,
+            self.expected_root_hash,
+        );
+        self.write_frozen_nodes()
+    }
+
+    /// Writes all the frozen nodes to storage.
+    fn write_frozen_nodes(&mut self) -> Result<()> {
+        let node_batch = std::mem::replace(&mut self.frozen_nodes, NodeBatch::new());
+        let num_new_leaves = node_batch.values().filter(|node| node.is_leaf()).count();
+        self.store.write_tree_update_batch(TreeUpdateBatch {
+            node_batch,
+            num_new_leaves,
+            ..Default::default()
+        })
+    }
+}
+
+/// Returns the `index`-th nibble of `key` as a `usize`, which is convenient for indexing into the
+/// children of an [`InternalInfo`].
+fn get_nibble(key: HashValue, index: usize) -> usize {
+    let byte = key[index / 2];
+    (if index % 2 == 0 {
+        byte >> 4
+    } else {
+        byte & 0x0f
+    }) as usize
+}
@@ -0,0 +1,157 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    iterator::JellyfishMerkleIterator, mock_tree_store::MockTreeStore,
+    restore::JellyfishMerkleRestore, JellyfishMerkleTree, TreeReader, TreeWriter,
+};
+use crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
+use failure::prelude::*;
+use proptest::{collection::btree_map, prelude::*};
+use std::collections::BTreeMap;
+use types::{account_state_blob::AccountStateBlob, transaction::Version};
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_restore_without_interruption(
+        btree in btree_map(any::<HashValue>(), any::<AccountStateBlob>(), 1..1000),
+        target_version in 0u64..2000,
+        chunk_size in 1usize..100,
+    ) {
+        let (db, version) = init_mock_db(&btree.clone().into_iter().collect::<Vec<_>>());
+        let tree = JellyfishMerkleTree::new(&db);
+        let expected_root_hash = tree.get_root_hash(version).unwrap();
+
+        let restore_db = MockTreeStore::default();
+        restore_in_chunks(&db, version, &restore_db, target_version, expected_root_hash, chunk_size)
+            .unwrap();
+
+        assert_success(&restore_db, expected_root_hash, &btree, target_version);
+    }
+
+    #[test]
+    fn test_restore_rejects_tampered_chunk(
+        btree in btree_map(any::<HashValue>(), any::<AccountStateBlob>(), 2..100),
+        drop_index in any::<prop::sample::Index>(),
+    ) {
+        let (db, version) = init_mock_db(&btree.clone().into_iter().collect::<Vec<_>>());
+        let tree = JellyfishMerkleTree::new(&db);
+        let expected_root_hash = tree.get_root_hash(version).unwrap();
+
+        // Leave one account out and send everything else in a single chunk.
+        let mut chunk = btree.into_iter().collect::<Vec<_>>();
+        chunk.remove(drop_index.index(chunk.len() - 1));
+        let proof = tree
+            .get_range_proof(chunk.last().unwrap().0, version)
+            .unwrap();
+
+        let restore_db = MockTreeStore::default();
+        let mut restore = JellyfishMerkleRestore::new(&restore_db, version, expected_root_hash);
+        prop_assert!(restore.add_chunk(chunk, proof).is_err());
+    }
+}
+
+#[test]
+fn test_restore_empty_tree() {
+    let restore_db = MockTreeStore::default();
+    let version = 10;
+    JellyfishMerkleRestore::new(&restore_db, version, *SPARSE_MERKLE_PLACEHOLDER_HASH)
+        .finish()
+        .unwrap();
+
+    assert_success(
+        &restore_db,
+        *SPARSE_MERKLE_PLACEHOLDER_HASH,
+        &BTreeMap::new(),
+        version,
+    );
+}
+
+#[test]
+fn test_restore_empty_tree_with_wrong_root_hash() {
+    let restore_db = MockTreeStore::default();
+    assert!(
+        JellyfishMerkleRestore::new(&restore_db, 0, HashValue::random())
+            .finish()
+            .is_err()
+    );
+}
+
+#[test]
+fn test_restore_single_leaf() {
+    let mut btree = BTreeMap::new();
+    btree.insert(HashValue::random(), AccountStateBlob::from(vec![1, 2, 3]));
+    let (db, version) = init_mock_db(&btree.clone().into_iter().collect::<Vec<_>>());
+    let expected_root_hash = JellyfishMerkleTree::new(&db)
+        .get_root_hash(version)
+        .unwrap();
+
+    let restore_db = MockTreeStore::default();
+    restore_in_chunks(&db, version, &restore_db, 5, expected_root_hash, 1).unwrap();
+
+    assert_success(&restore_db, expected_root_hash, &btree, 5);
+}
+
+fn init_mock_db(kvs: &[(HashValue, AccountStateBlob)]) -> (MockTreeStore, Version) {
+    assert!(!kvs.is_empty());
+
+    let db = MockTreeStore::default();
+    let tree = JellyfishMerkleTree::new(&db);
+
+    // Put the accounts in over a few versions so that the source tree has nodes of different
+    // versions, while the restored one has all of them at the target version.
+    for (i, (key, value)) in kvs.iter().enumerate() {
+        let (_root_hash, batch) = tree
+            .put_blob_set(vec![(*key, value.clone())], i as Version)
+            .unwrap();
+        db.write_tree_update_batch(batch).unwrap();
+    }
+
+    (db, (kvs.len() - 1) as Version)
+}
+
+/// Restores the tree at `version` in `db` to `target_version` in `restore_db`, sending
+/// `chunk_size` accounts at a time.
+fn restore_in_chunks<R: TreeReader>(
+    db: &R,
+    version: Version,
+    restore_db: &MockTreeStore,
+    target_version: Version,
+    expected_root_hash: HashValue,
+    chunk_size: usize,
+) -> Result<()> {
+    let tree = JellyfishMerkleTree::new(db);
+    let accounts = JellyfishMerkleIterator::new(db, version, HashValue::zero())?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut restore = JellyfishMerkleRestore::new(restore_db, target_version, expected_root_hash);
+    for chunk in accounts.chunks(chunk_size) {
+        let rightmost_key = chunk.last().expect("Chunk should not be empty.").0;
+        let proof = tree.get_range_proof(rightmost_key, version)?;
+        restore.add_chunk(chunk.to_vec(), proof)?;
+    }
+    restore.finish()
+}
+
+fn assert_success(
+    db: &MockTreeStore,
+    expected_root_hash: HashValue,
+    btree: &BTreeMap<HashValue, AccountStateBlob>,
+    version: Version,
+) {
+    let tree = JellyfishMerkleTree::new(db);
+    for (key, value) in btree {
+        assert_eq!(tree.get(*key, version).unwrap(), Some(value.clone()));
+    }
+
+    let actual_root_hash = tree.get_root_hash(version).unwrap();
+    assert_eq!(actual_root_hash, expected_root_hash);
+
+    let restored = JellyfishMerkleIterator::new(db, version, HashValue::zero())
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(restored, btree.clone().into_iter().collect::<Vec<_>>());
+}
@@ -0,0 +1,258 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides the APIs used by the offline backup and restore tooling.
+//!
+//! [`BackupHandler`] reads out everything needed to reconstruct a DB: the ledger infos, the
+//! transactions together with their infos and events, the frozen subtrees of the transaction
+//! accumulator at the first backed up version and all the account states at the backup version,
+//! along with range proofs for chunks of them.
+//!
+//! [`RestoreHandler`] writes those back into an empty DB. It does direct sanity checks, like
+//! verifying each chunk of account states against the expected state root hash, but it's the
+//! caller's responsibility to verify the data against a trusted [`LedgerInfoWithSignatures`] before
+//! feeding it in.
+
+#[cfg(test)]
+mod test;
+
+use crate::{
+    change_set::ChangeSet, schema::transaction_accumulator::TransactionAccumulatorSchema, LibraDB,
+};
+use crypto::{hash::CryptoHash, HashValue};
+use failure::prelude::*;
+use jellyfish_merkle::{restore::JellyfishMerkleRestore, JellyfishMerkleTree, TreeWriter};
+use types::{
+    account_state_blob::AccountStateBlob,
+    crypto_proxies::LedgerInfoWithSignatures,
+    proof::{position::FrozenSubTreeIterator, SparseMerkleRangeProof},
+    transaction::{TransactionListWithProof, Version},
+};
+
+/// Provides read access to the data to be backed up.
+pub struct BackupHandler<'a> {
+    db: &'a LibraDB,
+}
+
+impl<'a> BackupHandler<'a> {
+    pub(crate) fn new(db: &'a LibraDB) -> Self {
+        Self { db }
+    }
+
+    /// Returns the latest ledger info, against which a backup is taken by default.
+    pub fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
+        self.db.ledger_store.get_latest_ledger_info()
+    }
+
+    /// Returns all the ledger infos in the DB, i.e. the one ending each earlier epoch and the
+    /// latest one of the current epoch, ordered by epoch.
+    pub fn get_epoch_ledger_infos(&self) -> Result<Vec<LedgerInfoWithSignatures>> {
+        self.db.ledger_store.get_latest_ledger_infos_per_epoch(0)
+    }
+
+    /// Returns up to `limit` transactions starting from `start_version`, with their events and
+    /// proofs relative to the ledger at `ledger_version`.
+    pub fn get_transactions(
+        &self,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<TransactionListWithProof> {
+        self.db.get_transactions(
+            start_version,
+            limit,
+            ledger_version,
+            true, /* fetch_events */
+        )
+    }
+
+    /// Returns the root hashes of the frozen subtrees of the transaction accumulator when it has
+    /// `num_leaves` leaves, from left to right.
+    pub fn get_frozen_subtree_hashes(&self, num_leaves: u64) -> Result<Vec<HashValue>> {
+        if num_leaves == 0 {
+            return Ok(vec![]);
+        }
+        self.db
+            .ledger_store
+            .get_ledger_frozen_subtree_hashes(num_leaves - 1)
+    }
+
+    /// Returns an iterator over all the account states at `version`, ordered by the hashed account
+    /// address.
+    pub fn get_account_iter(
+        &self,
+        version: Version,
+    ) -> Result<impl Iterator<Item = Result<(HashValue, AccountStateBlob)>> + 'a> {
+        self.db.state_store.get_account_iter(version)
+    }
+
+    /// Returns the proof that all the account states at `version` up to the one with
+    /// `rightmost_key` exist in the state tree.
+    pub fn get_account_state_range_proof(
+        &self,
+        rightmost_key: HashValue,
+        version: Version,
+    ) -> Result<SparseMerkleRangeProof> {
+        JellyfishMerkleTree::new(&self.db.state_store).get_range_proof(rightmost_key, version)
+    }
+}
+
+/// Provides write access to an empty DB to restore a backup into.
+pub struct RestoreHandler<'a> {
+    db: &'a LibraDB,
+}
+
+impl<'a> RestoreHandler<'a> {
+    pub(crate) fn new(db: &'a LibraDB) -> Self {
+        Self { db }
+    }
+
+    /// Returns the version the next restored transaction is expected to have, which is 0 if the
+    /// DB doesn't have any transaction yet.
+    pub fn get_next_expected_version(&self) -> Result<Version> {
+        Ok(self
+            .db
+            .ledger_store
+            .get_latest_transaction_info_option()?
+            .map_or(0, |(version, _)| version + 1))
+    }
+
+    /// Saves the root hashes of the frozen subtrees of the transaction accumulator with
+    /// `num_leaves` leaves, so that transactions can be restored starting from version
+    /// `num_leaves` without the history before it.
+    pub fn save_frozen_subtrees(
+        &self,
+        num_leaves: u64,
+        frozen_subtrees: &[HashValue],
+    ) -> Result<()> {
+        ensure!(
+            self.get_next_expected_version()? == 0,
+            "Frozen subtrees can only be saved into an empty DB."
+        );
+        let positions: Vec<_> = FrozenSubTreeIterator::new(num_leaves).collect();
+        ensure!(
+            positions.len() == frozen_subtrees.len(),
+            "Wrong number of frozen subtrees for {} leaves. Expected: {}, got: {}",
+            num_leaves,
+            positions.len(),
+            frozen_subtrees.len(),
+        );
+
+        let mut cs = ChangeSet::new();
+        positions
+            .iter()
+            .zip(frozen_subtrees)
+            .map(|(pos, hash)| cs.batch.put::<TransactionAccumulatorSchema>(pos, hash))
+            .collect::<Result<Vec<_>>>()?;
+        self.db.db.write_schemas(cs.batch)
+    }
+
+    /// Saves a chunk of transactions together with their infos and events, returning the root
+    /// hash of the transaction accumulator after they are appended.
+    pub fn save_transactions(
+        &self,
+        txn_list_with_proof: &TransactionListWithProof,
+    ) -> Result<HashValue> {
+        let first_version = txn_list_with_proof
+            .first_transaction_version
+            .ok_or_else(|| format_err!("Can't restore an empty transaction list."))?;
+        let num_txns = txn_list_with_proof.len() as u64;
+        let events = txn_list_with_proof
+            .events
+            .as_ref()
+            .ok_or_else(|| format_err!("Events are required to restore transactions."))?;
+        ensure!(
+            events.len() as u64 == num_txns,
+            "Number of event lists ({}) doesn't match number of transactions ({}).",
+            events.len(),
+            num_txns,
+        );
+        let next_expected_version = self.get_next_expected_version()?;
+        // The DB is either empty with the frozen subtrees saved at `first_version`, or has exactly
+        // the transactions before `first_version`.
+        ensure!(
+            first_version == next_expected_version
+                || (next_expected_version == 0
+                    && self
+                        .db
+                        .ledger_store
+                        .get_ledger_frozen_subtree_hashes(first_version - 1)
+                        .is_ok()),
+            "Transactions not applicable: first_version {}, next expected version {}.",
+            first_version,
+            next_expected_version,
+        );
+
+        let mut cs = ChangeSet::new();
+        let mut txn_infos = Vec::with_capacity(num_txns as usize);
+        for (idx, ((signed_txn, txn_info), events)) in txn_list_with_proof
+            .transaction_and_infos
+            .iter()
+            .zip(events)
+            .enumerate()
+        {
+            let version = first_version + idx as u64;
+            ensure!(
+                signed_txn.hash() == txn_info.signed_transaction_hash(),
+                "Signed transaction hash doesn't match the transaction info at version {}.",
+                version,
+            );
+            let event_root_hash = self.db.event_store.put_events(version, events, &mut cs)?;
+            ensure!(
+                event_root_hash == txn_info.event_root_hash(),
+                "Event root hash doesn't match the transaction info at version {}.",
+                version,
+            );
+            self.db
+                .transaction_store
+                .put_transaction(version, signed_txn, &mut cs)?;
+            txn_infos.push(txn_info.clone());
+        }
+        let root_hash =
+            self.db
+                .ledger_store
+                .put_transaction_infos(first_version, &txn_infos, &mut cs)?;
+
+        let (sealed_cs, _counters) = self.db.seal_change_set(first_version, num_txns, cs)?;
+        self.db.commit(sealed_cs)?;
+        Ok(root_hash)
+    }
+
+    /// Returns a [`JellyfishMerkleRestore`] that rebuilds the account state tree at `version`
+    /// from chunks of account states, each verified against `expected_root_hash` with a range
+    /// proof, and writes the tree nodes to the DB as it goes.
+    pub fn get_state_restore_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> JellyfishMerkleRestore<'a, impl TreeWriter> {
+        JellyfishMerkleRestore::new(&self.db.state_store, version, expected_root_hash)
+    }
+
+    /// Saves the ledger infos and makes the one with the highest epoch the latest. This is meant
+    /// to be the last step of a restore so that a partially restored DB doesn't look bootstrapped.
+    pub fn save_ledger_infos(&self, ledger_infos: &[LedgerInfoWithSignatures]) -> Result<()> {
+        let latest = match ledger_infos
+            .iter()
+            .max_by_key(|li| li.ledger_info().epoch_num())
+        {
+            Some(li) => li,
+            None => return Ok(()),
+        };
+        let next_expected_version = self.get_next_expected_version()?;
+        ensure!(
+            latest.ledger_info().version() < next_expected_version,
+            "Latest ledger info at version {} is beyond the restored transactions.",
+            latest.ledger_info().version(),
+        );
+
+        let mut cs = ChangeSet::new();
+        ledger_infos
+            .iter()
+            .map(|li| self.db.ledger_store.put_ledger_info(li, &mut cs))
+            .collect::<Result<Vec<_>>>()?;
+        self.db.db.write_schemas(cs.batch)?;
+        self.db.ledger_store.set_latest_ledger_info(latest.clone());
+        Ok(())
+    }
+}
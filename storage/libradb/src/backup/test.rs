@@ -0,0 +1,125 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{mock_genesis::db_with_mock_genesis, test_helper::arb_blocks_to_commit};
+use proptest::prelude::*;
+use tools::tempdir::TempPath;
+use types::transaction::TransactionToCommit;
+
+fn restore_and_verify(
+    src_db: &LibraDB,
+    target_ledger_info: &LedgerInfoWithSignatures,
+    first_version: Version,
+) -> Result<()> {
+    let tmp_dir = TempPath::new();
+    let db = LibraDB::new(&tmp_dir);
+    let backup = src_db.get_backup_handler();
+    let restore = db.get_restore_handler();
+    let target_version = target_ledger_info.ledger_info().version();
+
+    if first_version > 0 {
+        restore.save_frozen_subtrees(
+            first_version,
+            &backup.get_frozen_subtree_hashes(first_version)?,
+        )?;
+    }
+
+    let mut version = first_version;
+    let mut root_hash = HashValue::zero();
+    while version <= target_version {
+        let txn_list_with_proof =
+            backup.get_transactions(version, 2 /* limit */, target_version)?;
+        txn_list_with_proof.verify(target_ledger_info.ledger_info(), Some(version))?;
+        root_hash = restore.save_transactions(&txn_list_with_proof)?;
+        version += txn_list_with_proof.len() as u64;
+    }
+    assert_eq!(
+        root_hash,
+        target_ledger_info
+            .ledger_info()
+            .transaction_accumulator_hash()
+    );
+
+    let state_root_hash = src_db
+        .ledger_store
+        .get_transaction_info(target_version)?
+        .state_root_hash();
+    let accounts = backup
+        .get_account_iter(target_version)?
+        .collect::<Result<Vec<_>>>()?;
+    // A chunk with an account left out should be rejected.
+    if accounts.len() > 1 {
+        let mut tampered_chunk = accounts.clone();
+        tampered_chunk.remove(0);
+        let proof = backup
+            .get_account_state_range_proof(tampered_chunk.last().unwrap().0, target_version)?;
+        assert!(restore
+            .get_state_restore_receiver(target_version, state_root_hash)
+            .add_chunk(tampered_chunk, proof)
+            .is_err());
+    }
+    let mut receiver = restore.get_state_restore_receiver(target_version, state_root_hash);
+    for chunk in accounts.chunks(2) {
+        let proof =
+            backup.get_account_state_range_proof(chunk.last().unwrap().0, target_version)?;
+        receiver.add_chunk(chunk.to_vec(), proof)?;
+    }
+    receiver.finish()?;
+    restore.save_ledger_infos(&backup.get_epoch_ledger_infos()?)?;
+
+    let expected = src_db.get_startup_info()?.unwrap();
+    let actual = db.get_startup_info()?.unwrap();
+    assert_eq!(actual.latest_version, expected.latest_version);
+    assert_eq!(
+        actual.account_state_root_hash,
+        expected.account_state_root_hash
+    );
+    assert_eq!(
+        actual.ledger_frozen_subtree_hashes,
+        expected.ledger_frozen_subtree_hashes
+    );
+
+    // The restored transactions can be served with proofs.
+    db.get_transactions(
+        first_version,
+        target_version - first_version + 1,
+        target_version,
+        true, /* fetch_events */
+    )?
+    .verify(target_ledger_info.ledger_info(), Some(first_version))
+}
+
+fn test_backup_and_restore_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    let tmp_dir = TempPath::new();
+    let db = db_with_mock_genesis(&tmp_dir)?;
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            &txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs.clone()),
+        )?;
+        cur_ver += txns_to_commit.len() as u64;
+    }
+
+    let target_ledger_info = db.get_backup_handler().get_latest_ledger_info()?;
+    restore_and_verify(&db, &target_ledger_info, 0 /* first_version */)?;
+    restore_and_verify(
+        &db,
+        &target_ledger_info,
+        cur_ver / 2, /* first_version */
+    )
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_backup_and_restore(input in arb_blocks_to_commit()) {
+        test_backup_and_restore_impl(input).unwrap();
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides [`DebugHandler`], the APIs used by the offline inspection tooling to look
+//! inside a DB, typically one opened with [`LibraDB::open_readonly`].
+
+#[cfg(test)]
+mod test;
+
+use crate::LibraDB;
+use failure::prelude::*;
+use std::collections::BTreeMap;
+use types::{
+    account_address::AccountAddress,
+    account_state_blob::AccountStateWithProof,
+    contract_event::ContractEvent,
+    crypto_proxies::LedgerInfoWithSignatures,
+    transaction::{SignedTransaction, TransactionInfo, Version},
+};
+
+/// Provides raw read access to what's in the DB.
+pub struct DebugHandler<'a> {
+    db: &'a LibraDB,
+}
+
+impl<'a> DebugHandler<'a> {
+    pub(crate) fn new(db: &'a LibraDB) -> Self {
+        Self { db }
+    }
+
+    /// Returns the latest ledger info.
+    pub fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
+        self.db.ledger_store.get_latest_ledger_info()
+    }
+
+    /// Returns the ledger infos ending each earlier epoch and the latest one of the current epoch,
+    /// ordered by epoch.
+    pub fn get_epoch_ledger_infos(&self) -> Result<Vec<LedgerInfoWithSignatures>> {
+        self.db.ledger_store.get_latest_ledger_infos_per_epoch(0)
+    }
+
+    /// Returns the transaction at `version` together with its info and the events it emitted.
+    pub fn get_transaction(
+        &self,
+        version: Version,
+    ) -> Result<(SignedTransaction, TransactionInfo, Vec<ContractEvent>)> {
+        Ok((
+            self.db.transaction_store.get_transaction(version)?,
+            self.db.ledger_store.get_transaction_info(version)?,
+            self.db.event_store.get_events_by_version(version)?,
+        ))
+    }
+
+    /// Returns the state of the account at `address` at `version`, with proof relative to the
+    /// latest ledger info.
+    pub fn get_account_state_with_proof(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<AccountStateWithProof> {
+        let ledger_version = self.db.get_latest_version()?;
+        self.db
+            .get_account_state_with_proof(address, version, ledger_version)
+    }
+
+    /// Returns the approximate size of each column family in bytes.
+    pub fn get_column_family_sizes(&self) -> Result<BTreeMap<String, u64>> {
+        self.db.db.get_approximate_sizes_cf()
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{mock_genesis::db_with_mock_genesis, test_helper::arb_blocks_to_commit};
+use proptest::prelude::*;
+use tools::tempdir::TempPath;
+use types::transaction::TransactionToCommit;
+
+fn test_debug_handler_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    let tmp_dir = TempPath::new();
+    let db = db_with_mock_genesis(&tmp_dir)?;
+
+    let mut cur_ver = 0;
+    let mut txns = vec![];
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            &txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs.clone()),
+        )?;
+        cur_ver += txns_to_commit.len() as u64;
+        txns.extend(txns_to_commit.iter().cloned());
+    }
+
+    // Inspect the DB while it's still open by another instance.
+    let readonly_db = LibraDB::open_readonly(&tmp_dir)?;
+    let handler = readonly_db.get_debug_handler();
+    let (_, latest_ledger_info) = input.last().unwrap();
+    assert_eq!(handler.get_latest_ledger_info()?, *latest_ledger_info);
+    assert_eq!(
+        handler.get_epoch_ledger_infos()?,
+        db.get_backup_handler().get_epoch_ledger_infos()?
+    );
+
+    for (i, txn_to_commit) in txns.iter().enumerate() {
+        let version = i as Version + 1;
+        let (signed_txn, txn_info, events) = handler.get_transaction(version)?;
+        assert_eq!(signed_txn, *txn_to_commit.signed_txn());
+        assert_eq!(txn_info.gas_used(), txn_to_commit.gas_used());
+        assert_eq!(events, txn_to_commit.events().to_vec());
+
+        for (address, blob) in txn_to_commit.account_states() {
+            let account_state_with_proof =
+                handler.get_account_state_with_proof(*address, version)?;
+            assert_eq!(account_state_with_proof.blob.as_ref(), Some(blob));
+            account_state_with_proof.verify(latest_ledger_info.ledger_info(), version, *address)?;
+        }
+    }
+
+    assert!(handler.get_column_family_sizes()?.contains_key("default"));
+
+    // Writes are refused.
+    let (txns_to_commit, ledger_info_with_sigs) = input.first().unwrap();
+    assert!(readonly_db
+        .save_transactions(
+            &txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs.clone()),
+        )
+        .is_err());
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_debug_handler(input in arb_blocks_to_commit()) {
+        test_debug_handler_impl(input).unwrap();
+    }
+}
@@ -94,6 +94,20 @@ impl EventStore {
         Ok(ver)
     }
 
+    /// Get the smallest sequence number on `event_key` still in the DB, which is larger than 0 if
+    /// the ledger history has been pruned.
+    pub fn get_first_sequence_number(&self, event_key: &EventKey) -> Result<Option<u64>> {
+        let mut iter = self.db.iter::<EventByKeySchema>(ReadOptions::default())?;
+        iter.seek(&(*event_key, 0))?;
+        if let Some(res) = iter.next() {
+            let ((key, seq), _) = res?;
+            if key == *event_key {
+                return Ok(Some(seq));
+            }
+        }
+        Ok(None)
+    }
+
     /// Get the latest sequence number on `event_key` considering all transactions with versions
     /// no greater than `ledger_version`.
     pub fn get_latest_sequence_number(
@@ -116,7 +130,9 @@ impl EventStore {
                 let mut n_try_recent = 10;
                 #[cfg(any(test, feature = "testing"))]
                 let mut n_try_recent = 1;
-                while seq > 0 && n_try_recent > 0 {
+                // Older events might have been pruned.
+                let first_seq = self.get_first_sequence_number(event_key)?.unwrap_or(0);
+                while seq > first_seq && n_try_recent > 0 {
                     seq -= 1;
                     n_try_recent -= 1;
                     let ver = self.get_txn_ver_by_seq_num(event_key, seq)?;
@@ -126,7 +142,7 @@ impl EventStore {
                 }
 
                 // Fall back to binary search if the above short linear search didn't work out.
-                let (mut begin, mut end) = (0, seq);
+                let (mut begin, mut end) = (first_seq, seq);
                 while begin < end {
                     let mid = end - (end - begin) / 2;
                     let ver = self.get_txn_ver_by_seq_num(event_key, mid)?;
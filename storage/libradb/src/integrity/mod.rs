@@ -0,0 +1,220 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module implements [`LibraDB::verify_integrity`], which recomputes everything that can be
+//! derived from the raw data in the DB and checks it against what's stored, in order to catch
+//! silent corruption before bad proofs are served.
+
+#[cfg(test)]
+mod test;
+
+use crate::{schema::transaction_accumulator::TransactionAccumulatorSchema, LibraDB};
+use crypto::{
+    hash::{CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher},
+    HashValue,
+};
+use failure::prelude::*;
+use jellyfish_merkle::{node_type::NodeKey, TreeReader};
+use logger::prelude::*;
+use std::collections::BTreeMap;
+use types::{
+    contract_event::ContractEvent,
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier},
+    proof::{accumulator::Accumulator, position::Position, TransactionAccumulatorInternalNode},
+    transaction::Version,
+    validator_set::ValidatorSet,
+};
+
+/// Summarizes what [`LibraDB::verify_integrity`] has checked.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IntegrityReport {
+    /// Number of transactions whose info, events and accumulator nodes are checked.
+    pub num_transactions: u64,
+    /// Number of transactions whose state root hash is checked against the state tree. The state
+    /// tree of a version is not available if it has been pruned or the DB was restored from a
+    /// snapshot at a later version.
+    pub num_state_roots: u64,
+    /// Number of ledger infos whose transaction accumulator root hash is checked.
+    pub num_ledger_infos: u64,
+    /// Number of ledger infos whose signatures are checked against the validator set of their
+    /// epoch. The validator set of an epoch is only known if the ledger info ending the previous
+    /// epoch, carrying it, is in the DB.
+    pub num_ledger_info_signatures: u64,
+}
+
+pub(crate) fn verify_integrity(
+    db: &LibraDB,
+    start_version: Version,
+    end_version: Version,
+) -> Result<IntegrityReport> {
+    ensure!(
+        start_version <= end_version,
+        "Start version {} is greater than end version {}.",
+        start_version,
+        end_version,
+    );
+    let min_readable_version = db.pruner.get_min_readable_ledger_version();
+    ensure!(
+        start_version >= min_readable_version,
+        "Ledger history before version {} has been pruned.",
+        min_readable_version,
+    );
+    let (latest_version, _) = db.ledger_store.get_latest_transaction_info()?;
+    ensure!(
+        end_version <= latest_version,
+        "End version {} is greater than the latest version {}.",
+        end_version,
+        latest_version,
+    );
+
+    let mut report = IntegrityReport::default();
+    let ledger_infos = db.ledger_store.get_latest_ledger_infos_per_epoch(0)?;
+    let mut ledger_infos_by_version = BTreeMap::new();
+    for li in &ledger_infos {
+        ledger_infos_by_version
+            .entry(li.ledger_info().version())
+            .or_insert_with(Vec::new)
+            .push(li);
+    }
+
+    let frozen_subtrees = if start_version > 0 {
+        db.ledger_store
+            .get_ledger_frozen_subtree_hashes(start_version - 1)?
+    } else {
+        vec![]
+    };
+    let mut accumulator =
+        Accumulator::<TransactionAccumulatorHasher>::new(frozen_subtrees, start_version)?;
+
+    for version in start_version..=end_version {
+        let txn_info = db.ledger_store.get_transaction_info(version)?;
+
+        let signed_txn = db.transaction_store.get_transaction(version)?;
+        ensure!(
+            signed_txn.hash() == txn_info.signed_transaction_hash(),
+            "Transaction hash doesn't match its info at version {}.",
+            version,
+        );
+
+        let event_hashes = db
+            .event_store
+            .get_events_by_version(version)?
+            .iter()
+            .map(ContractEvent::hash)
+            .collect::<Vec<_>>();
+        let event_root_hash = Accumulator::<EventAccumulatorHasher>::default()
+            .append(event_hashes)
+            .root_hash();
+        ensure!(
+            event_root_hash == txn_info.event_root_hash(),
+            "Event root hash doesn't match the transaction info at version {}. {:?} vs {:?}",
+            version,
+            event_root_hash,
+            txn_info.event_root_hash(),
+        );
+
+        if let Some(root) = db
+            .state_store
+            .get_node_option(&NodeKey::new_empty_path(version))?
+        {
+            ensure!(
+                root.hash() == txn_info.state_root_hash(),
+                "State root hash doesn't match the transaction info at version {}. {:?} vs {:?}",
+                version,
+                root.hash(),
+                txn_info.state_root_hash(),
+            );
+            report.num_state_roots += 1;
+        }
+
+        verify_transaction_accumulator_nodes(db, version, txn_info.hash())?;
+        accumulator = accumulator.append(vec![txn_info.hash()]);
+        report.num_transactions += 1;
+
+        for li in ledger_infos_by_version.remove(&version).unwrap_or_default() {
+            ensure!(
+                li.ledger_info().transaction_accumulator_hash() == accumulator.root_hash(),
+                "Transaction accumulator root hash doesn't match ledger info of epoch {}. \
+                 {:?} vs {:?}",
+                li.ledger_info().epoch_num(),
+                li.ledger_info().transaction_accumulator_hash(),
+                accumulator.root_hash(),
+            );
+            report.num_ledger_infos += 1;
+        }
+    }
+
+    for (prev, li) in ledger_infos.iter().zip(ledger_infos.iter().skip(1)) {
+        let version = li.ledger_info().version();
+        if version < start_version || version > end_version {
+            continue;
+        }
+        let epoch = li.ledger_info().epoch_num();
+        match prev.ledger_info().next_validator_set() {
+            Some(validator_set) if prev.ledger_info().epoch_num() + 1 == epoch => {
+                verify_signatures(li, validator_set)?;
+                report.num_ledger_info_signatures += 1;
+            }
+            _ => warn!(
+                "Validator set of epoch {} unknown, signatures not checked.",
+                epoch
+            ),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Checks the transaction accumulator nodes frozen by appending the leaf at `version`, i.e. the
+/// leaf itself and all its ancestors whose subtree becomes full, by recomputing them from their
+/// left siblings, which have been checked earlier.
+fn verify_transaction_accumulator_nodes(
+    db: &LibraDB,
+    version: Version,
+    leaf_hash: HashValue,
+) -> Result<()> {
+    let mut position = Position::from_leaf_index(version);
+    let mut hash = leaf_hash;
+    loop {
+        let stored = db
+            .db
+            .get::<TransactionAccumulatorSchema>(&position)?
+            .ok_or_else(|| format_err!("Transaction accumulator node {} missing.", position))?;
+        ensure!(
+            stored == hash,
+            "Transaction accumulator node {} is corrupt. {:?} vs {:?}",
+            position,
+            stored,
+            hash,
+        );
+        if position.is_left_child() {
+            return Ok(());
+        }
+        let left_sibling = db
+            .db
+            .get::<TransactionAccumulatorSchema>(&position.sibling())?
+            .ok_or_else(|| {
+                format_err!(
+                    "Transaction accumulator node {} missing.",
+                    position.sibling()
+                )
+            })?;
+        hash = TransactionAccumulatorInternalNode::new(left_sibling, hash).hash();
+        position = position.parent();
+    }
+}
+
+fn verify_signatures(
+    ledger_info_with_sigs: &LedgerInfoWithSignatures,
+    validator_set: &ValidatorSet,
+) -> Result<()> {
+    ledger_info_with_sigs
+        .verify(&ValidatorVerifier::from(validator_set))
+        .map_err(|e| {
+            format_err!(
+                "Signatures on ledger info of epoch {} don't verify: {}",
+                ledger_info_with_sigs.ledger_info().epoch_num(),
+                e,
+            )
+        })
+}
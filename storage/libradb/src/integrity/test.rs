@@ -0,0 +1,168 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{
+    mock_genesis::{db_with_mock_genesis, in_memory_db_with_mock_genesis, GENESIS_INFO},
+    schema::transaction_info::TransactionInfoSchema,
+    test_helper::arb_blocks_to_commit,
+};
+use crypto::x25519;
+use proptest::prelude::*;
+use std::collections::HashMap;
+use tools::tempdir::TempPath;
+use types::{
+    crypto_proxies::ValidatorSigner,
+    ledger_info::LedgerInfo,
+    transaction::{TransactionInfo, TransactionToCommit},
+    validator_public_keys::ValidatorPublicKeys,
+};
+
+fn test_verify_integrity_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) -> Result<()> {
+    let db = in_memory_db_with_mock_genesis()?;
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        // The arbitrary signatures can't be verified, so leave the validator sets out.
+        let li = ledger_info_with_sigs.ledger_info();
+        let ledger_info_with_sigs = LedgerInfoWithSignatures::new(
+            LedgerInfo::new(
+                li.version(),
+                li.transaction_accumulator_hash(),
+                li.consensus_data_hash(),
+                li.consensus_block_id(),
+                li.epoch_num(),
+                li.timestamp_usecs(),
+                None, /* next_validator_set */
+            ),
+            ledger_info_with_sigs.signatures().clone(),
+        );
+        db.save_transactions(
+            &txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs),
+        )?;
+        cur_ver += txns_to_commit.len() as u64;
+    }
+
+    let report = db.verify_integrity(0, cur_ver)?;
+    assert_eq!(report.num_transactions, cur_ver + 1);
+    assert!(report.num_state_roots > 0);
+    assert!(report.num_ledger_infos > 0);
+    assert_eq!(report.num_ledger_info_signatures, 0);
+    assert_eq!(
+        db.verify_integrity(cur_ver / 2, cur_ver)?.num_transactions,
+        cur_ver - cur_ver / 2 + 1
+    );
+
+    // Corrupt the transaction info of a version.
+    let version = cur_ver / 2;
+    let txn_info = db.ledger_store.get_transaction_info(version)?;
+    db.db.put::<TransactionInfoSchema>(
+        &version,
+        &TransactionInfo::new(
+            txn_info.signed_transaction_hash(),
+            txn_info.state_root_hash(),
+            txn_info.event_root_hash(),
+            txn_info.gas_used() + 1,
+            txn_info.major_status(),
+        ),
+    )?;
+    assert!(db.verify_integrity(0, cur_ver).is_err());
+    assert!(db.verify_integrity(version, version).is_err());
+    if version > 0 {
+        db.verify_integrity(0, version - 1)?;
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_verify_integrity(input in arb_blocks_to_commit()) {
+        test_verify_integrity_impl(input).unwrap();
+    }
+}
+
+#[test]
+fn test_verify_ledger_info_signatures() {
+    let tmp_dir = TempPath::new();
+    let db = db_with_mock_genesis(&tmp_dir).unwrap();
+    db.save_transactions(
+        &[GENESIS_INFO.2.clone()],
+        1,     /* first_version */
+        &None, /* ledger_info_with_sigs */
+    )
+    .unwrap();
+    let root_hash = Accumulator::<TransactionAccumulatorHasher>::new(
+        db.ledger_store.get_ledger_frozen_subtree_hashes(1).unwrap(),
+        2, /* num_leaves */
+    )
+    .unwrap()
+    .root_hash();
+    let new_ledger_info = |epoch, timestamp_usecs, next_validator_set| {
+        LedgerInfo::new(
+            1, /* version */
+            root_hash,
+            HashValue::zero(), /* consensus_data_hash */
+            HashValue::zero(), /* consensus_block_id */
+            epoch,
+            timestamp_usecs,
+            next_validator_set,
+        )
+    };
+
+    // Epoch 1 announces the validator set of epoch 2.
+    let signer = ValidatorSigner::random(None);
+    let validator_set = ValidatorSet::new(vec![ValidatorPublicKeys::new(
+        signer.author(),
+        signer.public_key(),
+        signer.public_key(),
+        x25519::compat::generate_keypair(None).1,
+        None,
+    )]);
+    db.save_transactions(
+        &[],
+        2, /* first_version */
+        &Some(LedgerInfoWithSignatures::new(
+            new_ledger_info(1, 0, Some(validator_set)),
+            HashMap::new(),
+        )),
+    )
+    .unwrap();
+
+    let ledger_info = new_ledger_info(2, 0, None);
+    let mut signatures = HashMap::new();
+    signatures.insert(
+        signer.author(),
+        signer.sign_message(ledger_info.hash()).unwrap(),
+    );
+    db.save_transactions(
+        &[],
+        2, /* first_version */
+        &Some(LedgerInfoWithSignatures::new(
+            ledger_info,
+            signatures.clone(),
+        )),
+    )
+    .unwrap();
+    let report = db.verify_integrity(0, 1).unwrap();
+    assert_eq!(report.num_ledger_infos, 3);
+    assert_eq!(report.num_ledger_info_signatures, 1);
+
+    // The signatures don't match a tampered ledger info.
+    db.save_transactions(
+        &[],
+        2, /* first_version */
+        &Some(LedgerInfoWithSignatures::new(
+            new_ledger_info(2, 1, None),
+            signatures,
+        )),
+    )
+    .unwrap();
+    assert!(db.verify_integrity(0, 1).is_err());
+}
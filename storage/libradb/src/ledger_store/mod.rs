@@ -26,7 +26,7 @@ use types::{
     crypto_proxies::LedgerInfoWithSignatures,
     proof::{
         position::{FrozenSubTreeIterator, Position},
-        AccumulatorProof,
+        AccumulatorConsistencyProof, AccumulatorProof, AccumulatorRangeProof,
     },
     transaction::{TransactionInfo, Version},
 };
@@ -132,6 +132,35 @@ impl LedgerStore {
         Accumulator::get_proof(self, ledger_version + 1 /* num_leaves */, version)
     }
 
+    /// Get proof for `num_txns` consecutive transactions starting from `first_version` towards
+    /// root of ledger at `ledger_version`.
+    pub fn get_transaction_range_proof(
+        &self,
+        first_version: Version,
+        num_txns: u64,
+        ledger_version: Version,
+    ) -> Result<AccumulatorRangeProof> {
+        Accumulator::get_range_proof(
+            self,
+            ledger_version + 1, /* num_leaves */
+            first_version,
+            num_txns,
+        )
+    }
+
+    /// Get proof that the ledger at `ledger_version` extends the one at `client_known_version`.
+    pub fn get_consistency_proof(
+        &self,
+        client_known_version: Version,
+        ledger_version: Version,
+    ) -> Result<AccumulatorConsistencyProof> {
+        Accumulator::get_consistency_proof(
+            self,
+            ledger_version + 1,       /* full_acc_leaves */
+            client_known_version + 1, /* sub_acc_leaves */
+        )
+    }
+
     /// Write `txn_infos` to `batch`. Assigned `first_version` to the the version number of the
     /// first transaction, and so on.
     pub fn put_transaction_infos(
@@ -4,8 +4,10 @@
 use super::*;
 use crate::LibraDB;
 use proptest::{collection::vec, prelude::*};
-use tools::tempdir::TempPath;
-use types::proof::verify_transaction_accumulator_element;
+use types::proof::{
+    accumulator::Accumulator as InMemoryAccumulator, verify_accumulator_consistency,
+    verify_transaction_accumulator_element,
+};
 
 fn verify(
     store: &LedgerStore,
@@ -48,8 +50,7 @@ proptest! {
         batch2 in vec(any::<TransactionInfo>(), 1..100),
     ) {
 
-        let tmp_dir = TempPath::new();
-        let db = LibraDB::new(&tmp_dir);
+        let db = LibraDB::new_in_memory();
         let store = &db.ledger_store;
 
         // insert two batches of transaction infos
@@ -64,5 +65,15 @@ proptest! {
 
         // retrieve batch1 and verify against root_hash after batch1 was interted
         verify(store, &batch1, 0, ledger_version1, root_hash1);
+
+        // prove that the ledger after batch2 was inserted extends the one after batch1
+        let proof = store.get_consistency_proof(ledger_version1, ledger_version2).unwrap();
+        let accumulator = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            store.get_ledger_frozen_subtree_hashes(ledger_version1).unwrap(),
+            ledger_version1 + 1,
+        ).unwrap();
+        verify_accumulator_consistency(&accumulator, &proof, ledger_version2 + 1, root_hash2)
+            .unwrap();
+        prop_assert!(store.get_consistency_proof(ledger_version2, ledger_version1).is_err());
     }
 }
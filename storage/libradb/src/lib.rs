@@ -13,7 +13,10 @@ pub mod mock_genesis;
 #[cfg(any(test, feature = "testing"))]
 pub mod test_helper;
 
+pub mod backup;
+pub mod debug;
 pub mod errors;
+pub mod integrity;
 pub mod schema;
 
 mod change_set;
@@ -29,9 +32,12 @@ mod transaction_store;
 mod libradb_test;
 
 use crate::{
+    backup::{BackupHandler, RestoreHandler},
     change_set::{ChangeSet, SealedChangeSet},
+    debug::DebugHandler,
     errors::LibraDbError,
     event_store::EventStore,
+    integrity::IntegrityReport,
     ledger_counters::LedgerCounters,
     ledger_store::LedgerStore,
     pruner::Pruner,
@@ -46,7 +52,9 @@ use itertools::{izip, zip_eq};
 use lazy_static::lazy_static;
 use logger::prelude::*;
 use metrics::OpMetrics;
-use schemadb::{ColumnFamilyOptions, ColumnFamilyOptionsMap, DB, DEFAULT_CF_NAME};
+use schemadb::{
+    ColumnFamilyName, ColumnFamilyOptions, ColumnFamilyOptionsMap, DB, DEFAULT_CF_NAME,
+};
 use std::{convert::TryInto, iter::Iterator, path::Path, sync::Arc, time::Instant};
 use storage_proto::StartupInfo;
 use types::{
@@ -57,7 +65,10 @@ use types::{
     contract_event::EventWithProof,
     crypto_proxies::{LedgerInfoWithSignatures, ValidatorChangeEventWithProof},
     get_with_proof::{RequestItem, ResponseItem},
-    proof::{AccountStateProof, EventProof, SignedTransactionProof, SparseMerkleProof},
+    proof::{
+        AccountStateProof, AccumulatorConsistencyProof, EventProof, SignedTransactionProof,
+        SparseMerkleProof,
+    },
     transaction::{
         SignedTransactionWithProof, TransactionInfo, TransactionListWithProof, TransactionToCommit,
         Version,
@@ -95,37 +106,19 @@ impl LibraDB {
     /// Config parameter for the pruner.
     const NUM_HISTORICAL_VERSIONS_TO_KEEP: u64 = 1_000_000;
 
-    /// This creates an empty LibraDB instance on disk or opens one if it already exists.
+    /// This creates an empty LibraDB instance on disk or opens one if it already exists. The ledger
+    /// history is never pruned.
     pub fn new<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
-        let cf_opts_map: ColumnFamilyOptionsMap = [
-            (
-                /* LedgerInfo CF = */ DEFAULT_CF_NAME,
-                ColumnFamilyOptions::default(),
-            ),
-            (EVENT_ACCUMULATOR_CF_NAME, ColumnFamilyOptions::default()),
-            (EVENT_BY_KEY_CF_NAME, ColumnFamilyOptions::default()),
-            (EVENT_CF_NAME, ColumnFamilyOptions::default()),
-            (
-                JELLYFISH_MERKLE_NODE_CF_NAME,
-                ColumnFamilyOptions::default(),
-            ),
-            (LEDGER_COUNTERS_CF_NAME, ColumnFamilyOptions::default()),
-            (STALE_NODE_INDEX_CF_NAME, ColumnFamilyOptions::default()),
-            (SIGNED_TRANSACTION_CF_NAME, ColumnFamilyOptions::default()),
-            (
-                TRANSACTION_ACCUMULATOR_CF_NAME,
-                ColumnFamilyOptions::default(),
-            ),
-            (
-                TRANSACTION_BY_ACCOUNT_CF_NAME,
-                ColumnFamilyOptions::default(),
-            ),
-            (TRANSACTION_INFO_CF_NAME, ColumnFamilyOptions::default()),
-            (VALIDATOR_CF_NAME, ColumnFamilyOptions::default()),
-        ]
-        .iter()
-        .cloned()
-        .collect();
+        Self::open(db_root_path, None /* ledger_prune_window */)
+    }
+
+    /// Same as [`new`](LibraDB::new), but the ledger history older than `ledger_prune_window`
+    /// versions before the latest one is pruned if it's set.
+    pub fn open<P: AsRef<Path> + Clone>(db_root_path: P, ledger_prune_window: Option<u64>) -> Self {
+        let cf_opts_map: ColumnFamilyOptionsMap = Self::column_families()
+            .into_iter()
+            .map(|cf_name| (cf_name, ColumnFamilyOptions::default()))
+            .collect();
 
         let path = db_root_path.as_ref().join("libradb");
         let instant = Instant::now();
@@ -140,6 +133,47 @@ impl LibraDB {
             instant.elapsed().as_millis()
         );
 
+        Self::new_with_db(db, ledger_prune_window)
+    }
+
+    /// Opens an existing LibraDB instance in read-only mode, for offline inspection while the DB
+    /// might be in use by a running node. Any attempt to write to it fails.
+    pub fn open_readonly<P: AsRef<Path>>(db_root_path: P) -> Result<Self> {
+        let path = db_root_path.as_ref().join("libradb");
+        let db = Arc::new(DB::open_readonly(&path, Self::column_families())?);
+        info!("Opened LibraDB at {:?} in read-only mode", path);
+
+        Ok(Self::new_with_db(db, None /* ledger_prune_window */))
+    }
+
+    /// Creates an empty LibraDB instance kept in memory, which is lost when it's dropped. The
+    /// ledger history is never pruned. This is meant for tests, which run faster and don't
+    /// leave anything on disk this way.
+    pub fn new_in_memory() -> Self {
+        let db = Arc::new(DB::new_in_memory(Self::column_families()));
+        Self::new_with_db(db, None /* ledger_prune_window */)
+    }
+
+    fn column_families() -> Vec<ColumnFamilyName> {
+        vec![
+            /* LedgerInfo CF = */ DEFAULT_CF_NAME,
+            ACCOUNT_STATE_CHANGE_CF_NAME,
+            ACCOUNT_STATE_HISTORY_CF_NAME,
+            EVENT_ACCUMULATOR_CF_NAME,
+            EVENT_BY_KEY_CF_NAME,
+            EVENT_CF_NAME,
+            JELLYFISH_MERKLE_NODE_CF_NAME,
+            LEDGER_COUNTERS_CF_NAME,
+            STALE_NODE_INDEX_CF_NAME,
+            SIGNED_TRANSACTION_CF_NAME,
+            TRANSACTION_ACCUMULATOR_CF_NAME,
+            TRANSACTION_BY_ACCOUNT_CF_NAME,
+            TRANSACTION_INFO_CF_NAME,
+            VALIDATOR_CF_NAME,
+        ]
+    }
+
+    fn new_with_db(db: Arc<DB>, ledger_prune_window: Option<u64>) -> Self {
         LibraDB {
             db: Arc::clone(&db),
             event_store: EventStore::new(Arc::clone(&db)),
@@ -147,7 +181,11 @@ impl LibraDB {
             state_store: StateStore::new(Arc::clone(&db)),
             transaction_store: TransactionStore::new(Arc::clone(&db)),
             system_store: SystemStore::new(Arc::clone(&db)),
-            pruner: Pruner::new(Arc::clone(&db), Self::NUM_HISTORICAL_VERSIONS_TO_KEEP),
+            pruner: Pruner::new(
+                Arc::clone(&db),
+                Self::NUM_HISTORICAL_VERSIONS_TO_KEEP,
+                ledger_prune_window,
+            ),
         }
     }
 
@@ -209,9 +247,8 @@ impl LibraDB {
         } else {
             bail!("Nothing stored under address: {}", query_path.address);
         };
-        let event_key = account_resource
-            .get_event_handle_by_query_path(&query_path.path)?
-            .key();
+        let event_handle = account_resource.get_event_handle_by_query_path(&query_path.path)?;
+        let event_key = event_handle.key();
         let cursor = if get_latest {
             // Caller wants the latest, figure out the latest seq_num.
             // In the case of no events on that path, use 0 and expect empty result below.
@@ -225,6 +262,19 @@ impl LibraDB {
         // Convert requested range and order to a range in ascending order.
         let (first_seq, real_limit) = get_first_seq_num_and_limit(ascending, cursor, limit)?;
 
+        // Refuse to serve a partial result if part of the requested events have been pruned.
+        let min_readable_version = self.pruner.get_min_readable_ledger_version();
+        if min_readable_version > 0 && real_limit > 0 && first_seq < event_handle.count() {
+            let first_available_seq = self.event_store.get_first_sequence_number(event_key)?;
+            if first_available_seq.map_or(true, |seq| seq > first_seq) {
+                return Err(LibraDbError::Pruned(
+                    format!("Event {} of {:?}", first_seq, event_key),
+                    min_readable_version,
+                )
+                .into());
+            }
+        }
+
         // Query the index.
         let mut event_keys = self.event_store.lookup_events_by_key(
             &event_key,
@@ -275,6 +325,45 @@ impl LibraDB {
         Ok((events_with_proof, account_state))
     }
 
+    /// Returns the states of the account at `address` right after each transaction that changed it,
+    /// starting from `start_version`, at most `limit` of them, with proofs based on
+    /// `ledger_version`.
+    ///
+    /// Changes made by transactions restored from a backup are not indexed, so they are not
+    /// returned.
+    fn get_account_state_history(
+        &self,
+        address: AccountAddress,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<AccountStateWithProof>> {
+        error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+        // Refuse to serve a partial result if part of the requested history has been pruned, either
+        // from the ledger history or from the account state tree.
+        let min_readable_version = std::cmp::max(
+            self.pruner.get_min_readable_ledger_version(),
+            self.pruner.get_min_readable_state_version(),
+        );
+        if start_version < min_readable_version {
+            return Err(LibraDbError::Pruned(
+                format!(
+                    "State history of account {} from version {}",
+                    address, start_version
+                ),
+                min_readable_version,
+            )
+            .into());
+        }
+
+        self.state_store
+            .get_account_state_change_versions(address, start_version, limit, ledger_version)?
+            .into_iter()
+            .map(|version| self.get_account_state_with_proof(address, version, ledger_version))
+            .collect()
+    }
+
     /// Returns a signed transaction that is the `seq_num`-th one associated with the given account.
     /// If the signed transaction with given `seq_num` doesn't exist, returns `None`.
     fn get_txn_by_account(
@@ -284,10 +373,50 @@ impl LibraDB {
         ledger_version: Version,
         fetch_events: bool,
     ) -> Result<Option<SignedTransactionWithProof>> {
-        self.transaction_store
-            .lookup_transaction_by_account(address, seq_num, ledger_version)?
-            .map(|version| self.get_transaction_with_proof(version, ledger_version, fetch_events))
-            .transpose()
+        // Read once, so that the pruner moving on in the meantime can't make the checks below
+        // disagree with each other.
+        let min_readable_version = self.pruner.get_min_readable_ledger_version();
+        let pruned = || {
+            Err(LibraDbError::Pruned(
+                format!("Transaction {} sent by account {}", seq_num, address),
+                min_readable_version,
+            )
+            .into())
+        };
+        match self.transaction_store.lookup_transaction_by_account(
+            address,
+            seq_num,
+            ledger_version,
+        )? {
+            Some(version) if version >= min_readable_version => self
+                .get_transaction_with_proof(version, ledger_version, fetch_events)
+                .map(Some),
+            Some(_) => pruned(),
+            // The index of a pruned transaction is gone, but the account having sent it already
+            // tells it's not missing.
+            None if min_readable_version > 0
+                && self.get_account_sequence_number(address, ledger_version)? > seq_num =>
+            {
+                pruned()
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the sequence number of the account at `address` at `version`, 0 if the account
+    /// doesn't exist.
+    fn get_account_sequence_number(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<u64> {
+        let (account_state_blob, _) = self
+            .state_store
+            .get_account_state_with_proof_by_version(address, version)?;
+        match account_state_blob {
+            Some(blob) => Ok(AccountResource::make_from(&(&blob).try_into()?)?.sequence_number()),
+            None => Ok(0),
+        }
     }
 
     /// Gets the latest version number available in the ledger.
@@ -299,6 +428,11 @@ impl LibraDB {
             .version())
     }
 
+    /// Returns the latest ledger info, or `None` if the DB is not bootstrapped yet.
+    pub fn get_latest_ledger_info_option(&self) -> Option<LedgerInfoWithSignatures> {
+        self.ledger_store.get_latest_ledger_info_option()
+    }
+
     /// Returns the latest ledger infos per epoch starting with the given epoch num:
     /// - the latest ledger info of the current epoch is just the last ledger info in the system
     /// - the latest ledger infos of previous epochs contain reconfiguration validator sets.
@@ -439,14 +573,19 @@ impl LibraDB {
     /// This backs the `UpdateToLatestLedger` public read API which returns the latest
     /// [`LedgerInfoWithSignatures`] together with items requested and proofs relative to the same
     /// ledger info.
+    ///
+    /// Also returns a proof that the ledger at the latest version extends the one at
+    /// `client_known_version`, or `None` if it can't be provided, i.e. the client knows a version
+    /// newer than the latest one here or one whose ledger history has been pruned.
     pub fn update_to_latest_ledger(
         &self,
-        _client_known_version: u64,
+        client_known_version: u64,
         request_items: Vec<RequestItem>,
     ) -> Result<(
         Vec<ResponseItem>,
         LedgerInfoWithSignatures,
         Vec<ValidatorChangeEventWithProof>,
+        Option<AccumulatorConsistencyProof>,
     )> {
         error_if_too_many_requested(request_items.len() as u64, MAX_REQUEST_ITEMS)?;
 
@@ -523,13 +662,37 @@ impl LibraDB {
                         txn_list_with_proof,
                     })
                 }
+                RequestItem::GetAccountStateHistory {
+                    address,
+                    start_version,
+                    limit,
+                } => Ok(ResponseItem::GetAccountStateHistory {
+                    account_states_with_proof: self.get_account_state_history(
+                        address,
+                        start_version,
+                        limit,
+                        ledger_version,
+                    )?,
+                }),
             })
             .collect::<Result<Vec<_>>>()?;
 
+        let ledger_consistency_proof = if client_known_version <= ledger_version
+            && client_known_version >= self.pruner.get_min_readable_ledger_version()
+        {
+            Some(
+                self.ledger_store
+                    .get_consistency_proof(client_known_version, ledger_version)?,
+            )
+        } else {
+            None
+        };
+
         Ok((
             response_items,
             ledger_info_with_sigs,
             vec![], /* TODO: validator_change_events */
+            ledger_consistency_proof,
         ))
     }
 
@@ -592,6 +755,14 @@ impl LibraDB {
         if start_version > ledger_version || limit == 0 {
             return Ok(TransactionListWithProof::new_empty());
         }
+        let min_readable_version = self.pruner.get_min_readable_ledger_version();
+        if start_version < min_readable_version {
+            return Err(LibraDbError::Pruned(
+                format!("Transaction {}", start_version),
+                min_readable_version,
+            )
+            .into());
+        }
 
         let limit = std::cmp::min(limit, ledger_version - start_version + 1);
         let txn_and_txn_info_list = (start_version..start_version + limit)
@@ -602,18 +773,9 @@ impl LibraDB {
                 ))
             })
             .collect::<Result<Vec<_>>>()?;
-        let proof_of_first_transaction = Some(
+        let proof =
             self.ledger_store
-                .get_transaction_proof(start_version, ledger_version)?,
-        );
-        let proof_of_last_transaction = if limit == 1 {
-            None
-        } else {
-            Some(
-                self.ledger_store
-                    .get_transaction_proof(start_version + limit - 1, ledger_version)?,
-            )
-        };
+                .get_transaction_range_proof(start_version, limit, ledger_version)?;
         let events = if fetch_events {
             Some(
                 (start_version..start_version + limit)
@@ -628,11 +790,40 @@ impl LibraDB {
             txn_and_txn_info_list,
             events,
             Some(start_version),
-            proof_of_first_transaction,
-            proof_of_last_transaction,
+            proof,
         ))
     }
 
+    // ============================= Backup and Restore APIs ==================================
+    /// Gets an instance of [`BackupHandler`] to read out the data to be backed up.
+    pub fn get_backup_handler(&self) -> BackupHandler {
+        BackupHandler::new(self)
+    }
+
+    /// Gets an instance of [`RestoreHandler`] to restore a backup into this (empty) DB.
+    pub fn get_restore_handler(&self) -> RestoreHandler {
+        RestoreHandler::new(self)
+    }
+
+    // ============================= Offline Inspection APIs ==================================
+    /// Gets an instance of [`DebugHandler`] to look inside the DB.
+    pub fn get_debug_handler(&self) -> DebugHandler {
+        DebugHandler::new(self)
+    }
+
+    /// Recomputes what's derived from the raw data of versions in `[start_version, end_version]`
+    /// and checks it against what's stored: the transaction hashes, event root hashes and state
+    /// root hashes carried by the transaction infos, the transaction accumulator nodes and the
+    /// ledger infos, whose signatures are also checked against the validator set of their epoch
+    /// when it's known. Returns an error on the first mismatch found.
+    pub fn verify_integrity(
+        &self,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<IntegrityReport> {
+        integrity::verify_integrity(self, start_version, end_version)
+    }
+
     // ================================== Private APIs ==================================
     /// Convert a `ChangeSet` to `SealedChangeSet`.
     ///
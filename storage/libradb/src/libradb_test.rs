@@ -3,24 +3,25 @@
 
 use super::*;
 use crate::{
-    mock_genesis::{db_with_mock_genesis, GENESIS_INFO},
+    mock_genesis::{db_with_mock_genesis, in_memory_db_with_mock_genesis, GENESIS_INFO},
     test_helper::arb_blocks_to_commit,
 };
-use crypto::hash::CryptoHash;
+use crypto::hash::{CryptoHash, TransactionAccumulatorHasher};
 use proptest::prelude::*;
 use rusty_fork::{rusty_fork_id, rusty_fork_test, rusty_fork_test_name};
 use std::collections::HashMap;
 use tools::tempdir::TempPath;
 use types::{
-    account_config::get_account_resource_or_default, contract_event::ContractEvent,
+    account_config::get_account_resource_or_default,
+    contract_event::ContractEvent,
     ledger_info::LedgerInfo,
+    proof::{accumulator::Accumulator, verify_accumulator_consistency},
 };
 
 fn test_save_blocks_impl(
     input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
 ) -> Result<()> {
-    let tmp_dir = TempPath::new();
-    let db = db_with_mock_genesis(&tmp_dir)?;
+    let db = in_memory_db_with_mock_genesis()?;
 
     let num_batches = input.len();
     let mut cur_ver = 0;
@@ -66,14 +67,37 @@ fn test_save_blocks_impl(
         true, /* is_latest */
     )?;
 
+    // Prove that the latest ledger extends the one after the first batch.
+    let known_version = first_batch_ledger_info.ledger_info().version();
+    let (_, _, _, ledger_consistency_proof) = db.update_to_latest_ledger(known_version, vec![])?;
+    let known_accumulator = Accumulator::<TransactionAccumulatorHasher>::new(
+        db.ledger_store
+            .get_ledger_frozen_subtree_hashes(known_version)?,
+        known_version + 1,
+    )?;
+    verify_accumulator_consistency(
+        &known_accumulator,
+        &ledger_consistency_proof.expect("Known version is within the ledger history."),
+        latest_ledger_info.ledger_info().version() + 1,
+        latest_ledger_info
+            .ledger_info()
+            .transaction_accumulator_hash(),
+    )?;
+
+    // No proof can be given for a version beyond the latest one.
+    let (_, _, _, ledger_consistency_proof) =
+        db.update_to_latest_ledger(latest_ledger_info.ledger_info().version() + 1, vec![])?;
+    assert!(ledger_consistency_proof.is_none());
+
+    verify_account_state_history(&db, &input, latest_ledger_info.ledger_info())?;
+
     Ok(())
 }
 
 fn test_sync_transactions_impl(
     input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
 ) -> Result<()> {
-    let tmp_dir = TempPath::new();
-    let db = db_with_mock_genesis(&tmp_dir)?;
+    let db = in_memory_db_with_mock_genesis()?;
 
     let num_batches = input.len();
     let mut cur_ver = 0;
@@ -106,6 +130,62 @@ fn test_sync_transactions_impl(
     Ok(())
 }
 
+fn verify_account_state_history(
+    db: &LibraDB,
+    input: &[(Vec<TransactionToCommit>, LedgerInfoWithSignatures)],
+    ledger_info: &LedgerInfo,
+) -> Result<()> {
+    // The versions and states of each account changed, with the genesis at version 0.
+    let mut expected_histories = HashMap::new();
+    let txns_to_commit =
+        std::iter::once(&GENESIS_INFO.2).chain(input.iter().flat_map(|(txns, _)| txns));
+    for (version, txn_to_commit) in txns_to_commit.enumerate() {
+        for (address, blob) in txn_to_commit.account_states() {
+            expected_histories
+                .entry(*address)
+                .or_insert_with(Vec::new)
+                .push((version as Version, blob.clone()));
+        }
+    }
+
+    for (address, expected_history) in expected_histories {
+        // Page through the history, a few states at a time.
+        let mut history = Vec::new();
+        let mut start_version = 0;
+        loop {
+            let (mut response_items, _, _, _) = db.update_to_latest_ledger(
+                0, /* client_known_version */
+                vec![RequestItem::GetAccountStateHistory {
+                    address,
+                    start_version,
+                    limit: 2,
+                }],
+            )?;
+            let account_states_with_proof = response_items
+                .remove(0)
+                .into_get_account_state_history_response()?;
+            let last_version = match account_states_with_proof.last() {
+                Some(account_state_with_proof) => account_state_with_proof.version,
+                None => break,
+            };
+            for account_state_with_proof in account_states_with_proof {
+                let version = account_state_with_proof.version;
+                account_state_with_proof.verify(ledger_info, version, address)?;
+                history.push((
+                    version,
+                    account_state_with_proof
+                        .blob
+                        .expect("Account should exist after it's changed."),
+                ));
+            }
+            start_version = last_version + 1;
+        }
+        assert_eq!(history, expected_history);
+    }
+
+    Ok(())
+}
+
 fn get_events_by_query_path(
     db: &LibraDB,
     ledger_info: &LedgerInfo,
@@ -111,10 +111,18 @@ lazy_static! {
 /// The resulting db will have only one transaction at version 0 (the mock genesis transaction) and
 /// related outputs (the mock genesis state) in it.
 pub fn db_with_mock_genesis<P: AsRef<std::path::Path>>(dir: &P) -> Result<LibraDB> {
+    init_with_mock_genesis(LibraDB::new(dir))
+}
+
+/// Same as [`db_with_mock_genesis`], but the db is kept in memory.
+pub fn in_memory_db_with_mock_genesis() -> Result<LibraDB> {
+    init_with_mock_genesis(LibraDB::new_in_memory())
+}
+
+fn init_with_mock_genesis(db: LibraDB) -> Result<LibraDB> {
     let genesis_ledger_info_with_sigs = GENESIS_INFO.1.clone();
     let genesis_txn = GENESIS_INFO.2.clone();
 
-    let db = LibraDB::new(dir);
     db.save_transactions(
         &[genesis_txn],
         0, /* first_version */
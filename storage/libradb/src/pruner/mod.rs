@@ -3,10 +3,25 @@
 
 //! This module provides `Pruner` which manages a thread pruning old data in the background and is
 //! meant to be triggered by other threads as they commit new data to the DB.
+//!
+//! Two kinds of data are pruned:
+//!   - Stale Jellyfish Merkle nodes, so that only a limited number of historical versions of the
+//!     account state tree stay readable, together with the index of account state changes at the
+//!     versions no longer readable.
+//!   - If a ledger prune window is configured, the ledger history older than the window, i.e. the
+//!     transactions, transaction infos, events, their indices and the part of the transaction
+//!     accumulator no longer needed to prove anything within the window.
 
 use crate::{
     schema::{
-        jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        account_state_change::AccountStateChangeSchema,
+        account_state_history::AccountStateHistorySchema, event::EventSchema,
+        event_accumulator::EventAccumulatorSchema, event_by_key::EventByKeySchema,
+        jellyfish_merkle_node::JellyfishMerkleNodeSchema,
+        signed_transaction::SignedTransactionSchema, stale_node_index::StaleNodeIndexSchema,
+        transaction_accumulator::TransactionAccumulatorSchema,
+        transaction_by_account::TransactionByAccountSchema,
+        transaction_info::TransactionInfoSchema,
     },
     OP_COUNTER,
 };
@@ -20,7 +35,7 @@ use std::{
     },
     thread::JoinHandle,
 };
-use types::transaction::Version;
+use types::{proof::position::Position, transaction::Version};
 
 use failure::_core::sync::atomic::Ordering;
 use jellyfish_merkle::StaleNodeIndex;
@@ -41,6 +56,15 @@ pub(crate) struct Pruner {
     /// Other than the latest version, how many historical versions to keep being readable. For
     /// example, this being 0 means keep only the latest version.
     num_historical_versions_to_keep: u64,
+    /// Other than the latest version, how many historical versions of the ledger history to keep
+    /// being readable. `None` means the ledger history is never pruned.
+    ledger_prune_window: Option<u64>,
+    /// Versions of the account state tree before this are no longer guaranteed to be readable.
+    /// It's bumped before the worker thread is asked to prune.
+    min_readable_state_version: AtomicU64,
+    /// Versions of the ledger history before this are no longer readable. It's bumped before the
+    /// worker thread is asked to prune, so that readers never see partially pruned data.
+    min_readable_ledger_version: AtomicU64,
     /// The worker thread handle, created upon Pruner instance construction and joined upon its
     /// destruction. It only becomes `None` after joined in `drop()`.
     worker_thread: Option<JoinHandle<()>>,
@@ -50,32 +74,86 @@ pub(crate) struct Pruner {
     /// sets this atomic value to `V`, all versions before `V` can no longer be accessed.
     #[allow(dead_code)]
     worker_progress: Arc<AtomicU64>,
+    /// (For tests) Same as `worker_progress` but for the ledger history.
+    #[allow(dead_code)]
+    ledger_worker_progress: Arc<AtomicU64>,
+    /// (For tests) Same as `worker_progress` but for the index of account state changes.
+    #[allow(dead_code)]
+    account_state_history_worker_progress: Arc<AtomicU64>,
 }
 
 impl Pruner {
     /// Creates a worker thread that waits on a channel for pruning commands.
-    pub fn new(db: Arc<DB>, num_historical_versions_to_keep: u64) -> Self {
+    pub fn new(
+        db: Arc<DB>,
+        num_historical_versions_to_keep: u64,
+        ledger_prune_window: Option<u64>,
+    ) -> Self {
+        // The ledger history might have been pruned before the DB was last closed, or never been
+        // there if the DB is restored from a backup not starting from genesis.
+        let min_readable_ledger_version =
+            get_min_readable_ledger_version(&db).expect("Reading ledger history should succeed.");
+        let min_readable_state_version = get_latest_version(&db)
+            .expect("Reading ledger history should succeed.")
+            .saturating_sub(num_historical_versions_to_keep);
+        let min_account_state_history_version = get_min_account_state_history_version(&db)
+            .expect("Reading account state history should succeed.");
+
         let (command_sender, command_receiver) = channel();
         let worker_progress = Arc::new(AtomicU64::new(0));
         let worker_progress_clone = Arc::clone(&worker_progress);
+        let ledger_worker_progress = Arc::new(AtomicU64::new(min_readable_ledger_version));
+        let ledger_worker_progress_clone = Arc::clone(&ledger_worker_progress);
+        let account_state_history_worker_progress =
+            Arc::new(AtomicU64::new(min_account_state_history_version));
+        let account_state_history_worker_progress_clone =
+            Arc::clone(&account_state_history_worker_progress);
 
         let worker_thread = std::thread::Builder::new()
             .name("libradb_pruner".into())
-            .spawn(move || Worker::new(db, command_receiver, worker_progress_clone).work_loop())
+            .spawn(move || {
+                Worker::new(
+                    db,
+                    command_receiver,
+                    worker_progress_clone,
+                    ledger_worker_progress_clone,
+                    account_state_history_worker_progress_clone,
+                )
+                .work_loop()
+            })
             .expect("Creating pruner thread should succeed.");
 
         Self {
             num_historical_versions_to_keep,
+            ledger_prune_window,
+            min_readable_state_version: AtomicU64::new(min_readable_state_version),
+            min_readable_ledger_version: AtomicU64::new(min_readable_ledger_version),
             worker_thread: Some(worker_thread),
             command_sender: Mutex::new(command_sender),
             worker_progress,
+            ledger_worker_progress,
+            account_state_history_worker_progress,
         }
     }
 
+    /// Returns the smallest version of the account state tree that is guaranteed to be readable.
+    pub fn get_min_readable_state_version(&self) -> Version {
+        self.min_readable_state_version.load(Ordering::Relaxed)
+    }
+
+    /// Returns the smallest version of the ledger history that is still readable.
+    pub fn get_min_readable_ledger_version(&self) -> Version {
+        self.min_readable_ledger_version.load(Ordering::Relaxed)
+    }
+
     /// Sends pruning command to the worker thread when necessary.
     pub fn wake(&self, latest_version: Version) {
         if latest_version > self.num_historical_versions_to_keep {
             let least_readable_version = latest_version - self.num_historical_versions_to_keep;
+            if least_readable_version > self.get_min_readable_state_version() {
+                self.min_readable_state_version
+                    .store(least_readable_version, Ordering::Relaxed);
+            }
             self.command_sender
                 .lock()
                 .expect("command_sender to pruner thread should lock.")
@@ -84,6 +162,23 @@ impl Pruner {
                 })
                 .expect("Receiver should not destruct prematurely.");
         }
+
+        if let Some(ledger_prune_window) = self.ledger_prune_window {
+            if latest_version > ledger_prune_window {
+                let min_readable_version = latest_version - ledger_prune_window;
+                if min_readable_version > self.get_min_readable_ledger_version() {
+                    self.min_readable_ledger_version
+                        .store(min_readable_version, Ordering::Relaxed);
+                    self.command_sender
+                        .lock()
+                        .expect("command_sender to pruner thread should lock.")
+                        .send(Command::PruneLedger {
+                            min_readable_version,
+                        })
+                        .expect("Receiver should not destruct prematurely.");
+                }
+            }
+        }
     }
 
     /// (For tests only.) Notifies the worker thread and waits for it to finish its job by polling
@@ -92,21 +187,27 @@ impl Pruner {
     pub fn wake_and_wait(&self, latest_version: Version) -> Result<()> {
         self.wake(latest_version);
 
-        if latest_version > self.num_historical_versions_to_keep {
-            let least_readable_version = latest_version - self.num_historical_versions_to_keep;
-            // Assuming no big pruning chunks will be issued by a test.
-            const TIMEOUT: Duration = Duration::from_secs(10);
-            let end = Instant::now() + TIMEOUT;
-
-            while Instant::now() < end {
-                if self.worker_progress.load(Ordering::Relaxed) >= least_readable_version {
-                    return Ok(());
-                }
-                sleep(Duration::from_millis(1));
+        let least_readable_version =
+            latest_version.saturating_sub(self.num_historical_versions_to_keep);
+        let min_readable_ledger_version = self.get_min_readable_ledger_version();
+        // Assuming no big pruning chunks will be issued by a test.
+        const TIMEOUT: Duration = Duration::from_secs(10);
+        let end = Instant::now() + TIMEOUT;
+
+        while Instant::now() < end {
+            if self.worker_progress.load(Ordering::Relaxed) >= least_readable_version
+                && self.ledger_worker_progress.load(Ordering::Relaxed)
+                    >= min_readable_ledger_version
+                && self
+                    .account_state_history_worker_progress
+                    .load(Ordering::Relaxed)
+                    >= least_readable_version
+            {
+                return Ok(());
             }
-            bail!("Timeout waiting for pruner worker.");
+            sleep(Duration::from_millis(1));
         }
-        Ok(())
+        bail!("Timeout waiting for pruner worker.");
     }
 }
 
@@ -128,6 +229,7 @@ impl Drop for Pruner {
 enum Command {
     Quit,
     Prune { least_readable_version: Version },
+    PruneLedger { min_readable_version: Version },
 }
 
 struct Worker {
@@ -138,6 +240,14 @@ struct Worker {
     /// smaller than `V` are no longer readable.
     /// This being an atomic value is to communicate the info with the Pruner thread (for tests).
     least_readable_version: Arc<AtomicU64>,
+    target_min_readable_ledger_version: Version,
+    /// Keeps a record of the ledger history pruning progress. If this equals to version `V`, the
+    /// ledger history before `V` has been pruned.
+    min_readable_ledger_version: Arc<AtomicU64>,
+    /// Keeps a record of the account state change index pruning progress, whose target is
+    /// `target_least_readable_version` too. If this equals to version `V`, the changes before `V`
+    /// have been deleted from the index.
+    min_account_state_history_version: Arc<AtomicU64>,
     /// Indicates if there's NOT any pending work to do currently, to hint
     /// `Self::receive_commands()` to `recv()` blocking-ly.
     blocking_recv: bool,
@@ -152,12 +262,17 @@ impl Worker {
         db: Arc<DB>,
         command_receiver: Receiver<Command>,
         least_readable_version: Arc<AtomicU64>,
+        min_readable_ledger_version: Arc<AtomicU64>,
+        min_account_state_history_version: Arc<AtomicU64>,
     ) -> Self {
         Self {
             db,
             command_receiver,
             least_readable_version,
             target_least_readable_version: 0,
+            target_min_readable_ledger_version: min_readable_ledger_version.load(Ordering::Relaxed),
+            min_readable_ledger_version,
+            min_account_state_history_version,
             blocking_recv: true,
             index_min_nonpurged_version: 0,
             index_purged_at: Instant::now(),
@@ -168,35 +283,96 @@ impl Worker {
         while self.receive_commands() {
             // Process a reasonably small batch of work before trying to receive commands again,
             // in case `Command::Quit` is received (that's when we should quit.)
-            match prune_state(
-                Arc::clone(&self.db),
-                self.least_readable_version.load(Ordering::Relaxed),
-                self.target_least_readable_version,
-                Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
-            ) {
-                Ok(least_readable_version) => {
-                    // Make next recv() blocking if all done.
-                    self.blocking_recv =
-                        least_readable_version == self.target_least_readable_version;
-
-                    // Log the progress.
-                    self.least_readable_version
-                        .store(least_readable_version, Ordering::Relaxed);
-                    OP_COUNTER.set(
-                        "pruner.least_readable_state_version",
-                        least_readable_version as usize,
-                    );
-
-                    // Try to purge the log.
-                    if let Err(e) = self.maybe_purge_index() {
-                        crit!("Failed purging state state node index, ignored. Err: {}", e);
-                    }
-                }
-                Err(e) => {
-                    crit!("Error pruning stale state nodes. {:?}", e);
-                    // On error, stop retrying vigorously by making next recv() blocking.
-                    self.blocking_recv = true;
+            let state_done = self.prune_state_batch();
+            let history_done = self.prune_account_state_history_batch();
+            let ledger_done = self.prune_ledger_batch();
+            // Make next recv() blocking if all done.
+            self.blocking_recv = state_done && history_done && ledger_done;
+        }
+    }
+
+    /// Prunes a batch of stale state nodes. Returns `true` if there's no more work to do for now,
+    /// either because all done or because of an error.
+    fn prune_state_batch(&mut self) -> bool {
+        match prune_state(
+            Arc::clone(&self.db),
+            self.least_readable_version.load(Ordering::Relaxed),
+            self.target_least_readable_version,
+            Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+        ) {
+            Ok(least_readable_version) => {
+                // Log the progress.
+                self.least_readable_version
+                    .store(least_readable_version, Ordering::Relaxed);
+                OP_COUNTER.set(
+                    "pruner.least_readable_state_version",
+                    least_readable_version as usize,
+                );
+
+                // Try to purge the log.
+                if let Err(e) = self.maybe_purge_index() {
+                    crit!("Failed purging state state node index, ignored. Err: {}", e);
                 }
+
+                least_readable_version == self.target_least_readable_version
+            }
+            Err(e) => {
+                crit!("Error pruning stale state nodes. {:?}", e);
+                // On error, stop retrying vigorously by making next recv() blocking.
+                true
+            }
+        }
+    }
+
+    /// Prunes a batch of the index of account state changes. Returns `true` if there's no more work
+    /// to do for now, either because all done or because of an error.
+    fn prune_account_state_history_batch(&mut self) -> bool {
+        match prune_account_state_history(
+            &self.db,
+            self.min_account_state_history_version
+                .load(Ordering::Relaxed),
+            self.target_least_readable_version,
+            Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+        ) {
+            Ok(min_version) => {
+                self.min_account_state_history_version
+                    .store(min_version, Ordering::Relaxed);
+                OP_COUNTER.set(
+                    "pruner.min_account_state_history_version",
+                    min_version as usize,
+                );
+
+                min_version >= self.target_least_readable_version
+            }
+            Err(e) => {
+                crit!("Error pruning account state history. {:?}", e);
+                true
+            }
+        }
+    }
+
+    /// Prunes a batch of ledger history. Returns `true` if there's no more work to do for now,
+    /// either because all done or because of an error.
+    fn prune_ledger_batch(&mut self) -> bool {
+        match prune_ledger(
+            &self.db,
+            self.min_readable_ledger_version.load(Ordering::Relaxed),
+            self.target_min_readable_ledger_version,
+            Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+        ) {
+            Ok(min_readable_version) => {
+                self.min_readable_ledger_version
+                    .store(min_readable_version, Ordering::Relaxed);
+                OP_COUNTER.set(
+                    "pruner.min_readable_ledger_version",
+                    min_readable_version as usize,
+                );
+
+                min_readable_version == self.target_min_readable_ledger_version
+            }
+            Err(e) => {
+                crit!("Error pruning ledger history. {:?}", e);
+                true
             }
         }
     }
@@ -236,6 +412,14 @@ impl Worker {
                         self.blocking_recv = false;
                     }
                 }
+                Command::PruneLedger {
+                    min_readable_version,
+                } => {
+                    if min_readable_version > self.target_min_readable_ledger_version {
+                        self.target_min_readable_ledger_version = min_readable_version;
+                        self.blocking_recv = false;
+                    }
+                }
             }
         }
     }
@@ -357,5 +541,128 @@ pub fn prune_state(
     }
 }
 
+/// Deletes the ledger history of versions in `[min_readable_version, target_min_readable_version)`,
+/// at most `max_versions` of them at a time. Returns the new min readable version.
+///
+/// The transaction accumulator nodes are deleted once they are no longer needed, i.e. a leaf
+/// together with its sibling as soon as the right one of the two is pruned, then their parent
+/// together with its sibling, and so on. What's left is exactly the frozen subtrees the later
+/// leaves are built upon, which are needed to prove anything still readable.
+fn prune_ledger(
+    db: &DB,
+    min_readable_version: Version,
+    target_min_readable_version: Version,
+    max_versions: usize,
+) -> Result<Version> {
+    let end_version = std::cmp::min(
+        target_min_readable_version,
+        min_readable_version.saturating_add(max_versions as u64),
+    );
+    if end_version <= min_readable_version {
+        return Ok(min_readable_version);
+    }
+
+    let mut batch = SchemaBatch::new();
+    for version in min_readable_version..end_version {
+        // A version restored from a backup not starting from genesis has no transaction.
+        if let Some(signed_txn) = db.get::<SignedTransactionSchema>(&version)? {
+            batch.delete::<TransactionByAccountSchema>(&(
+                signed_txn.sender(),
+                signed_txn.sequence_number(),
+            ))?;
+            batch.delete::<SignedTransactionSchema>(&version)?;
+        }
+        batch.delete::<TransactionInfoSchema>(&version)?;
+
+        let mut iter = db.iter::<EventSchema>(ReadOptions::default())?;
+        iter.seek(&version)?;
+        for res in iter {
+            let ((ver, idx), event) = res?;
+            if ver != version {
+                break;
+            }
+            batch.delete::<EventByKeySchema>(&(*event.key(), event.sequence_number()))?;
+            batch.delete::<EventSchema>(&(ver, idx))?;
+        }
+
+        let mut iter = db.iter::<EventAccumulatorSchema>(ReadOptions::default())?;
+        iter.seek(&(version, Position::from_inorder_index(0)))?;
+        for res in iter {
+            let ((ver, position), _) = res?;
+            if ver != version {
+                break;
+            }
+            batch.delete::<EventAccumulatorSchema>(&(ver, position))?;
+        }
+
+        let mut position = Position::from_leaf_index(version);
+        while !position.is_left_child() {
+            position = position.parent();
+            batch.delete::<TransactionAccumulatorSchema>(&position.left_child())?;
+            batch.delete::<TransactionAccumulatorSchema>(&position.right_child())?;
+        }
+    }
+    db.write_schemas(batch)?;
+
+    Ok(end_version)
+}
+
+/// Deletes the account state changes at versions in `[min_version, target_min_version)` from both
+/// indices, at most `max_versions` versions of them at a time. Returns the new min version.
+fn prune_account_state_history(
+    db: &DB,
+    min_version: Version,
+    target_min_version: Version,
+    max_versions: usize,
+) -> Result<Version> {
+    let end_version = std::cmp::min(
+        target_min_version,
+        min_version.saturating_add(max_versions as u64),
+    );
+    if end_version <= min_version {
+        return Ok(min_version);
+    }
+
+    let mut batch = SchemaBatch::new();
+    let mut iter = db.iter::<AccountStateChangeSchema>(ReadOptions::default())?;
+    iter.seek(&min_version)?;
+    for res in iter {
+        let ((version, address), ()) = res?;
+        if version >= end_version {
+            break;
+        }
+        batch.delete::<AccountStateHistorySchema>(&(address, version))?;
+        batch.delete::<AccountStateChangeSchema>(&(version, address))?;
+    }
+    db.write_schemas(batch)?;
+
+    Ok(end_version)
+}
+
+/// Returns the latest version with a transaction info in the DB, 0 if there is none.
+fn get_latest_version(db: &DB) -> Result<Version> {
+    let mut iter = db.iter::<TransactionInfoSchema>(ReadOptions::default())?;
+    iter.seek_to_last();
+    Ok(iter.next().transpose()?.map_or(0, |(version, _)| version))
+}
+
+/// Returns the first version with an account state change in the index, 0 if there is none.
+fn get_min_account_state_history_version(db: &DB) -> Result<Version> {
+    let mut iter = db.iter::<AccountStateChangeSchema>(ReadOptions::default())?;
+    iter.seek_to_first();
+    Ok(iter
+        .next()
+        .transpose()?
+        .map_or(0, |((version, _), _)| version))
+}
+
+/// Returns the first version with a transaction info in the DB, which is where the readable ledger
+/// history starts.
+fn get_min_readable_ledger_version(db: &DB) -> Result<Version> {
+    let mut iter = db.iter::<TransactionInfoSchema>(ReadOptions::default())?;
+    iter.seek_to_first();
+    Ok(iter.next().transpose()?.map_or(0, |(version, _)| version))
+}
+
 #[cfg(test)]
 mod test;
@@ -2,13 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use crate::{change_set::ChangeSet, state_store::StateStore, LibraDB};
+use crate::{
+    change_set::ChangeSet, errors::LibraDbError, mock_genesis::GENESIS_INFO,
+    state_store::StateStore, test_helper::arb_blocks_to_commit, LibraDB,
+};
 use crypto::HashValue;
+use proptest::prelude::*;
 use std::collections::HashMap;
 use tools::tempdir::TempPath;
 use types::{
     account_address::{AccountAddress, ADDRESS_LENGTH},
     account_state_blob::AccountStateBlob,
+    crypto_proxies::LedgerInfoWithSignatures,
+    transaction::TransactionToCommit,
 };
 
 fn put_account_state_set(
@@ -54,7 +60,8 @@ fn test_pruner() {
     let state_store = &StateStore::new(Arc::clone(&db));
     let pruner = Pruner::new(
         Arc::clone(&db),
-        0, /* num_historical_versions_to_keep */
+        0,    /* num_historical_versions_to_keep */
+        None, /* ledger_prune_window */
     );
 
     let _root0 = put_account_state_set(
@@ -104,6 +111,28 @@ fn test_pruner() {
         // root2 is still there.
         verify_state_in_store(state_store, address, Some(&value2), 2);
     }
+    // The account state changes at the pruned versions are gone from both indices.
+    {
+        assert_eq!(pruner.get_min_readable_state_version(), 2);
+        for version in 0..2 {
+            assert!(db
+                .get::<AccountStateHistorySchema>(&(address, version))
+                .unwrap()
+                .is_none());
+            assert!(db
+                .get::<AccountStateChangeSchema>(&(version, address))
+                .unwrap()
+                .is_none());
+        }
+        assert!(db
+            .get::<AccountStateHistorySchema>(&(address, 2))
+            .unwrap()
+            .is_some());
+        assert!(db
+            .get::<AccountStateChangeSchema>(&(2, address))
+            .unwrap()
+            .is_some());
+    }
 }
 
 #[test]
@@ -142,6 +171,8 @@ fn test_worker_quit_eagerly() {
             Arc::clone(&db),
             command_receiver,
             Arc::new(AtomicU64::new(0)), /* progress */
+            Arc::new(AtomicU64::new(0)), /* ledger_progress */
+            Arc::new(AtomicU64::new(0)), /* account_state_history_progress */
         );
         command_sender
             .send(Command::Prune {
@@ -161,3 +192,118 @@ fn test_worker_quit_eagerly() {
         verify_state_in_store(state_store, address, Some(&value2), 2);
     }
 }
+
+fn ledger_pruner_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+    ledger_prune_window: u64,
+) {
+    let tmp_dir = TempPath::new();
+    let db = LibraDB::open(&tmp_dir, Some(ledger_prune_window));
+    db.save_transactions(
+        &[GENESIS_INFO.2.clone()],
+        0, /* first_version */
+        &Some(GENESIS_INFO.1.clone()),
+    )
+    .unwrap();
+    let mut cur_ver = 0;
+    let mut sent = Vec::new();
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            &txns_to_commit,
+            cur_ver + 1, /* first_version */
+            &Some(ledger_info_with_sigs.clone()),
+        )
+        .unwrap();
+        for txn_to_commit in txns_to_commit {
+            cur_ver += 1;
+            let signed_txn = txn_to_commit.signed_txn();
+            sent.push((cur_ver, signed_txn.sender(), signed_txn.sequence_number()));
+        }
+    }
+    db.pruner.wake_and_wait(cur_ver).unwrap();
+
+    let min_readable_version = cur_ver.saturating_sub(ledger_prune_window);
+    assert_eq!(
+        db.pruner.get_min_readable_ledger_version(),
+        min_readable_version
+    );
+
+    // Pruned history is refused and gone from the DB.
+    for version in 0..min_readable_version {
+        match db
+            .get_transactions(version, 1, cur_ver, true /* fetch_events */)
+            .unwrap_err()
+            .downcast::<LibraDbError>()
+            .unwrap()
+        {
+            LibraDbError::Pruned(_, ver) => assert_eq!(ver, min_readable_version),
+            e => panic!("Unexpected error: {}", e),
+        }
+        match db
+            .get_account_state_history(AccountAddress::default(), version, 1, cur_ver)
+            .unwrap_err()
+            .downcast::<LibraDbError>()
+            .unwrap()
+        {
+            LibraDbError::Pruned(_, ver) => assert_eq!(ver, min_readable_version),
+            e => panic!("Unexpected error: {}", e),
+        }
+        assert!(db
+            .db
+            .get::<SignedTransactionSchema>(&version)
+            .unwrap()
+            .is_none());
+        assert!(db
+            .db
+            .get::<TransactionInfoSchema>(&version)
+            .unwrap()
+            .is_none());
+    }
+    for (version, sender, seq_num) in sent {
+        let res = db.get_txn_by_account(sender, seq_num, cur_ver, false /* fetch_events */);
+        if version < min_readable_version {
+            match res.unwrap_err().downcast::<LibraDbError>().unwrap() {
+                LibraDbError::Pruned(_, ver) => assert_eq!(ver, min_readable_version),
+                e => panic!("Unexpected error: {}", e),
+            }
+        } else {
+            assert!(res.unwrap().is_some());
+        }
+    }
+    let mut iter = db.db.iter::<EventSchema>(ReadOptions::default()).unwrap();
+    iter.seek_to_first();
+    assert!(iter.all(|res| (res.unwrap().0).0 >= min_readable_version));
+    let mut iter = db
+        .db
+        .iter::<EventByKeySchema>(ReadOptions::default())
+        .unwrap();
+    iter.seek_to_first();
+    assert!(iter.all(|res| (res.unwrap().1).0 >= min_readable_version));
+
+    // What's left within the window can still be proven.
+    let (_, ledger_info_with_sigs) = input.last().unwrap();
+    db.get_transactions(
+        min_readable_version,
+        cur_ver - min_readable_version + 1,
+        cur_ver,
+        true, /* fetch_events */
+    )
+    .unwrap()
+    .verify(
+        ledger_info_with_sigs.ledger_info(),
+        Some(min_readable_version),
+    )
+    .unwrap();
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_ledger_pruner(
+        input in arb_blocks_to_commit(),
+        ledger_prune_window in 0u64..20,
+    ) {
+        ledger_pruner_impl(input, ledger_prune_window);
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the reverse of the index in
+//! [`AccountStateHistorySchema`](crate::schema::account_state_history::AccountStateHistorySchema),
+//! i.e. the accounts whose state changed at each version, so that the pruner can find the entries
+//! of the versions it prunes.
+//!
+//! ```text
+//! |<-------key------->|
+//! | version | address |
+//! ```
+//!
+//! `version` is serialized in big endian so that the changes are ordered by version.
+
+use crate::schema::{ensure_slice_len_eq, ACCOUNT_STATE_CHANGE_CF_NAME};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use failure::prelude::*;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, SeekKeyCodec, ValueCodec},
+};
+use std::{convert::TryFrom, mem::size_of};
+use types::{account_address::AccountAddress, transaction::Version};
+
+define_schema!(
+    AccountStateChangeSchema,
+    Key,
+    (),
+    ACCOUNT_STATE_CHANGE_CF_NAME
+);
+
+type Key = (Version, AccountAddress);
+
+impl KeyCodec<AccountStateChangeSchema> for Key {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (version, ref account_address) = *self;
+
+        let mut encoded = Vec::with_capacity(size_of::<Self>());
+        encoded.write_u64::<BigEndian>(version)?;
+        encoded.extend_from_slice(&account_address.to_vec());
+
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Self>())?;
+
+        let version = (&data[..size_of::<Version>()]).read_u64::<BigEndian>()?;
+        let address = AccountAddress::try_from(&data[size_of::<Version>()..])?;
+
+        Ok((version, address))
+    }
+}
+
+/// Seeks to the first change at a version.
+impl SeekKeyCodec<AccountStateChangeSchema> for Version {
+    fn encode_seek_key(&self) -> Result<Vec<u8>> {
+        let mut encoded = Vec::with_capacity(size_of::<Self>());
+        encoded.write_u64::<BigEndian>(*self)?;
+        Ok(encoded)
+    }
+}
+
+impl ValueCodec<AccountStateChangeSchema> for () {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,73 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for an index of the versions at which the state of
+//! an account changed, i.e. the transaction at each of them wrote to the account. With a version
+//! one can get the state of the account right after the change from the state Merkle tree.
+//!
+//! ```text
+//! |<-------key------->|
+//! | address | version |
+//! ```
+//!
+//! `version` is serialized in big endian so that the changes to an account are ordered by version.
+//!
+//! Entries are deleted by the pruner once the account state tree at their version is no longer
+//! readable, found via the reverse index in
+//! [`AccountStateChangeSchema`](crate::schema::account_state_change::AccountStateChangeSchema).
+
+use crate::schema::{ensure_slice_len_eq, ACCOUNT_STATE_HISTORY_CF_NAME};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use failure::prelude::*;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::{convert::TryFrom, mem::size_of};
+use types::{
+    account_address::{AccountAddress, ADDRESS_LENGTH},
+    transaction::Version,
+};
+
+define_schema!(
+    AccountStateHistorySchema,
+    Key,
+    (),
+    ACCOUNT_STATE_HISTORY_CF_NAME
+);
+
+type Key = (AccountAddress, Version);
+
+impl KeyCodec<AccountStateHistorySchema> for Key {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (ref account_address, version) = *self;
+
+        let mut encoded = account_address.to_vec();
+        encoded.write_u64::<BigEndian>(version)?;
+
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Self>())?;
+
+        let address = AccountAddress::try_from(&data[..ADDRESS_LENGTH])?;
+        let version = (&data[ADDRESS_LENGTH..]).read_u64::<BigEndian>()?;
+
+        Ok((address, version))
+    }
+}
+
+impl ValueCodec<AccountStateHistorySchema> for () {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
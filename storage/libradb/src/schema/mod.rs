@@ -6,6 +6,8 @@
 //!
 //! All schemas are `pub(crate)` so not shown in rustdoc, refer to the source code to see details.
 
+pub(crate) mod account_state_change;
+pub(crate) mod account_state_history;
 pub(crate) mod event;
 pub(crate) mod event_accumulator;
 pub(crate) mod event_by_key;
@@ -22,6 +24,8 @@ pub(crate) mod validator;
 use failure::prelude::*;
 use schemadb::ColumnFamilyName;
 
+pub(super) const ACCOUNT_STATE_CHANGE_CF_NAME: ColumnFamilyName = "account_state_change";
+pub(super) const ACCOUNT_STATE_HISTORY_CF_NAME: ColumnFamilyName = "account_state_history";
 pub(super) const EVENT_ACCUMULATOR_CF_NAME: ColumnFamilyName = "event_accumulator";
 pub(super) const EVENT_BY_KEY_CF_NAME: ColumnFamilyName = "event_by_key";
 pub(super) const EVENT_CF_NAME: ColumnFamilyName = "event";
@@ -10,16 +10,19 @@ use crate::{
     change_set::ChangeSet,
     ledger_counters::LedgerCounter,
     schema::{
+        account_state_change::AccountStateChangeSchema,
+        account_state_history::AccountStateHistorySchema,
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
     },
 };
 use crypto::{hash::CryptoHash, HashValue};
 use failure::prelude::*;
 use jellyfish_merkle::{
+    iterator::JellyfishMerkleIterator,
     node_type::{Node, NodeKey},
-    JellyfishMerkleTree, TreeReader,
+    JellyfishMerkleTree, TreeReader, TreeUpdateBatch, TreeWriter,
 };
-use schemadb::DB;
+use schemadb::{ReadOptions, SchemaBatch, DB};
 use std::{collections::HashMap, sync::Arc};
 use types::{
     account_address::AccountAddress, account_state_blob::AccountStateBlob,
@@ -46,14 +49,48 @@ impl StateStore {
         Ok((blob, proof))
     }
 
+    /// Returns the versions at which the state of the account at `address` changed, in ascending
+    /// order, starting from `start_version` up to `ledger_version`, at most `limit` of them.
+    pub fn get_account_state_change_versions(
+        &self,
+        address: AccountAddress,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<Version>> {
+        let mut iter = self
+            .db
+            .iter::<AccountStateHistorySchema>(ReadOptions::default())?;
+        iter.seek(&(address, start_version))?;
+
+        let mut versions = Vec::new();
+        for res in iter {
+            let ((addr, version), ()) = res?;
+            if addr != address || version > ledger_version || versions.len() as u64 >= limit {
+                break;
+            }
+            versions.push(version);
+        }
+        Ok(versions)
+    }
+
     /// Put the results generated by `account_state_sets` to `batch` and return the result root
-    /// hashes for each write set.
+    /// hashes for each write set. Also indexes the versions at which each account changed.
     pub fn put_account_state_sets(
         &self,
         account_state_sets: Vec<HashMap<AccountAddress, AccountStateBlob>>,
         first_version: Version,
         cs: &mut ChangeSet,
     ) -> Result<Vec<HashValue>> {
+        for (version, account_states) in (first_version..).zip(account_state_sets.iter()) {
+            for address in account_states.keys() {
+                cs.batch
+                    .put::<AccountStateHistorySchema>(&(*address, version), &())?;
+                cs.batch
+                    .put::<AccountStateChangeSchema>(&(version, *address), &())?;
+            }
+        }
+
         let blob_sets = account_state_sets
             .into_iter()
             .map(|account_states| {
@@ -97,6 +134,15 @@ impl StateStore {
 
         Ok(new_root_hash_vec)
     }
+
+    /// Returns an iterator over all the account state blobs of the tree at `version`, ordered by
+    /// the hashed account address.
+    pub fn get_account_iter(
+        &self,
+        version: Version,
+    ) -> Result<impl Iterator<Item = Result<(HashValue, AccountStateBlob)>> + '_> {
+        JellyfishMerkleIterator::new(self, version, HashValue::zero())
+    }
 }
 
 impl TreeReader for StateStore {
@@ -104,3 +150,20 @@ impl TreeReader for StateStore {
         Ok(self.db.get::<JellyfishMerkleNodeSchema>(node_key)?)
     }
 }
+
+impl TreeWriter for StateStore {
+    fn write_tree_update_batch(&self, batch: TreeUpdateBatch) -> Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+        batch
+            .node_batch
+            .iter()
+            .map(|(node_key, node)| schema_batch.put::<JellyfishMerkleNodeSchema>(node_key, node))
+            .collect::<Result<Vec<()>>>()?;
+        batch
+            .stale_node_index_batch
+            .iter()
+            .map(|row| schema_batch.put::<StaleNodeIndexSchema>(row, &()))
+            .collect::<Result<Vec<()>>>()?;
+        self.db.write_schemas(schema_batch)
+    }
+}
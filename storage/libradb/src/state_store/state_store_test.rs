@@ -227,3 +227,45 @@ fn test_retired_records() {
         verify_state_in_store(store, address3, Some(&value3_update), 2, root2);
     }
 }
+
+#[test]
+fn test_account_state_change_versions() {
+    let address1 = AccountAddress::new([1u8; ADDRESS_LENGTH]);
+    let address2 = AccountAddress::new([2u8; ADDRESS_LENGTH]);
+    let address3 = AccountAddress::new([3u8; ADDRESS_LENGTH]);
+    let value = AccountStateBlob::from(vec![0x01]);
+
+    let db = LibraDB::new_in_memory();
+    let store = &db.state_store;
+    let mut cs = ChangeSet::new();
+    store
+        .put_account_state_sets(
+            vec![
+                vec![(address1, value.clone()), (address2, value.clone())]
+                    .into_iter()
+                    .collect(),
+                vec![(address2, value.clone())].into_iter().collect(),
+                HashMap::new(),
+                vec![(address1, value.clone()), (address2, value)]
+                    .into_iter()
+                    .collect(),
+            ],
+            0, /* first_version */
+            &mut cs,
+        )
+        .unwrap();
+    store.db.write_schemas(cs.batch).unwrap();
+
+    let get_versions = |address, start_version, limit, ledger_version| {
+        store
+            .get_account_state_change_versions(address, start_version, limit, ledger_version)
+            .unwrap()
+    };
+    assert_eq!(get_versions(address1, 0, 10, 3), vec![0, 3]);
+    assert_eq!(get_versions(address2, 0, 10, 3), vec![0, 1, 3]);
+    assert_eq!(get_versions(address2, 1, 10, 3), vec![1, 3]);
+    assert_eq!(get_versions(address2, 0, 2, 3), vec![0, 1]);
+    assert_eq!(get_versions(address2, 0, 10, 2), vec![0, 1]);
+    assert!(get_versions(address2, 4, 10, 3).is_empty());
+    assert!(get_versions(address3, 0, 10, 3).is_empty());
+}
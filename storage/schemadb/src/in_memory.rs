@@ -0,0 +1,182 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module implements the in-memory storage engine behind [`DB::new_in_memory`](crate::DB),
+//! which keeps each column family in an ordered map. Nothing is persisted, so it's meant for tests
+//! that don't care about durability but want to run fast and deterministically.
+
+use crate::{ColumnFamilyName, WriteOp};
+use failure::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Bound,
+    sync::{Arc, RwLock},
+};
+
+type ColumnFamily = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Holds all column families behind a single lock, so a batch touching several of them is applied
+/// atomically. Each column family is copied on write only if an iterator still holds it, which
+/// gives iterators the same point-in-time view they have on RocksDB.
+#[derive(Debug)]
+pub(crate) struct InMemoryDB {
+    column_families: RwLock<HashMap<ColumnFamilyName, Arc<ColumnFamily>>>,
+}
+
+impl InMemoryDB {
+    pub fn new(cf_names: Vec<ColumnFamilyName>) -> Self {
+        Self {
+            column_families: RwLock::new(
+                cf_names
+                    .into_iter()
+                    .map(|cf_name| (cf_name, Arc::new(ColumnFamily::new())))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn get(&self, cf_name: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let column_families = self.column_families.read().unwrap();
+        Ok(get_cf(&column_families, cf_name)?.get(key).cloned())
+    }
+
+    pub fn put(&self, cf_name: &str, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        let mut column_families = self.column_families.write().unwrap();
+        Arc::make_mut(get_cf_mut(&mut column_families, cf_name)?).insert(key, value);
+        Ok(())
+    }
+
+    pub fn range_delete(&self, cf_name: &str, begin: &[u8], end: &[u8]) -> Result<()> {
+        let mut column_families = self.column_families.write().unwrap();
+        let cf = get_cf_mut(&mut column_families, cf_name)?;
+        if begin >= end {
+            return Ok(());
+        }
+
+        let cf = Arc::make_mut(cf);
+        let mut deleted = cf.split_off(begin);
+        let mut rest = deleted.split_off(end);
+        cf.append(&mut rest);
+        Ok(())
+    }
+
+    pub fn write(
+        &self,
+        rows: &HashMap<ColumnFamilyName, BTreeMap<Vec<u8>, WriteOp>>,
+    ) -> Result<()> {
+        let mut column_families = self.column_families.write().unwrap();
+        // Check all column families before touching any of them so a failed batch leaves no trace.
+        for cf_name in rows.keys() {
+            get_cf(&column_families, cf_name)?;
+        }
+
+        for (cf_name, cf_rows) in rows {
+            let cf = Arc::make_mut(get_cf_mut(&mut column_families, cf_name)?);
+            for (key, write_op) in cf_rows {
+                match write_op {
+                    WriteOp::Value(value) => cf.insert(key.clone(), value.clone()),
+                    WriteOp::Deletion => cf.remove(key),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    pub fn iter(&self, cf_name: &str) -> Result<InMemoryIterator> {
+        let column_families = self.column_families.read().unwrap();
+        Ok(InMemoryIterator {
+            cf: Arc::clone(get_cf(&column_families, cf_name)?),
+            cursor: None,
+        })
+    }
+
+    /// Returns the total size of the keys and values in each column family in bytes.
+    pub fn get_sizes_cf(&self) -> BTreeMap<String, u64> {
+        self.column_families
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(cf_name, cf)| {
+                let size = cf
+                    .iter()
+                    .map(|(key, value)| (key.len() + value.len()) as u64)
+                    .sum();
+                (cf_name.to_string(), size)
+            })
+            .collect()
+    }
+}
+
+fn get_cf<'a>(
+    column_families: &'a HashMap<ColumnFamilyName, Arc<ColumnFamily>>,
+    cf_name: &str,
+) -> Result<&'a Arc<ColumnFamily>> {
+    column_families
+        .get(cf_name)
+        .ok_or_else(|| format_err!("Column family not found: {}", cf_name))
+}
+
+fn get_cf_mut<'a>(
+    column_families: &'a mut HashMap<ColumnFamilyName, Arc<ColumnFamily>>,
+    cf_name: &str,
+) -> Result<&'a mut Arc<ColumnFamily>> {
+    column_families
+        .get_mut(cf_name)
+        .ok_or_else(|| format_err!("Column family not found: {}", cf_name))
+}
+
+/// Iterates over a snapshot of a column family taken when the iterator is created. Like a RocksDB
+/// iterator, it's not positioned until one of the seek methods is called.
+pub(crate) struct InMemoryIterator {
+    cf: Arc<ColumnFamily>,
+    /// The key the iterator is positioned at, or `None` if it's not at a valid position.
+    cursor: Option<Vec<u8>>,
+}
+
+impl InMemoryIterator {
+    pub fn seek_to_first(&mut self) -> bool {
+        self.cursor = self.cf.keys().next().cloned();
+        self.cursor.is_some()
+    }
+
+    pub fn seek_to_last(&mut self) -> bool {
+        self.cursor = self.cf.keys().next_back().cloned();
+        self.cursor.is_some()
+    }
+
+    pub fn seek(&mut self, key: &[u8]) -> bool {
+        self.cursor = self
+            .cf
+            .range::<[u8], _>((Bound::Included(key), Bound::Unbounded))
+            .next()
+            .map(|(k, _)| k.clone());
+        self.cursor.is_some()
+    }
+
+    pub fn seek_for_prev(&mut self, key: &[u8]) -> bool {
+        self.cursor = self
+            .cf
+            .range::<[u8], _>((Bound::Unbounded, Bound::Included(key)))
+            .next_back()
+            .map(|(k, _)| k.clone());
+        self.cursor.is_some()
+    }
+
+    pub fn next(&mut self) -> bool {
+        self.cursor = match &self.cursor {
+            Some(cursor) => self
+                .cf
+                .range::<[u8], _>((Bound::Excluded(cursor.as_slice()), Bound::Unbounded))
+                .next()
+                .map(|(k, _)| k.clone()),
+            None => None,
+        };
+        self.cursor.is_some()
+    }
+
+    pub fn kv(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        self.cursor
+            .as_ref()
+            .map(|key| (key.clone(), self.cf[key].clone()))
+    }
+}
@@ -10,11 +10,18 @@
 //! families.  To use this library to store a kind of key-value pairs, the user needs to use the
 //! [`define_schema!`] macro to define the schema name, the types of key and value, and name of the
 //! column family.
+//!
+//! For tests, the same API can be served by an in-memory storage engine instead of RocksDB, see
+//! [`DB::new_in_memory`].
 
 #[macro_use]
 pub mod schema;
+mod in_memory;
 
-use crate::schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec};
+use crate::{
+    in_memory::{InMemoryDB, InMemoryIterator},
+    schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec},
+};
 use failure::prelude::*;
 use lazy_static::lazy_static;
 use metrics::OpMetrics;
@@ -92,7 +99,7 @@ impl SchemaBatch {
 /// DB Iterator parameterized on [`Schema`] that seeks with [`Schema::Key`] and yields
 /// [`Schema::Key`] and [`Schema::Value`]
 pub struct SchemaIterator<'a, S> {
-    db_iter: rocksdb::DBIterator<&'a rocksdb::DB>,
+    db_iter: DBIterator<'a>,
     phantom: PhantomData<S>,
 }
 
@@ -100,7 +107,7 @@ impl<'a, S> SchemaIterator<'a, S>
 where
     S: Schema,
 {
-    fn new(db_iter: rocksdb::DBIterator<&'a rocksdb::DB>) -> Self {
+    fn new(db_iter: DBIterator<'a>) -> Self {
         SchemaIterator {
             db_iter,
             phantom: PhantomData,
@@ -109,12 +116,18 @@ where
 
     /// Seeks to the first key.
     pub fn seek_to_first(&mut self) -> bool {
-        self.db_iter.seek(rocksdb::SeekKey::Start)
+        match &mut self.db_iter {
+            DBIterator::RocksDB(db_iter) => db_iter.seek(rocksdb::SeekKey::Start),
+            DBIterator::InMemory(db_iter) => db_iter.seek_to_first(),
+        }
     }
 
     /// Seeks to the last key.
     pub fn seek_to_last(&mut self) -> bool {
-        self.db_iter.seek(rocksdb::SeekKey::End)
+        match &mut self.db_iter {
+            DBIterator::RocksDB(db_iter) => db_iter.seek(rocksdb::SeekKey::End),
+            DBIterator::InMemory(db_iter) => db_iter.seek_to_last(),
+        }
     }
 
     /// Seeks to the first key whose binary representation is equal to or greater than that of the
@@ -124,7 +137,10 @@ where
         SK: SeekKeyCodec<S>,
     {
         let key = <SK as SeekKeyCodec<S>>::encode_seek_key(seek_key)?;
-        Ok(self.db_iter.seek(rocksdb::SeekKey::Key(&key)))
+        Ok(match &mut self.db_iter {
+            DBIterator::RocksDB(db_iter) => db_iter.seek(rocksdb::SeekKey::Key(&key)),
+            DBIterator::InMemory(db_iter) => db_iter.seek(&key),
+        })
     }
 
     /// Seeks to the last key whose binary representation is less than or equal to that of the
@@ -136,7 +152,10 @@ where
         SK: SeekKeyCodec<S>,
     {
         let key = <SK as SeekKeyCodec<S>>::encode_seek_key(seek_key)?;
-        Ok(self.db_iter.seek_for_prev(rocksdb::SeekKey::Key(&key)))
+        Ok(match &mut self.db_iter {
+            DBIterator::RocksDB(db_iter) => db_iter.seek_for_prev(rocksdb::SeekKey::Key(&key)),
+            DBIterator::InMemory(db_iter) => db_iter.seek_for_prev(&key),
+        })
     }
 }
 
@@ -147,8 +166,17 @@ where
     type Item = Result<(S::Key, S::Value)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.db_iter.kv().map(|(raw_key, raw_value)| {
-            self.db_iter.next();
+        let kv = match &mut self.db_iter {
+            DBIterator::RocksDB(db_iter) => db_iter.kv().map(|kv| {
+                db_iter.next();
+                kv
+            }),
+            DBIterator::InMemory(db_iter) => db_iter.kv().map(|kv| {
+                db_iter.next();
+                kv
+            }),
+        };
+        kv.map(|(raw_key, raw_value)| {
             Ok((
                 <S::Key as KeyCodec<S>>::decode_key(&raw_key)?,
                 <S::Value as ValueCodec<S>>::decode_value(&raw_value)?,
@@ -170,11 +198,24 @@ fn convert_rocksdb_err(msg: String) -> failure::Error {
     format_err!("RocksDB internal error: {}.", msg)
 }
 
+/// The storage engine a [`DB`] runs on.
+#[derive(Debug)]
+enum Engine {
+    RocksDB(rocksdb::DB),
+    InMemory(InMemoryDB),
+}
+
+/// Raw iterator of the storage engine under a [`SchemaIterator`].
+enum DBIterator<'a> {
+    RocksDB(rocksdb::DBIterator<&'a rocksdb::DB>),
+    InMemory(InMemoryIterator),
+}
+
 /// This DB is a schematized RocksDB wrapper where all data passed in and out are typed according to
-/// [`Schema`]s.
+/// [`Schema`]s. It can also run on an in-memory engine, see [`DB::new_in_memory`].
 #[derive(Debug)]
 pub struct DB {
-    inner: rocksdb::DB,
+    inner: Engine,
 }
 
 impl DB {
@@ -209,6 +250,43 @@ impl DB {
         Ok(db)
     }
 
+    /// Opens an existing db at `path` in read-only mode, with the column families named in
+    /// `cf_names`. Writes to the returned instance fail, and it doesn't see updates made by
+    /// another instance opened on the same path after it's opened.
+    pub fn open_readonly<P: AsRef<Path>>(path: P, cf_names: Vec<ColumnFamilyName>) -> Result<Self> {
+        ensure!(
+            db_exists(path.as_ref()),
+            "DB doesn't exist at {:?}.",
+            path.as_ref()
+        );
+
+        let inner = rocksdb::DB::open_cf_for_read_only(
+            DBOptions::new(),
+            path.as_ref().to_str().ok_or_else(|| {
+                format_err!("Path {:?} can not be converted to string.", path.as_ref())
+            })?,
+            cf_names
+                .into_iter()
+                .map(|cf_name| (cf_name, ColumnFamilyOptions::default()))
+                .collect(),
+            false, /* error_if_log_file_exist */
+        )
+        .map_err(convert_rocksdb_err)?;
+
+        Ok(DB {
+            inner: Engine::RocksDB(inner),
+        })
+    }
+
+    /// Creates an empty db with the column families named in `cf_names`, kept in memory instead of
+    /// on disk. It supports the same operations as a RocksDB backed one and is dropped with it, so
+    /// it's mainly useful in tests.
+    pub fn new_in_memory(cf_names: Vec<ColumnFamilyName>) -> Self {
+        DB {
+            inner: Engine::InMemory(InMemoryDB::new(cf_names)),
+        }
+    }
+
     fn open_cf<'a, P, T>(opts: DBOptions, path: P, cfds: Vec<T>) -> Result<DB>
     where
         P: AsRef<Path>,
@@ -223,25 +301,36 @@ impl DB {
         )
         .map_err(convert_rocksdb_err)?;
 
-        Ok(DB { inner })
+        Ok(DB {
+            inner: Engine::RocksDB(inner),
+        })
     }
 
     fn create_cf<'a, T>(&mut self, cfd: T) -> Result<()>
     where
         T: Into<ColumnFamilyDescriptor<'a>>,
     {
-        let _cf_handle = self.inner.create_cf(cfd).map_err(convert_rocksdb_err)?;
-        Ok(())
+        match &mut self.inner {
+            Engine::RocksDB(db) => {
+                let _cf_handle = db.create_cf(cfd).map_err(convert_rocksdb_err)?;
+                Ok(())
+            }
+            Engine::InMemory(_) => bail!("Column families of an in-memory DB are fixed."),
+        }
     }
 
     /// Reads single record by key.
     pub fn get<S: Schema>(&self, schema_key: &S::Key) -> Result<Option<S::Value>> {
         let k = <S::Key as KeyCodec<S>>::encode_key(&schema_key)?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
 
-        self.inner
-            .get_cf(cf_handle, &k)
-            .map_err(convert_rocksdb_err)?
+        let raw_value = match &self.inner {
+            Engine::RocksDB(db) => db
+                .get_cf(self.get_cf_handle(S::COLUMN_FAMILY_NAME)?, &k)
+                .map_err(convert_rocksdb_err)?
+                .map(|raw_value| raw_value.to_vec()),
+            Engine::InMemory(db) => db.get(S::COLUMN_FAMILY_NAME, &k)?,
+        };
+        raw_value
             .map(|raw_value| <S::Value as ValueCodec<S>>::decode_value(&raw_value))
             .transpose()
     }
@@ -250,11 +339,18 @@ impl DB {
     pub fn put<S: Schema>(&self, key: &S::Key, value: &S::Value) -> Result<()> {
         let k = <S::Key as KeyCodec<S>>::encode_key(&key)?;
         let v = <S::Value as ValueCodec<S>>::encode_value(&value)?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
 
-        self.inner
-            .put_cf_opt(cf_handle, &k, &v, &default_write_options())
-            .map_err(convert_rocksdb_err)
+        match &self.inner {
+            Engine::RocksDB(db) => db
+                .put_cf_opt(
+                    self.get_cf_handle(S::COLUMN_FAMILY_NAME)?,
+                    &k,
+                    &v,
+                    &default_write_options(),
+                )
+                .map_err(convert_rocksdb_err),
+            Engine::InMemory(db) => db.put(S::COLUMN_FAMILY_NAME, k, v),
+        }
     }
 
     /// Delete all keys in range [begin, end).
@@ -268,37 +364,52 @@ impl DB {
     {
         let raw_begin = begin.encode_seek_key()?;
         let raw_end = end.encode_seek_key()?;
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
 
-        self.inner
-            .delete_range_cf(&cf_handle, &raw_begin, &raw_end)
-            .map_err(convert_rocksdb_err)
+        match &self.inner {
+            Engine::RocksDB(db) => db
+                .delete_range_cf(
+                    self.get_cf_handle(S::COLUMN_FAMILY_NAME)?,
+                    &raw_begin,
+                    &raw_end,
+                )
+                .map_err(convert_rocksdb_err),
+            Engine::InMemory(db) => db.range_delete(S::COLUMN_FAMILY_NAME, &raw_begin, &raw_end),
+        }
     }
 
-    /// Returns a [`SchemaIterator`] on a certain schema.
+    /// Returns a [`SchemaIterator`] on a certain schema. `opts` is ignored by the in-memory engine.
     pub fn iter<S: Schema>(&self, opts: ReadOptions) -> Result<SchemaIterator<S>> {
-        let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
-        Ok(SchemaIterator::new(self.inner.iter_cf_opt(cf_handle, opts)))
+        let db_iter = match &self.inner {
+            Engine::RocksDB(db) => DBIterator::RocksDB(
+                db.iter_cf_opt(self.get_cf_handle(S::COLUMN_FAMILY_NAME)?, opts),
+            ),
+            Engine::InMemory(db) => DBIterator::InMemory(db.iter(S::COLUMN_FAMILY_NAME)?),
+        };
+        Ok(SchemaIterator::new(db_iter))
     }
 
     /// Writes a group of records wrapped in a [`SchemaBatch`].
     pub fn write_schemas(&self, batch: SchemaBatch) -> Result<()> {
-        let db_batch = rocksdb::WriteBatch::new();
-        for (cf_name, rows) in &batch.rows {
-            let cf_handle = self.get_cf_handle(cf_name)?;
-            for (key, write_op) in rows {
-                match write_op {
-                    WriteOp::Value(value) => db_batch.put_cf(cf_handle, key, value),
-                    WriteOp::Deletion => db_batch.delete_cf(cf_handle, key),
+        match &self.inner {
+            Engine::RocksDB(db) => {
+                let db_batch = rocksdb::WriteBatch::new();
+                for (cf_name, rows) in &batch.rows {
+                    let cf_handle = self.get_cf_handle(cf_name)?;
+                    for (key, write_op) in rows {
+                        match write_op {
+                            WriteOp::Value(value) => db_batch.put_cf(cf_handle, key, value),
+                            WriteOp::Deletion => db_batch.delete_cf(cf_handle, key),
+                        }
+                        .map_err(convert_rocksdb_err)?;
+                    }
                 }
-                .map_err(convert_rocksdb_err)?;
+
+                db.write_opt(&db_batch, &default_write_options())
+                    .map_err(convert_rocksdb_err)?;
             }
+            Engine::InMemory(db) => db.write(&batch.rows)?,
         }
 
-        self.inner
-            .write_opt(&db_batch, &default_write_options())
-            .map_err(convert_rocksdb_err)?;
-
         // Bump counters only after DB write succeeds.
         for (cf_name, rows) in &batch.rows {
             for (key, write_op) in rows {
@@ -316,7 +427,11 @@ impl DB {
     }
 
     fn get_cf_handle(&self, cf_name: &str) -> Result<&CFHandle> {
-        self.inner.cf_handle(cf_name).ok_or_else(|| {
+        let db = match &self.inner {
+            Engine::RocksDB(db) => db,
+            Engine::InMemory(_) => bail!("An in-memory DB has no column family handles."),
+        };
+        db.cf_handle(cf_name).ok_or_else(|| {
             format_err!(
                 "DB::cf_handle not found for column family name: {}",
                 cf_name
@@ -326,12 +441,15 @@ impl DB {
 
     /// Returns the approximate size of each non-empty column family in bytes.
     pub fn get_approximate_sizes_cf(&self) -> Result<BTreeMap<String, u64>> {
+        let db = match &self.inner {
+            Engine::RocksDB(db) => db,
+            Engine::InMemory(db) => return Ok(db.get_sizes_cf()),
+        };
         let mut cf_sizes = BTreeMap::new();
 
-        for cf_name in self.inner.cf_names().into_iter().map(ToString::to_string) {
+        for cf_name in db.cf_names().into_iter().map(ToString::to_string) {
             let cf_handle = self.get_cf_handle(&cf_name)?;
-            let size = self
-                .inner
+            let size = db
                 .get_property_int_cf(cf_handle, "rocksdb.estimate-live-data-size")
                 .ok_or_else(|| {
                     format_err!(
@@ -346,13 +464,16 @@ impl DB {
     }
 
     /// Flushes all memtable data. If `sync` is true, the flush will wait until it's done. This is
-    /// only used for testing `get_approximate_sizes_cf` in unit tests.
+    /// only used for testing `get_approximate_sizes_cf` in unit tests, and is a no-op for an
+    /// in-memory DB.
     pub fn flush_all(&self, sync: bool) -> Result<()> {
-        for cf_name in self.inner.cf_names() {
+        let db = match &self.inner {
+            Engine::RocksDB(db) => db,
+            Engine::InMemory(_) => return Ok(()),
+        };
+        for cf_name in db.cf_names() {
             let cf_handle = self.get_cf_handle(cf_name)?;
-            self.inner
-                .flush_cf(cf_handle, sync)
-                .map_err(convert_rocksdb_err)?;
+            db.flush_cf(cf_handle, sync).map_err(convert_rocksdb_err)?;
         }
         Ok(())
     }
@@ -90,7 +90,7 @@ fn open_db(dir: &tools::tempdir::TempPath) -> DB {
 }
 
 struct TestDB {
-    _tmpdir: tools::tempdir::TempPath,
+    _tmpdir: Option<tools::tempdir::TempPath>,
     db: DB,
 }
 
@@ -100,10 +100,26 @@ impl TestDB {
         let db = open_db(&tmpdir);
 
         TestDB {
-            _tmpdir: tmpdir,
+            _tmpdir: Some(tmpdir),
             db,
         }
     }
+
+    fn new_in_memory() -> Self {
+        TestDB {
+            _tmpdir: None,
+            db: DB::new_in_memory(vec![
+                DEFAULT_CF_NAME,
+                TestSchema1::COLUMN_FAMILY_NAME,
+                TestSchema2::COLUMN_FAMILY_NAME,
+            ]),
+        }
+    }
+}
+
+/// Returns a DB on each storage engine, on which a test is expected to behave the same.
+fn test_dbs() -> Vec<TestDB> {
+    vec![TestDB::new(), TestDB::new_in_memory()]
 }
 
 impl std::ops::Deref for TestDB {
@@ -116,42 +132,42 @@ impl std::ops::Deref for TestDB {
 
 #[test]
 fn test_schema_put_get() {
-    let db = TestDB::new();
-
-    db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
-    db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
-    db.put::<TestSchema1>(&TestField(2), &TestField(2)).unwrap();
-    db.put::<TestSchema2>(&TestField(2), &TestField(3)).unwrap();
-    db.put::<TestSchema2>(&TestField(3), &TestField(4)).unwrap();
-    db.put::<TestSchema2>(&TestField(4), &TestField(5)).unwrap();
+    for db in test_dbs() {
+        db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+        db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
+        db.put::<TestSchema1>(&TestField(2), &TestField(2)).unwrap();
+        db.put::<TestSchema2>(&TestField(2), &TestField(3)).unwrap();
+        db.put::<TestSchema2>(&TestField(3), &TestField(4)).unwrap();
+        db.put::<TestSchema2>(&TestField(4), &TestField(5)).unwrap();
 
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(0)).unwrap(),
-        Some(TestField(0)),
-    );
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(1)).unwrap(),
-        Some(TestField(1)),
-    );
-    assert_eq!(
-        db.get::<TestSchema1>(&TestField(2)).unwrap(),
-        Some(TestField(2)),
-    );
-    assert_eq!(db.get::<TestSchema1>(&TestField(3)).unwrap(), None);
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(0)).unwrap(),
+            Some(TestField(0)),
+        );
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(1)).unwrap(),
+            Some(TestField(1)),
+        );
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(2)).unwrap(),
+            Some(TestField(2)),
+        );
+        assert_eq!(db.get::<TestSchema1>(&TestField(3)).unwrap(), None);
 
-    assert_eq!(db.get::<TestSchema2>(&TestField(1)).unwrap(), None);
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(2)).unwrap(),
-        Some(TestField(3)),
-    );
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(3)).unwrap(),
-        Some(TestField(4)),
-    );
-    assert_eq!(
-        db.get::<TestSchema2>(&TestField(4)).unwrap(),
-        Some(TestField(5)),
-    );
+        assert_eq!(db.get::<TestSchema2>(&TestField(1)).unwrap(), None);
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(2)).unwrap(),
+            Some(TestField(3)),
+        );
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(3)).unwrap(),
+            Some(TestField(4)),
+        );
+        assert_eq!(
+            db.get::<TestSchema2>(&TestField(4)).unwrap(),
+            Some(TestField(5)),
+        );
+    }
 }
 
 proptest! {
@@ -162,23 +178,25 @@ proptest! {
         ranges_to_delete in vec(
             (0..100u32).prop_flat_map(|begin| (Just(begin), (begin..100u32))), 0..10)
     ) {
-        let db = TestDB::new();
-        for i in 0..100u32 {
-            db.put::<TestSchema1>(&TestField(i), &TestField(i)).unwrap();
-        }
-        let mut should_exist = [true; 100];
-        for (begin, end) in ranges_to_delete {
-            db.range_delete::<TestSchema1, TestField>(&TestField(begin), &TestField(end)).unwrap();
-            for i in begin..end {
-                should_exist[i as usize] = false;
+        for db in test_dbs() {
+            for i in 0..100u32 {
+                db.put::<TestSchema1>(&TestField(i), &TestField(i)).unwrap();
+            }
+            let mut should_exist = [true; 100];
+            for &(begin, end) in &ranges_to_delete {
+                db.range_delete::<TestSchema1, TestField>(&TestField(begin), &TestField(end))
+                    .unwrap();
+                for i in begin..end {
+                    should_exist[i as usize] = false;
+                }
             }
-        }
 
-        for (i, should_exist) in should_exist.iter().enumerate() {
-            assert_eq!(
-                db.get::<TestSchema1>(&TestField(i as u32)).unwrap().is_some(),
-                *should_exist,
-            )
+            for (i, should_exist) in should_exist.iter().enumerate() {
+                assert_eq!(
+                    db.get::<TestSchema1>(&TestField(i as u32)).unwrap().is_some(),
+                    *should_exist,
+                )
+            }
         }
     }
 }
@@ -201,84 +219,84 @@ fn gen_expected_values(values: &[(u32, u32)]) -> Vec<(TestField, TestField)> {
 
 #[test]
 fn test_single_schema_batch() {
-    let db = TestDB::new();
-
-    let mut db_batch = SchemaBatch::new();
-    db_batch
-        .put::<TestSchema1>(&TestField(0), &TestField(0))
-        .unwrap();
-    db_batch
-        .put::<TestSchema1>(&TestField(1), &TestField(1))
-        .unwrap();
-    db_batch
-        .put::<TestSchema1>(&TestField(2), &TestField(2))
-        .unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(3), &TestField(3))
-        .unwrap();
-    db_batch.delete::<TestSchema2>(&TestField(4)).unwrap();
-    db_batch.delete::<TestSchema2>(&TestField(3)).unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(4), &TestField(4))
-        .unwrap();
-    db_batch
-        .put::<TestSchema2>(&TestField(5), &TestField(5))
-        .unwrap();
-    db.write_schemas(db_batch).unwrap();
+    for db in test_dbs() {
+        let mut db_batch = SchemaBatch::new();
+        db_batch
+            .put::<TestSchema1>(&TestField(0), &TestField(0))
+            .unwrap();
+        db_batch
+            .put::<TestSchema1>(&TestField(1), &TestField(1))
+            .unwrap();
+        db_batch
+            .put::<TestSchema1>(&TestField(2), &TestField(2))
+            .unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(3), &TestField(3))
+            .unwrap();
+        db_batch.delete::<TestSchema2>(&TestField(4)).unwrap();
+        db_batch.delete::<TestSchema2>(&TestField(3)).unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(4), &TestField(4))
+            .unwrap();
+        db_batch
+            .put::<TestSchema2>(&TestField(5), &TestField(5))
+            .unwrap();
+        db.write_schemas(db_batch).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1), (2, 2)]),
-    );
-    assert_eq!(
-        collect_values::<TestSchema2>(&db),
-        gen_expected_values(&[(4, 4), (5, 5)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1), (2, 2)]),
+        );
+        assert_eq!(
+            collect_values::<TestSchema2>(&db),
+            gen_expected_values(&[(4, 4), (5, 5)]),
+        );
+    }
 }
 
 #[test]
 fn test_two_schema_batches() {
-    let db = TestDB::new();
-
-    let mut db_batch1 = SchemaBatch::new();
-    db_batch1
-        .put::<TestSchema1>(&TestField(0), &TestField(0))
-        .unwrap();
-    db_batch1
-        .put::<TestSchema1>(&TestField(1), &TestField(1))
-        .unwrap();
-    db_batch1
-        .put::<TestSchema1>(&TestField(2), &TestField(2))
-        .unwrap();
-    db_batch1.delete::<TestSchema1>(&TestField(2)).unwrap();
-    db.write_schemas(db_batch1).unwrap();
+    for db in test_dbs() {
+        let mut db_batch1 = SchemaBatch::new();
+        db_batch1
+            .put::<TestSchema1>(&TestField(0), &TestField(0))
+            .unwrap();
+        db_batch1
+            .put::<TestSchema1>(&TestField(1), &TestField(1))
+            .unwrap();
+        db_batch1
+            .put::<TestSchema1>(&TestField(2), &TestField(2))
+            .unwrap();
+        db_batch1.delete::<TestSchema1>(&TestField(2)).unwrap();
+        db.write_schemas(db_batch1).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1)]),
+        );
 
-    let mut db_batch2 = SchemaBatch::new();
-    db_batch2.delete::<TestSchema2>(&TestField(3)).unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(3), &TestField(3))
-        .unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(4), &TestField(4))
-        .unwrap();
-    db_batch2
-        .put::<TestSchema2>(&TestField(5), &TestField(5))
-        .unwrap();
-    db.write_schemas(db_batch2).unwrap();
+        let mut db_batch2 = SchemaBatch::new();
+        db_batch2.delete::<TestSchema2>(&TestField(3)).unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(3), &TestField(3))
+            .unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(4), &TestField(4))
+            .unwrap();
+        db_batch2
+            .put::<TestSchema2>(&TestField(5), &TestField(5))
+            .unwrap();
+        db.write_schemas(db_batch2).unwrap();
 
-    assert_eq!(
-        collect_values::<TestSchema1>(&db),
-        gen_expected_values(&[(0, 0), (1, 1)]),
-    );
-    assert_eq!(
-        collect_values::<TestSchema2>(&db),
-        gen_expected_values(&[(3, 3), (4, 4), (5, 5)]),
-    );
+        assert_eq!(
+            collect_values::<TestSchema1>(&db),
+            gen_expected_values(&[(0, 0), (1, 1)]),
+        );
+        assert_eq!(
+            collect_values::<TestSchema2>(&db),
+            gen_expected_values(&[(3, 3), (4, 4), (5, 5)]),
+        );
+    }
 }
 
 #[test]
@@ -301,25 +319,66 @@ fn test_reopen() {
     }
 }
 
+#[test]
+fn test_open_readonly() {
+    let tmpdir = tools::tempdir::TempPath::new();
+    assert!(DB::open_readonly(&tmpdir.path(), vec![DEFAULT_CF_NAME]).is_err());
+    {
+        let db = open_db(&tmpdir);
+        db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+    }
+
+    let db = DB::open_readonly(
+        &tmpdir.path(),
+        vec![
+            DEFAULT_CF_NAME,
+            TestSchema1::COLUMN_FAMILY_NAME,
+            TestSchema2::COLUMN_FAMILY_NAME,
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        db.get::<TestSchema1>(&TestField(0)).unwrap(),
+        Some(TestField(0)),
+    );
+    assert!(db.put::<TestSchema1>(&TestField(1), &TestField(1)).is_err());
+}
+
 #[test]
 fn test_report_size() {
-    let db = TestDB::new();
+    for db in test_dbs() {
+        for i in 0..1000 {
+            let mut db_batch = SchemaBatch::new();
+            db_batch
+                .put::<TestSchema1>(&TestField(i), &TestField(i))
+                .unwrap();
+            db_batch
+                .put::<TestSchema2>(&TestField(i), &TestField(i))
+                .unwrap();
+            db.write_schemas(db_batch).unwrap();
+        }
 
-    for i in 0..1000 {
-        let mut db_batch = SchemaBatch::new();
-        db_batch
-            .put::<TestSchema1>(&TestField(i), &TestField(i))
-            .unwrap();
-        db_batch
-            .put::<TestSchema2>(&TestField(i), &TestField(i))
-            .unwrap();
-        db.write_schemas(db_batch).unwrap();
+        db.flush_all(/* sync = */ true).unwrap();
+
+        let cf_sizes = db.get_approximate_sizes_cf().unwrap();
+        assert!(*cf_sizes.get("TestCF1").unwrap() > 0);
+        assert!(*cf_sizes.get("TestCF2").unwrap() > 0);
+        assert_eq!(*cf_sizes.get("default").unwrap(), 0);
     }
+}
 
-    db.flush_all(/* sync = */ true).unwrap();
+#[test]
+fn test_in_memory_batch_with_unknown_column_family() {
+    let db = DB::new_in_memory(vec![DEFAULT_CF_NAME, TestSchema1::COLUMN_FAMILY_NAME]);
 
-    let cf_sizes = db.get_approximate_sizes_cf().unwrap();
-    assert!(*cf_sizes.get("TestCF1").unwrap() > 0);
-    assert!(*cf_sizes.get("TestCF2").unwrap() > 0);
-    assert_eq!(*cf_sizes.get("default").unwrap(), 0);
+    let mut db_batch = SchemaBatch::new();
+    db_batch
+        .put::<TestSchema1>(&TestField(0), &TestField(0))
+        .unwrap();
+    db_batch
+        .put::<TestSchema2>(&TestField(0), &TestField(0))
+        .unwrap();
+    assert!(db.write_schemas(db_batch).is_err());
+    assert_eq!(db.get::<TestSchema1>(&TestField(0)).unwrap(), None);
+    assert!(db.get::<TestSchema2>(&TestField(0)).is_err());
 }
@@ -71,7 +71,7 @@ fn collect_values(iter: SchemaIterator<TestSchema>) -> Vec<u32> {
 }
 
 struct TestDB {
-    _tmpdir: tools::tempdir::TempPath,
+    _tmpdir: Option<tools::tempdir::TempPath>,
     db: DB,
 }
 
@@ -90,6 +90,15 @@ impl TestDB {
         .collect();
         let db = DB::open(&tmpdir.path(), cf_opts_map).unwrap();
 
+        Self::populate(Some(tmpdir), db)
+    }
+
+    fn new_in_memory() -> Self {
+        let db = DB::new_in_memory(vec![DEFAULT_CF_NAME, TestSchema::COLUMN_FAMILY_NAME]);
+        Self::populate(None, db)
+    }
+
+    fn populate(tmpdir: Option<tools::tempdir::TempPath>, db: DB) -> Self {
         db.put::<TestSchema>(&TestKey(1, 0, 0), &TestValue(100))
             .unwrap();
         db.put::<TestSchema>(&TestKey(1, 0, 2), &TestValue(102))
@@ -114,6 +123,11 @@ impl TestDB {
     }
 }
 
+/// Returns a DB on each storage engine, on which a test is expected to behave the same.
+fn test_dbs() -> Vec<TestDB> {
+    vec![TestDB::new(), TestDB::new_in_memory()]
+}
+
 impl TestDB {
     fn iter(&self) -> SchemaIterator<TestSchema> {
         self.db
@@ -132,83 +146,122 @@ impl std::ops::Deref for TestDB {
 
 #[test]
 fn test_seek_to_first() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_to_first();
-    assert_eq!(
-        collect_values(iter),
-        [100, 102, 104, 110, 112, 114, 200, 202]
-    );
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        iter.seek_to_first();
+        assert_eq!(
+            collect_values(iter),
+            [100, 102, 104, 110, 112, 114, 200, 202]
+        );
+    }
 }
 
 #[test]
 fn test_seek_to_last() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_to_last();
-    assert_eq!(collect_values(iter), [202]);
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        iter.seek_to_last();
+        assert_eq!(collect_values(iter), [202]);
+    }
 }
 
 #[test]
 fn test_seek_by_existing_key() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        iter.seek(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_by_nonexistent_key() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [112, 114, 200, 202]);
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        iter.seek(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [112, 114, 200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_existing_key() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&TestKey(1, 1, 0)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_nonexistent_key() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
-    assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&TestKey(1, 1, 1)).unwrap();
+        assert_eq!(collect_values(iter), [110, 112, 114, 200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_by_1prefix() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [200, 202]);
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        iter.seek(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_1prefix() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
-    assert_eq!(collect_values(iter), [114, 200, 202]);
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&KeyPrefix1(2)).unwrap();
+        assert_eq!(collect_values(iter), [114, 200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_by_2prefix() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [200, 202]);
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        iter.seek(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [200, 202]);
+    }
 }
 
 #[test]
 fn test_seek_for_prev_by_2prefix() {
-    let db = TestDB::new();
-    let mut iter = db.iter();
-    iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
-    assert_eq!(collect_values(iter), [114, 200, 202]);
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        iter.seek_for_prev(&KeyPrefix2(2, 0)).unwrap();
+        assert_eq!(collect_values(iter), [114, 200, 202]);
+    }
+}
+
+#[test]
+fn test_seek_for_prev_before_first_key() {
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        assert!(!iter.seek_for_prev(&KeyPrefix1(1)).unwrap());
+        assert_eq!(collect_values(iter), Vec::<u32>::new());
+    }
+}
+
+#[test]
+fn test_iterator_sees_snapshot() {
+    for db in test_dbs() {
+        let mut iter = db.iter();
+        db.put::<TestSchema>(&TestKey(2, 0, 4), &TestValue(204))
+            .unwrap();
+        db.range_delete::<TestSchema, KeyPrefix1>(&KeyPrefix1(1), &KeyPrefix1(2))
+            .unwrap();
+
+        iter.seek_to_first();
+        assert_eq!(
+            collect_values(iter),
+            [100, 102, 104, 110, 112, 114, 200, 202]
+        );
+        let mut iter = db.iter();
+        iter.seek_to_first();
+        assert_eq!(collect_values(iter), [200, 202, 204]);
+    }
 }
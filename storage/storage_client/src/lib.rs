@@ -10,7 +10,11 @@
 mod state_view;
 
 use failure::prelude::*;
-use futures::{compat::Future01CompatExt, executor::block_on, prelude::*};
+use futures::{
+    compat::{Future01CompatExt, Stream01CompatExt},
+    executor::block_on,
+    prelude::*,
+};
 use futures_01::future::Future as Future01;
 use grpcio::{ChannelBuilder, Environment};
 use metrics::counters::SVC_COUNTERS;
@@ -30,9 +34,10 @@ use types::{
     account_state_blob::AccountStateBlob,
     crypto_proxies::{LedgerInfoWithSignatures, ValidatorChangeEventWithProof},
     get_with_proof::{
-        RequestItem, ResponseItem, UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
+        RequestItem, ResponseItem, SubscribeToTransactionsRequest, SubscribeToTransactionsResponse,
+        UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
     },
-    proof::SparseMerkleProof,
+    proof::{AccumulatorConsistencyProof, SparseMerkleProof},
     transaction::{TransactionListWithProof, TransactionToCommit, Version},
 };
 
@@ -106,6 +111,7 @@ impl StorageRead for StorageReadServiceClient {
         Vec<ResponseItem>,
         LedgerInfoWithSignatures,
         Vec<ValidatorChangeEventWithProof>,
+        Option<AccumulatorConsistencyProof>,
     )> {
         block_on(self.update_to_latest_ledger_async(client_known_version, requested_items))
     }
@@ -121,6 +127,7 @@ impl StorageRead for StorageReadServiceClient {
                         Vec<ResponseItem>,
                         LedgerInfoWithSignatures,
                         Vec<ValidatorChangeEventWithProof>,
+                        Option<AccumulatorConsistencyProof>,
                     )>,
                 > + Send,
         >,
@@ -139,6 +146,7 @@ impl StorageRead for StorageReadServiceClient {
                 rust_resp.response_items,
                 rust_resp.ledger_info_with_sigs,
                 rust_resp.validator_change_events,
+                rust_resp.ledger_consistency_proof,
             ))
         })
         .boxed()
@@ -176,6 +184,34 @@ impl StorageRead for StorageReadServiceClient {
             .boxed()
     }
 
+    fn subscribe_to_transactions(
+        &self,
+        start_version: Version,
+        batch_size: u64,
+    ) -> Pin<
+        Box<dyn Stream<Item = Result<(TransactionListWithProof, LedgerInfoWithSignatures)>> + Send>,
+    > {
+        let req = SubscribeToTransactionsRequest::new(start_version, batch_size);
+        match self
+            .client()
+            .subscribe_to_transactions(&log_and_convert(req))
+        {
+            Ok(receiver) => receiver
+                .compat()
+                .map(|resp| {
+                    let rust_resp = SubscribeToTransactionsResponse::from_proto(
+                        resp.map_err(convert_grpc_err)?,
+                    )?;
+                    Ok((
+                        rust_resp.txn_list_with_proof,
+                        rust_resp.ledger_info_with_sigs,
+                    ))
+                })
+                .boxed(),
+            Err(e) => stream::once(future::err(convert_grpc_err(e))).boxed(),
+        }
+    }
+
     fn get_account_state_with_proof_by_version(
         &self,
         address: AccountAddress,
@@ -307,6 +343,7 @@ pub trait StorageRead: Send + Sync {
         Vec<ResponseItem>,
         LedgerInfoWithSignatures,
         Vec<ValidatorChangeEventWithProof>,
+        Option<AccumulatorConsistencyProof>,
     )>;
 
     /// See [`LibraDB::update_to_latest_ledger`].
@@ -324,6 +361,7 @@ pub trait StorageRead: Send + Sync {
                         Vec<ResponseItem>,
                         LedgerInfoWithSignatures,
                         Vec<ValidatorChangeEventWithProof>,
+                        Option<AccumulatorConsistencyProof>,
                     )>,
                 > + Send,
         >,
@@ -351,6 +389,22 @@ pub trait StorageRead: Send + Sync {
         fetch_events: bool,
     ) -> Pin<Box<dyn Future<Output = Result<TransactionListWithProof>> + Send>>;
 
+    /// Streams chunks of up to `batch_size` committed transactions with their infos and events,
+    /// starting from `start_version`, each with proof relative to the ledger info that comes with
+    /// it. The stream keeps following the ledger as new blocks are committed, and ends after the
+    /// first error. Unlike the other APIs, this one has no counterpart in [`LibraDB`], but is
+    /// served by the storage service on top of [`LibraDB::get_transactions`].
+    ///
+    /// [`LibraDB`]: ../libradb/struct.LibraDB.html
+    /// [`LibraDB::get_transactions`]: ../libradb/struct.LibraDB.html#method.get_transactions
+    fn subscribe_to_transactions(
+        &self,
+        start_version: Version,
+        batch_size: u64,
+    ) -> Pin<
+        Box<dyn Stream<Item = Result<(TransactionListWithProof, LedgerInfoWithSignatures)>> + Send>,
+    >;
+
     /// See [`LibraDB::get_account_state_with_proof_by_version`].
     ///
     /// [`LibraDB::get_account_state_with_proof_by_version`]:
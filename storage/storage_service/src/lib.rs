@@ -7,11 +7,19 @@
 //! [`storage_client`](../storage_client/index.html) instead of via
 //! [`StorageClient`](../storage_proto/proto/storage_grpc/struct.StorageClient.html) directly.
 
+#![feature(async_await)]
+
 pub mod mocks;
+mod subscription;
 
+use crate::subscription::{subscribe_to_transactions, CommitNotifier};
 use config::config::NodeConfig;
 use failure::prelude::*;
-use grpc_helpers::{provide_grpc_response, spawn_service_thread_with_drop_closure, ServerHandle};
+use futures::prelude::*;
+use grpc_helpers::{
+    provide_grpc_response, provide_grpc_stream_response, spawn_service_thread_with_drop_closure,
+    ServerHandle,
+};
 use libradb::LibraDB;
 use logger::prelude::*;
 use metrics::counters::SVC_COUNTERS;
@@ -30,11 +38,17 @@ use storage_proto::proto::{
     },
     storage_grpc::{create_storage, Storage},
 };
-use types::proto::get_with_proof::{UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse};
+use types::proto::get_with_proof::{
+    SubscribeToTransactionsRequest, SubscribeToTransactionsResponse, UpdateToLatestLedgerRequest,
+    UpdateToLatestLedgerResponse,
+};
 
 /// Starts storage service according to config.
 pub fn start_storage_service(config: &NodeConfig) -> ServerHandle {
-    let (storage_service, shutdown_receiver) = StorageService::new(&config.storage.get_dir());
+    let (storage_service, shutdown_receiver) = StorageService::new(
+        &config.storage.get_dir(),
+        config.storage.ledger_prune_window,
+    );
     spawn_service_thread_with_drop_closure(
         create_storage(storage_service),
         config.storage.address.clone(),
@@ -71,16 +85,23 @@ pub struct StorageService {
 struct LibraDBWrapper {
     db: Option<LibraDB>,
     shutdown_sender: Mutex<mpsc::Sender<()>>,
+    /// Notified after each commit of a ledger info, which is what transaction subscriptions
+    /// follow.
+    commit_notifier: CommitNotifier,
 }
 
 impl LibraDBWrapper {
-    pub fn new<P: AsRef<Path>>(path: &P) -> (Self, mpsc::Receiver<()>) {
-        let db = LibraDB::new(path);
+    pub fn new<P: AsRef<Path>>(
+        path: &P,
+        ledger_prune_window: Option<u64>,
+    ) -> (Self, mpsc::Receiver<()>) {
+        let db = LibraDB::open(path, ledger_prune_window);
         let (shutdown_sender, shutdown_receiver) = mpsc::channel();
         (
             Self {
                 db: Some(db),
                 shutdown_sender: Mutex::new(shutdown_sender),
+                commit_notifier: CommitNotifier::default(),
             },
             shutdown_receiver,
         )
@@ -110,6 +131,7 @@ impl Deref for LibraDBWrapper {
 
 impl StorageService {
     /// This opens a [`LibraDB`] at `path` and returns a [`StorageService`] instance serving it.
+    /// The ledger history older than `ledger_prune_window` versions is pruned if it's set.
     ///
     /// A receiver side of a channel is also returned through which one can receive a notice after
     /// all resources used by the service including the underlying [`LibraDB`] instance are
@@ -119,15 +141,18 @@ impl StorageService {
     /// ```no_run,
     ///    # use storage_service::*;
     ///    # use std::path::Path;
-    ///    let (service, shutdown_receiver) = StorageService::new(&Path::new("path/to/db"));
+    ///    let (service, shutdown_receiver) = StorageService::new(&Path::new("path/to/db"), None);
     ///
     ///    drop(service);
     ///    shutdown_receiver.recv().expect("recv() should succeed.");
     ///
     ///    // LibraDB instance is guaranteed to be properly dropped at this point.
     /// ```
-    pub fn new<P: AsRef<Path>>(path: &P) -> (Self, mpsc::Receiver<()>) {
-        let (db_wrapper, shutdown_receiver) = LibraDBWrapper::new(path);
+    pub fn new<P: AsRef<Path>>(
+        path: &P,
+        ledger_prune_window: Option<u64>,
+    ) -> (Self, mpsc::Receiver<()>) {
+        let (db_wrapper, shutdown_receiver) = LibraDBWrapper::new(path, ledger_prune_window);
         (
             Self {
                 db: Arc::new(db_wrapper),
@@ -144,7 +169,12 @@ impl StorageService {
     ) -> Result<UpdateToLatestLedgerResponse> {
         let rust_req = types::get_with_proof::UpdateToLatestLedgerRequest::from_proto(req)?;
 
-        let (response_items, ledger_info_with_sigs, validator_change_events) = self
+        let (
+            response_items,
+            ledger_info_with_sigs,
+            validator_change_events,
+            ledger_consistency_proof,
+        ) = self
             .db
             .update_to_latest_ledger(rust_req.client_known_version, rust_req.requested_items)?;
 
@@ -152,6 +182,7 @@ impl StorageService {
             response_items,
             ledger_info_with_sigs,
             validator_change_events,
+            ledger_consistency_proof,
         };
 
         Ok(rust_resp.into_proto())
@@ -175,6 +206,29 @@ impl StorageService {
         Ok(rust_resp.into_proto())
     }
 
+    fn subscribe_to_transactions_inner(
+        &self,
+        req: SubscribeToTransactionsRequest,
+    ) -> Result<impl Stream<Item = Result<SubscribeToTransactionsResponse>> + Send + Unpin> {
+        let rust_req = types::get_with_proof::SubscribeToTransactionsRequest::from_proto(req)?;
+        ensure!(rust_req.batch_size > 0, "Batch size must be positive.");
+
+        Ok(subscribe_to_transactions(
+            Arc::downgrade(&self.db),
+            rust_req.start_version,
+            rust_req.batch_size,
+        )
+        .map(|chunk| {
+            let (txn_list_with_proof, ledger_info_with_sigs) = chunk?;
+            let rust_resp = types::get_with_proof::SubscribeToTransactionsResponse::new(
+                txn_list_with_proof,
+                ledger_info_with_sigs,
+            );
+            Ok(rust_resp.into_proto())
+        })
+        .boxed())
+    }
+
     fn get_account_state_with_proof_by_version_inner(
         &self,
         req: GetAccountStateWithProofByVersionRequest,
@@ -203,6 +257,9 @@ impl StorageService {
             rust_req.first_version,
             &rust_req.ledger_info_with_signatures,
         )?;
+        if rust_req.ledger_info_with_signatures.is_some() {
+            self.db.commit_notifier.notify();
+        }
         Ok(SaveTransactionsResponse::new())
     }
 
@@ -262,6 +319,18 @@ impl Storage for StorageService {
         provide_grpc_response(resp, ctx, sink);
     }
 
+    fn subscribe_to_transactions(
+        &mut self,
+        ctx: grpcio::RpcContext,
+        req: SubscribeToTransactionsRequest,
+        sink: grpcio::ServerStreamingSink<SubscribeToTransactionsResponse>,
+    ) {
+        debug!("[GRPC] Storage::subscribe_to_transactions");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let resp = self.subscribe_to_transactions_inner(req);
+        provide_grpc_stream_response(resp, ctx, sink);
+    }
+
     fn get_account_state_with_proof_by_version(
         &mut self,
         ctx: grpcio::RpcContext,
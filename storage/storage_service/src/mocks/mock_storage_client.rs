@@ -21,7 +21,7 @@ use types::{
     crypto_proxies::{LedgerInfoWithSignatures, ValidatorChangeEventWithProof},
     event::EventHandle,
     get_with_proof::{RequestItem, ResponseItem},
-    proof::SparseMerkleProof,
+    proof::{AccumulatorConsistencyProof, SparseMerkleProof},
     proto::{
         account_state_blob::AccountStateWithProof,
         get_with_proof::{
@@ -30,7 +30,6 @@ use types::{
             UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
         },
         ledger_info::LedgerInfoWithSignatures as ProtoLedgerInfoWithSignatures,
-        proof::AccumulatorProof,
         transaction::TransactionListWithProof,
         transaction_info::TransactionInfo,
     },
@@ -55,6 +54,7 @@ impl StorageRead for MockStorageReadClient {
         Vec<ResponseItem>,
         LedgerInfoWithSignatures,
         Vec<ValidatorChangeEventWithProof>,
+        Option<AccumulatorConsistencyProof>,
     )> {
         let request = types::get_with_proof::UpdateToLatestLedgerRequest::new(
             client_known_version,
@@ -68,6 +68,7 @@ impl StorageRead for MockStorageReadClient {
             response.response_items,
             response.ledger_info_with_sigs,
             response.validator_change_events,
+            response.ledger_consistency_proof,
         ))
     }
 
@@ -82,6 +83,7 @@ impl StorageRead for MockStorageReadClient {
                         Vec<ResponseItem>,
                         LedgerInfoWithSignatures,
                         Vec<ValidatorChangeEventWithProof>,
+                        Option<AccumulatorConsistencyProof>,
                     )>,
                 > + Send,
         >,
@@ -114,6 +116,23 @@ impl StorageRead for MockStorageReadClient {
         unimplemented!()
     }
 
+    fn subscribe_to_transactions(
+        &self,
+        _start_version: Version,
+        _batch_size: u64,
+    ) -> Pin<
+        Box<
+            dyn Stream<
+                    Item = Result<(
+                        types::transaction::TransactionListWithProof,
+                        LedgerInfoWithSignatures,
+                    )>,
+                > + Send,
+        >,
+    > {
+        unimplemented!()
+    }
+
     fn get_account_state_with_proof_by_version(
         &self,
         _address: AccountAddress,
@@ -232,12 +251,6 @@ fn get_mock_response_item(request_item: &ProtoRequestItem) -> Result<ProtoRespon
                 let sender = AccountAddress::new([1; ADDRESS_LENGTH]);
                 if request.limit > 0 {
                     let (txns, infos) = get_mock_txn_data(sender, 0, request.limit - 1);
-                    if !txns.is_empty() {
-                        ret.set_proof_of_first_transaction(get_accumulator_proof());
-                    }
-                    if txns.len() >= 2 {
-                        ret.set_proof_of_last_transaction(get_accumulator_proof());
-                    }
                     ret.set_transactions(protobuf::RepeatedField::from_vec(txns));
                     ret.set_infos(protobuf::RepeatedField::from_vec(infos));
                 }
@@ -247,6 +260,9 @@ fn get_mock_response_item(request_item: &ProtoRequestItem) -> Result<ProtoRespon
 
                 response_item.set_get_transactions_response(resp);
             }
+            RequestItem_oneof_requested_items::get_account_state_history_request(_request) => {
+                unimplemented!();
+            }
         }
     }
     Ok(response_item)
@@ -276,10 +292,6 @@ fn get_mock_txn_data(
     (txns, infos)
 }
 
-fn get_accumulator_proof() -> AccumulatorProof {
-    types::proof::AccumulatorProof::new(vec![]).into_proto()
-}
-
 fn get_transaction_info() -> types::transaction::TransactionInfo {
     types::transaction::TransactionInfo::new(
         HashValue::zero(),
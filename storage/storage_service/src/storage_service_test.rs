@@ -3,9 +3,10 @@
 
 use super::*;
 use config::config::NodeConfigHelpers;
+use futures::executor::block_on;
 use grpcio::EnvBuilder;
 use itertools::zip_eq;
-use libradb::mock_genesis::db_with_mock_genesis;
+use libradb::mock_genesis::{db_with_mock_genesis, GENESIS_INFO};
 #[cfg(any(test, feature = "testing"))]
 use libradb::test_helper::arb_blocks_to_commit;
 use proptest::prelude::*;
@@ -83,7 +84,8 @@ proptest! {
             let (
                 response_items,
                 response_ledger_info_with_sigs,
-                _validator_change_events
+                _validator_change_events,
+                _ledger_consistency_proof,
             ) = read_client
                 .update_to_latest_ledger(0, account_state_request_items).unwrap();
             for ((address, blob), response_item) in zip_eq(account_states, response_items) {
@@ -106,4 +108,60 @@ proptest! {
             prop_assert_eq!(ledger_info_with_sigs, &response_ledger_info_with_sigs);
          }
     }
+
+    #[test]
+    fn test_subscribe_to_transactions(blocks in arb_blocks_to_commit().no_shrink()) {
+        let (_tmp_dir, _server_handler, read_client, write_client) =
+            start_test_storage_with_read_write_client(/* need_to_use_genesis = */ true);
+
+        // Subscribe before anything but genesis is committed, so the stream may have to wait for
+        // the commits.
+        let mut chunks = read_client.subscribe_to_transactions(
+            0, /* start_version */
+            2, /* batch_size */
+        );
+
+        let mut expected_txns = vec![GENESIS_INFO.2.clone()];
+        let mut version = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in &blocks {
+            write_client
+                .save_transactions(txns_to_commit.clone(),
+                                   version + 1, /* first_version */
+                                   Some(ledger_info_with_sigs.clone()),
+                ).unwrap();
+            version += txns_to_commit.len() as u64;
+            expected_txns.extend(txns_to_commit.iter().cloned());
+        }
+
+        let mut next_version = 0;
+        while next_version <= version {
+            let (txn_list_with_proof, ledger_info_with_sigs) =
+                block_on(chunks.next()).unwrap().unwrap();
+            prop_assert!(!txn_list_with_proof.is_empty());
+            prop_assert!(txn_list_with_proof.len() <= 2);
+            txn_list_with_proof
+                .verify(ledger_info_with_sigs.ledger_info(), Some(next_version))
+                .unwrap();
+
+            let events = txn_list_with_proof.events.as_ref().unwrap();
+            for ((signed_txn, _txn_info), txn_events) in
+                zip_eq(&txn_list_with_proof.transaction_and_infos, events)
+            {
+                let expected = &expected_txns[next_version as usize];
+                prop_assert_eq!(signed_txn, expected.signed_txn());
+                prop_assert_eq!(txn_events, expected.events());
+                next_version += 1;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_subscribe_to_transactions_with_zero_batch_size() {
+    let (_tmp_dir, _server_handler, read_client, _write_client) =
+        start_test_storage_with_read_write_client(/* need_to_use_genesis = */ true);
+
+    let mut chunks =
+        read_client.subscribe_to_transactions(0 /* start_version */, 0 /* batch_size */);
+    assert!(block_on(chunks.next()).unwrap().is_err());
 }
@@ -0,0 +1,126 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module implements the server side of `Storage::SubscribeToTransactions`, which streams
+//! committed transactions and keeps following the ledger as new blocks are committed.
+
+use crate::LibraDBWrapper;
+use failure::prelude::*;
+use futures::{channel::oneshot, stream, Stream};
+use libradb::LibraDB;
+use std::sync::{Mutex, Weak};
+use types::{
+    crypto_proxies::LedgerInfoWithSignatures,
+    transaction::{TransactionListWithProof, Version},
+};
+
+/// Lets subscriptions that have caught up with the ledger wait for the next commit instead of
+/// polling the DB.
+#[derive(Default)]
+pub(crate) struct CommitNotifier {
+    waiters: Mutex<Vec<oneshot::Sender<()>>>,
+}
+
+impl CommitNotifier {
+    /// Returns a receiver that resolves after the next commit, or fails if the notifier is dropped
+    /// first.
+    pub fn next_commit(&self) -> oneshot::Receiver<()> {
+        let (sender, receiver) = oneshot::channel();
+        let mut waiters = self.waiters.lock().expect("Failed to lock mutex.");
+        // Forget about the subscriptions that have gone away in the meantime.
+        waiters.retain(|waiter| !waiter.is_canceled());
+        waiters.push(sender);
+        receiver
+    }
+
+    /// Wakes up everyone waiting for the next commit.
+    pub fn notify(&self) {
+        for waiter in self
+            .waiters
+            .lock()
+            .expect("Failed to lock mutex.")
+            .drain(..)
+        {
+            // The subscription may have gone away already.
+            let _ = waiter.send(());
+        }
+    }
+}
+
+/// Returns the stream of chunks of up to `batch_size` transactions starting at `start_version`.
+/// The stream ends after the first error, or when the service is dropped.
+///
+/// It only holds a weak reference to the DB, so a subscription waiting for the next commit
+/// doesn't stop the DB from being closed when the service shuts down.
+pub(crate) fn subscribe_to_transactions(
+    db: Weak<LibraDBWrapper>,
+    start_version: Version,
+    batch_size: u64,
+) -> impl Stream<Item = Result<(TransactionListWithProof, LedgerInfoWithSignatures)>> {
+    stream::unfold(Some(start_version), move |next_version| {
+        let db = db.clone();
+        async move {
+            let next_version = next_version?;
+            match next_chunk(db, next_version, batch_size).await {
+                Ok(Some((txn_list_with_proof, ledger_info_with_sigs))) => {
+                    let num_txns = txn_list_with_proof.len() as u64;
+                    Some((
+                        Ok((txn_list_with_proof, ledger_info_with_sigs)),
+                        Some(next_version + num_txns),
+                    ))
+                }
+                Ok(None) => None,
+                Err(e) => Some((Err(e), None)),
+            }
+        }
+    })
+}
+
+/// Returns the chunk starting at `start_version`, waiting for it to be committed if needed, or
+/// `None` if the service is dropped in the meantime.
+async fn next_chunk(
+    db: Weak<LibraDBWrapper>,
+    start_version: Version,
+    batch_size: u64,
+) -> Result<Option<(TransactionListWithProof, LedgerInfoWithSignatures)>> {
+    loop {
+        let commit = {
+            let db = match db.upgrade() {
+                Some(db) => db,
+                None => return Ok(None),
+            };
+            if let Some(chunk) = read_chunk(&db, start_version, batch_size)? {
+                return Ok(Some(chunk));
+            }
+            let commit = db.commit_notifier.next_commit();
+            // Something may have been committed before we started waiting.
+            if let Some(chunk) = read_chunk(&db, start_version, batch_size)? {
+                return Ok(Some(chunk));
+            }
+            commit
+        };
+        if commit.await.is_err() {
+            return Ok(None);
+        }
+    }
+}
+
+/// Reads the chunk starting at `start_version` with proof relative to the latest ledger info, or
+/// returns `None` if the latest ledger info doesn't cover `start_version` yet.
+fn read_chunk(
+    db: &LibraDB,
+    start_version: Version,
+    batch_size: u64,
+) -> Result<Option<(TransactionListWithProof, LedgerInfoWithSignatures)>> {
+    let ledger_info_with_sigs = match db.get_latest_ledger_info_option() {
+        Some(li) if li.ledger_info().version() >= start_version => li,
+        _ => return Ok(None),
+    };
+    let txn_list_with_proof = db.get_transactions(
+        start_version,
+        batch_size,
+        ledger_info_with_sigs.ledger_info().version(),
+        true, /* fetch_events */
+    )?;
+    Ok(Some((txn_list_with_proof, ledger_info_with_sigs)))
+}
@@ -26,7 +26,11 @@ use crate::{
     validator_signer::ValidatorSigner as RawValidatorSigner,
     validator_verifier::{ValidatorVerifier as RawValidatorVerifier, VerifyError},
 };
-use crypto::{hash::HashValue, traits::Signature as RawSignature};
+use crypto::{
+    bls12381::{BLS12381PublicKey, BLS12381Signature},
+    hash::HashValue,
+    traits::Signature as RawSignature,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
@@ -65,6 +69,24 @@ impl<Sig: RawSignature> From<Sig> for SignatureWrapper<Sig> {
     }
 }
 
+impl SignatureWrapper<BLS12381Signature> {
+    pub fn aggregate(signatures: &[&Self]) -> failure::Result<Self> {
+        let signatures: Vec<_> = signatures.iter().map(|signature| &signature.0).collect();
+        BLS12381Signature::aggregate(&signatures).map(SignatureWrapper)
+    }
+
+    pub fn verify_aggregates(
+        validator_verifier: &RawValidatorVerifier<BLS12381PublicKey>,
+        aggregated_signatures: &[(HashValue, &[AccountAddress], &Self)],
+    ) -> std::result::Result<(), VerifyError> {
+        let aggregated_signatures: Vec<_> = aggregated_signatures
+            .iter()
+            .map(|(message, authors, signature)| (*message, *authors, &signature.0))
+            .collect();
+        validator_verifier.verify_aggregate_signatures(&aggregated_signatures)
+    }
+}
+
 // This sets the types containing cryptographic materials used in the
 // consensus crate. It is intended as a one-stop shop for changing the
 // signing scheme of the consensus. If consensus uses a type that
@@ -87,3 +109,25 @@ pub type LedgerInfoWithSignatures = RawLedgerInfoWithSignatures<Ed25519Signature
 pub type ValidatorVerifier = RawValidatorVerifier<Ed25519PublicKey>;
 pub type ValidatorSigner = RawValidatorSigner<Ed25519PrivateKey>;
 pub type ValidatorChangeEventWithProof = RawValidatorChangeEventWithProof<Ed25519Signature>;
+
+/// The instantiations for BLS12-381, whose signatures of a ledger info can be aggregated into a
+/// single one, verified with `ValidatorVerifier::verify_aggregate_signature` once the keys are
+/// registered with `ValidatorVerifier::new_with_proofs_of_possession`. Consensus aggregates them
+/// in its certificates when the validators sign with the BLS12-381 keys they registered alongside
+/// their consensus keys (see `ValidatorVerifier::with_aggregation_verifier`).
+pub mod bls12381 {
+    use super::{
+        RawLedgerInfoWithSignatures, RawValidatorChangeEventWithProof, RawValidatorSigner,
+        RawValidatorVerifier, SignatureWrapper,
+    };
+    use crypto::bls12381::*;
+
+    #[cfg(any(test, feature = "testing"))]
+    pub type SecretKey = BLS12381PrivateKey;
+
+    pub type Signature = SignatureWrapper<BLS12381Signature>;
+    pub type LedgerInfoWithSignatures = RawLedgerInfoWithSignatures<BLS12381Signature>;
+    pub type ValidatorVerifier = RawValidatorVerifier<BLS12381PublicKey>;
+    pub type ValidatorSigner = RawValidatorSigner<BLS12381PrivateKey>;
+    pub type ValidatorChangeEventWithProof = RawValidatorChangeEventWithProof<BLS12381Signature>;
+}
@@ -8,9 +8,12 @@ use crate::{
     account_state_blob::AccountStateWithProof,
     contract_event::EventWithProof,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::{
+        accumulator::Accumulator, verify_accumulator_consistency, AccumulatorConsistencyProof,
+    },
     proto::get_with_proof::{
-        GetAccountStateRequest, GetAccountStateResponse,
-        GetAccountTransactionBySequenceNumberRequest,
+        GetAccountStateHistoryRequest, GetAccountStateHistoryResponse, GetAccountStateRequest,
+        GetAccountStateResponse, GetAccountTransactionBySequenceNumberRequest,
         GetAccountTransactionBySequenceNumberResponse, GetEventsByEventAccessPathRequest,
         GetEventsByEventAccessPathResponse, GetTransactionsRequest, GetTransactionsResponse,
     },
@@ -18,7 +21,10 @@ use crate::{
     validator_change::ValidatorChangeEventWithProof,
     validator_verifier::ValidatorVerifier,
 };
-use crypto::{hash::CryptoHash, *};
+use crypto::{
+    hash::{CryptoHash, TransactionAccumulatorHasher},
+    *,
+};
 use failure::prelude::*;
 #[cfg(any(test, feature = "testing"))]
 use proptest_derive::Arbitrary;
@@ -47,6 +53,9 @@ pub struct UpdateToLatestLedgerResponse<Sig> {
     pub response_items: Vec<ResponseItem>,
     pub ledger_info_with_sigs: LedgerInfoWithSignatures<Sig>,
     pub validator_change_events: Vec<ValidatorChangeEventWithProof<Sig>>,
+    /// `None` if the server can't prove the ledger extends the one at `client_known_version`,
+    /// e.g. its history has been pruned.
+    pub ledger_consistency_proof: Option<AccumulatorConsistencyProof>,
 }
 
 impl<Sig: Signature> IntoProto for UpdateToLatestLedgerResponse<Sig> {
@@ -57,6 +66,9 @@ impl<Sig: Signature> IntoProto for UpdateToLatestLedgerResponse<Sig> {
         out.set_response_items(self.response_items.into_proto());
         out.set_ledger_info_with_sigs(self.ledger_info_with_sigs.into_proto());
         out.set_validator_change_events(self.validator_change_events.into_proto());
+        if let Some(proof) = self.ledger_consistency_proof {
+            out.set_ledger_consistency_proof(proof.into_proto());
+        }
         out
     }
 }
@@ -76,6 +88,11 @@ impl<Sig: Signature> FromProto for UpdateToLatestLedgerResponse<Sig> {
                 <Vec<ValidatorChangeEventWithProof<Sig>> as FromProto>::from_proto(
                     object.take_validator_change_events(),
                 )?,
+            ledger_consistency_proof: object
+                .ledger_consistency_proof
+                .take()
+                .map(AccumulatorConsistencyProof::from_proto)
+                .transpose()?,
         })
     }
 }
@@ -86,11 +103,13 @@ impl<Sig: Signature> UpdateToLatestLedgerResponse<Sig> {
         response_items: Vec<ResponseItem>,
         ledger_info_with_sigs: LedgerInfoWithSignatures<Sig>,
         validator_change_events: Vec<ValidatorChangeEventWithProof<Sig>>,
+        ledger_consistency_proof: Option<AccumulatorConsistencyProof>,
     ) -> Self {
         UpdateToLatestLedgerResponse {
             response_items,
             ledger_info_with_sigs,
             validator_change_events,
+            ledger_consistency_proof,
         }
     }
 
@@ -112,6 +131,33 @@ impl<Sig: Signature> UpdateToLatestLedgerResponse<Sig> {
             &self.ledger_info_with_sigs,
         )
     }
+
+    /// Verifies that the ledger in the response extends the one the client knew at the
+    /// `client_known_version` of the request, represented by `known_accumulator`, the transaction
+    /// accumulator of the first `client_known_version + 1` transactions. Returns the accumulator
+    /// of the ledger in the response, which the client can keep to verify the next response.
+    ///
+    /// This should be called after [`verify`](UpdateToLatestLedgerResponse::verify), which checks
+    /// the signatures on the ledger info. Fails if the response carries no consistency proof.
+    pub fn verify_ledger_consistency(
+        &self,
+        known_accumulator: &Accumulator<TransactionAccumulatorHasher>,
+    ) -> Result<Accumulator<TransactionAccumulatorHasher>> {
+        let ledger_info = self.ledger_info_with_sigs.ledger_info();
+        let ledger_consistency_proof = self.ledger_consistency_proof.as_ref().ok_or_else(|| {
+            format_err!(
+                "No proof that the ledger at version {} extends the one at version {}.",
+                ledger_info.version(),
+                known_accumulator.num_leaves().saturating_sub(1),
+            )
+        })?;
+        verify_accumulator_consistency(
+            known_accumulator,
+            ledger_consistency_proof,
+            ledger_info.version() + 1,
+            ledger_info.transaction_accumulator_hash(),
+        )
+    }
 }
 
 /// Verifies content of an [`UpdateToLatestLedgerResponse`] against the proofs it
@@ -225,6 +271,23 @@ fn verify_response_item(
             *fetch_events,
             txn_list_with_proof,
         ),
+        // GetAccountStateHistory
+        (
+            RequestItem::GetAccountStateHistory {
+                address,
+                start_version,
+                limit,
+            },
+            ResponseItem::GetAccountStateHistory {
+                account_states_with_proof,
+            },
+        ) => verify_get_account_state_history_resp(
+            ledger_info,
+            *address,
+            *start_version,
+            *limit,
+            account_states_with_proof,
+        ),
         // Request-response item types mismatch.
         _ => bail!(
             "RequestItem/ResponseItem types mismatch. request: {:?}, response: {:?}",
@@ -370,6 +433,42 @@ fn verify_get_txns_resp(
     }
 }
 
+/// Checks the states are of versions in the requested range, in ascending order, and each is what
+/// the account holds at its version. There's no way to tell if some change is left out.
+fn verify_get_account_state_history_resp(
+    ledger_info: &LedgerInfo,
+    req_address: AccountAddress,
+    req_start_version: Version,
+    req_limit: u64,
+    account_states_with_proof: &[AccountStateWithProof],
+) -> Result<()> {
+    ensure!(
+        account_states_with_proof.len() as u64 <= req_limit,
+        "Bad GetAccountStateHistory response. Limit: {}, states returned: {}.",
+        req_limit,
+        account_states_with_proof.len(),
+    );
+
+    let mut prev_version = None;
+    for account_state_with_proof in account_states_with_proof {
+        let version = account_state_with_proof.version;
+        ensure!(
+            version >= req_start_version
+                && version <= ledger_info.version()
+                && prev_version.map_or(true, |prev| version > prev),
+            "Bad GetAccountStateHistory response. Version {} out of order or out of range \
+             [{}, {}].",
+            version,
+            req_start_version,
+            ledger_info.version(),
+        );
+        account_state_with_proof.verify(ledger_info, version, req_address)?;
+        prev_version = Some(version);
+    }
+
+    Ok(())
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(any(test, feature = "testing"), derive(Arbitrary))]
 pub enum RequestItem {
@@ -393,6 +492,11 @@ pub enum RequestItem {
         limit: u64,
         fetch_events: bool,
     },
+    GetAccountStateHistory {
+        address: AccountAddress,
+        start_version: Version,
+        limit: u64,
+    },
 }
 
 impl FromProto for RequestItem {
@@ -439,6 +543,17 @@ impl FromProto for RequestItem {
                 limit,
                 fetch_events,
             }
+        } else if object.has_get_account_state_history_request() {
+            let mut req = object.take_get_account_state_history_request();
+            let address = AccountAddress::from_proto(req.take_address())?;
+            let start_version = req.get_start_version();
+            let limit = req.get_limit();
+
+            RequestItem::GetAccountStateHistory {
+                address,
+                start_version,
+                limit,
+            }
         } else {
             bail!("Unknown RequestItem type.")
         })
@@ -494,6 +609,18 @@ impl IntoProto for RequestItem {
 
                 out.set_get_transactions_request(req);
             }
+            RequestItem::GetAccountStateHistory {
+                address,
+                start_version,
+                limit,
+            } => {
+                let mut req = GetAccountStateHistoryRequest::new();
+                req.set_address(address.into_proto());
+                req.set_start_version(start_version);
+                req.set_limit(limit);
+
+                out.set_get_account_state_history_request(req);
+            }
         }
         out
     }
@@ -519,6 +646,9 @@ pub enum ResponseItem {
     GetTransactions {
         txn_list_with_proof: TransactionListWithProof,
     },
+    GetAccountStateHistory {
+        account_states_with_proof: Vec<AccountStateWithProof>,
+    },
 }
 
 impl ResponseItem {
@@ -569,6 +699,15 @@ impl ResponseItem {
             _ => bail!("Not ResponseItem::GetTransactions."),
         }
     }
+
+    pub fn into_get_account_state_history_response(self) -> Result<Vec<AccountStateWithProof>> {
+        match self {
+            ResponseItem::GetAccountStateHistory {
+                account_states_with_proof,
+            } => Ok(account_states_with_proof),
+            _ => bail!("Not ResponseItem::GetAccountStateHistory."),
+        }
+    }
 }
 
 impl FromProto for ResponseItem {
@@ -626,6 +765,17 @@ impl FromProto for ResponseItem {
             ResponseItem::GetTransactions {
                 txn_list_with_proof,
             }
+        } else if object.has_get_account_state_history_response() {
+            let account_states_with_proof = object
+                .take_get_account_state_history_response()
+                .take_account_states_with_proof()
+                .into_iter()
+                .map(AccountStateWithProof::from_proto)
+                .collect::<Result<Vec<_>>>()?;
+
+            ResponseItem::GetAccountStateHistory {
+                account_states_with_proof,
+            }
         } else {
             bail!("Unknown ResponseItem type.")
         })
@@ -684,7 +834,109 @@ impl IntoProto for ResponseItem {
 
                 out.set_get_transactions_response(res)
             }
+            ResponseItem::GetAccountStateHistory {
+                account_states_with_proof,
+            } => {
+                let mut res = GetAccountStateHistoryResponse::new();
+                res.set_account_states_with_proof(::protobuf::RepeatedField::from_vec(
+                    account_states_with_proof
+                        .into_iter()
+                        .map(AccountStateWithProof::into_proto)
+                        .collect(),
+                ));
+
+                out.set_get_account_state_history_response(res);
+            }
         }
         out
     }
 }
+
+#[derive(Clone, Debug, Eq, PartialEq, FromProto, IntoProto)]
+#[cfg_attr(any(test, feature = "testing"), derive(Arbitrary))]
+#[ProtoType(crate::proto::get_with_proof::SubscribeToTransactionsRequest)]
+pub struct SubscribeToTransactionsRequest {
+    pub start_version: Version,
+    pub batch_size: u64,
+}
+
+impl SubscribeToTransactionsRequest {
+    pub fn new(start_version: Version, batch_size: u64) -> Self {
+        SubscribeToTransactionsRequest {
+            start_version,
+            batch_size,
+        }
+    }
+}
+
+/// A chunk of transactions streamed in response to a [`SubscribeToTransactionsRequest`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubscribeToTransactionsResponse<Sig> {
+    pub txn_list_with_proof: TransactionListWithProof,
+    pub ledger_info_with_sigs: LedgerInfoWithSignatures<Sig>,
+}
+
+impl<Sig: Signature> IntoProto for SubscribeToTransactionsResponse<Sig> {
+    type ProtoType = crate::proto::get_with_proof::SubscribeToTransactionsResponse;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut out = Self::ProtoType::new();
+        out.set_txn_list_with_proof(self.txn_list_with_proof.into_proto());
+        out.set_ledger_info_with_sigs(self.ledger_info_with_sigs.into_proto());
+        out
+    }
+}
+
+impl<Sig: Signature> FromProto for SubscribeToTransactionsResponse<Sig> {
+    type ProtoType = crate::proto::get_with_proof::SubscribeToTransactionsResponse;
+
+    fn from_proto(mut object: Self::ProtoType) -> failure::Result<Self> {
+        Ok(SubscribeToTransactionsResponse {
+            txn_list_with_proof: TransactionListWithProof::from_proto(
+                object.take_txn_list_with_proof(),
+            )?,
+            ledger_info_with_sigs: LedgerInfoWithSignatures::from_proto(
+                object.take_ledger_info_with_sigs(),
+            )?,
+        })
+    }
+}
+
+impl<Sig: Signature> SubscribeToTransactionsResponse<Sig> {
+    /// Constructor.
+    pub fn new(
+        txn_list_with_proof: TransactionListWithProof,
+        ledger_info_with_sigs: LedgerInfoWithSignatures<Sig>,
+    ) -> Self {
+        SubscribeToTransactionsResponse {
+            txn_list_with_proof,
+            ledger_info_with_sigs,
+        }
+    }
+
+    /// Verifies that the chunk is not empty, starts at `expected_start_version` and is proven
+    /// against a ledger info signed by the validators. `expected_start_version` is the start
+    /// version of the request for the first chunk of a stream, and one past the last transaction
+    /// of the previous chunk for the others.
+    pub fn verify(
+        &self,
+        validator_verifier: Arc<ValidatorVerifier<Sig::VerifyingKeyMaterial>>,
+        expected_start_version: Version,
+    ) -> Result<()> {
+        ensure!(
+            !self.txn_list_with_proof.is_empty(),
+            "Got an empty chunk of transactions.",
+        );
+
+        let (ledger_info, signatures) = (
+            self.ledger_info_with_sigs.ledger_info(),
+            self.ledger_info_with_sigs.signatures(),
+        );
+        if !(ledger_info.version() == 0 && signatures.is_empty()) {
+            validator_verifier.batch_verify_aggregated_signature(ledger_info.hash(), signatures)?;
+        }
+
+        self.txn_list_with_proof
+            .verify(ledger_info, Some(expected_start_version))
+    }
+}
@@ -248,6 +248,77 @@ impl IntoProto for SparseMerkleProof {
     }
 }
 
+/// A proof that can be used to authenticate a range of consecutive leaves, from the leftmost leaf
+/// to a certain one, in a sparse Merkle tree. For example, given the following sparse Merkle
+/// tree:
+///
+/// ```text
+///                   root
+///                  /     \
+///                 /       \
+///                /         \
+///               o           o
+///              / \         / \
+///             a   o       o   h
+///                / \     / \
+///               o   d   e   X
+///              / \         / \
+///             b   c       f   g
+/// ```
+///
+/// if the proof wants show that `[a, b, c, d, e]` exists in the tree, it would need the siblings
+/// `X` and `h` on the right.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SparseMerkleRangeProof {
+    /// The vector of siblings on the right of the path from root to last leaf. The ones near the
+    /// bottom are at the beginning of the vector. In the above example, it's `[X, h]`.
+    right_siblings: Vec<HashValue>,
+}
+
+impl SparseMerkleRangeProof {
+    /// Constructs a new `SparseMerkleRangeProof`.
+    pub fn new(right_siblings: Vec<HashValue>) -> Self {
+        Self { right_siblings }
+    }
+
+    /// Returns the siblings.
+    pub fn right_siblings(&self) -> &[HashValue] {
+        &self.right_siblings
+    }
+}
+
+impl FromProto for SparseMerkleRangeProof {
+    type ProtoType = crate::proto::proof::SparseMerkleRangeProof;
+
+    fn from_proto(mut proto_proof: Self::ProtoType) -> Result<Self> {
+        let right_siblings = proto_proof
+            .take_right_siblings()
+            .into_iter()
+            .map(|hash_bytes| HashValue::from_slice(&hash_bytes))
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(
+            right_siblings.len() <= HashValue::LENGTH_IN_BITS,
+            "Sparse Merkle range proof has more than {} ({}) siblings.",
+            HashValue::LENGTH_IN_BITS,
+            right_siblings.len(),
+        );
+
+        Ok(Self::new(right_siblings))
+    }
+}
+
+impl IntoProto for SparseMerkleRangeProof {
+    type ProtoType = crate::proto::proof::SparseMerkleRangeProof;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto_proof = Self::ProtoType::new();
+        for sibling in self.right_siblings {
+            proto_proof.mut_right_siblings().push(sibling.to_vec());
+        }
+        proto_proof
+    }
+}
+
 /// A proof that can be used to show that two Merkle accumulators are consistent -- the big one can
 /// be obtained by appending certain leaves to the small one. For example, at some point in time a
 /// client knows that the root hash of the ledger at version 10 is `old_root` (it could be a
@@ -301,6 +372,103 @@ impl IntoProto for AccumulatorConsistencyProof {
     }
 }
 
+/// A proof that can be used to authenticate a range of consecutive leaves, from the leftmost one to
+/// the rightmost one, in an accumulator given trusted root hash. For example, the following
+/// accumulator has 8 leaves:
+/// ```text
+///                 root
+///                /    \
+///              /        \
+///            /            \
+///           o              o
+///         /   \          /   \
+///        /     \        /     \
+///       X       o      o       Y
+///      / \     / \    / \     / \
+///     o   o   a   b  c   Z   o   o
+/// ```
+///
+/// if the proof wants to show that `[a, b, c]` exists in the tree, it needs the siblings `X` on the
+/// left and `Y` and `Z` on the right. The siblings between the first and the last leaf are derived
+/// from the leaves themselves, so unlike two separate `AccumulatorProof`s for the first and last
+/// leaf, nothing is carried twice.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccumulatorRangeProof {
+    /// The siblings on the left of the path from root to the first leaf. The ones near the root
+    /// are at the beginning of the vector. In the above example, it's `[X]`.
+    left_siblings: Vec<HashValue>,
+    /// The siblings on the right of the path from root to the last leaf. The ones near the root
+    /// are at the beginning of the vector. In the above example, it's `[Y, Z]`.
+    right_siblings: Vec<HashValue>,
+}
+
+impl AccumulatorRangeProof {
+    /// Constructs a new `AccumulatorRangeProof` using `left_siblings` and `right_siblings`.
+    pub fn new(left_siblings: Vec<HashValue>, right_siblings: Vec<HashValue>) -> Self {
+        Self {
+            left_siblings,
+            right_siblings,
+        }
+    }
+
+    /// Constructs a proof for an empty list of leaves.
+    pub fn new_empty() -> Self {
+        Self::new(vec![], vec![])
+    }
+
+    /// Returns the left siblings.
+    pub fn left_siblings(&self) -> &[HashValue] {
+        &self.left_siblings
+    }
+
+    /// Returns the right siblings.
+    pub fn right_siblings(&self) -> &[HashValue] {
+        &self.right_siblings
+    }
+}
+
+impl FromProto for AccumulatorRangeProof {
+    type ProtoType = crate::proto::proof::AccumulatorRangeProof;
+
+    fn from_proto(mut proto_proof: Self::ProtoType) -> Result<Self> {
+        let left_siblings = proto_proof
+            .take_left_siblings()
+            .into_iter()
+            .map(|hash_bytes| HashValue::from_slice(&hash_bytes))
+            .collect::<Result<Vec<_>>>()?;
+        let right_siblings = proto_proof
+            .take_right_siblings()
+            .into_iter()
+            .map(|hash_bytes| HashValue::from_slice(&hash_bytes))
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(
+            left_siblings.len() <= MAX_ACCUMULATOR_PROOF_DEPTH
+                && right_siblings.len() <= MAX_ACCUMULATOR_PROOF_DEPTH,
+            "Accumulator range proof has more than {} siblings on either side ({} and {}).",
+            MAX_ACCUMULATOR_PROOF_DEPTH,
+            left_siblings.len(),
+            right_siblings.len(),
+        );
+
+        Ok(Self::new(left_siblings, right_siblings))
+    }
+}
+
+impl IntoProto for AccumulatorRangeProof {
+    type ProtoType = crate::proto::proof::AccumulatorRangeProof;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto_proof = Self::ProtoType::new();
+        for sibling in self.left_siblings {
+            proto_proof.mut_left_siblings().push(sibling.to_vec());
+        }
+        for sibling in self.right_siblings {
+            proto_proof.mut_right_siblings().push(sibling.to_vec());
+        }
+        proto_proof
+    }
+}
+
 /// The complete proof used to authenticate a `SignedTransaction` object.  This structure consists
 /// of an `AccumulatorProof` from `LedgerInfo` to `TransactionInfo` the verifier needs to verify
 /// the correctness of the `TransactionInfo` object, and the `TransactionInfo` object that is
@@ -15,7 +15,7 @@ use crate::{
     account_state_blob::AccountStateBlob,
     contract_event::ContractEvent,
     ledger_info::LedgerInfo,
-    proof::definition::MAX_ACCUMULATOR_PROOF_DEPTH,
+    proof::{accumulator::Accumulator, definition::MAX_ACCUMULATOR_PROOF_DEPTH},
     transaction::{TransactionInfo, TransactionListWithProof, Version},
 };
 use crypto::{
@@ -27,11 +27,11 @@ use crypto::{
     HashValue,
 };
 use failure::prelude::*;
-use std::{collections::VecDeque, marker::PhantomData};
+use std::marker::PhantomData;
 
 pub use crate::proof::definition::{
-    AccountStateProof, AccumulatorConsistencyProof, AccumulatorProof, EventProof,
-    SignedTransactionProof, SparseMerkleProof,
+    AccountStateProof, AccumulatorConsistencyProof, AccumulatorProof, AccumulatorRangeProof,
+    EventProof, SignedTransactionProof, SparseMerkleProof, SparseMerkleRangeProof,
 };
 
 /// Verifies that a `SignedTransaction` with hash value of `signed_transaction_hash`
@@ -131,16 +131,11 @@ pub(crate) fn verify_transaction_list(
     ledger_info: &LedgerInfo,
     transaction_list_with_proof: &TransactionListWithProof,
 ) -> Result<()> {
-    let (transaction_and_infos, event_lists, first_transaction_version, first_proof, last_proof) = (
+    let (transaction_and_infos, event_lists, first_transaction_version, proof) = (
         &transaction_list_with_proof.transaction_and_infos,
         transaction_list_with_proof.events.as_ref(),
         transaction_list_with_proof.first_transaction_version,
-        transaction_list_with_proof
-            .proof_of_first_transaction
-            .as_ref(),
-        transaction_list_with_proof
-            .proof_of_last_transaction
-            .as_ref(),
+        &transaction_list_with_proof.proof,
     );
 
     let num_txns = transaction_and_infos.len();
@@ -156,12 +151,8 @@ pub(crate) fn verify_transaction_list(
     // 1. Empty list;
     if num_txns == 0 {
         ensure!(
-            first_proof.is_none(),
-            "List is empty but proof of the first transaction is provided."
-        );
-        ensure!(
-            last_proof.is_none(),
-            "List is empty but proof of the last transaction is provided."
+            proof.left_siblings().is_empty() && proof.right_siblings().is_empty(),
+            "List is empty but proof is not."
         );
         ensure!(
             first_transaction_version.is_none(),
@@ -174,20 +165,15 @@ pub(crate) fn verify_transaction_list(
     let first_version = first_transaction_version.ok_or_else(|| {
         format_err!("Invalid TransactionListWithProof: First_transaction_version is None.")
     })?;
-    let siblings_of_first_txn = first_proof
-        .ok_or_else(|| {
-            format_err!("Invalid TransactionListWithProof: First transaction proof is None")
-        })?
-        .siblings();
-    let siblings_of_last_txn = match (num_txns, last_proof) {
-        (1, None) => siblings_of_first_txn,
-        (_, Some(last_proof)) => last_proof.siblings(),
-        _ => bail!(
-            "Invalid TransactionListWithProof: Last transaction proof is_none:{}, num_txns:{}",
-            last_proof.is_none(),
-            num_txns
-        ),
-    };
+    let last_version = first_version
+        .checked_add(num_txns as u64 - 1)
+        .ok_or_else(|| format_err!("Invalid TransactionListWithProof: Version overflows."))?;
+    ensure!(
+        last_version <= ledger_info.version(),
+        "Last transaction version {} is newer than LedgerInfo version {}.",
+        last_version,
+        ledger_info.version(),
+    );
 
     // Verify event root hashes match what is carried on the transaction infos.
     if let Some(event_lists) = event_lists {
@@ -203,7 +189,7 @@ pub(crate) fn verify_transaction_list(
     }
 
     // Get the hashes of all nodes at the accumulator leaf level.
-    let mut hashes = transaction_and_infos
+    let hashes = transaction_and_infos
         .iter()
         .map(|(txn, txn_info)| {
             // Verify all transaction_infos and signed_transactions are consistent.
@@ -213,76 +199,14 @@ pub(crate) fn verify_transaction_list(
             );
             Ok(txn_info.hash())
         })
-        .collect::<Result<VecDeque<_>>>()?;
-
-    let mut first_index = first_version;
+        .collect::<Result<Vec<_>>>()?;
 
-    // Verify level by level from the leaf level upwards.
-    for (first_sibling, last_sibling) in siblings_of_first_txn
-        .iter()
-        .zip(siblings_of_last_txn.iter())
-        .rev()
-    {
-        assert!(!hashes.is_empty());
-        let num_nodes = hashes.len();
-
-        if num_nodes > 1 {
-            let last_index = first_index + num_nodes as u64 - 1;
-            if last_index % 2 == 0 {
-                // if `last_index` is even, it is the left child of its parent so the sibling is not
-                // in `hashes`, we have to append it to `hashes` generate parent nodes' hashes.
-                hashes.push_back(*last_sibling);
-            } else {
-                // Otherwise, the sibling should be the second to last hash.
-                // Note: if we check `first_index` first we cannot use num_nodes to index because
-                // hashes length may change.
-                ensure!(hashes[num_nodes - 2] == *last_sibling,
-                        "Invalid TransactionListWithProof: Last transaction proof doesn't match provided siblings");
-            }
-            // We haven't reached the first common ancester of all transactions in the list.
-            if first_index % 2 == 0 {
-                // if `first_index` is even, it is the left child of its parent so the sibling must
-                // be the next node.
-                ensure!(hashes[1] == *first_sibling,
-                            "Invalid TransactionListWithProof: First transaction proof doesn't match provided siblings");
-            } else {
-                // Otherwise, the sibling is not in `hashes`, we have to prepend it to `hashes` to
-                // generate parent nodes' hashes.
-                hashes.push_front(*first_sibling);
-            }
-        } else {
-            // We have reached the first common ancestor of all the transactions in the list.
-            ensure!(
-                first_sibling == last_sibling,
-                "Invalid TransactionListWithProof: Either proof is invalid."
-            );
-            if first_index % 2 == 0 {
-                hashes.push_back(*first_sibling);
-            } else {
-                hashes.push_front(*first_sibling);
-            }
-        }
-        let mut hash_iter = hashes.into_iter();
-        let mut parent_hashes = VecDeque::new();
-        while let Some(left) = hash_iter.next() {
-            let right = hash_iter.next().expect("Can't be None");
-            parent_hashes.push_back(
-                MerkleTreeInternalNode::<TransactionAccumulatorHasher>::new(left, right).hash(),
-            )
-        }
-        hashes = parent_hashes;
-        // The parent node index at its level should be floor(index / 2)
-        first_index /= 2;
-    }
-    assert!(hashes.len() == 1);
-    let expected_root_hash = ledger_info.transaction_accumulator_hash();
-    ensure!(
-        hashes[0] == expected_root_hash,
-        "Root hashes do not match. Actual root hash: {:x}. Expected root hash: {:x}.",
-        hashes[0],
-        expected_root_hash
-    );
-    Ok(())
+    verify_transaction_accumulator_range(
+        ledger_info.transaction_accumulator_hash(),
+        first_version,
+        &hashes,
+        proof,
+    )
 }
 
 /// Verifies that a given `transaction_info` exists in the ledger using provided proof.
@@ -385,6 +309,110 @@ pub(crate) fn get_accumulator_root_hash<H: Clone + CryptoHasher>(
     current_level[0]
 }
 
+/// Verifies that the accumulator of `new_num_leaves` leaves whose root hash is
+/// `expected_root_hash` extends `known_accumulator`, i.e. it can be obtained by appending the
+/// subtrees in `consistency_proof` to `known_accumulator`. Returns the extended accumulator.
+pub fn verify_accumulator_consistency<H: CryptoHasher>(
+    known_accumulator: &Accumulator<H>,
+    consistency_proof: &AccumulatorConsistencyProof,
+    new_num_leaves: u64,
+    expected_root_hash: HashValue,
+) -> Result<Accumulator<H>> {
+    ensure!(
+        known_accumulator.num_leaves() <= new_num_leaves,
+        "The known accumulator has {} leaves, more than the new one ({}).",
+        known_accumulator.num_leaves(),
+        new_num_leaves,
+    );
+
+    let new_accumulator = known_accumulator.append_subtrees(
+        consistency_proof.subtrees(),
+        new_num_leaves - known_accumulator.num_leaves(),
+    )?;
+    ensure!(
+        new_accumulator.root_hash() == expected_root_hash,
+        "Root hashes do not match. Actual root hash: {:x}. Expected root hash: {:x}.",
+        new_accumulator.root_hash(),
+        expected_root_hash
+    );
+
+    Ok(new_accumulator)
+}
+
+/// Verifies that the consecutive elements whose hashes are `element_hashes`, the first one of
+/// which has index `first_element_index`, exist in the accumulator whose root hash is
+/// `expected_root_hash` using the provided proof.
+fn verify_accumulator_range<H: Clone + CryptoHasher>(
+    expected_root_hash: HashValue,
+    first_element_index: u64,
+    element_hashes: &[HashValue],
+    accumulator_range_proof: &AccumulatorRangeProof,
+) -> Result<()> {
+    let (left_siblings, right_siblings) = (
+        accumulator_range_proof.left_siblings(),
+        accumulator_range_proof.right_siblings(),
+    );
+    ensure!(!element_hashes.is_empty(), "No elements to verify.");
+    ensure!(
+        left_siblings.len() <= MAX_ACCUMULATOR_PROOF_DEPTH
+            && right_siblings.len() <= MAX_ACCUMULATOR_PROOF_DEPTH,
+        "Accumulator range proof has more than {} siblings on either side ({} and {}).",
+        MAX_ACCUMULATOR_PROOF_DEPTH,
+        left_siblings.len(),
+        right_siblings.len(),
+    );
+
+    let mut left_sibling_iter = left_siblings.iter().rev();
+    let mut right_sibling_iter = right_siblings.iter().rev();
+    // `first_index` denotes the index of the leftmost node at the current level.
+    let mut first_index = first_element_index;
+    let mut hashes = element_hashes.to_vec();
+
+    // Compute the parent level from the current one until only the root is left and all the
+    // siblings are used.
+    while hashes.len() > 1 || left_sibling_iter.len() > 0 || right_sibling_iter.len() > 0 {
+        let mut children = hashes.as_slice();
+        let mut parent_hashes = Vec::with_capacity(children.len() / 2 + 1);
+
+        // If the leftmost node is a right child, its sibling on the left comes from the proof.
+        if first_index % 2 == 1 {
+            let left_sibling = left_sibling_iter
+                .next()
+                .ok_or_else(|| format_err!("Accumulator range proof has too few left siblings."))?;
+            parent_hashes.push(MerkleTreeInternalNode::<H>::new(*left_sibling, children[0]).hash());
+            children = &children[1..];
+        }
+
+        let mut pairs = children.chunks_exact(2);
+        parent_hashes.extend(
+            pairs
+                .by_ref()
+                .map(|pair| MerkleTreeInternalNode::<H>::new(pair[0], pair[1]).hash()),
+        );
+
+        // If the rightmost node is a left child, its sibling on the right comes from the proof.
+        if let [last] = pairs.remainder() {
+            let right_sibling = right_sibling_iter.next().ok_or_else(|| {
+                format_err!("Accumulator range proof has too few right siblings.")
+            })?;
+            parent_hashes.push(MerkleTreeInternalNode::<H>::new(*last, *right_sibling).hash());
+        }
+
+        hashes = parent_hashes;
+        // The index of the parent at its level.
+        first_index /= 2;
+    }
+
+    ensure!(
+        hashes[0] == expected_root_hash,
+        "Root hashes do not match. Actual root hash: {:x}. Expected root hash: {:x}.",
+        hashes[0],
+        expected_root_hash
+    );
+
+    Ok(())
+}
+
 type AccumulatorElementVerifier = fn(
     expected_root_hash: HashValue,
     element_hash: HashValue,
@@ -404,6 +432,21 @@ pub const verify_transaction_accumulator_element: AccumulatorElementVerifier =
 pub const verify_test_accumulator_element: AccumulatorElementVerifier =
     verify_accumulator_element::<TestOnlyHasher>;
 
+type AccumulatorRangeVerifier = fn(
+    expected_root_hash: HashValue,
+    first_element_index: u64,
+    element_hashes: &[HashValue],
+    accumulator_range_proof: &AccumulatorRangeProof,
+) -> Result<()>;
+
+#[allow(non_upper_case_globals)]
+pub const verify_transaction_accumulator_range: AccumulatorRangeVerifier =
+    verify_accumulator_range::<TransactionAccumulatorHasher>;
+
+#[allow(non_upper_case_globals)]
+pub const verify_test_accumulator_range: AccumulatorRangeVerifier =
+    verify_accumulator_range::<TestOnlyHasher>;
+
 /// If `element_blob` is present, verifies an element whose key is `element_key` and value
 /// is `element_blob` exists in the Sparse Merkle Tree using the provided proof.
 /// Otherwise verifies the proof is a valid non-inclusion proof that shows this key doesn't exist
@@ -496,6 +539,62 @@ pub fn verify_sparse_merkle_element(
     Ok(())
 }
 
+/// Verifies that all the leaves from the leftmost one to `rightmost_known_leaf`, which is a pair of
+/// key and value hash, exist in the Sparse Merkle Tree with `expected_root_hash`. The verifier
+/// computes `left_siblings` from all the known leaves, i.e. the siblings on the left of the path
+/// from root to `rightmost_known_leaf`, with the ones near the bottom at the beginning, and
+/// `range_proof` provides the ones on the right.
+pub fn verify_sparse_merkle_range(
+    expected_root_hash: HashValue,
+    rightmost_known_leaf: (HashValue, HashValue),
+    left_siblings: &[HashValue],
+    range_proof: &SparseMerkleRangeProof,
+) -> Result<()> {
+    let (key, value_hash) = rightmost_known_leaf;
+    let right_siblings = range_proof.right_siblings();
+    let num_siblings = left_siblings.len() + right_siblings.len();
+    ensure!(
+        num_siblings <= HashValue::LENGTH_IN_BITS,
+        "Sparse Merkle range proof has more than {} ({}) siblings.",
+        HashValue::LENGTH_IN_BITS,
+        num_siblings,
+    );
+
+    let mut left_sibling_iter = left_siblings.iter();
+    let mut right_sibling_iter = right_siblings.iter();
+    let mut current_hash = SparseMerkleLeafNode::new(key, value_hash).hash();
+    for bit in key
+        .iter_bits()
+        .rev()
+        .skip(HashValue::LENGTH_IN_BITS - num_siblings)
+    {
+        current_hash = if bit {
+            let left_hash = left_sibling_iter
+                .next()
+                .ok_or_else(|| format_err!("Missing left sibling."))?;
+            SparseMerkleInternalNode::new(*left_hash, current_hash).hash()
+        } else {
+            let right_hash = right_sibling_iter
+                .next()
+                .ok_or_else(|| format_err!("Missing right sibling."))?;
+            SparseMerkleInternalNode::new(current_hash, *right_hash).hash()
+        };
+    }
+    ensure!(
+        left_sibling_iter.next().is_none() && right_sibling_iter.next().is_none(),
+        "Number of siblings doesn't match the path to key {:x}.",
+        key,
+    );
+    ensure!(
+        current_hash == expected_root_hash,
+        "Root hashes do not match. Actual root hash: {:x}. Expected root hash: {:x}.",
+        current_hash,
+        expected_root_hash,
+    );
+
+    Ok(())
+}
+
 pub struct MerkleTreeInternalNode<H> {
     left_child: HashValue,
     right_child: HashValue,
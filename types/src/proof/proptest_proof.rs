@@ -4,7 +4,10 @@
 //! All proofs generated in this module are not valid proofs. They are only for the purpose of
 //! testing conversion between Rust and Protobuf.
 
-use crate::proof::{AccumulatorConsistencyProof, AccumulatorProof, SparseMerkleProof};
+use crate::proof::{
+    AccumulatorConsistencyProof, AccumulatorProof, AccumulatorRangeProof, SparseMerkleProof,
+    SparseMerkleRangeProof,
+};
 use crypto::{
     hash::{ACCUMULATOR_PLACEHOLDER_HASH, SPARSE_MERKLE_PLACEHOLDER_HASH},
     HashValue,
@@ -57,6 +60,23 @@ prop_compose! {
     }
 }
 
+prop_compose! {
+    fn arb_accumulator_range_proof()(
+        left_siblings in vec(any::<HashValue>(), 0..=63),
+        right_siblings in vec(any::<HashValue>(), 0..=63),
+    ) -> AccumulatorRangeProof {
+        AccumulatorRangeProof::new(left_siblings, right_siblings)
+    }
+}
+
+prop_compose! {
+    fn arb_sparse_merkle_range_proof()(
+        right_siblings in vec(any::<HashValue>(), 0..=256),
+    ) -> SparseMerkleRangeProof {
+        SparseMerkleRangeProof::new(right_siblings)
+    }
+}
+
 macro_rules! impl_arbitrary_for_proof {
     ($proof_type: ident, $arb_func: ident) => {
         impl Arbitrary for $proof_type {
@@ -72,6 +92,8 @@ macro_rules! impl_arbitrary_for_proof {
 
 impl_arbitrary_for_proof!(AccumulatorProof, arb_accumulator_proof);
 impl_arbitrary_for_proof!(SparseMerkleProof, arb_sparse_merkle_proof);
+impl_arbitrary_for_proof!(AccumulatorRangeProof, arb_accumulator_range_proof);
+impl_arbitrary_for_proof!(SparseMerkleRangeProof, arb_sparse_merkle_range_proof);
 impl_arbitrary_for_proof!(
     AccumulatorConsistencyProof,
     arb_accumulator_consistency_proof
@@ -8,10 +8,10 @@ use crate::{
     proof::{
         definition::MAX_ACCUMULATOR_PROOF_DEPTH, verify_account_state, verify_event,
         verify_signed_transaction, verify_sparse_merkle_element, verify_test_accumulator_element,
-        AccountStateProof, AccumulatorProof, EventAccumulatorInternalNode, EventProof,
-        MerkleTreeInternalNode, SignedTransactionProof, SparseMerkleInternalNode,
-        SparseMerkleLeafNode, SparseMerkleProof, TestAccumulatorInternalNode,
-        TransactionAccumulatorInternalNode,
+        verify_test_accumulator_range, AccountStateProof, AccumulatorProof, AccumulatorRangeProof,
+        EventAccumulatorInternalNode, EventProof, MerkleTreeInternalNode, SignedTransactionProof,
+        SparseMerkleInternalNode, SparseMerkleLeafNode, SparseMerkleProof,
+        TestAccumulatorInternalNode, TransactionAccumulatorInternalNode,
     },
     transaction::{
         RawTransaction, Script, SignedTransaction, TransactionInfo, TransactionListWithProof,
@@ -99,6 +99,61 @@ fn test_verify_three_element_accumulator() {
     .is_ok());
 }
 
+#[test]
+fn test_verify_three_element_accumulator_range() {
+    let element0_hash = b"hello".test_only_hash();
+    let element1_hash = b"world".test_only_hash();
+    let element2_hash = b"!".test_only_hash();
+    let internal0_hash = TestAccumulatorInternalNode::new(element0_hash, element1_hash).hash();
+    let internal1_hash =
+        TestAccumulatorInternalNode::new(element2_hash, *ACCUMULATOR_PLACEHOLDER_HASH).hash();
+    let root_hash = TestAccumulatorInternalNode::new(internal0_hash, internal1_hash).hash();
+
+    assert!(verify_test_accumulator_range(
+        root_hash,
+        0,
+        &[element0_hash, element1_hash, element2_hash],
+        &AccumulatorRangeProof::new(vec![], vec![*ACCUMULATOR_PLACEHOLDER_HASH]),
+    )
+    .is_ok());
+    assert!(verify_test_accumulator_range(
+        root_hash,
+        1,
+        &[element1_hash, element2_hash],
+        &AccumulatorRangeProof::new(vec![element0_hash], vec![*ACCUMULATOR_PLACEHOLDER_HASH]),
+    )
+    .is_ok());
+    assert!(verify_test_accumulator_range(
+        root_hash,
+        1,
+        &[element1_hash],
+        &AccumulatorRangeProof::new(vec![element0_hash], vec![internal1_hash]),
+    )
+    .is_ok());
+
+    // Wrong index.
+    assert!(verify_test_accumulator_range(
+        root_hash,
+        0,
+        &[element1_hash],
+        &AccumulatorRangeProof::new(vec![element0_hash], vec![internal1_hash]),
+    )
+    .is_err());
+    // Missing sibling.
+    assert!(verify_test_accumulator_range(
+        root_hash,
+        1,
+        &[element1_hash, element2_hash],
+        &AccumulatorRangeProof::new(vec![], vec![*ACCUMULATOR_PLACEHOLDER_HASH]),
+    )
+    .is_err());
+    // Empty range.
+    assert!(
+        verify_test_accumulator_range(root_hash, 0, &[], &AccumulatorRangeProof::new_empty(),)
+            .is_err()
+    );
+}
+
 #[test]
 fn test_accumulator_proof_max_siblings_leftmost() {
     let element_hash = b"hello".test_only_hash();
@@ -521,7 +576,7 @@ proptest! {
 
         let txn_list_with_proof =
            if txn_and_infos.is_empty() {
-               TransactionListWithProof::new(vec![], None, None, None, None)
+               TransactionListWithProof::new_empty()
            } else {
                let mut hashes = txn_and_infos
                    .iter()
@@ -550,43 +605,32 @@ proptest! {
                assert_eq!(tree.last().unwrap().len(), 1);
                root_hash = tree.pop().unwrap()[0];
 
-               // Get proofs.
+               // Get the siblings on the left of the first transaction and those on the right of
+               // the last one.
                let mut first_index = first_version;
                let mut last_index = last_version;
-               let mut first_siblings = vec![];
-               let mut last_siblings = vec![];
+               let mut left_siblings = vec![];
+               let mut right_siblings = vec![];
                for nodes in tree {
-                   first_siblings.push(
-                       if first_index % 2 == 0 {
-                           nodes[first_index + 1]
-                       } else {
-                           nodes[first_index - 1]
-                       }
-                   );
-                   last_siblings.push(
-                       if last_index % 2 == 0 {
-                           nodes[last_index + 1]
-                       } else {
-                           nodes[last_index - 1]
-                       }
-                   );
+                   if first_index % 2 == 1 {
+                       left_siblings.push(nodes[first_index - 1]);
+                   }
+                   if last_index % 2 == 0 {
+                       right_siblings.push(nodes[last_index + 1]);
+                   }
                    first_index /= 2;
                    last_index /= 2;
                }
-               let first_proof =
-                   Some(AccumulatorProof::new(first_siblings.into_iter().rev().collect::<Vec<_>>()));
-               let last_proof = if first_version == last_version {
-                   None
-               } else {
-                   Some(AccumulatorProof::new(last_siblings.into_iter().rev().collect::<Vec<_>>()))
-               };
+               let proof = AccumulatorRangeProof::new(
+                   left_siblings.into_iter().rev().collect(),
+                   right_siblings.into_iter().rev().collect(),
+               );
 
                TransactionListWithProof::new(
                    txn_and_infos[first_version..=last_version].to_vec(),
                    None,
                    Some(first_version as u64),
-                   first_proof,
-                   last_proof,
+                   proof,
                )
            };
 
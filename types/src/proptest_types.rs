@@ -9,13 +9,13 @@ use crate::{
     byte_array::ByteArray,
     contract_event::ContractEvent,
     event::{EventHandle, EventKey},
-    get_with_proof::{ResponseItem, UpdateToLatestLedgerResponse},
+    get_with_proof::{ResponseItem, SubscribeToTransactionsResponse, UpdateToLatestLedgerResponse},
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    proof::AccumulatorProof,
+    proof::{AccumulatorConsistencyProof, AccumulatorRangeProof},
     transaction::{
         Module, Program, RawTransaction, Script, SignatureCheckedTransaction, SignedTransaction,
-        TransactionArgument, TransactionInfo, TransactionListWithProof, TransactionPayload,
-        TransactionStatus, TransactionToCommit, Version,
+        TransactionArgument, TransactionInfo, TransactionListWithProof, TransactionOutput,
+        TransactionPayload, TransactionStatus, TransactionToCommit, Version,
     },
     validator_change::ValidatorChangeEventWithProof,
     vm_error::{StatusCode, VMStatus},
@@ -440,6 +440,26 @@ impl Arbitrary for TransactionStatus {
     type Strategy = BoxedStrategy<Self>;
 }
 
+prop_compose! {
+    fn arb_transaction_output()(
+        write_set in any::<WriteSet>(),
+        events in vec(any::<ContractEvent>(), 0..10),
+        gas_used in any::<u64>(),
+        status in any::<TransactionStatus>(),
+    ) -> TransactionOutput {
+        TransactionOutput::new(write_set, events, gas_used, status)
+    }
+}
+
+impl Arbitrary for TransactionOutput {
+    type Parameters = ();
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        arb_transaction_output().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
 impl Arbitrary for TransactionPayload {
     type Parameters = ();
     fn arbitrary_with(_args: ()) -> Self::Strategy {
@@ -556,9 +576,14 @@ prop_compose! {
         response_items in vec(any::<ResponseItem>(), 0..10),
         ledger_info_with_sigs in any::<LedgerInfoWithSignatures<Ed25519Signature>>(),
         validator_change_events in vec(any::<ValidatorChangeEventWithProof<Ed25519Signature>>(), 0..10),
+        ledger_consistency_proof in any::<Option<AccumulatorConsistencyProof>>(),
     ) -> UpdateToLatestLedgerResponse<Ed25519Signature> {
         UpdateToLatestLedgerResponse::new(
-            response_items, ledger_info_with_sigs, validator_change_events)
+            response_items,
+            ledger_info_with_sigs,
+            validator_change_events,
+            ledger_consistency_proof,
+        )
     }
 }
 
@@ -571,6 +596,24 @@ impl Arbitrary for UpdateToLatestLedgerResponse<Ed25519Signature> {
     type Strategy = BoxedStrategy<Self>;
 }
 
+prop_compose! {
+    fn arb_subscribe_to_transactions_response()(
+        txn_list_with_proof in any::<TransactionListWithProof>(),
+        ledger_info_with_sigs in any::<LedgerInfoWithSignatures<Ed25519Signature>>(),
+    ) -> SubscribeToTransactionsResponse<Ed25519Signature> {
+        SubscribeToTransactionsResponse::new(txn_list_with_proof, ledger_info_with_sigs)
+    }
+}
+
+impl Arbitrary for SubscribeToTransactionsResponse<Ed25519Signature> {
+    type Parameters = ();
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        arb_subscribe_to_transactions_response().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
 #[derive(Arbitrary, Debug)]
 pub struct ContractEventGen {
     payload: Vec<u8>,
@@ -824,34 +867,20 @@ fn arb_transaction_list_with_proof() -> impl Strategy<Value = TransactionListWit
             Just(transaction_and_infos),
             option::of(Just(events)),
             any::<Version>(),
-            any::<AccumulatorProof>(),
-            any::<AccumulatorProof>(),
+            any::<AccumulatorRangeProof>(),
         )
     })
     .prop_map(
-        |(
-            transaction_and_infos,
-            events,
-            first_txn_version,
-            proof_of_first_txn,
-            proof_of_last_txn,
-        )| {
-            match transaction_and_infos.len() {
-                0 => TransactionListWithProof::new_empty(),
-                1 => TransactionListWithProof::new(
+        |(transaction_and_infos, events, first_txn_version, proof)| {
+            if transaction_and_infos.is_empty() {
+                TransactionListWithProof::new_empty()
+            } else {
+                TransactionListWithProof::new(
                     transaction_and_infos,
                     events,
                     Some(first_txn_version),
-                    Some(proof_of_first_txn),
-                    None,
-                ),
-                _ => TransactionListWithProof::new(
-                    transaction_and_infos,
-                    events,
-                    Some(first_txn_version),
-                    Some(proof_of_first_txn),
-                    Some(proof_of_last_txn),
-                ),
+                    proof,
+                )
             }
         },
     )
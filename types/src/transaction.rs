@@ -8,7 +8,7 @@ use crate::{
     ledger_info::LedgerInfo,
     proof::{
         get_accumulator_root_hash, verify_signed_transaction, verify_transaction_list,
-        AccumulatorProof, SignedTransactionProof,
+        AccumulatorRangeProof, SignedTransactionProof,
     },
     proto::events::{EventsForVersions, EventsList},
     vm_error::{StatusCode, StatusType, VMStatus},
@@ -766,6 +766,58 @@ impl TransactionOutput {
     }
 }
 
+impl FromProto for TransactionOutput {
+    type ProtoType = crate::proto::transaction::TransactionOutput;
+
+    fn from_proto(mut object: Self::ProtoType) -> Result<Self> {
+        let write_set = SimpleDeserializer::deserialize(object.get_write_set())?;
+        let events = object
+            .take_events()
+            .into_iter()
+            .map(ContractEvent::from_proto)
+            .collect::<Result<Vec<_>>>()?;
+        let vm_status = VMStatus::from_proto(object.take_vm_status())?;
+        let status = if object.get_discarded() {
+            TransactionStatus::Discard(vm_status)
+        } else {
+            TransactionStatus::Keep(vm_status)
+        };
+
+        Ok(TransactionOutput {
+            write_set,
+            events,
+            gas_used: object.get_gas_used(),
+            status,
+        })
+    }
+}
+
+impl IntoProto for TransactionOutput {
+    type ProtoType = crate::proto::transaction::TransactionOutput;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto = Self::ProtoType::new();
+        proto.set_write_set(
+            SimpleSerializer::<Vec<u8>>::serialize(&self.write_set)
+                .expect("Unable to serialize WriteSet"),
+        );
+        proto.set_events(protobuf::RepeatedField::from_vec(
+            self.events
+                .into_iter()
+                .map(ContractEvent::into_proto)
+                .collect::<Vec<_>>(),
+        ));
+        proto.set_gas_used(self.gas_used);
+        let (vm_status, discarded) = match self.status {
+            TransactionStatus::Discard(vm_status) => (vm_status, true),
+            TransactionStatus::Keep(vm_status) => (vm_status, false),
+        };
+        proto.set_vm_status(vm_status.into_proto());
+        proto.set_discarded(discarded);
+        proto
+    }
+}
+
 impl FromProto for TransactionInfo {
     type ProtoType = crate::proto::transaction_info::TransactionInfo;
     fn from_proto(mut proto_txn_info: Self::ProtoType) -> Result<Self> {
@@ -993,18 +1045,14 @@ impl IntoProto for TransactionToCommit {
     }
 }
 
-/// The list may have three states:
-/// 1. The list is empty. Both proofs must be `None`.
-/// 2. The list has only 1 transaction/transaction_info. Then `proof_of_first_transaction`
-/// must exist and `proof_of_last_transaction` must be `None`.
-/// 3. The list has 2+ transactions/transaction_infos. The both proofs must exist.
+/// A list of consecutive transactions with a range proof of their infos. If the list is empty,
+/// `first_transaction_version` must be `None` and the proof must be empty.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TransactionListWithProof {
     pub transaction_and_infos: Vec<(SignedTransaction, TransactionInfo)>,
     pub events: Option<Vec<Vec<ContractEvent>>>,
     pub first_transaction_version: Option<Version>,
-    pub proof_of_first_transaction: Option<AccumulatorProof>,
-    pub proof_of_last_transaction: Option<AccumulatorProof>,
+    pub proof: AccumulatorRangeProof,
 }
 
 impl TransactionListWithProof {
@@ -1013,21 +1061,19 @@ impl TransactionListWithProof {
         transaction_and_infos: Vec<(SignedTransaction, TransactionInfo)>,
         events: Option<Vec<Vec<ContractEvent>>>,
         first_transaction_version: Option<Version>,
-        proof_of_first_transaction: Option<AccumulatorProof>,
-        proof_of_last_transaction: Option<AccumulatorProof>,
+        proof: AccumulatorRangeProof,
     ) -> Self {
         Self {
             transaction_and_infos,
             events,
             first_transaction_version,
-            proof_of_first_transaction,
-            proof_of_last_transaction,
+            proof,
         }
     }
 
     /// Creates an empty transaction list.
     pub fn new_empty() -> Self {
-        Self::new(Vec::new(), None, None, None, None)
+        Self::new(Vec::new(), None, None, AccumulatorRangeProof::new_empty())
     }
 
     /// Verifies the transaction list with the proofs, both carried on `self`.
@@ -1080,25 +1126,12 @@ impl FromProto for TransactionListWithProof {
             num_txns,
             num_infos
         );
-        let (has_first, has_last, has_first_version) = (
-            object.has_proof_of_first_transaction(),
-            object.has_proof_of_last_transaction(),
-            object.has_first_transaction_version(),
+        ensure!(
+            object.has_first_transaction_version() == (num_txns > 0),
+            "First transaction version must exist if and only if the list is not empty. \
+             Number of transactions: {}.",
+            num_txns,
         );
-        match num_txns {
-            0 => ensure!(
-                !has_first && !has_last && !has_first_version,
-                "Some proof exists with 0 transactions"
-            ),
-            1 => ensure!(
-                has_first && !has_last && has_first_version,
-                "Proof of last transaction exists with 1 transaction"
-            ),
-            _ => ensure!(
-                has_first && has_last && has_first_version,
-                "Both proofs of first and last transactions must exist with 2+ transactions"
-            ),
-        }
 
         let events = object
             .events_for_versions
@@ -1134,16 +1167,7 @@ impl FromProto for TransactionListWithProof {
         Ok(TransactionListWithProof {
             transaction_and_infos,
             events,
-            proof_of_first_transaction: object
-                .proof_of_first_transaction
-                .take()
-                .map(AccumulatorProof::from_proto)
-                .transpose()?,
-            proof_of_last_transaction: object
-                .proof_of_last_transaction
-                .take()
-                .map(AccumulatorProof::from_proto)
-                .transpose()?,
+            proof: AccumulatorRangeProof::from_proto(object.take_proof())?,
             first_transaction_version: object
                 .first_transaction_version
                 .take()
@@ -1188,12 +1212,7 @@ impl IntoProto for TransactionListWithProof {
             ver.set_value(first_transaction_version);
             out.set_first_transaction_version(ver);
         }
-        if let Some(proof_of_first_transaction) = self.proof_of_first_transaction {
-            out.set_proof_of_first_transaction(proof_of_first_transaction.into_proto());
-        }
-        if let Some(proof_of_last_transaction) = self.proof_of_last_transaction {
-            out.set_proof_of_last_transaction(proof_of_last_transaction.into_proto());
-        }
+        out.set_proof(self.proof.into_proto());
         out
     }
 }
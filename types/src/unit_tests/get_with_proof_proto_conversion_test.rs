@@ -3,7 +3,8 @@
 
 use crate::{
     get_with_proof::{
-        RequestItem, ResponseItem, UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
+        RequestItem, ResponseItem, SubscribeToTransactionsRequest, SubscribeToTransactionsResponse,
+        UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
     },
     proto,
 };
@@ -23,6 +24,13 @@ proptest! {
     fn test_request_item_conversion(item in any::<RequestItem>()) {
         assert_protobuf_encode_decode(&item);
     }
+
+    #[test]
+    fn test_subscribe_to_transactions_request(
+        request in any::<SubscribeToTransactionsRequest>()
+    ) {
+        assert_protobuf_encode_decode(&request);
+    }
 }
 
 proptest! {
@@ -39,6 +47,13 @@ proptest! {
     ) {
         assert_protobuf_encode_decode(&response);
     }
+
+    #[test]
+    fn test_subscribe_to_transactions_response(
+        response in any::<SubscribeToTransactionsResponse<Ed25519Signature>>()
+    ) {
+        assert_protobuf_encode_decode(&response);
+    }
 }
 
 #[test]
@@ -25,6 +25,11 @@ proptest! {
     fn test_transaction_to_commit(txn_to_commit in any::<TransactionToCommit>()) {
         assert_protobuf_encode_decode(&txn_to_commit);
     }
+
+    #[test]
+    fn test_transaction_output(txn_output in any::<TransactionOutput>()) {
+        assert_protobuf_encode_decode(&txn_output);
+    }
 }
 
 proptest! {
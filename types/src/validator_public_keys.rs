@@ -8,9 +8,17 @@ use crate::{
 use canonical_serialization::{
     CanonicalDeserialize, CanonicalDeserializer, CanonicalSerialize, CanonicalSerializer,
 };
-use crypto::{ed25519::*, traits::ValidKey, x25519::X25519StaticPublicKey};
+use crypto::{
+    bls12381::{BLS12381ProofOfPossession, BLS12381PublicKey},
+    ed25519::*,
+    traits::ValidKey,
+    vrf::ecvrf::VRFPublicKey,
+    x25519::X25519StaticPublicKey,
+};
 use failure::Result;
 #[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+#[cfg(any(test, feature = "testing"))]
 use proptest_derive::Arbitrary;
 use proto_conv::{FromProto, IntoProto};
 use serde::{Deserialize, Serialize};
@@ -33,6 +41,15 @@ pub struct ValidatorPublicKeys {
     // This key establishes the corresponding PrivateKey holder's eligibility to join the p2p
     // network
     network_identity_public_key: X25519StaticPublicKey,
+    // This key verifies the VRF proofs of the proposals of this validator, if it registered one
+    vrf_public_key: Option<VRFPublicKey>,
+    // This key verifies the BLS12-381 signatures of this validator, which the certificates
+    // aggregate, along with the proof that the validator holds the matching private key
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "arb_bls_public_key()")
+    )]
+    bls_public_key: Option<(BLS12381PublicKey, BLS12381ProofOfPossession)>,
 }
 
 impl fmt::Display for ValidatorPublicKeys {
@@ -47,15 +64,29 @@ impl ValidatorPublicKeys {
         consensus_public_key: Ed25519PublicKey,
         network_signing_public_key: Ed25519PublicKey,
         network_identity_public_key: X25519StaticPublicKey,
+        vrf_public_key: Option<VRFPublicKey>,
     ) -> Self {
         ValidatorPublicKeys {
             account_address,
             consensus_public_key,
             network_signing_public_key,
             network_identity_public_key,
+            vrf_public_key,
+            bls_public_key: None,
         }
     }
 
+    /// Registers the BLS12-381 key of this validator along with the proof of possession of the
+    /// matching private key.
+    pub fn with_bls_public_key(
+        mut self,
+        bls_public_key: BLS12381PublicKey,
+        proof_of_possession: BLS12381ProofOfPossession,
+    ) -> Self {
+        self.bls_public_key = Some((bls_public_key, proof_of_possession));
+        self
+    }
+
     /// Returns the id of this validator (hash of the current public key of the
     /// validator associated account address)
     pub fn account_address(&self) -> &AccountAddress {
@@ -76,6 +107,17 @@ impl ValidatorPublicKeys {
     pub fn network_identity_public_key(&self) -> &X25519StaticPublicKey {
         &self.network_identity_public_key
     }
+
+    /// Returns the key for verifying the VRF proofs of this validator, if it registered one
+    pub fn vrf_public_key(&self) -> Option<&VRFPublicKey> {
+        self.vrf_public_key.as_ref()
+    }
+
+    /// Returns the key for verifying the BLS12-381 signatures of this validator along with the
+    /// proof of possession of its private key, if it registered one
+    pub fn bls_public_key(&self) -> Option<&(BLS12381PublicKey, BLS12381ProofOfPossession)> {
+        self.bls_public_key.as_ref()
+    }
 }
 
 impl FromProto for ValidatorPublicKeys {
@@ -88,12 +130,21 @@ impl FromProto for ValidatorPublicKeys {
             Ed25519PublicKey::try_from(object.get_network_signing_public_key())?;
         let network_identity_public_key =
             X25519StaticPublicKey::try_from(object.get_network_identity_public_key())?;
-        Ok(Self::new(
-            account_address,
-            consensus_public_key,
-            network_signing_public_key,
-            network_identity_public_key,
-        ))
+        let vrf_public_key = decode_vrf_public_key(object.get_vrf_public_key())?;
+        let bls_public_key = decode_bls_public_key(
+            object.get_bls_public_key(),
+            object.get_bls_proof_of_possession(),
+        )?;
+        Ok(Self {
+            bls_public_key,
+            ..Self::new(
+                account_address,
+                consensus_public_key,
+                network_signing_public_key,
+                network_identity_public_key,
+                vrf_public_key,
+            )
+        })
     }
 }
 
@@ -112,6 +163,13 @@ impl IntoProto for ValidatorPublicKeys {
         proto.set_network_identity_public_key(
             X25519StaticPublicKey::to_bytes(&self.network_identity_public_key).to_vec(),
         );
+        if let Some(vrf_public_key) = &self.vrf_public_key {
+            proto.set_vrf_public_key(ValidKey::to_bytes(vrf_public_key));
+        }
+        if let Some((bls_public_key, proof_of_possession)) = &self.bls_public_key {
+            proto.set_bls_public_key(ValidKey::to_bytes(bls_public_key));
+            proto.set_bls_proof_of_possession(proof_of_possession.to_bytes().to_vec());
+        }
         proto
     }
 }
@@ -122,7 +180,25 @@ impl CanonicalSerialize for ValidatorPublicKeys {
             .encode_struct(&self.account_address)?
             .encode_bytes(&self.consensus_public_key.to_bytes())?
             .encode_bytes(&X25519StaticPublicKey::to_bytes(&self.network_identity_public_key)[..])?
-            .encode_bytes(&self.network_signing_public_key.to_bytes())?;
+            .encode_bytes(&self.network_signing_public_key.to_bytes())?
+            .encode_bytes(
+                &self
+                    .vrf_public_key
+                    .as_ref()
+                    .map_or_else(Vec::new, ValidKey::to_bytes),
+            )?
+            .encode_bytes(
+                &self
+                    .bls_public_key
+                    .as_ref()
+                    .map_or_else(Vec::new, |(public_key, _)| ValidKey::to_bytes(public_key)),
+            )?
+            .encode_bytes(
+                &self
+                    .bls_public_key
+                    .as_ref()
+                    .map_or_else(Vec::new, |(_, proof)| proof.to_bytes().to_vec()),
+            )?;
         Ok(())
     }
 }
@@ -135,11 +211,57 @@ impl CanonicalDeserialize for ValidatorPublicKeys {
             X25519StaticPublicKey::try_from(&deserializer.decode_bytes()?[..])?;
         let network_signing_public_key =
             Ed25519PublicKey::try_from(&deserializer.decode_bytes()?[..])?;
-        Ok(ValidatorPublicKeys::new(
-            account_address,
-            consensus_public_key,
-            network_signing_public_key,
-            network_identity_public_key,
-        ))
+        let vrf_public_key = decode_vrf_public_key(&deserializer.decode_bytes()?)?;
+        let bls_public_key =
+            decode_bls_public_key(&deserializer.decode_bytes()?, &deserializer.decode_bytes()?)?;
+        Ok(ValidatorPublicKeys {
+            bls_public_key,
+            ..ValidatorPublicKeys::new(
+                account_address,
+                consensus_public_key,
+                network_signing_public_key,
+                network_identity_public_key,
+                vrf_public_key,
+            )
+        })
+    }
+}
+
+/// A validator that didn't register a VRF key has an empty one.
+fn decode_vrf_public_key(bytes: &[u8]) -> Result<Option<VRFPublicKey>> {
+    if bytes.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(VRFPublicKey::try_from(bytes)?))
     }
 }
+
+/// A validator that didn't register a BLS12-381 key has an empty one, as well as an empty proof of
+/// possession.
+fn decode_bls_public_key(
+    public_key_bytes: &[u8],
+    proof_bytes: &[u8],
+) -> Result<Option<(BLS12381PublicKey, BLS12381ProofOfPossession)>> {
+    if public_key_bytes.is_empty() && proof_bytes.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some((
+            BLS12381PublicKey::try_from(public_key_bytes)?,
+            BLS12381ProofOfPossession::try_from(proof_bytes)?,
+        )))
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+fn arb_bls_public_key(
+) -> impl Strategy<Value = Option<(BLS12381PublicKey, BLS12381ProofOfPossession)>> {
+    use crypto::{bls12381::BLS12381PrivateKey, test_utils::TEST_SEED, traits::Uniform};
+    use proptest::strategy::LazyJust;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    proptest::option::of(LazyJust::new(|| {
+        let private_key =
+            BLS12381PrivateKey::generate_for_testing(&mut StdRng::from_seed(TEST_SEED));
+        ((&private_key).into(), private_key.prove_possession())
+    }))
+}
@@ -2,7 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::account_address::{AccountAddress, ADDRESS_LENGTH};
-use crypto::{test_utils::TEST_SEED, HashValue, *};
+use crypto::{
+    bls12381::{
+        BLS12381PrivateKey, BLS12381ProofOfPossession, BLS12381PublicKey, BLS12381Signature,
+    },
+    test_utils::TEST_SEED,
+    vrf::ecvrf::{Proof, VRFPrivateKey, VRFPublicKey},
+    HashValue, *,
+};
 use failure::Error;
 use rand::{rngs::StdRng, SeedableRng};
 use std::convert::TryFrom;
@@ -16,6 +23,10 @@ pub struct ValidatorSigner<PrivateKey: SigningKey> {
     author: AccountAddress,
     public_key: PrivateKey::VerifyingKeyMaterial,
     private_key: PrivateKey,
+    // Only the validators proving the VRF outputs of their proposals have one.
+    vrf_private_key: Option<VRFPrivateKey>,
+    // Only the validators aggregating their signatures in the certificates have one.
+    bls_private_key: Option<BLS12381PrivateKey>,
 }
 
 impl<PrivateKey: SigningKey> ValidatorSigner<PrivateKey> {
@@ -32,9 +43,24 @@ impl<PrivateKey: SigningKey> ValidatorSigner<PrivateKey> {
             author: account_address,
             public_key,
             private_key,
+            vrf_private_key: None,
+            bls_private_key: None,
         }
     }
 
+    /// Sets the VRF key of the signer, which is separate from its signing key.
+    pub fn with_vrf_private_key(mut self, vrf_private_key: VRFPrivateKey) -> Self {
+        self.vrf_private_key = Some(vrf_private_key);
+        self
+    }
+
+    /// Sets the BLS12-381 key of the signer, which is separate from its signing key and whose
+    /// signatures the certificates aggregate.
+    pub fn with_bls_private_key(mut self, bls_private_key: BLS12381PrivateKey) -> Self {
+        self.bls_private_key = Some(bls_private_key);
+        self
+    }
+
     /// Constructs a signature for `message` using `private_key`.
     pub fn sign_message(&self, message: HashValue) -> Result<PrivateKey::SignatureMaterial, Error> {
         Ok(self.private_key.sign_message(&message))
@@ -49,6 +75,46 @@ impl<PrivateKey: SigningKey> ValidatorSigner<PrivateKey> {
     pub fn public_key(&self) -> PrivateKey::VerifyingKeyMaterial {
         self.public_key.clone()
     }
+
+    /// Returns the VRF public key associated with this signer, if it has a VRF key.
+    pub fn vrf_public_key(&self) -> Option<VRFPublicKey> {
+        self.vrf_private_key.as_ref().map(VRFPublicKey::from)
+    }
+
+    /// Produces an ECVRF proof for `alpha`, which can be verified with the VRF public key
+    /// associated with this signer, if it has a VRF key.
+    pub fn vrf_prove(&self, alpha: &[u8]) -> Option<Proof> {
+        self.vrf_private_key
+            .as_ref()
+            .map(|vrf_private_key| vrf_private_key.prove(alpha))
+    }
+
+    /// Returns the BLS12-381 public key associated with this signer along with the proof of
+    /// possession of its private key, if it has a BLS12-381 key.
+    pub fn bls_public_key(&self) -> Option<(BLS12381PublicKey, BLS12381ProofOfPossession)> {
+        self.bls_private_key.as_ref().map(|bls_private_key| {
+            (
+                BLS12381PublicKey::from(bls_private_key),
+                bls_private_key.prove_possession(),
+            )
+        })
+    }
+
+    /// Constructs a BLS12-381 signature for `message`, which can be aggregated with the ones of
+    /// other signers, if the signer has a BLS12-381 key.
+    pub fn bls_sign_message(&self, message: HashValue) -> Option<BLS12381Signature> {
+        self.bls_private_key
+            .as_ref()
+            .map(|bls_private_key| bls_private_key.sign_message(&message))
+    }
+}
+
+impl ValidatorSigner<BLS12381PrivateKey> {
+    /// Proves the possession of the private key, which the registration of the public key for
+    /// aggregated signatures requires.
+    pub fn prove_possession(&self) -> BLS12381ProofOfPossession {
+        self.private_key.prove_possession()
+    }
 }
 
 impl<PrivateKey: SigningKey + Genesis> ValidatorSigner<PrivateKey> {
@@ -140,5 +206,36 @@ pub mod proptests {
             prop_assert_eq!(public_key, signer.public_key());
         }
 
+        #[test]
+        fn test_vrf_prove(
+            signing_key in arb_signing_key::<Ed25519PrivateKey>(),
+            alpha in any::<Vec<u8>>(),
+        ) {
+            let signer = ValidatorSigner::new(None, signing_key);
+            prop_assert!(signer.vrf_prove(&alpha).is_none());
+            let vrf_private_key =
+                VRFPrivateKey::generate_for_testing(&mut StdRng::from_seed(TEST_SEED));
+            let signer = signer.with_vrf_private_key(vrf_private_key);
+            let public_key = signer.vrf_public_key().unwrap();
+            let proof = signer.vrf_prove(&alpha).unwrap();
+            prop_assert!(public_key.verify(&proof, &alpha).is_ok());
+            prop_assert!(public_key.verify(&proof, &[&alpha[..], b"x"].concat()).is_err());
+        }
+
+        #[test]
+        fn test_bls_sign_message(signing_key in arb_signing_key::<Ed25519PrivateKey>()) {
+            let message = HashValue::random();
+            let signer = ValidatorSigner::new(None, signing_key);
+            prop_assert!(signer.bls_sign_message(message).is_none());
+            let bls_private_key =
+                BLS12381PrivateKey::generate_for_testing(&mut StdRng::from_seed(TEST_SEED));
+            let signer = signer.with_bls_private_key(bls_private_key);
+            let (public_key, proof_of_possession) = signer.bls_public_key().unwrap();
+            prop_assert!(public_key.verify_possession(&proof_of_possession).is_ok());
+            let signature = signer.bls_sign_message(message).unwrap();
+            prop_assert!(public_key.verify_signature(&message, &signature).is_ok());
+            prop_assert!(public_key.verify_signature(&HashValue::random(), &signature).is_err());
+        }
+
     }
 }
@@ -1,11 +1,19 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::account_address::AccountAddress;
-use crypto::*;
+use crate::{account_address::AccountAddress, validator_set::ValidatorSet};
+use crypto::{
+    bls12381::{BLS12381ProofOfPossession, BLS12381PublicKey, BLS12381Signature},
+    ed25519::Ed25519PublicKey,
+    vrf::ecvrf::VRFPublicKey,
+    *,
+};
 use failure::prelude::*;
 use logger::prelude::*;
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+};
 
 /// Errors possible during signature verification.
 #[derive(Debug, Fail, PartialEq)]
@@ -32,6 +40,13 @@ pub enum VerifyError {
     #[fail(display = "Signature is invalid")]
     /// The signature does not match the hash.
     InvalidSignature,
+    #[fail(display = "Author appears more than once")]
+    /// An aggregated signature lists the same author several times.
+    DuplicateAuthor,
+    #[fail(display = "The possession of the public keys is not proven")]
+    /// The public keys were registered without proofs of possession, so their aggregate can be
+    /// forged by a rogue key.
+    UnprovenPossession,
 }
 
 /// Supports validation of signatures for known authors. This struct can be used for all signature
@@ -40,6 +55,14 @@ pub enum VerifyError {
 pub struct ValidatorVerifier<P> {
     author_to_public_keys: HashMap<AccountAddress, P>,
     quorum_size: usize,
+    // The VRF keys of the authors that registered one.
+    author_to_vrf_public_keys: HashMap<AccountAddress, VRFPublicKey>,
+    // Whether every public key came with a valid proof of possession of its private key, which
+    // the verification of aggregated signatures requires.
+    possession_proven: bool,
+    // Set if the certificates aggregate the BLS12-381 signatures of the authors instead of
+    // carrying their individual signatures.
+    aggregation_verifier: Option<Box<ValidatorVerifier<BLS12381PublicKey>>>,
 }
 
 impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
@@ -54,6 +77,9 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
         ValidatorVerifier {
             author_to_public_keys,
             quorum_size,
+            author_to_vrf_public_keys: HashMap::new(),
+            possession_proven: false,
+            aggregation_verifier: None,
         }
     }
 
@@ -72,9 +98,22 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
         Ok(ValidatorVerifier {
             author_to_public_keys,
             quorum_size,
+            author_to_vrf_public_keys: HashMap::new(),
+            possession_proven: false,
+            aggregation_verifier: None,
         })
     }
 
+    /// Sets the VRF keys of the authors, which are only needed to verify the VRF proofs of the
+    /// proposals.
+    pub fn with_vrf_public_keys(
+        mut self,
+        author_to_vrf_public_keys: HashMap<AccountAddress, VRFPublicKey>,
+    ) -> Self {
+        self.author_to_vrf_public_keys = author_to_vrf_public_keys;
+        self
+    }
+
     /// Helper method to initialize with a single author and public key.
     pub fn new_single(author: AccountAddress, public_key: PublicKey) -> Self {
         let mut author_to_public_keys = HashMap::new();
@@ -167,7 +206,13 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
     where
         T: Into<PublicKey::SignatureMaterial> + Clone,
     {
-        let num_of_signatures = aggregated_signature.len();
+        self.check_num_of_signers(aggregated_signature.len())
+    }
+
+    fn check_num_of_signers(
+        &self,
+        num_of_signatures: usize,
+    ) -> std::result::Result<(), VerifyError> {
         if num_of_signatures < self.quorum_size {
             return Err(VerifyError::TooFewSignatures {
                 num_of_signatures,
@@ -205,6 +250,11 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
         self.author_to_public_keys.get(&author).cloned()
     }
 
+    /// Returns the VRF public key of the author, if it is known and registered one.
+    pub fn get_vrf_public_key(&self, author: AccountAddress) -> Option<&VRFPublicKey> {
+        self.author_to_vrf_public_keys.get(&author)
+    }
+
     /// Returns a ordered list of account addresses from smallest to largest.
     pub fn get_ordered_account_addresses(&self) -> Vec<AccountAddress> {
         let mut account_addresses: Vec<AccountAddress> =
@@ -229,6 +279,158 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
     }
 }
 
+/// The verifier of the signatures of the validators in a validator set, by their consensus keys.
+impl From<&ValidatorSet> for ValidatorVerifier<Ed25519PublicKey> {
+    fn from(validator_set: &ValidatorSet) -> Self {
+        ValidatorVerifier::new(
+            validator_set
+                .payload()
+                .iter()
+                .map(|keys| (*keys.account_address(), keys.consensus_public_key().clone()))
+                .collect(),
+        )
+        .with_vrf_public_keys(
+            validator_set
+                .payload()
+                .iter()
+                .filter_map(|keys| Some((*keys.account_address(), keys.vrf_public_key()?.clone())))
+                .collect(),
+        )
+    }
+}
+
+impl ValidatorVerifier<Ed25519PublicKey> {
+    /// Sets the verifier of the BLS12-381 signatures of the same authors, which the certificates
+    /// aggregate instead of carrying the individual signatures of the authors.
+    pub fn with_aggregation_verifier(
+        mut self,
+        aggregation_verifier: ValidatorVerifier<BLS12381PublicKey>,
+    ) -> Self {
+        self.aggregation_verifier = Some(Box::new(aggregation_verifier));
+        self
+    }
+
+    /// Returns the verifier of the aggregated signatures of the certificates, if they aggregate
+    /// them.
+    pub fn aggregation_verifier(&self) -> Option<&ValidatorVerifier<BLS12381PublicKey>> {
+        self.aggregation_verifier.as_ref().map(AsRef::as_ref)
+    }
+
+    /// Returns the verifier of the validators of the next epoch, whose certificates aggregate
+    /// their signatures if the ones of this epoch do. Fails if they do and one of the validators
+    /// didn't register a BLS12-381 key with a valid proof of possession.
+    pub fn for_next_epoch(&self, next_validator_set: &ValidatorSet) -> Result<Self> {
+        let verifier = Self::from(next_validator_set);
+        if self.aggregation_verifier.is_none() {
+            return Ok(verifier);
+        }
+        Ok(verifier.with_aggregation_verifier(ValidatorVerifier::try_from(next_validator_set)?))
+    }
+}
+
+/// The verifier of the aggregated signatures of the validators in a validator set, by their
+/// BLS12-381 keys. Fails if one of the validators didn't register a key with a valid proof of
+/// possession.
+impl TryFrom<&ValidatorSet> for ValidatorVerifier<BLS12381PublicKey> {
+    type Error = Error;
+
+    fn try_from(validator_set: &ValidatorSet) -> Result<Self> {
+        let author_to_public_keys = validator_set
+            .payload()
+            .iter()
+            .map(|keys| {
+                let bls_public_key = keys.bls_public_key().ok_or_else(|| {
+                    format_err!(
+                        "Validator {} didn't register a BLS12-381 key",
+                        keys.account_address()
+                    )
+                })?;
+                Ok((*keys.account_address(), bls_public_key.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Self::new_with_proofs_of_possession(author_to_public_keys)
+    }
+}
+
+impl ValidatorVerifier<BLS12381PublicKey> {
+    /// Initialize with a map of author to public key and the proof of possession of its private
+    /// key, and set quorum size to default (`2f + 1`). Fails if any of the proofs is invalid.
+    /// Only a verifier initialized this way verifies aggregated signatures.
+    pub fn new_with_proofs_of_possession(
+        author_to_public_keys: HashMap<
+            AccountAddress,
+            (BLS12381PublicKey, BLS12381ProofOfPossession),
+        >,
+    ) -> Result<Self> {
+        for (author, (public_key, proof)) in &author_to_public_keys {
+            public_key
+                .verify_possession(proof)
+                .map_err(|e| format_err!("Invalid key registration of {}: {}", author, e))?;
+        }
+        let mut verifier = Self::new(
+            author_to_public_keys
+                .into_iter()
+                .map(|(author, (public_key, _))| (author, public_key))
+                .collect(),
+        );
+        verifier.possession_proven = true;
+        Ok(verifier)
+    }
+
+    /// Verifies that `aggregated_signature` is the aggregate of the signatures of the hash by all
+    /// of `authors`, which must be known and at least quorum_size. Unlike
+    /// `verify_aggregated_signature`, this takes a single pairing check whatever the number of
+    /// authors. Fails unless the verifier was initialized with `new_with_proofs_of_possession`.
+    pub fn verify_aggregate_signature(
+        &self,
+        hash: HashValue,
+        authors: &[AccountAddress],
+        aggregated_signature: &BLS12381Signature,
+    ) -> std::result::Result<(), VerifyError> {
+        self.verify_aggregate_signatures(&[(hash, authors, aggregated_signature)])
+    }
+
+    /// Verifies aggregated signatures of different hashes, each of them signed by its own authors,
+    /// like `verify_aggregate_signature`: the authors of all the hashes together must be known,
+    /// distinct and at least quorum_size. This takes one pairing check per hash.
+    pub fn verify_aggregate_signatures(
+        &self,
+        aggregated_signatures: &[(HashValue, &[AccountAddress], &BLS12381Signature)],
+    ) -> std::result::Result<(), VerifyError> {
+        if !self.possession_proven {
+            return Err(VerifyError::UnprovenPossession);
+        }
+        let num_of_signers: usize = aggregated_signatures
+            .iter()
+            .map(|(_, authors, _)| authors.len())
+            .sum();
+        self.check_num_of_signers(num_of_signers)?;
+        let unique_authors: HashSet<_> = aggregated_signatures
+            .iter()
+            .flat_map(|(_, authors, _)| authors.iter())
+            .collect();
+        if unique_authors.len() != num_of_signers {
+            return Err(VerifyError::DuplicateAuthor);
+        }
+        for (hash, authors, aggregated_signature) in aggregated_signatures {
+            let public_keys = authors
+                .iter()
+                .map(|author| {
+                    self.author_to_public_keys
+                        .get(author)
+                        .ok_or(VerifyError::UnknownAuthor)
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let aggregated_public_key = BLS12381PublicKey::aggregate(&public_keys)
+                .map_err(|_| VerifyError::InvalidSignature)?;
+            aggregated_public_key
+                .verify_signature(hash, aggregated_signature)
+                .map_err(|_| VerifyError::InvalidSignature)?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -236,7 +438,7 @@ mod tests {
         validator_signer::ValidatorSigner,
         validator_verifier::{ValidatorVerifier, VerifyError},
     };
-    use crypto::{ed25519::*, test_utils::TEST_SEED, HashValue};
+    use crypto::{bls12381::*, ed25519::*, test_utils::TEST_SEED, HashValue};
     use std::collections::HashMap;
 
     #[test]
@@ -370,4 +572,201 @@ mod tests {
             Err(VerifyError::UnknownAuthor)
         );
     }
+
+    #[test]
+    fn test_aggregate_signature() {
+        const NUM_SIGNERS: u8 = 4;
+        let validator_signers: Vec<ValidatorSigner<BLS12381PrivateKey>> = (0..NUM_SIGNERS)
+            .map(|i| ValidatorSigner::random([i; 32]))
+            .collect();
+        let random_hash = HashValue::random();
+        let validator_verifier = ValidatorVerifier::new_with_proofs_of_possession(
+            validator_signers
+                .iter()
+                .map(|signer| {
+                    (
+                        signer.author(),
+                        (signer.public_key(), signer.prove_possession()),
+                    )
+                })
+                .collect(),
+        )
+        .unwrap();
+        let aggregate = |signers: Vec<&ValidatorSigner<BLS12381PrivateKey>>| {
+            let signatures: Vec<_> = signers
+                .iter()
+                .map(|signer| signer.sign_message(random_hash).unwrap())
+                .collect();
+            (
+                signers
+                    .iter()
+                    .map(|signer| signer.author())
+                    .collect::<Vec<_>>(),
+                BLS12381Signature::aggregate(&signatures.iter().collect::<Vec<_>>()).unwrap(),
+            )
+        };
+
+        // A quorum of 3 signers out of 4; this will pass.
+        let (authors, signature) = aggregate(validator_signers.iter().take(3).collect());
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(random_hash, &authors, &signature),
+            Ok(())
+        );
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(
+                HashValue::random(),
+                &authors,
+                &signature
+            ),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // The signature doesn't cover one of the authors; this will fail.
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(
+                random_hash,
+                &[authors.clone(), vec![validator_signers[3].author()]].concat(),
+                &signature
+            ),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // The same author is counted twice to reach the quorum; this will fail.
+        let (authors, signature) = aggregate(validator_signers.iter().take(2).collect());
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(
+                random_hash,
+                &[authors.clone(), vec![authors[0]]].concat(),
+                &signature
+            ),
+            Err(VerifyError::DuplicateAuthor)
+        );
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(random_hash, &authors, &signature),
+            Err(VerifyError::TooFewSignatures {
+                num_of_signatures: 2,
+                quorum_size: 3
+            })
+        );
+
+        // An unknown author; this will fail.
+        let unknown_validator_signer =
+            ValidatorSigner::<BLS12381PrivateKey>::random([NUM_SIGNERS + 1; 32]);
+        let (authors, signature) = aggregate(vec![
+            &validator_signers[0],
+            &validator_signers[1],
+            &unknown_validator_signer,
+        ]);
+        assert_eq!(
+            validator_verifier.verify_aggregate_signature(random_hash, &authors, &signature),
+            Err(VerifyError::UnknownAuthor)
+        );
+
+        // The keys were registered without proofs of possession; this will fail.
+        let unproven_validator_verifier = ValidatorVerifier::new(
+            validator_signers
+                .iter()
+                .map(|signer| (signer.author(), signer.public_key()))
+                .collect(),
+        );
+        let (authors, signature) = aggregate(validator_signers.iter().take(3).collect());
+        assert_eq!(
+            unproven_validator_verifier.verify_aggregate_signature(
+                random_hash,
+                &authors,
+                &signature
+            ),
+            Err(VerifyError::UnprovenPossession)
+        );
+    }
+
+    #[test]
+    fn test_aggregate_signatures_of_different_hashes() {
+        let validator_signers: Vec<ValidatorSigner<BLS12381PrivateKey>> =
+            (0..4).map(|i| ValidatorSigner::random([i; 32])).collect();
+        let validator_verifier = ValidatorVerifier::new_with_proofs_of_possession(
+            validator_signers
+                .iter()
+                .map(|signer| {
+                    (
+                        signer.author(),
+                        (signer.public_key(), signer.prove_possession()),
+                    )
+                })
+                .collect(),
+        )
+        .unwrap();
+        let aggregate = |signers: &[ValidatorSigner<BLS12381PrivateKey>], hash: HashValue| {
+            let signatures: Vec<_> = signers
+                .iter()
+                .map(|signer| signer.sign_message(hash).unwrap())
+                .collect();
+            (
+                signers
+                    .iter()
+                    .map(|signer| signer.author())
+                    .collect::<Vec<_>>(),
+                BLS12381Signature::aggregate(&signatures.iter().collect::<Vec<_>>()).unwrap(),
+            )
+        };
+        let (hash0, hash1) = (HashValue::random(), HashValue::random());
+
+        // Two signers of a hash and a signer of another one make a quorum of 3; this will pass.
+        let (authors0, signature0) = aggregate(&validator_signers[..2], hash0);
+        let (authors1, signature1) = aggregate(&validator_signers[2..3], hash1);
+        assert_eq!(
+            validator_verifier.verify_aggregate_signatures(&[
+                (hash0, &authors0[..], &signature0),
+                (hash1, &authors1[..], &signature1)
+            ]),
+            Ok(())
+        );
+
+        // The signatures are swapped; this will fail.
+        assert_eq!(
+            validator_verifier.verify_aggregate_signatures(&[
+                (hash0, &authors0[..], &signature1),
+                (hash1, &authors1[..], &signature0)
+            ]),
+            Err(VerifyError::InvalidSignature)
+        );
+
+        // The same author signed both hashes to reach the quorum; this will fail.
+        let (authors2, signature2) = aggregate(&validator_signers[..1], hash1);
+        assert_eq!(
+            validator_verifier.verify_aggregate_signatures(&[
+                (hash0, &authors0[..], &signature0),
+                (hash1, &authors2[..], &signature2)
+            ]),
+            Err(VerifyError::DuplicateAuthor)
+        );
+    }
+
+    #[test]
+    fn test_proofs_of_possession() {
+        let validator_signers: Vec<ValidatorSigner<BLS12381PrivateKey>> =
+            (0..4).map(|i| ValidatorSigner::random([i; 32])).collect();
+        let mut author_to_public_keys: HashMap<_, _> = validator_signers
+            .iter()
+            .map(|signer| {
+                (
+                    signer.author(),
+                    (signer.public_key(), signer.prove_possession()),
+                )
+            })
+            .collect();
+        assert!(
+            ValidatorVerifier::new_with_proofs_of_possession(author_to_public_keys.clone()).is_ok()
+        );
+
+        // A key registered with the proof of possession of another key; this will fail.
+        author_to_public_keys.insert(
+            validator_signers[0].author(),
+            (
+                validator_signers[0].public_key(),
+                validator_signers[1].prove_possession(),
+            ),
+        );
+        assert!(ValidatorVerifier::new_with_proofs_of_possession(author_to_public_keys).is_err());
+    }
 }
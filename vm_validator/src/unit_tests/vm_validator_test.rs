@@ -19,9 +19,10 @@ use types::{
     account_address, account_config,
     test_helpers::transaction_test_helpers,
     transaction::{
-        Module, Script, SignedTransaction, TransactionArgument, MAX_TRANSACTION_SIZE_IN_BYTES,
+        Module, Script, SignedTransaction, TransactionArgument, TransactionStatus,
+        MAX_TRANSACTION_SIZE_IN_BYTES,
     },
-    vm_error::StatusCode,
+    vm_error::{StatusCode, VMStatus},
 };
 use vm_runtime::MoveVM;
 
@@ -135,6 +136,78 @@ fn test_validate_invalid_signature() {
     assert_eq!(ret.unwrap().major_status, StatusCode::INVALID_SIGNATURE);
 }
 
+#[test]
+fn test_simulate_transaction() {
+    let (config, keypair) = get_test_config();
+    let vm_validator = TestValidator::new(&config);
+
+    let address = account_config::association_address();
+    let program = encode_transfer_script(&address, 100);
+    let signed_txn = transaction_test_helpers::get_test_signed_txn(
+        address,
+        1,
+        keypair.private_key,
+        keypair.public_key,
+        Some(program),
+    );
+    let signed_txn = SignedTransaction::from_proto(signed_txn).unwrap();
+    let output = vm_validator
+        .simulate_transaction(signed_txn.clone(), true)
+        .wait()
+        .unwrap();
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+    );
+    assert!(output.gas_used() > 0);
+    assert!(!output.write_set().is_empty());
+    assert!(!output.events().is_empty());
+
+    // The VM is reused, and the first simulation left no trace.
+    let other_output = vm_validator
+        .simulate_transaction(signed_txn, true)
+        .wait()
+        .unwrap();
+    assert_eq!(other_output, output);
+}
+
+#[test]
+fn test_simulate_invalid_signature() {
+    let (config, keypair) = get_test_config();
+    let vm_validator = TestValidator::new(&config);
+
+    let mut rng = ::rand::rngs::StdRng::from_seed([1u8; 32]);
+    let (other_private_key, _) = compat::generate_keypair(&mut rng);
+
+    let address = account_config::association_address();
+    let program = encode_transfer_script(&address, 100);
+    let signed_txn = transaction_test_helpers::get_test_unchecked_txn(
+        address,
+        1,
+        other_private_key,
+        keypair.public_key,
+        Some(program),
+    );
+    let output = vm_validator
+        .simulate_transaction(signed_txn.clone(), true)
+        .wait()
+        .unwrap();
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Discard(VMStatus::new(StatusCode::INVALID_SIGNATURE))
+    );
+
+    // Executed as if it was signed if the signature isn't checked.
+    let output = vm_validator
+        .simulate_transaction(signed_txn, false)
+        .wait()
+        .unwrap();
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+    );
+}
+
 #[test]
 fn test_validate_known_script_too_large_args() {
     let (config, keypair) = get_test_config();
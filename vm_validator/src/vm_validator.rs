@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use config::config::NodeConfig;
+use crypto::HashValue;
 use failure::prelude::*;
 use futures::future::{err, ok, Future};
 use scratchpad::SparseMerkleTree;
@@ -11,10 +12,10 @@ use types::{
     account_address::{AccountAddress, ADDRESS_LENGTH},
     account_config::get_account_resource_or_default,
     get_with_proof::{RequestItem, ResponseItem},
-    transaction::SignedTransaction,
+    transaction::{SignedTransaction, TransactionOutput, Version},
     vm_error::VMStatus,
 };
-use vm_runtime::{MoveVM, VMVerifier};
+use vm_runtime::{MoveVM, VMSimulator, VMVerifier};
 
 #[cfg(test)]
 #[path = "unit_tests/vm_validator_test.rs"]
@@ -27,6 +28,14 @@ pub trait TransactionValidation: Send + Sync {
         &self,
         _txn: SignedTransaction,
     ) -> Box<dyn Future<Item = Option<VMStatus>, Error = failure::Error> + Send>;
+
+    /// Execute a txn from client against the latest state without submitting it, the signature
+    /// being checked only if `check_signature` is set
+    fn simulate_transaction(
+        &self,
+        _txn: SignedTransaction,
+        _check_signature: bool,
+    ) -> Box<dyn Future<Item = TransactionOutput, Error = failure::Error> + Send>;
 }
 
 #[derive(Clone)]
@@ -42,15 +51,9 @@ impl VMValidator {
             vm: MoveVM::new(&config.vm_config),
         }
     }
-}
 
-impl TransactionValidation for VMValidator {
-    type ValidationInstance = MoveVM;
-
-    fn validate_transaction(
-        &self,
-        txn: SignedTransaction,
-    ) -> Box<dyn Future<Item = Option<VMStatus>, Error = failure::Error> + Send> {
+    /// Returns the version and the state root of the latest ledger info in storage.
+    fn get_latest_state(&self) -> Result<(Option<Version>, HashValue)> {
         // TODO: For transaction validation, there are two options to go:
         // 1. Trust storage: there is no need to get root hash from storage here. We will
         // create another struct similar to `VerifiedStateView` that implements `StateView`
@@ -67,37 +70,67 @@ impl TransactionValidation for VMValidator {
         let address = AccountAddress::new([0xff; ADDRESS_LENGTH]);
         let item = RequestItem::GetAccountState { address };
 
-        match self
+        let (mut items, ledger_info_with_sigs, _, _) = self
             .storage_read_client
-            .update_to_latest_ledger(/* client_known_version = */ 0, vec![item])
-        {
-            Ok((mut items, ledger_info_with_sigs, _)) => {
-                if items.len() != 1 {
-                    return Box::new(err(format_err!(
-                        "Unexpected number of items ({}).",
-                        items.len()
-                    )));
-                }
+            .update_to_latest_ledger(/* client_known_version = */ 0, vec![item])?;
+        if items.len() != 1 {
+            bail!("Unexpected number of items ({}).", items.len());
+        }
 
-                match items.remove(0) {
-                    ResponseItem::GetAccountState {
-                        account_state_with_proof,
-                    } => {
-                        let transaction_info = account_state_with_proof.proof.transaction_info();
-                        let state_root = transaction_info.state_root_hash();
-                        let smt = SparseMerkleTree::new(state_root);
-                        let state_view = VerifiedStateView::new(
-                            Arc::clone(&self.storage_read_client),
-                            (
-                                Some(ledger_info_with_sigs.ledger_info().version()),
-                                state_root,
-                            ),
-                            &smt,
-                        );
-                        Box::new(ok(self.vm.validate_transaction(txn, &state_view)))
-                    }
-                    _ => panic!("Unexpected item in response."),
-                }
+        match items.remove(0) {
+            ResponseItem::GetAccountState {
+                account_state_with_proof,
+            } => {
+                let transaction_info = account_state_with_proof.proof.transaction_info();
+                Ok((
+                    Some(ledger_info_with_sigs.ledger_info().version()),
+                    transaction_info.state_root_hash(),
+                ))
+            }
+            _ => panic!("Unexpected item in response."),
+        }
+    }
+}
+
+impl TransactionValidation for VMValidator {
+    type ValidationInstance = MoveVM;
+
+    fn validate_transaction(
+        &self,
+        txn: SignedTransaction,
+    ) -> Box<dyn Future<Item = Option<VMStatus>, Error = failure::Error> + Send> {
+        match self.get_latest_state() {
+            Ok((version, state_root)) => {
+                let smt = SparseMerkleTree::new(state_root);
+                let state_view = VerifiedStateView::new(
+                    Arc::clone(&self.storage_read_client),
+                    (version, state_root),
+                    &smt,
+                );
+                Box::new(ok(self.vm.validate_transaction(txn, &state_view)))
+            }
+            Err(e) => Box::new(err(e)),
+        }
+    }
+
+    fn simulate_transaction(
+        &self,
+        txn: SignedTransaction,
+        check_signature: bool,
+    ) -> Box<dyn Future<Item = TransactionOutput, Error = failure::Error> + Send> {
+        match self.get_latest_state() {
+            Ok((version, state_root)) => {
+                let smt = SparseMerkleTree::new(state_root);
+                let state_view = VerifiedStateView::new(
+                    Arc::clone(&self.storage_read_client),
+                    (version, state_root),
+                    &smt,
+                );
+                Box::new(ok(self.vm.simulate_transaction(
+                    txn,
+                    check_signature,
+                    &state_view,
+                )))
             }
             Err(e) => Box::new(err(e)),
         }
@@ -111,7 +144,7 @@ pub async fn get_account_state(
     address: AccountAddress,
 ) -> Result<(u64, u64)> {
     let req_item = RequestItem::GetAccountState { address };
-    let (response_items, _, _) = storage_read_client
+    let (response_items, _, _, _) = storage_read_client
         .update_to_latest_ledger_async(0 /* client_known_version */, vec![req_item])
         .await?;
     let account_state = match &response_items[0] {